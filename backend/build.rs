@@ -0,0 +1,22 @@
+//! Build script
+//!
+//! Detects whether the frontend has been built so the server can embed it.
+//! Without `frontend/dist/index.html` the binary still builds, but serves a
+//! short notice instead of the UI.
+
+use std::path::Path;
+
+fn main() {
+    println!("cargo::rustc-check-cfg=cfg(frontend_embedded)");
+    println!("cargo::rerun-if-changed=../frontend/dist");
+    println!("cargo::rerun-if-changed=../frontend/dist/index.html");
+
+    if Path::new("../frontend/dist/index.html").exists() {
+        println!("cargo::rustc-cfg=frontend_embedded");
+    } else {
+        println!(
+            "cargo::warning=frontend/dist not found; building without the embedded UI \
+             (run `npm run build` in frontend/ first)"
+        );
+    }
+}
@@ -0,0 +1,62 @@
+//! Activity feed
+//!
+//! Notable events (runs starting and finishing, repositories added or cloned,
+//! commits) are recorded as they happen and served newest first by
+//! `GET /api/activity`. Recording is best effort: a failure is logged and
+//! never fails the operation that triggered it.
+
+use uuid::Uuid;
+
+use crate::db::models::ActivityKind;
+use crate::db::Database;
+
+/// Longest summary stored, in characters
+const MAX_SUMMARY_CHARS: usize = 200;
+
+/// Add an entry to the feed, summarized to the first line of `summary`
+pub fn record(db: &Database, kind: ActivityKind, repo_id: Uuid, session_id: Option<Uuid>, summary: &str) {
+    let summary = summarize(summary);
+    if let Err(e) = db.record_activity(kind, repo_id, session_id, &summary) {
+        tracing::warn!("Failed to record {} activity: {}", kind.as_str(), e);
+    }
+}
+
+/// [`record`] from async code, on the database's blocking pool
+pub async fn record_async(db: &Database, kind: ActivityKind, repo_id: Uuid, session_id: Option<Uuid>, summary: &str) {
+    let summary = summary.to_string();
+    let recorded = db
+        .call(move |db| {
+            record(db, kind, repo_id, session_id, &summary);
+            Ok(())
+        })
+        .await;
+    if let Err(e) = recorded {
+        tracing::warn!("Failed to record {} activity: {}", kind.as_str(), e);
+    }
+}
+
+/// First non-empty line of `text`, shortened to [`MAX_SUMMARY_CHARS`]
+fn summarize(text: &str) -> String {
+    let line = text.lines().map(str::trim).find(|line| !line.is_empty()).unwrap_or("");
+    if line.chars().count() > MAX_SUMMARY_CHARS {
+        let mut short: String = line.chars().take(MAX_SUMMARY_CHARS - 1).collect();
+        short.push('…');
+        short
+    } else {
+        line.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summarize() {
+        assert_eq!(summarize("\n  Fix the tests  \n\nThen refactor"), "Fix the tests");
+        assert_eq!(summarize(""), "");
+        let long = summarize(&"x".repeat(500));
+        assert_eq!(long.chars().count(), MAX_SUMMARY_CHARS);
+        assert!(long.ends_with('…'));
+    }
+}
@@ -0,0 +1,12 @@
+//! Daily analytics aggregates
+//!
+//! The dashboard reads per-repository daily totals from `daily_stats` rather
+//! than scanning sessions and output logs on every load. The
+//! `aggregate_refresh` maintenance job (see [`crate::maintenance`]) folds rows
+//! added to the activity feed and output logs since its last pass into those
+//! totals.
+
+use std::time::Duration;
+
+/// How often new activity and output are counted, unless overridden
+pub const REFRESH_INTERVAL: Duration = Duration::from_secs(60);
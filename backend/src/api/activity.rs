@@ -0,0 +1,85 @@
+//! Activity feed endpoint
+
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Query, State},
+    routing::get,
+    Json, Router,
+};
+
+use crate::db::models::ActivityEvent;
+use crate::error::{AppError, AppResult};
+use crate::users::CurrentUser;
+
+use super::pagination::{Page, PageParams, DEFAULT_LIMIT};
+use super::AppState;
+
+/// GET /api/activity - Recent events across the repositories the user can see, newest first
+async fn list_activity(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Query(params): Query<PageParams>,
+) -> AppResult<Json<Page<ActivityEvent>>> {
+    let owners: HashMap<_, _> = state
+        .db
+        .call(|db| db.list_repos())
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?
+        .into_iter()
+        .map(|repo| (repo.id, repo.owner_id))
+        .collect();
+
+    let events = state
+        .db
+        .call(|db| db.list_activity())
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?
+        .into_iter()
+        .filter(|event| {
+            owners
+                .get(&event.repo_id)
+                .is_some_and(|owner_id| user.can_view(*owner_id))
+        })
+        .collect();
+
+    Ok(Json(Page::from_vec(events, &params, DEFAULT_LIMIT)?))
+}
+
+/// Create the activity router
+pub fn router() -> Router<AppState> {
+    Router::new().route("/activity", get(list_activity))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::ActivityKind;
+    use crate::db::Database;
+    use axum_test::TestServer;
+
+    #[tokio::test]
+    async fn test_list_activity() {
+        let db = Database::in_memory().expect("Failed to create test database");
+        let repo = db.insert_repo("/path/to/repo", "my-repo").unwrap();
+        let other = db.insert_repo("/path/to/other", "other").unwrap();
+        crate::activity::record(&db, ActivityKind::RepoAdded, repo.id, None, "my-repo");
+        crate::activity::record(&db, ActivityKind::RepoAdded, other.id, None, "other");
+        crate::activity::record(&db, ActivityKind::CommitMade, repo.id, None, "Fix the tests\n\nDetails");
+
+        let state = AppState::new(db);
+        let server = TestServer::new(router().with_state(state.clone())).unwrap();
+        let response = server.get("/activity?limit=1").await;
+        response.assert_status_ok();
+        let page: Page<ActivityEvent> = response.json();
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].kind, ActivityKind::CommitMade);
+        assert_eq!(page.items[0].summary, "Fix the tests");
+        assert!(page.next_cursor.is_some());
+
+        // Entries go with their repository
+        state.db.delete_repo(other.id).unwrap();
+        let page: Page<ActivityEvent> = server.get("/activity").await.json();
+        assert_eq!(page.total, 2);
+    }
+}
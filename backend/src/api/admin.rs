@@ -0,0 +1,104 @@
+//! Administration API
+//!
+//! Server internals for administrators, starting with the background
+//! maintenance jobs (see [`crate::maintenance`]).
+
+use axum::{
+    extract::State,
+    routing::{get, post},
+    Json, Router,
+};
+
+use crate::db::models::PrunedOutput;
+use crate::error::{AppError, AppResult};
+use crate::maintenance::{self, JobStatus};
+use crate::users::CurrentUser;
+
+use super::AppState;
+
+/// GET /api/admin/jobs - Each maintenance job with its latest run
+async fn list_jobs(State(state): State<AppState>, user: CurrentUser) -> AppResult<Json<Vec<JobStatus>>> {
+    user.require_admin()?;
+    Ok(Json(state.maintenance.statuses()))
+}
+
+/// POST /api/maintenance/prune - Delete session output past the retention limits now
+async fn prune_output(
+    State(state): State<AppState>,
+    user: CurrentUser,
+) -> AppResult<Json<PrunedOutput>> {
+    user.require_admin()?;
+    let settings = &state.settings.maintenance;
+    let before = settings
+        .output_retention
+        .map(maintenance::cutoff)
+        .transpose()
+        .map_err(AppError::Internal)?;
+    let max_lines = settings.output_max_lines;
+    let pruned = state
+        .db
+        .call(move |db| db.prune_output_logs(before, max_lines))
+        .await?;
+    Ok(Json(pruned))
+}
+
+/// Create the admin router
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/jobs", get(list_jobs))
+        .route("/maintenance/prune", post(prune_output))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::{Orchestrator, OutputStream};
+    use crate::db::Database;
+    use crate::maintenance::{JobOutcome, AGGREGATE_REFRESH, JOB_NAMES};
+    use crate::settings::{MaintenanceSettings, Settings};
+    use axum_test::TestServer;
+
+    #[tokio::test]
+    async fn test_list_jobs() {
+        let state = AppState::new(Database::in_memory().unwrap());
+        let server = TestServer::new(router().with_state(state.clone())).unwrap();
+
+        let jobs: Vec<JobStatus> = server.get("/admin/jobs").await.json();
+        let names: Vec<&str> = jobs.iter().map(|job| job.name.as_str()).collect();
+        assert_eq!(names, JOB_NAMES);
+        assert!(jobs.iter().all(|job| job.runs == 0 && !job.running));
+
+        state.maintenance.run(AGGREGATE_REFRESH).await.unwrap();
+        let jobs: Vec<JobStatus> = server.get("/admin/jobs").await.json();
+        let refresh = jobs.iter().find(|job| job.name == AGGREGATE_REFRESH).unwrap();
+        assert_eq!(refresh.runs, 1);
+        assert_eq!(refresh.last_outcome, Some(JobOutcome::Succeeded));
+    }
+
+    #[tokio::test]
+    async fn test_prune_output() {
+        let db = Database::in_memory().unwrap();
+        let repo = db.insert_repo("/path/to/repo", "repo").unwrap();
+        let session = db.insert_session(repo.id, None, Orchestrator::Ralph).unwrap();
+        for line in ["one", "two", "three"] {
+            db.insert_output_log(session.id, OutputStream::Stdout, line).unwrap();
+        }
+        let state = AppState::with_settings(
+            db,
+            Settings {
+                maintenance: MaintenanceSettings {
+                    output_max_lines: Some(1),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+        let server = TestServer::new(router().with_state(state.clone())).unwrap();
+
+        let pruned: PrunedOutput = server.post("/maintenance/prune").await.json();
+        assert_eq!(pruned.deleted, 2);
+        let logs = state.db.list_output_logs(session.id, None, None, None).unwrap();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].content, "three");
+    }
+}
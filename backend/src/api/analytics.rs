@@ -0,0 +1,177 @@
+//! Analytics endpoints backed by the daily aggregates (see [`crate::analytics`])
+
+use std::collections::{BTreeMap, HashMap};
+
+use axum::{
+    extract::{Query, State},
+    routing::get,
+    Json, Router,
+};
+use chrono::{Days, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::db::models::RunStats;
+use crate::error::{AppError, AppResult};
+use crate::users::CurrentUser;
+
+use super::AppState;
+
+/// Days covered when the client doesn't give a start
+const DEFAULT_DAYS: u64 = 30;
+
+/// Longest range that can be requested, in days
+const MAX_DAYS: u64 = 366;
+
+/// Query parameters for daily totals
+#[derive(Debug, Deserialize)]
+pub struct DailyStatsQuery {
+    /// Only this repository; all visible repositories when omitted
+    pub repo_id: Option<Uuid>,
+    /// First day (YYYY-MM-DD); defaults to 30 days before `to`
+    pub from: Option<NaiveDate>,
+    /// Last day (YYYY-MM-DD); defaults to today (UTC)
+    pub to: Option<NaiveDate>,
+}
+
+/// Totals for one day
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DayStats {
+    pub day: NaiveDate,
+    #[serde(flatten)]
+    pub stats: RunStats,
+}
+
+/// Response for daily totals
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DailyStatsResponse {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub repo_id: Option<Uuid>,
+    /// One entry per day in the range, oldest first, including empty days
+    pub days: Vec<DayStats>,
+    pub totals: RunStats,
+}
+
+/// GET /api/analytics/daily - Run and output totals per day
+async fn daily_stats(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Query(query): Query<DailyStatsQuery>,
+) -> AppResult<Json<DailyStatsResponse>> {
+    let to = query.to.unwrap_or_else(|| Utc::now().date_naive());
+    let from = match query.from {
+        Some(from) => from,
+        None => to - Days::new(DEFAULT_DAYS - 1),
+    };
+    if from > to {
+        return Err(AppError::BadRequest("from must not be after to".to_string()));
+    }
+    if (to - from).num_days() >= MAX_DAYS as i64 {
+        return Err(AppError::BadRequest(format!(
+            "Date range must be at most {} days",
+            MAX_DAYS
+        )));
+    }
+
+    let owners: HashMap<_, _> = state
+        .db
+        .call(|db| db.list_repos())
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?
+        .into_iter()
+        .map(|repo| (repo.id, repo.owner_id))
+        .collect();
+    if let Some(repo_id) = query.repo_id {
+        let owner_id = owners
+            .get(&repo_id)
+            .ok_or_else(|| AppError::NotFound(format!("Repository not found: {}", repo_id)))?;
+        user.check_view(*owner_id, "Repository")?;
+    }
+
+    let mut days: BTreeMap<NaiveDate, RunStats> = from
+        .iter_days()
+        .take_while(|day| *day <= to)
+        .map(|day| (day, RunStats::default()))
+        .collect();
+    let mut totals = RunStats::default();
+    let repo_id = query.repo_id;
+    for row in state
+        .db
+        .call(move |db| db.list_daily_stats(repo_id, from, to))
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?
+    {
+        let visible = owners
+            .get(&row.repo_id)
+            .is_some_and(|owner_id| user.can_view(*owner_id));
+        if visible && let Some(day) = days.get_mut(&row.day) {
+            day.add(&row.stats);
+            totals.add(&row.stats);
+        }
+    }
+
+    Ok(Json(DailyStatsResponse {
+        from,
+        to,
+        repo_id: query.repo_id,
+        days: days
+            .into_iter()
+            .map(|(day, stats)| DayStats { day, stats })
+            .collect(),
+        totals,
+    }))
+}
+
+/// Create the analytics router
+pub fn router() -> Router<AppState> {
+    Router::new().route("/analytics/daily", get(daily_stats))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::{ActivityKind, Orchestrator};
+    use crate::db::Database;
+    use axum::http::StatusCode;
+    use axum_test::TestServer;
+
+    #[tokio::test]
+    async fn test_daily_stats() {
+        let db = Database::in_memory().expect("Failed to create test database");
+        let repo = db.insert_repo("/path/to/repo", "my-repo").unwrap();
+        let other = db.insert_repo("/path/to/other", "other").unwrap();
+        for repo_id in [repo.id, other.id] {
+            let session = db.insert_session(repo_id, None, Orchestrator::Ralph).unwrap();
+            db.record_activity(ActivityKind::SessionStarted, repo_id, Some(session.id), "Go")
+                .unwrap();
+            db.record_activity(ActivityKind::SessionFinished, repo_id, Some(session.id), "error")
+                .unwrap();
+        }
+        db.refresh_daily_stats().unwrap();
+        let server = TestServer::new(router().with_state(AppState::new(db))).unwrap();
+
+        let response = server.get("/analytics/daily").await;
+        response.assert_status_ok();
+        let stats: DailyStatsResponse = response.json();
+        assert_eq!(stats.days.len(), DEFAULT_DAYS as usize);
+        assert_eq!(stats.days.last().unwrap().day, Utc::now().date_naive());
+        assert_eq!(stats.days.last().unwrap().stats.runs, 2);
+        assert_eq!(stats.totals.failures, 2);
+
+        let stats: DailyStatsResponse = server
+            .get(&format!("/analytics/daily?repo_id={}", repo.id))
+            .await
+            .json();
+        assert_eq!(stats.totals.runs, 1);
+
+        server
+            .get("/analytics/daily?from=2026-02-01&to=2026-01-01")
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+        server
+            .get(&format!("/analytics/daily?repo_id={}", Uuid::new_v4()))
+            .await
+            .assert_status_not_found();
+    }
+}
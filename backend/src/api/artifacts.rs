@@ -0,0 +1,160 @@
+//! Run artifact endpoints
+//!
+//! Artifacts are files a run left in the repository (see [`crate::artifacts`]).
+//! Only their paths are recorded; downloads read the current file from the
+//! working tree.
+
+use std::path::PathBuf;
+
+use axum::{
+    body::Body,
+    extract::{Path as AxumPath, Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use tokio::io::AsyncReadExt;
+use uuid::Uuid;
+
+use crate::db::models::Artifact;
+use crate::error::{AppError, AppResult};
+use crate::users::CurrentUser;
+
+use super::pagination::{Page, PageParams, DEFAULT_LIMIT};
+use super::AppState;
+
+/// Bytes read from the file per chunk of a download
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// List a session's artifacts, most recently detected first
+async fn list_artifacts(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+    Query(params): Query<PageParams>,
+) -> AppResult<Json<Page<Artifact>>> {
+    let session = state.db.call(move |db| db.get_session(id)).await.map_err(|e| match e {
+        crate::db::DbError::NotFound => AppError::NotFound(format!("Session not found: {}", id)),
+        _ => AppError::Internal(e.to_string()),
+    })?;
+    user.check_view(session.owner_id, "Session")?;
+
+    let artifacts = state
+        .db
+        .call(move |db| db.list_artifacts_by_session(id))
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+
+    Ok(Json(Page::from_vec(artifacts, &params, DEFAULT_LIMIT)?))
+}
+
+/// Download an artifact's current contents
+async fn download_artifact(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+) -> AppResult<Response> {
+    let artifact = state.db.call(move |db| db.get_artifact(id)).await.map_err(|e| match e {
+        crate::db::DbError::NotFound => AppError::NotFound(format!("Artifact not found: {}", id)),
+        _ => AppError::Internal(e.to_string()),
+    })?;
+    let (session_id, repo_id) = (artifact.session_id, artifact.repo_id);
+    let session = state.db.call(move |db| db.get_session(session_id)).await?;
+    user.check_view(session.owner_id, "Session")?;
+    let repo = state.db.call(move |db| db.get_repo(repo_id)).await?;
+
+    // The file may have been replaced by a symlink since it was recorded, so
+    // make sure it still resolves to somewhere inside the repository
+    let gone = || AppError::NotFound(format!("Artifact file no longer exists: {}", artifact.path));
+    let root = tokio::fs::canonicalize(&repo.path).await.map_err(|_| gone())?;
+    let path = tokio::fs::canonicalize(PathBuf::from(&repo.path).join(&artifact.path))
+        .await
+        .map_err(|_| gone())?;
+    if !path.starts_with(&root) {
+        return Err(gone());
+    }
+    let mut file = tokio::fs::File::open(&path).await.map_err(|_| gone())?;
+    let length = file
+        .metadata()
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?
+        .len();
+
+    let body = async_stream::try_stream! {
+        let mut buf = vec![0u8; CHUNK_SIZE];
+        loop {
+            let read = file.read(&mut buf).await?;
+            if read == 0 {
+                break;
+            }
+            yield buf[..read].to_vec();
+        }
+    };
+    let body: std::pin::Pin<Box<dyn futures::Stream<Item = std::io::Result<Vec<u8>>> + Send>> =
+        Box::pin(body);
+
+    let file_name = artifact.path.rsplit('/').next().unwrap_or(&artifact.path).replace('"', "");
+    let mime = mime_guess::from_path(&artifact.path).first_or_octet_stream();
+    Ok((
+        [
+            (header::CONTENT_TYPE, mime.to_string()),
+            (header::CONTENT_LENGTH, length.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", file_name),
+            ),
+        ],
+        Body::from_stream(body),
+    )
+        .into_response())
+}
+
+/// Create the artifacts router
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/sessions/{id}/artifacts", get(list_artifacts))
+        .route("/artifacts/{id}/download", get(download_artifact))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::Orchestrator;
+    use crate::db::Database;
+    use axum_test::TestServer;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_list_and_download_artifacts() {
+        let work = TempDir::new().unwrap();
+        std::fs::create_dir(work.path().join("reports")).unwrap();
+        std::fs::write(work.path().join("reports/summary.md"), "# Done").unwrap();
+
+        let db = Database::in_memory().expect("Failed to create test database");
+        let repo = db.insert_repo(&work.path().to_string_lossy(), "repo").unwrap();
+        let session = db.insert_session(repo.id, None, Orchestrator::Ralph).unwrap();
+        let report = db.record_artifact(session.id, repo.id, "reports/summary.md", 6).unwrap();
+        let missing = db.record_artifact(session.id, repo.id, "shot.png", 10).unwrap();
+        let server = TestServer::new(router().with_state(AppState::new(db))).unwrap();
+
+        let page: Page<Artifact> = server
+            .get(&format!("/sessions/{}/artifacts", session.id))
+            .await
+            .json();
+        assert_eq!(page.total, 2);
+
+        let response = server.get(&format!("/artifacts/{}/download", report.id)).await;
+        response.assert_status_ok();
+        assert_eq!(response.text(), "# Done");
+        assert_eq!(
+            response.header(header::CONTENT_DISPOSITION),
+            "attachment; filename=\"summary.md\""
+        );
+
+        server
+            .get(&format!("/artifacts/{}/download", missing.id))
+            .await
+            .assert_status_not_found();
+    }
+}
@@ -0,0 +1,233 @@
+//! Session attachment endpoints
+//!
+//! `POST /api/sessions/{id}/attachments` takes a multipart form and streams
+//! each file part to the attachment store (see [`crate::attachments`]), with
+//! the same `upload_max_mb` limit as prompt uploads. Attachments are listed
+//! in session details and handed to every local run of the session.
+
+use axum::{
+    body::Body,
+    extract::{DefaultBodyLimit, Multipart, Path as AxumPath, State},
+    http::header,
+    response::{IntoResponse, Response},
+    routing::{delete, get, post},
+    Json, Router,
+};
+use tokio::io::AsyncReadExt;
+use uuid::Uuid;
+
+use crate::attachments::AttachmentStore;
+use crate::db::models::{Attachment, Session};
+use crate::error::{AppError, AppResult};
+use crate::users::CurrentUser;
+
+use super::uploads::receive_files;
+use super::AppState;
+
+/// Bytes read from the file per chunk of a download
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// The attachment store, if the server has a data directory to keep it in
+pub(super) fn store(state: &AppState) -> AppResult<AttachmentStore> {
+    state
+        .settings
+        .data_dir
+        .as_deref()
+        .map(AttachmentStore::in_data_dir)
+        .ok_or_else(|| AppError::BadRequest("Attachments need a data directory".to_string()))
+}
+
+async fn get_session(state: &AppState, id: Uuid) -> AppResult<Session> {
+    state.db.call(move |db| db.get_session(id)).await.map_err(|e| match e {
+        crate::db::DbError::NotFound => AppError::NotFound(format!("Session not found: {}", id)),
+        _ => AppError::Internal(e.to_string()),
+    })
+}
+
+/// Get an attachment and the session it belongs to, if the user can see it
+async fn get_visible(state: &AppState, user: &CurrentUser, id: Uuid) -> AppResult<(Attachment, Session)> {
+    let attachment = state.db.call(move |db| db.get_attachment(id)).await.map_err(|e| match e {
+        crate::db::DbError::NotFound => AppError::NotFound(format!("Attachment not found: {}", id)),
+        _ => AppError::Internal(e.to_string()),
+    })?;
+    let session = get_session(state, attachment.session_id).await?;
+    user.check_view(session.owner_id, "Session")?;
+    Ok((attachment, session))
+}
+
+/// POST /api/sessions/{id}/attachments - Attach the files of a multipart form
+async fn attach_files(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+    mut multipart: Multipart,
+) -> AppResult<Json<Vec<Attachment>>> {
+    let session = get_session(&state, id).await?;
+    user.check_modify(session.owner_id, "Session")?;
+    let store = store(&state)?;
+    let limit = state.settings.body_limits.upload_bytes;
+    let received = receive_files(&mut multipart, limit, |file_id| store.dir(id, file_id)).await?;
+
+    let attachments = state
+        .db
+        .call(move |db| {
+            received
+                .iter()
+                .map(|file| {
+                    db.insert_attachment(
+                        file.id,
+                        id,
+                        &file.filename,
+                        file.content_type.as_deref(),
+                        file.size_bytes,
+                    )
+                })
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .await?;
+    Ok(Json(attachments))
+}
+
+/// GET /api/sessions/{id}/attachments - A session's attachments, oldest first
+async fn list_attachments(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+) -> AppResult<Json<Vec<Attachment>>> {
+    let session = get_session(&state, id).await?;
+    user.check_view(session.owner_id, "Session")?;
+    Ok(Json(state.db.call(move |db| db.list_attachments(id)).await?))
+}
+
+/// GET /api/attachments/{id}/download - An attachment's contents
+async fn download_attachment(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+) -> AppResult<Response> {
+    let (attachment, _) = get_visible(&state, &user, id).await?;
+    let path = store(&state)?.path(&attachment);
+    let mut file = tokio::fs::File::open(&path)
+        .await
+        .map_err(|_| AppError::NotFound(format!("Attachment file is missing: {}", attachment.filename)))?;
+
+    let body = async_stream::try_stream! {
+        let mut buf = vec![0u8; CHUNK_SIZE];
+        loop {
+            let read = file.read(&mut buf).await?;
+            if read == 0 {
+                break;
+            }
+            yield buf[..read].to_vec();
+        }
+    };
+    let body: std::pin::Pin<Box<dyn futures::Stream<Item = std::io::Result<Vec<u8>>> + Send>> =
+        Box::pin(body);
+
+    let content_type = attachment.content_type.clone().unwrap_or_else(|| {
+        mime_guess::from_path(&attachment.filename)
+            .first_or_octet_stream()
+            .to_string()
+    });
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type),
+            (header::CONTENT_LENGTH, attachment.size_bytes.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", attachment.filename.replace('"', "")),
+            ),
+        ],
+        Body::from_stream(body),
+    )
+        .into_response())
+}
+
+/// DELETE /api/attachments/{id} - Remove an attachment and its file
+async fn delete_attachment(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+) -> AppResult<()> {
+    let (attachment, session) = get_visible(&state, &user, id).await?;
+    user.check_modify(session.owner_id, "Session")?;
+    state.db.call(move |db| db.delete_attachment(id)).await?;
+    if let Ok(store) = store(&state) {
+        store
+            .remove(&attachment)
+            .map_err(|e| AppError::Internal(format!("Failed to delete attachment: {}", e)))?;
+    }
+    Ok(())
+}
+
+/// Create the attachments router
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/sessions/{id}/attachments",
+            // The limit is enforced while streaming, against `upload_max_mb`
+            post(attach_files)
+                .layer(DefaultBodyLimit::disable())
+                .get(list_attachments),
+        )
+        .route("/attachments/{id}/download", get(download_attachment))
+        .route("/attachments/{id}", delete(delete_attachment))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::Orchestrator;
+    use crate::db::Database;
+    use crate::settings::Settings;
+    use axum_test::multipart::{MultipartForm, Part};
+    use axum_test::TestServer;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_attach_list_download_delete() {
+        let data = TempDir::new().unwrap();
+        let db = Database::in_memory().unwrap();
+        let repo = db.insert_repo("/tmp/attachments-repo", "repo").unwrap();
+        let session = db.insert_session(repo.id, None, Orchestrator::Ralph).unwrap();
+        let state = AppState::with_settings(
+            db,
+            Settings {
+                data_dir: Some(data.path().to_path_buf()),
+                ..Default::default()
+            },
+        );
+        let server = TestServer::new(router().with_state(state.clone())).unwrap();
+
+        let form = MultipartForm::new()
+            .add_part("file", Part::bytes(b"# Design".to_vec()).file_name("design.md"))
+            .add_part("file", Part::bytes(b"panic at line 3".to_vec()).file_name("crash.log"));
+        let response = server
+            .post(&format!("/sessions/{}/attachments", session.id))
+            .multipart(form)
+            .await;
+        response.assert_status_ok();
+        let attachments: Vec<Attachment> = response.json();
+        assert_eq!(attachments.len(), 2);
+
+        let listed: Vec<Attachment> = server
+            .get(&format!("/sessions/{}/attachments", session.id))
+            .await
+            .json();
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[0].filename, "design.md");
+
+        let response = server
+            .get(&format!("/attachments/{}/download", attachments[0].id))
+            .await;
+        response.assert_status_ok();
+        assert_eq!(response.text(), "# Design");
+
+        server
+            .delete(&format!("/attachments/{}", attachments[1].id))
+            .await
+            .assert_status_ok();
+        assert!(!store(&state).unwrap().path(&attachments[1]).exists());
+        assert_eq!(state.db.list_attachments(session.id).unwrap().len(), 1);
+    }
+}
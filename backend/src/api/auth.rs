@@ -0,0 +1,194 @@
+//! Browser session API
+//!
+//! Exchanges the configured auth token for a session cookie and issues the
+//! CSRF token that state-changing cookie-authenticated requests must echo.
+
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, HeaderValue},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, AppResult};
+use crate::middleware::auth::{authenticate, SESSION_COOKIE};
+use crate::middleware::csrf::{new_token, CSRF_COOKIE};
+use crate::settings::Settings;
+
+use super::AppState;
+
+/// Request body for starting a session
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CreateSessionRequest {
+    pub token: String,
+}
+
+/// Response carrying a freshly issued CSRF token
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CsrfResponse {
+    pub csrf_token: String,
+}
+
+/// Build a Set-Cookie value scoped to the app's base path
+fn set_cookie(
+    name: &str,
+    value: &str,
+    http_only: bool,
+    settings: &Settings,
+    max_age: Option<u64>,
+) -> HeaderValue {
+    let path = if settings.base_path.is_empty() {
+        "/"
+    } else {
+        settings.base_path.as_str()
+    };
+    let mut cookie = format!("{}={}; Path={}; SameSite=Strict", name, value, path);
+    if http_only {
+        cookie.push_str("; HttpOnly");
+    }
+    if settings.tls.enabled {
+        cookie.push_str("; Secure");
+    }
+    if let Some(max_age) = max_age {
+        cookie.push_str(&format!("; Max-Age={}", max_age));
+    }
+    HeaderValue::from_str(&cookie).expect("cookie values are ASCII")
+}
+
+/// Issue a CSRF token as both a cookie and a JSON body
+fn csrf_response(settings: &Settings, mut headers: HeaderMap) -> Response {
+    let csrf_token = new_token();
+    headers.append(
+        header::SET_COOKIE,
+        set_cookie(CSRF_COOKIE, &csrf_token, false, settings, None),
+    );
+    (headers, Json(CsrfResponse { csrf_token })).into_response()
+}
+
+/// POST /api/auth/session - Exchange an auth or user token for a session cookie
+async fn create_session(
+    State(state): State<AppState>,
+    Json(req): Json<CreateSessionRequest>,
+) -> AppResult<Response> {
+    if !state.settings.auth.is_enabled() {
+        return Err(AppError::BadRequest(
+            "Authentication is not enabled on this server".to_string(),
+        ));
+    }
+    let token = req.token.trim();
+    if authenticate(&state, token).await.is_none() {
+        return Err(AppError::Unauthorized("Invalid auth token".to_string()));
+    }
+
+    let mut headers = HeaderMap::new();
+    headers.append(
+        header::SET_COOKIE,
+        set_cookie(SESSION_COOKIE, token, true, &state.settings, None),
+    );
+
+    Ok(csrf_response(&state.settings, headers))
+}
+
+/// DELETE /api/auth/session - Clear the session and CSRF cookies
+async fn delete_session(State(state): State<AppState>) -> Response {
+    let mut headers = HeaderMap::new();
+    headers.append(
+        header::SET_COOKIE,
+        set_cookie(SESSION_COOKIE, "", true, &state.settings, Some(0)),
+    );
+    headers.append(
+        header::SET_COOKIE,
+        set_cookie(CSRF_COOKIE, "", false, &state.settings, Some(0)),
+    );
+    (headers, Json(serde_json::json!({ "success": true }))).into_response()
+}
+
+/// GET /api/auth/csrf - Issue a new CSRF token
+async fn get_csrf(State(state): State<AppState>) -> Response {
+    csrf_response(&state.settings, HeaderMap::new())
+}
+
+/// Create the auth router
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/auth/session", post(create_session).delete(delete_session))
+        .route("/auth/csrf", get(get_csrf))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::settings::{AuthSettings, Settings};
+    use axum::http::StatusCode;
+    use axum_test::TestServer;
+
+    const TOKEN: &str = "0123456789abcdef";
+
+    fn create_test_server(token: Option<&str>) -> TestServer {
+        let db = Database::in_memory().expect("Failed to create test database");
+        let settings = Settings {
+            auth: AuthSettings {
+                token: token.map(String::from),
+            },
+            ..Settings::default()
+        };
+        let app = Router::new()
+            .merge(router())
+            .with_state(AppState::with_settings(db, settings));
+        TestServer::new(app).expect("Failed to create test server")
+    }
+
+    #[tokio::test]
+    async fn test_create_session_sets_cookies() {
+        let server = create_test_server(Some(TOKEN));
+
+        let response = server
+            .post("/auth/session")
+            .json(&CreateSessionRequest {
+                token: TOKEN.to_string(),
+            })
+            .await;
+        response.assert_status_ok();
+
+        let body: CsrfResponse = response.json();
+        let cookies: Vec<String> = response
+            .iter_headers_by_name(header::SET_COOKIE)
+            .map(|v| v.to_str().unwrap().to_string())
+            .collect();
+        assert!(cookies
+            .iter()
+            .any(|c| c.starts_with(SESSION_COOKIE) && c.contains("HttpOnly")));
+        assert!(cookies
+            .iter()
+            .any(|c| c.starts_with(&format!("{}={}", CSRF_COOKIE, body.csrf_token))));
+    }
+
+    #[tokio::test]
+    async fn test_create_session_rejects_wrong_token() {
+        let server = create_test_server(Some(TOKEN));
+
+        let response = server
+            .post("/auth/session")
+            .json(&CreateSessionRequest {
+                token: "wrong-token-value".to_string(),
+            })
+            .await;
+        response.assert_status(StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_create_session_without_auth_configured() {
+        let server = create_test_server(None);
+
+        let response = server
+            .post("/auth/session")
+            .json(&CreateSessionRequest {
+                token: TOKEN.to_string(),
+            })
+            .await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,382 @@
+//! Batch requests
+//!
+//! `POST /api/batch` runs several API requests in order and returns each
+//! one's status and body, so the UI can chain calls (create a session, then
+//! start a run in it) in one round trip. Every sub-request goes through the
+//! full middleware stack with the batch's own credentials, so permissions,
+//! read-only mode, and rate limits apply per item. `{index.field}`
+//! placeholders in a sub-request's path or string body values are filled from
+//! the bodies of earlier results.
+
+use std::net::SocketAddr;
+
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, Request},
+    http::{header, HeaderMap, HeaderName, Method},
+    response::{IntoResponse, Response},
+    routing::post,
+    Extension, Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use crate::error::{AppError, AppResult};
+
+use super::triggers::render_prompt;
+use super::AppState;
+
+/// Most sub-requests accepted in one batch
+pub const MAX_REQUESTS: usize = 25;
+
+/// Largest sub-request response body kept, in bytes
+const MAX_RESPONSE_BODY: usize = 16 * 1024 * 1024;
+
+/// Headers of the batch request that aren't copied to sub-requests
+const SKIPPED_HEADERS: [HeaderName; 5] = [
+    header::CONTENT_LENGTH,
+    header::CONTENT_TYPE,
+    header::CONTENT_ENCODING,
+    header::TRANSFER_ENCODING,
+    header::ACCEPT_ENCODING,
+];
+
+/// Request body for a batch
+#[derive(Debug, Deserialize, Serialize)]
+pub struct BatchRequest {
+    pub requests: Vec<SubRequest>,
+    /// Keep going after a sub-request fails; by default the rest are skipped
+    #[serde(default)]
+    pub continue_on_error: bool,
+}
+
+/// One request within a batch
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SubRequest {
+    pub method: String,
+    /// API path, e.g. "/api/sessions/{0.id}/run"
+    pub path: String,
+    #[serde(default)]
+    pub body: Option<Value>,
+}
+
+/// Outcome of one sub-request
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SubResponse {
+    pub status: u16,
+    /// Parsed JSON body, the raw text for other bodies, or null when empty
+    pub body: Value,
+}
+
+/// Response for a batch, with one result per sub-request
+#[derive(Debug, Deserialize, Serialize)]
+pub struct BatchResponse {
+    pub results: Vec<SubResponse>,
+}
+
+/// Router that sub-requests are dispatched to
+#[derive(Clone)]
+struct Dispatcher(Router);
+
+/// Make the whole app available to the batch endpoint
+pub fn mount(app: Router) -> Router {
+    let dispatcher = Dispatcher(app.clone());
+    Router::new().fallback(move |mut req: Request| {
+        let app = app.clone();
+        req.extensions_mut().insert(dispatcher.clone());
+        async move {
+            match app.oneshot(req).await {
+                Ok(response) => response,
+                Err(never) => match never {},
+            }
+        }
+    })
+}
+
+/// POST /api/batch - Run several requests in order
+async fn run_batch(
+    dispatcher: Option<Extension<Dispatcher>>,
+    connect_info: Option<Extension<ConnectInfo<SocketAddr>>>,
+    headers: HeaderMap,
+    Json(batch): Json<BatchRequest>,
+) -> AppResult<Json<BatchResponse>> {
+    // Sub-requests don't carry the dispatcher, so batches can't nest
+    let Some(Extension(Dispatcher(app))) = dispatcher else {
+        return Err(AppError::BadRequest(
+            "Batch requests can't be nested".to_string(),
+        ));
+    };
+    if batch.requests.len() > MAX_REQUESTS {
+        return Err(AppError::BadRequest(format!(
+            "A batch may contain at most {} requests",
+            MAX_REQUESTS
+        )));
+    }
+
+    let mut results: Vec<SubResponse> = Vec::with_capacity(batch.requests.len());
+    let mut failed = false;
+
+    for sub in batch.requests {
+        if failed && !batch.continue_on_error {
+            results.push(SubResponse {
+                status: 424,
+                body: json!({
+                    "error": {
+                        "code": "SKIPPED",
+                        "message": "Skipped after an earlier request failed",
+                    }
+                }),
+            });
+            continue;
+        }
+
+        let earlier = Value::Array(results.iter().map(|result| result.body.clone()).collect());
+        let response = match build_request(sub, &earlier, &headers, connect_info.as_deref()) {
+            Ok(req) => match app.clone().oneshot(req).await {
+                Ok(response) => response,
+                Err(never) => match never {},
+            },
+            Err(e) => e.into_response(),
+        };
+
+        let result = collect(response).await;
+        failed |= result.status >= 400;
+        results.push(result);
+    }
+
+    Ok(Json(BatchResponse { results }))
+}
+
+/// Turn a sub-request into a request carrying the batch's headers
+fn build_request(
+    sub: SubRequest,
+    earlier: &Value,
+    headers: &HeaderMap,
+    connect_info: Option<&ConnectInfo<SocketAddr>>,
+) -> AppResult<Request> {
+    let method = Method::from_bytes(sub.method.to_ascii_uppercase().as_bytes())
+        .map_err(|_| AppError::BadRequest(format!("Invalid method: {}", sub.method)))?;
+    let path = render_prompt(&sub.path, earlier);
+    if !path.starts_with("/api/") {
+        return Err(AppError::BadRequest(format!(
+            "Batch requests must target the API, not {}",
+            path
+        )));
+    }
+
+    let mut builder = Request::builder().method(method).uri(&path);
+    for (name, value) in headers {
+        if !SKIPPED_HEADERS.contains(name) {
+            builder = builder.header(name, value);
+        }
+    }
+
+    let body = match sub.body {
+        Some(body) => {
+            builder = builder.header(header::CONTENT_TYPE, "application/json");
+            Body::from(render_body(body, earlier).to_string())
+        }
+        None => Body::empty(),
+    };
+
+    let mut req = builder
+        .body(body)
+        .map_err(|e| AppError::BadRequest(format!("Invalid request for {}: {}", path, e)))?;
+    if let Some(connect_info) = connect_info {
+        req.extensions_mut().insert(*connect_info);
+    }
+    Ok(req)
+}
+
+/// Fill placeholders in every string of a JSON body
+fn render_body(body: Value, earlier: &Value) -> Value {
+    match body {
+        Value::String(s) => Value::String(render_prompt(&s, earlier)),
+        Value::Array(items) => Value::Array(
+            items
+                .into_iter()
+                .map(|item| render_body(item, earlier))
+                .collect(),
+        ),
+        Value::Object(fields) => Value::Object(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key, render_body(value, earlier)))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+/// Read a sub-request's response into a result
+async fn collect(response: Response) -> SubResponse {
+    let status = response.status().as_u16();
+    let streaming = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/event-stream"));
+    if streaming {
+        return SubResponse {
+            status: 400,
+            body: json!({
+                "error": {
+                    "code": "BAD_REQUEST",
+                    "message": "Streaming endpoints can't be used in a batch",
+                }
+            }),
+        };
+    }
+
+    let body = match axum::body::to_bytes(response.into_body(), MAX_RESPONSE_BODY).await {
+        Ok(bytes) if bytes.is_empty() => Value::Null,
+        Ok(bytes) => serde_json::from_slice(&bytes)
+            .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&bytes).into_owned())),
+        Err(e) => {
+            return SubResponse {
+                status: 500,
+                body: json!({
+                    "error": {
+                        "code": "INTERNAL_ERROR",
+                        "message": format!("Failed to read response: {}", e),
+                    }
+                }),
+            };
+        }
+    };
+
+    SubResponse { status, body }
+}
+
+/// Create the batch router
+pub fn router() -> Router<AppState> {
+    Router::new().route("/batch", post(run_batch))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_app;
+    use crate::db::Database;
+    use crate::settings::Settings;
+    use axum::http::StatusCode;
+    use axum_test::TestServer;
+
+    fn create_test_server(settings: Settings) -> TestServer {
+        let db = Database::in_memory().expect("Failed to create test database");
+        db.insert_repo("/path/to/repo", "my-repo").unwrap();
+        TestServer::new(create_app(AppState::with_settings(db, settings)))
+            .expect("Failed to create test server")
+    }
+
+    fn sub(method: &str, path: &str, body: Option<Value>) -> SubRequest {
+        SubRequest {
+            method: method.to_string(),
+            path: path.to_string(),
+            body,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batch_chains_requests() {
+        let server = create_test_server(Settings::default());
+
+        let response = server
+            .post("/api/batch")
+            .json(&BatchRequest {
+                requests: vec![
+                    sub("GET", "/api/repos", None),
+                    sub(
+                        "POST",
+                        "/api/sessions",
+                        Some(json!({ "repo_id": "{0.items.0.id}", "name": "from {0.items.0.name}" })),
+                    ),
+                    sub("get", "/api/v1/sessions/{1.id}", None),
+                ],
+                continue_on_error: false,
+            })
+            .await;
+        response.assert_status_ok();
+
+        let results = response.json::<BatchResponse>().results;
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(|result| result.status == 200));
+        assert_eq!(results[1].body["name"], "from my-repo");
+        assert_eq!(results[2].body["id"], results[1].body["id"]);
+    }
+
+    #[tokio::test]
+    async fn test_batch_stops_after_failure() {
+        let server = create_test_server(Settings::default());
+        let missing = uuid::Uuid::new_v4();
+
+        let batch = |continue_on_error| BatchRequest {
+            requests: vec![
+                sub("GET", &format!("/api/sessions/{}", missing), None),
+                sub("GET", "/api/repos", None),
+            ],
+            continue_on_error,
+        };
+
+        let results = server
+            .post("/api/batch")
+            .json(&batch(false))
+            .await
+            .json::<BatchResponse>()
+            .results;
+        assert_eq!(results[0].status, 404);
+        assert_eq!(results[1].status, 424);
+
+        let results = server
+            .post("/api/batch")
+            .json(&batch(true))
+            .await
+            .json::<BatchResponse>()
+            .results;
+        assert_eq!(results[1].status, 200);
+    }
+
+    #[tokio::test]
+    async fn test_batch_applies_policies_per_request() {
+        let server = create_test_server(Settings {
+            read_only: true,
+            ..Settings::default()
+        });
+
+        let results = server
+            .post("/api/batch")
+            .json(&BatchRequest {
+                requests: vec![
+                    sub("GET", "/api/repos", None),
+                    sub("PUT", "/api/config/theme", Some(json!({ "value": "dark" }))),
+                    sub("POST", "/api/batch", Some(json!({ "requests": [] }))),
+                    sub("GET", "/index.html", None),
+                ],
+                continue_on_error: true,
+            })
+            .await
+            .json::<BatchResponse>()
+            .results;
+        assert_eq!(results[0].status, 200);
+        assert_eq!(results[1].status, 403);
+        assert_eq!(results[2].status, 400);
+        assert_eq!(results[3].status, 400);
+    }
+
+    #[tokio::test]
+    async fn test_batch_size_is_limited() {
+        let server = create_test_server(Settings::default());
+
+        server
+            .post("/api/batch")
+            .json(&BatchRequest {
+                requests: (0..=MAX_REQUESTS)
+                    .map(|_| sub("GET", "/api/health", None))
+                    .collect(),
+                continue_on_error: false,
+            })
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+    }
+}
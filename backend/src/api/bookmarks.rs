@@ -0,0 +1,257 @@
+//! Repository bookmark endpoints
+//!
+//! Bookmarks pin files and directories that matter in a repository (entry
+//! points, TODO docs) so prompts and reviews can point at them quickly. Only
+//! the relative path is stored; whether it still exists and its git status
+//! are looked up whenever bookmarks are listed.
+
+use std::path::Path;
+
+use axum::{
+    extract::{Path as AxumPath, State},
+    routing::{get, put},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::db::models::{Bookmark, Repo};
+use crate::error::{AppError, AppResult};
+use crate::git::{FileStatusType, GitManager};
+use crate::sanitize;
+use crate::users::CurrentUser;
+
+use super::AppState;
+
+/// Request body for bookmarking a path
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CreateBookmarkRequest {
+    /// Path relative to the repository root
+    pub path: String,
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+/// Request body for changing a bookmark; omitted fields are left unchanged
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct UpdateBookmarkRequest {
+    pub path: Option<String>,
+    /// Set to `null` to remove the label
+    #[serde(default, deserialize_with = "present")]
+    pub label: Option<Option<String>>,
+}
+
+/// Deserialize a field that is present (even as `null`) into `Some`
+fn present<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: serde::Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+/// A bookmark with what its path currently points at
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolvedBookmark {
+    #[serde(flatten)]
+    pub bookmark: Bookmark,
+    /// Whether the path exists in the working tree
+    pub exists: bool,
+    pub is_dir: bool,
+    /// Uncommitted change to the file, if any
+    pub git_status: Option<FileStatusType>,
+}
+
+/// Look up where each bookmark points in the repository at `repo_path`
+pub(super) fn resolve(repo_path: &Path, bookmarks: Vec<Bookmark>) -> Vec<ResolvedBookmark> {
+    bookmarks
+        .into_iter()
+        .map(|bookmark| {
+            let path = repo_path.join(&bookmark.path);
+            // Don't follow a symlink out of the repository
+            let metadata = std::fs::symlink_metadata(&path).ok();
+            let git_status = GitManager::path_status(repo_path, &bookmark.path).unwrap_or(None);
+            ResolvedBookmark {
+                exists: metadata.is_some(),
+                is_dir: metadata.is_some_and(|m| m.is_dir()),
+                git_status,
+                bookmark,
+            }
+        })
+        .collect()
+}
+
+async fn get_repo(state: &AppState, id: Uuid) -> AppResult<Repo> {
+    state.db.call(move |db| db.get_repo(id)).await.map_err(|e| match e {
+        crate::db::DbError::NotFound => AppError::NotFound(format!("Repository not found: {}", id)),
+        _ => AppError::Internal(e.to_string()),
+    })
+}
+
+/// Get a bookmark and its repository, if the user may change them
+async fn get_modifiable(
+    state: &AppState,
+    user: &CurrentUser,
+    id: Uuid,
+) -> AppResult<(Bookmark, Repo)> {
+    let bookmark = state.db.call(move |db| db.get_bookmark(id)).await;
+    let bookmark = bookmark.map_err(|e| match e {
+        crate::db::DbError::NotFound => AppError::NotFound(format!("Bookmark not found: {}", id)),
+        _ => AppError::Internal(e.to_string()),
+    })?;
+    let repo = get_repo(state, bookmark.repo_id).await?;
+    user.check_modify(repo.owner_id, "Repository")?;
+    Ok((bookmark, repo))
+}
+
+fn valid_path(path: &str) -> AppResult<&str> {
+    sanitize::relative_path(path).map_err(|e| AppError::BadRequest(e.to_string()))
+}
+
+/// Empty labels are no label
+fn valid_label(label: Option<&str>) -> Option<&str> {
+    label.map(str::trim).filter(|label| !label.is_empty())
+}
+
+/// GET /api/repos/{id}/bookmarks - A repository's bookmarks, by path
+async fn list_bookmarks(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+) -> AppResult<Json<Vec<ResolvedBookmark>>> {
+    let repo = get_repo(&state, id).await?;
+    user.check_view(repo.owner_id, "Repository")?;
+    let bookmarks = state.db.call(move |db| db.list_bookmarks(id)).await?;
+    Ok(Json(resolve(Path::new(&repo.path), bookmarks)))
+}
+
+/// POST /api/repos/{id}/bookmarks - Bookmark a path in a repository
+async fn create_bookmark(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+    Json(req): Json<CreateBookmarkRequest>,
+) -> AppResult<Json<ResolvedBookmark>> {
+    let repo = get_repo(&state, id).await?;
+    user.check_modify(repo.owner_id, "Repository")?;
+    let path = valid_path(&req.path)?.to_string();
+    let label = valid_label(req.label.as_deref()).map(str::to_string);
+
+    let bookmark = state
+        .db
+        .call(move |db| db.insert_bookmark(id, &path, label.as_deref()))
+        .await?;
+    let resolved = resolve(Path::new(&repo.path), vec![bookmark]).remove(0);
+    Ok(Json(resolved))
+}
+
+/// PUT /api/bookmarks/{id} - Change a bookmark's path or label
+async fn update_bookmark(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+    Json(req): Json<UpdateBookmarkRequest>,
+) -> AppResult<Json<ResolvedBookmark>> {
+    let (bookmark, repo) = get_modifiable(&state, &user, id).await?;
+    let path = match &req.path {
+        Some(path) => valid_path(path)?.to_string(),
+        None => bookmark.path,
+    };
+    let label = match &req.label {
+        Some(label) => valid_label(label.as_deref()).map(str::to_string),
+        None => bookmark.label,
+    };
+
+    let updated = state
+        .db
+        .call(move |db| {
+            db.update_bookmark(id, &path, label.as_deref())?;
+            db.get_bookmark(id)
+        })
+        .await?;
+    Ok(Json(resolve(Path::new(&repo.path), vec![updated]).remove(0)))
+}
+
+/// DELETE /api/bookmarks/{id} - Remove a bookmark
+async fn delete_bookmark(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+) -> AppResult<Json<()>> {
+    get_modifiable(&state, &user, id).await?;
+    state.db.call(move |db| db.delete_bookmark(id)).await?;
+    Ok(Json(()))
+}
+
+/// Create the bookmarks router
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/repos/{id}/bookmarks", get(list_bookmarks).post(create_bookmark))
+        .route("/bookmarks/{id}", put(update_bookmark).delete(delete_bookmark))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use axum::http::StatusCode;
+    use axum_test::TestServer;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_bookmark_crud_and_resolution() {
+        let work = TempDir::new().unwrap();
+        git2::Repository::init(work.path()).unwrap();
+        std::fs::create_dir(work.path().join("docs")).unwrap();
+        std::fs::write(work.path().join("docs/TODO.md"), "- ship it").unwrap();
+
+        let db = Database::in_memory().unwrap();
+        let repo = db.insert_repo(&work.path().to_string_lossy(), "repo").unwrap();
+        let server = TestServer::new(router().with_state(AppState::new(db))).unwrap();
+        let url = format!("/repos/{}/bookmarks", repo.id);
+
+        let todo: ResolvedBookmark = server
+            .post(&url)
+            .json(&json!({ "path": "docs/TODO.md", "label": "Todo" }))
+            .await
+            .json();
+        assert!(todo.exists);
+        assert!(!todo.is_dir);
+        assert_eq!(todo.git_status, Some(FileStatusType::Untracked));
+
+        server
+            .post(&url)
+            .json(&json!({ "path": "docs/TODO.md" }))
+            .await
+            .assert_status(StatusCode::CONFLICT);
+        server
+            .post(&url)
+            .json(&json!({ "path": "../elsewhere" }))
+            .await
+            .assert_status_bad_request();
+
+        let main: ResolvedBookmark = server.post(&url).json(&json!({ "path": "src/main.rs" })).await.json();
+        assert!(!main.exists);
+
+        let listed: Vec<ResolvedBookmark> = server.get(&url).await.json();
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[0].bookmark.path, "docs/TODO.md");
+
+        let moved: ResolvedBookmark = server
+            .put(&format!("/bookmarks/{}", todo.bookmark.id))
+            .json(&json!({ "path": "docs/", "label": null }))
+            .await
+            .json();
+        assert_eq!(moved.bookmark.path, "docs");
+        assert_eq!(moved.bookmark.label, None);
+        assert!(moved.is_dir);
+
+        server
+            .delete(&format!("/bookmarks/{}", main.bookmark.id))
+            .await
+            .assert_status_ok();
+        let listed: Vec<ResolvedBookmark> = server.get(&url).await.json();
+        assert_eq!(listed.len(), 1);
+    }
+}
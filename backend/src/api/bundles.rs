@@ -0,0 +1,217 @@
+//! Session bundle endpoints
+//!
+//! `GET /api/sessions/{id}/bundle` downloads a session as a bundle (see
+//! [`crate::bundle`]) and `POST /api/sessions/import?repo_id=` brings one into
+//! a repository on this instance as a new session. Only the record of the
+//! session travels; the repository's files don't.
+
+use std::path::Path;
+
+use axum::{
+    body::Bytes,
+    extract::{DefaultBodyLimit, Path as AxumPath, Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use chrono::Utc;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::bundle::{self, Bundle, FORMAT_VERSION, MAX_BUNDLE_BYTES};
+use crate::db::models::{Session, SessionStatus};
+use crate::error::{AppError, AppResult};
+use crate::git::GitManager;
+use crate::settings::BUNDLE_SIGNING_KEY_KEY;
+use crate::users::CurrentUser;
+
+use super::{compare, AppState};
+
+/// Query parameters for importing a bundle
+#[derive(Debug, Deserialize)]
+pub struct ImportQuery {
+    /// Repository the imported session belongs to
+    pub repo_id: Uuid,
+}
+
+/// The configured signing key, if any
+async fn signing_key(state: &AppState) -> AppResult<Option<String>> {
+    Ok(state
+        .db
+        .call(|db| db.get_config(BUNDLE_SIGNING_KEY_KEY))
+        .await?
+        .filter(|key| !key.is_empty()))
+}
+
+/// GET /api/sessions/{id}/bundle - Download a session as a signed bundle
+async fn export_bundle(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+) -> AppResult<Response> {
+    let (summary, _) = compare::summarize(&state, &user, id).await?;
+    let session = summary.session;
+    let repo_id = session.repo_id;
+    let (repo, messages, output) = state
+        .db
+        .call(move |db| {
+            Ok((
+                db.get_repo(repo_id)?,
+                db.list_messages(id)?,
+                db.list_output_logs(id, None, None, None)?,
+            ))
+        })
+        .await?;
+
+    let bundle = Bundle {
+        format: FORMAT_VERSION,
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        exported_at: Utc::now(),
+        repo_name: repo.name,
+        repo_url: GitManager::remote_url(Path::new(&repo.path), "origin").ok(),
+        session,
+        messages,
+        output,
+        changes: summary.changes,
+    };
+    let key = signing_key(&state).await?;
+    let data = tokio::task::spawn_blocking(move || bundle::encode(&bundle, key.as_deref()))
+        .await
+        .map_err(|e| AppError::Internal(format!("Bundle task failed: {}", e)))?
+        .map_err(|e| AppError::Internal(format!("Failed to write bundle: {}", e)))?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/gzip".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"session-{}.ralphtown\"", id),
+            ),
+        ],
+        data,
+    )
+        .into_response())
+}
+
+/// POST /api/sessions/import - Create a session from a bundle
+async fn import_bundle(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Query(query): Query<ImportQuery>,
+    body: Bytes,
+) -> AppResult<Json<Session>> {
+    let repo_id = query.repo_id;
+    let repo = state.db.call(move |db| db.get_repo(repo_id)).await.map_err(|e| match e {
+        crate::db::DbError::NotFound => {
+            AppError::BadRequest(format!("Repository not found: {}", query.repo_id))
+        }
+        _ => AppError::Internal(e.to_string()),
+    })?;
+    if !user.can_view(repo.owner_id) {
+        return Err(AppError::BadRequest(format!(
+            "Repository not found: {}",
+            query.repo_id
+        )));
+    }
+
+    let key = signing_key(&state).await?;
+    let mut bundle = tokio::task::spawn_blocking(move || bundle::decode(&body, key.as_deref()))
+        .await
+        .map_err(|e| AppError::Internal(format!("Bundle task failed: {}", e)))??;
+
+    // Nothing is running the imported session here
+    if matches!(bundle.session.status, SessionStatus::Running | SessionStatus::Queued) {
+        bundle.session.status = SessionStatus::Idle;
+    }
+    let owner_id = user.id;
+    let session = state
+        .db
+        .call(move |db| {
+            db.import_session(repo_id, owner_id, &bundle.session, &bundle.messages, &bundle.output)
+        })
+        .await?;
+
+    Ok(Json(session))
+}
+
+/// Create the bundles router
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/sessions/{id}/bundle", get(export_bundle))
+        .route(
+            "/sessions/import",
+            post(import_bundle).layer(DefaultBodyLimit::max(MAX_BUNDLE_BYTES as usize)),
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::{MessageRole, Orchestrator, OutputStream};
+    use crate::db::Database;
+    use axum::http::StatusCode;
+    use axum_test::TestServer;
+
+    #[tokio::test]
+    async fn test_bundle_round_trip() {
+        let source = AppState::new(Database::in_memory().unwrap());
+        let dir = tempfile::TempDir::new().unwrap();
+        git2::Repository::init(dir.path()).unwrap();
+        let repo = source.db.insert_repo(&dir.path().to_string_lossy(), "app").unwrap();
+        let session = source
+            .db
+            .insert_session(repo.id, Some("Fix tests"), Orchestrator::Ralph)
+            .unwrap();
+        source.db.insert_message(session.id, MessageRole::User, "Fix the tests").unwrap();
+        source.db.insert_output_log(session.id, OutputStream::Stdout, "one").unwrap();
+        source.db.insert_output_log(session.id, OutputStream::Stderr, "two").unwrap();
+        source.db.update_session_status(session.id, SessionStatus::Completed).unwrap();
+        source.db.set_config(BUNDLE_SIGNING_KEY_KEY, "shared-key").unwrap();
+
+        let server = TestServer::new(router().with_state(source)).unwrap();
+        let response = server.get(&format!("/sessions/{}/bundle", session.id)).await;
+        response.assert_status_ok();
+        let data = response.as_bytes().clone();
+
+        let target = AppState::new(Database::in_memory().unwrap());
+        let target_repo = target.db.insert_repo("/elsewhere/app", "app").unwrap();
+        let server = TestServer::new(router().with_state(target.clone())).unwrap();
+
+        // The target must share the key before it trusts the bundle
+        server
+            .post(&format!("/sessions/import?repo_id={}", target_repo.id))
+            .bytes(data.clone())
+            .await
+            .assert_status(StatusCode::FORBIDDEN);
+        target.db.set_config(BUNDLE_SIGNING_KEY_KEY, "shared-key").unwrap();
+
+        let response = server
+            .post(&format!("/sessions/import?repo_id={}", target_repo.id))
+            .bytes(data)
+            .await;
+        response.assert_status_ok();
+        let imported: Session = response.json();
+        assert_ne!(imported.id, session.id);
+        assert_eq!(imported.repo_id, target_repo.id);
+        assert_eq!(imported.name.as_deref(), Some("Fix tests"));
+        assert_eq!(imported.status, SessionStatus::Completed);
+
+        let messages = target.db.list_messages(imported.id).unwrap();
+        assert_eq!(messages[0].content, "Fix the tests");
+        let output: Vec<String> = target
+            .db
+            .list_output_logs(imported.id, None, None, None)
+            .unwrap()
+            .into_iter()
+            .map(|log| log.content)
+            .collect();
+        assert_eq!(output, ["one", "two"]);
+
+        server
+            .post(&format!("/sessions/import?repo_id={}", target_repo.id))
+            .bytes(Bytes::from_static(b"not a bundle"))
+            .await
+            .assert_status_bad_request();
+    }
+}
@@ -0,0 +1,348 @@
+//! Side-by-side comparison of two sessions
+//!
+//! `GET /api/sessions/compare?a=&b=` summarizes what each session changed and
+//! what its output ended with, so prompt variants run on copies of a
+//! repository can be judged against each other. A session's changes are
+//! measured from the commit its first run started on (taken from its
+//! snapshots) to the current working tree; without snapshots only
+//! uncommitted changes are counted.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use axum::{
+    extract::{Query, State},
+    routing::get,
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::db::models::{MessageRole, OutputStream, Session, SnapshotReason};
+use crate::error::{AppError, AppResult};
+use crate::git::{Commit, GitManager};
+use crate::users::CurrentUser;
+
+use super::AppState;
+
+/// Last stdout lines included for each session
+const TAIL_LINES: usize = 20;
+
+/// First stderr lines included for each session
+const ERROR_LINES: usize = 20;
+
+/// Most commits listed for each session
+const MAX_COMMITS: usize = 50;
+
+/// Query parameters naming the sessions to compare
+#[derive(Debug, Deserialize)]
+pub struct CompareQuery {
+    pub a: Uuid,
+    pub b: Uuid,
+}
+
+/// Lines added and removed in a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LineChanges {
+    pub added: usize,
+    pub removed: usize,
+}
+
+/// What a session changed in its repository
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChangeSummary {
+    /// Commit changes are measured from; null when only uncommitted changes count
+    pub base_commit: Option<String>,
+    /// Commits made since the base, newest first
+    pub commits: Vec<Commit>,
+    pub files_changed: usize,
+    pub total_added: usize,
+    pub total_removed: usize,
+}
+
+/// One side of a comparison
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionSummary {
+    pub session: Session,
+    /// Prompts sent in the session, oldest first
+    pub prompts: Vec<String>,
+    /// Null when the repository couldn't be read; see `changes_error`
+    pub changes: Option<ChangeSummary>,
+    pub changes_error: Option<String>,
+    pub output_lines: usize,
+    pub error_lines: usize,
+    /// Last lines written to stdout
+    pub output_tail: Vec<String>,
+    /// First lines written to stderr
+    pub errors: Vec<String>,
+}
+
+/// A file changed by either session
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ComparedFile {
+    pub path: String,
+    /// Changes by session `a`; null when it didn't touch the file
+    pub a: Option<LineChanges>,
+    pub b: Option<LineChanges>,
+}
+
+/// Response for a comparison
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CompareResponse {
+    pub a: SessionSummary,
+    pub b: SessionSummary,
+    /// Every file either session changed, by path
+    pub files: Vec<ComparedFile>,
+}
+
+/// GET /api/sessions/compare - Compare two sessions side by side
+async fn compare_sessions(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Query(query): Query<CompareQuery>,
+) -> AppResult<Json<CompareResponse>> {
+    let (a, a_files) = summarize(&state, &user, query.a).await?;
+    let (b, b_files) = summarize(&state, &user, query.b).await?;
+
+    let mut files: BTreeMap<String, ComparedFile> = BTreeMap::new();
+    for (path, changes) in a_files {
+        files.insert(
+            path.clone(),
+            ComparedFile {
+                path,
+                a: Some(changes),
+                b: None,
+            },
+        );
+    }
+    for (path, changes) in b_files {
+        files
+            .entry(path.clone())
+            .or_insert(ComparedFile {
+                path,
+                a: None,
+                b: None,
+            })
+            .b = Some(changes);
+    }
+
+    Ok(Json(CompareResponse {
+        a,
+        b,
+        files: files.into_values().collect(),
+    }))
+}
+
+/// Summarize one session, returning its per-file changes separately
+pub(super) async fn summarize(
+    state: &AppState,
+    user: &CurrentUser,
+    id: Uuid,
+) -> AppResult<(SessionSummary, Vec<(String, LineChanges)>)> {
+    let session = state.db.call(move |db| db.get_session(id)).await.map_err(|e| match e {
+        crate::db::DbError::NotFound => AppError::NotFound(format!("Session not found: {}", id)),
+        _ => AppError::Internal(e.to_string()),
+    })?;
+    user.check_view(session.owner_id, "Session")?;
+
+    // Everything but the changes, which come from the repository
+    let (mut summary, base_commit, repo_path) = state
+        .db
+        .call(move |db| {
+            let prompts = db
+                .list_messages(id)?
+                .into_iter()
+                .filter(|message| message.role == MessageRole::User)
+                .map(|message| message.content)
+                .collect();
+
+            let output_lines = db.count_output_logs(id, Some(OutputStream::Stdout))?;
+            let error_lines = db.count_output_logs(id, Some(OutputStream::Stderr))?;
+            let output_tail = db
+                .list_output_logs(
+                    id,
+                    Some(OutputStream::Stdout),
+                    Some(TAIL_LINES as i64),
+                    Some(output_lines.saturating_sub(TAIL_LINES) as i64),
+                )?
+                .into_iter()
+                .map(|log| log.content)
+                .collect();
+            let errors = db
+                .list_output_logs(id, Some(OutputStream::Stderr), Some(ERROR_LINES as i64), None)?
+                .into_iter()
+                .map(|log| log.content)
+                .collect();
+
+            // Snapshots are listed newest first, so the last pre-run one is the first run
+            let base_commit = db
+                .list_snapshots_by_session(id)?
+                .into_iter()
+                .rev()
+                .find(|snapshot| snapshot.reason == SnapshotReason::PreRun)
+                .and_then(|snapshot| snapshot.head_commit);
+            let repo_path = PathBuf::from(db.get_repo(session.repo_id)?.path);
+
+            let summary = SessionSummary {
+                session,
+                prompts,
+                changes: None,
+                changes_error: None,
+                output_lines,
+                error_lines,
+                output_tail,
+                errors,
+            };
+            Ok((summary, base_commit, repo_path))
+        })
+        .await?;
+
+    let changes = tokio::task::spawn_blocking(move || {
+        let deltas = GitManager::diff_stats_since(&repo_path, base_commit.as_deref())?;
+        let commits = match &base_commit {
+            Some(base) => GitManager::commits_since(&repo_path, base, MAX_COMMITS)?,
+            None => Vec::new(),
+        };
+        Ok::<_, crate::git::GitError>((base_commit, commits, deltas))
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Diff task failed: {}", e)))?;
+
+    let files = match changes {
+        Ok((base_commit, commits, deltas)) => {
+            let files: Vec<(String, LineChanges)> = deltas
+                .into_iter()
+                .map(|delta| {
+                    (
+                        delta.path,
+                        LineChanges {
+                            added: delta.added,
+                            removed: delta.removed,
+                        },
+                    )
+                })
+                .collect();
+            summary.changes = Some(ChangeSummary {
+                base_commit,
+                commits,
+                files_changed: files.len(),
+                total_added: files.iter().map(|(_, c)| c.added).sum(),
+                total_removed: files.iter().map(|(_, c)| c.removed).sum(),
+            });
+            files
+        }
+        Err(e) => {
+            summary.changes_error = Some(e.to_string());
+            Vec::new()
+        }
+    };
+
+    Ok((summary, files))
+}
+
+/// Create the compare router
+pub fn router() -> Router<AppState> {
+    Router::new().route("/sessions/compare", get(compare_sessions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::{Orchestrator, Snapshot};
+    use crate::db::Database;
+    use axum_test::TestServer;
+    use chrono::Utc;
+    use tempfile::TempDir;
+
+    fn init_repo(dir: &std::path::Path) -> String {
+        let repo = git2::Repository::init(dir).unwrap();
+        std::fs::write(dir.join("shared.txt"), "one\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(std::path::Path::new("shared.txt")).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = git2::Signature::now("Test", "test@example.com").unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, "Initial commit", &tree, &[])
+            .unwrap()
+            .to_string()
+    }
+
+    #[tokio::test]
+    async fn test_compare_sessions() {
+        let dir_a = TempDir::new().unwrap();
+        let dir_b = TempDir::new().unwrap();
+        let base_a = init_repo(dir_a.path());
+        init_repo(dir_b.path());
+
+        let db = Database::in_memory().unwrap();
+        let repo_a = db.insert_repo(&dir_a.path().to_string_lossy(), "a").unwrap();
+        let repo_b = db.insert_repo(&dir_b.path().to_string_lossy(), "b").unwrap();
+        let a = db.insert_session(repo_a.id, None, Orchestrator::Ralph).unwrap();
+        let b = db.insert_session(repo_b.id, None, Orchestrator::Ralph).unwrap();
+
+        db.insert_message(a.id, MessageRole::User, "Add a feature").unwrap();
+        db.insert_message(b.id, MessageRole::User, "Add a feature, tests first").unwrap();
+        for i in 0..30 {
+            db.insert_output_log(a.id, OutputStream::Stdout, &format!("line {}", i))
+                .unwrap();
+        }
+        db.insert_output_log(b.id, OutputStream::Stderr, "error: build failed")
+            .unwrap();
+        db.insert_snapshot(&Snapshot {
+            id: Uuid::new_v4(),
+            session_id: a.id,
+            repo_id: repo_a.id,
+            reason: SnapshotReason::PreRun,
+            head_commit: Some(base_a.clone()),
+            file_count: 0,
+            deleted_count: 0,
+            size_bytes: 0,
+            created_at: Utc::now(),
+        })
+        .unwrap();
+
+        // Session a committed its change; b left one uncommitted plus a new file
+        let repo = git2::Repository::open(dir_a.path()).unwrap();
+        std::fs::write(dir_a.path().join("shared.txt"), "one\ntwo\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(std::path::Path::new("shared.txt")).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = git2::Signature::now("Test", "test@example.com").unwrap();
+        let parent = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, "Add two", &tree, &[&parent])
+            .unwrap();
+        std::fs::write(dir_b.path().join("shared.txt"), "uno\n").unwrap();
+
+        let server = TestServer::new(router().with_state(AppState::new(db))).unwrap();
+        let response = server
+            .get(&format!("/sessions/compare?a={}&b={}", a.id, b.id))
+            .await;
+        response.assert_status_ok();
+        let comparison: CompareResponse = response.json();
+
+        assert_eq!(comparison.a.prompts, vec!["Add a feature"]);
+        assert_eq!(comparison.a.output_lines, 30);
+        assert_eq!(comparison.a.output_tail.len(), TAIL_LINES);
+        assert_eq!(comparison.a.output_tail.last().unwrap(), "line 29");
+        assert_eq!(comparison.b.errors, vec!["error: build failed"]);
+
+        let changes_a = comparison.a.changes.unwrap();
+        assert_eq!(changes_a.base_commit.as_deref(), Some(base_a.as_str()));
+        assert_eq!(changes_a.commits.len(), 1);
+        assert_eq!(changes_a.total_added, 1);
+        let changes_b = comparison.b.changes.unwrap();
+        assert_eq!(changes_b.base_commit, None);
+        assert_eq!(changes_b.total_removed, 1);
+
+        assert_eq!(comparison.files.len(), 1);
+        assert_eq!(comparison.files[0].path, "shared.txt");
+        assert_eq!(comparison.files[0].a, Some(LineChanges { added: 1, removed: 0 }));
+        assert_eq!(comparison.files[0].b, Some(LineChanges { added: 1, removed: 1 }));
+
+        server
+            .get(&format!("/sessions/compare?a={}&b={}", a.id, Uuid::new_v4()))
+            .await
+            .assert_status_not_found();
+    }
+}
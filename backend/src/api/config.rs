@@ -6,7 +6,15 @@ use axum::{
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use crate::db::models::Orchestrator as DbOrchestrator;
 use crate::error::{AppError, AppResult};
+use crate::ralph::{adapter, OrchestratorAdapter};
+use crate::sanitize::{MAX_COMMIT_MESSAGE_LEN, MAX_PROMPT_LEN};
+use crate::settings::{
+    AUTH_TOKEN_KEY, BITBUCKET_TOKEN_KEY, BUNDLE_SIGNING_KEY_KEY, GITHUB_TOKEN_KEY, GITLAB_TOKEN_KEY,
+    GOTIFY_TOKEN_KEY, NTFY_TOKEN_KEY, NTFY_URL_KEY,
+};
+use crate::users::CurrentUser;
 
 use super::AppState;
 
@@ -63,14 +71,95 @@ pub struct PresetsResponse {
     pub presets: Vec<Preset>,
 }
 
-/// Get all config values
-async fn get_all_config(State(state): State<AppState>) -> AppResult<Json<ConfigResponse>> {
+/// What the server supports, so the SPA can adapt to this build and config
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UiConfigResponse {
+    /// Server version
+    pub version: String,
+    /// Path prefix the app is mounted under ("" at the root)
+    pub base_path: String,
+    /// Path of the REST API
+    pub api_base: String,
+    /// API version served at `api_base` and `{api_base}/v{api_version}`
+    pub api_version: u32,
+    /// Path of the WebSocket endpoint
+    pub ws_path: String,
+    pub features: UiFeatures,
+    pub orchestrators: Vec<Orchestrator>,
+    pub limits: UiLimits,
+}
+
+/// Optional behavior that is switched on or off
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UiFeatures {
+    /// Requests must carry a token or session cookie
+    pub auth: bool,
+    /// The server is served over HTTPS
+    pub tls: bool,
+    /// Mutating requests are rejected
+    pub read_only: bool,
+    /// Expensive endpoints are rate limited
+    pub rate_limit: bool,
+    /// Finished sessions raise a desktop notification on the server machine
+    pub desktop_notifications: bool,
+    /// Runs can be sandboxed in a container
+    pub sandbox: bool,
+}
+
+/// An orchestrator the server can run sessions with
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Orchestrator {
+    pub id: String,
+    pub name: String,
+    /// Description of an orchestrator declared in configuration
+    pub description: Option<String>,
+    /// Whether its CLI was found on this machine
+    pub available: bool,
+}
+
+/// Input limits enforced by the server
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UiLimits {
+    pub max_prompt_bytes: usize,
+    pub max_commit_message_bytes: usize,
+    /// Burst size for rate-limited endpoints, when rate limiting is on
+    pub rate_limit_burst: Option<u32>,
+    /// Sustained requests per minute for rate-limited endpoints, when rate limiting is on
+    pub rate_limit_per_minute: Option<u32>,
+    /// Most local runs going at once before more are queued, when limited
+    pub max_concurrent_runs: Option<usize>,
+}
+
+/// Whether a config key may only be read by administrators
+fn is_secret_key(key: &str) -> bool {
+    matches!(
+        key,
+        AUTH_TOKEN_KEY
+            | GITHUB_TOKEN_KEY
+            | GITLAB_TOKEN_KEY
+            | BITBUCKET_TOKEN_KEY
+            | NTFY_URL_KEY
+            | NTFY_TOKEN_KEY
+            | GOTIFY_TOKEN_KEY
+            | BUNDLE_SIGNING_KEY_KEY
+    )
+}
+
+/// Get all config values (secrets are hidden from non-admins)
+async fn get_all_config(
+    State(state): State<AppState>,
+    user: CurrentUser,
+) -> AppResult<Json<ConfigResponse>> {
     let entries = state
         .db
-        .list_config()
+        .call(|db| db.list_config())
+        .await
         .map_err(|e| AppError::Internal(e.to_string()))?;
 
-    let config: HashMap<String, String> = entries.into_iter().collect();
+    let config: HashMap<String, String> = entries
+        .into_iter()
+        .filter(|(key, _)| user.is_admin() || !is_secret_key(key))
+        .collect();
 
     Ok(Json(ConfigResponse { config }))
 }
@@ -78,27 +167,42 @@ async fn get_all_config(State(state): State<AppState>) -> AppResult<Json<ConfigR
 /// Update multiple config values at once
 async fn update_config(
     State(state): State<AppState>,
+    user: CurrentUser,
     Json(req): Json<UpdateConfigRequest>,
 ) -> AppResult<Json<ConfigResponse>> {
-    for (key, value) in &req.config {
-        state
-            .db
-            .set_config(key, value)
-            .map_err(|e| AppError::Internal(e.to_string()))?;
-    }
+    user.require_admin()?;
+
+    let config = req.config;
+    state
+        .db
+        .call(move |db| {
+            for (key, value) in &config {
+                db.set_config(key, value)?;
+            }
+            Ok(())
+        })
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?;
 
     // Return updated config
-    get_all_config(State(state)).await
+    get_all_config(State(state), user).await
 }
 
 /// Get a single config value by key
 async fn get_config_value(
     State(state): State<AppState>,
+    user: CurrentUser,
     AxumPath(key): AxumPath<String>,
 ) -> AppResult<Json<ConfigValueResponse>> {
+    if is_secret_key(&key) {
+        user.require_admin()?;
+    }
+
+    let lookup = key.clone();
     let value = state
         .db
-        .get_config(&key)
+        .call(move |db| db.get_config(&lookup))
+        .await
         .map_err(|e| AppError::Internal(e.to_string()))?;
 
     Ok(Json(ConfigValueResponse { key, value }))
@@ -107,12 +211,17 @@ async fn get_config_value(
 /// Set a single config value
 async fn set_config_value(
     State(state): State<AppState>,
+    user: CurrentUser,
     AxumPath(key): AxumPath<String>,
     Json(req): Json<SetConfigValueRequest>,
 ) -> AppResult<Json<ConfigValueResponse>> {
+    user.require_admin()?;
+
+    let (stored, value) = (key.clone(), req.value.clone());
     state
         .db
-        .set_config(&key, &req.value)
+        .call(move |db| db.set_config(&stored, &value))
+        .await
         .map_err(|e| AppError::Internal(e.to_string()))?;
 
     Ok(Json(ConfigValueResponse {
@@ -124,11 +233,15 @@ async fn set_config_value(
 /// Delete a config value
 async fn delete_config_value(
     State(state): State<AppState>,
+    user: CurrentUser,
     AxumPath(key): AxumPath<String>,
 ) -> AppResult<Json<()>> {
+    user.require_admin()?;
+
     state
         .db
-        .delete_config(&key)
+        .call(move |db| db.delete_config(&key))
+        .await
         .map_err(|e| AppError::Internal(e.to_string()))?;
 
     Ok(Json(()))
@@ -197,6 +310,56 @@ async fn list_presets() -> Json<PresetsResponse> {
     Json(PresetsResponse { presets })
 }
 
+/// GET /api/ui-config - Describe the server's capabilities to the SPA
+async fn get_ui_config(State(state): State<AppState>) -> Json<UiConfigResponse> {
+    let settings = &state.settings;
+    let base_path = settings.base_path.clone();
+    let rate_limit = settings.rate_limit.enabled.then_some(&settings.rate_limit);
+
+    Json(UiConfigResponse {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        api_base: format!("{}/api", base_path),
+        api_version: crate::middleware::api_version::CURRENT_VERSION,
+        ws_path: format!("{}/api/ws", base_path),
+        base_path,
+        features: UiFeatures {
+            auth: settings.auth.is_enabled(),
+            tls: settings.tls.enabled,
+            read_only: settings.read_only,
+            rate_limit: settings.rate_limit.enabled,
+            desktop_notifications: settings.desktop_notifications,
+            sandbox: settings.sandbox.is_some(),
+        },
+        orchestrators: [
+            (DbOrchestrator::Ralph, "Ralph"),
+            (DbOrchestrator::Claude, "Claude Code"),
+            (DbOrchestrator::Aider, "Aider"),
+            (DbOrchestrator::Gemini, "Gemini CLI"),
+        ]
+        .into_iter()
+        .map(|(orchestrator, name)| Orchestrator {
+            id: orchestrator.as_str().to_string(),
+            name: name.to_string(),
+            description: None,
+            available: adapter::builtin(&orchestrator).is_some_and(|adapter| adapter.is_installed()),
+        })
+        .chain(settings.orchestrators.iter().map(|plugin| Orchestrator {
+            id: plugin.id.clone(),
+            name: plugin.name.clone(),
+            description: plugin.description.clone(),
+            available: plugin.is_installed(),
+        }))
+        .collect(),
+        limits: UiLimits {
+            max_prompt_bytes: MAX_PROMPT_LEN,
+            max_commit_message_bytes: MAX_COMMIT_MESSAGE_LEN,
+            rate_limit_burst: rate_limit.map(|r| r.burst),
+            rate_limit_per_minute: rate_limit.map(|r| r.per_minute),
+            max_concurrent_runs: settings.max_concurrent_runs,
+        },
+    })
+}
+
 /// Create the config router
 pub fn router() -> Router<AppState> {
     Router::new()
@@ -209,6 +372,7 @@ pub fn router() -> Router<AppState> {
         )
         .route("/config/presets", get(list_presets))
         .route("/config/backends", get(list_backends))
+        .route("/ui-config", get(get_ui_config))
 }
 
 #[cfg(test)]
@@ -364,6 +528,50 @@ mod tests {
         assert!(tdd.is_some());
     }
 
+    #[tokio::test]
+    async fn test_ui_config_reports_base_path() {
+        let db = Database::in_memory().expect("Failed to create test database");
+        let settings = crate::settings::Settings {
+            base_path: "/ralphtown".to_string(),
+            ..Default::default()
+        };
+        let server = create_test_server(AppState::with_settings(db, settings));
+
+        let response = server.get("/ui-config").await;
+        response.assert_status_ok();
+
+        let ui_config: UiConfigResponse = response.json();
+        assert_eq!(ui_config.base_path, "/ralphtown");
+        assert_eq!(ui_config.api_base, "/ralphtown/api");
+        assert_eq!(ui_config.ws_path, "/ralphtown/api/ws");
+    }
+
+    #[tokio::test]
+    async fn test_ui_config_reports_capabilities() {
+        let db = Database::in_memory().expect("Failed to create test database");
+        let settings = crate::settings::Settings {
+            read_only: true,
+            orchestrators: crate::orchestrator::parse_plugins(
+                r#"[{"id": "opencode", "name": "OpenCode", "command": ["opencode", "run", "{prompt}"]}]"#,
+            )
+            .unwrap(),
+            ..Default::default()
+        };
+        let server = create_test_server(AppState::with_settings(db, settings));
+
+        let ui_config: UiConfigResponse = server.get("/ui-config").await.json();
+        assert_eq!(ui_config.version, env!("CARGO_PKG_VERSION"));
+        assert!(!ui_config.features.auth);
+        assert!(ui_config.features.read_only);
+        assert!(ui_config.features.rate_limit);
+        assert_eq!(ui_config.orchestrators[0].id, "ralph");
+        assert_eq!(ui_config.orchestrators[1].id, "claude");
+        assert_eq!(ui_config.orchestrators[4].id, "opencode");
+        assert_eq!(ui_config.orchestrators[4].name, "OpenCode");
+        assert_eq!(ui_config.limits.max_prompt_bytes, MAX_PROMPT_LEN);
+        assert_eq!(ui_config.limits.rate_limit_burst, Some(10));
+    }
+
     #[tokio::test]
     async fn test_update_existing_config_value() {
         let state = create_test_state();
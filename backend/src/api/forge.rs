@@ -0,0 +1,322 @@
+//! Forge (GitHub, GitLab, Bitbucket) integration endpoints
+
+use std::path::Path;
+
+use axum::{
+    extract::{Path as AxumPath, State},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::db::models::{Orchestrator, Repo, Session};
+use crate::error::{AppError, AppResult};
+use crate::forge::{self, ForgeKind, ForgeRepo, Issue, PullRequestOptions};
+use crate::users::CurrentUser;
+
+use super::AppState;
+
+/// A supported forge and whether a token is stored for it
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ForgeInfo {
+    pub id: ForgeKind,
+    pub name: String,
+    pub configured: bool,
+}
+
+/// Response listing repositories a forge token can access
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ForgeReposResponse {
+    pub forge: ForgeKind,
+    pub repos: Vec<ForgeRepo>,
+}
+
+/// Response for an opened pull request
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PullRequestResponse {
+    pub session_id: Uuid,
+    pub forge: ForgeKind,
+    pub number: u64,
+    pub url: String,
+}
+
+/// Response listing a repository's open issues
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IssuesResponse {
+    pub repo_id: Uuid,
+    pub forge: ForgeKind,
+    pub issues: Vec<Issue>,
+}
+
+/// Request body for creating a session from an issue
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ImportIssueRequest {
+    /// Orchestrator to use for the session (defaults to ralph)
+    pub orchestrator: Orchestrator,
+}
+
+/// Look up a repository the user can see
+async fn visible_repo(state: &AppState, user: &CurrentUser, id: Uuid) -> AppResult<Repo> {
+    let repo = state.db.call(move |db| db.get_repo(id)).await;
+    let repo = repo.map_err(|e| match e {
+        crate::db::DbError::NotFound => AppError::NotFound(format!("Repository not found: {}", id)),
+        _ => AppError::Internal(e.to_string()),
+    })?;
+    user.check_view(repo.owner_id, "Repository")?;
+    Ok(repo)
+}
+
+/// GET /api/forges - List supported forges and which have a token
+async fn list_forges(State(state): State<AppState>) -> AppResult<Json<Vec<ForgeInfo>>> {
+    let forges = state
+        .db
+        .call(|db| {
+            let mut forges = Vec::new();
+            for kind in ForgeKind::ALL {
+                let configured = db
+                    .get_config(kind.token_key())?
+                    .is_some_and(|token| !token.trim().is_empty());
+                forges.push(ForgeInfo {
+                    id: kind,
+                    name: kind.name().to_string(),
+                    configured,
+                });
+            }
+            Ok(forges)
+        })
+        .await?;
+    Ok(Json(forges))
+}
+
+/// GET /api/forges/{forge}/repos - Repositories the stored token can access, for cloning
+async fn list_forge_repos(
+    State(state): State<AppState>,
+    AxumPath(forge): AxumPath<String>,
+) -> AppResult<Json<ForgeReposResponse>> {
+    let kind = ForgeKind::parse(&forge)
+        .ok_or_else(|| AppError::NotFound(format!("Unknown forge: {}", forge)))?;
+    let repos = forge::forge_for(&state.db, kind)?.list_repos().await?;
+
+    Ok(Json(ForgeReposResponse { forge: kind, repos }))
+}
+
+/// GET /api/repos/{id}/issues - List open issues on the repository's remote
+async fn list_issues(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+) -> AppResult<Json<IssuesResponse>> {
+    let repo = visible_repo(&state, &user, id).await?;
+    let remote = forge::remote_repo(&state.db, Path::new(&repo.path))?;
+    let issues = forge::forge_for(&state.db, remote.kind)?
+        .list_issues(&remote)
+        .await?;
+
+    Ok(Json(IssuesResponse {
+        repo_id: id,
+        forge: remote.kind,
+        issues,
+    }))
+}
+
+/// POST /api/repos/{id}/issues/{number}/session - Create a session pre-filled from an issue
+async fn import_issue(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath((id, number)): AxumPath<(Uuid, u64)>,
+    Json(req): Json<ImportIssueRequest>,
+) -> AppResult<Json<Session>> {
+    if !state.settings.orchestrator_available(&req.orchestrator) {
+        return Err(AppError::BadRequest(format!(
+            "Orchestrator '{}' is not yet available",
+            req.orchestrator.as_str()
+        )));
+    }
+
+    let repo = visible_repo(&state, &user, id).await?;
+    let remote = forge::remote_repo(&state.db, Path::new(&repo.path))?;
+    let issue = forge::forge_for(&state.db, remote.kind)?
+        .get_issue(&remote, number)
+        .await?;
+
+    let name = format!("#{} {}", issue.number, issue.title);
+    let (url, prompt) = (issue.url.clone(), issue.prompt());
+    let session = state
+        .db
+        .call(move |db| {
+            let session = db.insert_session_for(id, Some(&name), req.orchestrator, user.id)?;
+            db.set_session_issue(session.id, &url, &prompt)?;
+            db.get_session(session.id)
+        })
+        .await?;
+
+    Ok(Json(session))
+}
+
+/// POST /api/sessions/{id}/pull-request - Push the session's branch and open a pull request
+async fn create_pull_request(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+    Json(options): Json<PullRequestOptions>,
+) -> AppResult<Json<PullRequestResponse>> {
+    let session = state.db.call(move |db| db.get_session(id)).await;
+    let session = session.map_err(|e| match e {
+        crate::db::DbError::NotFound => AppError::NotFound(format!("Session not found: {}", id)),
+        _ => AppError::Internal(e.to_string()),
+    })?;
+    user.check_modify(session.owner_id, "Session")?;
+
+    if state.ralph_manager.is_session_running(id).await {
+        return Err(AppError::Conflict(format!(
+            "Session {} is still running",
+            id
+        )));
+    }
+
+    let repo_id = session.repo_id;
+    let repo = state.db.call(move |db| db.get_repo(repo_id)).await?;
+    let remote = forge::remote_repo(&state.db, Path::new(&repo.path))?;
+    let pull_request = forge::open_pull_request(&state.db, id, options).await?;
+
+    Ok(Json(PullRequestResponse {
+        session_id: id,
+        forge: remote.kind,
+        number: pull_request.number,
+        url: pull_request.url,
+    }))
+}
+
+/// Create the forge router
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/forges", get(list_forges))
+        .route("/forges/{forge}/repos", get(list_forge_repos))
+        .route("/sessions/{id}/pull-request", post(create_pull_request))
+        .route("/repos/{id}/issues", get(list_issues))
+        .route("/repos/{id}/issues/{number}/session", post(import_issue))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::forge::serve_fake_api;
+    use crate::settings::{GITHUB_API_URL_KEY, GITHUB_TOKEN_KEY};
+    use axum_test::TestServer;
+    use tempfile::TempDir;
+
+    fn create_test_server(db: Database) -> TestServer {
+        let app = Router::new().merge(router()).with_state(AppState::new(db));
+        TestServer::new(app).expect("Failed to create test server")
+    }
+
+    #[tokio::test]
+    async fn test_rejects_sessions_without_forge_remote() {
+        let db = Database::in_memory().expect("Failed to create test database");
+        let repo = db.insert_repo("/tmp/not-a-repo", "repo").unwrap();
+        let session = db
+            .insert_session(repo.id, None, Default::default())
+            .unwrap();
+        let server = create_test_server(db);
+
+        let response = server
+            .post(&format!("/sessions/{}/pull-request", session.id))
+            .json(&serde_json::json!({}))
+            .await;
+        response.assert_status(axum::http::StatusCode::BAD_REQUEST);
+
+        server
+            .post(&format!("/sessions/{}/pull-request", Uuid::new_v4()))
+            .json(&serde_json::json!({}))
+            .await
+            .assert_status_not_found();
+    }
+
+    #[tokio::test]
+    async fn test_list_forges() {
+        let db = Database::in_memory().expect("Failed to create test database");
+        db.set_config(GITHUB_TOKEN_KEY, "ghp_test").unwrap();
+        let server = create_test_server(db);
+
+        let forges: Vec<ForgeInfo> = server.get("/forges").await.json();
+        assert_eq!(forges.len(), 3);
+        assert!(forges[0].configured);
+        assert!(!forges[1].configured);
+
+        server.get("/forges/sourcehut/repos").await.assert_status_not_found();
+    }
+
+    /// Serve a fake GitHub API with one issue and one pull request
+    async fn start_fake_github() -> String {
+        let issue = serde_json::json!({
+            "number": 42,
+            "title": "Login fails",
+            "body": "It fails on Safari",
+            "html_url": "https://github.com/octo/hello/issues/42",
+            "labels": [{ "name": "bug" }],
+        });
+        let pull = serde_json::json!({
+            "number": 43,
+            "title": "Fix login",
+            "body": null,
+            "html_url": "https://github.com/octo/hello/pull/43",
+            "pull_request": {},
+        });
+        let app = Router::new()
+            .route(
+                "/repos/octo/hello/issues",
+                get({
+                    let issue = issue.clone();
+                    || async move { Json(serde_json::json!([issue, pull])) }
+                }),
+            )
+            .route(
+                "/repos/octo/hello/issues/42",
+                get(|| async move { Json(issue) }),
+            );
+        serve_fake_api(app).await
+    }
+
+    #[tokio::test]
+    async fn test_import_issue_as_session() {
+        let work_dir = TempDir::new().unwrap();
+        let repo = git2::Repository::init(work_dir.path()).unwrap();
+        repo.remote("origin", "git@github.com:octo/hello.git").unwrap();
+
+        let db = Database::in_memory().expect("Failed to create test database");
+        db.set_config(GITHUB_TOKEN_KEY, "ghp_test").unwrap();
+        db.set_config(GITHUB_API_URL_KEY, &start_fake_github().await)
+            .unwrap();
+        let repo = db
+            .insert_repo(&work_dir.path().to_string_lossy(), "hello")
+            .unwrap();
+        let server = create_test_server(db);
+
+        // Pull requests aren't listed as issues
+        let response = server.get(&format!("/repos/{}/issues", repo.id)).await;
+        response.assert_status_ok();
+        let issues: IssuesResponse = response.json();
+        assert_eq!(issues.forge, ForgeKind::GitHub);
+        assert_eq!(issues.issues.len(), 1);
+        assert_eq!(issues.issues[0].labels, vec!["bug"]);
+
+        let response = server
+            .post(&format!("/repos/{}/issues/42/session", repo.id))
+            .json(&serde_json::json!({}))
+            .await;
+        response.assert_status_ok();
+        let session: Session = response.json();
+        assert_eq!(session.name.as_deref(), Some("#42 Login fails"));
+        assert_eq!(
+            session.issue_url.as_deref(),
+            Some("https://github.com/octo/hello/issues/42")
+        );
+        assert_eq!(
+            session.prompt_draft.as_deref(),
+            Some("Fix issue #42: Login fails\n\nIt fails on Safari")
+        );
+    }
+}
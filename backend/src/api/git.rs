@@ -3,26 +3,44 @@
 //! Provides endpoints for git operations on session repositories:
 //! - Read operations: status, log, branches, diff
 //! - Write operations: pull, push, commit, reset, checkout
+//!
+//! And on repositories directly, under `/git/{repo_id}`:
+//! - Diffs: the unified diff of one file
+//! - Files: a file's content at a revision, directory listings
+//! - Staging: stage, unstage
+//! - Stashes: list, push, pop, drop
+//! - Fetch: a remote's refs, with progress streamed over SSE
+//! - Tags: list, create, delete, push
+//! - Remotes: list, add, remove, change URL
+//! - Identity: the `user.name` and `user.email` commits are made with
+//! - Merge and rebase: start, continue, abort
+//! - Conflicts: each side's version, resolving a file
 
 use axum::{
     extract::{Path as AxumPath, Query, State},
-    routing::{get, post},
+    response::sse::{Event, KeepAlive, Sse},
+    routing::{delete, get, post, put},
     Json, Router,
 };
 use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
 use uuid::Uuid;
 
+use crate::activity;
+use crate::db::models::ActivityKind;
 use crate::error::{AppError, AppResult};
-use crate::git::{Branch, Commit, CommandOutput, FileDelta, GitError, GitManager, GitStatus};
+use crate::git::{
+    Branch, CloneError, CloneProgress, Commit, CommandOutput, ConflictFile, ConflictResolution,
+    FileAtRevision, FileDelta, FileDiff, GitError, GitIdentity, GitManager, GitStatus, LogFilter,
+    MergeOutcome, RemoteInfo, StashEntry, Tag, TreeEntry,
+};
 
-use super::AppState;
+use crate::users::CurrentUser;
+use crate::webhooks::EventType;
 
-/// Query parameters for git log
-#[derive(Debug, Deserialize)]
-pub struct LogQueryParams {
-    /// Maximum number of commits to return (default: 20)
-    pub limit: Option<usize>,
-}
+use super::pagination::{Page, PageParams};
+use super::repos::{SseResponse, SseStream};
+use super::AppState;
 
 /// Request body for git commit
 #[derive(Debug, Deserialize, Serialize)]
@@ -48,6 +66,186 @@ pub struct CheckoutRequest {
     pub branch: String,
 }
 
+/// Query parameters for the commit log
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct LogQueryParams {
+    /// Maximum number of commits to return (default: 20)
+    pub limit: Option<usize>,
+    /// `next_cursor` from the previous page
+    pub cursor: Option<String>,
+    /// Branch, tag or commit to start from (default: HEAD)
+    pub rev: Option<String>,
+    /// Only commits whose author name or email contains this
+    pub author: Option<String>,
+    /// Only commits that change this file or directory
+    pub path: Option<String>,
+}
+
+/// Query parameters for a file's diff
+#[derive(Debug, Deserialize, Serialize)]
+pub struct FileDiffQuery {
+    /// File path relative to the repository root
+    pub path: String,
+    /// Compare the index with HEAD instead of the working tree with the index
+    #[serde(default)]
+    pub staged: bool,
+}
+
+/// Query parameters for a file's content at a revision
+#[derive(Debug, Deserialize, Serialize)]
+pub struct FileAtRevisionQuery {
+    /// File path relative to the repository root
+    pub path: String,
+    /// Branch, tag or commit (default: HEAD)
+    #[serde(rename = "ref", default)]
+    pub rev: Option<String>,
+}
+
+/// Query parameters for listing a directory
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct TreeQuery {
+    /// Branch, tag or commit; the working directory when left out
+    #[serde(rename = "ref", default)]
+    pub rev: Option<String>,
+    /// Directory relative to the repository root (default: the root)
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
+/// Request body for staging or unstaging files
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PathsRequest {
+    /// File or directory paths relative to the repository root
+    pub paths: Vec<String>,
+}
+
+/// Request body for stashing local changes
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct StashRequest {
+    /// Stash message; git's default names the branch and commit
+    #[serde(default)]
+    pub message: Option<String>,
+    /// Whether to stash untracked files too
+    #[serde(default)]
+    pub include_untracked: bool,
+}
+
+/// Request body for creating a tag
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CreateTagRequest {
+    pub name: String,
+    /// Branch, tag or commit to tag (default: HEAD)
+    #[serde(default)]
+    pub target: Option<String>,
+    /// Message of an annotated tag; without one the tag is lightweight
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+/// Request body for pushing a tag
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PushTagRequest {
+    /// Remote to push to (default: origin)
+    #[serde(default = "default_remote")]
+    pub remote: String,
+}
+
+/// Request body for adding a remote
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AddRemoteRequest {
+    pub name: String,
+    pub url: String,
+}
+
+/// Request body for changing a remote's URL
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SetRemoteUrlRequest {
+    pub url: String,
+    /// Change only the URL pushes go to
+    #[serde(default)]
+    pub push: bool,
+}
+
+/// Request body for merging a branch into the current one
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MergeRequest {
+    pub branch: String,
+}
+
+/// Request body for rebasing the current branch
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RebaseRequest {
+    /// Branch to replay the current branch's commits onto
+    pub onto: String,
+}
+
+/// Request body for cherry-picking a commit onto the current branch
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CherryPickRequest {
+    /// Commit to apply, by id or any revision git understands
+    pub commit: String,
+}
+
+/// Request body for reverting a commit
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RevertRequest {
+    /// Commit to undo, by id or any revision git understands
+    pub commit: String,
+    /// Stage the undo without committing it
+    #[serde(default)]
+    pub no_commit: bool,
+}
+
+/// Side of a conflict to keep
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictSide {
+    Ours,
+    Theirs,
+}
+
+/// Request body for resolving a conflicted file: either `accept` a side or
+/// give the merged `content`
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ResolveConflictRequest {
+    pub path: String,
+    #[serde(default)]
+    pub accept: Option<ConflictSide>,
+    #[serde(default)]
+    pub content: Option<String>,
+}
+
+/// Query parameters for the fetch progress SSE endpoint
+#[derive(Debug, Deserialize)]
+pub struct FetchProgressQuery {
+    /// Remote to fetch from (default: origin)
+    #[serde(default = "default_remote")]
+    pub remote: String,
+}
+
+fn default_remote() -> String {
+    "origin".to_string()
+}
+
+/// SSE event types for fetch progress
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum FetchEvent {
+    /// Progress update during the fetch
+    Progress(CloneProgress),
+    /// Fetch completed successfully
+    Complete { message: String },
+    /// Fetch failed with error
+    Error {
+        message: String,
+        #[serde(skip_serializing_if = "Vec::is_empty", default)]
+        help_steps: Vec<String>,
+        /// Auth type hint: "ssh", "github_pat", "https_basic"
+        #[serde(skip_serializing_if = "Option::is_none")]
+        auth_type: Option<String>,
+    },
+}
+
 /// Response wrapper for git status
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GitStatusResponse {
@@ -56,13 +254,6 @@ pub struct GitStatusResponse {
     pub status: GitStatus,
 }
 
-/// Response wrapper for git log
-#[derive(Debug, Serialize, Deserialize)]
-pub struct GitLogResponse {
-    pub session_id: Uuid,
-    pub commits: Vec<Commit>,
-}
-
 /// Response wrapper for branches
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GitBranchesResponse {
@@ -87,14 +278,27 @@ pub struct GitCommandResponse {
     pub output: CommandOutput,
 }
 
-/// Helper to get the repo path for a session
-async fn get_session_repo_path(state: &AppState, session_id: Uuid) -> AppResult<std::path::PathBuf> {
-    let session = state.db.get_session(session_id).map_err(|e| match e {
+/// Helper to get the repo path for a session the user may read (or modify)
+async fn get_session_repo_path(
+    state: &AppState,
+    user: &CurrentUser,
+    session_id: Uuid,
+    modify: bool,
+) -> AppResult<std::path::PathBuf> {
+    let session = state.db.call(move |db| db.get_session(session_id)).await;
+    let session = session.map_err(|e| match e {
         crate::db::DbError::NotFound => AppError::NotFound(format!("Session not found: {}", session_id)),
         _ => AppError::Internal(e.to_string()),
     })?;
+    if modify {
+        user.check_modify(session.owner_id, "Session")?;
+    } else {
+        user.check_view(session.owner_id, "Session")?;
+    }
 
-    let repo = state.db.get_repo(session.repo_id).map_err(|e| match e {
+    let repo_id = session.repo_id;
+    let repo = state.db.call(move |db| db.get_repo(repo_id)).await;
+    let repo = repo.map_err(|e| match e {
         crate::db::DbError::NotFound => {
             AppError::Internal(format!("Repository not found for session: {}", session_id))
         }
@@ -104,22 +308,65 @@ async fn get_session_repo_path(state: &AppState, session_id: Uuid) -> AppResult<
     Ok(std::path::PathBuf::from(&repo.path))
 }
 
+/// Helper to get the path of a repository the user may read (or modify)
+async fn get_repo_path(
+    state: &AppState,
+    user: &CurrentUser,
+    repo_id: Uuid,
+    modify: bool,
+) -> AppResult<std::path::PathBuf> {
+    let repo = state.db.call(move |db| db.get_repo(repo_id)).await;
+    let repo = repo.map_err(|e| match e {
+        crate::db::DbError::NotFound => AppError::NotFound(format!("Repository not found: {}", repo_id)),
+        _ => AppError::Internal(e.to_string()),
+    })?;
+    if modify {
+        user.check_modify(repo.owner_id, "Repository")?;
+    } else {
+        user.check_view(repo.owner_id, "Repository")?;
+    }
+
+    Ok(std::path::PathBuf::from(&repo.path))
+}
+
+/// Refuse to change the working tree under a running process
+async fn ensure_repo_idle(state: &AppState, repo_id: Uuid) -> AppResult<()> {
+    if state.ralph_manager.is_repo_busy(repo_id).await {
+        return Err(AppError::Conflict(
+            "Repository has a running process; cancel it before changing the working tree".to_string(),
+        ));
+    }
+    Ok(())
+}
+
 /// Map GitError to AppError
-fn map_git_error(e: GitError) -> AppError {
+pub(super) fn map_git_error(e: GitError) -> AppError {
     match e {
         GitError::NotARepo(msg) => AppError::BadRequest(format!("Not a git repository: {}", msg)),
         GitError::InvalidBranch(msg) => AppError::BadRequest(format!("Invalid branch: {}", msg)),
+        GitError::InvalidArgument(msg) => AppError::BadRequest(msg),
         GitError::OperationFailed(msg) => AppError::Internal(format!("Git operation failed: {}", msg)),
         GitError::CommandFailed(msg) => AppError::Internal(format!("Git command failed: {}", msg)),
+        GitError::AuthFailed(err) => err.into(),
+    }
+}
+
+/// Map a GitError, treating a command git refused as the client's to fix,
+/// e.g. local changes in the way of a merge
+fn map_refused_error(e: GitError) -> AppError {
+    match e {
+        GitError::CommandFailed(msg) => AppError::BadRequest(msg),
+        e => map_git_error(e),
     }
 }
 
 /// GET /api/sessions/{id}/git/status - Get git status
-async fn get_status(
+pub(super) async fn get_status(
     State(state): State<AppState>,
+    user: CurrentUser,
     AxumPath(id): AxumPath<Uuid>,
 ) -> AppResult<Json<GitStatusResponse>> {
-    let repo_path = get_session_repo_path(&state, id).await?;
+    let repo_path = get_session_repo_path(&state, &user, id, false).await?;
     let status = GitManager::status(&repo_path).map_err(map_git_error)?;
 
     Ok(Json(GitStatusResponse {
@@ -131,25 +378,35 @@ async fn get_status(
 /// GET /api/sessions/{id}/git/log - Get recent commits
 async fn get_log(
     State(state): State<AppState>,
+    user: CurrentUser,
     AxumPath(id): AxumPath<Uuid>,
     Query(params): Query<LogQueryParams>,
-) -> AppResult<Json<GitLogResponse>> {
-    let repo_path = get_session_repo_path(&state, id).await?;
-    let limit = params.limit.unwrap_or(20);
-    let commits = GitManager::log(&repo_path, limit).map_err(map_git_error)?;
-
-    Ok(Json(GitLogResponse {
-        session_id: id,
-        commits,
-    }))
+) -> AppResult<Json<Page<Commit>>> {
+    let repo_path = get_session_repo_path(&state, &user, id, false).await?;
+    let page = PageParams {
+        limit: params.limit,
+        cursor: params.cursor,
+    };
+    let offset = page.offset()?;
+    let non_empty = |value: Option<String>| value.filter(|v| !v.trim().is_empty());
+    let filter = LogFilter {
+        rev: non_empty(params.rev),
+        author: non_empty(params.author),
+        path: non_empty(params.path),
+    };
+    let (commits, total) =
+        GitManager::log_filtered(&repo_path, &filter, offset, page.limit_or(20)).map_err(map_git_error)?;
+
+    Ok(Json(Page::new(commits, total, offset)))
 }
 
 /// GET /api/sessions/{id}/git/branches - List branches
 async fn get_branches(
     State(state): State<AppState>,
+    user: CurrentUser,
     AxumPath(id): AxumPath<Uuid>,
 ) -> AppResult<Json<GitBranchesResponse>> {
-    let repo_path = get_session_repo_path(&state, id).await?;
+    let repo_path = get_session_repo_path(&state, &user, id, false).await?;
     let branches = GitManager::branches(&repo_path).map_err(map_git_error)?;
 
     Ok(Json(GitBranchesResponse {
@@ -161,9 +418,10 @@ async fn get_branches(
 /// GET /api/sessions/{id}/git/diff - Get diff statistics
 async fn get_diff(
     State(state): State<AppState>,
+    user: CurrentUser,
     AxumPath(id): AxumPath<Uuid>,
 ) -> AppResult<Json<GitDiffResponse>> {
-    let repo_path = get_session_repo_path(&state, id).await?;
+    let repo_path = get_session_repo_path(&state, &user, id, false).await?;
     let files = GitManager::diff_stats(&repo_path).map_err(map_git_error)?;
 
     let total_added: usize = files.iter().map(|f| f.added).sum();
@@ -180,9 +438,10 @@ async fn get_diff(
 /// POST /api/sessions/{id}/git/pull - Execute git pull
 async fn post_pull(
     State(state): State<AppState>,
+    user: CurrentUser,
     AxumPath(id): AxumPath<Uuid>,
 ) -> AppResult<Json<GitCommandResponse>> {
-    let repo_path = get_session_repo_path(&state, id).await?;
+    let repo_path = get_session_repo_path(&state, &user, id, true).await?;
     let output = GitManager::pull(&repo_path).map_err(map_git_error)?;
 
     Ok(Json(GitCommandResponse {
@@ -194,11 +453,24 @@ async fn post_pull(
 /// POST /api/sessions/{id}/git/push - Execute git push
 async fn post_push(
     State(state): State<AppState>,
+    user: CurrentUser,
     AxumPath(id): AxumPath<Uuid>,
 ) -> AppResult<Json<GitCommandResponse>> {
-    let repo_path = get_session_repo_path(&state, id).await?;
+    let repo_path = get_session_repo_path(&state, &user, id, true).await?;
     let output = GitManager::push(&repo_path).map_err(map_git_error)?;
 
+    state
+        .webhooks
+        .emit(
+            EventType::GitPush,
+            serde_json::json!({
+                "session_id": id,
+                "repo_path": repo_path,
+                "output": output,
+            }),
+        )
+        .await;
+
     Ok(Json(GitCommandResponse {
         session_id: id,
         output,
@@ -208,10 +480,11 @@ async fn post_push(
 /// POST /api/sessions/{id}/git/commit - Execute git commit
 async fn post_commit(
     State(state): State<AppState>,
+    user: CurrentUser,
     AxumPath(id): AxumPath<Uuid>,
     Json(req): Json<CommitRequest>,
 ) -> AppResult<Json<GitCommandResponse>> {
-    let repo_path = get_session_repo_path(&state, id).await?;
+    let repo_path = get_session_repo_path(&state, &user, id, true).await?;
 
     // Validate message
     if req.message.trim().is_empty() {
@@ -224,6 +497,10 @@ async fn post_commit(
     }
 
     let output = GitManager::commit(&repo_path, &req.message).map_err(map_git_error)?;
+    if let Ok(session) = state.db.call(move |db| db.get_session(id)).await {
+        let kind = ActivityKind::CommitMade;
+        activity::record_async(&state.db, kind, session.repo_id, Some(id), &req.message).await;
+    }
 
     Ok(Json(GitCommandResponse {
         session_id: id,
@@ -234,6 +511,7 @@ async fn post_commit(
 /// POST /api/sessions/{id}/git/reset - Execute git reset --hard
 async fn post_reset(
     State(state): State<AppState>,
+    user: CurrentUser,
     AxumPath(id): AxumPath<Uuid>,
     Json(req): Json<ResetRequest>,
 ) -> AppResult<Json<GitCommandResponse>> {
@@ -244,7 +522,7 @@ async fn post_reset(
         ));
     }
 
-    let repo_path = get_session_repo_path(&state, id).await?;
+    let repo_path = get_session_repo_path(&state, &user, id, true).await?;
     let output = GitManager::reset_hard(&repo_path).map_err(map_git_error)?;
 
     Ok(Json(GitCommandResponse {
@@ -256,10 +534,11 @@ async fn post_reset(
 /// POST /api/sessions/{id}/git/checkout - Switch branch
 async fn post_checkout(
     State(state): State<AppState>,
+    user: CurrentUser,
     AxumPath(id): AxumPath<Uuid>,
     Json(req): Json<CheckoutRequest>,
 ) -> AppResult<Json<GitCommandResponse>> {
-    let repo_path = get_session_repo_path(&state, id).await?;
+    let repo_path = get_session_repo_path(&state, &user, id, true).await?;
 
     // Validate branch name
     if req.branch.trim().is_empty() {
@@ -274,6 +553,567 @@ async fn post_checkout(
     }))
 }
 
+/// GET /api/git/{repo_id}/diff - Get the unified diff of one file
+async fn get_file_diff(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(repo_id): AxumPath<Uuid>,
+    Query(query): Query<FileDiffQuery>,
+) -> AppResult<Json<FileDiff>> {
+    let repo_path = get_repo_path(&state, &user, repo_id, false).await?;
+    let diff = GitManager::diff_file(&repo_path, &query.path, query.staged).map_err(map_git_error)?;
+
+    Ok(Json(diff))
+}
+
+/// GET /api/git/{repo_id}/file - A file's content at a revision
+async fn get_file_at_revision(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(repo_id): AxumPath<Uuid>,
+    Query(query): Query<FileAtRevisionQuery>,
+) -> AppResult<Json<FileAtRevision>> {
+    let repo_path = get_repo_path(&state, &user, repo_id, false).await?;
+    let rev = query.rev.as_deref().map(str::trim).filter(|rev| !rev.is_empty());
+    let file = GitManager::show(&repo_path, &query.path, rev).map_err(map_git_error)?;
+
+    Ok(Json(file))
+}
+
+/// GET /api/git/{repo_id}/tree - A directory's files and subdirectories
+async fn get_tree(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(repo_id): AxumPath<Uuid>,
+    Query(query): Query<TreeQuery>,
+) -> AppResult<Json<Vec<TreeEntry>>> {
+    let repo_path = get_repo_path(&state, &user, repo_id, false).await?;
+    let non_empty = |value: &Option<String>| {
+        value.as_deref().map(str::trim).filter(|value| !value.is_empty()).map(str::to_string)
+    };
+    let (rev, path) = (non_empty(&query.rev), non_empty(&query.path));
+    let entries = GitManager::tree(&repo_path, rev.as_deref(), path.as_deref()).map_err(map_git_error)?;
+
+    Ok(Json(entries))
+}
+
+/// POST /api/git/{repo_id}/stage - Stage files, returning the new status
+async fn post_stage(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(repo_id): AxumPath<Uuid>,
+    Json(req): Json<PathsRequest>,
+) -> AppResult<Json<GitStatus>> {
+    let repo_path = get_repo_path(&state, &user, repo_id, true).await?;
+    GitManager::stage(&repo_path, &req.paths).map_err(map_git_error)?;
+    let status = GitManager::status(&repo_path).map_err(map_git_error)?;
+
+    Ok(Json(status))
+}
+
+/// POST /api/git/{repo_id}/unstage - Unstage files, returning the new status
+async fn post_unstage(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(repo_id): AxumPath<Uuid>,
+    Json(req): Json<PathsRequest>,
+) -> AppResult<Json<GitStatus>> {
+    let repo_path = get_repo_path(&state, &user, repo_id, true).await?;
+    GitManager::unstage(&repo_path, &req.paths).map_err(map_git_error)?;
+    let status = GitManager::status(&repo_path).map_err(map_git_error)?;
+
+    Ok(Json(status))
+}
+
+/// GET /api/git/{repo_id}/stash - List stashes, most recent first
+async fn list_stashes(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(repo_id): AxumPath<Uuid>,
+) -> AppResult<Json<Vec<StashEntry>>> {
+    let repo_path = get_repo_path(&state, &user, repo_id, false).await?;
+    let stashes = GitManager::stash_list(&repo_path).map_err(map_git_error)?;
+
+    Ok(Json(stashes))
+}
+
+/// POST /api/git/{repo_id}/stash - Stash local changes
+async fn push_stash(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(repo_id): AxumPath<Uuid>,
+    Json(req): Json<StashRequest>,
+) -> AppResult<Json<StashEntry>> {
+    let repo_path = get_repo_path(&state, &user, repo_id, true).await?;
+    ensure_repo_idle(&state, repo_id).await?;
+
+    let message = req.message.as_deref().map(str::trim).filter(|m| !m.is_empty());
+    let entry = GitManager::stash_push(&repo_path, message, req.include_untracked).map_err(map_git_error)?;
+
+    Ok(Json(entry))
+}
+
+/// POST /api/git/{repo_id}/stash/{index}/pop - Apply a stash and drop it
+async fn pop_stash(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath((repo_id, index)): AxumPath<(Uuid, usize)>,
+) -> AppResult<Json<Vec<StashEntry>>> {
+    let repo_path = get_repo_path(&state, &user, repo_id, true).await?;
+    ensure_repo_idle(&state, repo_id).await?;
+
+    GitManager::stash_pop(&repo_path, index).map_err(map_git_error)?;
+    let stashes = GitManager::stash_list(&repo_path).map_err(map_git_error)?;
+
+    Ok(Json(stashes))
+}
+
+/// DELETE /api/git/{repo_id}/stash/{index} - Throw a stash away
+async fn drop_stash(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath((repo_id, index)): AxumPath<(Uuid, usize)>,
+) -> AppResult<Json<Vec<StashEntry>>> {
+    let repo_path = get_repo_path(&state, &user, repo_id, true).await?;
+
+    GitManager::stash_drop(&repo_path, index).map_err(map_git_error)?;
+    let stashes = GitManager::stash_list(&repo_path).map_err(map_git_error)?;
+
+    Ok(Json(stashes))
+}
+
+/// GET /api/git/{repo_id}/tags - List tags
+async fn list_tags(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(repo_id): AxumPath<Uuid>,
+) -> AppResult<Json<Vec<Tag>>> {
+    let repo_path = get_repo_path(&state, &user, repo_id, false).await?;
+    let tags = GitManager::tags(&repo_path).map_err(map_git_error)?;
+
+    Ok(Json(tags))
+}
+
+/// POST /api/git/{repo_id}/tags - Create a lightweight or annotated tag
+async fn create_tag(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(repo_id): AxumPath<Uuid>,
+    Json(req): Json<CreateTagRequest>,
+) -> AppResult<Json<Tag>> {
+    let repo_path = get_repo_path(&state, &user, repo_id, true).await?;
+
+    let target = req.target.as_deref().map(str::trim).filter(|t| !t.is_empty());
+    let message = req.message.as_deref().map(str::trim).filter(|m| !m.is_empty());
+    let tag = GitManager::create_tag(&repo_path, req.name.trim(), target, message).map_err(map_git_error)?;
+
+    Ok(Json(tag))
+}
+
+/// DELETE /api/git/{repo_id}/tags/{name} - Delete a local tag
+async fn delete_tag(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath((repo_id, name)): AxumPath<(Uuid, String)>,
+) -> AppResult<Json<Vec<Tag>>> {
+    let repo_path = get_repo_path(&state, &user, repo_id, true).await?;
+
+    GitManager::delete_tag(&repo_path, &name).map_err(map_git_error)?;
+    let tags = GitManager::tags(&repo_path).map_err(map_git_error)?;
+
+    Ok(Json(tags))
+}
+
+/// POST /api/git/{repo_id}/tags/{name}/push - Push a tag to a remote
+async fn push_tag(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath((repo_id, name)): AxumPath<(Uuid, String)>,
+    Json(req): Json<PushTagRequest>,
+) -> AppResult<Json<CommandOutput>> {
+    let repo_path = get_repo_path(&state, &user, repo_id, true).await?;
+    let output = GitManager::push_tag(&repo_path, req.remote.trim(), &name).map_err(|e| match e {
+        // Git explains why the remote refused the tag, e.g. it already exists there
+        GitError::CommandFailed(msg) => AppError::BadRequest(msg),
+        e => map_git_error(e),
+    })?;
+
+    Ok(Json(output))
+}
+
+/// GET /api/git/{repo_id}/remotes - List remotes
+async fn list_remotes(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(repo_id): AxumPath<Uuid>,
+) -> AppResult<Json<Vec<RemoteInfo>>> {
+    let repo_path = get_repo_path(&state, &user, repo_id, false).await?;
+    let remotes = GitManager::remotes(&repo_path).map_err(map_git_error)?;
+
+    Ok(Json(remotes))
+}
+
+/// POST /api/git/{repo_id}/remotes - Add a remote
+async fn add_remote(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(repo_id): AxumPath<Uuid>,
+    Json(req): Json<AddRemoteRequest>,
+) -> AppResult<Json<RemoteInfo>> {
+    let repo_path = get_repo_path(&state, &user, repo_id, true).await?;
+    let remote = GitManager::add_remote(&repo_path, req.name.trim(), req.url.trim()).map_err(map_git_error)?;
+
+    Ok(Json(remote))
+}
+
+/// PUT /api/git/{repo_id}/remotes/{name} - Change a remote's URL
+async fn set_remote_url(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath((repo_id, name)): AxumPath<(Uuid, String)>,
+    Json(req): Json<SetRemoteUrlRequest>,
+) -> AppResult<Json<RemoteInfo>> {
+    let repo_path = get_repo_path(&state, &user, repo_id, true).await?;
+    let remote = GitManager::set_remote_url(&repo_path, &name, req.url.trim(), req.push).map_err(map_git_error)?;
+
+    Ok(Json(remote))
+}
+
+/// DELETE /api/git/{repo_id}/remotes/{name} - Remove a remote
+async fn remove_remote(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath((repo_id, name)): AxumPath<(Uuid, String)>,
+) -> AppResult<Json<Vec<RemoteInfo>>> {
+    let repo_path = get_repo_path(&state, &user, repo_id, true).await?;
+
+    GitManager::remove_remote(&repo_path, &name).map_err(map_git_error)?;
+    let remotes = GitManager::remotes(&repo_path).map_err(map_git_error)?;
+
+    Ok(Json(remotes))
+}
+
+/// GET /api/git/{repo_id}/identity - The repository's own commit identity
+async fn get_identity(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(repo_id): AxumPath<Uuid>,
+) -> AppResult<Json<GitIdentity>> {
+    let repo_path = get_repo_path(&state, &user, repo_id, false).await?;
+    let identity = GitManager::identity(&repo_path).map_err(map_git_error)?;
+
+    Ok(Json(identity))
+}
+
+/// PUT /api/git/{repo_id}/identity - Set the identity commits in the repository are made with
+async fn put_identity(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(repo_id): AxumPath<Uuid>,
+    Json(req): Json<GitIdentity>,
+) -> AppResult<Json<GitIdentity>> {
+    let repo_path = get_repo_path(&state, &user, repo_id, true).await?;
+    let identity = GitManager::set_identity(&repo_path, &req).map_err(map_git_error)?;
+
+    Ok(Json(identity))
+}
+
+/// POST /api/git/{repo_id}/merge - Merge a branch into the current one
+async fn post_merge(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(repo_id): AxumPath<Uuid>,
+    Json(req): Json<MergeRequest>,
+) -> AppResult<Json<MergeOutcome>> {
+    let repo_path = get_repo_path(&state, &user, repo_id, true).await?;
+    ensure_repo_idle(&state, repo_id).await?;
+
+    let outcome = GitManager::merge(&repo_path, req.branch.trim()).map_err(map_refused_error)?;
+    Ok(Json(outcome))
+}
+
+/// POST /api/git/{repo_id}/merge/continue - Commit a merge once its conflicts are resolved
+async fn post_merge_continue(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(repo_id): AxumPath<Uuid>,
+) -> AppResult<Json<MergeOutcome>> {
+    let repo_path = get_repo_path(&state, &user, repo_id, true).await?;
+    ensure_repo_idle(&state, repo_id).await?;
+
+    let outcome = GitManager::merge_continue(&repo_path).map_err(map_refused_error)?;
+    Ok(Json(outcome))
+}
+
+/// POST /api/git/{repo_id}/merge/abort - Give up on a merge
+async fn post_merge_abort(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(repo_id): AxumPath<Uuid>,
+) -> AppResult<Json<GitStatus>> {
+    let repo_path = get_repo_path(&state, &user, repo_id, true).await?;
+    ensure_repo_idle(&state, repo_id).await?;
+
+    GitManager::merge_abort(&repo_path).map_err(map_refused_error)?;
+    let status = GitManager::status(&repo_path).map_err(map_git_error)?;
+    Ok(Json(status))
+}
+
+/// POST /api/git/{repo_id}/rebase - Rebase the current branch onto another
+async fn post_rebase(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(repo_id): AxumPath<Uuid>,
+    Json(req): Json<RebaseRequest>,
+) -> AppResult<Json<MergeOutcome>> {
+    let repo_path = get_repo_path(&state, &user, repo_id, true).await?;
+    ensure_repo_idle(&state, repo_id).await?;
+
+    let outcome = GitManager::rebase(&repo_path, req.onto.trim()).map_err(map_refused_error)?;
+    Ok(Json(outcome))
+}
+
+/// POST /api/git/{repo_id}/rebase/continue - Carry on once the current commit's conflicts are resolved
+async fn post_rebase_continue(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(repo_id): AxumPath<Uuid>,
+) -> AppResult<Json<MergeOutcome>> {
+    let repo_path = get_repo_path(&state, &user, repo_id, true).await?;
+    ensure_repo_idle(&state, repo_id).await?;
+
+    let outcome = GitManager::rebase_continue(&repo_path).map_err(map_refused_error)?;
+    Ok(Json(outcome))
+}
+
+/// POST /api/git/{repo_id}/rebase/abort - Give up on a rebase
+async fn post_rebase_abort(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(repo_id): AxumPath<Uuid>,
+) -> AppResult<Json<GitStatus>> {
+    let repo_path = get_repo_path(&state, &user, repo_id, true).await?;
+    ensure_repo_idle(&state, repo_id).await?;
+
+    GitManager::rebase_abort(&repo_path).map_err(map_refused_error)?;
+    let status = GitManager::status(&repo_path).map_err(map_git_error)?;
+    Ok(Json(status))
+}
+
+/// POST /api/git/{repo_id}/cherry-pick - Apply a commit onto the current branch
+async fn post_cherry_pick(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(repo_id): AxumPath<Uuid>,
+    Json(req): Json<CherryPickRequest>,
+) -> AppResult<Json<MergeOutcome>> {
+    let repo_path = get_repo_path(&state, &user, repo_id, true).await?;
+    ensure_repo_idle(&state, repo_id).await?;
+
+    let outcome = GitManager::cherry_pick(&repo_path, req.commit.trim()).map_err(map_refused_error)?;
+    Ok(Json(outcome))
+}
+
+/// POST /api/git/{repo_id}/cherry-pick/continue - Commit a cherry-pick once its conflicts are resolved
+async fn post_cherry_pick_continue(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(repo_id): AxumPath<Uuid>,
+) -> AppResult<Json<MergeOutcome>> {
+    let repo_path = get_repo_path(&state, &user, repo_id, true).await?;
+    ensure_repo_idle(&state, repo_id).await?;
+
+    let outcome = GitManager::cherry_pick_continue(&repo_path).map_err(map_refused_error)?;
+    Ok(Json(outcome))
+}
+
+/// POST /api/git/{repo_id}/cherry-pick/abort - Give up on a cherry-pick
+async fn post_cherry_pick_abort(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(repo_id): AxumPath<Uuid>,
+) -> AppResult<Json<GitStatus>> {
+    let repo_path = get_repo_path(&state, &user, repo_id, true).await?;
+    ensure_repo_idle(&state, repo_id).await?;
+
+    GitManager::cherry_pick_abort(&repo_path).map_err(map_refused_error)?;
+    let status = GitManager::status(&repo_path).map_err(map_git_error)?;
+    Ok(Json(status))
+}
+
+/// POST /api/git/{repo_id}/revert - Undo a commit with a new one
+async fn post_revert(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(repo_id): AxumPath<Uuid>,
+    Json(req): Json<RevertRequest>,
+) -> AppResult<Json<MergeOutcome>> {
+    let repo_path = get_repo_path(&state, &user, repo_id, true).await?;
+    ensure_repo_idle(&state, repo_id).await?;
+
+    let outcome =
+        GitManager::revert(&repo_path, req.commit.trim(), req.no_commit).map_err(map_refused_error)?;
+    Ok(Json(outcome))
+}
+
+/// POST /api/git/{repo_id}/revert/continue - Commit a revert once its conflicts are resolved
+async fn post_revert_continue(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(repo_id): AxumPath<Uuid>,
+) -> AppResult<Json<MergeOutcome>> {
+    let repo_path = get_repo_path(&state, &user, repo_id, true).await?;
+    ensure_repo_idle(&state, repo_id).await?;
+
+    let outcome = GitManager::revert_continue(&repo_path).map_err(map_refused_error)?;
+    Ok(Json(outcome))
+}
+
+/// POST /api/git/{repo_id}/revert/abort - Give up on a revert
+async fn post_revert_abort(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(repo_id): AxumPath<Uuid>,
+) -> AppResult<Json<GitStatus>> {
+    let repo_path = get_repo_path(&state, &user, repo_id, true).await?;
+    ensure_repo_idle(&state, repo_id).await?;
+
+    GitManager::revert_abort(&repo_path).map_err(map_refused_error)?;
+    let status = GitManager::status(&repo_path).map_err(map_git_error)?;
+    Ok(Json(status))
+}
+
+/// GET /api/git/{repo_id}/conflicts - Conflicted files with each side's version
+async fn list_conflicts(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(repo_id): AxumPath<Uuid>,
+) -> AppResult<Json<Vec<ConflictFile>>> {
+    let repo_path = get_repo_path(&state, &user, repo_id, false).await?;
+    let conflicts = GitManager::conflicts(&repo_path).map_err(map_git_error)?;
+
+    Ok(Json(conflicts))
+}
+
+/// POST /api/git/{repo_id}/conflicts - Resolve a conflicted file, returning the rest
+async fn resolve_conflict(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(repo_id): AxumPath<Uuid>,
+    Json(req): Json<ResolveConflictRequest>,
+) -> AppResult<Json<Vec<ConflictFile>>> {
+    let repo_path = get_repo_path(&state, &user, repo_id, true).await?;
+    ensure_repo_idle(&state, repo_id).await?;
+
+    let resolution = match (req.accept, req.content) {
+        (Some(ConflictSide::Ours), None) => ConflictResolution::Ours,
+        (Some(ConflictSide::Theirs), None) => ConflictResolution::Theirs,
+        (None, Some(content)) => ConflictResolution::Content(content),
+        _ => {
+            return Err(AppError::BadRequest(
+                "Give either a side to accept or the resolved content".to_string(),
+            ));
+        }
+    };
+    GitManager::resolve_conflict(&repo_path, &req.path, resolution).map_err(map_git_error)?;
+    let conflicts = GitManager::conflicts(&repo_path).map_err(map_git_error)?;
+
+    Ok(Json(conflicts))
+}
+
+/// Encode a fetch event as an SSE event, naming the final ones
+fn fetch_event(event: FetchEvent) -> Event {
+    let name = match event {
+        FetchEvent::Progress(_) => None,
+        FetchEvent::Complete { .. } => Some("complete"),
+        FetchEvent::Error { .. } => Some("fetch_error"),
+    };
+    let data = serde_json::to_string(&event).unwrap_or_default();
+    match name {
+        Some(name) => Event::default().event(name).data(data),
+        None => Event::default().data(data),
+    }
+}
+
+/// Create an error SSE response for a fetch that never started
+fn fetch_error_sse(message: String) -> SseResponse {
+    let stream = async_stream::stream! {
+        yield Ok(fetch_event(FetchEvent::Error {
+            message,
+            help_steps: Vec::new(),
+            auth_type: None,
+        }));
+    };
+    Sse::new(Box::pin(stream) as SseStream).keep_alive(KeepAlive::default())
+}
+
+/// GET /api/git/{repo_id}/fetch-progress - Fetch from a remote with SSE progress
+///
+/// Streams progress events and a final complete/fetch_error event, like
+/// `/api/repos/clone-progress`. Authentication failures carry the same help
+/// steps as a failed clone.
+async fn fetch_with_progress_sse(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(repo_id): AxumPath<Uuid>,
+    Query(query): Query<FetchProgressQuery>,
+) -> SseResponse {
+    let repo_path = match get_repo_path(&state, &user, repo_id, true).await {
+        Ok(path) => path,
+        Err(e) => return fetch_error_sse(e.to_string()),
+    };
+    let remote = query.remote.trim().to_string();
+    if remote.is_empty() || remote.starts_with('-') {
+        return fetch_error_sse(format!("Invalid remote name: {}", query.remote));
+    }
+
+    let (progress_tx, mut progress_rx) = mpsc::channel::<CloneProgress>(32);
+    let remote_clone = remote.clone();
+    let fetch_handle = tokio::task::spawn_blocking(move || {
+        GitManager::fetch_with_progress(&repo_path, &remote_clone, None, progress_tx)
+    });
+
+    let stream = async_stream::stream! {
+        // The channel closes once the fetch is done, whatever the outcome
+        while let Some(p) = progress_rx.recv().await {
+            yield Ok(fetch_event(FetchEvent::Progress(p)));
+        }
+
+        let event = match fetch_handle.await {
+            Ok(Ok(())) => FetchEvent::Complete {
+                message: format!("Fetched from {}", remote),
+            },
+            Ok(Err(fetch_error)) => {
+                let (message, help_steps, auth_type) = match fetch_error {
+                    CloneError::SshAuthFailed { message, help_steps, .. } => {
+                        (message, help_steps, Some("ssh".to_string()))
+                    }
+                    CloneError::HttpsAuthFailed { message, help_steps, is_github } => {
+                        let auth = if is_github { "github_pat" } else { "https_basic" };
+                        (message, help_steps, Some(auth.to_string()))
+                    }
+                    CloneError::NetworkError { message } => {
+                        (format!("Network error: {}", message), Vec::new(), None)
+                    }
+                    CloneError::OperationFailed { message } => {
+                        (format!("Fetch failed: {}", message), Vec::new(), None)
+                    }
+                    CloneError::Cancelled => ("Fetch was cancelled".to_string(), Vec::new(), None),
+                };
+                FetchEvent::Error { message, help_steps, auth_type }
+            }
+            Err(e) => FetchEvent::Error {
+                message: format!("Fetch task panicked: {}", e),
+                help_steps: Vec::new(),
+                auth_type: None,
+            },
+        };
+        yield Ok(fetch_event(event));
+    };
+
+    Sse::new(Box::pin(stream) as SseStream).keep_alive(KeepAlive::default())
+}
+
 /// Create the git router (nested under sessions)
 pub fn router() -> Router<AppState> {
     Router::new()
@@ -286,6 +1126,34 @@ pub fn router() -> Router<AppState> {
         .route("/sessions/{id}/git/commit", post(post_commit))
         .route("/sessions/{id}/git/reset", post(post_reset))
         .route("/sessions/{id}/git/checkout", post(post_checkout))
+        .route("/git/{repo_id}/diff", get(get_file_diff))
+        .route("/git/{repo_id}/file", get(get_file_at_revision))
+        .route("/git/{repo_id}/tree", get(get_tree))
+        .route("/git/{repo_id}/stage", post(post_stage))
+        .route("/git/{repo_id}/unstage", post(post_unstage))
+        .route("/git/{repo_id}/stash", get(list_stashes).post(push_stash))
+        .route("/git/{repo_id}/stash/{index}", delete(drop_stash))
+        .route("/git/{repo_id}/stash/{index}/pop", post(pop_stash))
+        .route("/git/{repo_id}/fetch-progress", get(fetch_with_progress_sse))
+        .route("/git/{repo_id}/tags", get(list_tags).post(create_tag))
+        .route("/git/{repo_id}/tags/{name}", delete(delete_tag))
+        .route("/git/{repo_id}/tags/{name}/push", post(push_tag))
+        .route("/git/{repo_id}/remotes", get(list_remotes).post(add_remote))
+        .route("/git/{repo_id}/remotes/{name}", put(set_remote_url).delete(remove_remote))
+        .route("/git/{repo_id}/identity", get(get_identity).put(put_identity))
+        .route("/git/{repo_id}/merge", post(post_merge))
+        .route("/git/{repo_id}/merge/continue", post(post_merge_continue))
+        .route("/git/{repo_id}/merge/abort", post(post_merge_abort))
+        .route("/git/{repo_id}/rebase", post(post_rebase))
+        .route("/git/{repo_id}/rebase/continue", post(post_rebase_continue))
+        .route("/git/{repo_id}/rebase/abort", post(post_rebase_abort))
+        .route("/git/{repo_id}/cherry-pick", post(post_cherry_pick))
+        .route("/git/{repo_id}/cherry-pick/continue", post(post_cherry_pick_continue))
+        .route("/git/{repo_id}/cherry-pick/abort", post(post_cherry_pick_abort))
+        .route("/git/{repo_id}/revert", post(post_revert))
+        .route("/git/{repo_id}/revert/continue", post(post_revert_continue))
+        .route("/git/{repo_id}/revert/abort", post(post_revert_abort))
+        .route("/git/{repo_id}/conflicts", get(list_conflicts).post(resolve_conflict))
 }
 
 #[cfg(test)]
@@ -407,10 +1275,10 @@ mod tests {
             .await;
         response.assert_status_ok();
 
-        let log: GitLogResponse = response.json();
-        assert_eq!(log.session_id, session.id);
-        assert!(!log.commits.is_empty());
-        assert_eq!(log.commits[0].message, "Initial commit");
+        let log: Page<Commit> = response.json();
+        assert_eq!(log.total, 1);
+        assert_eq!(log.items[0].message, "Initial commit");
+        assert_eq!(log.next_cursor, None);
     }
 
     #[tokio::test]
@@ -424,8 +1292,58 @@ mod tests {
             .await;
         response.assert_status_ok();
 
-        let log: GitLogResponse = response.json();
-        assert!(log.commits.len() <= 5);
+        let log: Page<Commit> = response.json();
+        assert!(log.items.len() <= 5);
+    }
+
+    #[tokio::test]
+    async fn test_get_log_filtered() {
+        let state = create_test_state();
+        let server = create_test_server(state);
+        let (session, temp_dir) = create_test_session(&server).await;
+
+        let repo = git2::Repository::open(temp_dir.path()).expect("Failed to open repo");
+        fs::write(temp_dir.path().join("notes.txt"), "notes").expect("Failed to write file");
+        let mut index = repo.index().expect("Failed to get index");
+        index.add_path(std::path::Path::new("notes.txt")).expect("Failed to add file");
+        index.write().expect("Failed to write index");
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = git2::Signature::now("Other Author", "other@example.com").unwrap();
+        let parent = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, "Add notes", &tree, &[&parent])
+            .expect("Failed to commit");
+
+        let log: Page<Commit> = server
+            .get(&format!("/sessions/{}/git/log?limit=1", session.id))
+            .await
+            .json();
+        assert_eq!((log.total, log.items[0].message.as_str()), (2, "Add notes"));
+        let log: Page<Commit> = server
+            .get(&format!("/sessions/{}/git/log?limit=1&cursor={}", session.id, log.next_cursor.unwrap()))
+            .await
+            .json();
+        assert_eq!(log.items[0].message, "Initial commit");
+
+        let log: Page<Commit> = server
+            .get(&format!("/sessions/{}/git/log?author=test%20user", session.id))
+            .await
+            .json();
+        assert_eq!((log.total, log.items[0].message.as_str()), (1, "Initial commit"));
+        let log: Page<Commit> = server
+            .get(&format!("/sessions/{}/git/log?path=notes.txt", session.id))
+            .await
+            .json();
+        assert_eq!((log.total, log.items[0].message.as_str()), (1, "Add notes"));
+        let log: Page<Commit> = server
+            .get(&format!("/sessions/{}/git/log?rev=HEAD~1", session.id))
+            .await
+            .json();
+        assert_eq!(log.total, 1);
+
+        server
+            .get(&format!("/sessions/{}/git/log?rev=missing", session.id))
+            .await
+            .assert_status_bad_request();
     }
 
     #[tokio::test]
@@ -510,6 +1428,438 @@ mod tests {
         response.assert_status_bad_request();
     }
 
+    #[tokio::test]
+    async fn test_get_file_diff() {
+        let state = create_test_state();
+        let server = create_test_server(state);
+        let (session, temp_dir) = create_test_session(&server).await;
+        fs::write(temp_dir.path().join("new.txt"), "hello\n").expect("Failed to write file");
+
+        let response = server
+            .get(&format!("/git/{}/diff?path=new.txt", session.repo_id))
+            .await;
+        response.assert_status_ok();
+        let diff: FileDiff = response.json();
+        assert_eq!(diff.path, "new.txt");
+        assert_eq!(diff.hunks.len(), 1);
+        assert_eq!(diff.hunks[0].lines[0].content, "hello");
+        assert_eq!(diff.hunks[0].lines[0].new_lineno, Some(1));
+
+        // Nothing is staged yet
+        let response = server
+            .get(&format!("/git/{}/diff?path=new.txt&staged=true", session.repo_id))
+            .await;
+        response.assert_status_ok();
+        assert!(response.json::<FileDiff>().hunks.is_empty());
+
+        server
+            .get(&format!("/git/{}/diff?path=../outside.txt", session.repo_id))
+            .await
+            .assert_status_bad_request();
+    }
+
+    #[tokio::test]
+    async fn test_stage_and_unstage() {
+        let state = create_test_state();
+        let server = create_test_server(state);
+        let (session, temp_dir) = create_test_session(&server).await;
+        fs::write(temp_dir.path().join("a.txt"), "a").expect("Failed to write file");
+        fs::write(temp_dir.path().join("b.txt"), "b").expect("Failed to write file");
+
+        let response = server
+            .post(&format!("/git/{}/stage", session.repo_id))
+            .json(&PathsRequest {
+                paths: vec!["a.txt".to_string()],
+            })
+            .await;
+        response.assert_status_ok();
+        let status: GitStatus = response.json();
+        assert_eq!(status.staged.len(), 1);
+        assert_eq!(status.staged[0].path, "a.txt");
+        assert_eq!(status.untracked, ["b.txt"]);
+
+        let response = server
+            .post(&format!("/git/{}/unstage", session.repo_id))
+            .json(&PathsRequest {
+                paths: vec!["a.txt".to_string()],
+            })
+            .await;
+        response.assert_status_ok();
+        assert!(response.json::<GitStatus>().staged.is_empty());
+
+        server
+            .post(&format!("/git/{}/stage", session.repo_id))
+            .json(&PathsRequest { paths: vec![] })
+            .await
+            .assert_status_bad_request();
+    }
+
+    #[tokio::test]
+    async fn test_stash_endpoints() {
+        let state = create_test_state();
+        let server = create_test_server(state);
+        let (session, temp_dir) = create_test_session(&server).await;
+        let base = format!("/git/{}/stash", session.repo_id);
+
+        let response = server.post(&base).json(&StashRequest::default()).await;
+        response.assert_status_bad_request();
+
+        fs::write(temp_dir.path().join("notes.txt"), "draft").expect("Failed to write file");
+        let response = server
+            .post(&base)
+            .json(&StashRequest {
+                message: Some("Before the run".to_string()),
+                include_untracked: true,
+            })
+            .await;
+        response.assert_status_ok();
+        let entry: StashEntry = response.json();
+        assert_eq!(entry.index, 0);
+        assert!(entry.message.contains("Before the run"));
+        assert!(!temp_dir.path().join("notes.txt").exists());
+
+        let stashes: Vec<StashEntry> = server.get(&base).await.json();
+        assert_eq!(stashes.len(), 1);
+
+        let response = server.post(&format!("{}/0/pop", base)).await;
+        response.assert_status_ok();
+        assert!(response.json::<Vec<StashEntry>>().is_empty());
+        assert!(temp_dir.path().join("notes.txt").exists());
+
+        server.delete(&format!("{}/0", base)).await.assert_status_bad_request();
+        server
+            .get(&format!("/git/{}/stash", Uuid::new_v4()))
+            .await
+            .assert_status_not_found();
+    }
+
+    #[tokio::test]
+    async fn test_tag_endpoints() {
+        let state = create_test_state();
+        let server = create_test_server(state);
+        let (session, _temp_dir) = create_test_session(&server).await;
+        let base = format!("/git/{}/tags", session.repo_id);
+
+        let response = server
+            .post(&base)
+            .json(&CreateTagRequest {
+                name: "v1.0".to_string(),
+                target: None,
+                message: Some("First release".to_string()),
+            })
+            .await;
+        response.assert_status_ok();
+        let tag: Tag = response.json();
+        assert!(tag.annotated);
+        assert_eq!(tag.message.as_deref(), Some("First release"));
+
+        server
+            .post(&base)
+            .json(&CreateTagRequest {
+                name: "v1.0".to_string(),
+                target: None,
+                message: None,
+            })
+            .await
+            .assert_status_bad_request();
+
+        let tags: Vec<Tag> = server.get(&base).await.json();
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags[0].name, "v1.0");
+
+        // The test repository has no remote to push to
+        server
+            .post(&format!("{}/v1.0/push", base))
+            .json(&PushTagRequest {
+                remote: "origin".to_string(),
+            })
+            .await
+            .assert_status_bad_request();
+
+        let response = server.delete(&format!("{}/v1.0", base)).await;
+        response.assert_status_ok();
+        assert!(response.json::<Vec<Tag>>().is_empty());
+        server.delete(&format!("{}/v1.0", base)).await.assert_status_bad_request();
+    }
+
+    #[tokio::test]
+    async fn test_remote_endpoints() {
+        let state = create_test_state();
+        let server = create_test_server(state);
+        let (session, _temp_dir) = create_test_session(&server).await;
+        let base = format!("/git/{}/remotes", session.repo_id);
+
+        let response = server
+            .post(&base)
+            .json(&AddRemoteRequest {
+                name: "upstream".to_string(),
+                url: "https://example.com/upstream.git".to_string(),
+            })
+            .await;
+        response.assert_status_ok();
+        assert_eq!(response.json::<RemoteInfo>().name, "upstream");
+        server
+            .post(&base)
+            .json(&AddRemoteRequest {
+                name: "-bad".to_string(),
+                url: "https://example.com/bad.git".to_string(),
+            })
+            .await
+            .assert_status_bad_request();
+
+        let response = server
+            .put(&format!("{}/upstream", base))
+            .json(&SetRemoteUrlRequest {
+                url: "https://example.com/moved.git".to_string(),
+                push: false,
+            })
+            .await;
+        response.assert_status_ok();
+        assert_eq!(
+            response.json::<RemoteInfo>().url.as_deref(),
+            Some("https://example.com/moved.git")
+        );
+
+        let remotes: Vec<RemoteInfo> = server.get(&base).await.json();
+        assert_eq!(remotes.len(), 1);
+
+        let response = server.delete(&format!("{}/upstream", base)).await;
+        response.assert_status_ok();
+        assert!(response.json::<Vec<RemoteInfo>>().is_empty());
+        server.delete(&format!("{}/upstream", base)).await.assert_status_bad_request();
+    }
+
+    #[tokio::test]
+    async fn test_identity_endpoints() {
+        let state = create_test_state();
+        let server = create_test_server(state);
+        let (session, temp_dir) = create_test_session(&server).await;
+        let url = format!("/git/{}/identity", session.repo_id);
+
+        let response = server
+            .put(&url)
+            .json(&GitIdentity {
+                name: Some("Ralph Bot".to_string()),
+                email: Some("ralph@example.com".to_string()),
+            })
+            .await;
+        response.assert_status_ok();
+        let config = git2::Repository::open(temp_dir.path()).unwrap().config().unwrap();
+        assert_eq!(config.get_string("user.email").unwrap(), "ralph@example.com");
+        let identity: GitIdentity = server.get(&url).await.json();
+        assert_eq!(identity.name.as_deref(), Some("Ralph Bot"));
+
+        server
+            .put(&url)
+            .json(&GitIdentity {
+                name: Some("Ralph\nBot".to_string()),
+                email: None,
+            })
+            .await
+            .assert_status_bad_request();
+    }
+
+    #[tokio::test]
+    async fn test_merge_endpoints() {
+        let state = create_test_state();
+        let server = create_test_server(state);
+        let (session, temp_dir) = create_test_session(&server).await;
+        let git = git2::Repository::open(temp_dir.path()).unwrap();
+        git.branch("other", &git.head().unwrap().peel_to_commit().unwrap(), false)
+            .unwrap();
+        let base = format!("/git/{}", session.repo_id);
+
+        let response = server
+            .post(&format!("{}/merge", base))
+            .json(&MergeRequest {
+                branch: "other".to_string(),
+            })
+            .await;
+        response.assert_status_ok();
+        let outcome: MergeOutcome = response.json();
+        assert!(outcome.completed);
+        assert!(outcome.conflicts.is_empty());
+
+        server
+            .post(&format!("{}/merge", base))
+            .json(&MergeRequest {
+                branch: "missing".to_string(),
+            })
+            .await
+            .assert_status_bad_request();
+        server
+            .post(&format!("{}/merge/abort", base))
+            .await
+            .assert_status_bad_request();
+        server
+            .post(&format!("{}/rebase/continue", base))
+            .await
+            .assert_status_bad_request();
+    }
+
+    #[tokio::test]
+    async fn test_cherry_pick_endpoints() {
+        let state = create_test_state();
+        let server = create_test_server(state);
+        let (session, temp_dir) = create_test_session(&server).await;
+        let git = git2::Repository::open(temp_dir.path()).unwrap();
+        let head = git.head().unwrap().peel_to_commit().unwrap();
+        let sig = git2::Signature::now("Test", "test@example.com").unwrap();
+        fs::write(temp_dir.path().join("picked.txt"), "picked\n").unwrap();
+        let mut index = git.index().unwrap();
+        index.add_path(std::path::Path::new("picked.txt")).unwrap();
+        let tree = git.find_tree(index.write_tree().unwrap()).unwrap();
+        // A commit on another branch, leaving the checkout alone
+        let picked = git
+            .commit(Some("refs/heads/agent"), &sig, &sig, "Agent change", &tree, &[&head])
+            .unwrap();
+        fs::remove_file(temp_dir.path().join("picked.txt")).unwrap();
+        let base = format!("/git/{}", session.repo_id);
+
+        let response = server
+            .post(&format!("{}/cherry-pick", base))
+            .json(&CherryPickRequest {
+                commit: picked.to_string(),
+            })
+            .await;
+        response.assert_status_ok();
+        let outcome: MergeOutcome = response.json();
+        assert!(outcome.completed);
+        assert!(temp_dir.path().join("picked.txt").exists());
+
+        server
+            .post(&format!("{}/cherry-pick", base))
+            .json(&CherryPickRequest {
+                commit: "missing".to_string(),
+            })
+            .await
+            .assert_status_bad_request();
+        server
+            .post(&format!("{}/cherry-pick/abort", base))
+            .await
+            .assert_status_bad_request();
+    }
+
+    #[tokio::test]
+    async fn test_revert_endpoint() {
+        let state = create_test_state();
+        let server = create_test_server(state);
+        let (session, temp_dir) = create_test_session(&server).await;
+        let git = git2::Repository::open(temp_dir.path()).unwrap();
+        let head = git.head().unwrap().peel_to_commit().unwrap();
+        let sig = git2::Signature::now("Test", "test@example.com").unwrap();
+        fs::write(temp_dir.path().join("bad.txt"), "bad\n").unwrap();
+        let mut index = git.index().unwrap();
+        index.add_path(std::path::Path::new("bad.txt")).unwrap();
+        index.write().unwrap();
+        let tree = git.find_tree(index.write_tree().unwrap()).unwrap();
+        let bad = git
+            .commit(Some("HEAD"), &sig, &sig, "Bad change", &tree, &[&head])
+            .unwrap();
+        let base = format!("/git/{}", session.repo_id);
+
+        let response = server
+            .post(&format!("{}/revert", base))
+            .json(&RevertRequest {
+                commit: bad.to_string(),
+                no_commit: false,
+            })
+            .await;
+        response.assert_status_ok();
+        assert!(response.json::<MergeOutcome>().completed);
+        assert!(!temp_dir.path().join("bad.txt").exists());
+        assert_eq!(git.head().unwrap().peel_to_commit().unwrap().parent_id(0).unwrap(), bad);
+
+        server
+            .post(&format!("{}/revert", base))
+            .json(&RevertRequest {
+                commit: "missing".to_string(),
+                no_commit: true,
+            })
+            .await
+            .assert_status_bad_request();
+        server
+            .post(&format!("{}/revert/continue", base))
+            .await
+            .assert_status_bad_request();
+    }
+
+    #[tokio::test]
+    async fn test_conflict_endpoints() {
+        let state = create_test_state();
+        let server = create_test_server(state);
+        let (session, _temp_dir) = create_test_session(&server).await;
+        let url = format!("/git/{}/conflicts", session.repo_id);
+
+        let conflicts: Vec<ConflictFile> = server.get(&url).await.json();
+        assert!(conflicts.is_empty());
+
+        server
+            .post(&url)
+            .json(&ResolveConflictRequest {
+                path: "README.md".to_string(),
+                accept: Some(ConflictSide::Ours),
+                content: Some("both".to_string()),
+            })
+            .await
+            .assert_status_bad_request();
+        server
+            .post(&url)
+            .json(&ResolveConflictRequest {
+                path: "README.md".to_string(),
+                accept: Some(ConflictSide::Theirs),
+                content: None,
+            })
+            .await
+            .assert_status_bad_request();
+    }
+
+    #[tokio::test]
+    async fn test_get_file_at_revision() {
+        let state = create_test_state();
+        let server = create_test_server(state);
+        let (session, temp_dir) = create_test_session(&server).await;
+        fs::write(temp_dir.path().join("notes.txt"), "first\n").unwrap();
+        GitManager::stage(temp_dir.path(), &["notes.txt".to_string()]).unwrap();
+        GitManager::commit(temp_dir.path(), "Add notes").unwrap();
+        fs::write(temp_dir.path().join("notes.txt"), "second\n").unwrap();
+        let url = format!("/git/{}/file", session.repo_id);
+
+        let response = server.get(&format!("{}?path=notes.txt", url)).await;
+        response.assert_status_ok();
+        let file: FileAtRevision = response.json();
+        assert_eq!(file.content.as_deref(), Some("first\n"));
+
+        server
+            .get(&format!("{}?path=notes.txt&ref=HEAD~1", url))
+            .await
+            .assert_status_bad_request();
+    }
+
+    #[tokio::test]
+    async fn test_get_tree() {
+        let state = create_test_state();
+        let server = create_test_server(state);
+        let (session, temp_dir) = create_test_session(&server).await;
+        fs::create_dir(temp_dir.path().join("docs")).unwrap();
+        fs::write(temp_dir.path().join("docs/guide.md"), "# Guide\n").unwrap();
+        let url = format!("/git/{}/tree", session.repo_id);
+
+        let response = server.get(&format!("{}?path=docs", url)).await;
+        response.assert_status_ok();
+        let entries: Vec<TreeEntry> = response.json();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].path, "docs/guide.md");
+        assert_eq!(entries[0].size, Some(8));
+
+        // Not committed, so not in HEAD's tree
+        server
+            .get(&format!("{}?path=docs&ref=HEAD", url))
+            .await
+            .assert_status_bad_request();
+        server.get(&format!("{}?path=../..", url)).await.assert_status_bad_request();
+    }
+
     #[tokio::test]
     async fn test_nonexistent_session() {
         let state = create_test_state();
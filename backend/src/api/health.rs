@@ -0,0 +1,95 @@
+//! Liveness and readiness probes
+//!
+//! `GET /api/health/live` answers as long as the process can serve requests.
+//! `GET /api/health/ready` answers 200 only once the database schema is
+//! current, every listener is bound, and startup recovery has finished, and
+//! goes back to 503 as soon as a shutdown signal arrives, so a load balancer
+//! stops sending traffic before connections are drained. Both are public.
+
+use axum::{extract::State, http::StatusCode, routing::get, Json, Router};
+use serde::{Deserialize, Serialize};
+
+use crate::readiness::ReadinessChecks;
+
+use super::AppState;
+
+/// Response for the liveness probe
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LiveResponse {
+    pub status: String,
+}
+
+/// Response for the readiness probe
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReadyResponse {
+    /// `ready`, `starting`, or `stopping`
+    pub status: String,
+    pub checks: ReadinessChecks,
+}
+
+/// GET /api/health/live - The process is up
+async fn live() -> Json<LiveResponse> {
+    Json(LiveResponse {
+        status: "ok".to_string(),
+    })
+}
+
+/// GET /api/health/ready - The server is ready for traffic
+async fn ready(State(state): State<AppState>) -> (StatusCode, Json<ReadyResponse>) {
+    let database = state.db.call(|db| Ok(db.is_current())).await.unwrap_or(false);
+    let checks = state.readiness.checks(database);
+
+    let (code, status) = if checks.is_ready() {
+        (StatusCode::OK, "ready")
+    } else if checks.shutting_down {
+        (StatusCode::SERVICE_UNAVAILABLE, "stopping")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "starting")
+    };
+    (
+        code,
+        Json(ReadyResponse {
+            status: status.to_string(),
+            checks,
+        }),
+    )
+}
+
+/// Create the health probe router
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/health/live", get(live))
+        .route("/health/ready", get(ready))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use axum_test::TestServer;
+
+    #[tokio::test]
+    async fn test_ready_follows_startup_and_shutdown() {
+        let state = AppState::new(Database::in_memory().unwrap());
+        let server = TestServer::new(router().with_state(state.clone())).unwrap();
+
+        server.get("/health/live").await.assert_status_ok();
+
+        let response = server.get("/health/ready").await;
+        response.assert_status(StatusCode::SERVICE_UNAVAILABLE);
+        let body: ReadyResponse = response.json();
+        assert_eq!(body.status, "starting");
+        assert!(body.checks.database);
+        assert!(!body.checks.recovery);
+
+        state.readiness.listeners_bound();
+        state.readiness.recovery_finished();
+        server.get("/health/ready").await.assert_status_ok();
+
+        state.readiness.shutting_down();
+        let response = server.get("/health/ready").await;
+        response.assert_status(StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.json::<ReadyResponse>().status, "stopping");
+        server.get("/health/live").await.assert_status_ok();
+    }
+}
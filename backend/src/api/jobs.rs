@@ -0,0 +1,387 @@
+//! Background jobs API
+//!
+//! Starts clones, scans, and maintenance runs as jobs (see [`crate::jobs`])
+//! and reports on them. Starting a job returns it straight away; clients poll
+//! `GET /api/jobs/{id}` until it finishes.
+
+use std::path::PathBuf;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::db::models::{Job, JobKind, JobState};
+use crate::error::{AppError, AppResult};
+use crate::git::{CancelToken, CloneCredentials, CloneError, CloneOptions, CloneProgress, GitManager};
+use crate::jobs::{JobContext, JobError};
+use crate::maintenance::{JobOutcome, JOB_NAMES};
+use crate::users::CurrentUser;
+
+use super::pagination::{Page, PageParams, DEFAULT_LIMIT};
+use super::repos::{self, ApiCredentials, ScanRequest};
+use super::AppState;
+
+/// Request body for starting a job
+#[derive(Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum JobRequest {
+    /// Clone a repository and register it
+    Clone {
+        url: String,
+        #[serde(default)]
+        credentials: Option<ApiCredentials>,
+    },
+    /// Look for git repositories, like `POST /api/repos/scan`
+    Scan(ScanRequest),
+    /// Run a maintenance job now (admin only)
+    Maintenance { job: String },
+}
+
+/// Query parameters for listing jobs
+#[derive(Debug, Deserialize)]
+pub struct ListJobsQuery {
+    pub kind: Option<String>,
+    pub state: Option<String>,
+    pub limit: Option<usize>,
+    pub cursor: Option<String>,
+}
+
+/// Message for a job that failed with an API error
+fn failure(err: AppError) -> JobError {
+    match err {
+        AppError::UserActionRequired { message, .. } => JobError::Failed(message),
+        other => JobError::Failed(other.to_string()),
+    }
+}
+
+/// Fraction and note for a clone progress update
+fn clone_progress(progress: &CloneProgress) -> (Option<f64>, String) {
+    if progress.total_deltas > 0 && progress.received_objects == progress.total_objects {
+        let fraction = progress.indexed_deltas as f64 / progress.total_deltas as f64;
+        let note = format!("Resolving deltas {}/{}", progress.indexed_deltas, progress.total_deltas);
+        (Some(fraction), note)
+    } else if progress.total_objects > 0 {
+        let fraction = progress.received_objects as f64 / progress.total_objects as f64;
+        let note = format!("Receiving objects {}/{}", progress.received_objects, progress.total_objects);
+        (Some(fraction), note)
+    } else {
+        (None, "Connecting".to_string())
+    }
+}
+
+/// Clone into `dest` and register the repository
+///
+/// Cancellation is noticed at the clone's next progress update, or once it
+/// has finished, and the copy is thrown away.
+async fn run_clone(
+    state: AppState,
+    context: JobContext,
+    clone: (String, PathBuf, PathBuf),
+    url: String,
+    credentials: Option<CloneCredentials>,
+    owner_id: Option<Uuid>,
+) -> Result<serde_json::Value, JobError> {
+    let (repo_name, root, dest) = clone;
+    let (progress_tx, mut progress_rx) = mpsc::channel::<CloneProgress>(32);
+    let (source, target) = (url.clone(), dest.clone());
+    let cancel = CancelToken::new();
+    let token = cancel.clone();
+    let handle = tokio::task::spawn_blocking(move || {
+        let options = CloneOptions::default();
+        GitManager::clone_with_credentials(&source, &target, &options, credentials, progress_tx, &token)
+    });
+
+    while let Some(progress) = progress_rx.recv().await {
+        let (fraction, note) = clone_progress(&progress);
+        context.progress(fraction, &note).await;
+        if context.is_cancelled().await {
+            cancel.cancel();
+        }
+    }
+    match handle
+        .await
+        .map_err(|e| JobError::Failed(format!("Clone task panicked: {}", e)))?
+    {
+        Ok(_) => {}
+        Err(CloneError::Cancelled) => return Err(JobError::Cancelled),
+        Err(e) => return Err(failure(e.into())),
+    }
+
+    if context.is_cancelled().await {
+        if let Err(e) = std::fs::remove_dir_all(&dest) {
+            tracing::warn!("Failed to remove cancelled clone {:?}: {}", dest, e);
+        }
+        return Err(JobError::Cancelled);
+    }
+
+    let repo = repos::finish_clone(&state, &root, &dest, &repo_name, &url, owner_id)
+        .await
+        .map_err(failure)?;
+    Ok(serde_json::to_value(repo).unwrap_or_default())
+}
+
+/// POST /api/jobs - Start a job
+async fn create_job(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Json(req): Json<JobRequest>,
+) -> AppResult<(StatusCode, Json<Job>)> {
+    let job = match req {
+        JobRequest::Clone { url, credentials } => {
+            let clone = repos::prepare_clone(&state, &url).await?;
+            let credentials = credentials.map(CloneCredentials::from);
+            let owner_id = user.id;
+            let job_state = state.clone();
+            let description = format!("Clone {}", url);
+            state
+                .jobs
+                .submit(JobKind::Clone, user.id, &description, move |context| {
+                    run_clone(job_state, context, clone, url, credentials, owner_id)
+                })
+                .await
+        }
+        JobRequest::Scan(scan) => {
+            let description = format!("Scan {}", scan.directories.join(", "));
+            state
+                .jobs
+                .submit(JobKind::Scan, user.id, &description, |_| async move {
+                    let found = tokio::task::spawn_blocking(move || repos::scan(&scan))
+                        .await
+                        .map_err(|e| JobError::Failed(format!("Scan task panicked: {}", e)))?;
+                    Ok(serde_json::to_value(found).unwrap_or_default())
+                })
+                .await
+        }
+        JobRequest::Maintenance { job } => {
+            user.require_admin()?;
+            if !JOB_NAMES.contains(&job.as_str()) {
+                return Err(AppError::BadRequest(format!("Unknown maintenance job: {}", job)));
+            }
+            let scheduler = state.maintenance.clone();
+            let description = format!("Run {}", job);
+            state
+                .jobs
+                .submit(JobKind::Maintenance, user.id, &description, |_| async move {
+                    let status = scheduler.run(&job).await.ok_or_else(|| {
+                        JobError::Failed(format!("Unknown maintenance job: {}", job))
+                    })?;
+                    if status.last_outcome == Some(JobOutcome::Failed) {
+                        return Err(JobError::Failed(status.last_message.unwrap_or_default()));
+                    }
+                    Ok(serde_json::to_value(status).unwrap_or_default())
+                })
+                .await
+        }
+    }
+    .map_err(|e| AppError::Internal(e.to_string()))?;
+
+    Ok((StatusCode::ACCEPTED, Json(job)))
+}
+
+/// GET /api/jobs - Jobs visible to the current user, newest first
+async fn list_jobs(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Query(query): Query<ListJobsQuery>,
+) -> AppResult<Json<Page<Job>>> {
+    let kind = query
+        .kind
+        .as_deref()
+        .map(JobKind::from_str)
+        .transpose()
+        .map_err(AppError::BadRequest)?;
+    let job_state = query
+        .state
+        .as_deref()
+        .map(JobState::from_str)
+        .transpose()
+        .map_err(AppError::BadRequest)?;
+
+    let jobs: Vec<Job> = state
+        .db
+        .call(move |db| db.list_jobs(kind, job_state))
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?
+        .into_iter()
+        .filter(|job| user.can_view(job.owner_id))
+        .collect();
+
+    let page = PageParams {
+        limit: query.limit,
+        cursor: query.cursor,
+    };
+    Ok(Json(Page::from_vec(jobs, &page, DEFAULT_LIMIT)?))
+}
+
+/// Look up a job, hiding it from users who can't see it
+async fn find_job(state: &AppState, user: &CurrentUser, id: Uuid) -> AppResult<Job> {
+    let job = state.db.call(move |db| db.get_job(id)).await.map_err(|e| match e {
+        crate::db::DbError::NotFound => AppError::NotFound(format!("Job {} not found", id)),
+        _ => AppError::Internal(e.to_string()),
+    })?;
+    user.check_view(job.owner_id, "job")?;
+    Ok(job)
+}
+
+/// GET /api/jobs/{id} - A job's state, progress, and result
+async fn get_job(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<Job>> {
+    Ok(Json(find_job(&state, &user, id).await?))
+}
+
+/// POST /api/jobs/{id}/cancel - Drop a queued job or ask a running one to stop
+async fn cancel_job(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<Job>> {
+    let job = find_job(&state, &user, id).await?;
+    user.check_modify(job.owner_id, "job")?;
+    if job.state.is_finished() {
+        return Err(AppError::Conflict(format!("Job {} has already finished", id)));
+    }
+
+    let job = state
+        .db
+        .call(move |db| db.cancel_job(id))
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+    Ok(Json(job))
+}
+
+/// Create the jobs router
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/jobs", get(list_jobs).post(create_job))
+        .route("/jobs/{id}", get(get_job))
+        .route("/jobs/{id}/cancel", post(cancel_job))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::maintenance::AGGREGATE_REFRESH;
+    use axum_test::TestServer;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    async fn wait_until_finished(server: &TestServer, id: Uuid) -> Job {
+        for _ in 0..500 {
+            let job: Job = server.get(&format!("/jobs/{}", id)).await.json();
+            if job.state.is_finished() {
+                return job;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("job {} did not finish", id);
+    }
+
+    #[tokio::test]
+    async fn test_scan_and_maintenance_jobs() {
+        let state = AppState::new(Database::in_memory().unwrap());
+        let server = TestServer::new(router().with_state(state)).unwrap();
+
+        let dir = TempDir::new().unwrap();
+        git2::Repository::init(dir.path().join("project")).unwrap();
+
+        let response = server
+            .post("/jobs")
+            .json(&serde_json::json!({
+                "kind": "scan",
+                "directories": [dir.path().to_string_lossy()],
+            }))
+            .await;
+        response.assert_status(StatusCode::ACCEPTED);
+        let job: Job = response.json();
+        assert_eq!((job.kind, job.state), (JobKind::Scan, JobState::Queued));
+
+        let job = wait_until_finished(&server, job.id).await;
+        assert_eq!(job.state, JobState::Done);
+        let result = job.result.unwrap();
+        assert_eq!(result["found"][0]["name"], "project");
+
+        let job: Job = server
+            .post("/jobs")
+            .json(&serde_json::json!({ "kind": "maintenance", "job": AGGREGATE_REFRESH }))
+            .await
+            .json();
+        let job = wait_until_finished(&server, job.id).await;
+        assert_eq!(job.state, JobState::Done);
+        assert_eq!(job.result.unwrap()["runs"], 1);
+
+        server
+            .post("/jobs")
+            .json(&serde_json::json!({ "kind": "maintenance", "job": "defragment" }))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+
+        let page: Page<Job> = server.get("/jobs?kind=scan").await.json();
+        assert_eq!(page.items.len(), 1);
+        server.get("/jobs?state=paused").await.assert_status(StatusCode::BAD_REQUEST);
+
+        // Finished jobs can't be cancelled
+        server
+            .post(&format!("/jobs/{}/cancel", job.id))
+            .await
+            .assert_status(StatusCode::CONFLICT);
+        server
+            .get(&format!("/jobs/{}", Uuid::new_v4()))
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_clone_job() {
+        let source_dir = TempDir::new().unwrap();
+        let source_repo = git2::Repository::init(source_dir.path()).unwrap();
+        {
+            let tree = source_repo.find_tree(source_repo.index().unwrap().write_tree().unwrap()).unwrap();
+            let sig = git2::Signature::now("Test", "test@example.com").unwrap();
+            source_repo
+                .commit(Some("HEAD"), &sig, &sig, "Initial commit", &tree, &[])
+                .unwrap();
+        }
+
+        let clone_root = TempDir::new().unwrap();
+        let state = AppState::with_settings(
+            Database::in_memory().unwrap(),
+            crate::settings::Settings {
+                storage: crate::settings::StorageSettings {
+                    clone_root: Some(clone_root.path().to_path_buf()),
+                    clone_quota_bytes: None,
+                },
+                ..Default::default()
+            },
+        );
+        let server = TestServer::new(router().with_state(state.clone())).unwrap();
+        let url = source_dir.path().to_string_lossy().to_string();
+
+        let job: Job = server
+            .post("/jobs")
+            .json(&serde_json::json!({ "kind": "clone", "url": url }))
+            .await
+            .json();
+        let job = wait_until_finished(&server, job.id).await;
+        assert_eq!(job.state, JobState::Done, "{:?}", job.error);
+        let repos = state.db.list_repos().unwrap();
+        assert_eq!(repos.len(), 1);
+        assert_eq!(job.result.unwrap()["id"], repos[0].id.to_string());
+
+        // The destination now exists, so a second clone is refused up front
+        server
+            .post("/jobs")
+            .json(&serde_json::json!({ "kind": "clone", "url": url }))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,478 @@
+//! Model Context Protocol (MCP) server
+//!
+//! `POST /api/mcp` speaks MCP's Streamable HTTP transport (with plain JSON
+//! responses) so other AI agents and IDE assistants can list repositories,
+//! start sessions, and follow their output and git status. Tools go through
+//! the same handlers as the REST API, with the caller's permissions.
+//! `ralphtown mcp` bridges this endpoint to stdio for clients that launch
+//! servers as subprocesses.
+
+use axum::{
+    body::Bytes,
+    extract::{Path as AxumPath, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router,
+};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::db::models::Orchestrator;
+use crate::error::{AppError, AppResult};
+use crate::users::CurrentUser;
+
+use super::pagination::{PageParams, MAX_LIMIT};
+use super::sessions::{
+    CreateSessionRequest, ListSessionsParams, OutputQueryParams, RunSessionRequest,
+};
+use super::{git, repos, sessions, AppState};
+
+/// Protocol revisions understood, newest first
+pub const PROTOCOL_VERSIONS: [&str; 3] = ["2025-06-18", "2025-03-26", "2024-11-05"];
+
+/// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+type RpcResult = Result<Value, (i64, String)>;
+
+/// Parameters of a `tools/call` request
+#[derive(Debug, Deserialize)]
+struct ToolCall {
+    name: String,
+    #[serde(default)]
+    arguments: Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListSessionsArgs {
+    repo_id: Option<Uuid>,
+    tag: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StartSessionArgs {
+    prompt: String,
+    /// Existing session to continue; a new one is created in `repo_id` otherwise
+    session_id: Option<Uuid>,
+    repo_id: Option<Uuid>,
+    name: Option<String>,
+    #[serde(default)]
+    orchestrator: Orchestrator,
+}
+
+#[derive(Debug, Deserialize)]
+struct SessionArgs {
+    session_id: Uuid,
+}
+
+#[derive(Debug, Deserialize)]
+struct SessionOutputArgs {
+    session_id: Uuid,
+    stream: Option<String>,
+    channel: Option<String>,
+    limit: Option<usize>,
+    cursor: Option<String>,
+}
+
+/// Tools advertised by `tools/list`
+fn tools() -> Value {
+    let session_id = json!({ "type": "string", "format": "uuid", "description": "Session ID" });
+    json!([
+        {
+            "name": "list_repos",
+            "description": "List the git repositories Ralphtown manages",
+            "inputSchema": { "type": "object", "properties": {} },
+        },
+        {
+            "name": "list_sessions",
+            "description": "List sessions with their status and tags, optionally for one repository or tag",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "repo_id": { "type": "string", "format": "uuid", "description": "Only list this repository's sessions" },
+                    "tag": { "type": "string", "description": "Only list sessions with this tag; separate several with commas" },
+                },
+            },
+        },
+        {
+            "name": "start_session",
+            "description": "Run an orchestrator with a prompt, in a new session for a repository or continuing an existing session. Returns immediately; poll get_session_output for progress.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "prompt": { "type": "string", "description": "Task for the agent" },
+                    "repo_id": { "type": "string", "format": "uuid", "description": "Repository to start a new session in" },
+                    "session_id": { "type": "string", "format": "uuid", "description": "Session to continue instead" },
+                    "name": { "type": "string", "description": "Name for a new session" },
+                    "orchestrator": { "type": "string", "description": "Orchestrator for a new session (default: ralph)" },
+                },
+                "required": ["prompt"],
+            },
+        },
+        {
+            "name": "get_session_output",
+            "description": "Get a session's status and the output its runs produced",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "session_id": session_id,
+                    "stream": { "type": "string", "enum": ["stdout", "stderr"], "description": "Only this stream" },
+                    "channel": { "type": "string", "description": "Comma-separated channels to include: assistant_text, tool_call, system, stderr" },
+                    "limit": { "type": "integer", "minimum": 1, "description": "Maximum lines to return" },
+                    "cursor": { "type": "string", "description": "next_cursor from a previous call, to continue reading" },
+                },
+                "required": ["session_id"],
+            },
+        },
+        {
+            "name": "cancel_session",
+            "description": "Stop a session's running process",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "session_id": session_id },
+                "required": ["session_id"],
+            },
+        },
+        {
+            "name": "git_status",
+            "description": "Get the branch and changed files of a session's repository",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "session_id": session_id },
+                "required": ["session_id"],
+            },
+        },
+    ])
+}
+
+/// Parse tool arguments, treating missing arguments as an empty object
+fn arguments<T: DeserializeOwned>(value: Value) -> AppResult<T> {
+    let value = if value.is_null() { json!({}) } else { value };
+    serde_json::from_value(value)
+        .map_err(|e| AppError::BadRequest(format!("Invalid arguments: {}", e)))
+}
+
+/// Refuse tools that change state while the server is read-only
+fn check_writable(state: &AppState, tool: &str) -> AppResult<()> {
+    if state.settings.read_only {
+        return Err(AppError::Forbidden(format!(
+            "Ralphtown is in read-only mode; {} is disabled",
+            tool
+        )));
+    }
+    Ok(())
+}
+
+async fn list_sessions_tool(state: &AppState, user: CurrentUser, args: Value) -> AppResult<Value> {
+    let args: ListSessionsArgs = arguments(args)?;
+    let params = ListSessionsParams {
+        tag: args.tag,
+        limit: Some(MAX_LIMIT),
+        cursor: None,
+    };
+    let Json(sessions) = sessions::list_sessions(State(state.clone()), user, Query(params)).await?;
+    let sessions: Vec<_> = sessions
+        .items
+        .into_iter()
+        .filter(|session| args.repo_id.is_none_or(|repo_id| session.repo_id == repo_id))
+        .collect();
+    Ok(json!({ "sessions": sessions }))
+}
+
+async fn start_session_tool(state: &AppState, user: CurrentUser, args: Value) -> AppResult<Value> {
+    check_writable(state, "start_session")?;
+    let args: StartSessionArgs = arguments(args)?;
+
+    let session_id = match (args.session_id, args.repo_id) {
+        (Some(session_id), _) => session_id,
+        (None, Some(repo_id)) => {
+            let request = CreateSessionRequest {
+                repo_id,
+                name: args.name,
+                orchestrator: args.orchestrator,
+            };
+            let Json(session) =
+                sessions::create_session(State(state.clone()), user.clone(), Json(request)).await?;
+            session.id
+        }
+        (None, None) => {
+            return Err(AppError::BadRequest(
+                "Either repo_id or session_id is required".to_string(),
+            ));
+        }
+    };
+
+    let request = RunSessionRequest {
+        prompt: args.prompt,
+        ..Default::default()
+    };
+    let Json(response) =
+        sessions::run_session(State(state.clone()), user, AxumPath(session_id), Json(request)).await?;
+    Ok(json!(response))
+}
+
+async fn session_output_tool(state: &AppState, user: CurrentUser, args: Value) -> AppResult<Value> {
+    let args: SessionOutputArgs = arguments(args)?;
+    let params = OutputQueryParams {
+        stream: args.stream,
+        channel: args.channel,
+        limit: args.limit,
+        cursor: args.cursor,
+    };
+    let Json(output) =
+        sessions::get_session_output(State(state.clone()), user, AxumPath(args.session_id), Query(params))
+            .await?;
+    let session_id = args.session_id;
+    let status = state.db.call(move |db| db.get_session(session_id)).await?.status;
+
+    Ok(json!({
+        "session_id": args.session_id,
+        "status": status,
+        "logs": output.items,
+        "total": output.total,
+        "next_cursor": output.next_cursor,
+    }))
+}
+
+async fn cancel_session_tool(state: &AppState, user: CurrentUser, args: Value) -> AppResult<Value> {
+    check_writable(state, "cancel_session")?;
+    let args: SessionArgs = arguments(args)?;
+    let Json(response) =
+        sessions::cancel_session(State(state.clone()), user, AxumPath(args.session_id)).await?;
+    Ok(json!(response))
+}
+
+async fn git_status_tool(state: &AppState, user: CurrentUser, args: Value) -> AppResult<Value> {
+    let args: SessionArgs = arguments(args)?;
+    let Json(status) = git::get_status(State(state.clone()), user, AxumPath(args.session_id)).await?;
+    Ok(json!(status))
+}
+
+/// Run a tool and wrap its outcome as a tool result
+///
+/// Tool failures are reported in the result (with `isError`) rather than as
+/// JSON-RPC errors, so the calling model can see what went wrong.
+async fn call_tool(state: &AppState, user: CurrentUser, params: Value) -> RpcResult {
+    let call: ToolCall = serde_json::from_value(params)
+        .map_err(|e| (INVALID_PARAMS, format!("Invalid tool call: {}", e)))?;
+
+    let result = match call.name.as_str() {
+        "list_repos" => repos::list_repos(State(state.clone()), user, Query(PageParams::largest()))
+            .await
+            .map(|Json(repos)| json!({ "repos": repos.items })),
+        "list_sessions" => list_sessions_tool(state, user, call.arguments).await,
+        "start_session" => start_session_tool(state, user, call.arguments).await,
+        "get_session_output" => session_output_tool(state, user, call.arguments).await,
+        "cancel_session" => cancel_session_tool(state, user, call.arguments).await,
+        "git_status" => git_status_tool(state, user, call.arguments).await,
+        _ => return Err((INVALID_PARAMS, format!("Unknown tool: {}", call.name))),
+    };
+
+    Ok(match result {
+        Ok(value) => json!({
+            "content": [{ "type": "text", "text": value.to_string() }],
+            "structuredContent": value,
+            "isError": false,
+        }),
+        Err(e) => json!({
+            "content": [{ "type": "text", "text": e.to_string() }],
+            "isError": true,
+        }),
+    })
+}
+
+/// Agree on a protocol revision and describe the server
+fn initialize(params: &Value) -> Value {
+    let requested = params.get("protocolVersion").and_then(Value::as_str);
+    let version = PROTOCOL_VERSIONS
+        .into_iter()
+        .find(|version| Some(*version) == requested)
+        .unwrap_or(PROTOCOL_VERSIONS[0]);
+
+    json!({
+        "protocolVersion": version,
+        "capabilities": { "tools": {} },
+        "serverInfo": { "name": "ralphtown", "version": env!("CARGO_PKG_VERSION") },
+        "instructions": "Start sessions with start_session, then poll get_session_output until the status is no longer running.",
+    })
+}
+
+fn rpc_error(id: Value, code: i64, message: String) -> Response {
+    Json(json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    }))
+    .into_response()
+}
+
+/// POST /api/mcp - Handle one JSON-RPC message from an MCP client
+async fn handle_message(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    body: Bytes,
+) -> Response {
+    let message: Value = match serde_json::from_slice(&body) {
+        Ok(message) => message,
+        Err(e) => return rpc_error(Value::Null, PARSE_ERROR, e.to_string()),
+    };
+    if !message.is_object() {
+        return rpc_error(
+            Value::Null,
+            INVALID_REQUEST,
+            "Expected a single JSON-RPC message".to_string(),
+        );
+    }
+
+    // Notifications and responses to our (nonexistent) requests need no reply
+    let (Some(method), Some(id)) = (message.get("method").and_then(Value::as_str), message.get("id"))
+    else {
+        return StatusCode::ACCEPTED.into_response();
+    };
+    let id = id.clone();
+    let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+    let result = match method {
+        "initialize" => Ok(initialize(&params)),
+        "ping" => Ok(json!({})),
+        "tools/list" => Ok(json!({ "tools": tools() })),
+        "tools/call" => call_tool(&state, user, params).await,
+        _ => Err((METHOD_NOT_FOUND, format!("Method not found: {}", method))),
+    };
+
+    match result {
+        Ok(result) => Json(json!({ "jsonrpc": "2.0", "id": id, "result": result })).into_response(),
+        Err((code, message)) => rpc_error(id, code, message),
+    }
+}
+
+/// Create the MCP router
+pub fn router() -> Router<AppState> {
+    Router::new().route("/mcp", post(handle_message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::settings::Settings;
+    use axum_test::TestServer;
+    use tempfile::TempDir;
+
+    fn create_test_server(db: Database, read_only: bool) -> TestServer {
+        let settings = Settings {
+            read_only,
+            ..Settings::default()
+        };
+        let app = Router::new()
+            .merge(router())
+            .with_state(AppState::with_settings(db, settings));
+        TestServer::new(app).expect("Failed to create test server")
+    }
+
+    async fn rpc(server: &TestServer, method: &str, params: Value) -> Value {
+        let response = server
+            .post("/mcp")
+            .json(&json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }))
+            .await;
+        response.assert_status_ok();
+        response.json()
+    }
+
+    #[tokio::test]
+    async fn test_initialize_and_list_tools() {
+        let server = create_test_server(Database::in_memory().unwrap(), false);
+
+        let response = rpc(&server, "initialize", json!({ "protocolVersion": "2025-03-26" })).await;
+        assert_eq!(response["result"]["protocolVersion"], "2025-03-26");
+        assert_eq!(response["result"]["serverInfo"]["name"], "ralphtown");
+
+        server
+            .post("/mcp")
+            .json(&json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }))
+            .await
+            .assert_status(StatusCode::ACCEPTED);
+
+        let response = rpc(&server, "tools/list", Value::Null).await;
+        let names: Vec<&str> = response["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|tool| tool["name"].as_str().unwrap())
+            .collect();
+        assert!(names.contains(&"start_session"));
+        assert!(names.contains(&"git_status"));
+
+        let response = rpc(&server, "resources/list", Value::Null).await;
+        assert_eq!(response["error"]["code"], METHOD_NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_call_tools() {
+        let dir = TempDir::new().unwrap();
+        let git_repo = git2::Repository::init(dir.path()).unwrap();
+        let sig = git2::Signature::now("Test User", "test@example.com").unwrap();
+        let tree_id = git_repo.index().unwrap().write_tree().unwrap();
+        let tree = git_repo.find_tree(tree_id).unwrap();
+        git_repo
+            .commit(Some("HEAD"), &sig, &sig, "Initial commit", &tree, &[])
+            .unwrap();
+        let db = Database::in_memory().unwrap();
+        let repo = db
+            .insert_repo(&dir.path().to_string_lossy(), "hello")
+            .unwrap();
+        let session = db.insert_session(repo.id, None, Orchestrator::Ralph).unwrap();
+        let server = create_test_server(db, false);
+
+        let response = rpc(&server, "tools/call", json!({ "name": "list_repos" })).await;
+        let result = &response["result"];
+        assert_eq!(result["isError"], false);
+        assert_eq!(result["structuredContent"]["repos"][0]["name"], "hello");
+
+        let response = rpc(
+            &server,
+            "tools/call",
+            json!({ "name": "git_status", "arguments": { "session_id": session.id } }),
+        )
+        .await;
+        assert_eq!(response["result"]["isError"], false);
+        assert_eq!(response["result"]["structuredContent"]["session_id"], json!(session.id));
+
+        // Failures are reported to the model rather than as protocol errors
+        let response = rpc(
+            &server,
+            "tools/call",
+            json!({ "name": "get_session_output", "arguments": { "session_id": Uuid::new_v4() } }),
+        )
+        .await;
+        assert_eq!(response["result"]["isError"], true);
+
+        let response = rpc(&server, "tools/call", json!({ "name": "rm_rf" })).await;
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+    }
+
+    #[tokio::test]
+    async fn test_read_only_blocks_starting_sessions() {
+        let db = Database::in_memory().unwrap();
+        let repo = db.insert_repo("/tmp/repo", "repo").unwrap();
+        let server = create_test_server(db, true);
+
+        let response = rpc(
+            &server,
+            "tools/call",
+            json!({ "name": "start_session", "arguments": { "repo_id": repo.id, "prompt": "hi" } }),
+        )
+        .await;
+        assert_eq!(response["result"]["isError"], true);
+        assert!(response["result"]["content"][0]["text"]
+            .as_str()
+            .unwrap()
+            .contains("read-only"));
+    }
+}
@@ -1,14 +1,56 @@
+pub mod activity;
+pub mod admin;
+pub mod analytics;
+pub mod artifacts;
+pub mod attachments;
+pub mod auth;
+pub mod batch;
+pub mod bookmarks;
+pub mod bundles;
+pub mod compare;
 pub mod config;
+pub mod forge;
 pub mod git;
+pub mod health;
+pub mod jobs;
+pub mod mcp;
+pub mod pagination;
+pub mod prompts;
 pub mod repos;
+pub mod schedules;
+pub mod search;
 pub mod service;
 pub mod sessions;
+pub mod snapshots;
+pub mod storage;
+pub mod templates;
+pub mod terminals;
+pub mod triggers;
+pub mod uploads;
+pub mod users;
+pub mod webhooks;
+pub mod workers;
+pub mod worktrees;
 
 use std::sync::Arc;
 
+use crate::attachments::AttachmentStore;
 use crate::db::Database;
+use crate::jobs::JobQueue;
+use crate::keep_awake::KeepAwake;
+use crate::maintenance::Scheduler;
+use crate::middleware::rate_limit::RateLimiter;
+use crate::notify::Notifier;
 use crate::ralph::RalphManager;
+use crate::readiness::Readiness;
+use crate::repo_sync::RepoSyncCache;
+use crate::settings::Settings;
+use crate::snapshot::SnapshotStore;
+use crate::terminal::TerminalManager;
+use crate::webhooks::WebhookDispatcher;
+use crate::workers::WorkerRegistry;
 use crate::ws::ConnectionManager;
+use repos::ActiveClones;
 
 /// Application state shared across all handlers
 #[derive(Clone)]
@@ -16,14 +58,91 @@ pub struct AppState {
     pub db: Arc<Database>,
     pub connections: ConnectionManager,
     pub ralph_manager: RalphManager,
+    pub settings: Arc<Settings>,
+    pub rate_limiter: RateLimiter,
+    pub webhooks: WebhookDispatcher,
+    /// Workspace snapshots; `None` when the server has no data directory
+    pub snapshots: Option<SnapshotStore>,
+    /// Open web terminals
+    pub terminals: TerminalManager,
+    /// Background maintenance jobs; started by the server, not by tests
+    pub maintenance: Scheduler,
+    /// Clones, scans, and other long operations started through the jobs API
+    pub jobs: JobQueue,
+    /// Remote workers sessions can be run on
+    pub workers: WorkerRegistry,
+    /// Clones streaming progress, so they can be cancelled
+    pub clones: ActiveClones,
+    /// Ahead/behind counts from the `auto_fetch` maintenance job
+    pub repo_sync: RepoSyncCache,
+    /// Startup and shutdown progress; advanced by the server, not by tests
+    pub readiness: Readiness,
 }
 
 impl AppState {
     pub fn new(db: Database) -> Self {
+        Self::with_settings(db, Settings::default())
+    }
+
+    /// Create state with explicitly loaded settings
+    pub fn with_settings(db: Database, settings: Settings) -> Self {
+        let db = Arc::new(db);
+        let webhooks = WebhookDispatcher::new(db.clone());
+        let workers = WorkerRegistry::new();
+        let snapshots = settings.data_dir.as_deref().map(SnapshotStore::in_data_dir);
+        let mut ralph_manager = RalphManager::with_notifier(Notifier::new(
+            settings.desktop_notifications,
+        ))
+        .with_webhooks(webhooks.clone())
+        .with_plugins(settings.orchestrators.clone())
+        .with_output_limits(settings.output)
+        .with_run_limits(settings.run_limits)
+        .with_workers(workers.clone());
+        if let Some(snapshots) = &snapshots {
+            ralph_manager = ralph_manager.with_snapshots(snapshots.clone());
+        }
+        if !settings.artifacts.is_empty() {
+            ralph_manager = ralph_manager.with_artifacts(settings.artifacts.clone());
+        }
+        if let Some(data_dir) = &settings.data_dir {
+            ralph_manager = ralph_manager.with_attachments(AttachmentStore::in_data_dir(data_dir));
+        }
+        if settings.keep_awake {
+            ralph_manager = ralph_manager.with_keep_awake(KeepAwake::new());
+        }
+        if settings.run_in_pty {
+            ralph_manager = ralph_manager.with_pty();
+        }
+        if let Some(max) = settings.max_concurrent_runs {
+            ralph_manager = ralph_manager.with_max_concurrent_runs(max);
+        }
+        if let Some(sandbox) = &settings.sandbox {
+            ralph_manager = ralph_manager.with_sandbox(sandbox.clone());
+        }
+        let connections = ConnectionManager::new();
+        let repo_sync = RepoSyncCache::new();
+        let maintenance = Scheduler::standard(
+            db.clone(),
+            snapshots.clone(),
+            repo_sync.clone(),
+            connections.clone(),
+            &settings.maintenance,
+        );
         Self {
-            db: Arc::new(db),
-            connections: ConnectionManager::new(),
-            ralph_manager: RalphManager::new(),
+            ralph_manager,
+            maintenance,
+            jobs: JobQueue::new(db.clone()),
+            workers,
+            clones: ActiveClones::new(),
+            readiness: Readiness::new(),
+            snapshots,
+            db,
+            connections,
+            repo_sync,
+            webhooks,
+            terminals: TerminalManager::new(),
+            rate_limiter: RateLimiter::new(settings.rate_limit.clone()),
+            settings: Arc::new(settings),
         }
     }
 }
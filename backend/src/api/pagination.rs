@@ -0,0 +1,124 @@
+//! Pagination envelope shared by list endpoints
+//!
+//! List endpoints return a [`Page`] rather than a bare array and accept
+//! `?limit=` and `?cursor=` query parameters. Cursors are opaque to clients:
+//! pass back the `next_cursor` of one page to fetch the next, and stop when it
+//! is null.
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, AppResult};
+
+/// Page size used when the client doesn't ask for one
+pub const DEFAULT_LIMIT: usize = 100;
+
+/// Largest page a client may ask for
+pub const MAX_LIMIT: usize = 1000;
+
+/// One page of a listing
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Number of items across all pages
+    pub total: usize,
+    /// Cursor for the following page; null on the last page
+    pub next_cursor: Option<String>,
+}
+
+/// Query parameters for paginated listings
+#[derive(Debug, Default, Deserialize)]
+pub struct PageParams {
+    /// Maximum number of items to return (default: 100, at most 1000)
+    pub limit: Option<usize>,
+    /// `next_cursor` from the previous page
+    pub cursor: Option<String>,
+}
+
+impl PageParams {
+    /// Parameters for the first page with the largest allowed size
+    pub fn largest() -> Self {
+        Self {
+            limit: Some(MAX_LIMIT),
+            cursor: None,
+        }
+    }
+
+    /// Number of items to return, falling back to `default`
+    pub fn limit_or(&self, default: usize) -> usize {
+        self.limit.unwrap_or(default).clamp(1, MAX_LIMIT)
+    }
+
+    /// Position of the first item of the requested page
+    pub fn offset(&self) -> AppResult<usize> {
+        match &self.cursor {
+            None => Ok(0),
+            Some(cursor) => cursor
+                .parse()
+                .map_err(|_| AppError::BadRequest(format!("Invalid cursor: {}", cursor))),
+        }
+    }
+}
+
+impl<T> Page<T> {
+    /// Wrap the items found at `offset`, out of `total`
+    pub fn new(items: Vec<T>, total: usize, offset: usize) -> Self {
+        let end = offset + items.len();
+        Self {
+            next_cursor: (end < total).then(|| end.to_string()),
+            items,
+            total,
+        }
+    }
+
+    /// Cut the requested page out of a complete listing
+    pub fn from_vec(all: Vec<T>, params: &PageParams, default_limit: usize) -> AppResult<Self> {
+        let offset = params.offset()?;
+        let total = all.len();
+        let items = all
+            .into_iter()
+            .skip(offset)
+            .take(params.limit_or(default_limit))
+            .collect();
+        Ok(Self::new(items, total, offset))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(limit: Option<usize>, cursor: Option<&str>) -> PageParams {
+        PageParams {
+            limit,
+            cursor: cursor.map(String::from),
+        }
+    }
+
+    #[test]
+    fn test_page_from_vec() {
+        let page = Page::from_vec((0..5).collect(), &params(Some(2), None), DEFAULT_LIMIT).unwrap();
+        assert_eq!(page.items, vec![0, 1]);
+        assert_eq!(page.total, 5);
+        assert_eq!(page.next_cursor.as_deref(), Some("2"));
+
+        let page = Page::from_vec((0..5).collect(), &params(Some(2), Some("4")), DEFAULT_LIMIT).unwrap();
+        assert_eq!(page.items, vec![4]);
+        assert_eq!(page.next_cursor, None);
+
+        let page = Page::from_vec((0..5).collect::<Vec<i32>>(), &params(None, Some("9")), DEFAULT_LIMIT)
+            .unwrap();
+        assert!(page.items.is_empty());
+        assert_eq!(page.next_cursor, None);
+    }
+
+    #[test]
+    fn test_page_params() {
+        assert_eq!(params(None, None).limit_or(20), 20);
+        assert_eq!(params(Some(0), None).limit_or(20), 1);
+        assert_eq!(params(Some(50_000), None).limit_or(20), MAX_LIMIT);
+        assert!(matches!(
+            params(None, Some("abc")).offset(),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+}
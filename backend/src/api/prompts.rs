@@ -0,0 +1,101 @@
+//! Prompt history
+//!
+//! Every prompt that starts a run is remembered per repository, so the run
+//! dialog can suggest prompts used before.
+
+use axum::{
+    extract::{Query, State},
+    routing::get,
+    Json, Router,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::db::models::PromptHistoryEntry;
+use crate::error::{AppError, AppResult};
+use crate::users::CurrentUser;
+
+use super::pagination::{Page, PageParams};
+use super::AppState;
+
+/// Prompts returned when the client doesn't ask for a page size
+const DEFAULT_LIMIT: usize = 20;
+
+/// Query parameters for recent prompts
+#[derive(Debug, Deserialize)]
+pub struct RecentPromptsQuery {
+    pub repo_id: Uuid,
+    /// Only prompts containing this text (case-insensitive)
+    pub q: Option<String>,
+    pub limit: Option<usize>,
+    pub cursor: Option<String>,
+}
+
+/// GET /api/prompts/recent - A repository's prompts, most recently used first
+async fn recent_prompts(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Query(query): Query<RecentPromptsQuery>,
+) -> AppResult<Json<Page<PromptHistoryEntry>>> {
+    let repo_id = query.repo_id;
+    let repo = state.db.call(move |db| db.get_repo(repo_id)).await.map_err(|e| match e {
+        crate::db::DbError::NotFound => {
+            AppError::NotFound(format!("Repository not found: {}", query.repo_id))
+        }
+        _ => AppError::Internal(e.to_string()),
+    })?;
+    user.check_view(repo.owner_id, "Repository")?;
+
+    let filter = query.q.as_deref().map(str::trim).filter(|q| !q.is_empty()).map(str::to_string);
+    let prompts = state
+        .db
+        .call(move |db| db.list_recent_prompts(repo_id, filter.as_deref()))
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+
+    let page = PageParams {
+        limit: query.limit,
+        cursor: query.cursor,
+    };
+    Ok(Json(Page::from_vec(prompts, &page, DEFAULT_LIMIT)?))
+}
+
+/// Create the prompts router
+pub fn router() -> Router<AppState> {
+    Router::new().route("/prompts/recent", get(recent_prompts))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use axum_test::TestServer;
+
+    #[tokio::test]
+    async fn test_recent_prompts() {
+        let db = Database::in_memory().expect("Failed to create test database");
+        let repo = db.insert_repo("/path/to/repo", "my-repo").unwrap();
+        db.record_prompt(repo.id, "Fix the flaky test").unwrap();
+        db.record_prompt(repo.id, "Write docs").unwrap();
+        let server = TestServer::new(router().with_state(AppState::new(db))).unwrap();
+
+        let page: Page<PromptHistoryEntry> = server
+            .get(&format!("/prompts/recent?repo_id={}", repo.id))
+            .await
+            .json();
+        assert_eq!(page.total, 2);
+        assert_eq!(page.items[0].prompt, "Write docs");
+
+        let page: Page<PromptHistoryEntry> = server
+            .get(&format!("/prompts/recent?repo_id={}&q=flaky&limit=1", repo.id))
+            .await
+            .json();
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].prompt, "Fix the flaky test");
+
+        server
+            .get(&format!("/prompts/recent?repo_id={}", Uuid::new_v4()))
+            .await
+            .assert_status_not_found();
+    }
+}
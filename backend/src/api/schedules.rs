@@ -0,0 +1,401 @@
+//! Scheduled runs
+//!
+//! Administrators define schedules that run a prompt on a repository at the
+//! times of a cron expression (see [`crate::cron`]). A background task checks
+//! for due schedules twice a minute; each firing starts a new, shared session
+//! named after the schedule and is recorded with the session it started, or
+//! why it couldn't. Times missed while the server was down are made up with a
+//! single run when it starts again.
+
+use std::time::Duration;
+
+use axum::{
+    extract::{Path as AxumPath, State},
+    routing::get,
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::cron::Cron;
+use crate::db::models::{Orchestrator, Schedule, ScheduleRun};
+use crate::error::{AppError, AppResult};
+use crate::sanitize;
+use crate::users::CurrentUser;
+
+use super::sessions::{self, CreateSessionRequest, RunSessionRequest};
+use super::AppState;
+
+/// How often due schedules are looked for
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Most runs listed per schedule
+const MAX_LISTED_RUNS: u32 = 50;
+
+/// Request body for creating a schedule
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CreateScheduleRequest {
+    pub name: String,
+    pub repo_id: Uuid,
+    pub prompt: String,
+    /// Five-field cron expression, in UTC
+    pub cron: String,
+    #[serde(default)]
+    pub orchestrator: Orchestrator,
+}
+
+/// Request body for changing a schedule; omitted fields are left unchanged
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct UpdateScheduleRequest {
+    pub name: Option<String>,
+    pub prompt: Option<String>,
+    pub cron: Option<String>,
+    pub orchestrator: Option<Orchestrator>,
+    pub enabled: Option<bool>,
+}
+
+fn schedule_not_found(id: Uuid) -> impl FnOnce(crate::db::DbError) -> AppError {
+    move |e| match e {
+        crate::db::DbError::NotFound => AppError::NotFound(format!("Schedule not found: {}", id)),
+        _ => AppError::Internal(e.to_string()),
+    }
+}
+
+/// Check a schedule's settings before saving them, returning its expression
+async fn validate(
+    state: &AppState,
+    name: &str,
+    repo_id: Uuid,
+    prompt: &str,
+    cron: &str,
+    orchestrator: &Orchestrator,
+) -> AppResult<Cron> {
+    if name.trim().is_empty() {
+        return Err(AppError::BadRequest("Schedule name is required".to_string()));
+    }
+    if prompt.trim().is_empty() {
+        return Err(AppError::BadRequest("Prompt is required".to_string()));
+    }
+    sanitize::prompt(prompt).map_err(|e| AppError::BadRequest(e.to_string()))?;
+    let cron = Cron::parse(cron)
+        .map_err(|e| AppError::BadRequest(format!("Invalid cron expression: {}", e)))?;
+    if !state.settings.orchestrator_available(orchestrator) {
+        return Err(AppError::BadRequest(format!(
+            "Orchestrator '{}' is not yet available",
+            orchestrator.as_str()
+        )));
+    }
+
+    state.db.call(move |db| db.get_repo(repo_id)).await.map_err(|e| match e {
+        crate::db::DbError::NotFound => {
+            AppError::BadRequest(format!("Repository not found: {}", repo_id))
+        }
+        _ => AppError::Internal(e.to_string()),
+    })?;
+    Ok(cron)
+}
+
+/// GET /api/schedules - List schedules
+async fn list_schedules(
+    State(state): State<AppState>,
+    user: CurrentUser,
+) -> AppResult<Json<Vec<Schedule>>> {
+    user.require_admin()?;
+    Ok(Json(state.db.call(|db| db.list_schedules()).await?))
+}
+
+/// POST /api/schedules - Create a schedule
+async fn create_schedule(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Json(req): Json<CreateScheduleRequest>,
+) -> AppResult<Json<Schedule>> {
+    user.require_admin()?;
+    let cron = validate(
+        &state,
+        &req.name,
+        req.repo_id,
+        &req.prompt,
+        &req.cron,
+        &req.orchestrator,
+    )
+    .await?;
+
+    let next_run_at = cron.next_after(Utc::now());
+    let schedule = state
+        .db
+        .call(move |db| {
+            db.insert_schedule(
+                req.name.trim(),
+                req.repo_id,
+                &req.prompt,
+                req.cron.trim(),
+                req.orchestrator,
+                next_run_at,
+            )
+        })
+        .await?;
+    Ok(Json(schedule))
+}
+
+/// GET /api/schedules/{id} - Get a schedule
+async fn get_schedule(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+) -> AppResult<Json<Schedule>> {
+    user.require_admin()?;
+    let schedule = state.db.call(move |db| db.get_schedule(id)).await;
+    Ok(Json(schedule.map_err(schedule_not_found(id))?))
+}
+
+/// PUT /api/schedules/{id} - Change a schedule
+///
+/// Its next time is worked out again from now.
+async fn update_schedule(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+    Json(req): Json<UpdateScheduleRequest>,
+) -> AppResult<Json<Schedule>> {
+    user.require_admin()?;
+    let mut schedule = state
+        .db
+        .call(move |db| db.get_schedule(id))
+        .await
+        .map_err(schedule_not_found(id))?;
+
+    if let Some(name) = req.name {
+        schedule.name = name.trim().to_string();
+    }
+    if let Some(prompt) = req.prompt {
+        schedule.prompt = prompt;
+    }
+    if let Some(cron) = req.cron {
+        schedule.cron = cron.trim().to_string();
+    }
+    if let Some(orchestrator) = req.orchestrator {
+        schedule.orchestrator = orchestrator;
+    }
+    if let Some(enabled) = req.enabled {
+        schedule.enabled = enabled;
+    }
+    let cron = validate(
+        &state,
+        &schedule.name,
+        schedule.repo_id,
+        &schedule.prompt,
+        &schedule.cron,
+        &schedule.orchestrator,
+    )
+    .await?;
+    schedule.next_run_at = cron.next_after(Utc::now());
+
+    state
+        .db
+        .call(move |db| db.update_schedule(&schedule))
+        .await
+        .map_err(schedule_not_found(id))?;
+    Ok(Json(state.db.call(move |db| db.get_schedule(id)).await?))
+}
+
+/// DELETE /api/schedules/{id} - Delete a schedule
+async fn delete_schedule(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+) -> AppResult<Json<()>> {
+    user.require_admin()?;
+    state
+        .db
+        .call(move |db| db.delete_schedule(id))
+        .await
+        .map_err(schedule_not_found(id))?;
+    Ok(Json(()))
+}
+
+/// GET /api/schedules/{id}/runs - A schedule's most recent runs, newest first
+async fn list_schedule_runs(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+) -> AppResult<Json<Vec<ScheduleRun>>> {
+    user.require_admin()?;
+    state
+        .db
+        .call(move |db| db.get_schedule(id))
+        .await
+        .map_err(schedule_not_found(id))?;
+    Ok(Json(state.db.call(move |db| db.list_schedule_runs(id, MAX_LISTED_RUNS)).await?))
+}
+
+/// Start firing schedules in the background
+pub fn start(state: AppState) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            fire_due(&state, Utc::now()).await;
+        }
+    });
+}
+
+/// Fire every enabled schedule whose time has come by `now`
+async fn fire_due(state: &AppState, now: DateTime<Utc>) {
+    let schedules = match state.db.call(|db| db.list_schedules()).await {
+        Ok(schedules) => schedules,
+        Err(e) => {
+            tracing::warn!("Failed to look for due schedules: {}", e);
+            return;
+        }
+    };
+    let due = schedules.into_iter().filter(|schedule| {
+        schedule.enabled && schedule.next_run_at.is_some_and(|at| at <= now)
+    });
+
+    for schedule in due {
+        // Worked out from now, so times missed while the server was down
+        // don't each fire
+        let next = Cron::parse(&schedule.cron)
+            .ok()
+            .and_then(|cron| cron.next_after(now));
+        let schedule_id = schedule.id;
+        let fired = state.db.call(move |db| db.mark_schedule_fired(schedule_id, now, next)).await;
+        if let Err(e) = fired {
+            tracing::warn!("Failed to record schedule '{}' firing: {}", schedule.name, e);
+            continue;
+        }
+
+        let (session_id, error) = start_run(state, &schedule).await;
+        match &error {
+            Some(e) => tracing::warn!("Schedule '{}' failed to start a run: {}", schedule.name, e),
+            None => tracing::info!("Schedule '{}' started a run", schedule.name),
+        }
+        let recorded = state
+            .db
+            .call(move |db| db.insert_schedule_run(schedule_id, session_id, error.as_deref(), now))
+            .await;
+        if let Err(e) = recorded {
+            tracing::warn!("Failed to record schedule '{}' run: {}", schedule.name, e);
+        }
+    }
+}
+
+/// Start a new session running a schedule's prompt
+///
+/// Returns the session, if one was created, and why the run couldn't start.
+async fn start_run(state: &AppState, schedule: &Schedule) -> (Option<Uuid>, Option<String>) {
+    // Schedules act for the server, so their sessions are shared
+    let user = CurrentUser::root();
+    let request = CreateSessionRequest {
+        repo_id: schedule.repo_id,
+        name: Some(schedule.name.clone()),
+        orchestrator: schedule.orchestrator.clone(),
+    };
+    let created = sessions::create_session(State(state.clone()), user.clone(), Json(request)).await;
+    let session = match created {
+        Ok(Json(session)) => session,
+        Err(e) => return (None, Some(e.to_string())),
+    };
+
+    let request = RunSessionRequest {
+        prompt: schedule.prompt.clone(),
+        ..Default::default()
+    };
+    let run =
+        sessions::run_session(State(state.clone()), user, AxumPath(session.id), Json(request)).await;
+    (Some(session.id), run.err().map(|e| e.to_string()))
+}
+
+/// Create the schedules router
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/schedules", get(list_schedules).post(create_schedule))
+        .route(
+            "/schedules/{id}",
+            get(get_schedule).put(update_schedule).delete(delete_schedule),
+        )
+        .route("/schedules/{id}/runs", get(list_schedule_runs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use axum_test::TestServer;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_schedule_crud_and_firing() {
+        let db = Database::in_memory().expect("Failed to create test database");
+        let repo = db.insert_repo("/tmp/ralphtown-missing-repo", "repo").unwrap();
+        let state = AppState::new(db.clone());
+        let server = TestServer::new(router().with_state(state.clone())).unwrap();
+
+        server
+            .post("/schedules")
+            .json(&json!({
+                "name": "nightly",
+                "repo_id": repo.id,
+                "prompt": "Update dependencies",
+                "cron": "0 3 * * *",
+            }))
+            .await
+            .assert_status_ok();
+        server
+            .post("/schedules")
+            .json(&json!({
+                "name": "broken",
+                "repo_id": repo.id,
+                "prompt": "Update dependencies",
+                "cron": "0 25 * * *",
+            }))
+            .await
+            .assert_status_bad_request();
+
+        let schedules: Vec<Schedule> = server.get("/schedules").await.json();
+        assert_eq!(schedules.len(), 1);
+        let schedule = &schedules[0];
+        let next = schedule.next_run_at.unwrap();
+        assert!(next > Utc::now());
+        assert_eq!(next.format("%H:%M").to_string(), "03:00");
+
+        // Nothing is due yet; once it is, it fires once and moves on
+        fire_due(&state, Utc::now()).await;
+        assert!(db.list_sessions().unwrap().is_empty());
+        fire_due(&state, next).await;
+        let sessions = db.list_sessions().unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].name.as_deref(), Some("nightly"));
+        let fired = db.get_schedule(schedule.id).unwrap();
+        assert_eq!(fired.last_run_at, Some(next));
+        assert_eq!(fired.next_run_at, Some(next + chrono::Duration::days(1)));
+
+        // The run itself fails without ralph or a real repository
+        let runs: Vec<ScheduleRun> = server
+            .get(&format!("/schedules/{}/runs", schedule.id))
+            .await
+            .json();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].session_id, Some(sessions[0].id));
+
+        let disabled: Schedule = server
+            .put(&format!("/schedules/{}", schedule.id))
+            .json(&json!({ "enabled": false }))
+            .await
+            .json();
+        assert!(!disabled.enabled);
+        fire_due(&state, next + chrono::Duration::days(2)).await;
+        assert_eq!(db.list_sessions().unwrap().len(), 1);
+
+        server
+            .delete(&format!("/schedules/{}", schedule.id))
+            .await
+            .assert_status_ok();
+        server
+            .get(&format!("/schedules/{}", schedule.id))
+            .await
+            .assert_status_not_found();
+    }
+}
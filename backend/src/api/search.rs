@@ -0,0 +1,84 @@
+//! Full-text search over session history
+//!
+//! Finds where a word or phrase came up in any session's messages or run
+//! output, in the sessions the user can see.
+
+use axum::{
+    extract::{Query, State},
+    routing::get,
+    Json, Router,
+};
+use serde::Deserialize;
+
+use crate::db::models::SearchResults;
+use crate::error::{AppError, AppResult};
+use crate::users::CurrentUser;
+
+use super::AppState;
+
+/// Hits of each kind returned when the client doesn't ask for a number
+const DEFAULT_LIMIT: usize = 20;
+
+/// Most hits of each kind a client may ask for
+const MAX_LIMIT: usize = 100;
+
+/// Query parameters for a search
+#[derive(Debug, Deserialize)]
+pub struct SearchParams {
+    /// Words that must all appear, matched whole and ignoring case
+    pub q: String,
+    /// Maximum number of sessions, messages, and output lines each (default: 20, at most 100)
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// GET /api/search?q= - Sessions, messages, and output lines mentioning the query
+async fn search(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Query(params): Query<SearchParams>,
+) -> AppResult<Json<SearchResults>> {
+    if params.q.trim().is_empty() {
+        return Err(AppError::BadRequest("Search query is empty".to_string()));
+    }
+    let limit = params.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let visible_to = user.visible_owner();
+    let results = state
+        .db
+        .call(move |db| db.search(&params.q, visible_to, limit))
+        .await?;
+    Ok(Json(results))
+}
+
+/// Create the search router
+pub fn router() -> Router<AppState> {
+    Router::new().route("/search", get(search))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::{MessageRole, Orchestrator, OutputStream};
+    use crate::db::Database;
+    use axum_test::TestServer;
+
+    #[tokio::test]
+    async fn test_search() {
+        let db = Database::in_memory().expect("Failed to create test database");
+        let repo = db.insert_repo("/path/to/repo", "my-repo").unwrap();
+        let session = db.insert_session(repo.id, None, Orchestrator::Ralph).unwrap();
+        db.insert_message(session.id, MessageRole::Assistant, "Added retries to the uploader")
+            .unwrap();
+        db.insert_output_log(session.id, OutputStream::Stdout, "uploader: 3 retries left")
+            .unwrap();
+
+        let server = TestServer::new(router().with_state(AppState::new(db))).unwrap();
+        let results: SearchResults = server.get("/search?q=retries%20uploader").await.json();
+        assert_eq!(results.sessions.len(), 1);
+        assert_eq!(results.sessions[0].session_id, session.id);
+        assert_eq!(results.messages.len(), 1);
+        assert_eq!(results.output.len(), 1);
+
+        server.get("/search?q=%20").await.assert_status_bad_request();
+    }
+}
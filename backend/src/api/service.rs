@@ -6,14 +6,19 @@
 //! - POST /api/service/uninstall - Uninstall system service
 //! - POST /api/service/start - Start the service
 //! - POST /api/service/stop - Stop the service
+//!
+//! Operations other than status require an administrator.
 
 use axum::{
+    extract::State,
     routing::{get, post},
     Json, Router,
 };
 use serde::{Deserialize, Serialize};
 
+use crate::runtime::RuntimeState;
 use crate::service::{ServiceController, ServiceStatus};
+use crate::users::CurrentUser;
 use crate::{AppError, AppResult};
 
 /// Response for service status
@@ -23,6 +28,8 @@ pub struct ServiceStatusResponse {
     pub installed: bool,
     pub running: bool,
     pub label: String,
+    /// Where the server is listening, from the runtime state file
+    pub url: Option<String>,
 }
 
 /// Response for service operations
@@ -33,7 +40,7 @@ pub struct ServiceOperationResponse {
 }
 
 /// Get the current service status
-async fn get_status() -> AppResult<Json<ServiceStatusResponse>> {
+async fn get_status(State(state): State<super::AppState>) -> AppResult<Json<ServiceStatusResponse>> {
     let controller = ServiceController::new();
     let status = controller.status();
 
@@ -49,11 +56,18 @@ async fn get_status() -> AppResult<Json<ServiceStatusResponse>> {
         installed,
         running,
         label: controller.label().to_string(),
+        url: state
+            .settings
+            .data_dir
+            .as_deref()
+            .and_then(RuntimeState::read)
+            .map(|runtime| runtime.url),
     }))
 }
 
 /// Install Ralphtown as a system service
-async fn install_service() -> AppResult<Json<ServiceOperationResponse>> {
+async fn install_service(user: CurrentUser) -> AppResult<Json<ServiceOperationResponse>> {
+    user.require_admin()?;
     let controller = ServiceController::new();
 
     match controller.install() {
@@ -67,7 +81,8 @@ async fn install_service() -> AppResult<Json<ServiceOperationResponse>> {
 }
 
 /// Uninstall the Ralphtown system service
-async fn uninstall_service() -> AppResult<Json<ServiceOperationResponse>> {
+async fn uninstall_service(user: CurrentUser) -> AppResult<Json<ServiceOperationResponse>> {
+    user.require_admin()?;
     let controller = ServiceController::new();
 
     match controller.uninstall() {
@@ -83,7 +98,8 @@ async fn uninstall_service() -> AppResult<Json<ServiceOperationResponse>> {
 }
 
 /// Start the Ralphtown service
-async fn start_service() -> AppResult<Json<ServiceOperationResponse>> {
+async fn start_service(user: CurrentUser) -> AppResult<Json<ServiceOperationResponse>> {
+    user.require_admin()?;
     let controller = ServiceController::new();
 
     match controller.start() {
@@ -96,7 +112,8 @@ async fn start_service() -> AppResult<Json<ServiceOperationResponse>> {
 }
 
 /// Stop the Ralphtown service
-async fn stop_service() -> AppResult<Json<ServiceOperationResponse>> {
+async fn stop_service(user: CurrentUser) -> AppResult<Json<ServiceOperationResponse>> {
+    user.require_admin()?;
     let controller = ServiceController::new();
 
     match controller.stop() {
@@ -1,15 +1,22 @@
 use axum::{
     extract::{Path as AxumPath, Query, State},
-    routing::{get, post},
+    routing::{delete, get, patch, post},
     Json, Router,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
 use uuid::Uuid;
 
-use crate::db::models::{Message, Orchestrator, OutputStream, OutputLog, Session, SessionStatus};
+use crate::db::models::{
+    Attachment, Iteration, Message, MessageRole, Orchestrator, OutputChannel, OutputStream, OutputLog, PipelineStep, Run, Session, SessionStats, SessionStatus,
+};
 use crate::error::{AppError, AppResult};
-use crate::ralph::RalphError;
+use crate::sanitize;
+use crate::ralph::{QueueEntry, RalphError, RunOptions, RunStart, RunTarget, WorkingDir};
+use crate::users::CurrentUser;
 
+use super::pagination::{Page, PageParams, DEFAULT_LIMIT};
 use super::AppState;
 
 /// Request body for creating a new session
@@ -30,76 +37,256 @@ pub struct SessionDetails {
     #[serde(flatten)]
     pub session: Session,
     pub messages: Vec<Message>,
+    /// Files attached to the session, handed to each run
+    pub attachments: Vec<Attachment>,
+    /// Place in the repository's run queue while queued; 1 is next
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub queue_position: Option<usize>,
 }
 
 /// Request body for running ralph on a session
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct RunSessionRequest {
     /// The prompt to send to ralph
+    #[serde(default)]
     pub prompt: String,
+    /// Run on this connected worker instead of locally
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worker_id: Option<Uuid>,
+    /// Run inside a container with the repository mounted, instead of
+    /// directly on the host
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub sandbox: bool,
+    /// Upload whose text is added to the prompt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_upload_id: Option<Uuid>,
+    /// Uploads the agent is pointed at as context files
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub context_upload_ids: Vec<Uuid>,
+    /// Environment variables to set, added to the session's and kept for
+    /// its later runs
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// Prompts to run after this one, in order; each starts only once the
+    /// one before it completed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pipeline: Vec<String>,
+    /// Where the process starts: a directory relative to the repository's
+    /// root, or the absolute path of one of its worktrees
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub working_dir: Option<String>,
+}
+
+/// Query parameters for listing sessions
+#[derive(Debug, Default, Deserialize)]
+pub struct ListSessionsParams {
+    /// Comma-separated tags; only sessions with all of them are listed
+    pub tag: Option<String>,
+    /// Maximum number of sessions to return
+    pub limit: Option<usize>,
+    /// `next_cursor` from the previous page
+    pub cursor: Option<String>,
+}
+
+/// Request body for tagging a session
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AddTagRequest {
+    pub tag: String,
+}
+
+/// A tag and how many of the listed sessions have it
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TagCount {
+    pub tag: String,
+    pub sessions: usize,
+}
+
+/// Longest tag allowed, in characters
+const MAX_TAG_CHARS: usize = 50;
+
+/// Request body for archiving or restoring a session
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ArchiveSessionRequest {
+    /// `false` restores an archived session
+    pub archived: bool,
+}
+
+/// Environment variables a session's runs are started with
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SessionEnv {
+    pub env: BTreeMap<String, String>,
 }
 
 /// Response for run session endpoint
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RunSessionResponse {
     pub session_id: Uuid,
+    /// `running`, or `queued` when the repository is busy with another run
     pub status: SessionStatus,
     pub message: String,
+    /// Place in the repository's run queue when queued; 1 is next
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub queue_position: Option<usize>,
+}
+
+/// Response for the run queue
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RunQueueResponse {
+    /// Runs going on this machine
+    pub running: usize,
+    /// Most local runs going at once, when limited
+    pub max_concurrent_runs: Option<usize>,
+    /// Runs waiting to start, in the order they were requested
+    pub queued: Vec<QueueEntry>,
 }
 
 /// Query parameters for fetching session output
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 pub struct OutputQueryParams {
     /// Filter by stream type (stdout, stderr)
     pub stream: Option<String>,
+    /// Comma-separated channels to include (assistant_text, tool_call, system, stderr)
+    pub channel: Option<String>,
     /// Maximum number of entries to return
-    pub limit: Option<i64>,
-    /// Offset for pagination
-    pub offset: Option<i64>,
+    pub limit: Option<usize>,
+    /// `next_cursor` from the previous page
+    pub cursor: Option<String>,
 }
 
-/// Response for session output
-#[derive(Debug, Serialize, Deserialize)]
-pub struct OutputResponse {
-    pub session_id: Uuid,
-    pub logs: Vec<OutputLog>,
-    pub total: usize,
+/// Trim and lowercase a tag, so "Bugfix" and "bugfix " are one label
+fn normalize_tag(tag: &str) -> AppResult<String> {
+    let tag = tag.trim().to_lowercase();
+    if tag.is_empty() {
+        return Err(AppError::BadRequest("Tag must not be empty".to_string()));
+    }
+    if tag.chars().count() > MAX_TAG_CHARS {
+        return Err(AppError::BadRequest(format!(
+            "Tag is longer than {} characters",
+            MAX_TAG_CHARS
+        )));
+    }
+    if tag.contains(',') || tag.chars().any(char::is_control) {
+        return Err(AppError::BadRequest(format!(
+            "Tags can't contain commas or control characters: {}",
+            tag
+        )));
+    }
+    Ok(tag)
 }
 
-/// List all sessions
-async fn list_sessions(State(state): State<AppState>) -> AppResult<Json<Vec<Session>>> {
-    let sessions = state
+/// Sessions visible to the current user with every tag `params` asks for,
+/// archived ones or the others
+async fn filter_sessions(
+    state: &AppState,
+    user: &CurrentUser,
+    params: &ListSessionsParams,
+    archived: bool,
+) -> AppResult<Vec<Session>> {
+    let tags = params
+        .tag
+        .as_deref()
+        .unwrap_or_default()
+        .split(',')
+        .filter(|tag| !tag.trim().is_empty())
+        .map(normalize_tag)
+        .collect::<AppResult<Vec<_>>>()?;
+
+    Ok(state
         .db
-        .list_sessions()
-        .map_err(|e| AppError::Internal(e.to_string()))?;
+        .call(|db| db.list_sessions())
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?
+        .into_iter()
+        .filter(|session| session.archived == archived && user.can_view(session.owner_id))
+        .filter(|session| tags.iter().all(|tag| session.tags.contains(tag)))
+        .collect())
+}
 
-    Ok(Json(sessions))
+/// List the sessions visible to the current user, leaving out archived ones
+pub(super) async fn list_sessions(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Query(params): Query<ListSessionsParams>,
+) -> AppResult<Json<Page<Session>>> {
+    let sessions = filter_sessions(&state, &user, &params, false).await?;
+    let page = PageParams {
+        limit: params.limit,
+        cursor: params.cursor,
+    };
+    Ok(Json(Page::from_vec(sessions, &page, DEFAULT_LIMIT)?))
+}
+
+/// List archived sessions visible to the current user
+async fn list_archived_sessions(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Query(params): Query<ListSessionsParams>,
+) -> AppResult<Json<Page<Session>>> {
+    let sessions = filter_sessions(&state, &user, &params, true).await?;
+    let page = PageParams {
+        limit: params.limit,
+        cursor: params.cursor,
+    };
+    Ok(Json(Page::from_vec(sessions, &page, DEFAULT_LIMIT)?))
+}
+
+/// GET /api/sessions/tags - Tags in use on the session list, with how many
+/// sessions have each
+async fn list_tags(
+    State(state): State<AppState>,
+    user: CurrentUser,
+) -> AppResult<Json<Vec<TagCount>>> {
+    let sessions = filter_sessions(&state, &user, &ListSessionsParams::default(), false).await?;
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for tag in sessions.into_iter().flat_map(|session| session.tags) {
+        *counts.entry(tag).or_default() += 1;
+    }
+
+    Ok(Json(
+        counts
+            .into_iter()
+            .map(|(tag, sessions)| TagCount { tag, sessions })
+            .collect(),
+    ))
 }
 
 /// Create a new session
-async fn create_session(
+pub(super) async fn create_session(
     State(state): State<AppState>,
+    user: CurrentUser,
     Json(req): Json<CreateSessionRequest>,
 ) -> AppResult<Json<Session>> {
     // Validate orchestrator is available
-    if !req.orchestrator.is_available() {
+    if !state.settings.orchestrator_available(&req.orchestrator) {
         return Err(AppError::BadRequest(format!(
             "Orchestrator '{}' is not yet available",
             req.orchestrator.as_str()
         )));
     }
 
-    // Verify repo exists
-    state.db.get_repo(req.repo_id).map_err(|e| match e {
+    // Verify repo exists and is visible to the user
+    let repo_id = req.repo_id;
+    let repo = state.db.call(move |db| db.get_repo(repo_id)).await;
+    let repo = repo.map_err(|e| match e {
         crate::db::DbError::NotFound => {
             AppError::BadRequest(format!("Repository not found: {}", req.repo_id))
         }
         _ => AppError::Internal(e.to_string()),
     })?;
+    if !user.can_view(repo.owner_id) {
+        return Err(AppError::BadRequest(format!(
+            "Repository not found: {}",
+            req.repo_id
+        )));
+    }
 
+    let owner_id = user.id;
     let session = state
         .db
-        .insert_session(req.repo_id, req.name.as_deref(), req.orchestrator)
+        .call(move |db| {
+            db.insert_session_for(repo_id, req.name.as_deref(), req.orchestrator, owner_id)
+        })
+        .await
         .map_err(|e| AppError::Internal(e.to_string()))?;
 
     Ok(Json(session))
@@ -108,102 +295,443 @@ async fn create_session(
 /// Get a session by ID with its messages
 async fn get_session(
     State(state): State<AppState>,
+    user: CurrentUser,
     AxumPath(id): AxumPath<Uuid>,
 ) -> AppResult<Json<SessionDetails>> {
-    let session = state.db.get_session(id).map_err(|e| match e {
+    let session = state.db.call(move |db| db.get_session(id)).await;
+    let session = session.map_err(|e| match e {
+        crate::db::DbError::NotFound => AppError::NotFound(format!("Session not found: {}", id)),
+        _ => AppError::Internal(e.to_string()),
+    })?;
+    user.check_view(session.owner_id, "Session")?;
+
+    let messages = state
+        .db
+        .call(move |db| db.list_messages(id))
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+
+    let attachments = state
+        .db
+        .call(move |db| db.list_attachments(id))
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+
+    let queue_position = state.ralph_manager.queue_position(id).await;
+
+    Ok(Json(SessionDetails {
+        session,
+        messages,
+        attachments,
+        queue_position,
+    }))
+}
+
+/// List a session's messages
+async fn list_messages(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+    Query(params): Query<PageParams>,
+) -> AppResult<Json<Page<Message>>> {
+    let session = state.db.call(move |db| db.get_session(id)).await;
+    let session = session.map_err(|e| match e {
         crate::db::DbError::NotFound => AppError::NotFound(format!("Session not found: {}", id)),
         _ => AppError::Internal(e.to_string()),
     })?;
+    user.check_view(session.owner_id, "Session")?;
 
     let messages = state
         .db
-        .list_messages(id)
+        .call(move |db| db.list_messages(id))
+        .await
         .map_err(|e| AppError::Internal(e.to_string()))?;
 
-    Ok(Json(SessionDetails { session, messages }))
+    Ok(Json(Page::from_vec(messages, &params, DEFAULT_LIMIT)?))
 }
 
 /// Delete a session by ID
 async fn delete_session(
     State(state): State<AppState>,
+    user: CurrentUser,
     AxumPath(id): AxumPath<Uuid>,
 ) -> AppResult<Json<()>> {
-    state.db.delete_session(id).map_err(|e| match e {
+    let session = state.db.call(move |db| db.get_session(id)).await;
+    let session = session.map_err(|e| match e {
         crate::db::DbError::NotFound => AppError::NotFound(format!("Session not found: {}", id)),
         _ => AppError::Internal(e.to_string()),
     })?;
+    user.check_modify(session.owner_id, "Session")?;
+
+    let snapshots = state
+        .db
+        .call(move |db| db.list_snapshots_by_session(id))
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+    let worktree = state.db.call(move |db| db.get_session_worktree(id)).await?;
+
+    state.db.call(move |db| db.delete_session(id)).await.map_err(|e| match e {
+        crate::db::DbError::NotFound => AppError::NotFound(format!("Session not found: {}", id)),
+        _ => AppError::Internal(e.to_string()),
+    })?;
+
+    // The records went with the session; their files are cleaned up here
+    if let Some(store) = &state.snapshots {
+        for snapshot in snapshots {
+            store.remove_files(snapshot.id);
+        }
+    }
+    if let Ok(store) = super::attachments::store(&state)
+        && let Err(e) = store.remove_session(id)
+    {
+        tracing::warn!("Failed to delete the attachments of session {}: {}", id, e);
+    }
+    // A worktree with uncommitted work is kept, no longer tied to a session
+    if let Some(worktree) = worktree
+        && let Ok(repo) = state.db.call(move |db| db.get_repo(worktree.repo_id)).await
+        && let Err(e) = super::worktrees::remove(&repo, &worktree, false, &state).await
+    {
+        tracing::warn!("Kept the worktree of deleted session {}: {}", id, e);
+    }
 
     Ok(Json(()))
 }
 
+/// Archive a session, or restore an archived one
+async fn archive_session(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+    Json(req): Json<ArchiveSessionRequest>,
+) -> AppResult<Json<Session>> {
+    let session = state.db.call(move |db| db.get_session(id)).await;
+    let session = session.map_err(|e| match e {
+        crate::db::DbError::NotFound => AppError::NotFound(format!("Session not found: {}", id)),
+        _ => AppError::Internal(e.to_string()),
+    })?;
+    user.check_modify(session.owner_id, "Session")?;
+    if req.archived && matches!(session.status, SessionStatus::Running | SessionStatus::Queued) {
+        return Err(AppError::Conflict(format!(
+            "Session {} is still running; cancel it before archiving",
+            id
+        )));
+    }
+
+    let archived = req.archived;
+    let session = state
+        .db
+        .call(move |db| {
+            db.set_session_archived(id, archived)?;
+            db.get_session(id)
+        })
+        .await?;
+    Ok(Json(session))
+}
+
+/// PATCH /api/sessions/{id}/metadata - Change the session's metadata with a
+/// JSON merge patch: keys set to null are removed, and objects are merged
+async fn patch_session_metadata(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+    Json(patch): Json<serde_json::Map<String, serde_json::Value>>,
+) -> AppResult<Json<Session>> {
+    let session = state.db.call(move |db| db.get_session(id)).await;
+    let session = session.map_err(|e| match e {
+        crate::db::DbError::NotFound => AppError::NotFound(format!("Session not found: {}", id)),
+        _ => AppError::Internal(e.to_string()),
+    })?;
+    user.check_modify(session.owner_id, "Session")?;
+
+    let session = state
+        .db
+        .call(move |db| {
+            db.patch_session_metadata(id, &patch)?;
+            db.get_session(id)
+        })
+        .await;
+    let session = session.map_err(|e| match e {
+        crate::db::DbError::NotFound => AppError::NotFound(format!("Session not found: {}", id)),
+        crate::db::DbError::InvalidData(msg) => AppError::BadRequest(msg),
+        _ => AppError::Internal(e.to_string()),
+    })?;
+    Ok(Json(session))
+}
+
 /// Run ralph on a session
-async fn run_session(
+pub(super) async fn run_session(
     State(state): State<AppState>,
+    user: CurrentUser,
     AxumPath(id): AxumPath<Uuid>,
     Json(req): Json<RunSessionRequest>,
 ) -> AppResult<Json<RunSessionResponse>> {
     // Get the session
-    let session = state.db.get_session(id).map_err(|e| match e {
+    let session = state.db.call(move |db| db.get_session(id)).await;
+    let session = session.map_err(|e| match e {
         crate::db::DbError::NotFound => AppError::NotFound(format!("Session not found: {}", id)),
         _ => AppError::Internal(e.to_string()),
     })?;
+    user.check_modify(session.owner_id, "Session")?;
 
     // Get the repo path
-    let repo = state.db.get_repo(session.repo_id).map_err(|e| match e {
+    let repo = state.db.call(move |db| db.get_repo(session.repo_id)).await;
+    let repo = repo.map_err(|e| match e {
         crate::db::DbError::NotFound => {
             AppError::Internal(format!("Repository not found for session: {}", id))
         }
         _ => AppError::Internal(e.to_string()),
     })?;
 
-    // Start ralph
-    state
-        .ralph_manager
-        .run(
-            id,
-            session.repo_id,
-            &repo.path,
-            &req.prompt,
-            state.db.clone(),
-            state.connections.clone(),
-        )
+    let target = match (req.worker_id, req.sandbox) {
+        (Some(_), true) => {
+            return Err(AppError::BadRequest(
+                "A run on a worker can't also be sandboxed".to_string(),
+            ));
+        }
+        (Some(worker_id), false) => RunTarget::Worker(worker_id),
+        (None, true) => RunTarget::Sandbox,
+        (None, false) => RunTarget::Local,
+    };
+    // Runs of a session with a worktree start in it unless told otherwise
+    let worktree = state.db.call(move |db| db.get_session_worktree(id)).await?;
+    let working_dir = match req.working_dir.as_deref().or(worktree.as_ref().map(|w| w.path.as_str())) {
+        Some(_) if req.worker_id.is_some() => {
+            return Err(AppError::BadRequest(
+                "A run on a worker starts at the repository's root".to_string(),
+            ));
+        }
+        Some(dir) => Some(WorkingDir::resolve(Path::new(&repo.path), dir)?),
+        None => None,
+    };
+    let options = RunOptions { target, working_dir };
+    let prompt = super::uploads::build_prompt(&state, &user, &req).await?;
+
+    if !req.env.is_empty() {
+        validate_env(&req.env)?;
+        let added = req.env.clone();
+        state
+            .db
+            .call(move |db| {
+                let mut env = db.get_session_env(id)?;
+                env.extend(added);
+                db.set_session_env(id, &env)
+            })
+            .await?;
+    }
+
+    // Start ralph, here, in a container or on the chosen worker, or queue it
+    // behind the repository's current run
+    let started = if req.pipeline.is_empty() {
+        state
+            .ralph_manager
+            .run_or_queue(
+                id,
+                &repo,
+                options,
+                &prompt,
+                state.db.clone(),
+                state.connections.clone(),
+            )
+            .await
+    } else {
+        let prompts = std::iter::once(prompt).chain(req.pipeline.iter().cloned()).collect();
+        state
+            .ralph_manager
+            .run_pipeline(
+                id,
+                &repo,
+                options,
+                prompts,
+                state.db.clone(),
+                state.connections.clone(),
+            )
+            .await
+    };
+    let started = started.map_err(|e| match e {
+        RalphError::RepoBusy(repo_id) => AppError::BadRequest(format!(
+            "Repository {} already has a running ralph process",
+            repo_id
+        )),
+        RalphError::SessionAlreadyRunning(session_id) => AppError::BadRequest(format!(
+            "Session {} already has a running process",
+            session_id
+        )),
+        RalphError::SpawnFailed(msg) => AppError::Internal(format!("Failed to start ralph: {}", msg)),
+        RalphError::NotFound { message, help_steps } => AppError::UserActionRequired {
+            code: "RALPH_NOT_FOUND".to_string(),
+            message,
+            details: None,
+            help_steps,
+        },
+        RalphError::InvalidArgument(msg) => AppError::BadRequest(msg),
+        RalphError::WorkerUnavailable(worker_id) => {
+            AppError::BadRequest(format!("Worker {} is not connected", worker_id))
+        }
+        RalphError::ShuttingDown => AppError::Conflict("The server is shutting down".to_string()),
+        error @ RalphError::AtCapacity(_) => AppError::Conflict(error.to_string()),
+        error @ (RalphError::NotRunning(_) | RalphError::InputFailed(_)) => {
+            AppError::Internal(error.to_string())
+        }
+    })?;
+
+    // Only what was typed is remembered; uploads would swamp the history
+    let typed = req.prompt.trim().to_string();
+    if !typed.is_empty()
+        && let Err(e) = state.db.call(move |db| db.record_prompt(session.repo_id, &typed)).await
+    {
+        tracing::warn!("Failed to record prompt history: {}", e);
+    }
+
+    Ok(Json(match started {
+        RunStart::Started => RunSessionResponse {
+            session_id: id,
+            status: SessionStatus::Running,
+            message: "Ralph process started".to_string(),
+            queue_position: None,
+        },
+        RunStart::Queued { position } => RunSessionResponse {
+            session_id: id,
+            status: SessionStatus::Queued,
+            message: format!("Queued at position {}", position),
+            queue_position: Some(position),
+        },
+    }))
+}
+
+fn validate_env(env: &BTreeMap<String, String>) -> AppResult<()> {
+    for (name, value) in env {
+        sanitize::env_var(name, value).map_err(|e| AppError::BadRequest(e.to_string()))?;
+    }
+    Ok(())
+}
+
+/// Get the environment variables a session's runs are started with
+///
+/// Values are often API keys, so only those who may run the session see them.
+async fn get_session_env(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+) -> AppResult<Json<SessionEnv>> {
+    let session = state.db.call(move |db| db.get_session(id)).await;
+    let session = session.map_err(|e| match e {
+        crate::db::DbError::NotFound => AppError::NotFound(format!("Session not found: {}", id)),
+        _ => AppError::Internal(e.to_string()),
+    })?;
+    user.check_modify(session.owner_id, "Session")?;
+
+    Ok(Json(SessionEnv {
+        env: state.db.call(move |db| db.get_session_env(id)).await?,
+    }))
+}
+
+/// Replace the environment variables a session's runs are started with
+///
+/// A run already going keeps the environment it started with.
+async fn set_session_env(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+    Json(req): Json<SessionEnv>,
+) -> AppResult<Json<SessionEnv>> {
+    let session = state.db.call(move |db| db.get_session(id)).await;
+    let session = session.map_err(|e| match e {
+        crate::db::DbError::NotFound => AppError::NotFound(format!("Session not found: {}", id)),
+        _ => AppError::Internal(e.to_string()),
+    })?;
+    user.check_modify(session.owner_id, "Session")?;
+    validate_env(&req.env)?;
+
+    let env = req.env.clone();
+    state.db.call(move |db| db.set_session_env(id, &env)).await?;
+    Ok(Json(req))
+}
+
+/// GET /api/sessions/{id}/tags - The session's tags, in alphabetical order
+async fn list_session_tags(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+) -> AppResult<Json<Vec<String>>> {
+    let session = state.db.call(move |db| db.get_session(id)).await;
+    let session = session.map_err(|e| match e {
+        crate::db::DbError::NotFound => AppError::NotFound(format!("Session not found: {}", id)),
+        _ => AppError::Internal(e.to_string()),
+    })?;
+    user.check_view(session.owner_id, "Session")?;
+
+    Ok(Json(session.tags))
+}
+
+/// POST /api/sessions/{id}/tags - Attach a tag to the session
+async fn add_session_tag(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+    Json(req): Json<AddTagRequest>,
+) -> AppResult<Json<Vec<String>>> {
+    let session = state.db.call(move |db| db.get_session(id)).await;
+    let session = session.map_err(|e| match e {
+        crate::db::DbError::NotFound => AppError::NotFound(format!("Session not found: {}", id)),
+        _ => AppError::Internal(e.to_string()),
+    })?;
+    user.check_modify(session.owner_id, "Session")?;
+    let tag = normalize_tag(&req.tag)?;
+
+    let tags = state
+        .db
+        .call(move |db| {
+            db.add_session_tag(id, &tag)?;
+            db.list_session_tags(id)
+        })
+        .await?;
+    Ok(Json(tags))
+}
+
+/// DELETE /api/sessions/{id}/tags/{tag} - Remove a tag from the session
+async fn remove_session_tag(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath((id, tag)): AxumPath<(Uuid, String)>,
+) -> AppResult<Json<Vec<String>>> {
+    let session = state.db.call(move |db| db.get_session(id)).await;
+    let session = session.map_err(|e| match e {
+        crate::db::DbError::NotFound => AppError::NotFound(format!("Session not found: {}", id)),
+        _ => AppError::Internal(e.to_string()),
+    })?;
+    user.check_modify(session.owner_id, "Session")?;
+    let tag = normalize_tag(&tag)?;
+
+    let removed = tag.clone();
+    let tags = state
+        .db
+        .call(move |db| {
+            db.remove_session_tag(id, &removed)?;
+            db.list_session_tags(id)
+        })
         .await
         .map_err(|e| match e {
-            RalphError::RepoBusy(repo_id) => AppError::BadRequest(format!(
-                "Repository {} already has a running ralph process",
-                repo_id
-            )),
-            RalphError::SessionAlreadyRunning(session_id) => AppError::BadRequest(format!(
-                "Session {} already has a running process",
-                session_id
-            )),
-            RalphError::SpawnFailed(msg) => AppError::Internal(format!("Failed to start ralph: {}", msg)),
-            RalphError::NotFound { message, help_steps } => AppError::UserActionRequired {
-                code: "RALPH_NOT_FOUND".to_string(),
-                message,
-                details: None,
-                help_steps,
-            },
-            RalphError::NotRunning(_) => unreachable!(),
+            crate::db::DbError::NotFound => {
+                AppError::NotFound(format!("Session {} has no tag {}", id, tag))
+            }
+            _ => AppError::Internal(e.to_string()),
         })?;
-
-    Ok(Json(RunSessionResponse {
-        session_id: id,
-        status: SessionStatus::Running,
-        message: "Ralph process started".to_string(),
-    }))
+    Ok(Json(tags))
 }
 
 /// Cancel a running ralph session
-async fn cancel_session(
+pub(super) async fn cancel_session(
     State(state): State<AppState>,
+    user: CurrentUser,
     AxumPath(id): AxumPath<Uuid>,
 ) -> AppResult<Json<CancelSessionResponse>> {
-    // Verify session exists
-    state.db.get_session(id).map_err(|e| match e {
+    // Verify session exists and belongs to the user
+    let session = state.db.call(move |db| db.get_session(id)).await;
+    let session = session.map_err(|e| match e {
         crate::db::DbError::NotFound => AppError::NotFound(format!("Session not found: {}", id)),
         _ => AppError::Internal(e.to_string()),
     })?;
+    user.check_modify(session.owner_id, "Session")?;
 
     // Cancel the ralph process
     state
@@ -224,6 +752,100 @@ async fn cancel_session(
     }))
 }
 
+/// How stopping one session's run went
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CancelResult {
+    pub session_id: Uuid,
+    pub cancelled: bool,
+    /// Why the run couldn't be stopped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Who owns each session, to check access to many at once
+async fn session_owners(state: &AppState) -> AppResult<HashMap<Uuid, Option<Uuid>>> {
+    let sessions = state.db.call(|db| db.list_sessions()).await?;
+    Ok(sessions.into_iter().map(|session| (session.id, session.owner_id)).collect())
+}
+
+/// Stop every running session the current user may modify, in every
+/// repository
+///
+/// Their queued runs are dropped first, so none starts in a freed slot.
+async fn cancel_all_sessions(
+    State(state): State<AppState>,
+    user: CurrentUser,
+) -> AppResult<Json<Vec<CancelResult>>> {
+    let owners = session_owners(&state).await?;
+    let may_modify = |id: &Uuid| owners.get(id).is_some_and(|owner| user.can_modify(*owner));
+    let mut results = Vec::new();
+    for run in state.ralph_manager.queued_runs().await {
+        if !may_modify(&run.session_id) {
+            continue;
+        }
+        let cancelled = state
+            .ralph_manager
+            .cancel(run.session_id, state.db.clone(), state.connections.clone())
+            .await;
+        results.push(CancelResult {
+            session_id: run.session_id,
+            cancelled: cancelled.is_ok(),
+            error: cancelled.err().map(|e| e.to_string()),
+        });
+    }
+
+    // Each process gets a few seconds to exit; stop them all at once
+    let session_ids = state.ralph_manager.active_sessions().await.into_iter().filter(may_modify);
+    let cancels = session_ids.map(|session_id| {
+        let state = state.clone();
+        async move {
+            let cancelled = state
+                .ralph_manager
+                .cancel(session_id, state.db.clone(), state.connections.clone())
+                .await;
+            CancelResult {
+                session_id,
+                cancelled: cancelled.is_ok(),
+                error: cancelled.err().map(|e| e.to_string()),
+            }
+        }
+    });
+
+    results.extend(futures::future::join_all(cancels).await);
+
+    Ok(Json(results))
+}
+
+/// Request body for sending input to a running session
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SessionInputRequest {
+    pub text: String,
+}
+
+/// Send follow-up input to a running session's process
+///
+/// The text is written to the process's stdin and kept as a user message.
+async fn send_input(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+    Json(req): Json<SessionInputRequest>,
+) -> AppResult<Json<Message>> {
+    let session = state.db.call(move |db| db.get_session(id)).await;
+    let session = session.map_err(|e| match e {
+        crate::db::DbError::NotFound => AppError::NotFound(format!("Session not found: {}", id)),
+        _ => AppError::Internal(e.to_string()),
+    })?;
+    user.check_modify(session.owner_id, "Session")?;
+
+    state.ralph_manager.send_input(id, &req.text).await?;
+    let message = state
+        .db
+        .call(move |db| db.insert_message(id, MessageRole::User, &req.text))
+        .await?;
+    Ok(Json(message))
+}
+
 /// Response for cancel session endpoint
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CancelSessionResponse {
@@ -233,16 +855,19 @@ pub struct CancelSessionResponse {
 }
 
 /// Get session output logs (historical)
-async fn get_session_output(
+pub(super) async fn get_session_output(
     State(state): State<AppState>,
+    user: CurrentUser,
     AxumPath(id): AxumPath<Uuid>,
     Query(params): Query<OutputQueryParams>,
-) -> AppResult<Json<OutputResponse>> {
-    // Verify session exists
-    state.db.get_session(id).map_err(|e| match e {
+) -> AppResult<Json<Page<OutputLog>>> {
+    // Verify session exists and is visible to the user
+    let session = state.db.call(move |db| db.get_session(id)).await;
+    let session = session.map_err(|e| match e {
         crate::db::DbError::NotFound => AppError::NotFound(format!("Session not found: {}", id)),
         _ => AppError::Internal(e.to_string()),
     })?;
+    user.check_view(session.owner_id, "Session")?;
 
     // Parse stream filter
     let stream_filter = params.stream.and_then(|s| match s.to_lowercase().as_str() {
@@ -250,18 +875,128 @@ async fn get_session_output(
         "stderr" => Some(OutputStream::Stderr),
         _ => None,
     });
-
-    let logs = state
+    let channels = params
+        .channel
+        .as_deref()
+        .map(|value| {
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(|name| OutputChannel::from_str(&name.to_lowercase()))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(AppError::BadRequest)
+        })
+        .transpose()?;
+
+    let page = PageParams {
+        limit: params.limit,
+        cursor: params.cursor,
+    };
+    let offset = page.offset()?;
+    let limit = page.limit_or(DEFAULT_LIMIT) as i64;
+    let (logs, total) = state
         .db
-        .list_output_logs(id, stream_filter, params.limit, params.offset)
+        .call(move |db| {
+            let channels = channels.as_deref();
+            let logs = db.list_channel_output_logs(
+                id,
+                stream_filter,
+                channels,
+                Some(limit),
+                Some(offset as i64),
+            )?;
+            let total = db.count_channel_output_logs(id, stream_filter, channels)?;
+            Ok((logs, total))
+        })
+        .await
         .map_err(|e| AppError::Internal(e.to_string()))?;
 
-    let total = logs.len();
+    Ok(Json(Page::new(logs, total, offset)))
+}
 
-    Ok(Json(OutputResponse {
-        session_id: id,
-        logs,
-        total,
+/// GET /api/sessions/{id}/iterations - Iterations of the session's runs
+pub(super) async fn list_iterations(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+) -> AppResult<Json<Vec<Iteration>>> {
+    let session = state.db.call(move |db| db.get_session(id)).await;
+    let session = session.map_err(|e| match e {
+        crate::db::DbError::NotFound => AppError::NotFound(format!("Session not found: {}", id)),
+        _ => AppError::Internal(e.to_string()),
+    })?;
+    user.check_view(session.owner_id, "Session")?;
+
+    Ok(Json(state.db.call(move |db| db.list_iterations(id)).await?))
+}
+
+/// GET /api/sessions/{id}/runs - The session's runs, newest first
+pub(super) async fn list_runs(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+) -> AppResult<Json<Vec<Run>>> {
+    let session = state.db.call(move |db| db.get_session(id)).await;
+    let session = session.map_err(|e| match e {
+        crate::db::DbError::NotFound => AppError::NotFound(format!("Session not found: {}", id)),
+        _ => AppError::Internal(e.to_string()),
+    })?;
+    user.check_view(session.owner_id, "Session")?;
+
+    Ok(Json(state.db.call(move |db| db.list_runs(id)).await?))
+}
+
+/// GET /api/sessions/{id}/stats - Tokens and cost over all of the session's runs
+pub(super) async fn get_session_stats(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+) -> AppResult<Json<SessionStats>> {
+    let session = state.db.call(move |db| db.get_session(id)).await;
+    let session = session.map_err(|e| match e {
+        crate::db::DbError::NotFound => AppError::NotFound(format!("Session not found: {}", id)),
+        _ => AppError::Internal(e.to_string()),
+    })?;
+    user.check_view(session.owner_id, "Session")?;
+
+    Ok(Json(state.db.call(move |db| db.session_stats(id)).await?))
+}
+
+/// GET /api/sessions/{id}/pipeline - Steps of the session's last pipeline
+pub(super) async fn list_pipeline_steps(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+) -> AppResult<Json<Vec<PipelineStep>>> {
+    let session = state.db.call(move |db| db.get_session(id)).await;
+    let session = session.map_err(|e| match e {
+        crate::db::DbError::NotFound => AppError::NotFound(format!("Session not found: {}", id)),
+        _ => AppError::Internal(e.to_string()),
+    })?;
+    user.check_view(session.owner_id, "Session")?;
+
+    Ok(Json(state.db.call(move |db| db.list_pipeline_steps(id)).await?))
+}
+
+/// Runs waiting to start, for sessions visible to the current user
+async fn get_run_queue(
+    State(state): State<AppState>,
+    user: CurrentUser,
+) -> AppResult<Json<RunQueueResponse>> {
+    let owners = session_owners(&state).await?;
+    let queued = state
+        .ralph_manager
+        .queued_runs()
+        .await
+        .into_iter()
+        .filter(|run| owners.get(&run.session_id).is_some_and(|owner| user.can_view(*owner)))
+        .collect();
+
+    Ok(Json(RunQueueResponse {
+        running: state.ralph_manager.local_run_count().await,
+        max_concurrent_runs: state.ralph_manager.max_concurrent_runs(),
+        queued,
     }))
 }
 
@@ -269,17 +1004,32 @@ async fn get_session_output(
 pub fn router() -> Router<AppState> {
     Router::new()
         .route("/sessions", get(list_sessions).post(create_session))
+        .route("/sessions/queue", get(get_run_queue))
+        .route("/sessions/archived", get(list_archived_sessions))
+        .route("/sessions/tags", get(list_tags))
+        .route("/sessions/cancel-all", post(cancel_all_sessions))
         .route("/sessions/{id}", get(get_session).delete(delete_session))
+        .route("/sessions/{id}/messages", get(list_messages))
+        .route("/sessions/{id}/archive", patch(archive_session))
+        .route("/sessions/{id}/metadata", patch(patch_session_metadata))
         .route("/sessions/{id}/run", post(run_session))
         .route("/sessions/{id}/cancel", post(cancel_session))
+        .route("/sessions/{id}/input", post(send_input))
+        .route("/sessions/{id}/env", get(get_session_env).put(set_session_env))
+        .route("/sessions/{id}/tags", get(list_session_tags).post(add_session_tag))
+        .route("/sessions/{id}/tags/{tag}", delete(remove_session_tag))
         .route("/sessions/{id}/output", get(get_session_output))
+        .route("/sessions/{id}/iterations", get(list_iterations))
+        .route("/sessions/{id}/runs", get(list_runs))
+        .route("/sessions/{id}/stats", get(get_session_stats))
+        .route("/sessions/{id}/pipeline", get(list_pipeline_steps))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::api::repos::{router as repos_router, AddRepoRequest};
-    use crate::db::models::Repo;
+    use crate::db::models::{Repo, StepStatus};
     use crate::db::Database;
     use axum_test::TestServer;
     use tempfile::TempDir;
@@ -325,8 +1075,9 @@ mod tests {
         let response = server.get("/sessions").await;
         response.assert_status_ok();
 
-        let sessions: Vec<Session> = response.json();
-        assert!(sessions.is_empty());
+        let sessions: Page<Session> = response.json();
+        assert!(sessions.items.is_empty());
+        assert_eq!(sessions.total, 0);
     }
 
     #[tokio::test]
@@ -375,10 +1126,10 @@ mod tests {
         // List sessions
         let response = server.get("/sessions").await;
         response.assert_status_ok();
-        let sessions: Vec<Session> = response.json();
-        assert_eq!(sessions.len(), 1);
-        assert_eq!(sessions[0].name, Some("Test Session".to_string()));
-        assert_eq!(sessions[0].orchestrator, Orchestrator::Ralph);
+        let sessions: Page<Session> = response.json();
+        assert_eq!(sessions.total, 1);
+        assert_eq!(sessions.items[0].name, Some("Test Session".to_string()));
+        assert_eq!(sessions.items[0].orchestrator, Orchestrator::Ralph);
     }
 
     #[tokio::test]
@@ -425,6 +1176,25 @@ mod tests {
         assert_eq!(details.messages.len(), 2);
         assert_eq!(details.messages[0].content, "Hello!");
         assert_eq!(details.messages[1].content, "Hi there!");
+
+        // Page through messages
+        let response = server
+            .get(&format!("/sessions/{}/messages?limit=1", session.id))
+            .await;
+        response.assert_status_ok();
+        let messages: Page<Message> = response.json();
+        assert_eq!(messages.items[0].content, "Hello!");
+        assert_eq!(messages.total, 2);
+        let response = server
+            .get(&format!(
+                "/sessions/{}/messages?limit=1&cursor={}",
+                session.id,
+                messages.next_cursor.unwrap()
+            ))
+            .await;
+        let messages: Page<Message> = response.json();
+        assert_eq!(messages.items[0].content, "Hi there!");
+        assert_eq!(messages.next_cursor, None);
     }
 
     #[tokio::test]
@@ -464,6 +1234,160 @@ mod tests {
         response.assert_status_not_found();
     }
 
+    #[tokio::test]
+    async fn test_archive_session() {
+        let state = create_test_state();
+        let server = create_test_server(state.clone());
+
+        let repo = create_test_repo(&server).await;
+        let session = state.db.insert_session(repo.id, Some("Old"), Orchestrator::Ralph).unwrap();
+        let url = format!("/sessions/{}/archive", session.id);
+
+        let archived: Session = server
+            .patch(&url)
+            .json(&ArchiveSessionRequest { archived: true })
+            .await
+            .json();
+        assert!(archived.archived && archived.archived_at.is_some());
+        assert_eq!(server.get("/sessions").await.json::<Page<Session>>().total, 0);
+        let listed: Page<Session> = server.get("/sessions/archived").await.json();
+        assert_eq!(listed.items[0].id, session.id);
+        // Archived sessions can still be opened
+        server.get(&format!("/sessions/{}", session.id)).await.assert_status_ok();
+
+        server
+            .patch(&url)
+            .json(&ArchiveSessionRequest { archived: false })
+            .await
+            .assert_status_ok();
+        assert_eq!(server.get("/sessions").await.json::<Page<Session>>().total, 1);
+        assert_eq!(server.get("/sessions/archived").await.json::<Page<Session>>().total, 0);
+
+        // Running sessions have to be cancelled before they're archived
+        state.db.update_session_status(session.id, SessionStatus::Running).unwrap();
+        server
+            .patch(&url)
+            .json(&ArchiveSessionRequest { archived: true })
+            .await
+            .assert_status(axum::http::StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn test_session_tags() {
+        let state = create_test_state();
+        let server = create_test_server(state.clone());
+
+        let repo = create_test_repo(&server).await;
+        let fix = state.db.insert_session(repo.id, Some("Fix"), Orchestrator::Ralph).unwrap();
+        let spike = state.db.insert_session(repo.id, Some("Spike"), Orchestrator::Ralph).unwrap();
+        let tag = |id: Uuid, tag: &str| {
+            server
+                .post(&format!("/sessions/{}/tags", id))
+                .json(&AddTagRequest { tag: tag.to_string() })
+        };
+
+        let tags: Vec<String> = tag(fix.id, " Bugfix ").await.json();
+        assert_eq!(tags, ["bugfix"]);
+        tag(fix.id, "urgent").await.assert_status_ok();
+        tag(spike.id, "experiment").await.assert_status_ok();
+        tag(spike.id, "a,b").await.assert_status_bad_request();
+
+        let listed: Page<Session> = server.get("/sessions?tag=bugfix").await.json();
+        assert_eq!(listed.items.len(), 1);
+        assert_eq!(listed.items[0].tags, ["bugfix", "urgent"]);
+        let listed: Page<Session> = server.get("/sessions?tag=bugfix,experiment").await.json();
+        assert_eq!(listed.total, 0);
+        assert_eq!(server.get("/sessions").await.json::<Page<Session>>().total, 2);
+
+        let counts: Vec<TagCount> = server.get("/sessions/tags").await.json();
+        let counts: Vec<_> =
+            counts.iter().map(|count| (count.tag.as_str(), count.sessions)).collect();
+        assert_eq!(counts, [("bugfix", 1), ("experiment", 1), ("urgent", 1)]);
+
+        let tags: Vec<String> = server
+            .delete(&format!("/sessions/{}/tags/urgent", fix.id))
+            .await
+            .json();
+        assert_eq!(tags, ["bugfix"]);
+        server
+            .delete(&format!("/sessions/{}/tags/urgent", fix.id))
+            .await
+            .assert_status_not_found();
+        let tags: Vec<String> = server.get(&format!("/sessions/{}/tags", fix.id)).await.json();
+        assert_eq!(tags, ["bugfix"]);
+    }
+
+    #[tokio::test]
+    async fn test_session_metadata() {
+        let state = create_test_state();
+        let server = create_test_server(state.clone());
+
+        let repo = create_test_repo(&server).await;
+        let session = state.db.insert_session(repo.id, None, Orchestrator::Ralph).unwrap();
+        let url = format!("/sessions/{}/metadata", session.id);
+
+        server
+            .patch(&url)
+            .json(&serde_json::json!({ "ticket": "ENG-42", "reviewers": ["ana"] }))
+            .await
+            .assert_status_ok();
+        let updated: Session = server
+            .patch(&url)
+            .json(&serde_json::json!({ "ticket": null, "pr": 7 }))
+            .await
+            .json();
+        let expected = serde_json::json!({ "reviewers": ["ana"], "pr": 7 });
+        assert_eq!(serde_json::Value::Object(updated.metadata), expected);
+        let fetched: SessionDetails = server.get(&format!("/sessions/{}", session.id)).await.json();
+        assert_eq!(serde_json::Value::Object(fetched.session.metadata), expected);
+
+        // Only objects can be merged in
+        server
+            .patch(&url)
+            .json(&serde_json::json!(["ENG-42"]))
+            .await
+            .assert_status(axum::http::StatusCode::UNPROCESSABLE_ENTITY);
+        let huge = "x".repeat(crate::db::MAX_SESSION_METADATA_BYTES);
+        server
+            .patch(&url)
+            .json(&serde_json::json!({ "log": huge }))
+            .await
+            .assert_status_bad_request();
+    }
+
+    #[tokio::test]
+    async fn test_session_env() {
+        let state = create_test_state();
+        let server = create_test_server(state);
+
+        let repo = create_test_repo(&server).await;
+        let session: Session = server
+            .post("/sessions")
+            .json(&CreateSessionRequest {
+                repo_id: repo.id,
+                name: None,
+                orchestrator: Orchestrator::Ralph,
+            })
+            .await
+            .json();
+        let path = format!("/sessions/{}/env", session.id);
+
+        let env: SessionEnv = server.get(&path).await.json();
+        assert!(env.env.is_empty());
+
+        let set = SessionEnv {
+            env: BTreeMap::from([("HTTPS_PROXY".to_string(), "http://proxy:8080".to_string())]),
+        };
+        server.put(&path).json(&set).await.assert_status_ok();
+        let env: SessionEnv = server.get(&path).await.json();
+        assert_eq!(env.env, set.env);
+
+        let invalid = SessionEnv {
+            env: BTreeMap::from([("NOT-VALID".to_string(), "x".to_string())]),
+        };
+        server.put(&path).json(&invalid).await.assert_status_bad_request();
+    }
+
     #[tokio::test]
     async fn test_delete_nonexistent_session() {
         let state = create_test_state();
@@ -497,10 +1421,10 @@ mod tests {
             .get(&format!("/sessions/{}/output", session.id))
             .await;
         response.assert_status_ok();
-        let output: OutputResponse = response.json();
-        assert_eq!(output.session_id, session.id);
-        assert!(output.logs.is_empty());
+        let output: Page<OutputLog> = response.json();
+        assert!(output.items.is_empty());
         assert_eq!(output.total, 0);
+        assert_eq!(output.next_cursor, None);
     }
 
     #[tokio::test]
@@ -540,43 +1464,66 @@ mod tests {
             .get(&format!("/sessions/{}/output", session.id))
             .await;
         response.assert_status_ok();
-        let output: OutputResponse = response.json();
-        assert_eq!(output.logs.len(), 3);
+        let output: Page<OutputLog> = response.json();
+        assert_eq!(output.items.len(), 3);
 
         // Get stdout only
         let response = server
             .get(&format!("/sessions/{}/output?stream=stdout", session.id))
             .await;
         response.assert_status_ok();
-        let output: OutputResponse = response.json();
-        assert_eq!(output.logs.len(), 2);
-        assert!(output.logs.iter().all(|l| l.stream == OutputStream::Stdout));
+        let output: Page<OutputLog> = response.json();
+        assert_eq!(output.items.len(), 2);
+        assert!(output.items.iter().all(|l| l.stream == OutputStream::Stdout));
 
         // Get stderr only
         let response = server
             .get(&format!("/sessions/{}/output?stream=stderr", session.id))
             .await;
         response.assert_status_ok();
-        let output: OutputResponse = response.json();
-        assert_eq!(output.logs.len(), 1);
-        assert_eq!(output.logs[0].content, "Hello stderr!");
+        let output: Page<OutputLog> = response.json();
+        assert_eq!(output.items.len(), 1);
+        assert_eq!(output.items[0].content, "Hello stderr!");
+
+        // Filter by channel
+        let response = server
+            .get(&format!("/sessions/{}/output?channel=stderr,system", session.id))
+            .await;
+        response.assert_status_ok();
+        let output: Page<OutputLog> = response.json();
+        assert_eq!(output.items.len(), 1);
+        assert_eq!(output.items[0].channel, OutputChannel::Stderr);
+        server
+            .get(&format!("/sessions/{}/output?channel=prose", session.id))
+            .await
+            .assert_status_bad_request();
 
         // Test limit
         let response = server
             .get(&format!("/sessions/{}/output?limit=2", session.id))
             .await;
         response.assert_status_ok();
-        let output: OutputResponse = response.json();
-        assert_eq!(output.logs.len(), 2);
+        let output: Page<OutputLog> = response.json();
+        assert_eq!(output.items.len(), 2);
+
+        assert_eq!(output.total, 3);
+        assert_eq!(output.next_cursor.as_deref(), Some("2"));
 
-        // Test offset
+        // Follow the cursor
         let response = server
-            .get(&format!("/sessions/{}/output?offset=1", session.id))
+            .get(&format!("/sessions/{}/output?limit=2&cursor=2", session.id))
             .await;
         response.assert_status_ok();
-        let output: OutputResponse = response.json();
-        assert_eq!(output.logs.len(), 2);
-        assert_eq!(output.logs[0].content, "Hello stderr!");
+        let output: Page<OutputLog> = response.json();
+        assert_eq!(output.items.len(), 1);
+        assert_eq!(output.items[0].content, "More stdout!");
+        assert_eq!(output.next_cursor, None);
+
+        // Malformed cursors are rejected
+        server
+            .get(&format!("/sessions/{}/output?cursor=abc", session.id))
+            .await
+            .assert_status_bad_request();
     }
 
     #[tokio::test]
@@ -646,4 +1593,156 @@ mod tests {
 
         response.assert_status_bad_request();
     }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_session_on_configured_orchestrator() {
+        let db = Database::in_memory().expect("Failed to create test database");
+        let settings = crate::settings::Settings {
+            orchestrators: crate::orchestrator::parse_plugins(
+                r#"[{
+                    "id": "echo-agent",
+                    "command": ["sh", "-c", "echo '{\"text\": \"{prompt}\"}'; echo FAILED"],
+                    "parser": "json",
+                    "error_pattern": "^FAILED"
+                }]"#,
+            )
+            .unwrap(),
+            ..Default::default()
+        };
+        let state = AppState::with_settings(db, settings);
+        let server = create_test_server(state.clone());
+        let repo = create_test_repo(&server).await;
+
+        // Unknown orchestrators are rejected
+        server
+            .post("/sessions")
+            .json(&serde_json::json!({ "repo_id": repo.id, "orchestrator": "other-agent" }))
+            .await
+            .assert_status_bad_request();
+
+        let response = server
+            .post("/sessions")
+            .json(&serde_json::json!({ "repo_id": repo.id, "orchestrator": "echo-agent" }))
+            .await;
+        response.assert_status_ok();
+        let session: Session = response.json();
+        assert_eq!(session.orchestrator, Orchestrator::Plugin("echo-agent".to_string()));
+
+        server
+            .post(&format!("/sessions/{}/run", session.id))
+            .json(&RunSessionRequest {
+                prompt: "hello".to_string(),
+                ..Default::default()
+            })
+            .await
+            .assert_status_ok();
+
+        // The process exits successfully, but its output marks the run as failed
+        let mut status = SessionStatus::Running;
+        for _ in 0..100 {
+            status = state.db.get_session(session.id).unwrap().status;
+            if status != SessionStatus::Running {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+        assert_eq!(status, SessionStatus::Error);
+
+        let logs = state.db.list_output_logs(session.id, None, None, None).unwrap();
+        assert_eq!(logs[0].content, "hello");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_pipeline_stops_at_failed_step() {
+        let db = Database::in_memory().expect("Failed to create test database");
+        let settings = crate::settings::Settings {
+            orchestrators: crate::orchestrator::parse_plugins(
+                r#"[{
+                    "id": "step-agent",
+                    "command": ["sh", "-c", "echo '{\"text\": \"{prompt}\"}'; test '{prompt}' != fail"],
+                    "parser": "json"
+                }]"#,
+            )
+            .unwrap(),
+            ..Default::default()
+        };
+        let state = AppState::with_settings(db, settings);
+        let server = create_test_server(state.clone());
+        let repo = create_test_repo(&server).await;
+        let session: Session = server
+            .post("/sessions")
+            .json(&serde_json::json!({ "repo_id": repo.id, "orchestrator": "step-agent" }))
+            .await
+            .json();
+
+        server
+            .post(&format!("/sessions/{}/run", session.id))
+            .json(&RunSessionRequest {
+                prompt: "one".to_string(),
+                pipeline: vec!["fail".to_string(), "three".to_string()],
+                ..Default::default()
+            })
+            .await
+            .assert_status_ok();
+
+        let path = format!("/sessions/{}/pipeline", session.id);
+        let mut steps: Vec<PipelineStep> = Vec::new();
+        for _ in 0..100 {
+            steps = server.get(&path).await.json();
+            if steps.iter().all(|step| step.status != StepStatus::Pending && step.status != StepStatus::Running) {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+        assert_eq!(
+            steps.iter().map(|step| step.status).collect::<Vec<_>>(),
+            [StepStatus::Completed, StepStatus::Error, StepStatus::Skipped]
+        );
+        assert_eq!(state.db.get_session(session.id).unwrap().status, SessionStatus::Error);
+        assert_eq!(state.db.list_runs(session.id).unwrap().len(), 2);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_cancel_all_sessions() {
+        let db = Database::in_memory().expect("Failed to create test database");
+        let settings = crate::settings::Settings {
+            orchestrators: crate::orchestrator::parse_plugins(
+                r#"[{ "id": "sleeper", "command": ["sh", "-c", "sleep 30", "{prompt}"] }]"#,
+            )
+            .unwrap(),
+            ..Default::default()
+        };
+        let state = AppState::with_settings(db, settings);
+        let server = create_test_server(state.clone());
+
+        let mut sessions = Vec::new();
+        for _ in 0..2 {
+            let repo = create_test_repo(&server).await;
+            let session: Session = server
+                .post("/sessions")
+                .json(&serde_json::json!({ "repo_id": repo.id, "orchestrator": "sleeper" }))
+                .await
+                .json();
+            server
+                .post(&format!("/sessions/{}/run", session.id))
+                .json(&RunSessionRequest {
+                    prompt: "wait".to_string(),
+                    ..Default::default()
+                })
+                .await
+                .assert_status_ok();
+            sessions.push(session.id);
+        }
+
+        let results: Vec<CancelResult> = server.post("/sessions/cancel-all").await.json();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|result| result.cancelled && result.error.is_none()));
+        for id in sessions {
+            assert_eq!(state.db.get_session(id).unwrap().status, SessionStatus::Cancelled);
+        }
+        assert!(state.ralph_manager.active_sessions().await.is_empty());
+    }
 }
@@ -0,0 +1,238 @@
+//! Workspace snapshot endpoints
+//!
+//! A snapshot of the repository's uncommitted work is taken before every run
+//! (see [`crate::snapshot`]). These endpoints list a session's snapshots and
+//! roll the working tree back to one of them.
+
+use std::path::PathBuf;
+
+use axum::{
+    extract::{Path as AxumPath, Query, State},
+    routing::{delete, get, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::db::models::{Session, Snapshot};
+use crate::error::{AppError, AppResult};
+use crate::snapshot::SnapshotStore;
+use crate::users::CurrentUser;
+
+use super::pagination::{Page, PageParams, DEFAULT_LIMIT};
+use super::AppState;
+
+/// Response for a restore
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RestoreSnapshotResponse {
+    pub restored: Uuid,
+    /// Snapshot of the working tree as it was before the restore
+    pub safety_snapshot: Snapshot,
+}
+
+/// The snapshot store, if the server keeps snapshots
+fn store(state: &AppState) -> AppResult<SnapshotStore> {
+    state.snapshots.clone().ok_or_else(|| {
+        AppError::BadRequest("Snapshots are not enabled on this server".to_string())
+    })
+}
+
+/// Look up a snapshot along with its session
+async fn find_snapshot(state: &AppState, id: Uuid) -> AppResult<(Snapshot, Session)> {
+    let found = state
+        .db
+        .call(move |db| {
+            let snapshot = db.get_snapshot(id)?;
+            let session = db.get_session(snapshot.session_id)?;
+            Ok((snapshot, session))
+        })
+        .await;
+    found.map_err(|e| match e {
+        crate::db::DbError::NotFound => AppError::NotFound(format!("Snapshot not found: {}", id)),
+        _ => AppError::Internal(e.to_string()),
+    })
+}
+
+/// List a session's snapshots, newest first
+async fn list_snapshots(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+    Query(params): Query<PageParams>,
+) -> AppResult<Json<Page<Snapshot>>> {
+    let session = state.db.call(move |db| db.get_session(id)).await;
+    let session = session.map_err(|e| match e {
+        crate::db::DbError::NotFound => AppError::NotFound(format!("Session not found: {}", id)),
+        _ => AppError::Internal(e.to_string()),
+    })?;
+    user.check_view(session.owner_id, "Session")?;
+
+    let snapshots = state
+        .db
+        .call(move |db| db.list_snapshots_by_session(id))
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+
+    Ok(Json(Page::from_vec(snapshots, &params, DEFAULT_LIMIT)?))
+}
+
+/// Put the repository's working tree back to a snapshot
+async fn restore_snapshot(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+) -> AppResult<Json<RestoreSnapshotResponse>> {
+    let store = store(&state)?;
+    let (snapshot, session) = find_snapshot(&state, id).await?;
+    user.check_modify(session.owner_id, "Session")?;
+
+    // Rewriting files under a running orchestrator would corrupt both
+    if state.ralph_manager.is_repo_busy(snapshot.repo_id).await {
+        return Err(AppError::Conflict(
+            "Repository has a running process; cancel it before restoring".to_string(),
+        ));
+    }
+    let repo_id = snapshot.repo_id;
+    let repo = state.db.call(move |db| db.get_repo(repo_id)).await?;
+
+    let db = state.db.clone();
+    let safety_snapshot = tokio::task::spawn_blocking(move || {
+        store.restore(&db, &snapshot, &PathBuf::from(repo.path))
+    })
+    .await
+    .map_err(|e| AppError::Internal(e.to_string()))??;
+
+    Ok(Json(RestoreSnapshotResponse {
+        restored: id,
+        safety_snapshot,
+    }))
+}
+
+/// Delete a snapshot
+async fn delete_snapshot(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+) -> AppResult<Json<()>> {
+    let store = store(&state)?;
+    let (_, session) = find_snapshot(&state, id).await?;
+    user.check_modify(session.owner_id, "Session")?;
+
+    let db = state.db.clone();
+    tokio::task::spawn_blocking(move || store.remove(&db, id))
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))??;
+    Ok(Json(()))
+}
+
+/// Create the snapshots router
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/sessions/{id}/snapshots", get(list_snapshots))
+        .route("/snapshots/{id}/restore", post(restore_snapshot))
+        .route("/snapshots/{id}", delete(delete_snapshot))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::{Orchestrator, SnapshotReason};
+    use crate::db::Database;
+    use crate::settings::Settings;
+    use axum::http::StatusCode;
+    use axum_test::TestServer;
+    use tempfile::TempDir;
+
+    fn create_test_server(data_dir: Option<PathBuf>) -> (TestServer, AppState) {
+        let db = Database::in_memory().expect("Failed to create test database");
+        let state = AppState::with_settings(
+            db,
+            Settings {
+                data_dir,
+                ..Settings::default()
+            },
+        );
+        let app = Router::new()
+            .merge(super::super::sessions::router())
+            .merge(router())
+            .with_state(state.clone());
+        (
+            TestServer::new(app).expect("Failed to create test server"),
+            state,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_list_restore_and_delete_snapshots() {
+        let work = TempDir::new().unwrap();
+        let data = TempDir::new().unwrap();
+        git2::Repository::init(work.path()).unwrap();
+        std::fs::write(work.path().join("notes.txt"), "before").unwrap();
+
+        let (server, state) = create_test_server(Some(data.path().to_path_buf()));
+        let repo = state
+            .db
+            .insert_repo(&work.path().to_string_lossy(), "repo")
+            .unwrap();
+        let session = state.db.insert_session(repo.id, None, Orchestrator::Ralph).unwrap();
+        let snapshot = state
+            .snapshots
+            .as_ref()
+            .unwrap()
+            .capture(&state.db, session.id, repo.id, work.path(), SnapshotReason::PreRun)
+            .unwrap();
+
+        let page: Page<Snapshot> = server
+            .get(&format!("/sessions/{}/snapshots", session.id))
+            .await
+            .json();
+        assert_eq!(page.total, 1);
+        assert_eq!(page.items[0].id, snapshot.id);
+
+        std::fs::write(work.path().join("notes.txt"), "after").unwrap();
+        let response = server
+            .post(&format!("/snapshots/{}/restore", snapshot.id))
+            .await;
+        response.assert_status_ok();
+        let restored: RestoreSnapshotResponse = response.json();
+        assert_eq!(restored.safety_snapshot.reason, SnapshotReason::PreRestore);
+        assert_eq!(
+            std::fs::read_to_string(work.path().join("notes.txt")).unwrap(),
+            "before"
+        );
+
+        server
+            .delete(&format!("/snapshots/{}", snapshot.id))
+            .await
+            .assert_status_ok();
+        server
+            .post(&format!("/snapshots/{}/restore", snapshot.id))
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+
+        // Deleting the session removes the files of its remaining snapshots
+        server
+            .delete(&format!("/sessions/{}", session.id))
+            .await
+            .assert_status_ok();
+        let dir = data.path().join("snapshots").join(restored.safety_snapshot.id.to_string());
+        assert!(!dir.exists());
+    }
+
+    #[tokio::test]
+    async fn test_snapshots_disabled_without_data_dir() {
+        let (server, state) = create_test_server(None);
+        let repo = state.db.insert_repo("/path/to/repo", "repo").unwrap();
+        let session = state.db.insert_session(repo.id, None, Orchestrator::Ralph).unwrap();
+
+        let page: Page<Snapshot> = server
+            .get(&format!("/sessions/{}/snapshots", session.id))
+            .await
+            .json();
+        assert_eq!(page.total, 0);
+        server
+            .post(&format!("/snapshots/{}/restore", Uuid::new_v4()))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+    }
+}
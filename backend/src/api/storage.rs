@@ -0,0 +1,74 @@
+//! Storage usage endpoint
+
+use axum::{extract::State, routing::get, Json, Router};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, AppResult};
+use crate::storage::CloneRootUsage;
+use crate::users::CurrentUser;
+
+use super::AppState;
+
+/// Response for the storage endpoint
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StorageResponse {
+    /// Usage of the directory repositories are cloned into
+    pub clone_root: CloneRootUsage,
+}
+
+/// GET /api/storage - Disk usage and quota of the clone directory
+async fn get_storage(State(state): State<AppState>, _user: CurrentUser) -> AppResult<Json<StorageResponse>> {
+    let storage = state.settings.storage.clone();
+    let root = storage
+        .clone_root
+        .clone()
+        .ok_or_else(|| AppError::Internal("Could not determine home directory".to_string()))?;
+
+    let clone_root = tokio::task::spawn_blocking(move || CloneRootUsage::measure(&root, &storage))
+        .await
+        .map_err(|e| AppError::Internal(format!("Disk usage task failed: {}", e)))?
+        .map_err(|e| AppError::Internal(format!("Failed to measure clone directory: {}", e)))?;
+
+    Ok(Json(StorageResponse { clone_root }))
+}
+
+/// Create the storage router
+pub fn router() -> Router<AppState> {
+    Router::new().route("/storage", get(get_storage))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::settings::{Settings, StorageSettings};
+    use axum_test::TestServer;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_get_storage() {
+        let root = TempDir::new().unwrap();
+        std::fs::create_dir(root.path().join("repo")).unwrap();
+        std::fs::write(root.path().join("repo/file"), vec![0u8; 2048]).unwrap();
+
+        let db = Database::in_memory().expect("Failed to create test database");
+        let state = AppState::with_settings(
+            db,
+            Settings {
+                storage: StorageSettings {
+                    clone_root: Some(root.path().to_path_buf()),
+                    clone_quota_bytes: Some(1024 * 1024),
+                },
+                ..Settings::default()
+            },
+        );
+        let server = TestServer::new(router().with_state(state)).unwrap();
+
+        let response = server.get("/storage").await;
+        response.assert_status_ok();
+        let usage = response.json::<StorageResponse>().clone_root;
+        assert_eq!(usage.used_bytes, 2048);
+        assert_eq!(usage.quota_bytes, Some(1024 * 1024));
+        assert_eq!(usage.remaining_bytes, Some(1024 * 1024 - 2048));
+    }
+}
@@ -0,0 +1,265 @@
+//! Project templates and new projects
+//!
+//! `POST /api/projects` creates a repository from a template (see
+//! [`crate::templates`]) in the clone directory: the template's files are
+//! copied in, committed as the first commit, and the repository is
+//! registered. With `start` set, a first session is created and run with the
+//! given prompt or the template's own.
+
+use std::path::Path;
+
+use axum::{
+    extract::{Path as AxumPath, State},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::activity;
+use crate::db::models::{ActivityKind, Orchestrator, Repo, Session};
+use crate::error::{AppError, AppResult};
+use crate::git::GitManager;
+use crate::templates::{Template, TemplateStore, EMPTY_TEMPLATE};
+use crate::users::CurrentUser;
+
+use super::repos::{check_clone_quota, clone_root};
+use super::sessions::{self, CreateSessionRequest, RunSessionRequest};
+use super::AppState;
+
+/// Longest project name accepted
+const MAX_NAME_LEN: usize = 100;
+
+/// Request body for creating a project
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CreateProjectRequest {
+    /// Name of the project, used as its directory name
+    pub name: String,
+    /// Template to start from (defaults to `empty`)
+    #[serde(default)]
+    pub template: Option<String>,
+    /// Start a first session right away
+    #[serde(default)]
+    pub start: bool,
+    /// Prompt for the first session; defaults to the template's
+    #[serde(default)]
+    pub prompt: Option<String>,
+    /// Orchestrator for the first session (defaults to ralph)
+    #[serde(default)]
+    pub orchestrator: Orchestrator,
+}
+
+/// Response for creating a project
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateProjectResponse {
+    pub repo: Repo,
+    /// Id of the first commit
+    pub commit: String,
+    /// The first session, when one was started
+    pub session: Option<Session>,
+}
+
+fn templates(state: &AppState) -> TemplateStore {
+    TemplateStore::in_data_dir(state.settings.data_dir.as_deref())
+}
+
+/// Check a project name can be used as a directory name
+fn validate_name(name: &str) -> AppResult<&str> {
+    let name = name.trim();
+    let valid = !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && !name.starts_with('.')
+        && !name.chars().any(|c| c.is_control())
+        && Path::new(name).file_name() == Some(std::ffi::OsStr::new(name));
+    if !valid {
+        return Err(AppError::BadRequest(format!("Invalid project name: {}", name)));
+    }
+    Ok(name)
+}
+
+/// GET /api/templates - Templates new projects can start from
+async fn list_templates(State(state): State<AppState>) -> AppResult<Json<Vec<Template>>> {
+    let store = templates(&state);
+    let templates = tokio::task::spawn_blocking(move || store.list())
+        .await
+        .map_err(|e| AppError::Internal(format!("Template task failed: {}", e)))??;
+    Ok(Json(templates))
+}
+
+/// POST /api/projects - Create a repository from a template
+async fn create_project(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Json(req): Json<CreateProjectRequest>,
+) -> AppResult<Json<CreateProjectResponse>> {
+    let name = validate_name(&req.name)?.to_string();
+    let store = templates(&state);
+    let template = store.get(req.template.as_deref().unwrap_or(EMPTY_TEMPLATE))?;
+    let prompt = req.prompt.clone().or_else(|| template.prompt.clone());
+    if req.start && prompt.as_deref().is_none_or(|p| p.trim().is_empty()) {
+        return Err(AppError::BadRequest(format!(
+            "Template '{}' has no prompt; give one to start a session",
+            template.id
+        )));
+    }
+    if !state.settings.orchestrator_available(&req.orchestrator) {
+        return Err(AppError::BadRequest(format!(
+            "Orchestrator '{}' is not yet available",
+            req.orchestrator.as_str()
+        )));
+    }
+
+    let root = clone_root(&state)?;
+    let dest = root.join(&name);
+    check_clone_quota(&state, &root, None).await?;
+    if dest.exists() {
+        return Err(AppError::BadRequest(format!(
+            "Directory already exists: {}",
+            dest.display()
+        )));
+    }
+
+    let (project_name, template_id, created) = (name.clone(), template.id.clone(), dest.clone());
+    let commit = tokio::task::spawn_blocking(move || {
+        let result = store
+            .apply(&template_id, &project_name, &created)
+            .map_err(AppError::from)
+            .and_then(|()| {
+                let message = format!("Initial commit from the {} template", template_id);
+                GitManager::init_with_commit(&created, &message)
+                    .map_err(|e| AppError::Internal(format!("Failed to create repository: {}", e)))
+            });
+        // Don't leave a half-made project behind to block the name
+        if result.is_err()
+            && let Err(e) = std::fs::remove_dir_all(&created)
+        {
+            tracing::warn!("Failed to remove unfinished project {:?}: {}", created, e);
+        }
+        result
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Project task failed: {}", e)))??;
+    check_clone_quota(&state, &root, Some(&dest)).await?;
+
+    let (path, repo_name, owner_id) = (dest.to_string_lossy().to_string(), name.clone(), user.id);
+    let repo = state
+        .db
+        .call(move |db| db.insert_repo_for(&path, &repo_name, owner_id))
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+    activity::record_async(&state.db, ActivityKind::RepoAdded, repo.id, None, &repo.name).await;
+
+    let session = match (req.start, prompt) {
+        (true, Some(prompt)) => {
+            let request = CreateSessionRequest {
+                repo_id: repo.id,
+                name: Some(format!("Scaffold {}", name)),
+                orchestrator: req.orchestrator,
+            };
+            let Json(session) =
+                sessions::create_session(State(state.clone()), user.clone(), Json(request)).await?;
+            let request = RunSessionRequest {
+                prompt,
+                ..Default::default()
+            };
+            let Json(_run) =
+                sessions::run_session(State(state.clone()), user, AxumPath(session.id), Json(request))
+                    .await?;
+            let session_id = session.id;
+            Some(state.db.call(move |db| db.get_session(session_id)).await?)
+        }
+        _ => None,
+    };
+
+    Ok(Json(CreateProjectResponse {
+        repo,
+        commit,
+        session,
+    }))
+}
+
+/// Create the templates router
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/templates", get(list_templates))
+        .route("/projects", post(create_project))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::settings::{Settings, StorageSettings};
+    use axum_test::TestServer;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_create_project_from_template() {
+        let data = TempDir::new().unwrap();
+        let clone_root = TempDir::new().unwrap();
+        let template = data.path().join("templates/web");
+        std::fs::create_dir_all(&template).unwrap();
+        std::fs::write(template.join("index.html"), "<h1>Hi</h1>\n").unwrap();
+        std::fs::write(template.join("template.json"), r#"{ "prompt": "Add a page" }"#).unwrap();
+
+        let state = AppState::with_settings(
+            Database::in_memory().unwrap(),
+            Settings {
+                storage: StorageSettings {
+                    clone_root: Some(clone_root.path().to_path_buf()),
+                    clone_quota_bytes: None,
+                },
+                data_dir: Some(data.path().to_path_buf()),
+                ..Default::default()
+            },
+        );
+        let server = TestServer::new(router().with_state(state.clone())).unwrap();
+
+        let templates: Vec<Template> = server.get("/templates").await.json();
+        assert_eq!(templates.len(), 2);
+        assert_eq!(templates[1].prompt.as_deref(), Some("Add a page"));
+
+        let request = |name: &str, template: Option<&str>| CreateProjectRequest {
+            name: name.to_string(),
+            template: template.map(String::from),
+            start: false,
+            prompt: None,
+            orchestrator: Orchestrator::Ralph,
+        };
+        let response = server.post("/projects").json(&request("site", Some("web"))).await;
+        response.assert_status_ok();
+        let project: CreateProjectResponse = response.json();
+        assert!(project.session.is_none());
+        let dir = clone_root.path().join("site");
+        assert_eq!(project.repo.path, dir.to_string_lossy());
+        assert!(dir.join("index.html").exists());
+        assert!(!dir.join("template.json").exists());
+        let log = GitManager::log(&dir, 10).unwrap();
+        assert_eq!(log[0].id, project.commit);
+        let status = GitManager::status(&dir).unwrap();
+        assert!(status.staged.is_empty() && status.unstaged.is_empty() && status.untracked.is_empty());
+        assert_eq!(state.db.list_repos().unwrap().len(), 1);
+
+        // Names are taken once, and must be plain directory names
+        server
+            .post("/projects")
+            .json(&request("site", None))
+            .await
+            .assert_status_bad_request();
+        server
+            .post("/projects")
+            .json(&request("../escape", None))
+            .await
+            .assert_status_bad_request();
+        server
+            .post("/projects")
+            .json(&request("other", Some("missing")))
+            .await
+            .assert_status_not_found();
+
+        // Starting a session needs a prompt
+        let mut no_prompt = request("plain", None);
+        no_prompt.start = true;
+        server.post("/projects").json(&no_prompt).await.assert_status_bad_request();
+        assert!(!clone_root.path().join("plain").exists());
+    }
+}
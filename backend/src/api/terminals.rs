@@ -0,0 +1,268 @@
+//! Web terminal endpoints
+//!
+//! `POST /repos/{id}/terminal` opens a shell in the repository (see
+//! [`crate::terminal`]) and `GET /terminals/{id}/ws` attaches to it. On the
+//! socket, binary frames carry raw bytes in both directions; the client may
+//! also send text frames with a [`TerminalInput`] message, and the server sends
+//! a [`TerminalExit`] text frame before closing once the shell exits.
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket},
+        Path as AxumPath, State, WebSocketUpgrade,
+    },
+    response::IntoResponse,
+    routing::{delete, get, post},
+    Json, Router,
+};
+use futures::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use uuid::Uuid;
+
+use crate::error::{AppError, AppResult};
+use crate::terminal::{Terminal, TerminalEvent, TerminalInfo};
+use crate::users::CurrentUser;
+
+use super::AppState;
+
+/// Size used when the client doesn't give one
+const DEFAULT_COLS: u16 = 80;
+const DEFAULT_ROWS: u16 = 24;
+
+/// Request to open a terminal
+#[derive(Debug, Default, Deserialize)]
+pub struct CreateTerminalRequest {
+    pub cols: Option<u16>,
+    pub rows: Option<u16>,
+}
+
+/// Text message from a terminal client
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TerminalInput {
+    Input { data: String },
+    Resize { cols: u16, rows: u16 },
+}
+
+/// Text message sent when the shell exits
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename = "exit")]
+pub struct TerminalExit {
+    pub code: Option<u32>,
+}
+
+/// Open a shell in a repository
+///
+/// The shell runs as the server's OS user and can reach everything it can,
+/// not just the repository, so only admins may open one.
+async fn create_terminal(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+    body: Option<Json<CreateTerminalRequest>>,
+) -> AppResult<Json<TerminalInfo>> {
+    user.require_admin()?;
+    let repo = state.db.call(move |db| db.get_repo(id)).await.map_err(|e| match e {
+        crate::db::DbError::NotFound => AppError::NotFound(format!("Repository not found: {}", id)),
+        _ => AppError::Internal(e.to_string()),
+    })?;
+
+    let req = body.map(|Json(req)| req).unwrap_or_default();
+    let (cols, rows) = size(req.cols, req.rows)?;
+    let info = state
+        .terminals
+        .spawn(repo.id, std::path::Path::new(&repo.path), user.id, cols, rows)?;
+    tracing::info!("Opened terminal {} in {}", info.id, repo.path);
+
+    Ok(Json(info))
+}
+
+/// Validate a requested terminal size
+fn size(cols: Option<u16>, rows: Option<u16>) -> AppResult<(u16, u16)> {
+    let cols = cols.unwrap_or(DEFAULT_COLS);
+    let rows = rows.unwrap_or(DEFAULT_ROWS);
+    if cols == 0 || rows == 0 {
+        return Err(AppError::BadRequest(
+            "Terminal size must be at least 1x1".to_string(),
+        ));
+    }
+    Ok((cols, rows))
+}
+
+/// List the terminals the user can use
+async fn list_terminals(
+    State(state): State<AppState>,
+    user: CurrentUser,
+) -> Json<Vec<TerminalInfo>> {
+    Json(
+        state
+            .terminals
+            .list()
+            .into_iter()
+            .filter(|info| user.can_modify(info.owner_id))
+            .collect(),
+    )
+}
+
+/// Look up a terminal the user may use
+fn find_terminal(state: &AppState, user: &CurrentUser, id: Uuid) -> AppResult<Arc<Terminal>> {
+    state
+        .terminals
+        .get(id)
+        .filter(|terminal| user.can_modify(terminal.info().owner_id))
+        .ok_or_else(|| AppError::NotFound(format!("Terminal not found: {}", id)))
+}
+
+/// Close a terminal, killing its shell
+async fn close_terminal(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+) -> AppResult<Json<()>> {
+    find_terminal(&state, &user, id)?;
+    state.terminals.close(id);
+    Ok(Json(()))
+}
+
+/// Attach to a terminal over a WebSocket
+async fn terminal_ws(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+) -> AppResult<impl IntoResponse> {
+    let terminal = find_terminal(&state, &user, id)?;
+    Ok(ws.on_upgrade(move |socket| handle_socket(socket, terminal)))
+}
+
+async fn handle_socket(socket: WebSocket, terminal: Arc<Terminal>) {
+    let (mut sender, mut receiver) = socket.split();
+    let (scrollback, mut events) = terminal.attach();
+
+    let sender_task = tokio::spawn(async move {
+        if !scrollback.is_empty() && sender.send(Message::Binary(scrollback.into())).await.is_err() {
+            return;
+        }
+        loop {
+            match events.recv().await {
+                Ok(TerminalEvent::Output(bytes)) => {
+                    if sender.send(Message::Binary(bytes.into())).await.is_err() {
+                        return;
+                    }
+                }
+                Ok(TerminalEvent::Exited(code)) => {
+                    if let Ok(json) = serde_json::to_string(&TerminalExit { code }) {
+                        let _ = sender.send(Message::Text(json.into())).await;
+                    }
+                    let _ = sender.send(Message::Close(None)).await;
+                    return;
+                }
+                // A slow client misses some output rather than stalling the shell
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::debug!("Terminal client lagged by {} chunks", skipped);
+                }
+                Err(RecvError::Closed) => return,
+            }
+        }
+    });
+
+    while let Some(Ok(msg)) = receiver.next().await {
+        let result = match msg {
+            Message::Binary(bytes) => terminal.write(&bytes),
+            Message::Text(text) => match serde_json::from_str::<TerminalInput>(&text) {
+                Ok(TerminalInput::Input { data }) => terminal.write(data.as_bytes()),
+                Ok(TerminalInput::Resize { cols, rows }) if cols > 0 && rows > 0 => {
+                    terminal.resize(cols, rows)
+                }
+                Ok(TerminalInput::Resize { .. }) => Ok(()),
+                Err(e) => {
+                    tracing::debug!("Ignoring invalid terminal message: {}", e);
+                    Ok(())
+                }
+            },
+            Message::Close(_) => break,
+            _ => Ok(()),
+        };
+        if let Err(e) = result {
+            tracing::debug!("Terminal input failed: {}", e);
+            break;
+        }
+    }
+
+    sender_task.abort();
+}
+
+/// Create the terminals router
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/repos/{id}/terminal", post(create_terminal))
+        .route("/terminals", get(list_terminals))
+        .route("/terminals/{id}", delete(close_terminal))
+        .route("/terminals/{id}/ws", get(terminal_ws))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::UserRole;
+    use crate::db::Database;
+    use axum::{http::StatusCode, Extension};
+    use axum_test::TestServer;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_open_list_and_close_terminal() {
+        let work = TempDir::new().unwrap();
+        let db = Database::in_memory().expect("Failed to create test database");
+        let repo = db.insert_repo(&work.path().to_string_lossy(), "repo").unwrap();
+        let state = AppState::new(db);
+        let server = TestServer::new(router().with_state(state.clone())).unwrap();
+
+        server
+            .post(&format!("/repos/{}/terminal", repo.id))
+            .json(&serde_json::json!({ "cols": 0 }))
+            .await
+            .assert_status_bad_request();
+
+        let info: TerminalInfo = server
+            .post(&format!("/repos/{}/terminal", repo.id))
+            .json(&serde_json::json!({ "cols": 120, "rows": 40 }))
+            .await
+            .json();
+        assert_eq!((info.cols, info.rows), (120, 40));
+        assert_eq!(info.repo_id, repo.id);
+
+        let terminals: Vec<TerminalInfo> = server.get("/terminals").await.json();
+        assert_eq!(terminals.len(), 1);
+
+        server
+            .delete(&format!("/terminals/{}", info.id))
+            .await
+            .assert_status_ok();
+        server
+            .delete(&format!("/terminals/{}", Uuid::new_v4()))
+            .await
+            .assert_status_not_found();
+    }
+
+    #[tokio::test]
+    async fn test_members_cannot_open_terminals() {
+        let work = TempDir::new().unwrap();
+        let db = Database::in_memory().expect("Failed to create test database");
+        let member = CurrentUser::from(db.insert_user("alice", UserRole::Member, "hash").unwrap());
+        let repo = db
+            .insert_repo_for(&work.path().to_string_lossy(), "repo", member.id)
+            .unwrap();
+        let state = AppState::new(db);
+        let app = router().layer(Extension(member)).with_state(state.clone());
+        let server = TestServer::new(app).unwrap();
+
+        server
+            .post(&format!("/repos/{}/terminal", repo.id))
+            .await
+            .assert_status(StatusCode::FORBIDDEN);
+        assert!(state.terminals.list().is_empty());
+    }
+}
@@ -0,0 +1,478 @@
+//! Inbound trigger webhooks
+//!
+//! Administrators define triggers that run a prompt on a repository; external
+//! systems (CI, GitHub Actions, cron) start them with `POST /api/hooks/trigger`
+//! using the trigger's own token, which is shown once and stored only as a
+//! hash. Fields of the JSON request body can be substituted into the prompt.
+
+use axum::{
+    body::Bytes,
+    extract::{Path as AxumPath, Query, State},
+    http::{header, HeaderMap},
+    routing::{get, post, put},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::db::models::{Orchestrator, SessionStatus, Trigger};
+use crate::error::{AppError, AppResult};
+use crate::users::{generate_token, hash_token, CurrentUser};
+
+use super::sessions::{self, CreateSessionRequest, RunSessionRequest};
+use super::AppState;
+
+/// Request body for creating a trigger
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CreateTriggerRequest {
+    pub name: String,
+    pub repo_id: Uuid,
+    /// Session every call continues; a new session is started per call when omitted
+    #[serde(default)]
+    pub session_id: Option<Uuid>,
+    pub prompt_template: String,
+    #[serde(default)]
+    pub orchestrator: Orchestrator,
+}
+
+/// Response for a newly created trigger, including its token
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CreateTriggerResponse {
+    pub trigger: Trigger,
+    /// Token for `POST /api/hooks/trigger`; it cannot be retrieved again
+    pub token: String,
+}
+
+/// Request body for changing a trigger; omitted fields are left unchanged
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct UpdateTriggerRequest {
+    pub name: Option<String>,
+    /// Set to `null` to start a new session per call
+    #[serde(default, deserialize_with = "present")]
+    pub session_id: Option<Option<Uuid>>,
+    pub prompt_template: Option<String>,
+    pub orchestrator: Option<Orchestrator>,
+    pub enabled: Option<bool>,
+}
+
+/// Deserialize a field that is present (even as `null`) into `Some`
+fn present<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: serde::Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+/// Query parameters accepted by the trigger hook
+#[derive(Debug, Deserialize)]
+pub struct TriggerQuery {
+    /// Trigger token, for callers that can't set an Authorization header
+    pub token: Option<String>,
+}
+
+/// Response for a fired trigger
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TriggerResponse {
+    pub trigger_id: Uuid,
+    pub session_id: Uuid,
+    pub status: SessionStatus,
+    /// Prompt after substituting the payload
+    pub prompt: String,
+}
+
+/// Fill `{field}` placeholders in a prompt template from a JSON payload
+///
+/// Fields are dotted paths into the payload (`{pull_request.title}`, with
+/// numeric segments indexing arrays). Strings are inserted as-is, other
+/// values as JSON, and placeholders that don't match a field are left alone.
+pub fn render_prompt(template: &str, payload: &Value) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let field = after
+            .find('}')
+            .map(|end| &after[..end])
+            .filter(|path| is_field_path(path));
+
+        match field.and_then(|path| lookup(payload, path).map(|value| (path, value))) {
+            Some((path, value)) => {
+                match value {
+                    Value::String(s) => rendered.push_str(s),
+                    Value::Null => {}
+                    other => rendered.push_str(&other.to_string()),
+                }
+                rest = &after[path.len() + 1..];
+            }
+            None => {
+                rendered.push('{');
+                rest = after;
+            }
+        }
+    }
+
+    rendered.push_str(rest);
+    rendered
+}
+
+fn is_field_path(path: &str) -> bool {
+    !path.is_empty()
+        && path
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+fn lookup<'a>(payload: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(payload, |value, segment| match value {
+        Value::Object(map) => map.get(segment),
+        Value::Array(items) => items.get(segment.parse::<usize>().ok()?),
+        _ => None,
+    })
+}
+
+fn trigger_not_found(id: Uuid) -> impl FnOnce(crate::db::DbError) -> AppError {
+    move |e| match e {
+        crate::db::DbError::NotFound => AppError::NotFound(format!("Trigger not found: {}", id)),
+        _ => AppError::Internal(e.to_string()),
+    }
+}
+
+/// Check a trigger's settings before saving them
+async fn validate(
+    state: &AppState,
+    name: &str,
+    repo_id: Uuid,
+    session_id: Option<Uuid>,
+    prompt_template: &str,
+    orchestrator: &Orchestrator,
+) -> AppResult<()> {
+    if name.trim().is_empty() {
+        return Err(AppError::BadRequest("Trigger name is required".to_string()));
+    }
+    if prompt_template.trim().is_empty() {
+        return Err(AppError::BadRequest("Prompt template is required".to_string()));
+    }
+    if !state.settings.orchestrator_available(orchestrator) {
+        return Err(AppError::BadRequest(format!(
+            "Orchestrator '{}' is not yet available",
+            orchestrator.as_str()
+        )));
+    }
+
+    state.db.call(move |db| db.get_repo(repo_id)).await.map_err(|e| match e {
+        crate::db::DbError::NotFound => {
+            AppError::BadRequest(format!("Repository not found: {}", repo_id))
+        }
+        _ => AppError::Internal(e.to_string()),
+    })?;
+    if let Some(session_id) = session_id {
+        let session = state.db.call(move |db| db.get_session(session_id)).await;
+        let session = session.map_err(|e| match e {
+            crate::db::DbError::NotFound => {
+                AppError::BadRequest(format!("Session not found: {}", session_id))
+            }
+            _ => AppError::Internal(e.to_string()),
+        })?;
+        if session.repo_id != repo_id {
+            return Err(AppError::BadRequest(format!(
+                "Session {} belongs to another repository",
+                session_id
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// GET /api/triggers - List triggers
+async fn list_triggers(
+    State(state): State<AppState>,
+    user: CurrentUser,
+) -> AppResult<Json<Vec<Trigger>>> {
+    user.require_admin()?;
+    Ok(Json(state.db.call(|db| db.list_triggers()).await?))
+}
+
+/// POST /api/triggers - Create a trigger and issue its token
+async fn create_trigger(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Json(req): Json<CreateTriggerRequest>,
+) -> AppResult<Json<CreateTriggerResponse>> {
+    user.require_admin()?;
+    validate(
+        &state,
+        &req.name,
+        req.repo_id,
+        req.session_id,
+        &req.prompt_template,
+        &req.orchestrator,
+    )
+    .await?;
+
+    let token = generate_token();
+    let token_hash = hash_token(&token);
+    let trigger = state
+        .db
+        .call(move |db| {
+            db.insert_trigger(
+                req.name.trim(),
+                req.repo_id,
+                req.session_id,
+                &req.prompt_template,
+                req.orchestrator,
+                &token_hash,
+            )
+        })
+        .await?;
+
+    Ok(Json(CreateTriggerResponse { trigger, token }))
+}
+
+/// PUT /api/triggers/{id} - Change a trigger
+async fn update_trigger(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+    Json(req): Json<UpdateTriggerRequest>,
+) -> AppResult<Json<Trigger>> {
+    user.require_admin()?;
+    let current = state
+        .db
+        .call(move |db| db.get_trigger(id))
+        .await
+        .map_err(trigger_not_found(id))?;
+
+    let name = req.name.unwrap_or(current.name);
+    let session_id = req.session_id.unwrap_or(current.session_id);
+    let prompt_template = req.prompt_template.unwrap_or(current.prompt_template);
+    let orchestrator = req.orchestrator.unwrap_or(current.orchestrator);
+    validate(
+        &state,
+        &name,
+        current.repo_id,
+        session_id,
+        &prompt_template,
+        &orchestrator,
+    )
+    .await?;
+
+    let enabled = req.enabled.unwrap_or(current.enabled);
+    state
+        .db
+        .call(move |db| {
+            db.update_trigger(id, name.trim(), session_id, &prompt_template, orchestrator, enabled)
+        })
+        .await
+        .map_err(trigger_not_found(id))?;
+
+    Ok(Json(state.db.call(move |db| db.get_trigger(id)).await?))
+}
+
+/// DELETE /api/triggers/{id} - Delete a trigger
+async fn delete_trigger(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+) -> AppResult<Json<()>> {
+    user.require_admin()?;
+    state
+        .db
+        .call(move |db| db.delete_trigger(id))
+        .await
+        .map_err(trigger_not_found(id))?;
+    Ok(Json(()))
+}
+
+/// POST /api/hooks/trigger - Start a trigger's prompt, authenticated by its token
+///
+/// The body is an optional JSON payload whose fields fill the prompt template.
+async fn fire_trigger(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<TriggerQuery>,
+    body: Bytes,
+) -> AppResult<Json<TriggerResponse>> {
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(|t| t.trim().to_string())
+        .or(query.token)
+        .ok_or_else(|| AppError::Unauthorized("Trigger token required".to_string()))?;
+
+    let token_hash = hash_token(&token);
+    let trigger = state
+        .db
+        .call(move |db| db.get_trigger_by_token_hash(&token_hash))
+        .await
+        .map_err(|e| match e {
+            crate::db::DbError::NotFound => {
+                AppError::Unauthorized("Invalid trigger token".to_string())
+            }
+            _ => AppError::Internal(e.to_string()),
+        })?;
+    if !trigger.enabled {
+        return Err(AppError::Forbidden(format!(
+            "Trigger '{}' is disabled",
+            trigger.name
+        )));
+    }
+
+    let payload: Value = if body.iter().all(u8::is_ascii_whitespace) {
+        Value::Object(Default::default())
+    } else {
+        serde_json::from_slice(&body)
+            .map_err(|e| AppError::BadRequest(format!("Invalid JSON payload: {}", e)))?
+    };
+    let prompt = render_prompt(&trigger.prompt_template, &payload);
+
+    // Triggers act for the server, so their sessions are shared
+    let user = CurrentUser::root();
+    let session_id = match trigger.session_id {
+        Some(session_id) => session_id,
+        None => {
+            let request = CreateSessionRequest {
+                repo_id: trigger.repo_id,
+                name: Some(trigger.name.clone()),
+                orchestrator: trigger.orchestrator,
+            };
+            let Json(session) =
+                sessions::create_session(State(state.clone()), user.clone(), Json(request)).await?;
+            session.id
+        }
+    };
+
+    let trigger_id = trigger.id;
+    state.db.call(move |db| db.mark_trigger_fired(trigger_id)).await?;
+    let request = RunSessionRequest {
+        prompt: prompt.clone(),
+        ..Default::default()
+    };
+    let Json(response) =
+        sessions::run_session(State(state.clone()), user, AxumPath(session_id), Json(request)).await?;
+
+    Ok(Json(TriggerResponse {
+        trigger_id: trigger.id,
+        session_id,
+        status: response.status,
+        prompt,
+    }))
+}
+
+/// Create the triggers router
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/triggers", get(list_triggers).post(create_trigger))
+        .route("/triggers/{id}", put(update_trigger).delete(delete_trigger))
+        .route("/hooks/trigger", post(fire_trigger))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use axum::http::StatusCode;
+    use axum_test::TestServer;
+    use serde_json::json;
+
+    fn create_test_server(db: Database) -> TestServer {
+        let app = Router::new().merge(router()).with_state(AppState::new(db));
+        TestServer::new(app).expect("Failed to create test server")
+    }
+
+    #[test]
+    fn test_render_prompt() {
+        let payload = json!({
+            "ref": "refs/heads/main",
+            "run": { "id": 42, "failed": true },
+            "commits": [{ "message": "Break the build" }],
+        });
+
+        assert_eq!(
+            render_prompt("Fix {ref} after run {run.id} ({commits.0.message})", &payload),
+            "Fix refs/heads/main after run 42 (Break the build)"
+        );
+        assert_eq!(render_prompt("failed={run.failed}", &payload), "failed=true");
+        // Unknown fields and non-placeholders are left alone
+        assert_eq!(
+            render_prompt("Keep {missing} and {not a field} and {", &payload),
+            "Keep {missing} and {not a field} and {"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_trigger_requires_its_token() {
+        let db = Database::in_memory().expect("Failed to create test database");
+        let repo = db.insert_repo("/tmp/ralphtown-missing-repo", "repo").unwrap();
+        let server = create_test_server(db.clone());
+
+        let response = server
+            .post("/triggers")
+            .json(&json!({
+                "name": "ci-failure",
+                "repo_id": repo.id,
+                "prompt_template": "Fix the build on {ref}",
+            }))
+            .await;
+        response.assert_status_ok();
+        let created: CreateTriggerResponse = response.json();
+
+        server
+            .post("/hooks/trigger")
+            .json(&json!({ "ref": "main" }))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+        server
+            .post("/hooks/trigger?token=wrong")
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+
+        // A valid token starts a session; the run itself fails without ralph
+        // or a real repository
+        let response = server
+            .post("/hooks/trigger")
+            .add_header(header::AUTHORIZATION, format!("Bearer {}", created.token))
+            .json(&json!({ "ref": "main" }))
+            .await;
+        assert_ne!(response.status_code(), StatusCode::UNAUTHORIZED);
+        let sessions = db.list_sessions().unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].name.as_deref(), Some("ci-failure"));
+        assert!(db.get_trigger(created.trigger.id).unwrap().last_triggered_at.is_some());
+
+        // Disabled triggers refuse to run
+        server
+            .put(&format!("/triggers/{}", created.trigger.id))
+            .json(&json!({ "enabled": false }))
+            .await
+            .assert_status_ok();
+        server
+            .post(&format!("/hooks/trigger?token={}", created.token))
+            .await
+            .assert_status(StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_trigger_session_must_match_repo() {
+        let db = Database::in_memory().expect("Failed to create test database");
+        let repo = db.insert_repo("/path/a", "a").unwrap();
+        let other = db.insert_repo("/path/b", "b").unwrap();
+        let session = db.insert_session(other.id, None, Orchestrator::Ralph).unwrap();
+        let server = create_test_server(db);
+
+        server
+            .post("/triggers")
+            .json(&json!({
+                "name": "nightly",
+                "repo_id": repo.id,
+                "session_id": session.id,
+                "prompt_template": "Tidy up",
+            }))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,312 @@
+//! Prompt upload endpoints
+//!
+//! `POST /api/uploads` takes a multipart form and streams each file part to
+//! the upload store (see [`crate::uploads`]) without holding it in memory.
+//! The whole request may be at most `upload_max_mb`, enforced while reading
+//! rather than by the global body limit. Run requests then refer to uploads
+//! by id: `prompt_upload_id` supplies the prompt's text, and
+//! `context_upload_ids` lists files the agent is pointed at.
+
+use std::path::PathBuf;
+
+use axum::{
+    extract::{multipart::MultipartError, DefaultBodyLimit, Multipart, Path as AxumPath, State},
+    routing::{get, post},
+    Json, Router,
+};
+use tokio::io::AsyncWriteExt;
+use uuid::Uuid;
+
+use crate::db::models::Upload;
+use crate::error::{AppError, AppResult};
+use crate::uploads::{safe_filename, UploadError, UploadStore};
+use crate::users::CurrentUser;
+
+use super::sessions::RunSessionRequest;
+use super::AppState;
+
+/// The upload store, if the server has a data directory to keep it in
+pub(super) fn store(state: &AppState) -> AppResult<UploadStore> {
+    state
+        .settings
+        .data_dir
+        .as_deref()
+        .map(UploadStore::in_data_dir)
+        .ok_or_else(|| AppError::BadRequest("Uploads need a data directory".to_string()))
+}
+
+fn multipart_error(e: MultipartError) -> AppError {
+    AppError::BadRequest(format!("Invalid upload: {}", e.body_text()))
+}
+
+fn too_large(limit: u64) -> AppError {
+    AppError::PayloadTooLarge(format!(
+        "Uploads are limited to {} MiB per request",
+        limit / 1024 / 1024
+    ))
+}
+
+/// Get an upload the user can see
+pub(super) async fn get_visible(state: &AppState, user: &CurrentUser, id: Uuid) -> AppResult<Upload> {
+    let upload = state.db.call(move |db| db.get_upload(id)).await.map_err(|e| match e {
+        crate::db::DbError::NotFound => AppError::NotFound(format!("Upload not found: {}", id)),
+        _ => AppError::Internal(e.to_string()),
+    })?;
+    user.check_view(upload.owner_id, "Upload")?;
+    Ok(upload)
+}
+
+/// A file part of a multipart form, stored on disk
+pub(super) struct ReceivedFile {
+    pub id: Uuid,
+    pub filename: String,
+    pub content_type: Option<String>,
+    pub size_bytes: u64,
+}
+
+/// Stream every file part of a multipart form to `dir_for(id)/<filename>`
+///
+/// Parts without a file name are skipped. At most `limit` bytes are taken
+/// across all files; when anything fails, the files already written are
+/// removed again.
+pub(super) async fn receive_files(
+    multipart: &mut Multipart,
+    limit: u64,
+    dir_for: impl Fn(Uuid) -> PathBuf,
+) -> AppResult<Vec<ReceivedFile>> {
+    let mut total = 0;
+    let mut received: Vec<ReceivedFile> = Vec::new();
+
+    let result = async {
+        while let Some(mut field) = multipart.next_field().await.map_err(multipart_error)? {
+            // Plain form fields carry no file
+            if field.file_name().is_none() {
+                continue;
+            }
+            let id = Uuid::new_v4();
+            let filename = safe_filename(field.file_name());
+            let content_type = field.content_type().map(String::from);
+            let dir = dir_for(id);
+            let store_error = |e: std::io::Error| AppError::Internal(format!("Failed to store upload: {}", e));
+
+            let written = async {
+                tokio::fs::create_dir_all(&dir).await.map_err(store_error)?;
+                let mut file = tokio::fs::File::create(dir.join(&filename)).await.map_err(store_error)?;
+                let mut size = 0;
+                while let Some(chunk) = field.chunk().await.map_err(multipart_error)? {
+                    size += chunk.len() as u64;
+                    total += chunk.len() as u64;
+                    if total > limit {
+                        return Err(too_large(limit));
+                    }
+                    file.write_all(&chunk).await.map_err(store_error)?;
+                }
+                file.flush().await.map_err(store_error)?;
+                Ok(size)
+            }
+            .await;
+            match written {
+                Ok(size_bytes) => received.push(ReceivedFile {
+                    id,
+                    filename,
+                    content_type,
+                    size_bytes,
+                }),
+                Err(e) => {
+                    let _ = tokio::fs::remove_dir_all(&dir).await;
+                    return Err(e);
+                }
+            }
+        }
+        Ok(())
+    }
+    .await;
+
+    // A failed request keeps none of its files
+    if let Err(e) = result {
+        for file in &received {
+            let _ = tokio::fs::remove_dir_all(dir_for(file.id)).await;
+        }
+        return Err(e);
+    }
+    if received.is_empty() {
+        return Err(AppError::BadRequest("No files in the request".to_string()));
+    }
+    Ok(received)
+}
+
+/// POST /api/uploads - Store the files of a multipart form
+async fn upload_files(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    mut multipart: Multipart,
+) -> AppResult<Json<Vec<Upload>>> {
+    let store = store(&state)?;
+    let limit = state.settings.body_limits.upload_bytes;
+    let received = receive_files(&mut multipart, limit, |id| store.dir(id)).await?;
+
+    let owner_id = user.id;
+    let uploads = state
+        .db
+        .call(move |db| {
+            received
+                .iter()
+                .map(|file| {
+                    db.insert_upload(
+                        file.id,
+                        owner_id,
+                        &file.filename,
+                        file.content_type.as_deref(),
+                        file.size_bytes,
+                    )
+                })
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .await?;
+    Ok(Json(uploads))
+}
+
+/// GET /api/uploads/{id} - An upload's details
+async fn get_upload(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+) -> AppResult<Json<Upload>> {
+    Ok(Json(get_visible(&state, &user, id).await?))
+}
+
+/// DELETE /api/uploads/{id} - Delete an upload and its file
+async fn delete_upload(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+) -> AppResult<()> {
+    let upload = get_visible(&state, &user, id).await?;
+    user.check_modify(upload.owner_id, "Upload")?;
+    state.db.call(move |db| db.delete_upload(id)).await?;
+    if let Ok(store) = store(&state) {
+        store
+            .remove(id)
+            .map_err(|e| AppError::Internal(format!("Failed to delete upload: {}", e)))?;
+    }
+    Ok(())
+}
+
+/// The prompt a run request asks for, with its uploads filled in
+///
+/// The text of `prompt_upload_id` follows the inline prompt, and context
+/// uploads are listed by path at the end for the agent to read. Context
+/// files only exist on this machine, so runs on workers can't use them.
+pub(super) async fn build_prompt(
+    state: &AppState,
+    user: &CurrentUser,
+    req: &RunSessionRequest,
+) -> AppResult<String> {
+    let mut prompt = req.prompt.clone();
+    if req.prompt_upload_id.is_none() && req.context_upload_ids.is_empty() {
+        return Ok(prompt);
+    }
+    let store = store(state)?;
+
+    if let Some(id) = req.prompt_upload_id {
+        let upload = get_visible(state, user, id).await?;
+        let reader = store.clone();
+        let text = tokio::task::spawn_blocking(move || reader.read_text(&upload))
+            .await
+            .map_err(|e| AppError::Internal(format!("Upload task failed: {}", e)))?
+            .map_err(|e| match e {
+                UploadError::NotText(_) => AppError::BadRequest(e.to_string()),
+                UploadError::Io(_) => AppError::Internal(format!("Failed to read upload: {}", e)),
+            })?;
+        if !prompt.trim().is_empty() {
+            prompt.push_str("\n\n");
+        }
+        prompt.push_str(&text);
+    }
+
+    if !req.context_upload_ids.is_empty() {
+        if req.worker_id.is_some() {
+            return Err(AppError::BadRequest(
+                "Context files can't be used for runs on workers".to_string(),
+            ));
+        }
+        prompt.push_str("\n\nContext files:");
+        for id in &req.context_upload_ids {
+            let upload = get_visible(state, user, *id).await?;
+            prompt.push_str(&format!("\n- {}", store.path(&upload).display()));
+        }
+    }
+    Ok(prompt)
+}
+
+/// Create the uploads router
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/uploads",
+            // The limit is enforced while streaming, against `upload_max_mb`
+            post(upload_files).layer(DefaultBodyLimit::disable()),
+        )
+        .route("/uploads/{id}", get(get_upload).delete(delete_upload))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::settings::{BodyLimits, Settings};
+    use axum_test::multipart::{MultipartForm, Part};
+    use axum_test::TestServer;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_upload_and_build_prompt() {
+        let data = TempDir::new().unwrap();
+        let state = AppState::with_settings(
+            Database::in_memory().unwrap(),
+            Settings {
+                data_dir: Some(data.path().to_path_buf()),
+                body_limits: BodyLimits {
+                    upload_bytes: 1024,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+        let server = TestServer::new(router().with_state(state.clone())).unwrap();
+
+        let form = MultipartForm::new()
+            .add_text("note", "ignored")
+            .add_part("file", Part::bytes(b"Build the parser".to_vec()).file_name("prompt.md"))
+            .add_part("file", Part::bytes(b"grammar".to_vec()).file_name("../spec.txt"));
+        let response = server.post("/uploads").multipart(form).await;
+        response.assert_status_ok();
+        let uploads: Vec<Upload> = response.json();
+        assert_eq!(uploads.len(), 2);
+        assert_eq!(uploads[1].filename, "spec.txt");
+        assert_eq!(uploads[1].size_bytes, 7);
+
+        let req = RunSessionRequest {
+            prompt: "Read this first.".to_string(),
+            prompt_upload_id: Some(uploads[0].id),
+            context_upload_ids: vec![uploads[1].id],
+            ..Default::default()
+        };
+        let prompt = build_prompt(&state, &CurrentUser::root(), &req).await.unwrap();
+        let spec = store(&state).unwrap().path(&uploads[1]);
+        assert_eq!(
+            prompt,
+            format!("Read this first.\n\nBuild the parser\n\nContext files:\n- {}", spec.display())
+        );
+
+        // Too large, and nothing is kept
+        let form = MultipartForm::new().add_part("file", Part::bytes(vec![b'x'; 2048]).file_name("big.log"));
+        let response = server.post("/uploads").multipart(form).await;
+        response.assert_status(axum::http::StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(std::fs::read_dir(data.path().join("uploads")).unwrap().count(), 2);
+
+        server.delete(&format!("/uploads/{}", uploads[0].id)).await.assert_status_ok();
+        server.get(&format!("/uploads/{}", uploads[0].id)).await.assert_status_not_found();
+        assert!(!store(&state).unwrap().dir(uploads[0].id).exists());
+    }
+}
@@ -0,0 +1,338 @@
+//! User management API
+//!
+//! Administrators create users and assign roles; each new user receives an
+//! API token that is shown once and stored only as a hash.
+
+use axum::{
+    extract::{Path as AxumPath, State},
+    routing::{get, put},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::db::models::{User, UserRole};
+use crate::error::{AppError, AppResult};
+use crate::settings::DESKTOP_NOTIFICATIONS_KEY;
+use crate::users::{generate_token, hash_token, CurrentUser};
+
+use super::AppState;
+
+/// Request body for creating a user
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CreateUserRequest {
+    pub username: String,
+    #[serde(default = "default_role")]
+    pub role: UserRole,
+}
+
+fn default_role() -> UserRole {
+    UserRole::Member
+}
+
+/// Response for a newly created user, including their token
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CreateUserResponse {
+    pub user: User,
+    /// API token for the user; it cannot be retrieved again
+    pub token: String,
+}
+
+/// Request body for changing a user's role
+#[derive(Debug, Deserialize, Serialize)]
+pub struct UpdateUserRequest {
+    pub role: UserRole,
+}
+
+/// Response describing the current user
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MeResponse {
+    pub id: Option<Uuid>,
+    pub username: String,
+    pub role: UserRole,
+    /// Whether the user's finished sessions raise desktop notifications
+    pub desktop_notifications: bool,
+}
+
+/// Request body for updating the current user's preferences
+#[derive(Debug, Deserialize, Serialize)]
+pub struct UpdatePreferencesRequest {
+    pub desktop_notifications: bool,
+}
+
+/// Describe a user, reading preferences from the database for named users
+async fn me_response(state: &AppState, user: CurrentUser) -> AppResult<MeResponse> {
+    let desktop_notifications = match user.id {
+        Some(id) => state.db.call(move |db| db.get_user(id)).await?.desktop_notifications,
+        None => state.settings.desktop_notifications,
+    };
+    Ok(MeResponse {
+        id: user.id,
+        username: user.username,
+        role: user.role,
+        desktop_notifications,
+    })
+}
+
+/// GET /api/users/me - Describe the authenticated user
+async fn get_me(State(state): State<AppState>, user: CurrentUser) -> AppResult<Json<MeResponse>> {
+    Ok(Json(me_response(&state, user).await?))
+}
+
+/// PUT /api/users/me/preferences - Update the authenticated user's preferences
+async fn update_preferences(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Json(req): Json<UpdatePreferencesRequest>,
+) -> AppResult<Json<MeResponse>> {
+    let Some(id) = user.id else {
+        return Err(AppError::BadRequest(format!(
+            "The built-in administrator uses the '{}' setting",
+            DESKTOP_NOTIFICATIONS_KEY
+        )));
+    };
+
+    state
+        .db
+        .call(move |db| db.set_user_desktop_notifications(id, req.desktop_notifications))
+        .await?;
+
+    Ok(Json(me_response(&state, user).await?))
+}
+
+/// GET /api/users - List all users
+async fn list_users(
+    State(state): State<AppState>,
+    user: CurrentUser,
+) -> AppResult<Json<Vec<User>>> {
+    user.require_admin()?;
+
+    let users = state
+        .db
+        .call(|db| db.list_users())
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+
+    Ok(Json(users))
+}
+
+/// POST /api/users - Create a user and issue their token
+async fn create_user(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Json(req): Json<CreateUserRequest>,
+) -> AppResult<Json<CreateUserResponse>> {
+    user.require_admin()?;
+
+    let username = req.username.trim();
+    if username.is_empty() || username.chars().any(char::is_control) {
+        return Err(AppError::BadRequest("Invalid username".to_string()));
+    }
+
+    let token = generate_token();
+    let (username, role, hash) = (username.to_string(), req.role, hash_token(&token));
+    let created = state
+        .db
+        .call(move |db| db.insert_user(&username, role, &hash))
+        .await?;
+
+    Ok(Json(CreateUserResponse {
+        user: created,
+        token,
+    }))
+}
+
+/// PUT /api/users/{id} - Change a user's role
+async fn update_user(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+    Json(req): Json<UpdateUserRequest>,
+) -> AppResult<Json<User>> {
+    user.require_admin()?;
+
+    let role = req.role;
+    state.db.call(move |db| db.update_user_role(id, role)).await.map_err(|e| match e {
+        crate::db::DbError::NotFound => AppError::NotFound(format!("User not found: {}", id)),
+        _ => AppError::Internal(e.to_string()),
+    })?;
+
+    Ok(Json(state.db.call(move |db| db.get_user(id)).await?))
+}
+
+/// DELETE /api/users/{id} - Remove a user; their repos and sessions become shared
+async fn delete_user(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+) -> AppResult<Json<()>> {
+    user.require_admin()?;
+
+    state.db.call(move |db| db.delete_user(id)).await.map_err(|e| match e {
+        crate::db::DbError::NotFound => AppError::NotFound(format!("User not found: {}", id)),
+        _ => AppError::Internal(e.to_string()),
+    })?;
+
+    Ok(Json(()))
+}
+
+/// Create the users router
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/users", get(list_users).post(create_user))
+        .route("/users/me", get(get_me))
+        .route("/users/me/preferences", put(update_preferences))
+        .route("/users/{id}", put(update_user).delete(delete_user))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::pagination::Page;
+    use crate::create_app;
+    use crate::db::Database;
+    use crate::settings::{AuthSettings, Settings};
+    use axum::http::{header, StatusCode};
+    use axum_test::TestServer;
+
+    const ADMIN_TOKEN: &str = "0123456789abcdef";
+
+    fn create_test_server() -> (TestServer, Database) {
+        let db = Database::in_memory().expect("Failed to create test database");
+        let settings = Settings {
+            auth: AuthSettings {
+                token: Some(ADMIN_TOKEN.to_string()),
+            },
+            ..Settings::default()
+        };
+        let server = TestServer::new(create_app(AppState::with_settings(db.clone(), settings)))
+            .expect("Failed to create test server");
+        (server, db)
+    }
+
+    fn bearer(token: &str) -> String {
+        format!("Bearer {}", token)
+    }
+
+    async fn create(server: &TestServer, username: &str, role: UserRole) -> CreateUserResponse {
+        let response = server
+            .post("/api/users")
+            .add_header(header::AUTHORIZATION, bearer(ADMIN_TOKEN))
+            .json(&CreateUserRequest {
+                username: username.to_string(),
+                role,
+            })
+            .await;
+        response.assert_status_ok();
+        response.json()
+    }
+
+    #[tokio::test]
+    async fn test_admin_creates_user_with_working_token() {
+        let (server, _db) = create_test_server();
+        let created = create(&server, "alice", UserRole::Member).await;
+
+        let response = server
+            .get("/api/users/me")
+            .add_header(header::AUTHORIZATION, bearer(&created.token))
+            .await;
+        response.assert_status_ok();
+        let me: MeResponse = response.json();
+        assert_eq!(me.username, "alice");
+        assert_eq!(me.role, UserRole::Member);
+        assert!(me.desktop_notifications);
+    }
+
+    #[tokio::test]
+    async fn test_user_turns_off_notifications() {
+        let (server, db) = create_test_server();
+        let created = create(&server, "alice", UserRole::Viewer).await;
+
+        let response = server
+            .put("/api/users/me/preferences")
+            .add_header(header::AUTHORIZATION, bearer(&created.token))
+            .json(&UpdatePreferencesRequest {
+                desktop_notifications: false,
+            })
+            .await;
+        response.assert_status_ok();
+        assert!(!response.json::<MeResponse>().desktop_notifications);
+        assert!(!db.get_user(created.user.id).unwrap().desktop_notifications);
+    }
+
+    #[tokio::test]
+    async fn test_members_cannot_manage_users() {
+        let (server, _db) = create_test_server();
+        let created = create(&server, "alice", UserRole::Member).await;
+
+        server
+            .get("/api/users")
+            .add_header(header::AUTHORIZATION, bearer(&created.token))
+            .await
+            .assert_status(StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_members_only_see_own_and_shared_repos() {
+        let (server, db) = create_test_server();
+        let alice = create(&server, "alice", UserRole::Member).await;
+        let bob = create(&server, "bob", UserRole::Member).await;
+
+        db.insert_repo_for("/repos/alice", "alice-repo", Some(alice.user.id))
+            .unwrap();
+        let bob_repo = db
+            .insert_repo_for("/repos/bob", "bob-repo", Some(bob.user.id))
+            .unwrap();
+        db.insert_repo("/repos/shared", "shared-repo").unwrap();
+        let bob_session = db
+            .insert_session_for(bob_repo.id, None, Default::default(), Some(bob.user.id))
+            .unwrap();
+
+        let response = server
+            .get("/api/repos")
+            .add_header(header::AUTHORIZATION, bearer(&alice.token))
+            .await;
+        let names: Vec<String> = response
+            .json::<Page<crate::db::models::Repo>>()
+            .items
+            .into_iter()
+            .map(|r| r.name)
+            .collect();
+        assert_eq!(names, vec!["alice-repo", "shared-repo"]);
+
+        // Other users' sessions are hidden and can't be cancelled
+        server
+            .get(&format!("/api/sessions/{}", bob_session.id))
+            .add_header(header::AUTHORIZATION, bearer(&alice.token))
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+        server
+            .post(&format!("/api/sessions/{}/cancel", bob_session.id))
+            .add_header(header::AUTHORIZATION, bearer(&alice.token))
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+
+        // The admin sees everything
+        let response = server
+            .get("/api/repos")
+            .add_header(header::AUTHORIZATION, bearer(ADMIN_TOKEN))
+            .await;
+        assert_eq!(response.json::<Page<crate::db::models::Repo>>().total, 3);
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_username_conflicts() {
+        let (server, _db) = create_test_server();
+        create(&server, "alice", UserRole::Member).await;
+
+        server
+            .post("/api/users")
+            .add_header(header::AUTHORIZATION, bearer(ADMIN_TOKEN))
+            .json(&CreateUserRequest {
+                username: "alice".to_string(),
+                role: UserRole::Viewer,
+            })
+            .await
+            .assert_status(StatusCode::CONFLICT);
+    }
+}
@@ -0,0 +1,296 @@
+//! Webhook management API
+//!
+//! Administrators register endpoints that receive signed lifecycle events,
+//! inspect the delivery log, and replay failed deliveries.
+
+use axum::{
+    extract::{Path as AxumPath, Query, State},
+    routing::{get, post, put},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::db::models::{Webhook, WebhookDelivery};
+use crate::error::{AppError, AppResult};
+use crate::users::{generate_token, CurrentUser};
+use crate::webhooks::EventType;
+
+use super::AppState;
+
+/// Default number of deliveries returned from the log
+const DEFAULT_DELIVERY_LIMIT: i64 = 50;
+
+/// Request body for registering a webhook
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CreateWebhookRequest {
+    pub url: String,
+    /// Signing secret; generated when omitted
+    #[serde(default)]
+    pub secret: Option<String>,
+    /// Event types to deliver; empty for all
+    #[serde(default)]
+    pub events: Vec<String>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// Response for a newly registered webhook, including its secret
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CreateWebhookResponse {
+    pub webhook: Webhook,
+    /// Signing secret; it cannot be retrieved again
+    pub secret: String,
+}
+
+/// Request body for changing a webhook; omitted fields are left unchanged
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct UpdateWebhookRequest {
+    pub url: Option<String>,
+    pub events: Option<Vec<String>>,
+    pub enabled: Option<bool>,
+}
+
+/// Query parameters for the delivery log
+#[derive(Debug, Deserialize)]
+pub struct DeliveriesQuery {
+    pub limit: Option<i64>,
+}
+
+/// Response listing the supported event types
+#[derive(Debug, Deserialize, Serialize)]
+pub struct EventTypesResponse {
+    pub events: Vec<String>,
+}
+
+fn validate_url(url: &str) -> AppResult<()> {
+    if !(url.starts_with("http://") || url.starts_with("https://")) {
+        return Err(AppError::BadRequest(
+            "Webhook URL must start with http:// or https://".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_events(events: &[String]) -> AppResult<()> {
+    match events.iter().find(|event| !EventType::is_valid(event)) {
+        Some(event) => Err(AppError::BadRequest(format!("Unknown event type: {}", event))),
+        None => Ok(()),
+    }
+}
+
+fn webhook_not_found(id: Uuid) -> impl FnOnce(crate::db::DbError) -> AppError {
+    move |e| match e {
+        crate::db::DbError::NotFound => AppError::NotFound(format!("Webhook not found: {}", id)),
+        _ => AppError::Internal(e.to_string()),
+    }
+}
+
+/// GET /api/webhooks - List webhooks
+async fn list_webhooks(
+    State(state): State<AppState>,
+    user: CurrentUser,
+) -> AppResult<Json<Vec<Webhook>>> {
+    user.require_admin()?;
+    Ok(Json(state.db.call(|db| db.list_webhooks()).await?))
+}
+
+/// GET /api/webhooks/events - List the event types webhooks can subscribe to
+async fn list_event_types(user: CurrentUser) -> AppResult<Json<EventTypesResponse>> {
+    user.require_admin()?;
+    Ok(Json(EventTypesResponse {
+        events: EventType::ALL
+            .iter()
+            .map(|event| event.as_str().to_string())
+            .collect(),
+    }))
+}
+
+/// POST /api/webhooks - Register a webhook
+async fn create_webhook(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    Json(req): Json<CreateWebhookRequest>,
+) -> AppResult<Json<CreateWebhookResponse>> {
+    user.require_admin()?;
+    validate_url(&req.url)?;
+    validate_events(&req.events)?;
+
+    let secret = req
+        .secret
+        .filter(|secret| !secret.is_empty())
+        .unwrap_or_else(generate_token);
+    let (url, events, enabled, stored) = (req.url, req.events, req.enabled, secret.clone());
+    let webhook = state
+        .db
+        .call(move |db| db.insert_webhook(&url, &stored, &events, enabled))
+        .await?;
+
+    Ok(Json(CreateWebhookResponse { webhook, secret }))
+}
+
+/// PUT /api/webhooks/{id} - Change a webhook's URL, events, or enabled flag
+async fn update_webhook(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+    Json(req): Json<UpdateWebhookRequest>,
+) -> AppResult<Json<Webhook>> {
+    user.require_admin()?;
+    let current = state
+        .db
+        .call(move |db| db.get_webhook(id))
+        .await
+        .map_err(webhook_not_found(id))?;
+
+    let url = req.url.unwrap_or(current.url);
+    let events = req.events.unwrap_or(current.events);
+    validate_url(&url)?;
+    validate_events(&events)?;
+    let enabled = req.enabled.unwrap_or(current.enabled);
+
+    state
+        .db
+        .call(move |db| db.update_webhook(id, &url, &events, enabled))
+        .await
+        .map_err(webhook_not_found(id))?;
+
+    Ok(Json(state.db.call(move |db| db.get_webhook(id)).await?))
+}
+
+/// DELETE /api/webhooks/{id} - Remove a webhook and its delivery log
+async fn delete_webhook(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+) -> AppResult<Json<()>> {
+    user.require_admin()?;
+    state
+        .db
+        .call(move |db| db.delete_webhook(id))
+        .await
+        .map_err(webhook_not_found(id))?;
+    Ok(Json(()))
+}
+
+/// GET /api/webhooks/{id}/deliveries - Recent deliveries, newest first
+async fn list_deliveries(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+    Query(query): Query<DeliveriesQuery>,
+) -> AppResult<Json<Vec<WebhookDelivery>>> {
+    user.require_admin()?;
+    state
+        .db
+        .call(move |db| db.get_webhook(id))
+        .await
+        .map_err(webhook_not_found(id))?;
+
+    let limit = query.limit.unwrap_or(DEFAULT_DELIVERY_LIMIT).clamp(1, 500);
+    Ok(Json(state.db.call(move |db| db.list_webhook_deliveries(id, limit)).await?))
+}
+
+/// POST /api/webhooks/deliveries/{id}/replay - Send a logged delivery again
+async fn replay_delivery(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+) -> AppResult<Json<WebhookDelivery>> {
+    user.require_admin()?;
+    let delivery = state.webhooks.replay(id).await.map_err(|e| match e {
+        crate::db::DbError::NotFound => AppError::NotFound(format!("Delivery not found: {}", id)),
+        _ => AppError::Internal(e.to_string()),
+    })?;
+    Ok(Json(delivery))
+}
+
+/// Create the webhooks router
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/webhooks", get(list_webhooks).post(create_webhook))
+        .route("/webhooks/events", get(list_event_types))
+        .route("/webhooks/{id}", put(update_webhook).delete(delete_webhook))
+        .route("/webhooks/{id}/deliveries", get(list_deliveries))
+        .route("/webhooks/deliveries/{id}/replay", post(replay_delivery))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use axum::http::StatusCode;
+    use axum_test::TestServer;
+
+    fn create_test_server() -> TestServer {
+        let db = Database::in_memory().expect("Failed to create test database");
+        let app = Router::new().merge(router()).with_state(AppState::new(db));
+        TestServer::new(app).expect("Failed to create test server")
+    }
+
+    #[tokio::test]
+    async fn test_create_and_update_webhook() {
+        let server = create_test_server();
+
+        let response = server
+            .post("/webhooks")
+            .json(&CreateWebhookRequest {
+                url: "https://example.com/hook".to_string(),
+                secret: None,
+                events: vec!["session.status".to_string()],
+                enabled: true,
+            })
+            .await;
+        response.assert_status_ok();
+        let created: CreateWebhookResponse = response.json();
+        assert_eq!(created.secret.len(), 64);
+
+        // The secret isn't included when listing
+        let response = server.get("/webhooks").await;
+        let listed: Vec<serde_json::Value> = response.json();
+        assert_eq!(listed.len(), 1);
+        assert!(listed[0].get("secret").is_none());
+
+        let response = server
+            .put(&format!("/webhooks/{}", created.webhook.id))
+            .json(&UpdateWebhookRequest {
+                enabled: Some(false),
+                ..Default::default()
+            })
+            .await;
+        response.assert_status_ok();
+        let updated: Webhook = response.json();
+        assert!(!updated.enabled);
+        assert_eq!(updated.events, vec!["session.status"]);
+    }
+
+    #[tokio::test]
+    async fn test_rejects_invalid_webhooks() {
+        let server = create_test_server();
+
+        server
+            .post("/webhooks")
+            .json(&serde_json::json!({ "url": "ftp://example.com" }))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+        server
+            .post("/webhooks")
+            .json(&serde_json::json!({ "url": "https://example.com", "events": ["nope"] }))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_replay_unknown_delivery() {
+        let server = create_test_server();
+
+        server
+            .post(&format!("/webhooks/deliveries/{}/replay", Uuid::new_v4()))
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+    }
+}
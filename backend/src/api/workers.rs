@@ -0,0 +1,204 @@
+//! Remote worker endpoints
+//!
+//! Workers started with `ralphtown worker` connect to `GET /workers/connect`
+//! (see [`crate::workers`]). On the socket each side sends JSON text frames:
+//! the worker opens with a `hello`, then sends `output` and `exited` messages
+//! for its runs, and the server answers with `welcome` and sends `run` and
+//! `cancel` messages. Connecting requires an administrator, since a worker
+//! runs whatever the server asks of it.
+
+use std::time::Duration;
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket},
+        State, WebSocketUpgrade,
+    },
+    response::IntoResponse,
+    routing::get,
+    Json, Router,
+};
+use futures::{SinkExt, StreamExt};
+
+use crate::error::AppResult;
+use crate::users::CurrentUser;
+use crate::workers::{FromWorker, ToWorker, WorkerInfo};
+
+use super::AppState;
+
+/// Time a worker has to introduce itself after connecting
+const HELLO_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// GET /api/workers - Connected workers
+async fn list_workers(State(state): State<AppState>) -> Json<Vec<WorkerInfo>> {
+    Json(state.workers.list())
+}
+
+/// GET /api/workers/connect - WebSocket a worker registers on
+async fn connect_worker(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    user: CurrentUser,
+) -> AppResult<impl IntoResponse> {
+    user.require_admin()?;
+    Ok(ws.on_upgrade(move |socket| handle_worker(socket, state)))
+}
+
+/// Read the next text frame as a worker message
+async fn next_message(socket: &mut futures::stream::SplitStream<WebSocket>) -> Option<FromWorker> {
+    while let Some(Ok(message)) = socket.next().await {
+        match message {
+            Message::Text(text) => match serde_json::from_str(&text) {
+                Ok(message) => return Some(message),
+                Err(e) => tracing::warn!("Ignoring unrecognized worker message: {}", e),
+            },
+            Message::Close(_) => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+async fn handle_worker(socket: WebSocket, state: AppState) {
+    let (mut sender, mut receiver) = socket.split();
+
+    let hello = tokio::time::timeout(HELLO_TIMEOUT, next_message(&mut receiver)).await;
+    let Ok(Some(FromWorker::Hello { name, version })) = hello else {
+        tracing::warn!("Worker connection closed without a hello");
+        return;
+    };
+    let (info, mut assignments) = state.workers.register(&name, &version);
+    tracing::info!("Worker {} ({}) connected", info.id, name);
+    let _ = state.workers.send(info.id, ToWorker::Welcome { worker_id: info.id });
+
+    let forward = tokio::spawn(async move {
+        while let Some(message) = assignments.recv().await {
+            let json = match serde_json::to_string(&message) {
+                Ok(json) => json,
+                Err(e) => {
+                    tracing::error!("Failed to serialize worker message: {}", e);
+                    continue;
+                }
+            };
+            if sender.send(Message::Text(json.into())).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    while let Some(message) = next_message(&mut receiver).await {
+        match message {
+            FromWorker::Hello { .. } => {}
+            FromWorker::Output {
+                session_id,
+                stream,
+                content,
+            } => {
+                state
+                    .ralph_manager
+                    .worker_output(info.id, session_id, stream, content, &state.db, &state.connections)
+                    .await;
+            }
+            FromWorker::Exited { session_id, success } => {
+                state
+                    .ralph_manager
+                    .worker_exited(
+                        info.id,
+                        session_id,
+                        success,
+                        state.db.clone(),
+                        state.connections.clone(),
+                    )
+                    .await;
+            }
+        }
+    }
+
+    forward.abort();
+    state.workers.unregister(info.id);
+    state
+        .ralph_manager
+        .worker_disconnected(info.id, state.db.clone(), state.connections.clone())
+        .await;
+    tracing::info!("Worker {} ({}) disconnected", info.id, name);
+}
+
+/// Create the workers router
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/workers", get(list_workers))
+        .route("/workers/connect", get(connect_worker))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::{Orchestrator, SessionStatus};
+    use crate::db::Database;
+    use crate::ws::messages::OutputStream;
+    use axum_test::TestServer;
+
+    #[tokio::test]
+    async fn test_session_runs_on_worker() {
+        let state = AppState::new(Database::in_memory().unwrap());
+        let server = TestServer::new(router().with_state(state.clone())).unwrap();
+        let workers: Vec<WorkerInfo> = server.get("/workers").await.json();
+        assert!(workers.is_empty());
+
+        let (worker, mut assignments) = state.workers.register("builder", "0.1.0");
+        let workers: Vec<WorkerInfo> = server.get("/workers").await.json();
+        assert_eq!(workers[0].name, "builder");
+
+        let repo_dir = tempfile::TempDir::new().unwrap();
+        git2::Repository::init(repo_dir.path()).unwrap();
+        let repo = state.db.insert_repo(&repo_dir.path().to_string_lossy(), "app").unwrap();
+        let session = state.db.insert_session(repo.id, None, Orchestrator::Ralph).unwrap();
+
+        let manager = &state.ralph_manager;
+        manager
+            .run_on_worker(
+                session.id,
+                &repo,
+                worker.id,
+                "fix the tests",
+                state.db.clone(),
+                state.connections.clone(),
+            )
+            .await
+            .unwrap();
+        let Some(ToWorker::Run { session_id, command, url, .. }) = assignments.recv().await else {
+            panic!("worker got no run");
+        };
+        assert_eq!((session_id, url), (session.id, None));
+        assert_eq!(command, ["ralph", "run", "--autonomous", "--prompt=fix the tests"]);
+        assert!(manager.is_repo_busy(repo.id).await);
+        assert_eq!(state.db.get_session(session.id).unwrap().status, SessionStatus::Running);
+
+        manager
+            .worker_output(worker.id, session.id, OutputStream::Stdout, "working".to_string(), &state.db, &state.connections)
+            .await;
+        manager
+            .worker_exited(worker.id, session.id, true, state.db.clone(), state.connections.clone())
+            .await;
+        assert!(!manager.is_repo_busy(repo.id).await);
+        assert_eq!(state.db.get_session(session.id).unwrap().status, SessionStatus::Completed);
+        let output = state.db.list_output_logs(session.id, None, None, None).unwrap();
+        assert_eq!(output[0].content, "working");
+
+        // Losing the worker fails what it was running
+        let second = state.db.insert_session(repo.id, None, Orchestrator::Ralph).unwrap();
+        manager
+            .run_on_worker(second.id, &repo, worker.id, "again", state.db.clone(), state.connections.clone())
+            .await
+            .unwrap();
+        state.workers.unregister(worker.id);
+        manager
+            .worker_disconnected(worker.id, state.db.clone(), state.connections.clone())
+            .await;
+        assert_eq!(state.db.get_session(second.id).unwrap().status, SessionStatus::Error);
+        assert!(manager
+            .run_on_worker(second.id, &repo, worker.id, "again", state.db.clone(), state.connections.clone())
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,300 @@
+//! Linked worktree endpoints
+//!
+//! A worktree is a second checkout of a repository on its own branch. Giving
+//! a session one keeps its runs' changes apart from the main working tree and
+//! from other sessions: runs of the session start in the worktree unless they
+//! ask for another directory. Worktrees created here live under `worktrees/`
+//! in the data directory; ones made with `git worktree add` directly are
+//! listed too.
+
+use std::path::{Path, PathBuf};
+
+use axum::{
+    extract::{Path as AxumPath, Query, State},
+    routing::{delete, get},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::db::models::{Repo, Worktree};
+use crate::error::{AppError, AppResult};
+use crate::git::{GitError, GitManager, WorktreeInfo};
+use crate::users::CurrentUser;
+
+use super::git::map_git_error;
+use super::AppState;
+
+/// Request body for creating a worktree
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CreateWorktreeRequest {
+    /// Branch to check out; created if it doesn't exist
+    pub branch: String,
+    /// Branch a new branch starts from (default: HEAD)
+    #[serde(default)]
+    pub base: Option<String>,
+    /// Session whose runs should work in the worktree
+    #[serde(default)]
+    pub session_id: Option<Uuid>,
+}
+
+/// Query parameters for removing a worktree
+#[derive(Debug, Default, Deserialize)]
+pub struct RemoveWorktreeParams {
+    /// Remove it even with uncommitted changes, which are lost
+    #[serde(default)]
+    pub force: bool,
+}
+
+/// A linked worktree as git sees it, with the record of it if it was
+/// created through the API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorktreeEntry {
+    #[serde(flatten)]
+    pub info: WorktreeInfo,
+    pub id: Option<Uuid>,
+    pub session_id: Option<Uuid>,
+}
+
+/// Where new worktrees are checked out, if the server has a data directory
+fn worktrees_dir(state: &AppState) -> AppResult<PathBuf> {
+    state
+        .settings
+        .data_dir
+        .as_deref()
+        .map(|dir| dir.join("worktrees"))
+        .ok_or_else(|| AppError::BadRequest("Worktrees need a data directory".to_string()))
+}
+
+async fn get_repo(state: &AppState, id: Uuid) -> AppResult<Repo> {
+    state.db.call(move |db| db.get_repo(id)).await.map_err(|e| match e {
+        crate::db::DbError::NotFound => AppError::NotFound(format!("Repository not found: {}", id)),
+        _ => AppError::Internal(e.to_string()),
+    })
+}
+
+/// Paths are compared once symlinks are resolved, as git may store either
+fn same_path(a: &Path, b: &Path) -> bool {
+    let canonical = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    canonical(a) == canonical(b)
+}
+
+/// Remove a worktree from disk and forget it
+pub(super) async fn remove(
+    repo: &Repo,
+    worktree: &Worktree,
+    force: bool,
+    state: &AppState,
+) -> AppResult<()> {
+    GitManager::worktree_remove(Path::new(&repo.path), Path::new(&worktree.path), force).map_err(|e| {
+        AppError::Conflict(format!("Failed to remove the worktree at {}: {}", worktree.path, e))
+    })?;
+    let id = worktree.id;
+    state.db.call(move |db| db.delete_worktree(id)).await?;
+    Ok(())
+}
+
+/// GET /api/repos/{id}/worktrees - A repository's linked worktrees
+async fn list_worktrees(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+) -> AppResult<Json<Vec<WorktreeEntry>>> {
+    let repo = get_repo(&state, id).await?;
+    user.check_view(repo.owner_id, "Repository")?;
+
+    let mut records = state.db.call(move |db| db.list_worktrees(id)).await?;
+    let infos = GitManager::worktrees(Path::new(&repo.path))
+        .map_err(|e| AppError::BadRequest(e.to_string()))?;
+    let mut entries: Vec<WorktreeEntry> = infos
+        .into_iter()
+        .map(|info| {
+            let record = records
+                .iter()
+                .position(|record| same_path(Path::new(&record.path), Path::new(&info.path)))
+                .map(|index| records.remove(index));
+            WorktreeEntry {
+                id: record.as_ref().map(|record| record.id),
+                session_id: record.and_then(|record| record.session_id),
+                info,
+            }
+        })
+        .collect();
+
+    // Records git no longer knows about, e.g. pruned by hand
+    entries.extend(records.into_iter().map(|record| WorktreeEntry {
+        info: WorktreeInfo {
+            name: Path::new(&record.path)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            path: record.path,
+            branch: Some(record.branch),
+            head: None,
+            locked: false,
+            prunable: true,
+        },
+        id: Some(record.id),
+        session_id: record.session_id,
+    }));
+    Ok(Json(entries))
+}
+
+/// POST /api/repos/{id}/worktrees - Check out a branch in a new worktree
+async fn create_worktree(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+    Json(req): Json<CreateWorktreeRequest>,
+) -> AppResult<Json<Worktree>> {
+    let repo = get_repo(&state, id).await?;
+    user.check_modify(repo.owner_id, "Repository")?;
+    if let Some(session_id) = req.session_id {
+        let session = state.db.call(move |db| db.get_session(session_id)).await;
+        let session = session.map_err(|e| match e {
+            crate::db::DbError::NotFound => AppError::NotFound(format!("Session not found: {}", session_id)),
+            _ => AppError::Internal(e.to_string()),
+        })?;
+        user.check_modify(session.owner_id, "Session")?;
+        if session.repo_id != id {
+            return Err(AppError::BadRequest(format!(
+                "Session {} belongs to another repository",
+                session_id
+            )));
+        }
+        if state.db.call(move |db| db.get_session_worktree(session_id)).await?.is_some() {
+            return Err(AppError::Conflict("The session already has a worktree".to_string()));
+        }
+    }
+
+    let dir = worktrees_dir(&state)?;
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| AppError::Internal(format!("Failed to create {}: {}", dir.display(), e)))?;
+    let path = dir.join(Uuid::new_v4().to_string());
+    let branch = req.branch.trim();
+    let base = req.base.as_deref().map(str::trim).filter(|base| !base.is_empty());
+    // Git explains why it can't check the branch out, e.g. it already is
+    GitManager::worktree_add(Path::new(&repo.path), &path, branch, base).map_err(|e| match e {
+        GitError::CommandFailed(msg) => AppError::BadRequest(msg),
+        e => map_git_error(e),
+    })?;
+
+    let path = path.to_string_lossy().to_string();
+    let (session_id, branch, record_path) = (req.session_id, branch.to_string(), path.clone());
+    let inserted = state
+        .db
+        .call(move |db| db.insert_worktree(id, session_id, &branch, &record_path))
+        .await;
+    match inserted {
+        Ok(worktree) => Ok(Json(worktree)),
+        Err(e) => {
+            // Don't leave a checkout nothing points at
+            if let Err(e) = GitManager::worktree_remove(Path::new(&repo.path), Path::new(&path), true) {
+                tracing::warn!("Failed to remove the worktree at {}: {}", path, e);
+            }
+            Err(e.into())
+        }
+    }
+}
+
+/// DELETE /api/worktrees/{id} - Remove a worktree; its branch is kept
+async fn delete_worktree(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    AxumPath(id): AxumPath<Uuid>,
+    Query(params): Query<RemoveWorktreeParams>,
+) -> AppResult<Json<()>> {
+    let worktree = state.db.call(move |db| db.get_worktree(id)).await;
+    let worktree = worktree.map_err(|e| match e {
+        crate::db::DbError::NotFound => AppError::NotFound(format!("Worktree not found: {}", id)),
+        _ => AppError::Internal(e.to_string()),
+    })?;
+    let repo = get_repo(&state, worktree.repo_id).await?;
+    user.check_modify(repo.owner_id, "Repository")?;
+
+    if state.ralph_manager.is_repo_busy(repo.id).await {
+        return Err(AppError::Conflict(
+            "Repository has a running process; cancel it before removing a worktree".to_string(),
+        ));
+    }
+    remove(&repo, &worktree, params.force, &state).await?;
+    Ok(Json(()))
+}
+
+/// Create the worktrees router
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/repos/{id}/worktrees", get(list_worktrees).post(create_worktree))
+        .route("/worktrees/{id}", delete(delete_worktree))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::Orchestrator;
+    use crate::db::Database;
+    use crate::settings::Settings;
+    use axum::http::StatusCode;
+    use axum_test::TestServer;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_worktree_lifecycle() {
+        let work = TempDir::new().unwrap();
+        let data = TempDir::new().unwrap();
+        let git = git2::Repository::init(work.path()).unwrap();
+        let sig = git2::Signature::now("Test", "test@example.com").unwrap();
+        let tree = git.find_tree(git.index().unwrap().write_tree().unwrap()).unwrap();
+        git.commit(Some("HEAD"), &sig, &sig, "Initial commit", &tree, &[]).unwrap();
+
+        let db = Database::in_memory().unwrap();
+        let repo = db.insert_repo(&work.path().to_string_lossy(), "repo").unwrap();
+        let session = db.insert_session(repo.id, None, Orchestrator::Ralph).unwrap();
+        let state = AppState::with_settings(
+            db,
+            Settings {
+                data_dir: Some(data.path().to_path_buf()),
+                ..Default::default()
+            },
+        );
+        let server = TestServer::new(router().with_state(state.clone())).unwrap();
+        let url = format!("/repos/{}/worktrees", repo.id);
+
+        let response = server
+            .post(&url)
+            .json(&json!({ "branch": "session-work", "session_id": session.id }))
+            .await;
+        response.assert_status_ok();
+        let worktree: Worktree = response.json();
+        assert!(worktree.path.starts_with(&*data.path().to_string_lossy()));
+        assert!(Path::new(&worktree.path).join(".git").exists());
+        assert_eq!(state.db.get_session_worktree(session.id).unwrap().unwrap().id, worktree.id);
+
+        server
+            .post(&url)
+            .json(&json!({ "branch": "second", "session_id": session.id }))
+            .await
+            .assert_status(StatusCode::CONFLICT);
+        server
+            .post(&url)
+            .json(&json!({ "branch": "-bad" }))
+            .await
+            .assert_status_bad_request();
+
+        let entries: Vec<WorktreeEntry> = server.get(&url).await.json();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].id, Some(worktree.id));
+        assert_eq!(entries[0].session_id, Some(session.id));
+        assert_eq!(entries[0].info.branch.as_deref(), Some("session-work"));
+
+        // Uncommitted work is only thrown away when forced
+        std::fs::write(Path::new(&worktree.path).join("wip.txt"), "wip").unwrap();
+        let removal = format!("/worktrees/{}", worktree.id);
+        server.delete(&removal).await.assert_status(StatusCode::CONFLICT);
+        server.delete(&format!("{}?force=true", removal)).await.assert_status_ok();
+        assert!(!Path::new(&worktree.path).exists());
+        assert!(server.get(&url).await.json::<Vec<WorktreeEntry>>().is_empty());
+        assert!(git.find_branch("session-work", git2::BranchType::Local).is_ok());
+    }
+}
@@ -0,0 +1,226 @@
+//! Run artifacts
+//!
+//! Besides code changes, a run may leave deliverables in the working tree:
+//! reports, screenshots, exported data. After each run, files matching the
+//! `artifact_patterns` globs that were written while it ran are recorded as
+//! the session's artifacts so they can be listed and downloaded.
+//!
+//! Patterns are matched against paths relative to the repository root, using
+//! `/` as the separator: `*` and `?` match within one path component, `**`
+//! matches any number of components.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::SystemTime;
+
+use regex::Regex;
+use uuid::Uuid;
+
+use crate::db::models::Artifact;
+use crate::db::{Database, DbResult};
+
+/// Patterns used when `artifact_patterns` is not set
+pub const DEFAULT_PATTERNS: &[&str] = &[
+    "**/*.pdf",
+    "**/*.png",
+    "**/*.jpg",
+    "**/*.jpeg",
+    "**/*.gif",
+    "**/*.webp",
+    "**/*.csv",
+    "**/*.xlsx",
+    "reports/**",
+    "artifacts/**",
+    "screenshots/**",
+];
+
+/// Directories never searched for artifacts
+const SKIPPED_DIRS: &[&str] = &[".git", "node_modules", "target"];
+
+/// Most artifacts recorded for a single run
+pub const MAX_ARTIFACTS_PER_RUN: usize = 100;
+
+/// Compiled artifact glob patterns
+#[derive(Debug, Clone)]
+pub struct ArtifactPatterns {
+    patterns: Vec<Regex>,
+}
+
+impl Default for ArtifactPatterns {
+    fn default() -> Self {
+        Self::parse(DEFAULT_PATTERNS).expect("default artifact patterns are valid")
+    }
+}
+
+impl ArtifactPatterns {
+    /// Compile glob patterns
+    pub fn parse<S: AsRef<str>>(patterns: &[S]) -> Result<Self, String> {
+        let patterns = patterns
+            .iter()
+            .map(|pattern| glob_to_regex(pattern.as_ref()))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { patterns })
+    }
+
+    /// Whether no pattern is configured, which turns detection off
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Whether a repository-relative path matches any pattern
+    pub fn matches(&self, path: &str) -> bool {
+        self.patterns.iter().any(|pattern| pattern.is_match(path))
+    }
+}
+
+/// Translate a glob into an anchored regex
+fn glob_to_regex(glob: &str) -> Result<Regex, String> {
+    let glob = glob.trim().trim_start_matches("./");
+    if glob.is_empty() || glob.starts_with('/') || glob.split('/').any(|part| part == "..") {
+        return Err(format!("'{}' is not a relative path pattern", glob));
+    }
+
+    let mut regex = String::from("^");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    regex.push_str("(?:.*/)?");
+                } else {
+                    regex.push_str(".*");
+                }
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+
+    Regex::new(&regex).map_err(|e| format!("'{}' is not a valid pattern: {}", glob, e))
+}
+
+/// A file found by [`detect`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FoundArtifact {
+    /// Path relative to the repository root, with `/` separators
+    pub path: String,
+    pub size_bytes: u64,
+}
+
+/// Find files under `repo_path` matching `patterns` that were modified at or
+/// after `since`, sorted by path
+pub fn detect(repo_path: &Path, patterns: &ArtifactPatterns, since: SystemTime) -> io::Result<Vec<FoundArtifact>> {
+    let mut found = Vec::new();
+    if !patterns.is_empty() {
+        walk(repo_path, "", patterns, since, &mut found)?;
+    }
+    found.sort_by(|a, b| a.path.cmp(&b.path));
+    found.truncate(MAX_ARTIFACTS_PER_RUN);
+    Ok(found)
+}
+
+fn walk(
+    dir: &Path,
+    prefix: &str,
+    patterns: &ArtifactPatterns,
+    since: SystemTime,
+    found: &mut Vec<FoundArtifact>,
+) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let Some(name) = entry.file_name().to_str().map(String::from) else {
+            continue;
+        };
+        let path = format!("{}{}", prefix, name);
+
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            if !SKIPPED_DIRS.contains(&name.as_str()) {
+                walk(&entry.path(), &format!("{}/", path), patterns, since, found)?;
+            }
+        } else if file_type.is_file() && patterns.matches(&path) {
+            let metadata = entry.metadata()?;
+            if metadata.modified()? >= since {
+                found.push(FoundArtifact {
+                    path,
+                    size_bytes: metadata.len(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Detect a finished run's artifacts and record them for its session
+pub fn record(
+    db: &Database,
+    session_id: Uuid,
+    repo_id: Uuid,
+    repo_path: &Path,
+    patterns: &ArtifactPatterns,
+    since: SystemTime,
+) -> DbResult<Vec<Artifact>> {
+    let found = match detect(repo_path, patterns, since) {
+        Ok(found) => found,
+        Err(e) => {
+            tracing::warn!("Failed to look for artifacts in {}: {}", repo_path.display(), e);
+            return Ok(Vec::new());
+        }
+    };
+
+    found
+        .into_iter()
+        .map(|artifact| db.record_artifact(session_id, repo_id, &artifact.path, artifact.size_bytes))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_patterns() {
+        let patterns = ArtifactPatterns::parse(&["**/*.png", "reports/**", "out?.txt"]).unwrap();
+        assert!(patterns.matches("shot.png"));
+        assert!(patterns.matches("docs/img/shot.png"));
+        assert!(patterns.matches("reports/2026/summary.md"));
+        assert!(patterns.matches("out1.txt"));
+        assert!(!patterns.matches("src/main.rs"));
+        assert!(!patterns.matches("out12.txt"));
+        assert!(!patterns.matches("old/reports/summary.md"));
+
+        assert!(ArtifactPatterns::parse(&["../secrets/*"]).is_err());
+        assert!(ArtifactPatterns::parse(&["/etc/*"]).is_err());
+        assert!(ArtifactPatterns::default().matches("screenshots/home.html"));
+    }
+
+    #[test]
+    fn test_detect_new_files() {
+        let repo = TempDir::new().unwrap();
+        fs::create_dir_all(repo.path().join("reports")).unwrap();
+        fs::create_dir_all(repo.path().join("node_modules/pkg")).unwrap();
+        fs::write(repo.path().join("old.png"), "old").unwrap();
+
+        let since = SystemTime::now() + Duration::from_millis(10);
+        std::thread::sleep(Duration::from_millis(20));
+        fs::write(repo.path().join("reports/summary.md"), "# Done").unwrap();
+        fs::write(repo.path().join("node_modules/pkg/logo.png"), "png").unwrap();
+        fs::write(repo.path().join("main.rs"), "fn main() {}").unwrap();
+
+        let found = detect(repo.path(), &ArtifactPatterns::default(), since).unwrap();
+        assert_eq!(
+            found,
+            vec![FoundArtifact {
+                path: "reports/summary.md".to_string(),
+                size_bytes: 6,
+            }]
+        );
+    }
+}
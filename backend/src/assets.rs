@@ -1,23 +1,144 @@
+//! Embedded frontend
+//!
+//! The built UI from `frontend/dist` is compiled into the binary when the
+//! build script finds it, and served for every path that isn't an API route.
+//! With `--dev-frontend` those paths are proxied to a frontend dev server
+//! instead, so UI changes show up without rebuilding the backend.
+//!
+//! Embedded files carry a content-hash `ETag`, so reloads are answered with
+//! `304 Not Modified`, and single byte ranges are served with `206`.
+
+use std::sync::OnceLock;
+
 use axum::{
     body::Body,
-    http::{header, Request, Response, StatusCode},
+    extract::State,
+    http::{header, HeaderMap, HeaderName, Request, Response, StatusCode},
     response::IntoResponse,
 };
+#[cfg(frontend_embedded)]
 use rust_embed::Embed;
+use sha2::{Digest, Sha256};
+
+use crate::api::AppState;
+use crate::error::AppError;
+
+/// Largest request body forwarded to the dev server
+const MAX_PROXY_BODY: usize = 10 * 1024 * 1024;
 
+/// Headers that apply to a single connection and must not be forwarded
+const HOP_BY_HOP_HEADERS: [&str; 8] = [
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+#[cfg(frontend_embedded)]
 #[derive(Embed)]
 #[folder = "../frontend/dist"]
 struct FrontendAssets;
 
-/// Serve embedded frontend assets or fall back to index.html for SPA routing
-pub async fn serve_frontend(req: Request<Body>) -> impl IntoResponse {
+/// Stand-in used when the frontend wasn't built before compiling
+#[cfg(not(frontend_embedded))]
+struct FrontendAssets;
+
+#[cfg(not(frontend_embedded))]
+impl FrontendAssets {
+    fn get(_path: &str) -> Option<rust_embed::EmbeddedFile> {
+        None
+    }
+}
+
+/// Serve the frontend for any path not handled by the API
+pub async fn serve_frontend(State(state): State<AppState>, req: Request<Body>) -> Response<Body> {
     let path = req.uri().path();
 
+    // Unknown API routes get a JSON 404 rather than the SPA shell
+    if path == "/api" || path.starts_with("/api/") {
+        return AppError::NotFound(format!("No API route for {}", path)).into_response();
+    }
+
+    match &state.settings.dev_frontend {
+        Some(dev_frontend) => proxy_to_dev_server(dev_frontend, req).await,
+        None => serve_embedded(path, req.headers(), &state.settings.base_path),
+    }
+}
+
+/// Forward a request to the frontend dev server and stream back its response
+async fn proxy_to_dev_server(base: &str, req: Request<Body>) -> Response<Body> {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    let client = CLIENT.get_or_init(reqwest::Client::new);
+
+    let path_and_query = req
+        .uri()
+        .path_and_query()
+        .map(|p| p.as_str())
+        .unwrap_or("/");
+    let url = format!("{}{}", base, path_and_query);
+
+    let (parts, body) = req.into_parts();
+    let body = match axum::body::to_bytes(body, MAX_PROXY_BODY).await {
+        Ok(body) => body,
+        Err(e) => {
+            return AppError::BadRequest(format!("Failed to read request body: {}", e))
+                .into_response();
+        }
+    };
+
+    let mut headers = strip_hop_by_hop(&parts.headers);
+    headers.remove(header::HOST);
+
+    let upstream = match client
+        .request(parts.method, &url)
+        .headers(headers)
+        .body(body)
+        .send()
+        .await
+    {
+        Ok(upstream) => upstream,
+        Err(e) => {
+            tracing::warn!("Frontend dev server request failed: {}", e);
+            return Response::builder()
+                .status(StatusCode::BAD_GATEWAY)
+                .header(header::CONTENT_TYPE, "text/plain")
+                .body(Body::from(format!(
+                    "Frontend dev server unavailable at {}: {}",
+                    base, e
+                )))
+                .unwrap();
+        }
+    };
+
+    let mut response = Response::builder().status(upstream.status());
+    if let Some(headers) = response.headers_mut() {
+        *headers = strip_hop_by_hop(upstream.headers());
+    }
+    response
+        .body(Body::from_stream(upstream.bytes_stream()))
+        .unwrap()
+}
+
+/// Copy headers, leaving out connection-specific ones
+fn strip_hop_by_hop(headers: &HeaderMap) -> HeaderMap {
+    headers
+        .iter()
+        .filter(|(name, _)| !HOP_BY_HOP_HEADERS.contains(&name.as_str()))
+        .map(|(name, value)| (HeaderName::clone(name), value.clone()))
+        .collect()
+}
+
+/// Serve embedded frontend assets or fall back to index.html for SPA routing
+fn serve_embedded(path: &str, headers: &HeaderMap, base_path: &str) -> Response<Body> {
     // Remove leading slash
     let path = path.trim_start_matches('/');
 
-    // Try to serve the exact file
-    if let Some(content) = FrontendAssets::get(path) {
+    // Try to serve the exact file (index.html always goes through the rewrite below)
+    if let Some(content) = FrontendAssets::get(path).filter(|_| path != "index.html") {
         let mime = mime_guess::from_path(path).first_or_octet_stream();
 
         // Add cache headers for static assets (CSS, JS, etc.)
@@ -27,27 +148,353 @@ pub async fn serve_frontend(req: Request<Body>) -> impl IntoResponse {
             "no-cache"
         };
 
-        return Response::builder()
-            .status(StatusCode::OK)
-            .header(header::CONTENT_TYPE, mime.as_ref())
-            .header(header::CACHE_CONTROL, cache_control)
-            .body(Body::from(content.data.into_owned()))
-            .unwrap();
+        let etag = etag_for(&content.metadata.sha256_hash());
+        return file_response(
+            headers,
+            content.data.into_owned(),
+            &etag,
+            mime.as_ref(),
+            cache_control,
+        );
     }
 
     // For SPA: fall back to index.html for any non-asset path
     if let Some(content) = FrontendAssets::get("index.html") {
-        return Response::builder()
-            .status(StatusCode::OK)
-            .header(header::CONTENT_TYPE, "text/html")
-            .header(header::CACHE_CONTROL, "no-cache")
-            .body(Body::from(content.data.into_owned()))
-            .unwrap();
+        let html = with_base_path(&String::from_utf8_lossy(&content.data), base_path);
+        // Hash the rewritten page so the ETag changes along with the base path
+        let etag = etag_for(&Sha256::digest(html.as_bytes()));
+        return file_response(headers, html.into_bytes(), &etag, "text/html", "no-cache");
     }
 
     // No frontend assets embedded
     Response::builder()
         .status(StatusCode::NOT_FOUND)
-        .body(Body::from("Frontend not found"))
+        .header(header::CONTENT_TYPE, "text/plain")
+        .body(Body::from(
+            "Frontend not found: this binary was built without frontend/dist. \
+             Run `npm run build` in frontend/ and rebuild.",
+        ))
         .unwrap()
 }
+
+/// Format a content hash as a strong ETag
+fn etag_for(hash: &[u8]) -> String {
+    let hex: String = hash.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("\"{}\"", hex)
+}
+
+/// Build the response for an embedded file, honoring conditional and range requests
+fn file_response(
+    headers: &HeaderMap,
+    data: Vec<u8>,
+    etag: &str,
+    content_type: &str,
+    cache_control: &str,
+) -> Response<Body> {
+    let builder = Response::builder()
+        .header(header::ETAG, etag)
+        .header(header::CACHE_CONTROL, cache_control)
+        .header(header::ACCEPT_RANGES, "bytes");
+
+    if headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| etag_matches(value, etag))
+    {
+        return builder
+            .status(StatusCode::NOT_MODIFIED)
+            .body(Body::empty())
+            .unwrap();
+    }
+
+    let builder = builder.header(header::CONTENT_TYPE, content_type);
+
+    // A Range is only honored if the client's copy (If-Range) is still current
+    let if_range_current = headers
+        .get(header::IF_RANGE)
+        .is_none_or(|value| value.as_bytes() == etag.as_bytes());
+    let range = headers
+        .get(header::RANGE)
+        .and_then(|value| value.to_str().ok())
+        .filter(|_| if_range_current)
+        .map_or(ByteRange::Full, |value| parse_range(value, data.len()));
+
+    match range {
+        ByteRange::Full => builder.status(StatusCode::OK).body(Body::from(data)),
+        ByteRange::Partial { start, end } => builder
+            .status(StatusCode::PARTIAL_CONTENT)
+            .header(
+                header::CONTENT_RANGE,
+                format!("bytes {}-{}/{}", start, end, data.len()),
+            )
+            .body(Body::from(data[start..=end].to_vec())),
+        ByteRange::Unsatisfiable => builder
+            .status(StatusCode::RANGE_NOT_SATISFIABLE)
+            .header(header::CONTENT_RANGE, format!("bytes */{}", data.len()))
+            .body(Body::empty()),
+    }
+    .unwrap()
+}
+
+/// Whether an `If-None-Match` value matches the current ETag
+fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    if_none_match.split(',').map(str::trim).any(|candidate| {
+        candidate == "*" || candidate.strip_prefix("W/").unwrap_or(candidate) == etag
+    })
+}
+
+/// Which part of a file a `Range` header asks for
+#[derive(Debug, PartialEq, Eq)]
+enum ByteRange {
+    /// No usable range; send the whole file
+    Full,
+    /// Inclusive byte range
+    Partial { start: usize, end: usize },
+    /// The range lies outside the file
+    Unsatisfiable,
+}
+
+/// Parse a single `bytes=` range against a file of `len` bytes
+///
+/// Multiple ranges and malformed headers are ignored, which per RFC 9110
+/// means answering with the full file.
+fn parse_range(value: &str, len: usize) -> ByteRange {
+    let Some(spec) = value.trim().strip_prefix("bytes=") else {
+        return ByteRange::Full;
+    };
+    let Some((start, end)) = spec.split_once('-').filter(|_| !spec.contains(',')) else {
+        return ByteRange::Full;
+    };
+    let (start, end) = (start.trim(), end.trim());
+
+    // Suffix range: the last N bytes
+    if start.is_empty() {
+        return match end.parse::<usize>() {
+            Ok(0) => ByteRange::Unsatisfiable,
+            Ok(_) if len == 0 => ByteRange::Unsatisfiable,
+            Ok(suffix) => ByteRange::Partial {
+                start: len.saturating_sub(suffix),
+                end: len - 1,
+            },
+            Err(_) => ByteRange::Full,
+        };
+    }
+
+    let Ok(start) = start.parse::<usize>() else {
+        return ByteRange::Full;
+    };
+    if start >= len {
+        return ByteRange::Unsatisfiable;
+    }
+    let end = if end.is_empty() {
+        len - 1
+    } else {
+        match end.parse::<usize>() {
+            Ok(end) if end >= start => end.min(len - 1),
+            _ => return ByteRange::Full,
+        }
+    };
+    ByteRange::Partial { start, end }
+}
+
+/// Global the UI reads its base path from, to build API, WebSocket and router URLs
+const BASE_PATH_GLOBAL: &str = "__RALPHTOWN_BASE_PATH__";
+
+/// Prefix root-relative `src` and `href` URLs in the SPA shell with the base path,
+/// and publish it to the UI's scripts ahead of the bundle in `<head>`
+fn with_base_path(html: &str, base_path: &str) -> String {
+    if base_path.is_empty() {
+        return html.to_string();
+    }
+
+    let script = format!(
+        "<script>window.{} = {};</script>",
+        BASE_PATH_GLOBAL,
+        serde_json::Value::from(base_path)
+    );
+    let html = match html.find("<head>") {
+        Some(i) => format!("{}{}{}", &html[..i + 6], script, &html[i + 6..]),
+        None => html.to_string(),
+    };
+
+    let mut out = String::with_capacity(html.len() + base_path.len() * 4);
+    let mut rest = html.as_str();
+    while let Some(i) = rest.find("=\"/") {
+        let (head, tail) = rest.split_at(i + 2);
+        out.push_str(head);
+        let is_url_attr = head.ends_with(" src=\"") || head.ends_with(" href=\"");
+        if is_url_attr && !tail.starts_with("//") {
+            out.push_str(base_path);
+        }
+        rest = tail;
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::AppState;
+    use crate::db::Database;
+    use crate::settings::Settings;
+    use crate::{create_app, create_test_app};
+    use axum::{extract::OriginalUri, http::StatusCode, routing::any, Router};
+    use axum_test::TestServer;
+
+    fn create_proxy_server(dev_frontend: &str) -> TestServer {
+        let db = Database::in_memory().expect("Failed to create test database");
+        let settings = Settings {
+            dev_frontend: Some(dev_frontend.to_string()),
+            ..Settings::default()
+        };
+        TestServer::new(create_app(AppState::with_settings(db, settings))).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_unknown_api_route_is_json_404() {
+        let server = TestServer::new(create_test_app()).unwrap();
+
+        let response = server.get("/api/does-not-exist").await;
+        response.assert_status(StatusCode::NOT_FOUND);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["error"]["code"], "NOT_FOUND");
+    }
+
+    #[tokio::test]
+    async fn test_api_routes_take_precedence() {
+        let server = TestServer::new(create_test_app()).unwrap();
+
+        server.get("/api/health").await.assert_status_ok();
+    }
+
+    #[test]
+    fn test_with_base_path_rewrites_root_relative_urls() {
+        let html = r#"<link rel="icon" href="/favicon.ico"><script src="/assets/app.js"></script><a href="//cdn.example.com/x" data-x="/keep">"#;
+
+        assert_eq!(
+            with_base_path(html, "/ralphtown"),
+            r#"<link rel="icon" href="/ralphtown/favicon.ico"><script src="/ralphtown/assets/app.js"></script><a href="//cdn.example.com/x" data-x="/keep">"#
+        );
+        assert_eq!(with_base_path(html, ""), html);
+    }
+
+    #[test]
+    fn test_with_base_path_publishes_base_path_to_scripts() {
+        let html = r#"<html><head><script type="module" src="/assets/app.js"></script></head></html>"#;
+
+        assert_eq!(
+            with_base_path(html, "/ralphtown"),
+            r#"<html><head><script>window.__RALPHTOWN_BASE_PATH__ = "/ralphtown";</script><script type="module" src="/ralphtown/assets/app.js"></script></head></html>"#
+        );
+    }
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range("bytes=0-9", 100), ByteRange::Partial { start: 0, end: 9 });
+        assert_eq!(parse_range("bytes=90-", 100), ByteRange::Partial { start: 90, end: 99 });
+        assert_eq!(parse_range("bytes=-10", 100), ByteRange::Partial { start: 90, end: 99 });
+        assert_eq!(parse_range("bytes=50-500", 100), ByteRange::Partial { start: 50, end: 99 });
+        assert_eq!(parse_range("bytes=100-", 100), ByteRange::Unsatisfiable);
+        assert_eq!(parse_range("bytes=-0", 100), ByteRange::Unsatisfiable);
+        assert_eq!(parse_range("bytes=0-1,5-6", 100), ByteRange::Full);
+        assert_eq!(parse_range("bytes=9-1", 100), ByteRange::Full);
+        assert_eq!(parse_range("items=0-1", 100), ByteRange::Full);
+    }
+
+    fn headers(pairs: &[(header::HeaderName, &str)]) -> HeaderMap {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.clone(), value.parse().unwrap()))
+            .collect()
+    }
+
+    async fn body_bytes(response: Response<Body>) -> Vec<u8> {
+        axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap()
+            .to_vec()
+    }
+
+    #[tokio::test]
+    async fn test_file_response_conditional_get() {
+        let etag = etag_for(&Sha256::digest(b"hello"));
+
+        let response =
+            file_response(&HeaderMap::new(), b"hello".to_vec(), &etag, "text/plain", "no-cache");
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::ETAG], etag.as_str());
+
+        let request = headers(&[(header::IF_NONE_MATCH, &format!("\"other\", W/{}", etag))]);
+        let response = file_response(&request, b"hello".to_vec(), &etag, "text/plain", "no-cache");
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert!(body_bytes(response).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_file_response_ranges() {
+        let etag = etag_for(&Sha256::digest(b"hello world"));
+        let data = b"hello world".to_vec();
+
+        let request = headers(&[(header::RANGE, "bytes=6-")]);
+        let response = file_response(&request, data.clone(), &etag, "text/plain", "no-cache");
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(response.headers()[header::CONTENT_RANGE], "bytes 6-10/11");
+        assert_eq!(body_bytes(response).await, b"world");
+
+        let request = headers(&[(header::RANGE, "bytes=20-")]);
+        let response = file_response(&request, data.clone(), &etag, "text/plain", "no-cache");
+        assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+
+        // A stale If-Range gets the whole file
+        let request = headers(&[(header::RANGE, "bytes=6-"), (header::IF_RANGE, "\"stale\"")]);
+        let response = file_response(&request, data, &etag, "text/plain", "no-cache");
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_proxies_to_dev_server() {
+        let dev_server = Router::new().fallback(any(|uri: OriginalUri| async move {
+            ([("x-dev-server", "vite")], format!("dev: {}", uri.0))
+        }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, dev_server).await });
+
+        let server = create_proxy_server(&format!("http://{}", addr));
+
+        let response = server.get("/src/main.tsx?t=1").await;
+        response.assert_status_ok();
+        response.assert_header("x-dev-server", "vite");
+        response.assert_text("dev: /src/main.tsx?t=1");
+
+        // The API is still served by the backend
+        server.get("/api/health").await.assert_status_ok();
+        server
+            .get("/api/does-not-exist")
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_dev_server_is_bad_gateway() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+
+        let server = create_proxy_server(&format!("http://{}", addr));
+
+        server.get("/").await.assert_status(StatusCode::BAD_GATEWAY);
+    }
+
+    #[cfg(frontend_embedded)]
+    #[tokio::test]
+    async fn test_spa_routes_serve_index() {
+        let server = TestServer::new(create_test_app()).unwrap();
+
+        let response = server.get("/sessions/123").await;
+        response.assert_status_ok();
+        let content_type = response.header("content-type");
+        assert!(content_type.to_str().unwrap().starts_with("text/html"));
+    }
+}
@@ -0,0 +1,166 @@
+//! Files attached to a session
+//!
+//! Specs, design docs and logs a session's runs should see are attached to
+//! the session once and kept in `attachments/<session_id>/<id>/<filename>` in
+//! the data directory. Before each local run they're copied into a fresh
+//! context directory in the system's temp directory, whose path is given to
+//! the agent in its prompt and as `RALPHTOWN_CONTEXT_DIR`. Copies rather than
+//! the stored files are handed out so a run can't change what the next one
+//! sees; the context directory is removed when the run ends.
+
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use uuid::Uuid;
+
+use crate::db::models::Attachment;
+
+/// Environment variable giving a run its context directory
+pub const CONTEXT_DIR_ENV: &str = "RALPHTOWN_CONTEXT_DIR";
+
+/// Attached files under a directory, one subdirectory per session
+#[derive(Debug, Clone)]
+pub struct AttachmentStore {
+    root: PathBuf,
+    /// Where context directories are made for runs
+    context_root: PathBuf,
+}
+
+impl AttachmentStore {
+    pub fn new(root: PathBuf, context_root: PathBuf) -> Self {
+        Self { root, context_root }
+    }
+
+    /// Store attachments in the `attachments` directory of the server's data
+    /// directory, with run context directories in the system temp directory
+    pub fn in_data_dir(data_dir: &Path) -> Self {
+        Self::new(
+            data_dir.join("attachments"),
+            std::env::temp_dir().join("ralphtown-context"),
+        )
+    }
+
+    /// Directory holding an attachment's file
+    pub fn dir(&self, session_id: Uuid, id: Uuid) -> PathBuf {
+        self.root.join(session_id.to_string()).join(id.to_string())
+    }
+
+    /// Where an attachment's file is stored
+    pub fn path(&self, attachment: &Attachment) -> PathBuf {
+        self.dir(attachment.session_id, attachment.id).join(&attachment.filename)
+    }
+
+    /// Delete an attachment's file
+    pub fn remove(&self, attachment: &Attachment) -> io::Result<()> {
+        remove_dir(&self.dir(attachment.session_id, attachment.id))
+    }
+
+    /// Delete the files of all of a session's attachments
+    pub fn remove_session(&self, session_id: Uuid) -> io::Result<()> {
+        remove_dir(&self.root.join(session_id.to_string()))
+    }
+
+    /// Context directory for a session's runs
+    pub fn context_dir(&self, session_id: Uuid) -> PathBuf {
+        self.context_root.join(session_id.to_string())
+    }
+
+    /// Copy attachments into a fresh context directory for a session's run
+    ///
+    /// Anything a previous run left there is cleared first. Attachments
+    /// sharing a file name are told apart by a numeric suffix.
+    pub fn materialize(&self, session_id: Uuid, attachments: &[Attachment]) -> io::Result<PathBuf> {
+        let dir = self.context_dir(session_id);
+        remove_dir(&dir)?;
+        fs::create_dir_all(&dir)?;
+
+        let mut taken = HashSet::new();
+        for attachment in attachments {
+            let name = unique_name(&attachment.filename, &mut taken);
+            fs::copy(self.path(attachment), dir.join(name))?;
+        }
+        Ok(dir)
+    }
+
+    /// Remove a session's context directory once its run is over
+    pub fn discard_context(&self, session_id: Uuid) {
+        if let Err(e) = remove_dir(&self.context_dir(session_id)) {
+            tracing::warn!("Failed to remove the context directory of {}: {}", session_id, e);
+        }
+    }
+}
+
+/// Remove a directory and everything in it, if it exists
+fn remove_dir(dir: &Path) -> io::Result<()> {
+    match fs::remove_dir_all(dir) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// `filename`, or `stem-2.ext`, `stem-3.ext`, ... if it's already taken
+fn unique_name(filename: &str, taken: &mut HashSet<String>) -> String {
+    if taken.insert(filename.to_string()) {
+        return filename.to_string();
+    }
+    let (stem, ext) = match filename.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => (stem, format!(".{}", ext)),
+        _ => (filename, String::new()),
+    };
+    (2..)
+        .map(|n| format!("{}-{}{}", stem, n, ext))
+        .find(|name| taken.insert(name.clone()))
+        .expect("some suffix is free")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use tempfile::TempDir;
+
+    fn attach(store: &AttachmentStore, session_id: Uuid, filename: &str, contents: &str) -> Attachment {
+        let attachment = Attachment {
+            id: Uuid::new_v4(),
+            session_id,
+            filename: filename.to_string(),
+            content_type: None,
+            size_bytes: contents.len() as u64,
+            created_at: Utc::now(),
+        };
+        fs::create_dir_all(store.dir(session_id, attachment.id)).unwrap();
+        fs::write(store.path(&attachment), contents).unwrap();
+        attachment
+    }
+
+    #[test]
+    fn test_materialize_context_dir() {
+        let data = TempDir::new().unwrap();
+        let temp = TempDir::new().unwrap();
+        let store = AttachmentStore::new(data.path().join("attachments"), temp.path().to_path_buf());
+        let session_id = Uuid::new_v4();
+        let attachments = vec![
+            attach(&store, session_id, "spec.md", "one"),
+            attach(&store, session_id, "spec.md", "two"),
+            attach(&store, session_id, "build.log", "three"),
+        ];
+
+        let dir = store.materialize(session_id, &attachments).unwrap();
+        assert_eq!(dir, store.context_dir(session_id));
+        assert_eq!(fs::read_to_string(dir.join("spec.md")).unwrap(), "one");
+        assert_eq!(fs::read_to_string(dir.join("spec-2.md")).unwrap(), "two");
+        assert_eq!(fs::read_to_string(dir.join("build.log")).unwrap(), "three");
+
+        // A later run starts from a clean directory
+        fs::write(dir.join("notes.txt"), "scratch").unwrap();
+        let dir = store.materialize(session_id, &attachments[2..]).unwrap();
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        store.discard_context(session_id);
+        assert!(!dir.exists());
+        store.remove_session(session_id).unwrap();
+        assert!(!store.path(&attachments[0]).exists());
+    }
+}
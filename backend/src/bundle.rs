@@ -0,0 +1,226 @@
+//! Shareable session bundles
+//!
+//! A bundle is a single file holding everything needed to look at or rerun a
+//! session on another instance: its configuration, the prompts and other
+//! messages, the output transcript, and a summary of what it changed. The file
+//! is gzip-compressed and starts with a signature line followed by the bundle
+//! as JSON.
+//!
+//! With `bundle_signing_key` set, bundles are signed with an HMAC-SHA256 of the
+//! JSON keyed by it, and only bundles signed with the same key are imported, so
+//! instances sharing the key can trust each other's bundles. Without a key the
+//! signature is a plain SHA-256 digest that only detects corruption, and
+//! bundles signed with a key are refused since they can't be checked.
+
+use std::io::{Read, Write};
+
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use hmac::{Hmac, KeyInit, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::api::compare::ChangeSummary;
+use crate::db::models::{Message, OutputLog, Session};
+use crate::middleware::auth::constant_time_eq;
+
+/// Bundle format written by this version
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Largest bundle accepted once decompressed
+pub const MAX_BUNDLE_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Signature prefix of a keyed bundle
+const HMAC_PREFIX: &str = "hmac-sha256=";
+/// Signature prefix of an unkeyed bundle
+const DIGEST_PREFIX: &str = "sha256=";
+
+/// Errors reading a bundle
+#[derive(Debug, Error)]
+pub enum BundleError {
+    #[error("Not a session bundle: {0}")]
+    Malformed(String),
+
+    #[error("Bundle is larger than {} MB", MAX_BUNDLE_BYTES / (1024 * 1024))]
+    TooLarge,
+
+    #[error("Bundle format {0} is not supported by this version")]
+    UnsupportedFormat(u32),
+
+    #[error("Bundle signature does not match")]
+    BadSignature,
+
+    #[error("Bundle is signed with a key; set bundle_signing_key to the same key to import it")]
+    KeyRequired,
+}
+
+/// A session as shared between instances
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Bundle {
+    pub format: u32,
+    /// Ralphtown version that wrote the bundle
+    pub version: String,
+    pub exported_at: DateTime<Utc>,
+    /// Name of the repository the session ran in
+    pub repo_name: String,
+    /// Origin of that repository, when it has one
+    pub repo_url: Option<String>,
+    pub session: Session,
+    /// Messages, oldest first; user messages are the prompts
+    pub messages: Vec<Message>,
+    /// Output transcript, oldest first
+    pub output: Vec<OutputLog>,
+    /// What the session changed, when the repository could be read
+    pub changes: Option<ChangeSummary>,
+}
+
+/// Signature of `payload`, keyed when `key` is set
+fn sign(key: Option<&str>, payload: &[u8]) -> String {
+    let bytes = match key {
+        Some(key) => {
+            let mut mac =
+                Hmac::<Sha256>::new_from_slice(key.as_bytes()).expect("HMAC accepts keys of any length");
+            mac.update(payload);
+            mac.finalize().into_bytes().to_vec()
+        }
+        None => Sha256::digest(payload).to_vec(),
+    };
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    match key {
+        Some(_) => format!("{}{}", HMAC_PREFIX, hex),
+        None => format!("{}{}", DIGEST_PREFIX, hex),
+    }
+}
+
+/// Write a signed, compressed bundle
+pub fn encode(bundle: &Bundle, key: Option<&str>) -> std::io::Result<Vec<u8>> {
+    let payload = serde_json::to_vec(bundle)?;
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(sign(key, &payload).as_bytes())?;
+    encoder.write_all(b"\n")?;
+    encoder.write_all(&payload)?;
+    encoder.finish()
+}
+
+/// Read a bundle, checking its signature
+pub fn decode(data: &[u8], key: Option<&str>) -> Result<Bundle, BundleError> {
+    let mut contents = Vec::new();
+    GzDecoder::new(data)
+        .take(MAX_BUNDLE_BYTES + 1)
+        .read_to_end(&mut contents)
+        .map_err(|e| BundleError::Malformed(e.to_string()))?;
+    if contents.len() as u64 > MAX_BUNDLE_BYTES {
+        return Err(BundleError::TooLarge);
+    }
+
+    let newline = contents
+        .iter()
+        .position(|b| *b == b'\n')
+        .ok_or_else(|| BundleError::Malformed("missing signature".to_string()))?;
+    let (signature, payload) = (&contents[..newline], &contents[newline + 1..]);
+    let signature = std::str::from_utf8(signature)
+        .map_err(|_| BundleError::Malformed("invalid signature".to_string()))?;
+
+    let expected = match key {
+        Some(_) if signature.starts_with(DIGEST_PREFIX) => return Err(BundleError::BadSignature),
+        None if signature.starts_with(HMAC_PREFIX) => return Err(BundleError::KeyRequired),
+        _ => sign(key, payload),
+    };
+    if !constant_time_eq(expected.as_bytes(), signature.as_bytes()) {
+        return Err(BundleError::BadSignature);
+    }
+
+    let bundle: Bundle =
+        serde_json::from_slice(payload).map_err(|e| BundleError::Malformed(e.to_string()))?;
+    if bundle.format > FORMAT_VERSION {
+        return Err(BundleError::UnsupportedFormat(bundle.format));
+    }
+    Ok(bundle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::{MessageKind, MessageRole, Orchestrator, OutputChannel, OutputStream, SessionStatus};
+    use uuid::Uuid;
+
+    fn bundle() -> Bundle {
+        let now = Utc::now();
+        let session_id = Uuid::new_v4();
+        Bundle {
+            format: FORMAT_VERSION,
+            version: "0.1.0".to_string(),
+            exported_at: now,
+            repo_name: "app".to_string(),
+            repo_url: None,
+            session: Session {
+                id: session_id,
+                repo_id: Uuid::new_v4(),
+                name: Some("Fix tests".to_string()),
+                orchestrator: Orchestrator::Ralph,
+                status: SessionStatus::Completed,
+                owner_id: None,
+                pr_url: None,
+                issue_url: None,
+                prompt_draft: None,
+                failure: None,
+                archived: false,
+                archived_at: None,
+                tags: Vec::new(),
+                metadata: serde_json::Map::new(),
+                created_at: now,
+                updated_at: now,
+            },
+            messages: vec![Message {
+                id: Uuid::new_v4(),
+                session_id,
+                role: MessageRole::User,
+                kind: MessageKind::Text,
+                content: "Fix the tests".to_string(),
+                data: None,
+                created_at: now,
+            }],
+            output: vec![OutputLog {
+                id: 1,
+                session_id,
+                stream: OutputStream::Stdout,
+                channel: OutputChannel::AssistantText,
+                content: "done".to_string(),
+                continues: false,
+                created_at: now,
+            }],
+            changes: None,
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let data = encode(&bundle(), None).unwrap();
+        let decoded = decode(&data, None).unwrap();
+        assert_eq!(decoded.messages[0].content, "Fix the tests");
+        assert_eq!(decoded.output[0].content, "done");
+
+        let signed = encode(&bundle(), Some("shared-key")).unwrap();
+        assert_eq!(decode(&signed, Some("shared-key")).unwrap().repo_name, "app");
+        assert!(matches!(decode(&signed, Some("other-key")), Err(BundleError::BadSignature)));
+        assert!(matches!(decode(&signed, None), Err(BundleError::KeyRequired)));
+        assert!(matches!(decode(&data, Some("shared-key")), Err(BundleError::BadSignature)));
+    }
+
+    #[test]
+    fn test_tampered_bundle_is_rejected() {
+        let data = encode(&bundle(), None).unwrap();
+        let mut contents = String::new();
+        GzDecoder::new(&data[..]).read_to_string(&mut contents).unwrap();
+        let tampered = contents.replace("Fix the tests", "Delete everything");
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(tampered.as_bytes()).unwrap();
+        let tampered = encoder.finish().unwrap();
+
+        assert!(matches!(decode(&tampered, None), Err(BundleError::BadSignature)));
+        assert!(matches!(decode(b"not gzip", None), Err(BundleError::Malformed(_))));
+    }
+}
@@ -0,0 +1,171 @@
+//! Cron expressions for scheduled runs
+//!
+//! The standard five fields, in UTC: minute (0-59), hour (0-23), day of the
+//! month (1-31), month (1-12), and day of the week (0-7, where 0 and 7 are
+//! Sunday). Each field is `*`, a number, a range `a-b`, or a comma-separated
+//! list of those, and `*` and ranges can take a step (`*/15`, `9-17/2`). As
+//! in classic cron, when both day fields are restricted a day matching either
+//! one matches.
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Timelike, Utc};
+
+/// Furthest ahead the next time is looked for; an expression such as
+/// `0 0 30 2 *` never matches
+const SEARCH_YEARS: i32 = 5;
+
+/// A parsed cron expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cron {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// Whether the day fields were `*`
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl Cron {
+    /// Parse a five-field expression
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!(
+                "expected 5 fields (minute hour day month weekday), got {}",
+                fields.len()
+            ));
+        };
+        // Sunday is both 0 and 7
+        let mut weekdays = parse_field(weekday, "weekday", 0, 7)?;
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7);
+        }
+        Ok(Self {
+            minutes: parse_field(minute, "minute", 0, 59)?,
+            hours: parse_field(hour, "hour", 0, 23)?,
+            days: parse_field(day, "day", 1, 31)?,
+            months: parse_field(month, "month", 1, 12)?,
+            weekdays,
+            any_day: day == "*",
+            any_weekday: weekday == "*",
+        })
+    }
+
+    /// The first matching minute after `after`, if there is one in the next few years
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut time = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let limit = after.with_year(after.year() + SEARCH_YEARS)?;
+        while time < limit {
+            if !has(self.months, time.month()) {
+                let (year, month) = match time.month() {
+                    12 => (time.year() + 1, 1),
+                    month => (time.year(), month + 1),
+                };
+                time = start_of_day(NaiveDate::from_ymd_opt(year, month, 1)?);
+            } else if !self.day_matches(time) {
+                time = start_of_day(time.date_naive().succ_opt()?);
+            } else if !has(self.hours, time.hour()) {
+                time = time.with_minute(0)? + Duration::hours(1);
+            } else if !has(self.minutes, time.minute()) {
+                time += Duration::minutes(1);
+            } else {
+                return Some(time);
+            }
+        }
+        None
+    }
+
+    fn day_matches(&self, time: DateTime<Utc>) -> bool {
+        let day = has(self.days, time.day());
+        let weekday = has(self.weekdays, time.weekday().num_days_from_sunday());
+        match (self.any_day, self.any_weekday) {
+            (false, false) => day || weekday,
+            _ => day && weekday,
+        }
+    }
+}
+
+fn has(set: u64, value: u32) -> bool {
+    set & (1 << value) != 0
+}
+
+fn start_of_day(date: NaiveDate) -> DateTime<Utc> {
+    Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).expect("midnight is valid"))
+}
+
+/// Parse one field into a set of values, bit `n` standing for `n`
+fn parse_field(field: &str, name: &str, min: u32, max: u32) -> Result<u64, String> {
+    let number = |s: &str| -> Result<u32, String> {
+        let value: u32 = s
+            .parse()
+            .map_err(|_| format!("invalid {} '{}'", name, s))?;
+        if !(min..=max).contains(&value) {
+            return Err(format!("{} {} is out of range {}-{}", name, value, min, max));
+        }
+        Ok(value)
+    };
+
+    let mut set = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(|| format!("invalid {} step '{}'", name, step))?;
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let (start, end) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((start, end)) => (number(start)?, number(end)?),
+            // A single value with a step runs to the end of the field
+            None if step > 1 => (number(range)?, max),
+            None => {
+                let value = number(range)?;
+                (value, value)
+            }
+        };
+        if start > end {
+            return Err(format!("{} range {} is backwards", name, range));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            set |= 1 << value;
+        }
+    }
+    Ok(set)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_next_after() {
+        let cron = Cron::parse("*/15 9-17 * * 1-5").unwrap();
+        // Friday evening rolls over to Monday morning
+        assert_eq!(cron.next_after(at("2026-01-02T17:50:00Z")), Some(at("2026-01-05T09:00:00Z")));
+        assert_eq!(cron.next_after(at("2026-01-05T09:00:30Z")), Some(at("2026-01-05T09:15:00Z")));
+
+        let cron = Cron::parse("30 2 1 * *").unwrap();
+        assert_eq!(cron.next_after(at("2026-12-15T00:00:00Z")), Some(at("2027-01-01T02:30:00Z")));
+
+        // Either day field matches when both are set; 7 is Sunday
+        let cron = Cron::parse("0 0 13 * 7").unwrap();
+        assert_eq!(cron.next_after(at("2026-02-01T00:00:00Z")), Some(at("2026-02-08T00:00:00Z")));
+        assert_eq!(cron.next_after(at("2026-02-09T00:00:00Z")), Some(at("2026-02-13T00:00:00Z")));
+
+        assert_eq!(Cron::parse("0 0 30 2 *").unwrap().next_after(at("2026-01-01T00:00:00Z")), None);
+
+        for invalid in ["* * * *", "60 * * * *", "* * 0 * *", "*/0 * * * *", "5-1 * * * *", "a * * * *"] {
+            assert!(Cron::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+}
@@ -1,16 +1,29 @@
 pub mod models;
 pub mod schema;
 
+use std::collections::{BTreeMap, HashSet};
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
-use chrono::{DateTime, Utc};
-use rusqlite::{params, Connection};
+use chrono::{DateTime, NaiveDate, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
 use thiserror::Error;
+
+use crate::failure::{Failure, FailureReason};
 use uuid::Uuid;
 
-use models::{Message, MessageRole, Orchestrator, OutputStream, OutputLog, Repo, Session, SessionStatus};
-use schema::{CREATE_TABLES, GET_SCHEMA_VERSION, MIGRATE_V1_TO_V2, SCHEMA_VERSION, UPSERT_SCHEMA_VERSION};
+use models::{
+    ActivityEvent, ActivityKind, Artifact, Attachment, Bookmark, DailyStats, DeliveryStatus, Iteration, Job, JobKind, JobState, Message, MessageKind, MessageRole, Orchestrator,
+    OutputChannel, OutputStream, OutputLog, PipelineStep, PromptHistoryEntry, Repo, Run, RunStats, Schedule, ScheduleRun, Session, SessionStats, SessionStatus, Snapshot, SnapshotReason, StepStatus, TokenUsage, Trigger, Upload, User, UserRole, Webhook, WebhookDelivery, Worktree,
+    MessageSearchHit, OutputSearchHit, PrunedOutput, SearchResults, SessionSearchHit, SnippetPart,
+};
+use schema::{
+    CREATE_OWNER_INDEXES, CREATE_SEARCH_INDEXES, CREATE_TABLES, GET_SCHEMA_VERSION,
+    MIGRATE_V1_TO_V2, MIGRATE_V2_TO_V3, MIGRATE_V3_TO_V4, MIGRATE_V4_TO_V5, MIGRATE_V5_TO_V6,
+    MIGRATE_V6_TO_V7, MIGRATE_V7_TO_V8, MIGRATE_V8_TO_V9, MIGRATE_V9_TO_V10, MIGRATE_V10_TO_V11,
+    MIGRATE_V11_TO_V12, MIGRATE_V13_TO_V14, MIGRATE_V14_TO_V15, SCHEMA_VERSION,
+    UPSERT_SCHEMA_VERSION,
+};
 
 /// Database error types
 #[derive(Debug, Error)]
@@ -62,6 +75,15 @@ fn parse_uuid(row: &rusqlite::Row, idx: usize, field: &str) -> rusqlite::Result<
     })
 }
 
+/// Parse a nullable UUID column
+fn parse_optional_uuid(row: &rusqlite::Row, idx: usize, field: &str) -> rusqlite::Result<Option<Uuid>> {
+    let value: Option<String> = row.get(idx)?;
+    match value {
+        None => Ok(None),
+        Some(_) => parse_uuid(row, idx, field).map(Some),
+    }
+}
+
 /// Parse a DateTime from a database row with descriptive error
 fn parse_datetime(row: &rusqlite::Row, idx: usize, field: &str) -> rusqlite::Result<DateTime<Utc>> {
     let value: String = row.get(idx)?;
@@ -80,6 +102,39 @@ fn parse_datetime(row: &rusqlite::Row, idx: usize, field: &str) -> rusqlite::Res
         })
 }
 
+/// Parse a nullable DateTime column
+fn parse_optional_datetime(
+    row: &rusqlite::Row,
+    idx: usize,
+    field: &str,
+) -> rusqlite::Result<Option<DateTime<Utc>>> {
+    let value: Option<String> = row.get(idx)?;
+    match value {
+        None => Ok(None),
+        Some(_) => parse_datetime(row, idx, field).map(Some),
+    }
+}
+
+/// Parse a JSON object from a database row with descriptive error
+fn parse_json_object(
+    row: &rusqlite::Row,
+    idx: usize,
+    field: &str,
+) -> rusqlite::Result<serde_json::Map<String, serde_json::Value>> {
+    let value: String = row.get(idx)?;
+    serde_json::from_str(&value).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(
+            idx,
+            rusqlite::types::Type::Text,
+            Box::new(DbError::ParseError {
+                message: e.to_string(),
+                value,
+                field: field.to_string(),
+            }),
+        )
+    })
+}
+
 /// Parse an enum from a database row with descriptive error
 fn parse_enum<T, F>(row: &rusqlite::Row, idx: usize, field: &str, parser: F) -> rusqlite::Result<T>
 where
@@ -99,7 +154,684 @@ where
     })
 }
 
+/// SQL condition limiting output logs to some channels
+///
+/// The channel names are fixed strings, so they're written into the query.
+fn channel_clause(channels: Option<&[OutputChannel]>) -> String {
+    match channels {
+        Some(channels) if !channels.is_empty() => {
+            let names: Vec<String> = channels.iter().map(|c| format!("'{}'", c.as_str())).collect();
+            format!("channel IN ({})", names.join(", "))
+        }
+        _ => "1 = 1".to_string(),
+    }
+}
+
+/// Longest iteration summary kept, in characters
+const ITERATION_SUMMARY_CHARS: usize = 200;
+
+/// End a session's open iteration before output log `before`, or at its last line
+///
+/// The summary is the iteration's last non-blank line of agent prose.
+fn end_open_iteration(conn: &Connection, session_id: Uuid, before: Option<i64>) -> DbResult<()> {
+    let open: Option<(i64, i64)> = conn
+        .query_row(
+            "SELECT id, first_output_id FROM iterations WHERE session_id = ?1 AND ended_at IS NULL ORDER BY id DESC LIMIT 1",
+            params![session_id.to_string()],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+    let Some((id, first_output_id)) = open else {
+        return Ok(());
+    };
+    let before = before.unwrap_or(i64::MAX);
+
+    let last_output_id: Option<i64> = conn.query_row(
+        "SELECT MAX(id) FROM output_logs WHERE session_id = ?1 AND id >= ?2 AND id < ?3",
+        params![session_id.to_string(), first_output_id, before],
+        |row| row.get(0),
+    )?;
+    let summary: Option<String> = conn
+        .query_row(
+            "SELECT content FROM output_logs
+             WHERE session_id = ?1 AND id > ?2 AND id < ?3 AND channel = 'assistant_text' AND compressed = 0
+               AND TRIM(content) != ''
+             ORDER BY id DESC LIMIT 1",
+            params![session_id.to_string(), first_output_id, before],
+            |row| row.get(0),
+        )
+        .optional()?
+        .map(|line: String| line.trim().chars().take(ITERATION_SUMMARY_CHARS).collect());
+
+    conn.execute(
+        "UPDATE iterations SET last_output_id = ?2, summary = ?3, ended_at = ?4 WHERE id = ?1",
+        params![id, last_output_id.unwrap_or(first_output_id), summary, Utc::now().to_rfc3339()],
+    )?;
+    Ok(())
+}
+
+/// Output log content at least this long is stored compressed
+const COMPRESS_MIN_BYTES: usize = 1024;
+
+/// zstd level for output log content; low levels are fast and still shrink logs well
+const COMPRESS_LEVEL: i32 = 3;
+
+/// Output log content as stored, and whether it's compressed
+///
+/// Content is compressed with zstd once it's long enough for that to pay
+/// off, and kept as text when compressing doesn't make it smaller.
+fn encode_output(content: &str) -> (rusqlite::types::Value, bool) {
+    if content.len() >= COMPRESS_MIN_BYTES
+        && let Ok(compressed) = zstd::bulk::compress(content.as_bytes(), COMPRESS_LEVEL)
+        && compressed.len() < content.len()
+    {
+        return (rusqlite::types::Value::Blob(compressed), true);
+    }
+    (rusqlite::types::Value::Text(content.to_string()), false)
+}
+
+/// Read output log content stored by [`encode_output`]
+fn decode_output(row: &rusqlite::Row, idx: usize, compressed: bool) -> rusqlite::Result<String> {
+    if !compressed {
+        return row.get(idx);
+    }
+    let conversion = |e: Box<dyn std::error::Error + Send + Sync>| {
+        rusqlite::Error::FromSqlConversionFailure(idx, rusqlite::types::Type::Blob, e)
+    };
+    let data: Vec<u8> = row.get(idx)?;
+    let bytes = zstd::decode_all(&data[..]).map_err(|e| conversion(Box::new(e)))?;
+    String::from_utf8(bytes).map_err(|e| conversion(Box::new(e)))
+}
+
+/// Add an output log's text to the search index
+///
+/// Content may be stored compressed, so the index can't be kept by a trigger.
+fn index_output_log(conn: &Connection, id: i64, content: &str) -> DbResult<()> {
+    conn.execute(
+        "INSERT INTO output_logs_fts (rowid, content) VALUES (?1, ?2)",
+        params![id, content],
+    )?;
+    Ok(())
+}
+
+/// An FTS5 query matching text with all of `text`'s words, each taken literally
+fn fts_query(text: &str) -> Option<String> {
+    let words: Vec<String> = text
+        .split_whitespace()
+        .map(|word| format!("\"{}\"", word.replace('"', "\"\"")))
+        .collect();
+    (!words.is_empty()).then(|| words.join(" "))
+}
+
+/// Split a search snippet into plain and matched parts
+///
+/// The search queries have `snippet()` put `\u{2}` before and `\u{3}` after
+/// each match, characters that don't turn up in messages or output.
+fn snippet_parts(snippet: &str) -> Vec<SnippetPart> {
+    let mut parts = Vec::new();
+    let mut push = |text: &str, highlight: bool| {
+        if !text.is_empty() {
+            parts.push(SnippetPart {
+                text: text.to_string(),
+                highlight,
+            });
+        }
+    };
+    let mut pieces = snippet.split('\u{2}');
+    if let Some(first) = pieces.next() {
+        push(first, false);
+    }
+    for piece in pieces {
+        let (matched, rest) = piece.split_once('\u{3}').unwrap_or((piece, ""));
+        push(matched, true);
+        push(rest, false);
+    }
+    parts
+}
+
+/// Largest a session's metadata may grow, serialized
+pub const MAX_SESSION_METADATA_BYTES: usize = 64 * 1024;
+
+/// Apply a JSON merge patch (RFC 7396): null removes a key, an object is
+/// merged into the object already there, and anything else replaces it
+fn merge_patch(
+    target: &mut serde_json::Map<String, serde_json::Value>,
+    patch: &serde_json::Map<String, serde_json::Value>,
+) {
+    for (key, value) in patch {
+        match value {
+            serde_json::Value::Null => {
+                target.remove(key);
+            }
+            serde_json::Value::Object(patch) => {
+                let entry = target.entry(key.clone()).or_insert(serde_json::Value::Null);
+                if !entry.is_object() {
+                    *entry = serde_json::Value::Object(serde_json::Map::new());
+                }
+                if let serde_json::Value::Object(target) = entry {
+                    merge_patch(target, patch);
+                }
+            }
+            value => {
+                target.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+/// Columns read by [`session_from_row`], in order
+const SESSION_COLUMNS: &str = "id, repo_id, name, orchestrator, status, owner_id, pr_url, issue_url, prompt_draft, \
+     created_at, updated_at, failure_reason, failure_line, archived, archived_at, metadata, \
+     (SELECT group_concat(tag) FROM session_tags WHERE session_tags.session_id = sessions.id)";
+
+/// Build a session from a row selected with [`SESSION_COLUMNS`]
+fn session_from_row(row: &rusqlite::Row) -> rusqlite::Result<Session> {
+    Ok(Session {
+        id: parse_uuid(row, 0, "id")?,
+        repo_id: parse_uuid(row, 1, "repo_id")?,
+        name: row.get(2)?,
+        orchestrator: parse_enum(row, 3, "orchestrator", Orchestrator::from_str)?,
+        status: parse_enum(row, 4, "status", SessionStatus::from_str)?,
+        owner_id: parse_optional_uuid(row, 5, "owner_id")?,
+        pr_url: row.get(6)?,
+        issue_url: row.get(7)?,
+        prompt_draft: row.get(8)?,
+        failure: match row.get::<_, Option<String>>(11)? {
+            Some(_) => {
+                let reason = parse_enum(row, 11, "failure_reason", FailureReason::from_str)?;
+                let line: Option<String> = row.get(12)?;
+                Some(Failure::new(reason, line.as_deref().unwrap_or_default()))
+            }
+            None => None,
+        },
+        archived: row.get(13)?,
+        archived_at: parse_optional_datetime(row, 14, "archived_at")?,
+        // Tags can't contain commas, group_concat's separator
+        tags: {
+            let tags: Option<String> = row.get(16)?;
+            let mut tags: Vec<String> =
+                tags.iter().flat_map(|tags| tags.split(',')).map(String::from).collect();
+            tags.sort();
+            tags
+        },
+        metadata: parse_json_object(row, 15, "metadata")?,
+        created_at: parse_datetime(row, 9, "created_at")?,
+        updated_at: parse_datetime(row, 10, "updated_at")?,
+    })
+}
+
+/// Columns read by [`user_from_row`], in order
+const USER_COLUMNS: &str = "id, username, role, desktop_notifications, created_at, updated_at";
+
+/// Build a user from a row selected with [`USER_COLUMNS`]
+fn user_from_row(row: &rusqlite::Row) -> rusqlite::Result<User> {
+    Ok(User {
+        id: parse_uuid(row, 0, "id")?,
+        username: row.get(1)?,
+        role: parse_enum(row, 2, "role", UserRole::from_str)?,
+        desktop_notifications: row.get(3)?,
+        created_at: parse_datetime(row, 4, "created_at")?,
+        updated_at: parse_datetime(row, 5, "updated_at")?,
+    })
+}
+
+/// Columns read by [`webhook_from_row`], in order
+const WEBHOOK_COLUMNS: &str = "id, url, secret, events, enabled, created_at, updated_at";
+
+/// Build a webhook from a row selected with [`WEBHOOK_COLUMNS`]
+fn webhook_from_row(row: &rusqlite::Row) -> rusqlite::Result<Webhook> {
+    let events: String = row.get(3)?;
+    Ok(Webhook {
+        id: parse_uuid(row, 0, "id")?,
+        url: row.get(1)?,
+        secret: row.get(2)?,
+        events: events
+            .split(',')
+            .filter(|e| !e.is_empty())
+            .map(String::from)
+            .collect(),
+        enabled: row.get(4)?,
+        created_at: parse_datetime(row, 5, "created_at")?,
+        updated_at: parse_datetime(row, 6, "updated_at")?,
+    })
+}
+
+/// Columns read by [`webhook_delivery_from_row`], in order
+const WEBHOOK_DELIVERY_COLUMNS: &str = "id, webhook_id, event, payload, status, attempts, response_status, error, created_at, updated_at";
+
+/// Build a delivery from a row selected with [`WEBHOOK_DELIVERY_COLUMNS`]
+fn webhook_delivery_from_row(row: &rusqlite::Row) -> rusqlite::Result<WebhookDelivery> {
+    let payload: String = row.get(3)?;
+    Ok(WebhookDelivery {
+        id: parse_uuid(row, 0, "id")?,
+        webhook_id: parse_uuid(row, 1, "webhook_id")?,
+        event: row.get(2)?,
+        payload: serde_json::from_str(&payload).unwrap_or(serde_json::Value::String(payload)),
+        status: parse_enum(row, 4, "status", DeliveryStatus::from_str)?,
+        attempts: row.get(5)?,
+        response_status: row.get(6)?,
+        error: row.get(7)?,
+        created_at: parse_datetime(row, 8, "created_at")?,
+        updated_at: parse_datetime(row, 9, "updated_at")?,
+    })
+}
+
+/// Columns read by [`trigger_from_row`], in order
+const TRIGGER_COLUMNS: &str = "id, name, repo_id, session_id, prompt_template, orchestrator, enabled, last_triggered_at, created_at, updated_at";
+
+/// Build a trigger from a row selected with [`TRIGGER_COLUMNS`]
+fn trigger_from_row(row: &rusqlite::Row) -> rusqlite::Result<Trigger> {
+    Ok(Trigger {
+        id: parse_uuid(row, 0, "id")?,
+        name: row.get(1)?,
+        repo_id: parse_uuid(row, 2, "repo_id")?,
+        session_id: parse_optional_uuid(row, 3, "session_id")?,
+        prompt_template: row.get(4)?,
+        orchestrator: parse_enum(row, 5, "orchestrator", Orchestrator::from_str)?,
+        enabled: row.get(6)?,
+        last_triggered_at: parse_optional_datetime(row, 7, "last_triggered_at")?,
+        created_at: parse_datetime(row, 8, "created_at")?,
+        updated_at: parse_datetime(row, 9, "updated_at")?,
+    })
+}
+
+/// Columns read by [`snapshot_from_row`], in order
+const SNAPSHOT_COLUMNS: &str = "id, session_id, repo_id, reason, head_commit, file_count, deleted_count, size_bytes, created_at";
+
+/// Build a snapshot from a row selected with [`SNAPSHOT_COLUMNS`]
+fn snapshot_from_row(row: &rusqlite::Row) -> rusqlite::Result<Snapshot> {
+    Ok(Snapshot {
+        id: parse_uuid(row, 0, "id")?,
+        session_id: parse_uuid(row, 1, "session_id")?,
+        repo_id: parse_uuid(row, 2, "repo_id")?,
+        reason: parse_enum(row, 3, "reason", SnapshotReason::from_str)?,
+        head_commit: row.get(4)?,
+        file_count: row.get(5)?,
+        deleted_count: row.get(6)?,
+        size_bytes: row.get(7)?,
+        created_at: parse_datetime(row, 8, "created_at")?,
+    })
+}
+
+/// Columns read by [`prompt_history_from_row`], in order
+const PROMPT_HISTORY_COLUMNS: &str = "id, repo_id, prompt, use_count, last_used_at, created_at";
+
+/// Build a history entry from a row selected with [`PROMPT_HISTORY_COLUMNS`]
+fn prompt_history_from_row(row: &rusqlite::Row) -> rusqlite::Result<PromptHistoryEntry> {
+    Ok(PromptHistoryEntry {
+        id: row.get(0)?,
+        repo_id: parse_uuid(row, 1, "repo_id")?,
+        prompt: row.get(2)?,
+        use_count: row.get(3)?,
+        last_used_at: parse_datetime(row, 4, "last_used_at")?,
+        created_at: parse_datetime(row, 5, "created_at")?,
+    })
+}
+
+/// Most prompts remembered per repository; the least recently used go first
+pub const MAX_PROMPT_HISTORY: usize = 200;
+
+const ACTIVITY_COLUMNS: &str = "id, kind, repo_id, session_id, summary, created_at";
+
+/// Build an activity entry from a row selected with [`ACTIVITY_COLUMNS`]
+fn activity_from_row(row: &rusqlite::Row) -> rusqlite::Result<ActivityEvent> {
+    Ok(ActivityEvent {
+        id: row.get(0)?,
+        kind: parse_enum(row, 1, "kind", ActivityKind::from_str)?,
+        repo_id: parse_uuid(row, 2, "repo_id")?,
+        session_id: parse_optional_uuid(row, 3, "session_id")?,
+        summary: row.get(4)?,
+        created_at: parse_datetime(row, 5, "created_at")?,
+    })
+}
+
+/// Most activity entries kept; older ones are dropped as new ones arrive
+pub const MAX_ACTIVITY: usize = 1000;
+
+const ARTIFACT_COLUMNS: &str = "id, session_id, repo_id, path, size_bytes, created_at";
+
+/// Build an artifact from a row selected with [`ARTIFACT_COLUMNS`]
+fn artifact_from_row(row: &rusqlite::Row) -> rusqlite::Result<Artifact> {
+    Ok(Artifact {
+        id: parse_uuid(row, 0, "id")?,
+        session_id: parse_uuid(row, 1, "session_id")?,
+        repo_id: parse_uuid(row, 2, "repo_id")?,
+        path: row.get(3)?,
+        size_bytes: row.get(4)?,
+        created_at: parse_datetime(row, 5, "created_at")?,
+    })
+}
+
+const UPLOAD_COLUMNS: &str = "id, owner_id, filename, content_type, size_bytes, created_at";
+
+fn upload_from_row(row: &rusqlite::Row) -> rusqlite::Result<Upload> {
+    Ok(Upload {
+        id: parse_uuid(row, 0, "id")?,
+        owner_id: parse_optional_uuid(row, 1, "owner_id")?,
+        filename: row.get(2)?,
+        content_type: row.get(3)?,
+        size_bytes: row.get(4)?,
+        created_at: parse_datetime(row, 5, "created_at")?,
+    })
+}
+
+const ATTACHMENT_COLUMNS: &str = "id, session_id, filename, content_type, size_bytes, created_at";
+
+fn attachment_from_row(row: &rusqlite::Row) -> rusqlite::Result<Attachment> {
+    Ok(Attachment {
+        id: parse_uuid(row, 0, "id")?,
+        session_id: parse_uuid(row, 1, "session_id")?,
+        filename: row.get(2)?,
+        content_type: row.get(3)?,
+        size_bytes: row.get(4)?,
+        created_at: parse_datetime(row, 5, "created_at")?,
+    })
+}
+
+/// A path bookmarked twice in the same repository is a conflict
+fn bookmark_conflict(e: rusqlite::Error, path: &str) -> DbError {
+    match e {
+        rusqlite::Error::SqliteFailure(err, _) if err.code == rusqlite::ErrorCode::ConstraintViolation => {
+            DbError::ConstraintViolation(format!("Already bookmarked: {}", path))
+        }
+        _ => DbError::Sqlite(e),
+    }
+}
+
+const BOOKMARK_COLUMNS: &str = "id, repo_id, path, label, created_at, updated_at";
+
+fn bookmark_from_row(row: &rusqlite::Row) -> rusqlite::Result<Bookmark> {
+    Ok(Bookmark {
+        id: parse_uuid(row, 0, "id")?,
+        repo_id: parse_uuid(row, 1, "repo_id")?,
+        path: row.get(2)?,
+        label: row.get(3)?,
+        created_at: parse_datetime(row, 4, "created_at")?,
+        updated_at: parse_datetime(row, 5, "updated_at")?,
+    })
+}
+
+/// A session gets one worktree, and a path holds one
+fn worktree_conflict(e: rusqlite::Error) -> DbError {
+    match e {
+        rusqlite::Error::SqliteFailure(err, _) if err.code == rusqlite::ErrorCode::ConstraintViolation => {
+            DbError::ConstraintViolation("The session already has a worktree".to_string())
+        }
+        _ => DbError::Sqlite(e),
+    }
+}
+
+const WORKTREE_COLUMNS: &str = "id, repo_id, session_id, branch, path, created_at";
+
+fn worktree_from_row(row: &rusqlite::Row) -> rusqlite::Result<Worktree> {
+    Ok(Worktree {
+        id: parse_uuid(row, 0, "id")?,
+        repo_id: parse_uuid(row, 1, "repo_id")?,
+        session_id: parse_optional_uuid(row, 2, "session_id")?,
+        branch: row.get(3)?,
+        path: row.get(4)?,
+        created_at: parse_datetime(row, 5, "created_at")?,
+    })
+}
+
+/// Columns read by [`schedule_from_row`], in order
+const SCHEDULE_COLUMNS: &str =
+    "id, name, repo_id, prompt, cron, orchestrator, enabled, next_run_at, last_run_at, created_at, updated_at";
+
+/// Build a schedule from a row selected with [`SCHEDULE_COLUMNS`]
+fn schedule_from_row(row: &rusqlite::Row) -> rusqlite::Result<Schedule> {
+    Ok(Schedule {
+        id: parse_uuid(row, 0, "id")?,
+        name: row.get(1)?,
+        repo_id: parse_uuid(row, 2, "repo_id")?,
+        prompt: row.get(3)?,
+        cron: row.get(4)?,
+        orchestrator: parse_enum(row, 5, "orchestrator", Orchestrator::from_str)?,
+        enabled: row.get(6)?,
+        next_run_at: parse_optional_datetime(row, 7, "next_run_at")?,
+        last_run_at: parse_optional_datetime(row, 8, "last_run_at")?,
+        created_at: parse_datetime(row, 9, "created_at")?,
+        updated_at: parse_datetime(row, 10, "updated_at")?,
+    })
+}
+
+const SCHEDULE_RUN_COLUMNS: &str = "id, schedule_id, session_id, error, fired_at";
+
+fn schedule_run_from_row(row: &rusqlite::Row) -> rusqlite::Result<ScheduleRun> {
+    Ok(ScheduleRun {
+        id: row.get(0)?,
+        schedule_id: parse_uuid(row, 1, "schedule_id")?,
+        session_id: parse_optional_uuid(row, 2, "session_id")?,
+        error: row.get(3)?,
+        fired_at: parse_datetime(row, 4, "fired_at")?,
+    })
+}
+
+const RUN_COLUMNS: &str = "id, session_id, prompt, status, exit_code, started_at, finished_at, input_tokens, output_tokens, cache_read_tokens, cache_write_tokens, cost_usd";
+
+/// Build a run from a row selected with [`RUN_COLUMNS`]
+fn run_from_row(row: &rusqlite::Row) -> rusqlite::Result<Run> {
+    let started_at = parse_datetime(row, 5, "started_at")?;
+    let finished_at = parse_optional_datetime(row, 6, "finished_at")?;
+    Ok(Run {
+        id: parse_uuid(row, 0, "id")?,
+        session_id: parse_uuid(row, 1, "session_id")?,
+        prompt: row.get(2)?,
+        status: parse_enum(row, 3, "status", SessionStatus::from_str)?,
+        exit_code: row.get(4)?,
+        started_at,
+        finished_at,
+        duration_ms: finished_at.map(|finished_at| (finished_at - started_at).num_milliseconds()),
+        usage: usage_from_row(row, 7)?,
+    })
+}
+
+/// Read token totals from the five columns starting at `idx`
+fn usage_from_row(row: &rusqlite::Row, idx: usize) -> rusqlite::Result<TokenUsage> {
+    Ok(TokenUsage {
+        input_tokens: row.get::<_, i64>(idx)? as u64,
+        output_tokens: row.get::<_, i64>(idx + 1)? as u64,
+        cache_read_tokens: row.get::<_, i64>(idx + 2)? as u64,
+        cache_write_tokens: row.get::<_, i64>(idx + 3)? as u64,
+        cost_usd: row.get(idx + 4)?,
+    })
+}
+
+const PIPELINE_STEP_COLUMNS: &str = "session_id, step, prompt, status, started_at, finished_at";
+
+/// Build a pipeline step from a row selected with [`PIPELINE_STEP_COLUMNS`]
+fn pipeline_step_from_row(row: &rusqlite::Row) -> rusqlite::Result<PipelineStep> {
+    Ok(PipelineStep {
+        session_id: parse_uuid(row, 0, "session_id")?,
+        step: row.get(1)?,
+        prompt: row.get(2)?,
+        status: parse_enum(row, 3, "status", StepStatus::from_str)?,
+        started_at: parse_optional_datetime(row, 4, "started_at")?,
+        finished_at: parse_optional_datetime(row, 5, "finished_at")?,
+    })
+}
+
+/// A session's pipeline steps with a status, in order
+fn pipeline_steps_with_status(conn: &Connection, session_id: Uuid, status: StepStatus) -> DbResult<Vec<PipelineStep>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM pipeline_steps WHERE session_id = ?1 AND status = ?2 ORDER BY step",
+        PIPELINE_STEP_COLUMNS
+    ))?;
+    let steps = stmt
+        .query_map(params![session_id.to_string(), status.as_str()], pipeline_step_from_row)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(steps)
+}
+
+const JOB_COLUMNS: &str = "id, kind, state, owner_id, description, progress, progress_message, result, error, cancel_requested, created_at, started_at, finished_at";
+
+/// Build a job from a row selected with [`JOB_COLUMNS`]
+fn job_from_row(row: &rusqlite::Row) -> rusqlite::Result<Job> {
+    let result: Option<String> = row.get(7)?;
+    Ok(Job {
+        id: parse_uuid(row, 0, "id")?,
+        kind: parse_enum(row, 1, "kind", JobKind::from_str)?,
+        state: parse_enum(row, 2, "state", JobState::from_str)?,
+        owner_id: parse_optional_uuid(row, 3, "owner_id")?,
+        description: row.get(4)?,
+        progress: row.get(5)?,
+        progress_message: row.get(6)?,
+        result: result.map(|result| serde_json::from_str(&result).unwrap_or(serde_json::Value::String(result))),
+        error: row.get(8)?,
+        cancel_requested: row.get(9)?,
+        created_at: parse_datetime(row, 10, "created_at")?,
+        started_at: parse_optional_datetime(row, 11, "started_at")?,
+        finished_at: parse_optional_datetime(row, 12, "finished_at")?,
+    })
+}
+
+/// Most finished jobs kept; older ones are dropped as new ones are queued
+pub const MAX_FINISHED_JOBS: usize = 500;
+
+const DAILY_STATS_COLUMNS: &str =
+    "day, repo_id, runs, successes, failures, cancelled, runtime_secs, output_lines, output_bytes";
+
+/// Build a day's totals from a row selected with [`DAILY_STATS_COLUMNS`]
+fn daily_stats_from_row(row: &rusqlite::Row) -> rusqlite::Result<DailyStats> {
+    let day: String = row.get(0)?;
+    let day = NaiveDate::parse_from_str(&day, "%Y-%m-%d").map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(
+            0,
+            rusqlite::types::Type::Text,
+            Box::new(DbError::ParseError {
+                message: e.to_string(),
+                value: day.clone(),
+                field: "day".to_string(),
+            }),
+        )
+    })?;
+    Ok(DailyStats {
+        day,
+        repo_id: parse_uuid(row, 1, "repo_id")?,
+        stats: RunStats {
+            runs: row.get(2)?,
+            successes: row.get(3)?,
+            failures: row.get(4)?,
+            cancelled: row.get(5)?,
+            runtime_secs: row.get(6)?,
+            output_lines: row.get(7)?,
+            output_bytes: row.get(8)?,
+        },
+    })
+}
+
+/// The UTC day a timestamp falls on, as stored in `daily_stats`
+fn day_of(time: DateTime<Utc>) -> String {
+    time.format("%Y-%m-%d").to_string()
+}
+
+/// Add `stats` to a repository's totals for `day`
+fn add_daily_stats(conn: &Connection, day: &str, repo_id: &str, stats: &RunStats) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO daily_stats (day, repo_id, runs, successes, failures, cancelled, runtime_secs, output_lines, output_bytes)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+         ON CONFLICT (day, repo_id) DO UPDATE SET
+             runs = runs + excluded.runs,
+             successes = successes + excluded.successes,
+             failures = failures + excluded.failures,
+             cancelled = cancelled + excluded.cancelled,
+             runtime_secs = runtime_secs + excluded.runtime_secs,
+             output_lines = output_lines + excluded.output_lines,
+             output_bytes = output_bytes + excluded.output_bytes",
+        params![
+            day,
+            repo_id,
+            stats.runs,
+            stats.successes,
+            stats.failures,
+            stats.cancelled,
+            stats.runtime_secs,
+            stats.output_lines,
+            stats.output_bytes
+        ],
+    )?;
+    Ok(())
+}
+
+/// Last row of `source` already counted into `daily_stats`
+fn aggregate_progress(conn: &Connection, source: &str) -> rusqlite::Result<i64> {
+    let last_id = conn
+        .query_row(
+            "SELECT last_id FROM aggregate_progress WHERE source = ?1",
+            params![source],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(last_id.unwrap_or(0))
+}
+
+fn set_aggregate_progress(conn: &Connection, source: &str, last_id: i64) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO aggregate_progress (source, last_id) VALUES (?1, ?2)
+         ON CONFLICT (source) DO UPDATE SET last_id = excluded.last_id",
+        params![source, last_id],
+    )?;
+    Ok(())
+}
+
+/// Outcome and runtime of the run a `session_finished` entry ends, with the
+/// day the run started
+///
+/// Returns `None` when the run was already counted as finished: cancelling a
+/// run records its end twice, once for the cancel and once when the process
+/// exits.
+fn finished_run(conn: &Connection, event: &ActivityEvent) -> rusqlite::Result<Option<(String, RunStats)>> {
+    let Some(session_id) = event.session_id else {
+        return Ok(None);
+    };
+
+    let mut stats = RunStats::default();
+    match SessionStatus::from_str(&event.summary) {
+        Ok(SessionStatus::Completed) => stats.successes = 1,
+        Ok(SessionStatus::Error) => stats.failures = 1,
+        Ok(SessionStatus::Cancelled) => stats.cancelled = 1,
+        _ => {}
+    }
+
+    let started = conn
+        .query_row(
+            "SELECT id, created_at FROM activity WHERE session_id = ?1 AND kind = ?2 AND id < ?3 ORDER BY id DESC LIMIT 1",
+            params![
+                session_id.to_string(),
+                ActivityKind::SessionStarted.as_str(),
+                event.id
+            ],
+            |row| Ok((row.get::<_, i64>(0)?, parse_datetime(row, 1, "created_at")?)),
+        )
+        .optional()?;
+    let Some((start_id, started_at)) = started else {
+        // The start was pruned from the feed; count the outcome on the day it finished
+        return Ok(Some((day_of(event.created_at), stats)));
+    };
+
+    let earlier_ends: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM activity WHERE session_id = ?1 AND kind = ?2 AND id > ?3 AND id < ?4",
+        params![
+            session_id.to_string(),
+            ActivityKind::SessionFinished.as_str(),
+            start_id,
+            event.id
+        ],
+        |row| row.get(0),
+    )?;
+    if earlier_ends > 0 {
+        return Ok(None);
+    }
+
+    stats.runtime_secs = (event.created_at - started_at).num_seconds().max(0) as u64;
+    Ok(Some((day_of(started_at), stats)))
+}
+
 /// Database wrapper with connection management
+///
+/// Methods are blocking: each locks the one connection and waits on SQLite.
+/// Async code runs them through [`Database::call`] so they don't stall the
+/// runtime's worker threads.
 #[derive(Clone)]
 pub struct Database {
     conn: Arc<Mutex<Connection>>,
@@ -137,12 +869,34 @@ impl Database {
         Ok(db)
     }
 
+    /// Run `f` with the database on tokio's blocking thread pool
+    ///
+    /// Callers queue on the connection there instead of on an async worker,
+    /// e.g. `state.db.call(move |db| db.get_repo(id)).await`.
+    pub async fn call<T, F>(&self, f: F) -> DbResult<T>
+    where
+        F: FnOnce(&Database) -> DbResult<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let db = self.clone();
+        tokio::task::spawn_blocking(move || f(&db))
+            .await
+            .map_err(|e| DbError::ConnectionError(format!("Database task failed: {}", e)))?
+    }
+
     /// Get the default database path based on platform
     pub fn default_path() -> DbResult<PathBuf> {
         let data_dir = dirs::data_dir().ok_or(DbError::NoDataDir)?;
         Ok(data_dir.join("ralphtown").join("ralphtown.db"))
     }
 
+    /// Whether the database answers and its schema is the one this build expects
+    pub fn is_current(&self) -> bool {
+        let conn = self.conn.lock().unwrap();
+        let version: Option<i32> = conn.query_row(GET_SCHEMA_VERSION, [], |row| row.get(0)).ok();
+        version == Some(SCHEMA_VERSION)
+    }
+
     /// Initialize database schema
     fn init_schema(&self) -> DbResult<()> {
         let conn = self.conn.lock().unwrap();
@@ -174,76 +928,291 @@ impl Database {
             }
         }
 
-        if version < SCHEMA_VERSION {
-            conn.execute(UPSERT_SCHEMA_VERSION, params![SCHEMA_VERSION])?;
+        if version < 3 {
+            // V2 to V3: Add owner columns to repos and sessions
+            let has_owner: bool = conn
+                .query_row(
+                    "SELECT COUNT(*) FROM pragma_table_info('repos') WHERE name = 'owner_id'",
+                    [],
+                    |row| row.get::<_, i32>(0).map(|c| c > 0),
+                )
+                .unwrap_or(false);
+
+            if !has_owner {
+                conn.execute_batch(MIGRATE_V2_TO_V3)?;
+            }
+            conn.execute_batch(CREATE_OWNER_INDEXES)?;
         }
 
-        Ok(())
-    }
+        if version < 4 {
+            // V3 to V4: Add desktop notification preference to users
+            let has_preference: bool = conn
+                .query_row(
+                    "SELECT COUNT(*) FROM pragma_table_info('users') WHERE name = 'desktop_notifications'",
+                    [],
+                    |row| row.get::<_, i32>(0).map(|c| c > 0),
+                )
+                .unwrap_or(false);
 
-    // ==================== Repo Operations ====================
+            if !has_preference {
+                conn.execute_batch(MIGRATE_V3_TO_V4)?;
+            }
+        }
 
-    /// Insert a new repository
-    pub fn insert_repo(&self, path: &str, name: &str) -> DbResult<Repo> {
-        let conn = self.conn.lock().unwrap();
-        let now = Utc::now();
-        let id = Uuid::new_v4();
+        if version < 5 {
+            // V4 to V5: Add pull request URL to sessions
+            let has_pr_url: bool = conn
+                .query_row(
+                    "SELECT COUNT(*) FROM pragma_table_info('sessions') WHERE name = 'pr_url'",
+                    [],
+                    |row| row.get::<_, i32>(0).map(|c| c > 0),
+                )
+                .unwrap_or(false);
 
-        conn.execute(
-            "INSERT INTO repos (id, path, name, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5)",
-            params![
-                id.to_string(),
-                path,
-                name,
-                now.to_rfc3339(),
-                now.to_rfc3339()
-            ],
-        )?;
+            if !has_pr_url {
+                conn.execute_batch(MIGRATE_V4_TO_V5)?;
+            }
+        }
 
-        Ok(Repo {
-            id,
-            path: path.to_string(),
-            name: name.to_string(),
-            created_at: now,
-            updated_at: now,
-        })
-    }
+        if version < 6 {
+            // V5 to V6: Add issue reference and prompt draft to sessions
+            let has_issue_url: bool = conn
+                .query_row(
+                    "SELECT COUNT(*) FROM pragma_table_info('sessions') WHERE name = 'issue_url'",
+                    [],
+                    |row| row.get::<_, i32>(0).map(|c| c > 0),
+                )
+                .unwrap_or(false);
 
-    /// Get a repository by ID
-    pub fn get_repo(&self, id: Uuid) -> DbResult<Repo> {
-        let conn = self.conn.lock().unwrap();
+            if !has_issue_url {
+                conn.execute_batch(MIGRATE_V5_TO_V6)?;
+            }
+        }
 
-        conn.query_row(
-            "SELECT id, path, name, created_at, updated_at FROM repos WHERE id = ?1",
-            params![id.to_string()],
-            |row| {
-                Ok(Repo {
-                    id: parse_uuid(row, 0, "id")?,
-                    path: row.get(1)?,
-                    name: row.get(2)?,
-                    created_at: parse_datetime(row, 3, "created_at")?,
-                    updated_at: parse_datetime(row, 4, "updated_at")?,
-                })
-            },
-        )
-        .map_err(|e| match e {
-            rusqlite::Error::QueryReturnedNoRows => DbError::NotFound,
-            _ => DbError::Sqlite(e),
-        })
-    }
+        if version < 7 {
+            // V6 to V7: Add output channels to output logs
+            let has_channel: bool = conn
+                .query_row(
+                    "SELECT COUNT(*) FROM pragma_table_info('output_logs') WHERE name = 'channel'",
+                    [],
+                    |row| row.get::<_, i32>(0).map(|c| c > 0),
+                )
+                .unwrap_or(false);
 
-    /// Get a repository by path
-    pub fn get_repo_by_path(&self, path: &str) -> DbResult<Repo> {
-        let conn = self.conn.lock().unwrap();
+            if !has_channel {
+                conn.execute_batch(MIGRATE_V6_TO_V7)?;
+            }
+        }
 
-        conn.query_row(
-            "SELECT id, path, name, created_at, updated_at FROM repos WHERE path = ?1",
-            params![path],
-            |row| {
-                Ok(Repo {
-                    id: parse_uuid(row, 0, "id")?,
-                    path: row.get(1)?,
+        if version < 8 {
+            // V7 to V8: Mark output logs continuing in the next one
+            let has_continues: bool = conn
+                .query_row(
+                    "SELECT COUNT(*) FROM pragma_table_info('output_logs') WHERE name = 'continues'",
+                    [],
+                    |row| row.get::<_, i32>(0).map(|c| c > 0),
+                )
+                .unwrap_or(false);
+
+            if !has_continues {
+                conn.execute_batch(MIGRATE_V7_TO_V8)?;
+            }
+        }
+
+        if version < 9 {
+            // V8 to V9: Allow compressed output log content
+            let has_compressed: bool = conn
+                .query_row(
+                    "SELECT COUNT(*) FROM pragma_table_info('output_logs') WHERE name = 'compressed'",
+                    [],
+                    |row| row.get::<_, i32>(0).map(|c| c > 0),
+                )
+                .unwrap_or(false);
+
+            if !has_compressed {
+                conn.execute_batch(MIGRATE_V8_TO_V9)?;
+            }
+        }
+
+        if version < 10 {
+            // V9 to V10: Record why a session's last run failed
+            let has_failure: bool = conn
+                .query_row(
+                    "SELECT COUNT(*) FROM pragma_table_info('sessions') WHERE name = 'failure_reason'",
+                    [],
+                    |row| row.get::<_, i32>(0).map(|c| c > 0),
+                )
+                .unwrap_or(false);
+
+            if !has_failure {
+                conn.execute_batch(MIGRATE_V9_TO_V10)?;
+            }
+        }
+
+        if version < 11 {
+            // V10 to V11: Add token and cost totals to runs
+            let has_usage: bool = conn
+                .query_row(
+                    "SELECT COUNT(*) FROM pragma_table_info('runs') WHERE name = 'cost_usd'",
+                    [],
+                    |row| row.get::<_, i32>(0).map(|c| c > 0),
+                )
+                .unwrap_or(false);
+
+            if !has_usage {
+                conn.execute_batch(MIGRATE_V10_TO_V11)?;
+            }
+        }
+
+        if version < 12 {
+            // V11 to V12: Add kinds and details to messages
+            let has_kind: bool = conn
+                .query_row(
+                    "SELECT COUNT(*) FROM pragma_table_info('messages') WHERE name = 'kind'",
+                    [],
+                    |row| row.get::<_, i32>(0).map(|c| c > 0),
+                )
+                .unwrap_or(false);
+
+            if !has_kind {
+                conn.execute_batch(MIGRATE_V11_TO_V12)?;
+            }
+        }
+
+        if version < 13 {
+            // V12 to V13: Add full-text search indexes, filled from existing rows
+            let has_search: bool = conn
+                .query_row(
+                    "SELECT COUNT(*) FROM sqlite_master WHERE name = 'output_logs_fts'",
+                    [],
+                    |row| row.get::<_, i32>(0).map(|c| c > 0),
+                )
+                .unwrap_or(false);
+
+            if !has_search {
+                let tx = conn.unchecked_transaction()?;
+                tx.execute_batch(CREATE_SEARCH_INDEXES)?;
+                tx.execute_batch("INSERT INTO messages_fts (messages_fts) VALUES ('rebuild')")?;
+                {
+                    let mut logs = tx.prepare("SELECT id, content, compressed FROM output_logs")?;
+                    let mut rows = logs.query([])?;
+                    while let Some(row) = rows.next()? {
+                        index_output_log(&tx, row.get(0)?, &decode_output(row, 1, row.get(2)?)?)?;
+                    }
+                }
+                tx.commit()?;
+            }
+        }
+
+        if version < 14 {
+            // V13 to V14: Let sessions be archived
+            let has_archived: bool = conn
+                .query_row(
+                    "SELECT COUNT(*) FROM pragma_table_info('sessions') WHERE name = 'archived'",
+                    [],
+                    |row| row.get::<_, i32>(0).map(|c| c > 0),
+                )
+                .unwrap_or(false);
+
+            if !has_archived {
+                conn.execute_batch(MIGRATE_V13_TO_V14)?;
+            }
+        }
+
+        if version < 15 {
+            // V14 to V15: Free-form metadata on sessions
+            let has_metadata: bool = conn
+                .query_row(
+                    "SELECT COUNT(*) FROM pragma_table_info('sessions') WHERE name = 'metadata'",
+                    [],
+                    |row| row.get::<_, i32>(0).map(|c| c > 0),
+                )
+                .unwrap_or(false);
+
+            if !has_metadata {
+                conn.execute_batch(MIGRATE_V14_TO_V15)?;
+            }
+        }
+
+        if version < SCHEMA_VERSION {
+            conn.execute(UPSERT_SCHEMA_VERSION, params![SCHEMA_VERSION])?;
+        }
+
+        Ok(())
+    }
+
+    // ==================== Repo Operations ====================
+
+    /// Insert a new shared repository
+    pub fn insert_repo(&self, path: &str, name: &str) -> DbResult<Repo> {
+        self.insert_repo_for(path, name, None)
+    }
+
+    /// Insert a new repository owned by a user (`None` for shared)
+    pub fn insert_repo_for(&self, path: &str, name: &str, owner_id: Option<Uuid>) -> DbResult<Repo> {
+        let conn = self.conn.lock().unwrap();
+        let now = Utc::now();
+        let id = Uuid::new_v4();
+
+        conn.execute(
+            "INSERT INTO repos (id, path, name, owner_id, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                id.to_string(),
+                path,
+                name,
+                owner_id.map(|id| id.to_string()),
+                now.to_rfc3339(),
+                now.to_rfc3339()
+            ],
+        )?;
+
+        Ok(Repo {
+            id,
+            path: path.to_string(),
+            name: name.to_string(),
+            owner_id,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    /// Get a repository by ID
+    pub fn get_repo(&self, id: Uuid) -> DbResult<Repo> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.query_row(
+            "SELECT id, path, name, created_at, updated_at, owner_id FROM repos WHERE id = ?1",
+            params![id.to_string()],
+            |row| {
+                Ok(Repo {
+                    id: parse_uuid(row, 0, "id")?,
+                    path: row.get(1)?,
+                    name: row.get(2)?,
+                    owner_id: parse_optional_uuid(row, 5, "owner_id")?,
+                    created_at: parse_datetime(row, 3, "created_at")?,
+                    updated_at: parse_datetime(row, 4, "updated_at")?,
+                })
+            },
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => DbError::NotFound,
+            _ => DbError::Sqlite(e),
+        })
+    }
+
+    /// Get a repository by path
+    pub fn get_repo_by_path(&self, path: &str) -> DbResult<Repo> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.query_row(
+            "SELECT id, path, name, created_at, updated_at, owner_id FROM repos WHERE path = ?1",
+            params![path],
+            |row| {
+                Ok(Repo {
+                    id: parse_uuid(row, 0, "id")?,
+                    path: row.get(1)?,
                     name: row.get(2)?,
+                    owner_id: parse_optional_uuid(row, 5, "owner_id")?,
                     created_at: parse_datetime(row, 3, "created_at")?,
                     updated_at: parse_datetime(row, 4, "updated_at")?,
                 })
@@ -259,7 +1228,7 @@ impl Database {
     pub fn list_repos(&self) -> DbResult<Vec<Repo>> {
         let conn = self.conn.lock().unwrap();
         let mut stmt =
-            conn.prepare("SELECT id, path, name, created_at, updated_at FROM repos ORDER BY name")?;
+            conn.prepare("SELECT id, path, name, created_at, updated_at, owner_id FROM repos ORDER BY name")?;
 
         let repos = stmt
             .query_map([], |row| {
@@ -267,6 +1236,7 @@ impl Database {
                     id: parse_uuid(row, 0, "id")?,
                     path: row.get(1)?,
                     name: row.get(2)?,
+                    owner_id: parse_optional_uuid(row, 5, "owner_id")?,
                     created_at: parse_datetime(row, 3, "created_at")?,
                     updated_at: parse_datetime(row, 4, "updated_at")?,
                 })
@@ -289,20 +1259,32 @@ impl Database {
 
     // ==================== Session Operations ====================
 
-    /// Insert a new session
+    /// Insert a new shared session
     pub fn insert_session(&self, repo_id: Uuid, name: Option<&str>, orchestrator: Orchestrator) -> DbResult<Session> {
+        self.insert_session_for(repo_id, name, orchestrator, None)
+    }
+
+    /// Insert a new session owned by a user (`None` for shared)
+    pub fn insert_session_for(
+        &self,
+        repo_id: Uuid,
+        name: Option<&str>,
+        orchestrator: Orchestrator,
+        owner_id: Option<Uuid>,
+    ) -> DbResult<Session> {
         let conn = self.conn.lock().unwrap();
         let now = Utc::now();
         let id = Uuid::new_v4();
 
         conn.execute(
-            "INSERT INTO sessions (id, repo_id, name, orchestrator, status, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            "INSERT INTO sessions (id, repo_id, name, orchestrator, status, owner_id, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
             params![
                 id.to_string(),
                 repo_id.to_string(),
                 name,
                 orchestrator.as_str(),
                 SessionStatus::Idle.as_str(),
+                owner_id.map(|id| id.to_string()),
                 now.to_rfc3339(),
                 now.to_rfc3339()
             ],
@@ -314,6 +1296,15 @@ impl Database {
             name: name.map(String::from),
             orchestrator,
             status: SessionStatus::Idle,
+            owner_id,
+            pr_url: None,
+            issue_url: None,
+            prompt_draft: None,
+            failure: None,
+            archived: false,
+            archived_at: None,
+            tags: Vec::new(),
+            metadata: serde_json::Map::new(),
             created_at: now,
             updated_at: now,
         })
@@ -324,19 +1315,9 @@ impl Database {
         let conn = self.conn.lock().unwrap();
 
         conn.query_row(
-            "SELECT id, repo_id, name, orchestrator, status, created_at, updated_at FROM sessions WHERE id = ?1",
+            &format!("SELECT {} FROM sessions WHERE id = ?1", SESSION_COLUMNS),
             params![id.to_string()],
-            |row| {
-                Ok(Session {
-                    id: parse_uuid(row, 0, "id")?,
-                    repo_id: parse_uuid(row, 1, "repo_id")?,
-                    name: row.get(2)?,
-                    orchestrator: parse_enum(row, 3, "orchestrator", Orchestrator::from_str)?,
-                    status: parse_enum(row, 4, "status", SessionStatus::from_str)?,
-                    created_at: parse_datetime(row, 5, "created_at")?,
-                    updated_at: parse_datetime(row, 6, "updated_at")?,
-                })
-            },
+            session_from_row,
         )
         .map_err(|e| match e {
             rusqlite::Error::QueryReturnedNoRows => DbError::NotFound,
@@ -347,22 +1328,13 @@ impl Database {
     /// List all sessions
     pub fn list_sessions(&self) -> DbResult<Vec<Session>> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, repo_id, name, orchestrator, status, created_at, updated_at FROM sessions ORDER BY updated_at DESC",
-        )?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM sessions ORDER BY updated_at DESC",
+            SESSION_COLUMNS
+        ))?;
 
         let sessions = stmt
-            .query_map([], |row| {
-                Ok(Session {
-                    id: parse_uuid(row, 0, "id")?,
-                    repo_id: parse_uuid(row, 1, "repo_id")?,
-                    name: row.get(2)?,
-                    orchestrator: parse_enum(row, 3, "orchestrator", Orchestrator::from_str)?,
-                    status: parse_enum(row, 4, "status", SessionStatus::from_str)?,
-                    created_at: parse_datetime(row, 5, "created_at")?,
-                    updated_at: parse_datetime(row, 6, "updated_at")?,
-                })
-            })?
+            .query_map([], session_from_row)?
             .collect::<Result<Vec<_>, _>>()?;
 
         Ok(sessions)
@@ -371,22 +1343,13 @@ impl Database {
     /// List sessions for a specific repository
     pub fn list_sessions_by_repo(&self, repo_id: Uuid) -> DbResult<Vec<Session>> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, repo_id, name, orchestrator, status, created_at, updated_at FROM sessions WHERE repo_id = ?1 ORDER BY updated_at DESC",
-        )?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM sessions WHERE repo_id = ?1 ORDER BY updated_at DESC",
+            SESSION_COLUMNS
+        ))?;
 
         let sessions = stmt
-            .query_map(params![repo_id.to_string()], |row| {
-                Ok(Session {
-                    id: parse_uuid(row, 0, "id")?,
-                    repo_id: parse_uuid(row, 1, "repo_id")?,
-                    name: row.get(2)?,
-                    orchestrator: parse_enum(row, 3, "orchestrator", Orchestrator::from_str)?,
-                    status: parse_enum(row, 4, "status", SessionStatus::from_str)?,
-                    created_at: parse_datetime(row, 5, "created_at")?,
-                    updated_at: parse_datetime(row, 6, "updated_at")?,
-                })
-            })?
+            .query_map(params![repo_id.to_string()], session_from_row)?
             .collect::<Result<Vec<_>, _>>()?;
 
         Ok(sessions)
@@ -408,6 +1371,127 @@ impl Database {
         Ok(())
     }
 
+    /// Archive a session, or restore it when `archived` is false
+    ///
+    /// Archiving an archived session keeps when it was first archived.
+    pub fn set_session_archived(&self, id: Uuid, archived: bool) -> DbResult<()> {
+        let conn = self.conn.lock().unwrap();
+
+        let affected = conn.execute(
+            "UPDATE sessions
+             SET archived = ?1, archived_at = CASE WHEN ?1 THEN COALESCE(archived_at, ?2) END
+             WHERE id = ?3",
+            params![archived, Utc::now().to_rfc3339(), id.to_string()],
+        )?;
+
+        if affected == 0 {
+            return Err(DbError::NotFound);
+        }
+        Ok(())
+    }
+
+    /// Merge a JSON merge patch into a session's metadata, returning the result
+    ///
+    /// Fails with `InvalidData`, changing nothing, if the result would be
+    /// larger than [`MAX_SESSION_METADATA_BYTES`].
+    pub fn patch_session_metadata(
+        &self,
+        id: Uuid,
+        patch: &serde_json::Map<String, serde_json::Value>,
+    ) -> DbResult<serde_json::Map<String, serde_json::Value>> {
+        let conn = self.conn.lock().unwrap();
+
+        let mut metadata = conn
+            .query_row(
+                "SELECT metadata FROM sessions WHERE id = ?1",
+                params![id.to_string()],
+                |row| parse_json_object(row, 0, "metadata"),
+            )
+            .optional()?
+            .ok_or(DbError::NotFound)?;
+        merge_patch(&mut metadata, patch);
+
+        let value =
+            serde_json::to_string(&metadata).map_err(|e| DbError::InvalidData(e.to_string()))?;
+        if value.len() > MAX_SESSION_METADATA_BYTES {
+            return Err(DbError::InvalidData(format!(
+                "Session metadata would be larger than {} bytes",
+                MAX_SESSION_METADATA_BYTES
+            )));
+        }
+        conn.execute(
+            "UPDATE sessions SET metadata = ?1 WHERE id = ?2",
+            params![value, id.to_string()],
+        )?;
+
+        Ok(metadata)
+    }
+
+    /// Put sessions still queued back to idle; queues don't outlive the server
+    pub fn reset_queued_sessions(&self) -> DbResult<usize> {
+        let conn = self.conn.lock().unwrap();
+        let affected = conn.execute(
+            "UPDATE sessions SET status = ?1, updated_at = ?2 WHERE status = ?3",
+            params![
+                SessionStatus::Idle.as_str(),
+                Utc::now().to_rfc3339(),
+                SessionStatus::Queued.as_str()
+            ],
+        )?;
+        Ok(affected)
+    }
+
+    /// Record the pull request opened for a session
+    pub fn set_session_pr_url(&self, id: Uuid, pr_url: &str) -> DbResult<()> {
+        let conn = self.conn.lock().unwrap();
+        let now = Utc::now();
+
+        let affected = conn.execute(
+            "UPDATE sessions SET pr_url = ?1, updated_at = ?2 WHERE id = ?3",
+            params![pr_url, now.to_rfc3339(), id.to_string()],
+        )?;
+
+        if affected == 0 {
+            return Err(DbError::NotFound);
+        }
+        Ok(())
+    }
+
+    /// Record why a session's run failed, or clear it with `None`
+    pub fn set_session_failure(&self, id: Uuid, failure: Option<&Failure>) -> DbResult<()> {
+        let conn = self.conn.lock().unwrap();
+
+        let affected = conn.execute(
+            "UPDATE sessions SET failure_reason = ?1, failure_line = ?2 WHERE id = ?3",
+            params![
+                failure.map(|f| f.reason.as_str()),
+                failure.map(|f| f.line.as_str()),
+                id.to_string()
+            ],
+        )?;
+
+        if affected == 0 {
+            return Err(DbError::NotFound);
+        }
+        Ok(())
+    }
+
+    /// Link a session to the issue it was created from and pre-fill its prompt
+    pub fn set_session_issue(&self, id: Uuid, issue_url: &str, prompt_draft: &str) -> DbResult<()> {
+        let conn = self.conn.lock().unwrap();
+        let now = Utc::now();
+
+        let affected = conn.execute(
+            "UPDATE sessions SET issue_url = ?1, prompt_draft = ?2, updated_at = ?3 WHERE id = ?4",
+            params![issue_url, prompt_draft, now.to_rfc3339(), id.to_string()],
+        )?;
+
+        if affected == 0 {
+            return Err(DbError::NotFound);
+        }
+        Ok(())
+    }
+
     /// Delete a session by ID
     pub fn delete_session(&self, id: Uuid) -> DbResult<()> {
         let conn = self.conn.lock().unwrap();
@@ -420,221 +1504,2356 @@ impl Database {
         Ok(())
     }
 
-    // ==================== Message Operations ====================
-
-    /// Insert a new message
-    pub fn insert_message(
+    /// Insert a session brought in from elsewhere, with its messages and output
+    ///
+    /// Everything gets new ids; timestamps, the name, orchestrator, and status
+    /// are kept. Either all of it is inserted or none.
+    pub fn import_session(
         &self,
-        session_id: Uuid,
-        role: MessageRole,
-        content: &str,
-    ) -> DbResult<Message> {
+        repo_id: Uuid,
+        owner_id: Option<Uuid>,
+        session: &Session,
+        messages: &[Message],
+        output: &[OutputLog],
+    ) -> DbResult<Session> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let id = Uuid::new_v4();
+
+        tx.execute(
+            "INSERT INTO sessions (id, repo_id, name, orchestrator, status, owner_id, failure_reason, failure_line, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                id.to_string(),
+                repo_id.to_string(),
+                session.name,
+                session.orchestrator.as_str(),
+                session.status.as_str(),
+                owner_id.map(|id| id.to_string()),
+                session.failure.as_ref().map(|f| f.reason.as_str()),
+                session.failure.as_ref().map(|f| f.line.as_str()),
+                session.created_at.to_rfc3339(),
+                session.updated_at.to_rfc3339()
+            ],
+        )?;
+        for message in messages {
+            tx.execute(
+                "INSERT INTO messages (id, session_id, role, kind, content, data, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    Uuid::new_v4().to_string(),
+                    id.to_string(),
+                    message.role.as_str(),
+                    message.kind.as_str(),
+                    message.content,
+                    message.data.as_ref().map(|data| data.to_string()),
+                    message.created_at.to_rfc3339()
+                ],
+            )?;
+        }
+        for log in output {
+            let (content, compressed) = encode_output(&log.content);
+            tx.execute(
+                "INSERT INTO output_logs (session_id, stream, channel, content, continues, compressed, size_bytes, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    id.to_string(),
+                    log.stream.as_str(),
+                    log.channel.as_str(),
+                    content,
+                    log.continues,
+                    compressed,
+                    log.content.len(),
+                    log.created_at.to_rfc3339()
+                ],
+            )?;
+            index_output_log(&tx, tx.last_insert_rowid(), &log.content)?;
+        }
+        tx.commit()?;
+
+        Ok(Session {
+            id,
+            repo_id,
+            owner_id,
+            pr_url: None,
+            issue_url: None,
+            prompt_draft: None,
+            archived: false,
+            archived_at: None,
+            tags: Vec::new(),
+            metadata: serde_json::Map::new(),
+            ..session.clone()
+        })
+    }
+
+    // ==================== User Operations ====================
+
+    /// Insert a new user with the hash of their API token
+    pub fn insert_user(&self, username: &str, role: UserRole, token_hash: &str) -> DbResult<User> {
         let conn = self.conn.lock().unwrap();
         let now = Utc::now();
         let id = Uuid::new_v4();
 
         conn.execute(
-            "INSERT INTO messages (id, session_id, role, content, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            "INSERT INTO users (id, username, role, token_hash, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                id.to_string(),
+                username,
+                role.as_str(),
+                token_hash,
+                now.to_rfc3339(),
+                now.to_rfc3339()
+            ],
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::SqliteFailure(err, _)
+                if err.code == rusqlite::ErrorCode::ConstraintViolation =>
+            {
+                DbError::ConstraintViolation(format!("User already exists: {}", username))
+            }
+            _ => DbError::Sqlite(e),
+        })?;
+
+        Ok(User {
+            id,
+            username: username.to_string(),
+            role,
+            desktop_notifications: true,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    /// Get a user by ID
+    pub fn get_user(&self, id: Uuid) -> DbResult<User> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.query_row(
+            &format!("SELECT {} FROM users WHERE id = ?1", USER_COLUMNS),
+            params![id.to_string()],
+            user_from_row,
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => DbError::NotFound,
+            _ => DbError::Sqlite(e),
+        })
+    }
+
+    /// Get the user an API token hash belongs to
+    pub fn get_user_by_token_hash(&self, token_hash: &str) -> DbResult<User> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.query_row(
+            &format!("SELECT {} FROM users WHERE token_hash = ?1", USER_COLUMNS),
+            params![token_hash],
+            user_from_row,
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => DbError::NotFound,
+            _ => DbError::Sqlite(e),
+        })
+    }
+
+    /// List all users
+    pub fn list_users(&self) -> DbResult<Vec<User>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt =
+            conn.prepare(&format!("SELECT {} FROM users ORDER BY username", USER_COLUMNS))?;
+
+        let users = stmt
+            .query_map([], user_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(users)
+    }
+
+    /// Change a user's role
+    pub fn update_user_role(&self, id: Uuid, role: UserRole) -> DbResult<()> {
+        let conn = self.conn.lock().unwrap();
+        let now = Utc::now();
+
+        let affected = conn.execute(
+            "UPDATE users SET role = ?1, updated_at = ?2 WHERE id = ?3",
+            params![role.as_str(), now.to_rfc3339(), id.to_string()],
+        )?;
+
+        if affected == 0 {
+            return Err(DbError::NotFound);
+        }
+        Ok(())
+    }
+
+    /// Turn a user's desktop notifications on or off
+    pub fn set_user_desktop_notifications(&self, id: Uuid, enabled: bool) -> DbResult<()> {
+        let conn = self.conn.lock().unwrap();
+        let now = Utc::now();
+
+        let affected = conn.execute(
+            "UPDATE users SET desktop_notifications = ?1, updated_at = ?2 WHERE id = ?3",
+            params![enabled, now.to_rfc3339(), id.to_string()],
+        )?;
+
+        if affected == 0 {
+            return Err(DbError::NotFound);
+        }
+        Ok(())
+    }
+
+    /// Delete a user; their repos and sessions become shared
+    pub fn delete_user(&self, id: Uuid) -> DbResult<()> {
+        let conn = self.conn.lock().unwrap();
+        let affected = conn.execute("DELETE FROM users WHERE id = ?1", params![id.to_string()])?;
+
+        if affected == 0 {
+            return Err(DbError::NotFound);
+        }
+        Ok(())
+    }
+
+    // ==================== Webhook Operations ====================
+
+    /// Insert a new webhook endpoint
+    pub fn insert_webhook(
+        &self,
+        url: &str,
+        secret: &str,
+        events: &[String],
+        enabled: bool,
+    ) -> DbResult<Webhook> {
+        let conn = self.conn.lock().unwrap();
+        let now = Utc::now();
+        let id = Uuid::new_v4();
+
+        conn.execute(
+            "INSERT INTO webhooks (id, url, secret, events, enabled, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                id.to_string(),
+                url,
+                secret,
+                events.join(","),
+                enabled,
+                now.to_rfc3339(),
+                now.to_rfc3339()
+            ],
+        )?;
+
+        Ok(Webhook {
+            id,
+            url: url.to_string(),
+            secret: secret.to_string(),
+            events: events.to_vec(),
+            enabled,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    /// Get a webhook by ID
+    pub fn get_webhook(&self, id: Uuid) -> DbResult<Webhook> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.query_row(
+            &format!("SELECT {} FROM webhooks WHERE id = ?1", WEBHOOK_COLUMNS),
+            params![id.to_string()],
+            webhook_from_row,
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => DbError::NotFound,
+            _ => DbError::Sqlite(e),
+        })
+    }
+
+    /// List all webhooks
+    pub fn list_webhooks(&self) -> DbResult<Vec<Webhook>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM webhooks ORDER BY created_at",
+            WEBHOOK_COLUMNS
+        ))?;
+
+        let webhooks = stmt
+            .query_map([], webhook_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(webhooks)
+    }
+
+    /// Update a webhook's URL, event filter, and enabled flag
+    pub fn update_webhook(
+        &self,
+        id: Uuid,
+        url: &str,
+        events: &[String],
+        enabled: bool,
+    ) -> DbResult<()> {
+        let conn = self.conn.lock().unwrap();
+        let now = Utc::now();
+
+        let affected = conn.execute(
+            "UPDATE webhooks SET url = ?1, events = ?2, enabled = ?3, updated_at = ?4 WHERE id = ?5",
+            params![url, events.join(","), enabled, now.to_rfc3339(), id.to_string()],
+        )?;
+
+        if affected == 0 {
+            return Err(DbError::NotFound);
+        }
+        Ok(())
+    }
+
+    /// Delete a webhook and its delivery log
+    pub fn delete_webhook(&self, id: Uuid) -> DbResult<()> {
+        let conn = self.conn.lock().unwrap();
+        let affected =
+            conn.execute("DELETE FROM webhooks WHERE id = ?1", params![id.to_string()])?;
+
+        if affected == 0 {
+            return Err(DbError::NotFound);
+        }
+        Ok(())
+    }
+
+    /// Record a new pending delivery
+    pub fn insert_webhook_delivery(
+        &self,
+        webhook_id: Uuid,
+        event: &str,
+        payload: &serde_json::Value,
+    ) -> DbResult<WebhookDelivery> {
+        let conn = self.conn.lock().unwrap();
+        let now = Utc::now();
+        let id = Uuid::new_v4();
+
+        conn.execute(
+            "INSERT INTO webhook_deliveries (id, webhook_id, event, payload, status, attempts, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, 0, ?6, ?7)",
+            params![
+                id.to_string(),
+                webhook_id.to_string(),
+                event,
+                payload.to_string(),
+                DeliveryStatus::Pending.as_str(),
+                now.to_rfc3339(),
+                now.to_rfc3339()
+            ],
+        )?;
+
+        Ok(WebhookDelivery {
+            id,
+            webhook_id,
+            event: event.to_string(),
+            payload: payload.clone(),
+            status: DeliveryStatus::Pending,
+            attempts: 0,
+            response_status: None,
+            error: None,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    /// Record the outcome of a delivery attempt
+    pub fn update_webhook_delivery(
+        &self,
+        id: Uuid,
+        status: DeliveryStatus,
+        attempts: u32,
+        response_status: Option<u16>,
+        error: Option<&str>,
+    ) -> DbResult<()> {
+        let conn = self.conn.lock().unwrap();
+        let now = Utc::now();
+
+        let affected = conn.execute(
+            "UPDATE webhook_deliveries SET status = ?1, attempts = ?2, response_status = ?3, error = ?4, updated_at = ?5 WHERE id = ?6",
+            params![
+                status.as_str(),
+                attempts,
+                response_status,
+                error,
+                now.to_rfc3339(),
+                id.to_string()
+            ],
+        )?;
+
+        if affected == 0 {
+            return Err(DbError::NotFound);
+        }
+        Ok(())
+    }
+
+    /// Get a delivery by ID
+    pub fn get_webhook_delivery(&self, id: Uuid) -> DbResult<WebhookDelivery> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.query_row(
+            &format!(
+                "SELECT {} FROM webhook_deliveries WHERE id = ?1",
+                WEBHOOK_DELIVERY_COLUMNS
+            ),
+            params![id.to_string()],
+            webhook_delivery_from_row,
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => DbError::NotFound,
+            _ => DbError::Sqlite(e),
+        })
+    }
+
+    /// List the most recent deliveries for a webhook, newest first
+    pub fn list_webhook_deliveries(
+        &self,
+        webhook_id: Uuid,
+        limit: i64,
+    ) -> DbResult<Vec<WebhookDelivery>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM webhook_deliveries WHERE webhook_id = ?1 ORDER BY created_at DESC LIMIT ?2",
+            WEBHOOK_DELIVERY_COLUMNS
+        ))?;
+
+        let deliveries = stmt
+            .query_map(params![webhook_id.to_string(), limit], webhook_delivery_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(deliveries)
+    }
+
+    /// Delete finished deliveries created before `before`, returning how many were removed
+    pub fn prune_webhook_deliveries(&self, before: DateTime<Utc>) -> DbResult<usize> {
+        let conn = self.conn.lock().unwrap();
+        let deleted = conn.execute(
+            "DELETE FROM webhook_deliveries WHERE status != ?1 AND created_at < ?2",
+            params![DeliveryStatus::Pending.as_str(), before.to_rfc3339()],
+        )?;
+
+        Ok(deleted)
+    }
+
+    // ==================== Trigger Operations ====================
+
+    /// Insert a new, enabled trigger
+    pub fn insert_trigger(
+        &self,
+        name: &str,
+        repo_id: Uuid,
+        session_id: Option<Uuid>,
+        prompt_template: &str,
+        orchestrator: Orchestrator,
+        token_hash: &str,
+    ) -> DbResult<Trigger> {
+        let conn = self.conn.lock().unwrap();
+        let now = Utc::now();
+        let id = Uuid::new_v4();
+
+        conn.execute(
+            "INSERT INTO triggers (id, name, repo_id, session_id, prompt_template, orchestrator, token_hash, enabled, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 1, ?8, ?9)",
+            params![
+                id.to_string(),
+                name,
+                repo_id.to_string(),
+                session_id.map(|id| id.to_string()),
+                prompt_template,
+                orchestrator.as_str(),
+                token_hash,
+                now.to_rfc3339(),
+                now.to_rfc3339()
+            ],
+        )?;
+
+        Ok(Trigger {
+            id,
+            name: name.to_string(),
+            repo_id,
+            session_id,
+            prompt_template: prompt_template.to_string(),
+            orchestrator,
+            enabled: true,
+            last_triggered_at: None,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    /// Get a trigger by ID
+    pub fn get_trigger(&self, id: Uuid) -> DbResult<Trigger> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.query_row(
+            &format!("SELECT {} FROM triggers WHERE id = ?1", TRIGGER_COLUMNS),
+            params![id.to_string()],
+            trigger_from_row,
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => DbError::NotFound,
+            _ => DbError::Sqlite(e),
+        })
+    }
+
+    /// Get the trigger whose token hashes to `token_hash`
+    pub fn get_trigger_by_token_hash(&self, token_hash: &str) -> DbResult<Trigger> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.query_row(
+            &format!("SELECT {} FROM triggers WHERE token_hash = ?1", TRIGGER_COLUMNS),
+            params![token_hash],
+            trigger_from_row,
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => DbError::NotFound,
+            _ => DbError::Sqlite(e),
+        })
+    }
+
+    /// List all triggers
+    pub fn list_triggers(&self) -> DbResult<Vec<Trigger>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM triggers ORDER BY created_at",
+            TRIGGER_COLUMNS
+        ))?;
+
+        let triggers = stmt
+            .query_map([], trigger_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(triggers)
+    }
+
+    /// Update a trigger's name, target session, prompt, orchestrator, and enabled flag
+    pub fn update_trigger(
+        &self,
+        id: Uuid,
+        name: &str,
+        session_id: Option<Uuid>,
+        prompt_template: &str,
+        orchestrator: Orchestrator,
+        enabled: bool,
+    ) -> DbResult<()> {
+        let conn = self.conn.lock().unwrap();
+        let now = Utc::now();
+
+        let affected = conn.execute(
+            "UPDATE triggers SET name = ?1, session_id = ?2, prompt_template = ?3, orchestrator = ?4, enabled = ?5, updated_at = ?6 WHERE id = ?7",
+            params![
+                name,
+                session_id.map(|id| id.to_string()),
+                prompt_template,
+                orchestrator.as_str(),
+                enabled,
+                now.to_rfc3339(),
+                id.to_string()
+            ],
+        )?;
+
+        if affected == 0 {
+            return Err(DbError::NotFound);
+        }
+        Ok(())
+    }
+
+    /// Record that a trigger was called
+    pub fn mark_trigger_fired(&self, id: Uuid) -> DbResult<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE triggers SET last_triggered_at = ?1 WHERE id = ?2",
+            params![Utc::now().to_rfc3339(), id.to_string()],
+        )?;
+        Ok(())
+    }
+
+    /// Delete a trigger
+    pub fn delete_trigger(&self, id: Uuid) -> DbResult<()> {
+        let conn = self.conn.lock().unwrap();
+        let affected =
+            conn.execute("DELETE FROM triggers WHERE id = ?1", params![id.to_string()])?;
+
+        if affected == 0 {
+            return Err(DbError::NotFound);
+        }
+        Ok(())
+    }
+
+    // ==================== Snapshot Operations ====================
+
+    /// Record a snapshot whose files have been written
+    pub fn insert_snapshot(&self, snapshot: &Snapshot) -> DbResult<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO snapshots (id, session_id, repo_id, reason, head_commit, file_count, deleted_count, size_bytes, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                snapshot.id.to_string(),
+                snapshot.session_id.to_string(),
+                snapshot.repo_id.to_string(),
+                snapshot.reason.as_str(),
+                snapshot.head_commit,
+                snapshot.file_count,
+                snapshot.deleted_count,
+                snapshot.size_bytes,
+                snapshot.created_at.to_rfc3339()
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Get a snapshot by ID
+    pub fn get_snapshot(&self, id: Uuid) -> DbResult<Snapshot> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.query_row(
+            &format!("SELECT {} FROM snapshots WHERE id = ?1", SNAPSHOT_COLUMNS),
+            params![id.to_string()],
+            snapshot_from_row,
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => DbError::NotFound,
+            _ => DbError::Sqlite(e),
+        })
+    }
+
+    /// List a session's snapshots, newest first
+    pub fn list_snapshots_by_session(&self, session_id: Uuid) -> DbResult<Vec<Snapshot>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM snapshots WHERE session_id = ?1 ORDER BY created_at DESC",
+            SNAPSHOT_COLUMNS
+        ))?;
+
+        let snapshots = stmt
+            .query_map(params![session_id.to_string()], snapshot_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(snapshots)
+    }
+
+    /// Delete a snapshot record
+    pub fn delete_snapshot(&self, id: Uuid) -> DbResult<()> {
+        let conn = self.conn.lock().unwrap();
+        let affected =
+            conn.execute("DELETE FROM snapshots WHERE id = ?1", params![id.to_string()])?;
+
+        if affected == 0 {
+            return Err(DbError::NotFound);
+        }
+        Ok(())
+    }
+
+    /// List snapshots taken before `before`, oldest first
+    pub fn list_snapshots_before(&self, before: DateTime<Utc>) -> DbResult<Vec<Snapshot>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM snapshots WHERE created_at < ?1 ORDER BY created_at",
+            SNAPSHOT_COLUMNS
+        ))?;
+
+        let snapshots = stmt
+            .query_map(params![before.to_rfc3339()], snapshot_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(snapshots)
+    }
+
+    /// IDs of every snapshot on record
+    pub fn list_snapshot_ids(&self) -> DbResult<HashSet<Uuid>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT id FROM snapshots")?;
+
+        let ids = stmt
+            .query_map([], |row| parse_uuid(row, 0, "id"))?
+            .collect::<Result<HashSet<_>, _>>()?;
+
+        Ok(ids)
+    }
+
+    // ==================== Prompt History Operations ====================
+
+    /// Remember a prompt run on a repository, bumping it if it was run before
+    pub fn record_prompt(&self, repo_id: Uuid, prompt: &str) -> DbResult<()> {
+        let conn = self.conn.lock().unwrap();
+        let now = Utc::now().to_rfc3339();
+
+        conn.execute(
+            "INSERT INTO prompt_history (repo_id, prompt, use_count, last_used_at, created_at) VALUES (?1, ?2, 1, ?3, ?3)
+             ON CONFLICT (repo_id, prompt) DO UPDATE SET use_count = use_count + 1, last_used_at = excluded.last_used_at",
+            params![repo_id.to_string(), prompt, now],
+        )?;
+        conn.execute(
+            "DELETE FROM prompt_history WHERE repo_id = ?1 AND id NOT IN (
+                SELECT id FROM prompt_history WHERE repo_id = ?1 ORDER BY last_used_at DESC, id DESC LIMIT ?2
+            )",
+            params![repo_id.to_string(), MAX_PROMPT_HISTORY as i64],
+        )?;
+        Ok(())
+    }
+
+    /// List a repository's prompts, most recently used first, optionally only
+    /// those containing `query` (case-insensitive)
+    pub fn list_recent_prompts(
+        &self,
+        repo_id: Uuid,
+        query: Option<&str>,
+    ) -> DbResult<Vec<PromptHistoryEntry>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM prompt_history WHERE repo_id = ?1 AND (?2 IS NULL OR instr(lower(prompt), lower(?2)) > 0) ORDER BY last_used_at DESC, id DESC",
+            PROMPT_HISTORY_COLUMNS
+        ))?;
+
+        let prompts = stmt
+            .query_map(params![repo_id.to_string(), query], prompt_history_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(prompts)
+    }
+
+    // ==================== Activity Operations ====================
+
+    /// Add an entry to the activity feed
+    pub fn record_activity(
+        &self,
+        kind: ActivityKind,
+        repo_id: Uuid,
+        session_id: Option<Uuid>,
+        summary: &str,
+    ) -> DbResult<()> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.execute(
+            "INSERT INTO activity (kind, repo_id, session_id, summary, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                kind.as_str(),
+                repo_id.to_string(),
+                session_id.map(|id| id.to_string()),
+                summary,
+                Utc::now().to_rfc3339()
+            ],
+        )?;
+        conn.execute(
+            "DELETE FROM activity WHERE id NOT IN (SELECT id FROM activity ORDER BY id DESC LIMIT ?1)",
+            params![MAX_ACTIVITY as i64],
+        )?;
+        Ok(())
+    }
+
+    /// List activity entries, newest first
+    pub fn list_activity(&self) -> DbResult<Vec<ActivityEvent>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM activity ORDER BY id DESC",
+            ACTIVITY_COLUMNS
+        ))?;
+
+        let events = stmt
+            .query_map([], activity_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(events)
+    }
+
+    // ==================== Analytics Operations ====================
+
+    /// Count activity and output added since the last refresh into the daily totals
+    pub fn refresh_daily_stats(&self) -> DbResult<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        // Runs and their outcomes come from the activity feed
+        let last_activity = aggregate_progress(&tx, "activity")?;
+        let events = {
+            let mut stmt = tx.prepare(&format!(
+                "SELECT {} FROM activity WHERE id > ?1 ORDER BY id",
+                ACTIVITY_COLUMNS
+            ))?;
+            stmt.query_map(params![last_activity], activity_from_row)?
+                .collect::<Result<Vec<_>, _>>()?
+        };
+        for event in &events {
+            let counted = match event.kind {
+                ActivityKind::SessionStarted => Some((
+                    day_of(event.created_at),
+                    RunStats {
+                        runs: 1,
+                        ..RunStats::default()
+                    },
+                )),
+                ActivityKind::SessionFinished => finished_run(&tx, event)?,
+                _ => None,
+            };
+            if let Some((day, stats)) = counted {
+                add_daily_stats(&tx, &day, &event.repo_id.to_string(), &stats)?;
+            }
+        }
+        if let Some(last) = events.last() {
+            set_aggregate_progress(&tx, "activity", last.id)?;
+        }
+
+        // Output volume comes from the output logs
+        let last_output = aggregate_progress(&tx, "output_logs")?;
+        let newest_output: Option<i64> =
+            tx.query_row("SELECT MAX(id) FROM output_logs", [], |row| row.get(0))?;
+        if let Some(newest) = newest_output.filter(|newest| *newest > last_output) {
+            let totals = {
+                let mut stmt = tx.prepare(
+                    "SELECT substr(o.created_at, 1, 10), s.repo_id, COUNT(*), SUM(COALESCE(o.size_bytes, length(CAST(o.content AS BLOB))))
+                     FROM output_logs o JOIN sessions s ON s.id = o.session_id
+                     WHERE o.id > ?1 AND o.id <= ?2
+                     GROUP BY 1, 2",
+                )?;
+                stmt.query_map(params![last_output, newest], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, u64>(2)?,
+                        row.get::<_, u64>(3)?,
+                    ))
+                })?
+                .collect::<Result<Vec<_>, _>>()?
+            };
+            for (day, repo_id, lines, bytes) in totals {
+                let stats = RunStats {
+                    output_lines: lines,
+                    output_bytes: bytes,
+                    ..RunStats::default()
+                };
+                add_daily_stats(&tx, &day, &repo_id, &stats)?;
+            }
+            set_aggregate_progress(&tx, "output_logs", newest)?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// List daily totals between two days (inclusive), optionally for one repository
+    pub fn list_daily_stats(
+        &self,
+        repo_id: Option<Uuid>,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> DbResult<Vec<DailyStats>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM daily_stats WHERE day >= ?1 AND day <= ?2 AND (?3 IS NULL OR repo_id = ?3) ORDER BY day, repo_id",
+            DAILY_STATS_COLUMNS
+        ))?;
+
+        let stats = stmt
+            .query_map(
+                params![
+                    from.format("%Y-%m-%d").to_string(),
+                    to.format("%Y-%m-%d").to_string(),
+                    repo_id.map(|id| id.to_string())
+                ],
+                daily_stats_from_row,
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(stats)
+    }
+
+    // ==================== Artifact Operations ====================
+
+    /// Record an artifact found after a run; a path already recorded for the
+    /// session is updated instead
+    pub fn record_artifact(
+        &self,
+        session_id: Uuid,
+        repo_id: Uuid,
+        path: &str,
+        size_bytes: u64,
+    ) -> DbResult<Artifact> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.execute(
+            "INSERT INTO artifacts (id, session_id, repo_id, path, size_bytes, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT (session_id, path) DO UPDATE SET size_bytes = excluded.size_bytes, created_at = excluded.created_at",
+            params![
+                Uuid::new_v4().to_string(),
+                session_id.to_string(),
+                repo_id.to_string(),
+                path,
+                size_bytes,
+                Utc::now().to_rfc3339()
+            ],
+        )?;
+
+        let artifact = conn.query_row(
+            &format!("SELECT {} FROM artifacts WHERE session_id = ?1 AND path = ?2", ARTIFACT_COLUMNS),
+            params![session_id.to_string(), path],
+            artifact_from_row,
+        )?;
+        Ok(artifact)
+    }
+
+    /// Get an artifact by ID
+    pub fn get_artifact(&self, id: Uuid) -> DbResult<Artifact> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.query_row(
+            &format!("SELECT {} FROM artifacts WHERE id = ?1", ARTIFACT_COLUMNS),
+            params![id.to_string()],
+            artifact_from_row,
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => DbError::NotFound,
+            _ => DbError::Sqlite(e),
+        })
+    }
+
+    /// List a session's artifacts, most recently detected first
+    pub fn list_artifacts_by_session(&self, session_id: Uuid) -> DbResult<Vec<Artifact>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM artifacts WHERE session_id = ?1 ORDER BY created_at DESC, path",
+            ARTIFACT_COLUMNS
+        ))?;
+
+        let artifacts = stmt
+            .query_map(params![session_id.to_string()], artifact_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(artifacts)
+    }
+
+    // ==================== Upload Operations ====================
+
+    /// Record an upload whose file has been stored under `id`
+    pub fn insert_upload(
+        &self,
+        id: Uuid,
+        owner_id: Option<Uuid>,
+        filename: &str,
+        content_type: Option<&str>,
+        size_bytes: u64,
+    ) -> DbResult<Upload> {
+        let conn = self.conn.lock().unwrap();
+        let now = Utc::now();
+
+        conn.execute(
+            &format!("INSERT INTO uploads ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6)", UPLOAD_COLUMNS),
+            params![
+                id.to_string(),
+                owner_id.map(|id| id.to_string()),
+                filename,
+                content_type,
+                size_bytes,
+                now.to_rfc3339()
+            ],
+        )?;
+
+        Ok(Upload {
+            id,
+            owner_id,
+            filename: filename.to_string(),
+            content_type: content_type.map(String::from),
+            size_bytes,
+            created_at: now,
+        })
+    }
+
+    /// Get an upload by id
+    pub fn get_upload(&self, id: Uuid) -> DbResult<Upload> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.query_row(
+            &format!("SELECT {} FROM uploads WHERE id = ?1", UPLOAD_COLUMNS),
+            params![id.to_string()],
+            upload_from_row,
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => DbError::NotFound,
+            _ => DbError::Sqlite(e),
+        })
+    }
+
+    /// Delete an upload's record
+    pub fn delete_upload(&self, id: Uuid) -> DbResult<()> {
+        let conn = self.conn.lock().unwrap();
+
+        let affected = conn.execute("DELETE FROM uploads WHERE id = ?1", params![id.to_string()])?;
+        if affected == 0 {
+            return Err(DbError::NotFound);
+        }
+
+        Ok(())
+    }
+
+    // ==================== Attachment Operations ====================
+
+    /// Record a session attachment whose file has been stored under `id`
+    pub fn insert_attachment(
+        &self,
+        id: Uuid,
+        session_id: Uuid,
+        filename: &str,
+        content_type: Option<&str>,
+        size_bytes: u64,
+    ) -> DbResult<Attachment> {
+        let conn = self.conn.lock().unwrap();
+        let now = Utc::now();
+
+        conn.execute(
+            &format!("INSERT INTO attachments ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6)", ATTACHMENT_COLUMNS),
+            params![
+                id.to_string(),
+                session_id.to_string(),
+                filename,
+                content_type,
+                size_bytes,
+                now.to_rfc3339()
+            ],
+        )?;
+
+        Ok(Attachment {
+            id,
+            session_id,
+            filename: filename.to_string(),
+            content_type: content_type.map(String::from),
+            size_bytes,
+            created_at: now,
+        })
+    }
+
+    /// Get an attachment by id
+    pub fn get_attachment(&self, id: Uuid) -> DbResult<Attachment> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.query_row(
+            &format!("SELECT {} FROM attachments WHERE id = ?1", ATTACHMENT_COLUMNS),
+            params![id.to_string()],
+            attachment_from_row,
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => DbError::NotFound,
+            _ => DbError::Sqlite(e),
+        })
+    }
+
+    /// List a session's attachments, oldest first
+    pub fn list_attachments(&self, session_id: Uuid) -> DbResult<Vec<Attachment>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM attachments WHERE session_id = ?1 ORDER BY created_at ASC",
+            ATTACHMENT_COLUMNS
+        ))?;
+
+        let attachments = stmt
+            .query_map(params![session_id.to_string()], attachment_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(attachments)
+    }
+
+    /// Environment variables a session's runs are started with, by name
+    pub fn get_session_env(&self, session_id: Uuid) -> DbResult<BTreeMap<String, String>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT name, value FROM session_env WHERE session_id = ?1")?;
+
+        let env = stmt
+            .query_map(params![session_id.to_string()], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<BTreeMap<_, _>, _>>()?;
+
+        Ok(env)
+    }
+
+    /// Replace a session's environment variables
+    pub fn set_session_env(&self, session_id: Uuid, env: &BTreeMap<String, String>) -> DbResult<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "DELETE FROM session_env WHERE session_id = ?1",
+            params![session_id.to_string()],
+        )?;
+        for (name, value) in env {
+            tx.execute(
+                "INSERT INTO session_env (session_id, name, value) VALUES (?1, ?2, ?3)",
+                params![session_id.to_string(), name, value],
+            )?;
+        }
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    /// List the tags attached to a session, in alphabetical order
+    pub fn list_session_tags(&self, session_id: Uuid) -> DbResult<Vec<String>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT tag FROM session_tags WHERE session_id = ?1 ORDER BY tag")?;
+
+        let tags = stmt
+            .query_map(params![session_id.to_string()], |row| row.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(tags)
+    }
+
+    /// Attach a tag to a session; attaching one it already has does nothing
+    pub fn add_session_tag(&self, session_id: Uuid, tag: &str) -> DbResult<()> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.execute(
+            "INSERT OR IGNORE INTO session_tags (session_id, tag) VALUES (?1, ?2)",
+            params![session_id.to_string(), tag],
+        )?;
+
+        Ok(())
+    }
+
+    /// Remove a tag from a session
+    pub fn remove_session_tag(&self, session_id: Uuid, tag: &str) -> DbResult<()> {
+        let conn = self.conn.lock().unwrap();
+
+        let affected = conn.execute(
+            "DELETE FROM session_tags WHERE session_id = ?1 AND tag = ?2",
+            params![session_id.to_string(), tag],
+        )?;
+        if affected == 0 {
+            return Err(DbError::NotFound);
+        }
+
+        Ok(())
+    }
+
+    /// Delete an attachment's record
+    pub fn delete_attachment(&self, id: Uuid) -> DbResult<()> {
+        let conn = self.conn.lock().unwrap();
+
+        let affected = conn.execute("DELETE FROM attachments WHERE id = ?1", params![id.to_string()])?;
+        if affected == 0 {
+            return Err(DbError::NotFound);
+        }
+
+        Ok(())
+    }
+
+    // ==================== Bookmark Operations ====================
+
+    /// Pin a path in a repository
+    pub fn insert_bookmark(&self, repo_id: Uuid, path: &str, label: Option<&str>) -> DbResult<Bookmark> {
+        let conn = self.conn.lock().unwrap();
+        let now = Utc::now();
+        let id = Uuid::new_v4();
+
+        conn.execute(
+            &format!("INSERT INTO bookmarks ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6)", BOOKMARK_COLUMNS),
+            params![
+                id.to_string(),
+                repo_id.to_string(),
+                path,
+                label,
+                now.to_rfc3339(),
+                now.to_rfc3339()
+            ],
+        )
+        .map_err(|e| bookmark_conflict(e, path))?;
+
+        Ok(Bookmark {
+            id,
+            repo_id,
+            path: path.to_string(),
+            label: label.map(String::from),
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    /// Get a bookmark by id
+    pub fn get_bookmark(&self, id: Uuid) -> DbResult<Bookmark> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.query_row(
+            &format!("SELECT {} FROM bookmarks WHERE id = ?1", BOOKMARK_COLUMNS),
+            params![id.to_string()],
+            bookmark_from_row,
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => DbError::NotFound,
+            _ => DbError::Sqlite(e),
+        })
+    }
+
+    /// List a repository's bookmarks by path
+    pub fn list_bookmarks(&self, repo_id: Uuid) -> DbResult<Vec<Bookmark>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM bookmarks WHERE repo_id = ?1 ORDER BY path ASC",
+            BOOKMARK_COLUMNS
+        ))?;
+
+        let bookmarks = stmt
+            .query_map(params![repo_id.to_string()], bookmark_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(bookmarks)
+    }
+
+    /// Change a bookmark's path and label
+    pub fn update_bookmark(&self, id: Uuid, path: &str, label: Option<&str>) -> DbResult<()> {
+        let conn = self.conn.lock().unwrap();
+        let now = Utc::now();
+
+        let affected = conn
+            .execute(
+                "UPDATE bookmarks SET path = ?1, label = ?2, updated_at = ?3 WHERE id = ?4",
+                params![path, label, now.to_rfc3339(), id.to_string()],
+            )
+            .map_err(|e| bookmark_conflict(e, path))?;
+
+        if affected == 0 {
+            return Err(DbError::NotFound);
+        }
+        Ok(())
+    }
+
+    /// Delete a bookmark
+    pub fn delete_bookmark(&self, id: Uuid) -> DbResult<()> {
+        let conn = self.conn.lock().unwrap();
+
+        let affected = conn.execute("DELETE FROM bookmarks WHERE id = ?1", params![id.to_string()])?;
+        if affected == 0 {
+            return Err(DbError::NotFound);
+        }
+        Ok(())
+    }
+
+    // ==================== Worktree Operations ====================
+
+    /// Record a worktree checked out at `path`
+    pub fn insert_worktree(
+        &self,
+        repo_id: Uuid,
+        session_id: Option<Uuid>,
+        branch: &str,
+        path: &str,
+    ) -> DbResult<Worktree> {
+        let conn = self.conn.lock().unwrap();
+        let now = Utc::now();
+        let id = Uuid::new_v4();
+
+        conn.execute(
+            &format!("INSERT INTO worktrees ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6)", WORKTREE_COLUMNS),
+            params![
+                id.to_string(),
+                repo_id.to_string(),
+                session_id.map(|id| id.to_string()),
+                branch,
+                path,
+                now.to_rfc3339()
+            ],
+        )
+        .map_err(worktree_conflict)?;
+
+        Ok(Worktree {
+            id,
+            repo_id,
+            session_id,
+            branch: branch.to_string(),
+            path: path.to_string(),
+            created_at: now,
+        })
+    }
+
+    /// Get a worktree by id
+    pub fn get_worktree(&self, id: Uuid) -> DbResult<Worktree> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.query_row(
+            &format!("SELECT {} FROM worktrees WHERE id = ?1", WORKTREE_COLUMNS),
+            params![id.to_string()],
+            worktree_from_row,
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => DbError::NotFound,
+            _ => DbError::Sqlite(e),
+        })
+    }
+
+    /// The worktree a session works in, if it has one
+    pub fn get_session_worktree(&self, session_id: Uuid) -> DbResult<Option<Worktree>> {
+        let conn = self.conn.lock().unwrap();
+
+        Ok(conn
+            .query_row(
+                &format!("SELECT {} FROM worktrees WHERE session_id = ?1", WORKTREE_COLUMNS),
+                params![session_id.to_string()],
+                worktree_from_row,
+            )
+            .optional()?)
+    }
+
+    /// List a repository's worktrees, oldest first
+    pub fn list_worktrees(&self, repo_id: Uuid) -> DbResult<Vec<Worktree>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM worktrees WHERE repo_id = ?1 ORDER BY created_at ASC",
+            WORKTREE_COLUMNS
+        ))?;
+
+        let worktrees = stmt
+            .query_map(params![repo_id.to_string()], worktree_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(worktrees)
+    }
+
+    /// Delete a worktree's record
+    pub fn delete_worktree(&self, id: Uuid) -> DbResult<()> {
+        let conn = self.conn.lock().unwrap();
+
+        let affected = conn.execute("DELETE FROM worktrees WHERE id = ?1", params![id.to_string()])?;
+        if affected == 0 {
+            return Err(DbError::NotFound);
+        }
+        Ok(())
+    }
+
+    // ==================== Schedule Operations ====================
+
+    /// Insert a new, enabled schedule
+    pub fn insert_schedule(
+        &self,
+        name: &str,
+        repo_id: Uuid,
+        prompt: &str,
+        cron: &str,
+        orchestrator: Orchestrator,
+        next_run_at: Option<DateTime<Utc>>,
+    ) -> DbResult<Schedule> {
+        let conn = self.conn.lock().unwrap();
+        let now = Utc::now();
+        let id = Uuid::new_v4();
+
+        conn.execute(
+            "INSERT INTO schedules (id, name, repo_id, prompt, cron, orchestrator, enabled, next_run_at, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, 1, ?7, ?8, ?9)",
+            params![
+                id.to_string(),
+                name,
+                repo_id.to_string(),
+                prompt,
+                cron,
+                orchestrator.as_str(),
+                next_run_at.map(|at| at.to_rfc3339()),
+                now.to_rfc3339(),
+                now.to_rfc3339()
+            ],
+        )?;
+
+        Ok(Schedule {
+            id,
+            name: name.to_string(),
+            repo_id,
+            prompt: prompt.to_string(),
+            cron: cron.to_string(),
+            orchestrator,
+            enabled: true,
+            next_run_at,
+            last_run_at: None,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    /// Get a schedule by ID
+    pub fn get_schedule(&self, id: Uuid) -> DbResult<Schedule> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.query_row(
+            &format!("SELECT {} FROM schedules WHERE id = ?1", SCHEDULE_COLUMNS),
+            params![id.to_string()],
+            schedule_from_row,
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => DbError::NotFound,
+            _ => DbError::Sqlite(e),
+        })
+    }
+
+    /// List all schedules
+    pub fn list_schedules(&self) -> DbResult<Vec<Schedule>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM schedules ORDER BY created_at",
+            SCHEDULE_COLUMNS
+        ))?;
+
+        let schedules = stmt
+            .query_map([], schedule_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(schedules)
+    }
+
+    /// Save a schedule's name, prompt, expression, orchestrator, enabled flag, and next time
+    pub fn update_schedule(&self, schedule: &Schedule) -> DbResult<()> {
+        let conn = self.conn.lock().unwrap();
+        let now = Utc::now();
+
+        let affected = conn.execute(
+            "UPDATE schedules SET name = ?1, prompt = ?2, cron = ?3, orchestrator = ?4, enabled = ?5, next_run_at = ?6, updated_at = ?7 WHERE id = ?8",
+            params![
+                schedule.name,
+                schedule.prompt,
+                schedule.cron,
+                schedule.orchestrator.as_str(),
+                schedule.enabled,
+                schedule.next_run_at.map(|at| at.to_rfc3339()),
+                now.to_rfc3339(),
+                schedule.id.to_string()
+            ],
+        )?;
+
+        if affected == 0 {
+            return Err(DbError::NotFound);
+        }
+        Ok(())
+    }
+
+    /// Record that a schedule fired at `fired_at`, and when it fires next
+    pub fn mark_schedule_fired(
+        &self,
+        id: Uuid,
+        fired_at: DateTime<Utc>,
+        next_run_at: Option<DateTime<Utc>>,
+    ) -> DbResult<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE schedules SET last_run_at = ?1, next_run_at = ?2 WHERE id = ?3",
+            params![
+                fired_at.to_rfc3339(),
+                next_run_at.map(|at| at.to_rfc3339()),
+                id.to_string()
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Delete a schedule and its run history
+    pub fn delete_schedule(&self, id: Uuid) -> DbResult<()> {
+        let conn = self.conn.lock().unwrap();
+        let affected =
+            conn.execute("DELETE FROM schedules WHERE id = ?1", params![id.to_string()])?;
+
+        if affected == 0 {
+            return Err(DbError::NotFound);
+        }
+        Ok(())
+    }
+
+    /// Record a time a schedule fired
+    pub fn insert_schedule_run(
+        &self,
+        schedule_id: Uuid,
+        session_id: Option<Uuid>,
+        error: Option<&str>,
+        fired_at: DateTime<Utc>,
+    ) -> DbResult<ScheduleRun> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO schedule_runs (schedule_id, session_id, error, fired_at) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                schedule_id.to_string(),
+                session_id.map(|id| id.to_string()),
+                error,
+                fired_at.to_rfc3339()
+            ],
+        )?;
+
+        Ok(ScheduleRun {
+            id: conn.last_insert_rowid(),
+            schedule_id,
+            session_id,
+            error: error.map(String::from),
+            fired_at,
+        })
+    }
+
+    /// A schedule's most recent runs, newest first
+    pub fn list_schedule_runs(&self, schedule_id: Uuid, limit: u32) -> DbResult<Vec<ScheduleRun>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM schedule_runs WHERE schedule_id = ?1 ORDER BY id DESC LIMIT ?2",
+            SCHEDULE_RUN_COLUMNS
+        ))?;
+
+        let runs = stmt
+            .query_map(params![schedule_id.to_string(), limit], schedule_run_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(runs)
+    }
+
+    // ==================== Job Operations ====================
+
+    /// Queue a new job
+    pub fn insert_job(&self, kind: JobKind, owner_id: Option<Uuid>, description: &str) -> DbResult<Job> {
+        let conn = self.conn.lock().unwrap();
+        let id = Uuid::new_v4();
+
+        conn.execute(
+            "INSERT INTO jobs (id, kind, state, owner_id, description, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                id.to_string(),
+                kind.as_str(),
+                JobState::Queued.as_str(),
+                owner_id.map(|id| id.to_string()),
+                description,
+                Utc::now().to_rfc3339()
+            ],
+        )?;
+        conn.execute(
+            "DELETE FROM jobs WHERE finished_at IS NOT NULL AND id NOT IN (
+                SELECT id FROM jobs WHERE finished_at IS NOT NULL ORDER BY finished_at DESC LIMIT ?1
+            )",
+            params![MAX_FINISHED_JOBS as i64],
+        )?;
+
+        let job = conn.query_row(
+            &format!("SELECT {} FROM jobs WHERE id = ?1", JOB_COLUMNS),
+            params![id.to_string()],
+            job_from_row,
+        )?;
+        Ok(job)
+    }
+
+    /// Get a job by ID
+    pub fn get_job(&self, id: Uuid) -> DbResult<Job> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.query_row(
+            &format!("SELECT {} FROM jobs WHERE id = ?1", JOB_COLUMNS),
+            params![id.to_string()],
+            job_from_row,
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => DbError::NotFound,
+            _ => DbError::Sqlite(e),
+        })
+    }
+
+    /// List jobs, newest first, optionally of one kind or in one state
+    pub fn list_jobs(&self, kind: Option<JobKind>, state: Option<JobState>) -> DbResult<Vec<Job>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM jobs WHERE (?1 IS NULL OR kind = ?1) AND (?2 IS NULL OR state = ?2)
+             ORDER BY created_at DESC",
+            JOB_COLUMNS
+        ))?;
+
+        let jobs = stmt
+            .query_map(
+                params![kind.map(|kind| kind.as_str()), state.map(|state| state.as_str())],
+                job_from_row,
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(jobs)
+    }
+
+    /// Move a queued job to running, returning false if it is no longer queued
+    pub fn start_job(&self, id: Uuid) -> DbResult<bool> {
+        let conn = self.conn.lock().unwrap();
+        let affected = conn.execute(
+            "UPDATE jobs SET state = ?1, started_at = ?2 WHERE id = ?3 AND state = ?4",
+            params![
+                JobState::Running.as_str(),
+                Utc::now().to_rfc3339(),
+                id.to_string(),
+                JobState::Queued.as_str()
+            ],
+        )?;
+        Ok(affected > 0)
+    }
+
+    /// Record how far a running job has got
+    pub fn update_job_progress(&self, id: Uuid, progress: Option<f64>, message: Option<&str>) -> DbResult<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE jobs SET progress = ?1, progress_message = ?2 WHERE id = ?3",
+            params![progress, message, id.to_string()],
+        )?;
+        Ok(())
+    }
+
+    /// Record a job's outcome
+    pub fn finish_job(
+        &self,
+        id: Uuid,
+        state: JobState,
+        result: Option<&serde_json::Value>,
+        error: Option<&str>,
+    ) -> DbResult<()> {
+        let conn = self.conn.lock().unwrap();
+        let affected = conn.execute(
+            "UPDATE jobs SET state = ?1, result = ?2, error = ?3, finished_at = ?4 WHERE id = ?5",
+            params![
+                state.as_str(),
+                result.map(|result| result.to_string()),
+                error,
+                Utc::now().to_rfc3339(),
+                id.to_string()
+            ],
+        )?;
+
+        if affected == 0 {
+            return Err(DbError::NotFound);
+        }
+        Ok(())
+    }
+
+    /// Cancel a queued job outright, or flag a running one to stop
+    ///
+    /// Finished jobs are returned unchanged.
+    pub fn cancel_job(&self, id: Uuid) -> DbResult<Job> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE jobs SET state = ?1, finished_at = ?2 WHERE id = ?3 AND state = ?4",
+            params![
+                JobState::Cancelled.as_str(),
+                Utc::now().to_rfc3339(),
+                id.to_string(),
+                JobState::Queued.as_str()
+            ],
+        )?;
+        conn.execute(
+            "UPDATE jobs SET cancel_requested = 1 WHERE id = ?1 AND state = ?2",
+            params![id.to_string(), JobState::Running.as_str()],
+        )?;
+
+        conn.query_row(
+            &format!("SELECT {} FROM jobs WHERE id = ?1", JOB_COLUMNS),
+            params![id.to_string()],
+            job_from_row,
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => DbError::NotFound,
+            _ => DbError::Sqlite(e),
+        })
+    }
+
+    /// Fail every job that was queued or running, returning how many there were
+    ///
+    /// Jobs don't survive a restart, so this is run when the server starts.
+    pub fn fail_unfinished_jobs(&self, error: &str) -> DbResult<usize> {
+        let conn = self.conn.lock().unwrap();
+        let affected = conn.execute(
+            "UPDATE jobs SET state = ?1, error = ?2, finished_at = ?3 WHERE state IN (?4, ?5)",
+            params![
+                JobState::Failed.as_str(),
+                error,
+                Utc::now().to_rfc3339(),
+                JobState::Queued.as_str(),
+                JobState::Running.as_str()
+            ],
+        )?;
+        Ok(affected)
+    }
+
+    // ==================== Message Operations ====================
+
+    /// Insert a new text message
+    pub fn insert_message(
+        &self,
+        session_id: Uuid,
+        role: MessageRole,
+        content: &str,
+    ) -> DbResult<Message> {
+        self.insert_typed_message(session_id, role, MessageKind::Text, content, None)
+    }
+
+    /// Insert a message of any kind, with its details
+    pub fn insert_typed_message(
+        &self,
+        session_id: Uuid,
+        role: MessageRole,
+        kind: MessageKind,
+        content: &str,
+        data: Option<&serde_json::Value>,
+    ) -> DbResult<Message> {
+        let conn = self.conn.lock().unwrap();
+        let now = Utc::now();
+        let id = Uuid::new_v4();
+
+        conn.execute(
+            "INSERT INTO messages (id, session_id, role, kind, content, data, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
             params![
                 id.to_string(),
                 session_id.to_string(),
                 role.as_str(),
+                kind.as_str(),
                 content,
+                data.map(|data| data.to_string()),
+                now.to_rfc3339()
+            ],
+        )?;
+
+        Ok(Message {
+            id,
+            session_id,
+            role,
+            kind,
+            content: content.to_string(),
+            data: data.cloned(),
+            created_at: now,
+        })
+    }
+
+    /// List messages for a session
+    pub fn list_messages(&self, session_id: Uuid) -> DbResult<Vec<Message>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, session_id, role, kind, content, data, created_at FROM messages
+             WHERE session_id = ?1 ORDER BY created_at",
+        )?;
+
+        let messages = stmt
+            .query_map(params![session_id.to_string()], |row| {
+                let data: Option<String> = row.get(5)?;
+                Ok(Message {
+                    id: parse_uuid(row, 0, "id")?,
+                    session_id: parse_uuid(row, 1, "session_id")?,
+                    role: parse_enum(row, 2, "role", MessageRole::from_str)?,
+                    kind: parse_enum(row, 3, "kind", MessageKind::from_str)?,
+                    content: row.get(4)?,
+                    data: data.map(|data| serde_json::from_str(&data).unwrap_or(serde_json::Value::String(data))),
+                    created_at: parse_datetime(row, 6, "created_at")?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(messages)
+    }
+
+    // ==================== Config Operations ====================
+
+    /// Get a config value
+    pub fn get_config(&self, key: &str) -> DbResult<Option<String>> {
+        let conn = self.conn.lock().unwrap();
+
+        match conn.query_row(
+            "SELECT value FROM config WHERE key = ?1",
+            params![key],
+            |row| row.get::<_, String>(0),
+        ) {
+            Ok(value) => Ok(Some(value)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(DbError::Sqlite(e)),
+        }
+    }
+
+    /// Set a config value
+    pub fn set_config(&self, key: &str, value: &str) -> DbResult<()> {
+        let conn = self.conn.lock().unwrap();
+        let now = Utc::now();
+
+        conn.execute(
+            "INSERT OR REPLACE INTO config (key, value, updated_at) VALUES (?1, ?2, ?3)",
+            params![key, value, now.to_rfc3339()],
+        )?;
+
+        Ok(())
+    }
+
+    /// Delete a config value
+    pub fn delete_config(&self, key: &str) -> DbResult<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM config WHERE key = ?1", params![key])?;
+        Ok(())
+    }
+
+    /// List all config values
+    pub fn list_config(&self) -> DbResult<Vec<(String, String)>> {
+        let conn = self.conn.lock().unwrap();
+
+        let mut stmt = conn.prepare("SELECT key, value FROM config")?;
+        let config = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(config)
+    }
+
+    // ==================== Output Log Operations ====================
+
+    /// Insert a new output log entry on the stream's default channel
+    pub fn insert_output_log(
+        &self,
+        session_id: Uuid,
+        stream: OutputStream,
+        content: &str,
+    ) -> DbResult<OutputLog> {
+        self.insert_channel_output_log(session_id, stream, OutputChannel::for_stream(stream), content, false)
+    }
+
+    /// Insert a new output log entry on a given channel
+    ///
+    /// `continues` marks a piece of a long line that goes on in the next entry.
+    pub fn insert_channel_output_log(
+        &self,
+        session_id: Uuid,
+        stream: OutputStream,
+        channel: OutputChannel,
+        content: &str,
+        continues: bool,
+    ) -> DbResult<OutputLog> {
+        let conn = self.conn.lock().unwrap();
+        let now = Utc::now();
+
+        let (stored, compressed) = encode_output(content);
+        let tx = conn.unchecked_transaction()?;
+        tx.execute(
+            "INSERT INTO output_logs (session_id, stream, channel, content, continues, compressed, size_bytes, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                session_id.to_string(),
+                stream.as_str(),
+                channel.as_str(),
+                stored,
+                continues,
+                compressed,
+                content.len(),
                 now.to_rfc3339()
             ],
         )?;
-
-        Ok(Message {
-            id,
-            session_id,
-            role,
-            content: content.to_string(),
-            created_at: now,
-        })
+
+        let id = tx.last_insert_rowid();
+        index_output_log(&tx, id, content)?;
+        tx.commit()?;
+
+        Ok(OutputLog {
+            id,
+            session_id,
+            stream,
+            channel,
+            content: content.to_string(),
+            continues,
+            created_at: now,
+        })
+    }
+
+    /// List output logs for a session
+    ///
+    /// # Arguments
+    /// * `session_id` - The session to get logs for
+    /// * `stream_filter` - Optional filter by stream type (stdout/stderr)
+    /// * `limit` - Optional limit on number of results
+    /// * `offset` - Optional offset for pagination
+    pub fn list_output_logs(
+        &self,
+        session_id: Uuid,
+        stream_filter: Option<OutputStream>,
+        limit: Option<i64>,
+        offset: Option<i64>,
+    ) -> DbResult<Vec<OutputLog>> {
+        self.list_channel_output_logs(session_id, stream_filter, None, limit, offset)
+    }
+
+    /// List output logs for a session, optionally limited to some channels
+    ///
+    /// An empty `channels` filter matches every channel.
+    pub fn list_channel_output_logs(
+        &self,
+        session_id: Uuid,
+        stream_filter: Option<OutputStream>,
+        channels: Option<&[OutputChannel]>,
+        limit: Option<i64>,
+        offset: Option<i64>,
+    ) -> DbResult<Vec<OutputLog>> {
+        let conn = self.conn.lock().unwrap();
+
+        // SQLite requires LIMIT when using OFFSET, so use -1 (unlimited) when only offset is provided
+        let query = format!(
+            "SELECT id, session_id, stream, channel, content, continues, created_at, compressed FROM output_logs
+             WHERE session_id = ?1 AND (?2 IS NULL OR stream = ?2) AND {}
+             ORDER BY id LIMIT {} OFFSET {}",
+            channel_clause(channels),
+            limit.unwrap_or(-1),
+            offset.unwrap_or(0)
+        );
+
+        let mut stmt = conn.prepare(&query)?;
+        let logs = stmt
+            .query_map(
+                params![session_id.to_string(), stream_filter.map(|s| s.as_str())],
+                |row| {
+                    Ok(OutputLog {
+                        id: row.get(0)?,
+                        session_id: parse_uuid(row, 1, "session_id")?,
+                        stream: parse_enum(row, 2, "stream", OutputStream::from_str)?,
+                        channel: parse_enum(row, 3, "channel", OutputChannel::from_str)?,
+                        content: decode_output(row, 4, row.get(7)?)?,
+                        continues: row.get(5)?,
+                        created_at: parse_datetime(row, 6, "created_at")?,
+                    })
+                },
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(logs)
+    }
+
+    /// Count output logs for a session, optionally filtered by stream
+    pub fn count_output_logs(
+        &self,
+        session_id: Uuid,
+        stream_filter: Option<OutputStream>,
+    ) -> DbResult<usize> {
+        self.count_channel_output_logs(session_id, stream_filter, None)
+    }
+
+    /// Count output logs for a session, optionally filtered by stream and channels
+    pub fn count_channel_output_logs(
+        &self,
+        session_id: Uuid,
+        stream_filter: Option<OutputStream>,
+        channels: Option<&[OutputChannel]>,
+    ) -> DbResult<usize> {
+        let conn = self.conn.lock().unwrap();
+        let count: i64 = conn.query_row(
+            &format!(
+                "SELECT COUNT(*) FROM output_logs WHERE session_id = ?1 AND (?2 IS NULL OR stream = ?2) AND {}",
+                channel_clause(channels)
+            ),
+            params![session_id.to_string(), stream_filter.map(|s| s.as_str())],
+            |row| row.get(0),
+        )?;
+        Ok(count as usize)
+    }
+
+    // ==================== Iteration Operations ====================
+
+    /// Start a new iteration of a session's run at an output log line
+    ///
+    /// The iteration still open is ended first. Without a `number` the
+    /// iteration follows the session's last one.
+    pub fn start_iteration(
+        &self,
+        session_id: Uuid,
+        number: Option<u32>,
+        first_output_id: i64,
+    ) -> DbResult<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        end_open_iteration(&tx, session_id, Some(first_output_id))?;
+        tx.execute(
+            "INSERT INTO iterations (session_id, number, first_output_id, started_at)
+             VALUES (?1, COALESCE(?2, (SELECT COALESCE(MAX(number), 0) + 1 FROM iterations WHERE session_id = ?1)), ?3, ?4)",
+            params![session_id.to_string(), number, first_output_id, Utc::now().to_rfc3339()],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Start a new iteration at the next output line written, for
+    /// iterations that start with something other than output
+    pub fn start_iteration_at_next_output(&self, session_id: Uuid, number: Option<u32>) -> DbResult<()> {
+        let next_output_id: i64 = {
+            let conn = self.conn.lock().unwrap();
+            conn.query_row("SELECT COALESCE(MAX(id), 0) + 1 FROM output_logs", [], |row| row.get(0))?
+        };
+        self.start_iteration(session_id, number, next_output_id)
+    }
+
+    /// End a session's open iteration, if any, once its run is over
+    pub fn end_iteration(&self, session_id: Uuid) -> DbResult<()> {
+        let conn = self.conn.lock().unwrap();
+        end_open_iteration(&conn, session_id, None)
+    }
+
+    /// List a session's iterations in order
+    pub fn list_iterations(&self, session_id: Uuid) -> DbResult<Vec<Iteration>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT i.id, i.session_id, i.number, i.first_output_id, i.last_output_id, i.summary,
+                    (SELECT COUNT(*) FROM output_logs o WHERE o.session_id = i.session_id AND o.id < i.first_output_id),
+                    i.started_at, i.ended_at
+             FROM iterations i WHERE i.session_id = ?1 ORDER BY i.id",
+        )?;
+
+        let iterations = stmt
+            .query_map(params![session_id.to_string()], |row| {
+                Ok(Iteration {
+                    id: row.get(0)?,
+                    session_id: parse_uuid(row, 1, "session_id")?,
+                    number: row.get(2)?,
+                    first_output_id: row.get(3)?,
+                    last_output_id: row.get(4)?,
+                    summary: row.get(5)?,
+                    output_cursor: row.get::<_, i64>(6)?.to_string(),
+                    started_at: parse_datetime(row, 7, "started_at")?,
+                    ended_at: parse_optional_datetime(row, 8, "ended_at")?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(iterations)
+    }
+
+    // ==================== Run Operations ====================
+
+    /// Record the start of a session's run
+    ///
+    /// A run the session still has open is closed as cancelled first.
+    pub fn insert_run(&self, session_id: Uuid, prompt: &str) -> DbResult<Run> {
+        let now = Utc::now();
+        let run = Run {
+            id: Uuid::new_v4(),
+            session_id,
+            prompt: prompt.to_string(),
+            status: SessionStatus::Running,
+            exit_code: None,
+            started_at: now,
+            finished_at: None,
+            duration_ms: None,
+            usage: TokenUsage::default(),
+        };
+
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "UPDATE runs SET status = ?2, finished_at = ?3 WHERE session_id = ?1 AND finished_at IS NULL",
+            params![session_id.to_string(), SessionStatus::Cancelled.as_str(), now.to_rfc3339()],
+        )?;
+        tx.execute(
+            "INSERT INTO runs (id, session_id, prompt, status, started_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                run.id.to_string(),
+                session_id.to_string(),
+                run.prompt,
+                run.status.as_str(),
+                now.to_rfc3339(),
+            ],
+        )?;
+        tx.commit()?;
+        Ok(run)
+    }
+
+    /// Record the exit code of a session's open run
+    pub fn set_run_exit_code(&self, session_id: Uuid, exit_code: Option<i32>) -> DbResult<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE runs SET exit_code = ?2 WHERE session_id = ?1 AND finished_at IS NULL",
+            params![session_id.to_string(), exit_code],
+        )?;
+        Ok(())
+    }
+
+    /// Add reported tokens and cost to a session's open run
+    ///
+    /// Returns the run's new totals, or None when no run is open.
+    pub fn add_run_usage(&self, session_id: Uuid, usage: &TokenUsage) -> DbResult<Option<TokenUsage>> {
+        let conn = self.conn.lock().unwrap();
+        let totals = conn
+            .query_row(
+                "UPDATE runs SET input_tokens = input_tokens + ?2, output_tokens = output_tokens + ?3,
+                    cache_read_tokens = cache_read_tokens + ?4, cache_write_tokens = cache_write_tokens + ?5,
+                    cost_usd = cost_usd + ?6
+                 WHERE session_id = ?1 AND finished_at IS NULL
+                 RETURNING input_tokens, output_tokens, cache_read_tokens, cache_write_tokens, cost_usd",
+                params![
+                    session_id.to_string(),
+                    usage.input_tokens as i64,
+                    usage.output_tokens as i64,
+                    usage.cache_read_tokens as i64,
+                    usage.cache_write_tokens as i64,
+                    usage.cost_usd,
+                ],
+                |row| usage_from_row(row, 0),
+            )
+            .optional()?;
+        Ok(totals)
+    }
+
+    /// Token and cost totals over all of a session's runs
+    pub fn session_stats(&self, session_id: Uuid) -> DbResult<SessionStats> {
+        let conn = self.conn.lock().unwrap();
+        let stats = conn.query_row(
+            "SELECT COUNT(*), COALESCE(SUM(input_tokens), 0), COALESCE(SUM(output_tokens), 0),
+                    COALESCE(SUM(cache_read_tokens), 0), COALESCE(SUM(cache_write_tokens), 0),
+                    COALESCE(SUM(cost_usd), 0.0)
+             FROM runs WHERE session_id = ?1",
+            params![session_id.to_string()],
+            |row| {
+                Ok(SessionStats {
+                    runs: row.get(0)?,
+                    usage: usage_from_row(row, 1)?,
+                })
+            },
+        )?;
+        Ok(stats)
     }
 
-    /// List messages for a session
-    pub fn list_messages(&self, session_id: Uuid) -> DbResult<Vec<Message>> {
+    /// Close a session's open run, if any, with how it ended
+    pub fn finish_run(&self, session_id: Uuid, status: SessionStatus) -> DbResult<()> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, session_id, role, content, created_at FROM messages WHERE session_id = ?1 ORDER BY created_at",
+        conn.execute(
+            "UPDATE runs SET status = ?2, finished_at = ?3 WHERE session_id = ?1 AND finished_at IS NULL",
+            params![session_id.to_string(), status.as_str(), Utc::now().to_rfc3339()],
         )?;
+        Ok(())
+    }
 
-        let messages = stmt
-            .query_map(params![session_id.to_string()], |row| {
-                Ok(Message {
-                    id: parse_uuid(row, 0, "id")?,
-                    session_id: parse_uuid(row, 1, "session_id")?,
-                    role: parse_enum(row, 2, "role", MessageRole::from_str)?,
-                    content: row.get(3)?,
-                    created_at: parse_datetime(row, 4, "created_at")?,
-                })
-            })?
+    /// List a session's runs, newest first
+    pub fn list_runs(&self, session_id: Uuid) -> DbResult<Vec<Run>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM runs WHERE session_id = ?1 ORDER BY started_at DESC, rowid DESC",
+            RUN_COLUMNS
+        ))?;
+
+        let runs = stmt
+            .query_map(params![session_id.to_string()], run_from_row)?
             .collect::<Result<Vec<_>, _>>()?;
 
-        Ok(messages)
+        Ok(runs)
     }
 
-    // ==================== Config Operations ====================
-
-    /// Get a config value
-    pub fn get_config(&self, key: &str) -> DbResult<Option<String>> {
-        let conn = self.conn.lock().unwrap();
+    // ==================== Pipeline Operations ====================
 
-        match conn.query_row(
-            "SELECT value FROM config WHERE key = ?1",
-            params![key],
-            |row| row.get::<_, String>(0),
-        ) {
-            Ok(value) => Ok(Some(value)),
-            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-            Err(e) => Err(DbError::Sqlite(e)),
+    /// Replace a session's pipeline with `prompts`, all pending
+    pub fn set_pipeline(&self, session_id: Uuid, prompts: &[String]) -> DbResult<Vec<PipelineStep>> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "DELETE FROM pipeline_steps WHERE session_id = ?1",
+            params![session_id.to_string()],
+        )?;
+        for (index, prompt) in prompts.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO pipeline_steps (session_id, step, prompt, status) VALUES (?1, ?2, ?3, ?4)",
+                params![session_id.to_string(), index + 1, prompt, StepStatus::Pending.as_str()],
+            )?;
         }
+        let steps = pipeline_steps_with_status(&tx, session_id, StepStatus::Pending)?;
+        tx.commit()?;
+        Ok(steps)
     }
 
-    /// Set a config value
-    pub fn set_config(&self, key: &str, value: &str) -> DbResult<()> {
-        let conn = self.conn.lock().unwrap();
+    /// Mark the first pending step of a session's pipeline as running
+    pub fn start_pipeline_step(&self, session_id: Uuid) -> DbResult<Option<PipelineStep>> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let Some(mut step) = pipeline_steps_with_status(&tx, session_id, StepStatus::Pending)?
+            .into_iter()
+            .next()
+        else {
+            return Ok(None);
+        };
         let now = Utc::now();
-
-        conn.execute(
-            "INSERT OR REPLACE INTO config (key, value, updated_at) VALUES (?1, ?2, ?3)",
-            params![key, value, now.to_rfc3339()],
+        tx.execute(
+            "UPDATE pipeline_steps SET status = ?3, started_at = ?4 WHERE session_id = ?1 AND step = ?2",
+            params![session_id.to_string(), step.step, StepStatus::Running.as_str(), now.to_rfc3339()],
         )?;
+        tx.commit()?;
+        step.status = StepStatus::Running;
+        step.started_at = Some(now);
+        Ok(Some(step))
+    }
 
-        Ok(())
+    /// End the running step of a session's pipeline with `status`
+    ///
+    /// Unless it completed, the steps still pending are skipped. Returns
+    /// every step that changed, in order.
+    pub fn finish_pipeline_step(&self, session_id: Uuid, status: StepStatus) -> DbResult<Vec<PipelineStep>> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let now = Utc::now();
+        let mut changed = Vec::new();
+        for mut step in pipeline_steps_with_status(&tx, session_id, StepStatus::Running)? {
+            step.status = status;
+            step.finished_at = Some(now);
+            changed.push(step);
+        }
+        if status != StepStatus::Completed {
+            for mut step in pipeline_steps_with_status(&tx, session_id, StepStatus::Pending)? {
+                step.status = StepStatus::Skipped;
+                changed.push(step);
+            }
+        }
+        for step in &changed {
+            tx.execute(
+                "UPDATE pipeline_steps SET status = ?3, finished_at = ?4 WHERE session_id = ?1 AND step = ?2",
+                params![
+                    session_id.to_string(),
+                    step.step,
+                    step.status.as_str(),
+                    step.finished_at.map(|t| t.to_rfc3339()),
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(changed)
     }
 
-    /// Delete a config value
-    pub fn delete_config(&self, key: &str) -> DbResult<()> {
+    /// The next step of a session's pipeline to run, if any
+    pub fn next_pipeline_step(&self, session_id: Uuid) -> DbResult<Option<PipelineStep>> {
         let conn = self.conn.lock().unwrap();
-        conn.execute("DELETE FROM config WHERE key = ?1", params![key])?;
-        Ok(())
+        Ok(pipeline_steps_with_status(&conn, session_id, StepStatus::Pending)?
+            .into_iter()
+            .next())
     }
 
-    /// List all config values
-    pub fn list_config(&self) -> DbResult<Vec<(String, String)>> {
+    /// List the steps of a session's pipeline in order
+    pub fn list_pipeline_steps(&self, session_id: Uuid) -> DbResult<Vec<PipelineStep>> {
         let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM pipeline_steps WHERE session_id = ?1 ORDER BY step",
+            PIPELINE_STEP_COLUMNS
+        ))?;
 
-        let mut stmt = conn.prepare("SELECT key, value FROM config")?;
-        let config = stmt
-            .query_map([], |row| {
-                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
-            })?
+        let steps = stmt
+            .query_map(params![session_id.to_string()], pipeline_step_from_row)?
             .collect::<Result<Vec<_>, _>>()?;
 
-        Ok(config)
+        Ok(steps)
     }
 
-    // ==================== Output Log Operations ====================
-
-    /// Insert a new output log entry
-    pub fn insert_output_log(
-        &self,
-        session_id: Uuid,
-        stream: OutputStream,
-        content: &str,
-    ) -> DbResult<OutputLog> {
+    /// Delete output logs for a session
+    pub fn delete_output_logs(&self, session_id: Uuid) -> DbResult<()> {
         let conn = self.conn.lock().unwrap();
-        let now = Utc::now();
-
         conn.execute(
-            "INSERT INTO output_logs (session_id, stream, content, created_at) VALUES (?1, ?2, ?3, ?4)",
-            params![
-                session_id.to_string(),
-                stream.as_str(),
-                content,
-                now.to_rfc3339()
-            ],
+            "DELETE FROM output_logs WHERE session_id = ?1",
+            params![session_id.to_string()],
         )?;
+        Ok(())
+    }
 
-        let id = conn.last_insert_rowid();
+    /// Delete output logs written before `before`, and all but the newest
+    /// `max_per_session` logs of each session
+    pub fn prune_output_logs(
+        &self,
+        before: Option<DateTime<Utc>>,
+        max_per_session: Option<u64>,
+    ) -> DbResult<PrunedOutput> {
+        let conn = self.conn.lock().unwrap();
+        let free_bytes = |conn: &Connection| -> DbResult<u64> {
+            let pages: i64 = conn.query_row("PRAGMA freelist_count", [], |row| row.get(0))?;
+            let page_size: i64 = conn.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+            Ok((pages * page_size) as u64)
+        };
+        let free_before = free_bytes(&conn)?;
+
+        let tx = conn.unchecked_transaction()?;
+        let mut deleted = 0;
+        if let Some(before) = before {
+            deleted += tx.execute(
+                "DELETE FROM output_logs WHERE created_at < ?1",
+                params![before.to_rfc3339()],
+            )?;
+        }
+        if let Some(max) = max_per_session {
+            deleted += tx.execute(
+                "DELETE FROM output_logs WHERE id IN (
+                     SELECT id FROM (
+                         SELECT id, ROW_NUMBER() OVER (PARTITION BY session_id ORDER BY id DESC) AS newer
+                         FROM output_logs
+                     ) WHERE newer > ?1
+                 )",
+                params![max as i64],
+            )?;
+        }
+        tx.commit()?;
 
-        Ok(OutputLog {
-            id,
-            session_id,
-            stream,
-            content: content.to_string(),
-            created_at: now,
+        Ok(PrunedOutput {
+            deleted,
+            bytes_freed: free_bytes(&conn)?.saturating_sub(free_before),
         })
     }
 
-    /// List output logs for a session
+    // ==================== Search Operations ====================
+
+    /// Search messages and output logs for all of `query`'s words
     ///
-    /// # Arguments
-    /// * `session_id` - The session to get logs for
-    /// * `stream_filter` - Optional filter by stream type (stdout/stderr)
-    /// * `limit` - Optional limit on number of results
-    /// * `offset` - Optional offset for pagination
-    pub fn list_output_logs(
-        &self,
-        session_id: Uuid,
-        stream_filter: Option<OutputStream>,
-        limit: Option<i64>,
-        offset: Option<i64>,
-    ) -> DbResult<Vec<OutputLog>> {
+    /// Words are searched for as written, so FTS5 query syntax in `query` is
+    /// never interpreted. `visible_to` limits hits to sessions that are shared
+    /// or owned by that user. Each list holds at most `limit` entries.
+    pub fn search(&self, query: &str, visible_to: Option<Uuid>, limit: usize) -> DbResult<SearchResults> {
+        let mut results = SearchResults {
+            sessions: Vec::new(),
+            messages: Vec::new(),
+            output: Vec::new(),
+        };
+        let Some(query) = fts_query(query) else {
+            return Ok(results);
+        };
         let conn = self.conn.lock().unwrap();
+        let visible_to = visible_to.map(|id| id.to_string());
+        let limit = limit as i64;
 
-        let base_query = "SELECT id, session_id, stream, content, created_at FROM output_logs WHERE session_id = ?1";
-
-        // SQLite requires LIMIT when using OFFSET, so use -1 (unlimited) when only offset is provided
-        let query = match (stream_filter, limit, offset) {
-            (Some(_), Some(lim), Some(off)) => format!(
-                "{} AND stream = ?2 ORDER BY id LIMIT {} OFFSET {}",
-                base_query, lim, off
-            ),
-            (Some(_), Some(lim), None) => {
-                format!("{} AND stream = ?2 ORDER BY id LIMIT {}", base_query, lim)
-            }
-            (Some(_), None, Some(off)) => {
-                format!("{} AND stream = ?2 ORDER BY id LIMIT -1 OFFSET {}", base_query, off)
-            }
-            (Some(_), None, None) => format!("{} AND stream = ?2 ORDER BY id", base_query),
-            (None, Some(lim), Some(off)) => {
-                format!("{} ORDER BY id LIMIT {} OFFSET {}", base_query, lim, off)
-            }
-            (None, Some(lim), None) => format!("{} ORDER BY id LIMIT {}", base_query, lim),
-            (None, None, Some(off)) => format!("{} ORDER BY id LIMIT -1 OFFSET {}", base_query, off),
-            (None, None, None) => format!("{} ORDER BY id", base_query),
-        };
+        let mut stmt = conn.prepare(
+            "SELECT s.id, s.repo_id, s.name, SUM(hits.message), SUM(1 - hits.message), MAX(hits.created_at)
+             FROM (
+                 SELECT m.session_id, 1 AS message, m.created_at
+                 FROM messages_fts JOIN messages m ON m.rowid = messages_fts.rowid
+                 WHERE messages_fts MATCH ?1
+                 UNION ALL
+                 SELECT o.session_id, 0, o.created_at
+                 FROM output_logs_fts JOIN output_logs o ON o.id = output_logs_fts.rowid
+                 WHERE output_logs_fts MATCH ?1
+             ) hits
+             JOIN sessions s ON s.id = hits.session_id
+             WHERE ?2 IS NULL OR s.owner_id IS NULL OR s.owner_id = ?2
+             GROUP BY s.id ORDER BY MAX(hits.created_at) DESC LIMIT ?3",
+        )?;
+        results.sessions = stmt
+            .query_map(params![query, visible_to, limit], |row| {
+                Ok(SessionSearchHit {
+                    session_id: parse_uuid(row, 0, "session_id")?,
+                    repo_id: parse_uuid(row, 1, "repo_id")?,
+                    name: row.get(2)?,
+                    message_matches: row.get(3)?,
+                    output_matches: row.get(4)?,
+                    last_match_at: parse_datetime(row, 5, "last_match_at")?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
 
-        let logs = if let Some(stream) = stream_filter {
-            let mut stmt = conn.prepare(&query)?;
-            stmt.query_map(params![session_id.to_string(), stream.as_str()], |row| {
-                Ok(OutputLog {
-                    id: row.get(0)?,
+        // snippet() marks matches with the characters snippet_parts splits on
+        let mut stmt = conn.prepare(
+            "SELECT m.id, m.session_id, m.role, m.kind, m.created_at,
+                    snippet(messages_fts, 0, char(2), char(3), '…', 24)
+             FROM messages_fts
+             JOIN messages m ON m.rowid = messages_fts.rowid
+             JOIN sessions s ON s.id = m.session_id
+             WHERE messages_fts MATCH ?1 AND (?2 IS NULL OR s.owner_id IS NULL OR s.owner_id = ?2)
+             ORDER BY rank LIMIT ?3",
+        )?;
+        results.messages = stmt
+            .query_map(params![query, visible_to, limit], |row| {
+                Ok(MessageSearchHit {
+                    message_id: parse_uuid(row, 0, "id")?,
                     session_id: parse_uuid(row, 1, "session_id")?,
-                    stream: parse_enum(row, 2, "stream", OutputStream::from_str)?,
-                    content: row.get(3)?,
+                    role: parse_enum(row, 2, "role", MessageRole::from_str)?,
+                    kind: parse_enum(row, 3, "kind", MessageKind::from_str)?,
                     created_at: parse_datetime(row, 4, "created_at")?,
+                    snippet: snippet_parts(&row.get::<_, String>(5)?),
                 })
             })?
-            .collect::<Result<Vec<_>, _>>()?
-        } else {
-            let mut stmt = conn.prepare(&query)?;
-            stmt.query_map(params![session_id.to_string()], |row| {
-                Ok(OutputLog {
-                    id: row.get(0)?,
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT o.id, o.session_id, o.stream, o.channel, o.created_at,
+                    snippet(output_logs_fts, 0, char(2), char(3), '…', 24)
+             FROM output_logs_fts
+             JOIN output_logs o ON o.id = output_logs_fts.rowid
+             JOIN sessions s ON s.id = o.session_id
+             WHERE output_logs_fts MATCH ?1 AND (?2 IS NULL OR s.owner_id IS NULL OR s.owner_id = ?2)
+             ORDER BY rank LIMIT ?3",
+        )?;
+        results.output = stmt
+            .query_map(params![query, visible_to, limit], |row| {
+                Ok(OutputSearchHit {
+                    output_id: row.get(0)?,
                     session_id: parse_uuid(row, 1, "session_id")?,
                     stream: parse_enum(row, 2, "stream", OutputStream::from_str)?,
-                    content: row.get(3)?,
+                    channel: parse_enum(row, 3, "channel", OutputChannel::from_str)?,
                     created_at: parse_datetime(row, 4, "created_at")?,
+                    snippet: snippet_parts(&row.get::<_, String>(5)?),
                 })
             })?
-            .collect::<Result<Vec<_>, _>>()?
-        };
-
-        Ok(logs)
-    }
+            .collect::<Result<Vec<_>, _>>()?;
 
-    /// Delete output logs for a session
-    pub fn delete_output_logs(&self, session_id: Uuid) -> DbResult<()> {
-        let conn = self.conn.lock().unwrap();
-        conn.execute(
-            "DELETE FROM output_logs WHERE session_id = ?1",
-            params![session_id.to_string()],
-        )?;
-        Ok(())
+        Ok(results)
     }
 }
 
@@ -686,6 +3905,315 @@ mod tests {
         assert!(repos.is_empty());
     }
 
+    #[tokio::test]
+    async fn test_call() {
+        let db = Database::in_memory().expect("Failed to create in-memory database");
+
+        // Writes made on the blocking pool are seen by the shared connection
+        let repo = db
+            .call(|db| db.insert_repo("/path/to/repo", "my-repo"))
+            .await
+            .expect("Failed to insert repo");
+        assert_eq!(db.get_repo(repo.id).unwrap().name, "my-repo");
+
+        let missing = db.call(|db| db.get_repo(Uuid::new_v4())).await;
+        assert!(matches!(missing, Err(DbError::NotFound)));
+    }
+
+    #[test]
+    fn test_user_crud() {
+        let db = Database::in_memory().expect("Failed to create in-memory database");
+
+        let user = db
+            .insert_user("alice", UserRole::Member, "hash-a")
+            .expect("Failed to insert user");
+        assert_eq!(user.role, UserRole::Member);
+
+        let fetched = db.get_user_by_token_hash("hash-a").expect("Failed to get user");
+        assert_eq!(fetched.id, user.id);
+        assert!(matches!(
+            db.get_user_by_token_hash("other"),
+            Err(DbError::NotFound)
+        ));
+
+        // Duplicate usernames are rejected
+        assert!(matches!(
+            db.insert_user("alice", UserRole::Viewer, "hash-b"),
+            Err(DbError::ConstraintViolation(_))
+        ));
+
+        db.update_user_role(user.id, UserRole::Admin).expect("Failed to update role");
+        assert_eq!(db.get_user(user.id).unwrap().role, UserRole::Admin);
+
+        assert!(user.desktop_notifications);
+        db.set_user_desktop_notifications(user.id, false)
+            .expect("Failed to update preference");
+        assert!(!db.get_user(user.id).unwrap().desktop_notifications);
+
+        assert_eq!(db.list_users().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_webhook_deliveries() {
+        let db = Database::in_memory().expect("Failed to create in-memory database");
+        let events = vec!["session.status".to_string()];
+        let webhook = db
+            .insert_webhook("https://example.com/hook", "secret", &events, true)
+            .expect("Failed to insert webhook");
+        assert_eq!(db.get_webhook(webhook.id).unwrap().events, events);
+
+        let payload = serde_json::json!({ "status": "completed" });
+        let delivery = db
+            .insert_webhook_delivery(webhook.id, "session.status", &payload)
+            .expect("Failed to insert delivery");
+        db.update_webhook_delivery(delivery.id, DeliveryStatus::Failed, 3, Some(500), Some("boom"))
+            .expect("Failed to update delivery");
+
+        let fetched = db.get_webhook_delivery(delivery.id).unwrap();
+        assert_eq!(fetched.status, DeliveryStatus::Failed);
+        assert_eq!(fetched.attempts, 3);
+        assert_eq!(fetched.response_status, Some(500));
+        assert_eq!(fetched.payload, payload);
+
+        // Deleting the webhook removes its delivery log
+        db.delete_webhook(webhook.id).unwrap();
+        assert!(matches!(
+            db.get_webhook_delivery(delivery.id),
+            Err(DbError::NotFound)
+        ));
+    }
+
+    #[test]
+    fn test_trigger_crud() {
+        let db = Database::in_memory().expect("Failed to create in-memory database");
+        let repo = db.insert_repo("/path/to/repo", "my-repo").unwrap();
+        let session = db.insert_session(repo.id, None, Orchestrator::Ralph).unwrap();
+        let trigger = db
+            .insert_trigger("nightly", repo.id, Some(session.id), "Fix {ref}", Orchestrator::Ralph, "hash-t")
+            .expect("Failed to insert trigger");
+
+        let fetched = db.get_trigger_by_token_hash("hash-t").unwrap();
+        assert_eq!(fetched.id, trigger.id);
+        assert_eq!(fetched.session_id, Some(session.id));
+        assert!(fetched.last_triggered_at.is_none());
+
+        db.mark_trigger_fired(trigger.id).unwrap();
+        assert!(db.get_trigger(trigger.id).unwrap().last_triggered_at.is_some());
+
+        // Deleting the target session falls back to a new session per call
+        db.delete_session(session.id).unwrap();
+        assert_eq!(db.get_trigger(trigger.id).unwrap().session_id, None);
+
+        db.delete_repo(repo.id).unwrap();
+        assert!(matches!(db.get_trigger(trigger.id), Err(DbError::NotFound)));
+    }
+
+    #[test]
+    fn test_snapshot_crud() {
+        let db = Database::in_memory().expect("Failed to create in-memory database");
+        let repo = db.insert_repo("/path/to/repo", "my-repo").unwrap();
+        let session = db.insert_session(repo.id, None, Orchestrator::Ralph).unwrap();
+
+        let snapshot = Snapshot {
+            id: Uuid::new_v4(),
+            session_id: session.id,
+            repo_id: repo.id,
+            reason: SnapshotReason::PreRun,
+            head_commit: Some("abc123".to_string()),
+            file_count: 2,
+            deleted_count: 1,
+            size_bytes: 4096,
+            created_at: Utc::now(),
+        };
+        db.insert_snapshot(&snapshot).expect("Failed to insert snapshot");
+
+        let fetched = db.get_snapshot(snapshot.id).unwrap();
+        assert_eq!(fetched.reason, SnapshotReason::PreRun);
+        assert_eq!(fetched.head_commit.as_deref(), Some("abc123"));
+        assert_eq!(fetched.size_bytes, 4096);
+        assert_eq!(db.list_snapshots_by_session(session.id).unwrap().len(), 1);
+
+        db.delete_snapshot(snapshot.id).unwrap();
+        assert!(matches!(db.delete_snapshot(snapshot.id), Err(DbError::NotFound)));
+
+        // Snapshots go with their session
+        db.insert_snapshot(&snapshot).unwrap();
+        db.delete_session(session.id).unwrap();
+        assert!(matches!(db.get_snapshot(snapshot.id), Err(DbError::NotFound)));
+    }
+
+    #[test]
+    fn test_worktree_crud() {
+        let db = Database::in_memory().expect("Failed to create in-memory database");
+        let repo = db.insert_repo("/path/to/repo", "my-repo").unwrap();
+        let session = db.insert_session(repo.id, None, Orchestrator::Ralph).unwrap();
+
+        let worktree = db
+            .insert_worktree(repo.id, Some(session.id), "feature", "/data/worktrees/a")
+            .expect("Failed to insert worktree");
+        assert_eq!(db.get_worktree(worktree.id).unwrap().branch, "feature");
+        assert_eq!(db.get_session_worktree(session.id).unwrap().unwrap().id, worktree.id);
+        assert!(matches!(
+            db.insert_worktree(repo.id, Some(session.id), "other", "/data/worktrees/b"),
+            Err(DbError::ConstraintViolation(_))
+        ));
+
+        // The record outlives its session
+        db.delete_session(session.id).unwrap();
+        let orphan = db.get_worktree(worktree.id).unwrap();
+        assert_eq!(orphan.session_id, None);
+        assert_eq!(db.list_worktrees(repo.id).unwrap().len(), 1);
+
+        db.delete_worktree(worktree.id).unwrap();
+        assert!(matches!(db.delete_worktree(worktree.id), Err(DbError::NotFound)));
+    }
+
+    #[test]
+    fn test_prompt_history() {
+        let db = Database::in_memory().expect("Failed to create in-memory database");
+        let repo = db.insert_repo("/path/to/repo", "my-repo").unwrap();
+        let other = db.insert_repo("/path/to/other", "other").unwrap();
+
+        db.record_prompt(repo.id, "Fix the tests").unwrap();
+        db.record_prompt(repo.id, "Add logging").unwrap();
+        db.record_prompt(repo.id, "Fix the tests").unwrap();
+        db.record_prompt(other.id, "Refactor").unwrap();
+
+        let prompts = db.list_recent_prompts(repo.id, None).unwrap();
+        assert_eq!(prompts.len(), 2);
+        assert_eq!(prompts[0].prompt, "Fix the tests");
+        assert_eq!(prompts[0].use_count, 2);
+
+        let matching = db.list_recent_prompts(repo.id, Some("LOG")).unwrap();
+        assert_eq!(matching.len(), 1);
+        assert_eq!(matching[0].prompt, "Add logging");
+
+        for i in 0..MAX_PROMPT_HISTORY {
+            db.record_prompt(repo.id, &format!("prompt {}", i)).unwrap();
+        }
+        let prompts = db.list_recent_prompts(repo.id, None).unwrap();
+        assert_eq!(prompts.len(), MAX_PROMPT_HISTORY);
+        assert!(prompts.iter().all(|entry| entry.prompt != "Add logging"));
+
+        db.delete_repo(other.id).unwrap();
+        assert!(db.list_recent_prompts(other.id, None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_activity() {
+        let db = Database::in_memory().expect("Failed to create in-memory database");
+        let repo = db.insert_repo("/path/to/repo", "my-repo").unwrap();
+        let session = db.insert_session(repo.id, None, Orchestrator::Ralph).unwrap();
+
+        db.record_activity(ActivityKind::RepoAdded, repo.id, None, "my-repo").unwrap();
+        db.record_activity(ActivityKind::SessionStarted, repo.id, Some(session.id), "Fix the tests")
+            .unwrap();
+
+        let events = db.list_activity().unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].kind, ActivityKind::SessionStarted);
+        assert_eq!(events[0].session_id, Some(session.id));
+        assert_eq!(events[1].summary, "my-repo");
+
+        // Deleting the session takes its entries with it
+        db.delete_session(session.id).unwrap();
+        assert_eq!(db.list_activity().unwrap().len(), 1);
+
+        for i in 0..MAX_ACTIVITY {
+            db.record_activity(ActivityKind::CommitMade, repo.id, None, &format!("commit {}", i))
+                .unwrap();
+        }
+        let events = db.list_activity().unwrap();
+        assert_eq!(events.len(), MAX_ACTIVITY);
+        assert!(events.iter().all(|event| event.kind == ActivityKind::CommitMade));
+    }
+
+    #[test]
+    fn test_daily_stats() {
+        let db = Database::in_memory().expect("Failed to create in-memory database");
+        let repo = db.insert_repo("/path/to/repo", "my-repo").unwrap();
+        let session = db.insert_session(repo.id, None, Orchestrator::Ralph).unwrap();
+        let today = Utc::now().date_naive();
+
+        db.record_activity(ActivityKind::SessionStarted, repo.id, Some(session.id), "Fix it")
+            .unwrap();
+        db.insert_output_log(session.id, OutputStream::Stdout, "héllo").unwrap();
+        db.insert_output_log(session.id, OutputStream::Stderr, "oops").unwrap();
+        db.record_activity(ActivityKind::SessionFinished, repo.id, Some(session.id), "completed")
+            .unwrap();
+        db.refresh_daily_stats().unwrap();
+        // Refreshing again without new rows changes nothing
+        db.refresh_daily_stats().unwrap();
+
+        let stats = db.list_daily_stats(Some(repo.id), today, today).unwrap();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].day, today);
+        assert_eq!(stats[0].stats.runs, 1);
+        assert_eq!(stats[0].stats.successes, 1);
+        assert_eq!(stats[0].stats.output_lines, 2);
+        assert_eq!(stats[0].stats.output_bytes, 10);
+
+        // A cancelled run reports its end twice but counts once
+        db.record_activity(ActivityKind::SessionStarted, repo.id, Some(session.id), "Again")
+            .unwrap();
+        db.record_activity(ActivityKind::SessionFinished, repo.id, Some(session.id), "cancelled")
+            .unwrap();
+        db.record_activity(ActivityKind::SessionFinished, repo.id, Some(session.id), "error")
+            .unwrap();
+        db.refresh_daily_stats().unwrap();
+
+        let stats = db.list_daily_stats(None, today, today).unwrap();
+        assert_eq!(stats[0].stats.runs, 2);
+        assert_eq!(stats[0].stats.cancelled, 1);
+        assert_eq!(stats[0].stats.failures, 0);
+        assert!(db
+            .list_daily_stats(None, today.pred_opt().unwrap(), today.pred_opt().unwrap())
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_artifacts() {
+        let db = Database::in_memory().expect("Failed to create in-memory database");
+        let repo = db.insert_repo("/path/to/repo", "my-repo").unwrap();
+        let session = db.insert_session(repo.id, None, Orchestrator::Ralph).unwrap();
+
+        let report = db.record_artifact(session.id, repo.id, "reports/summary.md", 10).unwrap();
+        db.record_artifact(session.id, repo.id, "shot.png", 2048).unwrap();
+        // Detecting the same file again after a later run updates it
+        let updated = db.record_artifact(session.id, repo.id, "reports/summary.md", 20).unwrap();
+        assert_eq!(updated.id, report.id);
+        assert_eq!(updated.size_bytes, 20);
+
+        let artifacts = db.list_artifacts_by_session(session.id).unwrap();
+        assert_eq!(artifacts.len(), 2);
+        assert_eq!(db.get_artifact(report.id).unwrap().path, "reports/summary.md");
+
+        db.delete_session(session.id).unwrap();
+        assert!(matches!(db.get_artifact(report.id), Err(DbError::NotFound)));
+    }
+
+    #[test]
+    fn test_deleting_user_shares_their_resources() {
+        let db = Database::in_memory().expect("Failed to create in-memory database");
+        let user = db
+            .insert_user("bob", UserRole::Member, "hash-b")
+            .expect("Failed to insert user");
+
+        let repo = db
+            .insert_repo_for("/path/to/repo", "my-repo", Some(user.id))
+            .expect("Failed to insert repo");
+        let session = db
+            .insert_session_for(repo.id, None, Orchestrator::Ralph, Some(user.id))
+            .expect("Failed to insert session");
+        assert_eq!(db.get_repo(repo.id).unwrap().owner_id, Some(user.id));
+        assert_eq!(db.get_session(session.id).unwrap().owner_id, Some(user.id));
+
+        db.delete_user(user.id).expect("Failed to delete user");
+        assert_eq!(db.get_repo(repo.id).unwrap().owner_id, None);
+        assert_eq!(db.get_session(session.id).unwrap().owner_id, None);
+    }
+
     #[test]
     fn test_session_crud() {
         let db = Database::in_memory().expect("Failed to create in-memory database");
@@ -713,6 +4241,29 @@ mod tests {
             .expect("Failed to update status");
         let updated = db.get_session(session.id).expect("Failed to get session");
         assert_eq!(updated.status, SessionStatus::Running);
+        assert!(updated.failure.is_none());
+
+        // Record and clear a failure
+        let failure = Failure::new(FailureReason::RateLimited, "429 Too Many Requests");
+        db.set_session_failure(session.id, Some(&failure))
+            .expect("Failed to set failure");
+        assert_eq!(db.get_session(session.id).unwrap().failure, Some(failure));
+        db.set_session_failure(session.id, None)
+            .expect("Failed to clear failure");
+        assert!(db.get_session(session.id).unwrap().failure.is_none());
+
+        // Archive, keeping when it was first archived, and restore
+        db.set_session_archived(session.id, true).expect("Failed to archive");
+        let archived = db.get_session(session.id).unwrap();
+        assert!(archived.archived);
+        let archived_at = archived.archived_at.expect("No archive time");
+        db.set_session_archived(session.id, true).expect("Failed to archive");
+        assert_eq!(db.get_session(session.id).unwrap().archived_at, Some(archived_at));
+        db.set_session_archived(session.id, false).expect("Failed to restore");
+        let restored = db.get_session(session.id).unwrap();
+        assert!(!restored.archived);
+        assert!(restored.archived_at.is_none());
+        assert!(matches!(db.set_session_archived(Uuid::new_v4(), true), Err(DbError::NotFound)));
 
         // List
         let sessions = db.list_sessions().expect("Failed to list sessions");
@@ -731,6 +4282,147 @@ mod tests {
         assert!(sessions.is_empty());
     }
 
+    #[test]
+    fn test_runs() {
+        let db = Database::in_memory().expect("Failed to create in-memory database");
+        let repo = db.insert_repo("/path/to/repo", "my-repo").unwrap();
+        let session = db.insert_session(repo.id, None, Orchestrator::Ralph).unwrap();
+
+        let first = db.insert_run(session.id, "Fix the tests").unwrap();
+        db.set_run_exit_code(session.id, Some(1)).unwrap();
+        let report = TokenUsage {
+            input_tokens: 100,
+            output_tokens: 20,
+            cost_usd: 0.5,
+            ..Default::default()
+        };
+        db.add_run_usage(session.id, &report).unwrap();
+        let totals = db.add_run_usage(session.id, &report).unwrap().unwrap();
+        assert_eq!((totals.input_tokens, totals.output_tokens, totals.cost_usd), (200, 40, 1.0));
+        db.finish_run(session.id, SessionStatus::Error).unwrap();
+        // Nothing is open to add to
+        assert_eq!(db.add_run_usage(session.id, &report).unwrap(), None);
+        // Finishing again leaves the closed run alone
+        db.finish_run(session.id, SessionStatus::Completed).unwrap();
+
+        // A run left open is cancelled when the next one starts
+        db.insert_run(session.id, "Try again").unwrap();
+        let last = db.insert_run(session.id, "And again").unwrap();
+
+        let runs = db.list_runs(session.id).unwrap();
+        assert_eq!(runs.len(), 3);
+        assert_eq!(runs[0].id, last.id);
+        assert_eq!(runs[0].status, SessionStatus::Running);
+        assert!(runs[0].duration_ms.is_none());
+        assert_eq!(runs[1].status, SessionStatus::Cancelled);
+        assert_eq!(runs[2].id, first.id);
+        assert_eq!(runs[2].prompt, "Fix the tests");
+        assert_eq!(runs[2].status, SessionStatus::Error);
+        assert_eq!(runs[2].exit_code, Some(1));
+        assert!(runs[2].duration_ms.unwrap() >= 0);
+        assert_eq!(runs[2].usage.input_tokens, 200);
+
+        let stats = db.session_stats(session.id).unwrap();
+        assert_eq!((stats.runs, stats.usage.output_tokens, stats.usage.cost_usd), (3, 40, 1.0));
+
+        db.delete_session(session.id).unwrap();
+        assert!(db.list_runs(session.id).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_session_tags() {
+        let db = Database::in_memory().expect("Failed to create in-memory database");
+        let repo = db.insert_repo("/path/to/repo", "my-repo").unwrap();
+        let session = db.insert_session(repo.id, None, Orchestrator::Ralph).unwrap();
+        let other = db.insert_session(repo.id, None, Orchestrator::Ralph).unwrap();
+
+        db.add_session_tag(session.id, "experiment").unwrap();
+        db.add_session_tag(session.id, "bugfix").unwrap();
+        db.add_session_tag(session.id, "bugfix").unwrap();
+        db.add_session_tag(other.id, "bugfix").unwrap();
+        assert_eq!(db.list_session_tags(session.id).unwrap(), ["bugfix", "experiment"]);
+        assert_eq!(db.get_session(session.id).unwrap().tags, ["bugfix", "experiment"]);
+        let sessions = db.list_sessions().unwrap();
+        assert!(sessions.iter().all(|session| session.tags.contains(&"bugfix".to_string())));
+
+        db.remove_session_tag(session.id, "experiment").unwrap();
+        assert_eq!(db.get_session(session.id).unwrap().tags, ["bugfix"]);
+        assert!(matches!(db.remove_session_tag(session.id, "experiment"), Err(DbError::NotFound)));
+
+        db.delete_session(session.id).unwrap();
+        assert!(db.list_session_tags(session.id).unwrap().is_empty());
+        assert_eq!(db.get_session(other.id).unwrap().tags, ["bugfix"]);
+    }
+
+    #[test]
+    fn test_session_metadata() {
+        let db = Database::in_memory().expect("Failed to create in-memory database");
+        let repo = db.insert_repo("/path/to/repo", "my-repo").unwrap();
+        let session = db.insert_session(repo.id, None, Orchestrator::Ralph).unwrap();
+        assert!(session.metadata.is_empty());
+        let patch = |value: serde_json::Value| value.as_object().unwrap().clone();
+
+        let first = serde_json::json!({
+            "ticket": "ENG-1",
+            "review": { "by": "ana", "done": false },
+        });
+        db.patch_session_metadata(session.id, &patch(first)).unwrap();
+        let metadata = db
+            .patch_session_metadata(
+                session.id,
+                &patch(serde_json::json!({ "ticket": null, "review": { "done": true } })),
+            )
+            .unwrap();
+        let expected = serde_json::json!({ "review": { "by": "ana", "done": true } });
+        assert_eq!(serde_json::Value::Object(metadata), expected);
+        let stored = db.get_session(session.id).unwrap().metadata;
+        assert_eq!(serde_json::Value::Object(stored), expected);
+
+        let huge = patch(serde_json::json!({ "log": "x".repeat(MAX_SESSION_METADATA_BYTES) }));
+        assert!(matches!(
+            db.patch_session_metadata(session.id, &huge),
+            Err(DbError::InvalidData(_))
+        ));
+        let stored = db.get_session(session.id).unwrap().metadata;
+        assert_eq!(serde_json::Value::Object(stored), expected);
+        assert!(matches!(
+            db.patch_session_metadata(Uuid::new_v4(), &patch(expected)),
+            Err(DbError::NotFound)
+        ));
+    }
+
+    #[test]
+    fn test_pipeline_steps() {
+        let db = Database::in_memory().expect("Failed to create in-memory database");
+        let repo = db.insert_repo("/path/to/repo", "my-repo").unwrap();
+        let session = db.insert_session(repo.id, None, Orchestrator::Ralph).unwrap();
+        let prompts = ["Write tests", "Fix them", "Refactor"].map(String::from);
+
+        let steps = db.set_pipeline(session.id, &prompts).unwrap();
+        assert_eq!(steps.iter().map(|step| step.step).collect::<Vec<_>>(), [1, 2, 3]);
+
+        let step = db.start_pipeline_step(session.id).unwrap().unwrap();
+        assert_eq!((step.step, step.status), (1, StepStatus::Running));
+        let changed = db.finish_pipeline_step(session.id, StepStatus::Completed).unwrap();
+        assert_eq!(changed.len(), 1);
+        assert_eq!(db.next_pipeline_step(session.id).unwrap().unwrap().prompt, "Fix them");
+
+        // A step that fails skips the rest
+        db.start_pipeline_step(session.id).unwrap();
+        let changed = db.finish_pipeline_step(session.id, StepStatus::Error).unwrap();
+        assert_eq!(
+            changed.iter().map(|step| (step.step, step.status)).collect::<Vec<_>>(),
+            [(2, StepStatus::Error), (3, StepStatus::Skipped)]
+        );
+        assert!(db.next_pipeline_step(session.id).unwrap().is_none());
+        assert!(db.start_pipeline_step(session.id).unwrap().is_none());
+
+        let steps = db.list_pipeline_steps(session.id).unwrap();
+        assert_eq!(steps[0].status, StepStatus::Completed);
+        assert!(steps[0].finished_at.is_some());
+        assert!(steps[2].started_at.is_none());
+    }
+
     #[test]
     fn test_message_crud() {
         let db = Database::in_memory().expect("Failed to create in-memory database");
@@ -761,6 +4453,15 @@ mod tests {
         assert_eq!(messages.len(), 2);
         assert_eq!(messages[0].content, "Hello!");
         assert_eq!(messages[1].content, "Hi there!");
+        assert_eq!(messages[1].kind, MessageKind::Text);
+
+        // Tool calls keep their details
+        let input = serde_json::json!({ "name": "Bash", "input": { "command": "cargo test" } });
+        db.insert_typed_message(session.id, MessageRole::Assistant, MessageKind::ToolCall, "Bash", Some(&input))
+            .unwrap();
+        let messages = db.list_messages(session.id).unwrap();
+        assert_eq!(messages[2].kind, MessageKind::ToolCall);
+        assert_eq!(messages[2].data, Some(input));
     }
 
     #[test]
@@ -877,6 +4578,53 @@ mod tests {
         assert_eq!(limited_offset.len(), 1);
         assert_eq!(limited_offset[0].content, "Hello stderr!");
 
+        // Count
+        assert_eq!(db.count_output_logs(session.id, None).unwrap(), 3);
+        assert_eq!(
+            db.count_output_logs(session.id, Some(OutputStream::Stdout)).unwrap(),
+            2
+        );
+
+        // Channels default from the stream and can be filtered on
+        assert_eq!(log1.channel, OutputChannel::AssistantText);
+        assert_eq!(log2.channel, OutputChannel::Stderr);
+        db.insert_channel_output_log(session.id, OutputStream::Stdout, OutputChannel::ToolCall, "read_file", false)
+            .expect("Failed to insert output log");
+        let tools = db
+            .list_channel_output_logs(session.id, None, Some(&[OutputChannel::ToolCall]), None, None)
+            .expect("Failed to list tool calls");
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].content, "read_file");
+        assert_eq!(
+            db.count_channel_output_logs(
+                session.id,
+                Some(OutputStream::Stdout),
+                Some(&[OutputChannel::AssistantText, OutputChannel::ToolCall])
+            )
+            .unwrap(),
+            3
+        );
+
+        // Long content is stored compressed and read back as written
+        let long = "cargo test -- all green\n".repeat(200);
+        let log = db
+            .insert_output_log(session.id, OutputStream::Stdout, &long)
+            .expect("Failed to insert output log");
+        let stored: (String, i64) = db
+            .conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT typeof(content), length(content) FROM output_logs WHERE id = ?1",
+                params![log.id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(stored.0, "blob");
+        assert!((stored.1 as usize) < long.len());
+        let all = db.list_output_logs(session.id, None, None, None).unwrap();
+        assert_eq!(all.last().unwrap().content, long);
+
         // Delete logs
         db.delete_output_logs(session.id)
             .expect("Failed to delete output logs");
@@ -911,4 +4659,78 @@ mod tests {
             .expect("Failed to list logs");
         assert!(logs.is_empty());
     }
+
+    #[test]
+    fn test_prune_output_logs() {
+        let db = Database::in_memory().expect("Failed to create in-memory database");
+        let repo = db.insert_repo("/path/to/repo", "my-repo").unwrap();
+        let busy = db.insert_session(repo.id, None, Orchestrator::Ralph).unwrap();
+        let quiet = db.insert_session(repo.id, None, Orchestrator::Ralph).unwrap();
+        let line = "x".repeat(1000);
+        for _ in 0..200 {
+            db.insert_output_log(busy.id, OutputStream::Stdout, &line).unwrap();
+        }
+        db.insert_output_log(quiet.id, OutputStream::Stdout, "only line").unwrap();
+
+        // Only the newest lines of each session are kept
+        let pruned = db.prune_output_logs(None, Some(10)).unwrap();
+        assert_eq!(pruned.deleted, 190);
+        assert!(pruned.bytes_freed > 0);
+        let kept = db.list_output_logs(busy.id, None, None, None).unwrap();
+        assert_eq!(kept.len(), 10);
+        assert!(kept.iter().all(|log| log.id > 190));
+        assert_eq!(db.count_output_logs(quiet.id, None).unwrap(), 1);
+
+        assert_eq!(db.prune_output_logs(None, None).unwrap(), PrunedOutput::default());
+        let pruned = db
+            .prune_output_logs(Some(Utc::now() + chrono::Duration::seconds(1)), Some(10))
+            .unwrap();
+        assert_eq!(pruned.deleted, 11);
+        assert_eq!(db.count_output_logs(busy.id, None).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_search_messages_and_output() {
+        let db = Database::in_memory().expect("Failed to create in-memory database");
+        let repo = db.insert_repo("/path/to/repo", "my-repo").unwrap();
+        let user = db.insert_user("alice", UserRole::Member, "alice-hash").unwrap();
+        let other = db.insert_user("bob", UserRole::Member, "bob-hash").unwrap();
+        let shared = db.insert_session(repo.id, Some("shared"), Orchestrator::Ralph).unwrap();
+        let private = db
+            .insert_session_for(repo.id, None, Orchestrator::Ralph, Some(other.id))
+            .unwrap();
+        db.insert_message(shared.id, MessageRole::Assistant, "Rewired the flux capacitor")
+            .unwrap();
+        db.insert_message(private.id, MessageRole::User, "Check the flux levels").unwrap();
+        // Long output is stored compressed and still searchable
+        let long = format!("{} flux overflow", "compiling ".repeat(200));
+        db.insert_output_log(shared.id, OutputStream::Stdout, &long).unwrap();
+
+        let results = db.search("FLUX", None, 10).unwrap();
+        assert_eq!(results.messages.len(), 2);
+        assert_eq!(results.output.len(), 1);
+        assert_eq!(results.sessions.len(), 2);
+        let hit = results.sessions.iter().find(|hit| hit.session_id == shared.id).unwrap();
+        assert_eq!((hit.message_matches, hit.output_matches), (1, 1));
+        let snippet = &results.output[0].snippet;
+        assert!(snippet.iter().any(|part| part.highlight && part.text == "flux"));
+        assert!(snippet.last().unwrap().text.ends_with("overflow"));
+
+        // All words must match, and query syntax is searched for literally
+        assert_eq!(db.search("flux capacitor", None, 10).unwrap().messages.len(), 1);
+        assert!(db.search("flux AND (\"", None, 10).unwrap().messages.is_empty());
+        assert!(db.search("   ", None, 10).unwrap().sessions.is_empty());
+
+        // Members only find shared sessions and their own
+        let visible = db.search("flux", Some(user.id), 10).unwrap();
+        assert!(visible.sessions.iter().all(|hit| hit.session_id == shared.id));
+        assert_eq!(visible.messages.len(), 1);
+
+        // Deleted output and messages leave the index
+        db.delete_output_logs(shared.id).unwrap();
+        db.delete_session(private.id).unwrap();
+        let results = db.search("flux", None, 10).unwrap();
+        assert!(results.output.is_empty());
+        assert_eq!(results.messages.len(), 1);
+    }
 }
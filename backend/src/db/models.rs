@@ -1,13 +1,17 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::failure::Failure;
+
 /// Repository model representing a git repository
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Repo {
     pub id: Uuid,
     pub path: String,
     pub name: String,
+    /// User who added the repo; `None` means shared with everyone
+    pub owner_id: Option<Uuid>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -17,6 +21,8 @@ pub struct Repo {
 #[serde(rename_all = "lowercase")]
 pub enum SessionStatus {
     Idle,
+    /// Waiting for the repository's earlier runs to end
+    Queued,
     Running,
     Completed,
     Error,
@@ -27,6 +33,7 @@ impl SessionStatus {
     pub fn as_str(&self) -> &'static str {
         match self {
             SessionStatus::Idle => "idle",
+            SessionStatus::Queued => "queued",
             SessionStatus::Running => "running",
             SessionStatus::Completed => "completed",
             SessionStatus::Error => "error",
@@ -37,6 +44,7 @@ impl SessionStatus {
     pub fn from_str(s: &str) -> Result<Self, String> {
         match s {
             "idle" => Ok(SessionStatus::Idle),
+            "queued" => Ok(SessionStatus::Queued),
             "running" => Ok(SessionStatus::Running),
             "completed" => Ok(SessionStatus::Completed),
             "error" => Ok(SessionStatus::Error),
@@ -47,20 +55,30 @@ impl SessionStatus {
 }
 
 /// Orchestrator type for AI coding sessions
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
+///
+/// Besides the built-in orchestrators, sessions can use orchestrators declared
+/// in configuration (see [`crate::orchestrator`]), identified by their id.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Orchestrator {
     Ralph,
     Gsd,
     Gastown,
+    Claude,
+    Aider,
+    Gemini,
+    Plugin(String),
 }
 
 impl Orchestrator {
-    pub fn as_str(&self) -> &'static str {
+    pub fn as_str(&self) -> &str {
         match self {
             Orchestrator::Ralph => "ralph",
             Orchestrator::Gsd => "gsd",
             Orchestrator::Gastown => "gastown",
+            Orchestrator::Claude => "claude",
+            Orchestrator::Aider => "aider",
+            Orchestrator::Gemini => "gemini",
+            Orchestrator::Plugin(id) => id,
         }
     }
 
@@ -69,26 +87,59 @@ impl Orchestrator {
             "ralph" => Ok(Orchestrator::Ralph),
             "gsd" => Ok(Orchestrator::Gsd),
             "gastown" => Ok(Orchestrator::Gastown),
+            "claude" => Ok(Orchestrator::Claude),
+            "aider" => Ok(Orchestrator::Aider),
+            "gemini" => Ok(Orchestrator::Gemini),
+            _ if is_plugin_id(s) => Ok(Orchestrator::Plugin(s.to_string())),
             _ => Err(format!("invalid orchestrator: '{}'", s)),
         }
     }
 
-    /// Check if this orchestrator is currently available
+    /// Check if this built-in orchestrator is currently available
+    ///
+    /// Configured orchestrators are checked against the server's settings
+    /// instead (see `Settings::orchestrator_available`).
     pub fn is_available(&self) -> bool {
         match self {
             Orchestrator::Ralph => true,
             Orchestrator::Gsd => false,
             Orchestrator::Gastown => false,
+            Orchestrator::Claude | Orchestrator::Aider | Orchestrator::Gemini => {
+                crate::ralph::adapter::builtin(self).is_some_and(|adapter| adapter.is_installed())
+            }
+            Orchestrator::Plugin(_) => false,
         }
     }
 }
 
+/// Whether `s` can name a configured orchestrator
+fn is_plugin_id(s: &str) -> bool {
+    (1..=64).contains(&s.len())
+        && s.starts_with(|c: char| c.is_ascii_lowercase() || c.is_ascii_digit())
+        && s
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '-' | '_'))
+}
+
 impl Default for Orchestrator {
     fn default() -> Self {
         Orchestrator::Ralph
     }
 }
 
+impl Serialize for Orchestrator {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Orchestrator {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Orchestrator::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
 /// Session model representing a Ralph session
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Session {
@@ -97,6 +148,28 @@ pub struct Session {
     pub name: Option<String>,
     pub orchestrator: Orchestrator,
     pub status: SessionStatus,
+    /// User who created the session; `None` means shared with everyone
+    pub owner_id: Option<Uuid>,
+    /// Pull request opened from the session's branch
+    pub pr_url: Option<String>,
+    /// Issue the session was created from
+    pub issue_url: Option<String>,
+    /// Prompt to pre-fill before the first run
+    pub prompt_draft: Option<String>,
+    /// Why the last run failed, when its stderr told
+    #[serde(default)]
+    pub failure: Option<Failure>,
+    /// Archived sessions are kept but left out of the session list
+    #[serde(default)]
+    pub archived: bool,
+    #[serde(default)]
+    pub archived_at: Option<DateTime<Utc>>,
+    /// Labels attached to the session, in alphabetical order
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Free-form JSON set by clients, e.g. ticket ids or reviewers
+    #[serde(default)]
+    pub metadata: serde_json::Map<String, serde_json::Value>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -129,13 +202,50 @@ impl MessageRole {
     }
 }
 
+/// What a message holds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageKind {
+    /// Text written by the user or the agent
+    #[default]
+    Text,
+    /// A tool the agent called; the data has its `name` and `input`
+    ToolCall,
+    /// A file the agent changed; the data has its `path` and the `tool` used
+    FileEdit,
+}
+
+impl MessageKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MessageKind::Text => "text",
+            MessageKind::ToolCall => "tool_call",
+            MessageKind::FileEdit => "file_edit",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "text" => Ok(MessageKind::Text),
+            "tool_call" => Ok(MessageKind::ToolCall),
+            "file_edit" => Ok(MessageKind::FileEdit),
+            _ => Err(format!("invalid message kind: '{}'", s)),
+        }
+    }
+}
+
 /// Message model representing a chat message
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Message {
     pub id: Uuid,
     pub session_id: Uuid,
     pub role: MessageRole,
+    #[serde(default)]
+    pub kind: MessageKind,
     pub content: String,
+    /// Details of a tool call or file edit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -164,13 +274,60 @@ impl OutputStream {
     }
 }
 
+/// What kind of output a line is, as told by the orchestrator's output parser
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputChannel {
+    /// Prose written by the agent
+    AssistantText,
+    /// A tool the agent invoked, or its result
+    ToolCall,
+    /// Lines from the orchestrator or Ralphtown itself rather than the agent
+    System,
+    /// Anything written to stderr
+    Stderr,
+}
+
+impl OutputChannel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutputChannel::AssistantText => "assistant_text",
+            OutputChannel::ToolCall => "tool_call",
+            OutputChannel::System => "system",
+            OutputChannel::Stderr => "stderr",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "assistant_text" => Ok(OutputChannel::AssistantText),
+            "tool_call" => Ok(OutputChannel::ToolCall),
+            "system" => Ok(OutputChannel::System),
+            "stderr" => Ok(OutputChannel::Stderr),
+            _ => Err(format!("invalid output channel: '{}'", s)),
+        }
+    }
+
+    /// Channel of a line nothing more is known about
+    pub fn for_stream(stream: OutputStream) -> Self {
+        match stream {
+            OutputStream::Stdout => OutputChannel::AssistantText,
+            OutputStream::Stderr => OutputChannel::Stderr,
+        }
+    }
+}
+
 /// Output log entry from Ralph process
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OutputLog {
     pub id: i64,
     pub session_id: Uuid,
     pub stream: OutputStream,
+    pub channel: OutputChannel,
     pub content: String,
+    /// The line was too long for one entry and continues in the next
+    #[serde(default)]
+    pub continues: bool,
     pub created_at: DateTime<Utc>,
 }
 
@@ -181,3 +338,659 @@ pub struct ConfigEntry {
     pub value: String,
     pub updated_at: DateTime<Utc>,
 }
+
+/// User role controlling what a user may see and change
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UserRole {
+    /// Full access to every resource, user management and server settings
+    Admin,
+    /// Manages their own repos and sessions, sees shared ones
+    Member,
+    /// Read-only access to every repo and session
+    Viewer,
+}
+
+impl UserRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UserRole::Admin => "admin",
+            UserRole::Member => "member",
+            UserRole::Viewer => "viewer",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "admin" => Ok(UserRole::Admin),
+            "member" => Ok(UserRole::Member),
+            "viewer" => Ok(UserRole::Viewer),
+            _ => Err(format!("invalid user role: '{}'", s)),
+        }
+    }
+}
+
+/// User account sharing the server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct User {
+    pub id: Uuid,
+    pub username: String,
+    pub role: UserRole,
+    /// Show a desktop notification when one of the user's sessions finishes
+    pub desktop_notifications: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Outgoing webhook endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Webhook {
+    pub id: Uuid,
+    pub url: String,
+    /// Key for the HMAC-SHA256 signature; never sent back to clients
+    #[serde(skip_serializing, default)]
+    pub secret: String,
+    /// Event types delivered to this endpoint; empty means every event
+    pub events: Vec<String>,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Webhook {
+    /// Whether this endpoint wants events of the given type
+    pub fn accepts(&self, event: &str) -> bool {
+        self.enabled && (self.events.is_empty() || self.events.iter().any(|e| e == event))
+    }
+}
+
+/// Inbound hook that starts a predefined prompt on a repository
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trigger {
+    pub id: Uuid,
+    pub name: String,
+    pub repo_id: Uuid,
+    /// Session each call continues; `None` starts a new session per call
+    pub session_id: Option<Uuid>,
+    /// Prompt with `{field}` placeholders filled from the request payload
+    pub prompt_template: String,
+    pub orchestrator: Orchestrator,
+    pub enabled: bool,
+    pub last_triggered_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A prompt run on a repository at the times of a cron expression
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Schedule {
+    pub id: Uuid,
+    pub name: String,
+    pub repo_id: Uuid,
+    pub prompt: String,
+    /// Five-field cron expression, in UTC (see [`crate::cron`])
+    pub cron: String,
+    pub orchestrator: Orchestrator,
+    pub enabled: bool,
+    /// When it fires next; `None` when the expression never matches again
+    pub next_run_at: Option<DateTime<Utc>>,
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A time a schedule fired
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleRun {
+    pub id: i64,
+    pub schedule_id: Uuid,
+    /// Session started for the run; `None` if none could be created
+    pub session_id: Option<Uuid>,
+    /// Why the run couldn't start
+    pub error: Option<String>,
+    pub fired_at: DateTime<Utc>,
+}
+
+/// A prompt that was run on a repository
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptHistoryEntry {
+    pub id: i64,
+    pub repo_id: Uuid,
+    pub prompt: String,
+    /// Number of times the prompt was submitted
+    pub use_count: u32,
+    pub last_used_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// What happened in an activity feed entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityKind {
+    SessionStarted,
+    /// The run completed, failed, or was cancelled
+    SessionFinished,
+    RepoAdded,
+    CommitMade,
+    CloneCompleted,
+}
+
+impl ActivityKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ActivityKind::SessionStarted => "session_started",
+            ActivityKind::SessionFinished => "session_finished",
+            ActivityKind::RepoAdded => "repo_added",
+            ActivityKind::CommitMade => "commit_made",
+            ActivityKind::CloneCompleted => "clone_completed",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "session_started" => Ok(ActivityKind::SessionStarted),
+            "session_finished" => Ok(ActivityKind::SessionFinished),
+            "repo_added" => Ok(ActivityKind::RepoAdded),
+            "commit_made" => Ok(ActivityKind::CommitMade),
+            "clone_completed" => Ok(ActivityKind::CloneCompleted),
+            _ => Err(format!("invalid activity kind: '{}'", s)),
+        }
+    }
+}
+
+/// An entry in the activity feed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityEvent {
+    pub id: i64,
+    pub kind: ActivityKind,
+    pub repo_id: Uuid,
+    pub session_id: Option<Uuid>,
+    /// One-line description, e.g. the commit message or final status
+    pub summary: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Run and output totals over some period
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunStats {
+    /// Runs started
+    pub runs: u32,
+    pub successes: u32,
+    pub failures: u32,
+    pub cancelled: u32,
+    /// Combined wall-clock time of the finished runs
+    pub runtime_secs: u64,
+    /// Lines of stdout and stderr written
+    pub output_lines: u64,
+    pub output_bytes: u64,
+}
+
+impl RunStats {
+    /// Add another period's totals to these
+    pub fn add(&mut self, other: &RunStats) {
+        self.runs += other.runs;
+        self.successes += other.successes;
+        self.failures += other.failures;
+        self.cancelled += other.cancelled;
+        self.runtime_secs += other.runtime_secs;
+        self.output_lines += other.output_lines;
+        self.output_bytes += other.output_bytes;
+    }
+}
+
+/// A repository's totals for one UTC day
+///
+/// Runs, outcomes, and runtime count toward the day a run started; output
+/// toward the day it was written.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyStats {
+    pub day: NaiveDate,
+    pub repo_id: Uuid,
+    #[serde(flatten)]
+    pub stats: RunStats,
+}
+
+/// A file a run left in the working tree, such as a report or screenshot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Artifact {
+    pub id: Uuid,
+    pub session_id: Uuid,
+    pub repo_id: Uuid,
+    /// Path relative to the repository root
+    pub path: String,
+    pub size_bytes: u64,
+    /// When the artifact was last detected
+    pub created_at: DateTime<Utc>,
+}
+
+/// A file uploaded for use in prompts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Upload {
+    pub id: Uuid,
+    pub owner_id: Option<Uuid>,
+    /// Name the file was uploaded with, reduced to a plain file name
+    pub filename: String,
+    pub content_type: Option<String>,
+    pub size_bytes: u64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A file or directory pinned in a repository
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub id: Uuid,
+    pub repo_id: Uuid,
+    /// Path relative to the repository root
+    pub path: String,
+    pub label: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A linked worktree checked out for a repository, giving a session its own
+/// working tree
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Worktree {
+    pub id: Uuid,
+    pub repo_id: Uuid,
+    /// Session whose runs work in it
+    pub session_id: Option<Uuid>,
+    pub branch: String,
+    pub path: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A file attached to a session, handed to every run of it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Attachment {
+    pub id: Uuid,
+    pub session_id: Uuid,
+    /// Name the file was uploaded with, reduced to a plain file name
+    pub filename: String,
+    pub content_type: Option<String>,
+    pub size_bytes: u64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// One run of a session: a prompt and how the process it started ended
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Run {
+    pub id: Uuid,
+    pub session_id: Uuid,
+    pub prompt: String,
+    /// Running until the run ends, then how it ended
+    pub status: SessionStatus,
+    /// The process's exit code; null while it runs, for runs on a worker, and
+    /// when it was killed by a signal
+    pub exit_code: Option<i32>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// How long the run took, in milliseconds; null while it runs
+    pub duration_ms: Option<i64>,
+    /// Tokens and cost the run's output reported
+    pub usage: TokenUsage,
+}
+
+/// Tokens an agent used and what they cost, as its output reported them
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// Input tokens read from the provider's prompt cache
+    pub cache_read_tokens: u64,
+    /// Input tokens written to the provider's prompt cache
+    pub cache_write_tokens: u64,
+    pub cost_usd: f64,
+}
+
+impl TokenUsage {
+    /// Whether nothing was reported
+    pub fn is_empty(&self) -> bool {
+        *self == TokenUsage::default()
+    }
+
+    /// Add another report to these totals
+    pub fn add(&mut self, other: &TokenUsage) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_read_tokens += other.cache_read_tokens;
+        self.cache_write_tokens += other.cache_write_tokens;
+        self.cost_usd += other.cost_usd;
+    }
+}
+
+/// Token and cost totals over all of a session's runs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionStats {
+    pub runs: u32,
+    #[serde(flatten)]
+    pub usage: TokenUsage,
+}
+
+/// Where a step of a pipeline is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    /// Waiting for the steps before it
+    Pending,
+    Running,
+    Completed,
+    Error,
+    Cancelled,
+    /// Not run because a step before it didn't complete
+    Skipped,
+}
+
+impl StepStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StepStatus::Pending => "pending",
+            StepStatus::Running => "running",
+            StepStatus::Completed => "completed",
+            StepStatus::Error => "error",
+            StepStatus::Cancelled => "cancelled",
+            StepStatus::Skipped => "skipped",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "pending" => Ok(StepStatus::Pending),
+            "running" => Ok(StepStatus::Running),
+            "completed" => Ok(StepStatus::Completed),
+            "error" => Ok(StepStatus::Error),
+            "cancelled" => Ok(StepStatus::Cancelled),
+            "skipped" => Ok(StepStatus::Skipped),
+            _ => Err(format!("invalid step status: '{}'", s)),
+        }
+    }
+
+    /// Status of a step whose run ended with `status`
+    pub fn ended_as(status: SessionStatus) -> Self {
+        match status {
+            SessionStatus::Completed => StepStatus::Completed,
+            SessionStatus::Cancelled => StepStatus::Cancelled,
+            _ => StepStatus::Error,
+        }
+    }
+}
+
+/// One prompt of a session's pipeline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineStep {
+    pub session_id: Uuid,
+    /// Place in the pipeline; 1 runs first
+    pub step: u32,
+    pub prompt: String,
+    pub status: StepStatus,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// One iteration of a run, as marked in its output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Iteration {
+    pub id: i64,
+    pub session_id: Uuid,
+    /// Iteration number as printed by the orchestrator
+    pub number: u32,
+    /// Output log line that started the iteration
+    pub first_output_id: i64,
+    /// Last output log line of the iteration; null while it runs
+    pub last_output_id: Option<i64>,
+    /// Last line of prose the agent wrote in the iteration
+    pub summary: Option<String>,
+    /// Cursor for the session's output listing that starts at this iteration
+    pub output_cursor: String,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>,
+}
+
+/// Why a workspace snapshot was taken
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapshotReason {
+    /// Before a run started in the session
+    PreRun,
+    /// Before another snapshot was restored over the working tree
+    PreRestore,
+}
+
+impl SnapshotReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SnapshotReason::PreRun => "pre_run",
+            SnapshotReason::PreRestore => "pre_restore",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "pre_run" => Ok(SnapshotReason::PreRun),
+            "pre_restore" => Ok(SnapshotReason::PreRestore),
+            _ => Err(format!("invalid snapshot reason: '{}'", s)),
+        }
+    }
+}
+
+/// Copy of a repository's uncommitted changes, kept outside of git
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub id: Uuid,
+    pub session_id: Uuid,
+    pub repo_id: Uuid,
+    pub reason: SnapshotReason,
+    /// Commit HEAD pointed at; `None` for a repository without commits
+    pub head_commit: Option<String>,
+    /// Modified and untracked files copied into the snapshot
+    pub file_count: u32,
+    /// Tracked files that were deleted in the working tree
+    pub deleted_count: u32,
+    pub size_bytes: u64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// State of a webhook delivery
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryStatus {
+    /// Not delivered yet; attempts may still be in progress
+    Pending,
+    /// The endpoint answered with a 2xx status
+    Delivered,
+    /// Every attempt failed
+    Failed,
+}
+
+impl DeliveryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryStatus::Pending => "pending",
+            DeliveryStatus::Delivered => "delivered",
+            DeliveryStatus::Failed => "failed",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "pending" => Ok(DeliveryStatus::Pending),
+            "delivered" => Ok(DeliveryStatus::Delivered),
+            "failed" => Ok(DeliveryStatus::Failed),
+            _ => Err(format!("invalid delivery status: '{}'", s)),
+        }
+    }
+}
+
+/// One event sent (or being sent) to a webhook endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub webhook_id: Uuid,
+    pub event: String,
+    pub payload: serde_json::Value,
+    pub status: DeliveryStatus,
+    pub attempts: u32,
+    /// HTTP status of the last response, if any
+    pub response_status: Option<u16>,
+    /// Error from the last failed attempt
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Kind of long-running operation tracked as a job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    /// Clone a repository and register it
+    Clone,
+    /// Look for git repositories under some directories
+    Scan,
+    /// Run one of the background maintenance jobs on demand
+    Maintenance,
+}
+
+impl JobKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobKind::Clone => "clone",
+            JobKind::Scan => "scan",
+            JobKind::Maintenance => "maintenance",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "clone" => Ok(JobKind::Clone),
+            "scan" => Ok(JobKind::Scan),
+            "maintenance" => Ok(JobKind::Maintenance),
+            _ => Err(format!("invalid job kind: '{}'", s)),
+        }
+    }
+}
+
+/// Where a job is in its lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    /// Waiting for a free worker
+    Queued,
+    Running,
+    /// Finished successfully; `result` holds its output
+    Done,
+    /// Finished with an error
+    Failed,
+    /// Stopped at the user's request
+    Cancelled,
+}
+
+impl JobState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobState::Queued => "queued",
+            JobState::Running => "running",
+            JobState::Done => "done",
+            JobState::Failed => "failed",
+            JobState::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "queued" => Ok(JobState::Queued),
+            "running" => Ok(JobState::Running),
+            "done" => Ok(JobState::Done),
+            "failed" => Ok(JobState::Failed),
+            "cancelled" => Ok(JobState::Cancelled),
+            _ => Err(format!("invalid job state: '{}'", s)),
+        }
+    }
+
+    /// Whether the job has stopped for good
+    pub fn is_finished(&self) -> bool {
+        matches!(self, JobState::Done | JobState::Failed | JobState::Cancelled)
+    }
+}
+
+/// A long-running operation run in the background
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Job {
+    pub id: Uuid,
+    pub kind: JobKind,
+    pub state: JobState,
+    /// User who started the job; `None` for the built-in admin
+    pub owner_id: Option<Uuid>,
+    /// What the job is working on, e.g. the URL being cloned
+    pub description: String,
+    /// Fraction complete between 0 and 1, when the job can tell
+    pub progress: Option<f64>,
+    /// Latest progress note
+    pub progress_message: Option<String>,
+    /// Output of a finished job, shaped by its kind
+    pub result: Option<serde_json::Value>,
+    pub error: Option<String>,
+    /// Whether cancellation was requested while the job was running
+    pub cancel_requested: bool,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// Part of a search hit's snippet; highlighted parts are what the query matched
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnippetPart {
+    pub text: String,
+    pub highlight: bool,
+}
+
+/// A session with messages or output matching a search
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSearchHit {
+    pub session_id: Uuid,
+    pub repo_id: Uuid,
+    pub name: Option<String>,
+    pub message_matches: usize,
+    pub output_matches: usize,
+    /// When the newest matching message or output was written
+    pub last_match_at: DateTime<Utc>,
+}
+
+/// A message matching a search
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageSearchHit {
+    pub message_id: Uuid,
+    pub session_id: Uuid,
+    pub role: MessageRole,
+    pub kind: MessageKind,
+    pub snippet: Vec<SnippetPart>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// An output log entry matching a search
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputSearchHit {
+    pub output_id: i64,
+    pub session_id: Uuid,
+    pub stream: OutputStream,
+    pub channel: OutputChannel,
+    pub snippet: Vec<SnippetPart>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Sessions, messages, and output lines matching a search, best matches first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResults {
+    pub sessions: Vec<SessionSearchHit>,
+    pub messages: Vec<MessageSearchHit>,
+    pub output: Vec<OutputSearchHit>,
+}
+
+/// What pruning session output deleted
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PrunedOutput {
+    /// Output lines deleted
+    pub deleted: usize,
+    /// Bytes of pages the deletes added to the database's free list; SQLite
+    /// reuses them before growing the file, but the file doesn't shrink
+    pub bytes_freed: u64,
+}
@@ -3,18 +3,140 @@
 /// Tables:
 /// - repos: Git repositories being managed
 /// - sessions: Ralph sessions tied to repos
-/// - messages: Chat messages within sessions
+/// - messages: Chat messages within sessions, and the agent's messages, tool calls and file edits
 /// - output_logs: Raw output from Ralph processes
 /// - config: Key-value configuration storage
+/// - users: Accounts that own repos and sessions on a shared server
+/// - webhooks: Outgoing webhook endpoints for lifecycle events
+/// - webhook_deliveries: Log of webhook delivery attempts
+/// - triggers: Inbound hooks that start a predefined prompt on a repo
+/// - snapshots: Copies of a session's uncommitted work taken before each run
+/// - prompt_history: Distinct prompts run on each repository, for reuse
+/// - activity: Recent events across all repositories, for the activity feed
+/// - daily_stats: Per-repository run and output totals for each day
+/// - aggregate_progress: Last row of each source table counted into daily_stats
+/// - artifacts: Non-code files runs left in the working tree
+/// - jobs: Long-running background operations (clones, scans, ...) and their outcomes
+/// - iterations: Where each iteration of a run starts and ends in its output
+/// - schedules: Prompts run on a repo at the times of a cron expression
+/// - schedule_runs: Each time a schedule fired, and the session it started
+/// - session_env: Environment variables set for a session's runs
+/// - session_tags: Free-form labels attached to sessions
+/// - runs: Each run of a session, with its prompt, exit code, timing, and token usage
+/// - pipeline_steps: The prompts of a session's pipeline and how far it got
+/// - worktrees: Linked worktrees created for a repository, and the session working in each
+/// - messages_fts, output_logs_fts: Full-text indexes over messages and output logs
 
 /// Schema version for migrations
-pub const SCHEMA_VERSION: i32 = 2;
+pub const SCHEMA_VERSION: i32 = 15;
 
 /// Migration from v1 to v2: Add orchestrator column to sessions
 pub const MIGRATE_V1_TO_V2: &str = r#"
 ALTER TABLE sessions ADD COLUMN orchestrator TEXT NOT NULL DEFAULT 'ralph';
 "#;
 
+/// Migration from v2 to v3: Add owner columns to repos and sessions
+pub const MIGRATE_V2_TO_V3: &str = r#"
+ALTER TABLE repos ADD COLUMN owner_id TEXT REFERENCES users(id) ON DELETE SET NULL;
+ALTER TABLE sessions ADD COLUMN owner_id TEXT REFERENCES users(id) ON DELETE SET NULL;
+"#;
+
+/// Migration from v3 to v4: Add per-user desktop notification preference
+pub const MIGRATE_V3_TO_V4: &str = r#"
+ALTER TABLE users ADD COLUMN desktop_notifications INTEGER NOT NULL DEFAULT 1;
+"#;
+
+/// Migration from v4 to v5: Add pull request URL to sessions
+pub const MIGRATE_V4_TO_V5: &str = r#"
+ALTER TABLE sessions ADD COLUMN pr_url TEXT;
+"#;
+
+/// Migration from v5 to v6: Add issue reference and prompt draft to sessions
+pub const MIGRATE_V5_TO_V6: &str = r#"
+ALTER TABLE sessions ADD COLUMN issue_url TEXT;
+ALTER TABLE sessions ADD COLUMN prompt_draft TEXT;
+"#;
+
+/// Migration from v6 to v7: Add output channels, backfilled from the stream
+pub const MIGRATE_V6_TO_V7: &str = r#"
+ALTER TABLE output_logs ADD COLUMN channel TEXT NOT NULL DEFAULT 'assistant_text';
+UPDATE output_logs SET channel = 'stderr' WHERE stream = 'stderr';
+"#;
+
+/// Migration from v7 to v8: Mark output logs holding part of a longer line
+pub const MIGRATE_V7_TO_V8: &str = r#"
+ALTER TABLE output_logs ADD COLUMN continues INTEGER NOT NULL DEFAULT 0;
+"#;
+
+/// Migration from v8 to v9: Allow compressed output log content
+pub const MIGRATE_V8_TO_V9: &str = r#"
+ALTER TABLE output_logs ADD COLUMN compressed INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE output_logs ADD COLUMN size_bytes INTEGER;
+"#;
+
+/// Migration from v9 to v10: Record why a session's last run failed
+pub const MIGRATE_V9_TO_V10: &str = r#"
+ALTER TABLE sessions ADD COLUMN failure_reason TEXT;
+ALTER TABLE sessions ADD COLUMN failure_line TEXT;
+"#;
+
+/// Migration from v10 to v11: Add token and cost totals to runs
+pub const MIGRATE_V10_TO_V11: &str = r#"
+ALTER TABLE runs ADD COLUMN input_tokens INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE runs ADD COLUMN output_tokens INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE runs ADD COLUMN cache_read_tokens INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE runs ADD COLUMN cache_write_tokens INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE runs ADD COLUMN cost_usd REAL NOT NULL DEFAULT 0;
+"#;
+
+/// Migration from v11 to v12: Add kinds and details to messages
+pub const MIGRATE_V11_TO_V12: &str = r#"
+ALTER TABLE messages ADD COLUMN kind TEXT NOT NULL DEFAULT 'text';
+ALTER TABLE messages ADD COLUMN data TEXT;
+"#;
+
+/// Migration from v12 to v13: Full-text indexes over messages and output logs
+///
+/// Messages are indexed from their own table and kept in sync by triggers.
+/// Output log content may be stored compressed, which SQL can't read, so each
+/// log's text is added to its index when the log is inserted; only deletes
+/// are left to a trigger.
+pub const CREATE_SEARCH_INDEXES: &str = r#"
+CREATE VIRTUAL TABLE IF NOT EXISTS messages_fts USING fts5(content, content='messages');
+CREATE TRIGGER IF NOT EXISTS messages_fts_insert AFTER INSERT ON messages BEGIN
+    INSERT INTO messages_fts (rowid, content) VALUES (new.rowid, new.content);
+END;
+CREATE TRIGGER IF NOT EXISTS messages_fts_delete AFTER DELETE ON messages BEGIN
+    INSERT INTO messages_fts (messages_fts, rowid, content) VALUES ('delete', old.rowid, old.content);
+END;
+CREATE TRIGGER IF NOT EXISTS messages_fts_update AFTER UPDATE OF content ON messages BEGIN
+    INSERT INTO messages_fts (messages_fts, rowid, content) VALUES ('delete', old.rowid, old.content);
+    INSERT INTO messages_fts (rowid, content) VALUES (new.rowid, new.content);
+END;
+
+CREATE VIRTUAL TABLE IF NOT EXISTS output_logs_fts USING fts5(content);
+CREATE TRIGGER IF NOT EXISTS output_logs_fts_delete AFTER DELETE ON output_logs BEGIN
+    DELETE FROM output_logs_fts WHERE rowid = old.id;
+END;
+"#;
+
+/// Migration from v13 to v14: Let sessions be archived
+pub const MIGRATE_V13_TO_V14: &str = r#"
+ALTER TABLE sessions ADD COLUMN archived INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE sessions ADD COLUMN archived_at TEXT;
+"#;
+
+/// Migration from v14 to v15: Free-form metadata on sessions
+pub const MIGRATE_V14_TO_V15: &str = r#"
+ALTER TABLE sessions ADD COLUMN metadata TEXT NOT NULL DEFAULT '{}';
+"#;
+
+/// Indexes on owner columns, created once the columns exist
+pub const CREATE_OWNER_INDEXES: &str = r#"
+CREATE INDEX IF NOT EXISTS idx_repos_owner_id ON repos(owner_id);
+CREATE INDEX IF NOT EXISTS idx_sessions_owner_id ON sessions(owner_id);
+"#;
+
 /// SQL to create all tables
 pub const CREATE_TABLES: &str = r#"
 -- Repositories table
@@ -22,6 +144,7 @@ CREATE TABLE IF NOT EXISTS repos (
     id TEXT PRIMARY KEY,
     path TEXT NOT NULL UNIQUE,
     name TEXT NOT NULL,
+    owner_id TEXT REFERENCES users(id) ON DELETE SET NULL,
     created_at TEXT NOT NULL,
     updated_at TEXT NOT NULL
 );
@@ -33,6 +156,15 @@ CREATE TABLE IF NOT EXISTS sessions (
     name TEXT,
     orchestrator TEXT NOT NULL DEFAULT 'ralph',
     status TEXT NOT NULL DEFAULT 'idle',
+    owner_id TEXT REFERENCES users(id) ON DELETE SET NULL,
+    pr_url TEXT,
+    issue_url TEXT,
+    prompt_draft TEXT,
+    failure_reason TEXT,
+    failure_line TEXT,
+    archived INTEGER NOT NULL DEFAULT 0,
+    archived_at TEXT,
+    metadata TEXT NOT NULL DEFAULT '{}',
     created_at TEXT NOT NULL,
     updated_at TEXT NOT NULL,
     FOREIGN KEY (repo_id) REFERENCES repos(id) ON DELETE CASCADE
@@ -43,7 +175,9 @@ CREATE TABLE IF NOT EXISTS messages (
     id TEXT PRIMARY KEY,
     session_id TEXT NOT NULL,
     role TEXT NOT NULL,
+    kind TEXT NOT NULL DEFAULT 'text',
     content TEXT NOT NULL,
+    data TEXT,
     created_at TEXT NOT NULL,
     FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
 );
@@ -53,7 +187,12 @@ CREATE TABLE IF NOT EXISTS output_logs (
     id INTEGER PRIMARY KEY AUTOINCREMENT,
     session_id TEXT NOT NULL,
     stream TEXT NOT NULL,
+    channel TEXT NOT NULL DEFAULT 'assistant_text',
     content TEXT NOT NULL,
+    continues INTEGER NOT NULL DEFAULT 0,
+    -- content is a zstd-compressed blob; size_bytes is its uncompressed length
+    compressed INTEGER NOT NULL DEFAULT 0,
+    size_bytes INTEGER,
     created_at TEXT NOT NULL,
     FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
 );
@@ -65,6 +204,301 @@ CREATE TABLE IF NOT EXISTS config (
     updated_at TEXT NOT NULL
 );
 
+-- Users table (token_hash is the SHA-256 of the user's API token)
+CREATE TABLE IF NOT EXISTS users (
+    id TEXT PRIMARY KEY,
+    username TEXT NOT NULL UNIQUE,
+    role TEXT NOT NULL DEFAULT 'member',
+    token_hash TEXT NOT NULL UNIQUE,
+    desktop_notifications INTEGER NOT NULL DEFAULT 1,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+-- Outgoing webhook endpoints (events is a comma-separated filter; empty means all)
+CREATE TABLE IF NOT EXISTS webhooks (
+    id TEXT PRIMARY KEY,
+    url TEXT NOT NULL,
+    secret TEXT NOT NULL,
+    events TEXT NOT NULL DEFAULT '',
+    enabled INTEGER NOT NULL DEFAULT 1,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+-- Webhook delivery log
+CREATE TABLE IF NOT EXISTS webhook_deliveries (
+    id TEXT PRIMARY KEY,
+    webhook_id TEXT NOT NULL,
+    event TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    response_status INTEGER,
+    error TEXT,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    FOREIGN KEY (webhook_id) REFERENCES webhooks(id) ON DELETE CASCADE
+);
+
+-- Inbound triggers (token_hash is the SHA-256 of the trigger's token; a NULL
+-- session_id starts a new session on every call)
+CREATE TABLE IF NOT EXISTS triggers (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    repo_id TEXT NOT NULL,
+    session_id TEXT,
+    prompt_template TEXT NOT NULL,
+    orchestrator TEXT NOT NULL DEFAULT 'ralph',
+    token_hash TEXT NOT NULL UNIQUE,
+    enabled INTEGER NOT NULL DEFAULT 1,
+    last_triggered_at TEXT,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    FOREIGN KEY (repo_id) REFERENCES repos(id) ON DELETE CASCADE,
+    FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE SET NULL
+);
+
+-- Workspace snapshots (files live in the data directory under snapshots/{id})
+CREATE TABLE IF NOT EXISTS snapshots (
+    id TEXT PRIMARY KEY,
+    session_id TEXT NOT NULL,
+    repo_id TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    head_commit TEXT,
+    file_count INTEGER NOT NULL,
+    deleted_count INTEGER NOT NULL,
+    size_bytes INTEGER NOT NULL,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE,
+    FOREIGN KEY (repo_id) REFERENCES repos(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_snapshots_session_id ON snapshots(session_id);
+
+-- Prompts run on each repository, one row per distinct prompt
+CREATE TABLE IF NOT EXISTS prompt_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    repo_id TEXT NOT NULL,
+    prompt TEXT NOT NULL,
+    use_count INTEGER NOT NULL DEFAULT 1,
+    last_used_at TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    UNIQUE (repo_id, prompt),
+    FOREIGN KEY (repo_id) REFERENCES repos(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_prompt_history_repo_id ON prompt_history(repo_id, last_used_at);
+
+-- Activity feed entries
+CREATE TABLE IF NOT EXISTS activity (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    kind TEXT NOT NULL,
+    repo_id TEXT NOT NULL,
+    session_id TEXT,
+    summary TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (repo_id) REFERENCES repos(id) ON DELETE CASCADE,
+    FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_activity_session_id ON activity(session_id);
+
+-- Daily aggregates for the analytics dashboard, updated by a background task
+CREATE TABLE IF NOT EXISTS daily_stats (
+    day TEXT NOT NULL,
+    repo_id TEXT NOT NULL,
+    runs INTEGER NOT NULL DEFAULT 0,
+    successes INTEGER NOT NULL DEFAULT 0,
+    failures INTEGER NOT NULL DEFAULT 0,
+    cancelled INTEGER NOT NULL DEFAULT 0,
+    runtime_secs INTEGER NOT NULL DEFAULT 0,
+    output_lines INTEGER NOT NULL DEFAULT 0,
+    output_bytes INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (day, repo_id),
+    FOREIGN KEY (repo_id) REFERENCES repos(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS aggregate_progress (
+    source TEXT PRIMARY KEY,
+    last_id INTEGER NOT NULL
+);
+
+-- Files detected after runs (the files themselves stay in the repository)
+CREATE TABLE IF NOT EXISTS artifacts (
+    id TEXT PRIMARY KEY,
+    session_id TEXT NOT NULL,
+    repo_id TEXT NOT NULL,
+    path TEXT NOT NULL,
+    size_bytes INTEGER NOT NULL,
+    created_at TEXT NOT NULL,
+    UNIQUE (session_id, path),
+    FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE,
+    FOREIGN KEY (repo_id) REFERENCES repos(id) ON DELETE CASCADE
+);
+
+-- Background jobs (result is JSON shaped by the job's kind)
+CREATE TABLE IF NOT EXISTS jobs (
+    id TEXT PRIMARY KEY,
+    kind TEXT NOT NULL,
+    state TEXT NOT NULL,
+    owner_id TEXT,
+    description TEXT NOT NULL,
+    progress REAL,
+    progress_message TEXT,
+    result TEXT,
+    error TEXT,
+    cancel_requested INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL,
+    started_at TEXT,
+    finished_at TEXT,
+    FOREIGN KEY (owner_id) REFERENCES users(id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_jobs_created_at ON jobs(created_at);
+
+-- Iterations of runs, bounded by output log ids
+CREATE TABLE IF NOT EXISTS iterations (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    session_id TEXT NOT NULL,
+    number INTEGER NOT NULL,
+    first_output_id INTEGER NOT NULL,
+    last_output_id INTEGER,
+    summary TEXT,
+    started_at TEXT NOT NULL,
+    ended_at TEXT,
+    FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_iterations_session_id ON iterations(session_id);
+
+-- Files uploaded for use in prompts, stored under the data directory
+CREATE TABLE IF NOT EXISTS uploads (
+    id TEXT PRIMARY KEY,
+    owner_id TEXT,
+    filename TEXT NOT NULL,
+    content_type TEXT,
+    size_bytes INTEGER NOT NULL,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (owner_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+-- Files attached to a session, stored under the data directory
+CREATE TABLE IF NOT EXISTS attachments (
+    id TEXT PRIMARY KEY,
+    session_id TEXT NOT NULL,
+    filename TEXT NOT NULL,
+    content_type TEXT,
+    size_bytes INTEGER NOT NULL,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_attachments_session_id ON attachments(session_id);
+
+-- Files and directories pinned in a repository
+CREATE TABLE IF NOT EXISTS bookmarks (
+    id TEXT PRIMARY KEY,
+    repo_id TEXT NOT NULL,
+    path TEXT NOT NULL,
+    label TEXT,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    FOREIGN KEY (repo_id) REFERENCES repos(id) ON DELETE CASCADE,
+    UNIQUE (repo_id, path)
+);
+
+-- Scheduled runs (cron is a five-field expression in UTC; next_run_at is NULL
+-- when it never matches again)
+CREATE TABLE IF NOT EXISTS schedules (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    repo_id TEXT NOT NULL,
+    prompt TEXT NOT NULL,
+    cron TEXT NOT NULL,
+    orchestrator TEXT NOT NULL DEFAULT 'ralph',
+    enabled INTEGER NOT NULL DEFAULT 1,
+    next_run_at TEXT,
+    last_run_at TEXT,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    FOREIGN KEY (repo_id) REFERENCES repos(id) ON DELETE CASCADE
+);
+
+-- Each time a schedule fired (session_id is NULL when no session could be created)
+CREATE TABLE IF NOT EXISTS schedule_runs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    schedule_id TEXT NOT NULL,
+    session_id TEXT,
+    error TEXT,
+    fired_at TEXT NOT NULL,
+    FOREIGN KEY (schedule_id) REFERENCES schedules(id) ON DELETE CASCADE,
+    FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE SET NULL
+);
+CREATE INDEX IF NOT EXISTS idx_schedule_runs_schedule_id ON schedule_runs(schedule_id, fired_at);
+
+-- Environment variables a session's runs are started with
+CREATE TABLE IF NOT EXISTS session_env (
+    session_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (session_id, name),
+    FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+);
+
+-- Labels attached to sessions, e.g. "bugfix" or "experiment"
+CREATE TABLE IF NOT EXISTS session_tags (
+    session_id TEXT NOT NULL,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (session_id, tag),
+    FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS idx_session_tags_tag ON session_tags(tag);
+
+-- Each run of a session (exit_code and finished_at are NULL while it runs)
+CREATE TABLE IF NOT EXISTS runs (
+    id TEXT PRIMARY KEY,
+    session_id TEXT NOT NULL,
+    prompt TEXT NOT NULL,
+    status TEXT NOT NULL,
+    exit_code INTEGER,
+    started_at TEXT NOT NULL,
+    finished_at TEXT,
+    input_tokens INTEGER NOT NULL DEFAULT 0,
+    output_tokens INTEGER NOT NULL DEFAULT 0,
+    cache_read_tokens INTEGER NOT NULL DEFAULT 0,
+    cache_write_tokens INTEGER NOT NULL DEFAULT 0,
+    cost_usd REAL NOT NULL DEFAULT 0,
+    FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS idx_runs_session_id ON runs(session_id, started_at);
+
+-- Steps of a session's pipeline, run in order while each completes
+CREATE TABLE IF NOT EXISTS pipeline_steps (
+    session_id TEXT NOT NULL,
+    step INTEGER NOT NULL,
+    prompt TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    started_at TEXT,
+    finished_at TEXT,
+    PRIMARY KEY (session_id, step),
+    FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+);
+
+-- Linked worktrees checked out for a repository (session_id is NULL once
+-- the session is deleted and the worktree couldn't be removed with it)
+CREATE TABLE IF NOT EXISTS worktrees (
+    id TEXT PRIMARY KEY,
+    repo_id TEXT NOT NULL,
+    session_id TEXT UNIQUE,
+    branch TEXT NOT NULL,
+    path TEXT NOT NULL UNIQUE,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (repo_id) REFERENCES repos(id) ON DELETE CASCADE,
+    FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE SET NULL
+);
+CREATE INDEX IF NOT EXISTS idx_worktrees_repo_id ON worktrees(repo_id);
+
 -- Schema version tracking
 CREATE TABLE IF NOT EXISTS schema_version (
     version INTEGER PRIMARY KEY
@@ -75,6 +509,7 @@ CREATE INDEX IF NOT EXISTS idx_sessions_repo_id ON sessions(repo_id);
 CREATE INDEX IF NOT EXISTS idx_sessions_status ON sessions(status);
 CREATE INDEX IF NOT EXISTS idx_messages_session_id ON messages(session_id);
 CREATE INDEX IF NOT EXISTS idx_output_logs_session_id ON output_logs(session_id);
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_webhook_id ON webhook_deliveries(webhook_id);
 "#;
 
 /// SQL to insert or update schema version
@@ -0,0 +1,184 @@
+//! Finding Ralphtown on the local network
+//!
+//! A server listening on a LAN address advertises itself over mDNS (Bonjour)
+//! as a `_ralphtown._tcp` service, named after the machine, with its port and
+//! a TXT record giving the URL scheme and base path. `ralphtown discover`
+//! browses for those advertisements, so a phone or another machine can find
+//! the server without anyone typing in an IP address. Loopback-only servers
+//! aren't advertised, since nothing else could reach them.
+
+use std::collections::BTreeMap;
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// mDNS service type servers are advertised under
+pub const SERVICE_TYPE: &str = "_ralphtown._tcp.local.";
+
+/// Discovery errors
+#[derive(Debug, Error)]
+pub enum DiscoveryError {
+    #[error("mDNS error: {0}")]
+    Mdns(#[from] mdns_sd::Error),
+}
+
+pub type DiscoveryResult<T> = Result<T, DiscoveryError>;
+
+/// A server advertised on the network; stops advertising when dropped
+pub struct Advertisement {
+    daemon: ServiceDaemon,
+    fullname: String,
+}
+
+impl Drop for Advertisement {
+    fn drop(&mut self) {
+        let _ = self.daemon.unregister(&self.fullname);
+        let _ = self.daemon.shutdown();
+    }
+}
+
+/// Advertise a server bound to `addr`
+///
+/// A server bound to every interface is advertised on all of the machine's
+/// addresses.
+pub fn advertise(addr: SocketAddr, tls: bool, base_path: &str) -> DiscoveryResult<Advertisement> {
+    let daemon = ServiceDaemon::new()?;
+    let name = host_name();
+    let host = format!("{}.local.", name);
+    let properties = [
+        ("scheme", if tls { "https" } else { "http" }),
+        ("path", base_path),
+        ("version", env!("CARGO_PKG_VERSION")),
+    ];
+    let ip = if addr.ip().is_unspecified() {
+        String::new()
+    } else {
+        addr.ip().to_string()
+    };
+    let mut info = ServiceInfo::new(SERVICE_TYPE, &name, &host, ip, addr.port(), &properties[..])?;
+    if addr.ip().is_unspecified() {
+        info = info.enable_addr_auto();
+    }
+    let fullname = info.get_fullname().to_string();
+    daemon.register(info)?;
+    tracing::info!("Advertising {} on the local network", fullname);
+    Ok(Advertisement { daemon, fullname })
+}
+
+/// A server found on the network
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Instance {
+    /// The advertising machine's name
+    pub name: String,
+    pub port: u16,
+    pub addresses: Vec<IpAddr>,
+    /// URLs to open the app at, one per address
+    pub urls: Vec<String>,
+}
+
+/// Browse the network for servers for `timeout`
+pub fn discover(timeout: Duration) -> DiscoveryResult<Vec<Instance>> {
+    let daemon = ServiceDaemon::new()?;
+    let receiver = daemon.browse(SERVICE_TYPE)?;
+    let deadline = Instant::now() + timeout;
+
+    // Keyed by full name so repeated announcements collapse into one
+    let mut found = BTreeMap::new();
+    while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+        match receiver.recv_timeout(remaining) {
+            Ok(ServiceEvent::ServiceResolved(info)) => {
+                found.insert(info.get_fullname().to_string(), instance(&info));
+            }
+            Ok(_) => {}
+            Err(_) => break,
+        }
+    }
+    let _ = daemon.shutdown();
+    Ok(found.into_values().collect())
+}
+
+fn instance(info: &ServiceInfo) -> Instance {
+    let scheme = info.get_property_val_str("scheme").unwrap_or("http");
+    let path = info.get_property_val_str("path").unwrap_or("");
+    let mut addresses: Vec<IpAddr> = info.get_addresses().iter().copied().collect();
+    // IPv4 first; it's what most people type
+    addresses.sort_by_key(|ip| (ip.is_ipv6(), *ip));
+    let urls = addresses
+        .iter()
+        .map(|ip| format!("{}://{}{}/", scheme, SocketAddr::new(*ip, info.get_port()), path))
+        .collect();
+    Instance {
+        name: info
+            .get_fullname()
+            .trim_end_matches(SERVICE_TYPE)
+            .trim_end_matches('.')
+            .to_string(),
+        port: info.get_port(),
+        addresses,
+        urls,
+    }
+}
+
+/// This machine's name, as used for the instance and mDNS host names
+fn host_name() -> String {
+    let name = system_host_name()
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .or_else(|| std::env::var("COMPUTERNAME").ok())
+        .unwrap_or_default();
+    // Drop any domain; mDNS names live under `.local`
+    let name = name.split('.').next().unwrap_or_default().trim();
+    if name.is_empty() {
+        "ralphtown".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+#[cfg(unix)]
+fn system_host_name() -> Option<String> {
+    let mut buf = [0u8; 256];
+    // SAFETY: the buffer is valid for its whole length, which is passed along
+    let result = unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len()) };
+    if result != 0 {
+        return None;
+    }
+    let len = buf.iter().position(|b| *b == 0).unwrap_or(buf.len());
+    String::from_utf8(buf[..len].to_vec()).ok()
+}
+
+#[cfg(not(unix))]
+fn system_host_name() -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_instance_from_service_info() {
+        let info = ServiceInfo::new(
+            SERVICE_TYPE,
+            "studio",
+            "studio.local.",
+            "192.168.1.20",
+            3001,
+            &[("scheme", "https"), ("path", "/ralph")][..],
+        )
+        .unwrap();
+        let instance = instance(&info);
+        assert_eq!(instance.name, "studio");
+        assert_eq!(instance.port, 3001);
+        assert_eq!(instance.urls, ["https://192.168.1.20:3001/ralph/"]);
+    }
+
+    #[test]
+    fn test_host_name_has_no_domain() {
+        let name = host_name();
+        assert!(!name.is_empty());
+        assert!(!name.contains('.'));
+    }
+}
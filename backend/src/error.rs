@@ -17,8 +17,16 @@ pub enum AppError {
     NotFound(String),
     /// Bad request (400)
     BadRequest(String),
+    /// Unauthorized (401) - missing or invalid credentials
+    Unauthorized(String),
+    /// Forbidden (403) - request understood but refused by policy
+    Forbidden(String),
     /// Conflict error (409) - e.g., constraint violations
     Conflict(String),
+    /// Too many requests (429) - client exceeded a rate limit
+    TooManyRequests(String),
+    /// Payload too large (413) - request body over a size limit
+    PayloadTooLarge(String),
     /// Unprocessable entity (422) - e.g., parse errors
     UnprocessableEntity {
         message: String,
@@ -69,7 +77,25 @@ impl IntoResponse for AppError {
             AppError::BadRequest(msg) => {
                 (StatusCode::BAD_REQUEST, "BAD_REQUEST", msg.clone(), None, Vec::new())
             }
+            AppError::Unauthorized(msg) => {
+                (StatusCode::UNAUTHORIZED, "UNAUTHORIZED", msg.clone(), None, Vec::new())
+            }
+            AppError::Forbidden(msg) => (StatusCode::FORBIDDEN, "FORBIDDEN", msg.clone(), None, Vec::new()),
             AppError::Conflict(msg) => (StatusCode::CONFLICT, "CONFLICT", msg.clone(), None, Vec::new()),
+            AppError::TooManyRequests(msg) => (
+                StatusCode::TOO_MANY_REQUESTS,
+                "RATE_LIMITED",
+                msg.clone(),
+                None,
+                Vec::new(),
+            ),
+            AppError::PayloadTooLarge(msg) => (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                "PAYLOAD_TOO_LARGE",
+                msg.clone(),
+                None,
+                Vec::new(),
+            ),
             AppError::UnprocessableEntity {
                 message,
                 field,
@@ -117,7 +143,11 @@ impl std::fmt::Display for AppError {
             AppError::Internal(msg) => write!(f, "Internal error: {}", msg),
             AppError::NotFound(msg) => write!(f, "Not found: {}", msg),
             AppError::BadRequest(msg) => write!(f, "Bad request: {}", msg),
+            AppError::Unauthorized(msg) => write!(f, "Unauthorized: {}", msg),
+            AppError::Forbidden(msg) => write!(f, "Forbidden: {}", msg),
             AppError::Conflict(msg) => write!(f, "Conflict: {}", msg),
+            AppError::TooManyRequests(msg) => write!(f, "Too many requests: {}", msg),
+            AppError::PayloadTooLarge(msg) => write!(f, "Payload too large: {}", msg),
             AppError::UnprocessableEntity { message, .. } => {
                 write!(f, "Unprocessable entity: {}", message)
             }
@@ -174,6 +204,7 @@ impl From<crate::git::CloneError> for AppError {
             crate::git::CloneError::OperationFailed { message } => {
                 AppError::Internal(format!("Clone failed: {}", message))
             }
+            crate::git::CloneError::Cancelled => AppError::Conflict("Clone was cancelled".to_string()),
         }
     }
 }
@@ -189,6 +220,10 @@ impl From<crate::ralph::RalphError> for AppError {
                 "Session {} already has a running process",
                 session_id
             )),
+            crate::ralph::RalphError::AtCapacity(max) => AppError::Conflict(format!(
+                "{} runs are already going, the most allowed at once",
+                max
+            )),
             crate::ralph::RalphError::SpawnFailed(msg) => {
                 AppError::Internal(format!("Failed to start ralph: {}", msg))
             }
@@ -204,6 +239,123 @@ impl From<crate::ralph::RalphError> for AppError {
                 "Session {} has no running process",
                 session_id
             )),
+            crate::ralph::RalphError::InvalidArgument(msg) => AppError::BadRequest(msg),
+            crate::ralph::RalphError::WorkerUnavailable(worker_id) => {
+                AppError::BadRequest(format!("Worker {} is not connected", worker_id))
+            }
+            crate::ralph::RalphError::InputFailed(msg) => {
+                AppError::Internal(format!("Failed to send input: {}", msg))
+            }
+            crate::ralph::RalphError::ShuttingDown => {
+                AppError::Conflict("The server is shutting down".to_string())
+            }
+        }
+    }
+}
+
+impl From<crate::forge::ForgeError> for AppError {
+    fn from(err: crate::forge::ForgeError) -> Self {
+        use crate::forge::ForgeError;
+        use crate::git::GitError;
+
+        match err {
+            ForgeError::NoToken(kind) => AppError::UserActionRequired {
+                code: "FORGE_TOKEN_REQUIRED".to_string(),
+                message: err.to_string(),
+                details: Some(serde_json::json!({ "forge": kind })),
+                help_steps: vec![
+                    format!(
+                        "Create a {} access token that can read and write the repository and its pull requests",
+                        kind.name()
+                    ),
+                    format!("Save it with PUT /api/config/{}", kind.token_key()),
+                ],
+            },
+            ForgeError::Api { kind, status: 401 | 403, ref message } => AppError::UserActionRequired {
+                code: "FORGE_AUTH_FAILED".to_string(),
+                message: format!("{} rejected the token: {}", kind.name(), message),
+                details: Some(serde_json::json!({ "forge": kind })),
+                help_steps: vec![
+                    "Check that the token hasn't expired".to_string(),
+                    "Check that it can push to and open pull requests on the repository".to_string(),
+                ],
+            },
+            ForgeError::Api { status: 409 | 422, message, .. } => AppError::Conflict(message),
+            ForgeError::Api { status: 404, .. } => AppError::NotFound(err.to_string()),
+            ForgeError::UnsupportedRemote(_) | ForgeError::OnBaseBranch(_) => {
+                AppError::BadRequest(err.to_string())
+            }
+            ForgeError::Git(
+                GitError::NotARepo(_) | GitError::InvalidBranch(_) | GitError::InvalidArgument(_),
+            ) => AppError::BadRequest(err.to_string()),
+            ForgeError::Git(GitError::AuthFailed(e)) => e.into(),
+            ForgeError::Db(e) => e.into(),
+            ForgeError::Git(_) | ForgeError::Api { .. } | ForgeError::Request { .. } => {
+                AppError::Internal(err.to_string())
+            }
+        }
+    }
+}
+
+impl From<crate::snapshot::SnapshotError> for AppError {
+    fn from(err: crate::snapshot::SnapshotError) -> Self {
+        use crate::snapshot::SnapshotError;
+
+        match err {
+            SnapshotError::Db(e) => e.into(),
+            SnapshotError::TooLarge | SnapshotError::BareRepository | SnapshotError::Damaged(..) => {
+                AppError::Conflict(err.to_string())
+            }
+            SnapshotError::Git(_) | SnapshotError::Io(_) => AppError::Internal(err.to_string()),
+        }
+    }
+}
+
+impl From<crate::terminal::TerminalError> for AppError {
+    fn from(err: crate::terminal::TerminalError) -> Self {
+        use crate::terminal::TerminalError;
+
+        match err {
+            TerminalError::TooMany => AppError::Conflict(err.to_string()),
+            TerminalError::Spawn(_) | TerminalError::Io(_) => AppError::Internal(err.to_string()),
+        }
+    }
+}
+
+impl From<crate::bundle::BundleError> for AppError {
+    fn from(err: crate::bundle::BundleError) -> Self {
+        use crate::bundle::BundleError;
+
+        match err {
+            BundleError::Malformed(_) | BundleError::TooLarge | BundleError::UnsupportedFormat(_) => {
+                AppError::BadRequest(err.to_string())
+            }
+            BundleError::BadSignature | BundleError::KeyRequired => AppError::Forbidden(err.to_string()),
+        }
+    }
+}
+
+impl From<crate::templates::TemplateError> for AppError {
+    fn from(err: crate::templates::TemplateError) -> Self {
+        use crate::templates::TemplateError;
+
+        match err {
+            TemplateError::NotFound(_) => AppError::NotFound(err.to_string()),
+            TemplateError::Invalid(..) | TemplateError::Io(_) => AppError::Internal(err.to_string()),
+        }
+    }
+}
+
+impl From<crate::storage::QuotaExceeded> for AppError {
+    fn from(err: crate::storage::QuotaExceeded) -> Self {
+        AppError::UserActionRequired {
+            code: "CLONE_QUOTA_EXCEEDED".to_string(),
+            message: err.to_string(),
+            details: Some(serde_json::json!({
+                "used_bytes": err.used_bytes,
+                "quota_bytes": err.quota_bytes,
+            })),
+            help_steps: err.help_steps(),
         }
     }
 }
@@ -0,0 +1,198 @@
+//! Why runs fail
+//!
+//! A failed run's stderr is scanned for known failure signatures so the
+//! session can say why it died, and what to do about it, without anyone
+//! reading the raw log. The first line matching a signature decides the
+//! reason; lines matching none are ignored.
+
+use std::sync::OnceLock;
+
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+/// Longest matched line kept as evidence, in characters
+const MAX_LINE_CHARS: usize = 500;
+
+/// A known cause of failed runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureReason {
+    /// The agent has no API key for its model provider
+    MissingApiKey,
+    /// The provider rejected the API key
+    AuthenticationFailed,
+    /// The provider is rate limiting or out of quota
+    RateLimited,
+    /// The provider or another service couldn't be reached
+    NetworkError,
+    /// Code in the repository didn't compile
+    CompileError,
+    /// The run went over a resource limit and was stopped (see
+    /// [`crate::ralph::RunLimits`]); not matched from stderr
+    LimitExceeded,
+    /// The run wrote no output for too long and was stopped; not matched
+    /// from stderr
+    Stalled,
+}
+
+impl FailureReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FailureReason::MissingApiKey => "missing_api_key",
+            FailureReason::AuthenticationFailed => "authentication_failed",
+            FailureReason::RateLimited => "rate_limited",
+            FailureReason::NetworkError => "network_error",
+            FailureReason::CompileError => "compile_error",
+            FailureReason::LimitExceeded => "limit_exceeded",
+            FailureReason::Stalled => "stalled",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "missing_api_key" => Ok(FailureReason::MissingApiKey),
+            "authentication_failed" => Ok(FailureReason::AuthenticationFailed),
+            "rate_limited" => Ok(FailureReason::RateLimited),
+            "network_error" => Ok(FailureReason::NetworkError),
+            "compile_error" => Ok(FailureReason::CompileError),
+            "limit_exceeded" => Ok(FailureReason::LimitExceeded),
+            "stalled" => Ok(FailureReason::Stalled),
+            _ => Err(format!("invalid failure reason: '{}'", s)),
+        }
+    }
+
+    /// What to do about a failure for this reason
+    pub fn help_steps(&self) -> Vec<String> {
+        let steps: &[&str] = match self {
+            FailureReason::MissingApiKey => &[
+                "Set the API key your agent needs (for example ANTHROPIC_API_KEY or OPENAI_API_KEY) in the environment Ralphtown runs in",
+                "Restart Ralphtown so runs pick up the key",
+            ],
+            FailureReason::AuthenticationFailed => &[
+                "Check that the API key is current and has not been revoked",
+                "Make sure the key belongs to the provider the agent is configured for",
+            ],
+            FailureReason::RateLimited => &[
+                "Wait a few minutes and run the session again",
+                "Check your provider's usage limits and billing",
+                "Run fewer sessions at the same time",
+            ],
+            FailureReason::NetworkError => &[
+                "Check that this machine can reach the internet and your model provider",
+                "Check proxy settings (HTTPS_PROXY) if you use one",
+            ],
+            FailureReason::CompileError => &[
+                "Read the compiler errors in the session output",
+                "Fix the build, or run the session again asking the agent to fix it",
+            ],
+            FailureReason::LimitExceeded => &[
+                "Split the task into smaller prompts that fit within the limit",
+                "Or raise run_timeout_minutes, run_max_memory_mb or run_max_output_mb and restart Ralphtown",
+            ],
+            FailureReason::Stalled => &[
+                "Read the end of the session output to see what the agent was waiting on",
+                "Run the session again; if the agent takes long silent steps, raise run_stall_minutes and restart Ralphtown",
+            ],
+        };
+        steps.iter().map(|step| step.to_string()).collect()
+    }
+}
+
+/// Why a run failed, as told by its stderr
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Failure {
+    pub reason: FailureReason,
+    pub help_steps: Vec<String>,
+    /// The stderr line that gave the reason away
+    pub line: String,
+}
+
+impl Failure {
+    pub fn new(reason: FailureReason, line: &str) -> Self {
+        Self {
+            reason,
+            help_steps: reason.help_steps(),
+            line: line.trim().chars().take(MAX_LINE_CHARS).collect(),
+        }
+    }
+}
+
+/// Signatures in the order they're tried; more specific ones come first
+fn signatures() -> &'static [(FailureReason, Regex)] {
+    static SIGNATURES: OnceLock<Vec<(FailureReason, Regex)>> = OnceLock::new();
+    SIGNATURES.get_or_init(|| {
+        [
+            (
+                FailureReason::MissingApiKey,
+                r"(api[_ -]?key|[A-Z]+_API_KEY)\b.*\b(not set|not found|missing|is required|must be set)|\b(missing|no) api[_ -]?key",
+            ),
+            (
+                FailureReason::AuthenticationFailed,
+                r"authentication_error|invalid[_ -]api[_ -]?key|incorrect api key|invalid x-api-key|401 unauthorized",
+            ),
+            (
+                FailureReason::RateLimited,
+                r"rate[_ -]?limit|too many requests|\b429\b|overloaded_error|quota exceeded|insufficient_quota",
+            ),
+            (
+                FailureReason::NetworkError,
+                r"connection refused|could not resolve host|dns error|failed to lookup address|network is unreachable|econnreset|enotfound|connection reset",
+            ),
+            (
+                FailureReason::CompileError,
+                r"^\s*error(\[E\d+\])?:|error TS\d+:|could not compile|compilation failed|SyntaxError:|cannot find symbol",
+            ),
+        ]
+        .into_iter()
+        .map(|(reason, pattern)| {
+            let regex = RegexBuilder::new(pattern)
+                .case_insensitive(true)
+                .build()
+                .expect("signature is valid");
+            (reason, regex)
+        })
+        .collect()
+    })
+}
+
+/// The failure a stderr line points to, if it matches a known signature
+pub fn classify(line: &str) -> Option<Failure> {
+    signatures()
+        .iter()
+        .find(|(_, regex)| regex.is_match(line))
+        .map(|(reason, _)| Failure::new(*reason, line))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        let reason = |line: &str| classify(line).map(|failure| failure.reason);
+
+        assert_eq!(
+            reason("Error: ANTHROPIC_API_KEY environment variable is not set"),
+            Some(FailureReason::MissingApiKey)
+        );
+        assert_eq!(
+            reason(r#"{"type":"error","error":{"type":"authentication_error","message":"invalid x-api-key"}}"#),
+            Some(FailureReason::AuthenticationFailed)
+        );
+        assert_eq!(reason("HTTP 429 Too Many Requests"), Some(FailureReason::RateLimited));
+        assert_eq!(
+            reason("error sending request: Connection refused (os error 111)"),
+            Some(FailureReason::NetworkError)
+        );
+        assert_eq!(
+            reason("error[E0425]: cannot find value `x` in this scope"),
+            Some(FailureReason::CompileError)
+        );
+        assert_eq!(reason("warning: unused variable"), None);
+
+        let failure = classify("  rate limit reached  ").unwrap();
+        assert_eq!(failure.line, "rate limit reached");
+        assert!(!failure.help_steps.is_empty());
+        assert_eq!(FailureReason::from_str(failure.reason.as_str()), Ok(failure.reason));
+    }
+}
@@ -0,0 +1,309 @@
+//! Bitbucket Cloud REST API 2.0
+
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+
+use crate::db::Database;
+use crate::settings::{BITBUCKET_API_URL_KEY, BITBUCKET_USERNAME_KEY};
+
+use super::{
+    config, ApiClient, Auth, Forge, ForgeKind, ForgeRepo, ForgeResult, Issue, NewPullRequest,
+    PullRequest, RemoteRepo,
+};
+
+/// API used when `bitbucket_api_url` is not set
+pub const DEFAULT_API_URL: &str = "https://api.bitbucket.org/2.0";
+
+/// Bitbucket client
+pub struct Bitbucket {
+    api: ApiClient,
+}
+
+/// A page of results
+#[derive(Deserialize)]
+struct Page<T> {
+    values: Vec<T>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BranchRef {
+    branch: Branch,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Branch {
+    name: String,
+}
+
+#[derive(Serialize)]
+struct CreatePull<'a> {
+    title: &'a str,
+    description: &'a str,
+    source: BranchRef,
+    destination: BranchRef,
+    draft: bool,
+}
+
+#[derive(Deserialize)]
+struct Link {
+    href: String,
+}
+
+#[derive(Deserialize)]
+struct HtmlLinks {
+    html: Link,
+}
+
+#[derive(Deserialize)]
+struct Pull {
+    id: u64,
+    links: HtmlLinks,
+}
+
+#[derive(Deserialize)]
+struct Content {
+    raw: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct BitbucketIssue {
+    id: u64,
+    title: String,
+    content: Option<Content>,
+    links: HtmlLinks,
+    kind: Option<String>,
+}
+
+impl From<BitbucketIssue> for Issue {
+    fn from(issue: BitbucketIssue) -> Self {
+        Issue {
+            number: issue.id,
+            title: issue.title,
+            body: issue.content.and_then(|content| content.raw),
+            url: issue.links.html.href,
+            labels: issue.kind.into_iter().collect(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct CloneLink {
+    name: String,
+    href: String,
+}
+
+#[derive(Deserialize)]
+struct RepoLinks {
+    #[serde(default)]
+    clone: Vec<CloneLink>,
+}
+
+#[derive(Deserialize)]
+struct Repository {
+    full_name: String,
+    name: String,
+    description: Option<String>,
+    is_private: bool,
+    mainbranch: Option<Branch>,
+    links: RepoLinks,
+}
+
+impl Bitbucket {
+    pub fn new(api_url: &str, auth: Auth) -> Self {
+        Self {
+            api: ApiClient::new(ForgeKind::Bitbucket, api_url, auth),
+        }
+    }
+
+    /// Create a client using `bitbucket_api_url`
+    ///
+    /// With `bitbucket_username` set the token is sent as an app password;
+    /// otherwise it's sent as an access token.
+    pub fn from_config(db: &Database, token: String) -> ForgeResult<Self> {
+        let api_url = config(db, BITBUCKET_API_URL_KEY)?.unwrap_or_else(|| DEFAULT_API_URL.to_string());
+        let auth = match config(db, BITBUCKET_USERNAME_KEY)? {
+            Some(username) => Auth::Basic {
+                username,
+                password: token,
+            },
+            None => Auth::Bearer(token),
+        };
+        Ok(Self::new(&api_url, auth))
+    }
+}
+
+impl Forge for Bitbucket {
+    fn kind(&self) -> ForgeKind {
+        ForgeKind::Bitbucket
+    }
+
+    fn default_branch<'a>(&'a self, repo: &'a RemoteRepo) -> BoxFuture<'a, ForgeResult<String>> {
+        Box::pin(async move {
+            let path = format!("/repositories/{}", repo.full_name());
+            let info: Repository = self.api.send(self.api.request(reqwest::Method::GET, &path)).await?;
+            Ok(info
+                .mainbranch
+                .map(|branch| branch.name)
+                .unwrap_or_else(|| "main".to_string()))
+        })
+    }
+
+    fn create_pull_request<'a>(
+        &'a self,
+        repo: &'a RemoteRepo,
+        pr: &'a NewPullRequest<'a>,
+    ) -> BoxFuture<'a, ForgeResult<PullRequest>> {
+        Box::pin(async move {
+            let path = format!("/repositories/{}/pullrequests", repo.full_name());
+            let request = self.api.request(reqwest::Method::POST, &path).json(&CreatePull {
+                title: pr.title,
+                description: pr.body,
+                source: BranchRef {
+                    branch: Branch {
+                        name: pr.head.to_string(),
+                    },
+                },
+                destination: BranchRef {
+                    branch: Branch {
+                        name: pr.base.to_string(),
+                    },
+                },
+                draft: pr.draft,
+            });
+            let pull: Pull = self.api.send(request).await?;
+            Ok(PullRequest {
+                number: pull.id,
+                url: pull.links.html.href,
+            })
+        })
+    }
+
+    fn list_issues<'a>(&'a self, repo: &'a RemoteRepo) -> BoxFuture<'a, ForgeResult<Vec<Issue>>> {
+        Box::pin(async move {
+            let path = format!("/repositories/{}/issues", repo.full_name());
+            let request = self.api.request(reqwest::Method::GET, &path).query(&[
+                ("q", "state=\"new\" OR state=\"open\""),
+                ("sort", "-updated_on"),
+                ("pagelen", "50"),
+            ]);
+            let page: Page<BitbucketIssue> = self.api.send(request).await?;
+            Ok(page.values.into_iter().map(Issue::from).collect())
+        })
+    }
+
+    fn get_issue<'a>(&'a self, repo: &'a RemoteRepo, number: u64) -> BoxFuture<'a, ForgeResult<Issue>> {
+        Box::pin(async move {
+            let path = format!("/repositories/{}/issues/{}", repo.full_name(), number);
+            let issue: BitbucketIssue =
+                self.api.send(self.api.request(reqwest::Method::GET, &path)).await?;
+            Ok(issue.into())
+        })
+    }
+
+    fn list_repos(&self) -> BoxFuture<'_, ForgeResult<Vec<ForgeRepo>>> {
+        Box::pin(async move {
+            let request = self.api.request(reqwest::Method::GET, "/repositories").query(&[
+                ("role", "member"),
+                ("sort", "-updated_on"),
+                ("pagelen", "100"),
+            ]);
+            let page: Page<Repository> = self.api.send(request).await?;
+            Ok(page
+                .values
+                .into_iter()
+                .filter_map(|repo| {
+                    let link = |name: &str| {
+                        repo.links
+                            .clone
+                            .iter()
+                            .find(|link| link.name == name)
+                            .map(|link| link.href.clone())
+                    };
+                    Some(ForgeRepo {
+                        clone_url: link("https")?,
+                        ssh_url: link("ssh"),
+                        full_name: repo.full_name,
+                        name: repo.name,
+                        description: repo.description.filter(|d| !d.is_empty()),
+                        private: repo.is_private,
+                        default_branch: repo.mainbranch.map(|branch| branch.name),
+                    })
+                })
+                .collect())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::forge::serve_fake_api;
+    use axum::{http::HeaderMap, routing::get, routing::post, Json, Router};
+
+    #[tokio::test]
+    async fn test_pull_requests_and_repos() {
+        let app = Router::new()
+            .route(
+                "/repositories/team/hello/pullrequests",
+                post(|headers: HeaderMap, Json(body): Json<serde_json::Value>| async move {
+                    // App passwords use basic auth
+                    let auth = headers["authorization"].to_str().unwrap();
+                    assert!(auth.starts_with("Basic "));
+                    assert_eq!(body["source"]["branch"]["name"], "feature");
+                    assert_eq!(body["destination"]["branch"]["name"], "main");
+                    Json(serde_json::json!({
+                        "id": 5,
+                        "links": { "html": { "href": "https://bitbucket.org/team/hello/pull-requests/5" } },
+                    }))
+                }),
+            )
+            .route(
+                "/repositories",
+                get(|| async {
+                    Json(serde_json::json!({ "values": [{
+                        "full_name": "team/hello",
+                        "name": "hello",
+                        "description": "",
+                        "is_private": true,
+                        "mainbranch": { "name": "main" },
+                        "links": { "clone": [
+                            { "name": "https", "href": "https://me@bitbucket.org/team/hello.git" },
+                            { "name": "ssh", "href": "git@bitbucket.org:team/hello.git" },
+                        ] },
+                    }] }))
+                }),
+            );
+        let bitbucket = Bitbucket::new(
+            &serve_fake_api(app).await,
+            Auth::Basic {
+                username: "me".to_string(),
+                password: "app-password".to_string(),
+            },
+        );
+        let repo = RemoteRepo {
+            kind: ForgeKind::Bitbucket,
+            host: "bitbucket.org".to_string(),
+            namespace: "team".to_string(),
+            name: "hello".to_string(),
+        };
+
+        let pull_request = bitbucket
+            .create_pull_request(
+                &repo,
+                &NewPullRequest {
+                    title: "Add a greeting",
+                    body: "",
+                    head: "feature",
+                    base: "main",
+                    draft: false,
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(pull_request.number, 5);
+
+        let repos = bitbucket.list_repos().await.unwrap();
+        assert_eq!(repos[0].ssh_url.as_deref(), Some("git@bitbucket.org:team/hello.git"));
+        assert_eq!(repos[0].description, None);
+    }
+}
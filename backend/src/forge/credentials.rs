@@ -0,0 +1,133 @@
+//! Git credential helper backed by stored forge tokens
+//!
+//! Pushes and pulls run by the server shell out to `git`, which would
+//! otherwise ask for a password on a terminal nobody is watching. Those
+//! commands register `ralphtown credential-helper` (see
+//! [`crate::git::GitManager`]), and git runs it to ask for credentials. This
+//! module answers those requests: for an HTTPS remote on a forge with a stored
+//! token, it replies with a username and the token as password, following
+//! git's credential helper protocol (`key=value` lines on stdin and stdout).
+
+use std::collections::HashMap;
+
+use crate::db::Database;
+use crate::settings::BITBUCKET_USERNAME_KEY;
+
+use super::{config, gitlab_host, parse_remote, ForgeKind, ForgeResult};
+
+/// A `get` request from git
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CredentialRequest {
+    pub protocol: Option<String>,
+    pub host: Option<String>,
+}
+
+impl CredentialRequest {
+    /// Parse the attributes git writes to a helper's stdin
+    pub fn parse(input: &str) -> Self {
+        let attributes: HashMap<&str, &str> = input
+            .lines()
+            .take_while(|line| !line.is_empty())
+            .filter_map(|line| line.split_once('='))
+            .collect();
+        let get = |key: &str| attributes.get(key).map(|value| value.to_string());
+        Self {
+            protocol: get("protocol"),
+            host: get("host"),
+        }
+    }
+}
+
+/// Credentials handed back to git
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Credential {
+    pub username: String,
+    pub password: String,
+}
+
+impl Credential {
+    /// Format as a helper's reply
+    pub fn to_helper_output(&self) -> String {
+        format!("username={}\npassword={}\n", self.username, self.password)
+    }
+}
+
+/// Username sent with a forge token over HTTPS
+fn token_username(db: &Database, kind: ForgeKind) -> ForgeResult<String> {
+    Ok(match kind {
+        ForgeKind::GitHub => "x-access-token".to_string(),
+        ForgeKind::GitLab => "oauth2".to_string(),
+        // An app password belongs to a user; an access token has a fixed username
+        ForgeKind::Bitbucket => {
+            config(db, BITBUCKET_USERNAME_KEY)?.unwrap_or_else(|| "x-token-auth".to_string())
+        }
+    })
+}
+
+/// Find stored credentials for a request
+///
+/// Only HTTPS requests for a recognized forge host are answered, so a token
+/// is never sent in the clear or to a host it wasn't issued for.
+pub fn lookup(db: &Database, request: &CredentialRequest) -> ForgeResult<Option<Credential>> {
+    let (Some("https"), Some(host)) = (request.protocol.as_deref(), request.host.as_deref()) else {
+        return Ok(None);
+    };
+    let gitlab_host = gitlab_host(db)?;
+    let Some(remote) = parse_remote(&format!("https://{}/_/_", host), gitlab_host.as_deref()) else {
+        return Ok(None);
+    };
+    let Some(token) = config(db, remote.kind.token_key())? else {
+        return Ok(None);
+    };
+
+    Ok(Some(Credential {
+        username: token_username(db, remote.kind)?,
+        password: token,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::{BITBUCKET_TOKEN_KEY, GITHUB_TOKEN_KEY, GITLAB_TOKEN_KEY, GITLAB_URL_KEY};
+
+    fn request(protocol: &str, host: &str) -> CredentialRequest {
+        CredentialRequest::parse(&format!("protocol={}\nhost={}\n\n", protocol, host))
+    }
+
+    #[test]
+    fn test_parse_request() {
+        let parsed = CredentialRequest::parse(
+            "protocol=https\nhost=github.com\nusername=octo\npath=octo/hello.git\n\nhost=ignored\n",
+        );
+        assert_eq!(
+            parsed,
+            CredentialRequest {
+                protocol: Some("https".to_string()),
+                host: Some("github.com".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_lookup() {
+        let db = Database::in_memory().unwrap();
+        assert_eq!(lookup(&db, &request("https", "github.com")).unwrap(), None);
+
+        db.set_config(GITHUB_TOKEN_KEY, "ghp_test").unwrap();
+        db.set_config(GITLAB_TOKEN_KEY, "glpat_test").unwrap();
+        db.set_config(GITLAB_URL_KEY, "https://git.example.com").unwrap();
+        db.set_config(BITBUCKET_TOKEN_KEY, "app-password").unwrap();
+        db.set_config(BITBUCKET_USERNAME_KEY, "octo").unwrap();
+
+        let github = lookup(&db, &request("https", "github.com")).unwrap().unwrap();
+        assert_eq!(github.to_helper_output(), "username=x-access-token\npassword=ghp_test\n");
+        let gitlab = lookup(&db, &request("https", "git.example.com")).unwrap().unwrap();
+        assert_eq!((gitlab.username.as_str(), gitlab.password.as_str()), ("oauth2", "glpat_test"));
+        let bitbucket = lookup(&db, &request("https", "bitbucket.org")).unwrap().unwrap();
+        assert_eq!(bitbucket.username, "octo");
+
+        assert_eq!(lookup(&db, &request("http", "github.com")).unwrap(), None);
+        assert_eq!(lookup(&db, &request("https", "example.com")).unwrap(), None);
+    }
+}
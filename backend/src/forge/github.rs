@@ -0,0 +1,180 @@
+//! GitHub (and GitHub Enterprise) REST API
+
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+
+use crate::db::Database;
+use crate::settings::GITHUB_API_URL_KEY;
+
+use super::{
+    config, ApiClient, Auth, Forge, ForgeKind, ForgeRepo, ForgeResult, Issue, NewPullRequest,
+    PullRequest, RemoteRepo,
+};
+
+/// API used when `github_api_url` is not set
+pub const DEFAULT_API_URL: &str = "https://api.github.com";
+
+/// GitHub client
+pub struct GitHub {
+    api: ApiClient,
+}
+
+#[derive(Serialize)]
+struct CreatePull<'a> {
+    title: &'a str,
+    body: &'a str,
+    head: &'a str,
+    base: &'a str,
+    draft: bool,
+}
+
+#[derive(Deserialize)]
+struct Pull {
+    number: u64,
+    html_url: String,
+}
+
+#[derive(Deserialize)]
+struct GitHubIssue {
+    number: u64,
+    title: String,
+    body: Option<String>,
+    html_url: String,
+    #[serde(default)]
+    labels: Vec<GitHubLabel>,
+    /// Present when the "issue" is actually a pull request
+    pull_request: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct GitHubLabel {
+    name: String,
+}
+
+impl From<GitHubIssue> for Issue {
+    fn from(issue: GitHubIssue) -> Self {
+        Issue {
+            number: issue.number,
+            title: issue.title,
+            body: issue.body,
+            url: issue.html_url,
+            labels: issue.labels.into_iter().map(|label| label.name).collect(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RepoInfo {
+    default_branch: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GitHubRepo {
+    full_name: String,
+    name: String,
+    description: Option<String>,
+    private: bool,
+    default_branch: Option<String>,
+    clone_url: String,
+    ssh_url: Option<String>,
+}
+
+impl GitHub {
+    pub fn new(api_url: &str, token: String) -> Self {
+        Self {
+            api: ApiClient::new(ForgeKind::GitHub, api_url, Auth::Bearer(token)),
+        }
+    }
+
+    /// Create a client using `github_api_url`
+    pub fn from_config(db: &Database, token: String) -> ForgeResult<Self> {
+        let api_url = config(db, GITHUB_API_URL_KEY)?.unwrap_or_else(|| DEFAULT_API_URL.to_string());
+        Ok(Self::new(&api_url, token))
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        self.api
+            .request(method, path)
+            .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+            .header("x-github-api-version", "2022-11-28")
+    }
+}
+
+impl Forge for GitHub {
+    fn kind(&self) -> ForgeKind {
+        ForgeKind::GitHub
+    }
+
+    fn default_branch<'a>(&'a self, repo: &'a RemoteRepo) -> BoxFuture<'a, ForgeResult<String>> {
+        Box::pin(async move {
+            let path = format!("/repos/{}", repo.full_name());
+            let info: RepoInfo = self.api.send(self.request(reqwest::Method::GET, &path)).await?;
+            Ok(info.default_branch.unwrap_or_else(|| "main".to_string()))
+        })
+    }
+
+    fn create_pull_request<'a>(
+        &'a self,
+        repo: &'a RemoteRepo,
+        pr: &'a NewPullRequest<'a>,
+    ) -> BoxFuture<'a, ForgeResult<PullRequest>> {
+        Box::pin(async move {
+            let path = format!("/repos/{}/pulls", repo.full_name());
+            let request = self.request(reqwest::Method::POST, &path).json(&CreatePull {
+                title: pr.title,
+                body: pr.body,
+                head: pr.head,
+                base: pr.base,
+                draft: pr.draft,
+            });
+            let pull: Pull = self.api.send(request).await?;
+            Ok(PullRequest {
+                number: pull.number,
+                url: pull.html_url,
+            })
+        })
+    }
+
+    fn list_issues<'a>(&'a self, repo: &'a RemoteRepo) -> BoxFuture<'a, ForgeResult<Vec<Issue>>> {
+        Box::pin(async move {
+            let path = format!(
+                "/repos/{}/issues?state=open&sort=updated&per_page=100",
+                repo.full_name()
+            );
+            let issues: Vec<GitHubIssue> =
+                self.api.send(self.request(reqwest::Method::GET, &path)).await?;
+            Ok(issues
+                .into_iter()
+                .filter(|issue| issue.pull_request.is_none())
+                .map(Issue::from)
+                .collect())
+        })
+    }
+
+    fn get_issue<'a>(&'a self, repo: &'a RemoteRepo, number: u64) -> BoxFuture<'a, ForgeResult<Issue>> {
+        Box::pin(async move {
+            let path = format!("/repos/{}/issues/{}", repo.full_name(), number);
+            let issue: GitHubIssue = self.api.send(self.request(reqwest::Method::GET, &path)).await?;
+            Ok(issue.into())
+        })
+    }
+
+    fn list_repos(&self) -> BoxFuture<'_, ForgeResult<Vec<ForgeRepo>>> {
+        Box::pin(async move {
+            let path = "/user/repos?sort=updated&per_page=100";
+            let repos: Vec<GitHubRepo> = self.api.send(self.request(reqwest::Method::GET, path)).await?;
+            Ok(repos
+                .into_iter()
+                .map(|repo| ForgeRepo {
+                    full_name: repo.full_name,
+                    name: repo.name,
+                    description: repo.description,
+                    private: repo.private,
+                    default_branch: repo.default_branch,
+                    clone_url: repo.clone_url,
+                    ssh_url: repo.ssh_url,
+                })
+                .collect())
+        })
+    }
+}
@@ -0,0 +1,235 @@
+//! GitLab (gitlab.com or self-managed) REST API v4
+
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+
+use crate::db::Database;
+use crate::settings::GITLAB_URL_KEY;
+
+use super::{
+    config, ApiClient, Auth, Forge, ForgeKind, ForgeRepo, ForgeResult, Issue, NewPullRequest,
+    PullRequest, RemoteRepo,
+};
+
+/// Instance used when `gitlab_url` is not set
+pub const DEFAULT_URL: &str = "https://gitlab.com";
+
+/// GitLab client
+pub struct GitLab {
+    api: ApiClient,
+}
+
+#[derive(Serialize)]
+struct CreateMergeRequest<'a> {
+    source_branch: &'a str,
+    target_branch: &'a str,
+    title: &'a str,
+    description: &'a str,
+}
+
+#[derive(Deserialize)]
+struct MergeRequest {
+    iid: u64,
+    web_url: String,
+}
+
+#[derive(Deserialize)]
+struct GitLabIssue {
+    iid: u64,
+    title: String,
+    description: Option<String>,
+    web_url: String,
+    #[serde(default)]
+    labels: Vec<String>,
+}
+
+impl From<GitLabIssue> for Issue {
+    fn from(issue: GitLabIssue) -> Self {
+        Issue {
+            number: issue.iid,
+            title: issue.title,
+            body: issue.description,
+            url: issue.web_url,
+            labels: issue.labels,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Project {
+    path_with_namespace: String,
+    name: String,
+    description: Option<String>,
+    visibility: Option<String>,
+    default_branch: Option<String>,
+    http_url_to_repo: String,
+    ssh_url_to_repo: Option<String>,
+}
+
+/// Project ID for API paths: the URL-encoded `namespace/name`
+fn project_id(repo: &RemoteRepo) -> String {
+    repo.full_name().replace('/', "%2F")
+}
+
+impl GitLab {
+    /// Create a client for the instance at `url` (not its `/api/v4` path)
+    pub fn new(url: &str, token: String) -> Self {
+        let api_url = format!("{}/api/v4", url.trim_end_matches('/'));
+        Self {
+            api: ApiClient::new(ForgeKind::GitLab, &api_url, Auth::Bearer(token)),
+        }
+    }
+
+    /// Create a client using `gitlab_url`
+    pub fn from_config(db: &Database, token: String) -> ForgeResult<Self> {
+        let url = config(db, GITLAB_URL_KEY)?.unwrap_or_else(|| DEFAULT_URL.to_string());
+        Ok(Self::new(&url, token))
+    }
+}
+
+impl Forge for GitLab {
+    fn kind(&self) -> ForgeKind {
+        ForgeKind::GitLab
+    }
+
+    fn default_branch<'a>(&'a self, repo: &'a RemoteRepo) -> BoxFuture<'a, ForgeResult<String>> {
+        Box::pin(async move {
+            let path = format!("/projects/{}", project_id(repo));
+            let project: Project = self.api.send(self.api.request(reqwest::Method::GET, &path)).await?;
+            Ok(project.default_branch.unwrap_or_else(|| "main".to_string()))
+        })
+    }
+
+    fn create_pull_request<'a>(
+        &'a self,
+        repo: &'a RemoteRepo,
+        pr: &'a NewPullRequest<'a>,
+    ) -> BoxFuture<'a, ForgeResult<PullRequest>> {
+        Box::pin(async move {
+            // GitLab marks drafts by title prefix
+            let title = if pr.draft {
+                format!("Draft: {}", pr.title)
+            } else {
+                pr.title.to_string()
+            };
+            let path = format!("/projects/{}/merge_requests", project_id(repo));
+            let request = self
+                .api
+                .request(reqwest::Method::POST, &path)
+                .json(&CreateMergeRequest {
+                    source_branch: pr.head,
+                    target_branch: pr.base,
+                    title: &title,
+                    description: pr.body,
+                });
+            let merge_request: MergeRequest = self.api.send(request).await?;
+            Ok(PullRequest {
+                number: merge_request.iid,
+                url: merge_request.web_url,
+            })
+        })
+    }
+
+    fn list_issues<'a>(&'a self, repo: &'a RemoteRepo) -> BoxFuture<'a, ForgeResult<Vec<Issue>>> {
+        Box::pin(async move {
+            let path = format!(
+                "/projects/{}/issues?state=opened&order_by=updated_at&per_page=100",
+                project_id(repo)
+            );
+            let issues: Vec<GitLabIssue> =
+                self.api.send(self.api.request(reqwest::Method::GET, &path)).await?;
+            Ok(issues.into_iter().map(Issue::from).collect())
+        })
+    }
+
+    fn get_issue<'a>(&'a self, repo: &'a RemoteRepo, number: u64) -> BoxFuture<'a, ForgeResult<Issue>> {
+        Box::pin(async move {
+            let path = format!("/projects/{}/issues/{}", project_id(repo), number);
+            let issue: GitLabIssue = self.api.send(self.api.request(reqwest::Method::GET, &path)).await?;
+            Ok(issue.into())
+        })
+    }
+
+    fn list_repos(&self) -> BoxFuture<'_, ForgeResult<Vec<ForgeRepo>>> {
+        Box::pin(async move {
+            let path = "/projects?membership=true&order_by=last_activity_at&per_page=100";
+            let projects: Vec<Project> = self.api.send(self.api.request(reqwest::Method::GET, path)).await?;
+            Ok(projects
+                .into_iter()
+                .map(|project| ForgeRepo {
+                    full_name: project.path_with_namespace,
+                    name: project.name,
+                    description: project.description,
+                    private: project.visibility.as_deref() != Some("public"),
+                    default_branch: project.default_branch,
+                    clone_url: project.http_url_to_repo,
+                    ssh_url: project.ssh_url_to_repo,
+                })
+                .collect())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::forge::serve_fake_api;
+    use axum::{extract::Path as AxumPath, routing::get, routing::post, Json, Router};
+
+    #[tokio::test]
+    async fn test_merge_requests_and_issues() {
+        let app = Router::new()
+            .route(
+                "/api/v4/projects/{id}/merge_requests",
+                post(
+                    |AxumPath(id): AxumPath<String>, Json(body): Json<serde_json::Value>| async move {
+                        assert_eq!(id, "group/sub/hello");
+                        assert_eq!(body["source_branch"], "feature");
+                        assert_eq!(body["title"], "Draft: Add a greeting");
+                        Json(serde_json::json!({
+                            "iid": 3,
+                            "web_url": "https://gitlab.com/group/sub/hello/-/merge_requests/3",
+                        }))
+                    },
+                ),
+            )
+            .route(
+                "/api/v4/projects/{id}/issues",
+                get(|| async {
+                    Json(serde_json::json!([{
+                        "iid": 9,
+                        "title": "Crash on start",
+                        "description": null,
+                        "web_url": "https://gitlab.com/group/sub/hello/-/issues/9",
+                        "labels": ["bug"],
+                    }]))
+                }),
+            );
+        let gitlab = GitLab::new(&serve_fake_api(app).await, "glpat-test".to_string());
+        let repo = RemoteRepo {
+            kind: ForgeKind::GitLab,
+            host: "gitlab.com".to_string(),
+            namespace: "group/sub".to_string(),
+            name: "hello".to_string(),
+        };
+
+        let merge_request = gitlab
+            .create_pull_request(
+                &repo,
+                &NewPullRequest {
+                    title: "Add a greeting",
+                    body: "",
+                    head: "feature",
+                    base: "main",
+                    draft: true,
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(merge_request.number, 3);
+
+        let issues = gitlab.list_issues(&repo).await.unwrap();
+        assert_eq!(issues[0].number, 9);
+        assert_eq!(issues[0].labels, vec!["bug"]);
+    }
+}
@@ -0,0 +1,763 @@
+//! Git hosting (forge) integrations
+//!
+//! GitHub, GitLab, and Bitbucket are supported behind the [`Forge`] trait and
+//! selected from a repository's `origin` URL. With a token stored for the
+//! host, a session's branch can be pushed and opened as a pull (or merge)
+//! request, open issues can be turned into sessions with a pre-filled prompt,
+//! and the token's repositories can be listed for cloning. Titles and bodies
+//! are rendered from templates using the session's prompt and the commits on
+//! the branch. Integration settings are read when they're used, so a new
+//! token takes effect without a restart.
+
+pub mod bitbucket;
+pub mod credentials;
+pub mod github;
+pub mod gitlab;
+
+use std::path::Path;
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::db::models::MessageRole;
+use crate::db::{Database, DbError};
+use crate::git::{Commit, GitError, GitManager};
+use crate::settings::{AUTO_PR_KEY, GITLAB_URL_KEY, PR_BODY_TEMPLATE_KEY, PR_TITLE_TEMPLATE_KEY};
+
+/// Remote that session branches are pushed to
+pub const REMOTE: &str = "origin";
+
+/// Default pull request title
+pub const DEFAULT_TITLE_TEMPLATE: &str = "{prompt_title}";
+
+/// Default pull request body
+pub const DEFAULT_BODY_TEMPLATE: &str =
+    "{prompt}\n\n## Changes\n\n{commits}\n\n---\nOpened by Ralphtown from session {session}.";
+
+/// Longest title generated from a prompt, in characters
+const MAX_TITLE_CHARS: usize = 72;
+
+/// Most commits listed in a generated body
+const MAX_COMMITS: usize = 50;
+
+/// How long a single API request may take
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Supported git hosts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ForgeKind {
+    GitHub,
+    GitLab,
+    Bitbucket,
+}
+
+impl ForgeKind {
+    pub const ALL: [ForgeKind; 3] = [ForgeKind::GitHub, ForgeKind::GitLab, ForgeKind::Bitbucket];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ForgeKind::GitHub => "github",
+            ForgeKind::GitLab => "gitlab",
+            ForgeKind::Bitbucket => "bitbucket",
+        }
+    }
+
+    /// Parse a forge from its `as_str` name
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.as_str() == s)
+    }
+
+    /// Human-readable name
+    pub fn name(&self) -> &'static str {
+        match self {
+            ForgeKind::GitHub => "GitHub",
+            ForgeKind::GitLab => "GitLab",
+            ForgeKind::Bitbucket => "Bitbucket",
+        }
+    }
+
+    /// Config key holding the forge's access token
+    pub fn token_key(&self) -> &'static str {
+        match self {
+            ForgeKind::GitHub => crate::settings::GITHUB_TOKEN_KEY,
+            ForgeKind::GitLab => crate::settings::GITLAB_TOKEN_KEY,
+            ForgeKind::Bitbucket => crate::settings::BITBUCKET_TOKEN_KEY,
+        }
+    }
+}
+
+/// Errors from forge integrations
+#[derive(Debug, Error)]
+pub enum ForgeError {
+    #[error("No {} token is configured; set '{}'", .0.name(), .0.token_key())]
+    NoToken(ForgeKind),
+
+    #[error("Remote '{REMOTE}' is not a GitHub, GitLab, or Bitbucket repository: {0}")]
+    UnsupportedRemote(String),
+
+    #[error("Branch '{0}' is the base branch; check out a feature branch first")]
+    OnBaseBranch(String),
+
+    #[error(transparent)]
+    Git(#[from] GitError),
+
+    #[error(transparent)]
+    Db(#[from] DbError),
+
+    #[error("{forge} returned {status}: {message}", forge = .kind.name())]
+    Api {
+        kind: ForgeKind,
+        status: u16,
+        message: String,
+    },
+
+    #[error("Request to {forge} failed: {message}", forge = .kind.name())]
+    Request { kind: ForgeKind, message: String },
+}
+
+pub type ForgeResult<T> = Result<T, ForgeError>;
+
+/// A repository on a forge, identified from a remote URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteRepo {
+    pub kind: ForgeKind,
+    pub host: String,
+    /// Owner, workspace, or (for GitLab) the full group path
+    pub namespace: String,
+    pub name: String,
+}
+
+impl RemoteRepo {
+    /// `namespace/name`
+    pub fn full_name(&self) -> String {
+        format!("{}/{}", self.namespace, self.name)
+    }
+}
+
+/// Split a remote URL into host and path
+///
+/// Accepts HTTPS (`https://host/o/r.git`), scp-style SSH (`git@host:o/r.git`),
+/// and `ssh://` URLs.
+fn split_remote(url: &str) -> Option<(String, String)> {
+    let url = url.trim();
+    let (host, path) = match url.split_once("://") {
+        Some((_, rest)) => {
+            let (authority, path) = rest.split_once('/')?;
+            let host = authority.rsplit('@').next()?;
+            (host.split(':').next()?, path)
+        }
+        None => {
+            let (authority, path) = url.split_once(':')?;
+            (authority.rsplit('@').next()?, path)
+        }
+    };
+
+    let path = path.trim_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+    if host.is_empty() || path.is_empty() {
+        return None;
+    }
+    Some((host.to_ascii_lowercase(), path.to_string()))
+}
+
+/// Identify the forge and repository a remote URL points at
+///
+/// GitHub and Bitbucket are recognized by their public hosts. GitLab is
+/// recognized by any host containing "gitlab" or matching `gitlab_host`
+/// (for self-managed instances).
+pub fn parse_remote(url: &str, gitlab_host: Option<&str>) -> Option<RemoteRepo> {
+    let (host, path) = split_remote(url)?;
+    let kind = match host.as_str() {
+        "github.com" | "www.github.com" => ForgeKind::GitHub,
+        "bitbucket.org" | "www.bitbucket.org" => ForgeKind::Bitbucket,
+        _ if host.contains("gitlab") || gitlab_host.is_some_and(|h| h.eq_ignore_ascii_case(&host)) => {
+            ForgeKind::GitLab
+        }
+        _ => return None,
+    };
+
+    let (namespace, name) = path.rsplit_once('/')?;
+    let nested = namespace.contains('/');
+    if namespace.is_empty() || name.is_empty() || (nested && kind != ForgeKind::GitLab) {
+        return None;
+    }
+    Some(RemoteRepo {
+        kind,
+        host,
+        namespace: namespace.to_string(),
+        name: name.to_string(),
+    })
+}
+
+/// Overrides for a pull request; unset fields are generated
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PullRequestOptions {
+    pub title: Option<String>,
+    pub body: Option<String>,
+    /// Branch to merge into; the repository's default branch when unset
+    pub base: Option<String>,
+    pub draft: bool,
+}
+
+/// A pull request to open
+#[derive(Debug)]
+pub struct NewPullRequest<'a> {
+    pub title: &'a str,
+    pub body: &'a str,
+    /// Branch with the changes
+    pub head: &'a str,
+    /// Branch to merge into
+    pub base: &'a str,
+    pub draft: bool,
+}
+
+/// An opened pull (or merge) request
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PullRequest {
+    pub number: u64,
+    pub url: String,
+}
+
+/// An open issue
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Issue {
+    pub number: u64,
+    pub title: String,
+    pub body: Option<String>,
+    pub url: String,
+    pub labels: Vec<String>,
+}
+
+impl Issue {
+    /// Prompt asking the agent to resolve the issue
+    pub fn prompt(&self) -> String {
+        let body = self.body.as_deref().unwrap_or_default().trim();
+        if body.is_empty() {
+            format!("Fix issue #{}: {}", self.number, self.title)
+        } else {
+            format!("Fix issue #{}: {}\n\n{}", self.number, self.title, body)
+        }
+    }
+}
+
+/// A repository the token can access, for picking one to clone
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ForgeRepo {
+    /// `namespace/name`
+    pub full_name: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub private: bool,
+    pub default_branch: Option<String>,
+    pub clone_url: String,
+    pub ssh_url: Option<String>,
+}
+
+/// Operations a git host supports
+pub trait Forge: Send + Sync {
+    fn kind(&self) -> ForgeKind;
+
+    /// Name of a repository's default branch
+    fn default_branch<'a>(&'a self, repo: &'a RemoteRepo) -> BoxFuture<'a, ForgeResult<String>>;
+
+    /// Open a pull (or merge) request
+    fn create_pull_request<'a>(
+        &'a self,
+        repo: &'a RemoteRepo,
+        pr: &'a NewPullRequest<'a>,
+    ) -> BoxFuture<'a, ForgeResult<PullRequest>>;
+
+    /// Open issues, most recently updated first
+    fn list_issues<'a>(&'a self, repo: &'a RemoteRepo) -> BoxFuture<'a, ForgeResult<Vec<Issue>>>;
+
+    /// A single issue by number
+    fn get_issue<'a>(&'a self, repo: &'a RemoteRepo, number: u64) -> BoxFuture<'a, ForgeResult<Issue>>;
+
+    /// Repositories the token can access, most recently updated first
+    fn list_repos(&self) -> BoxFuture<'_, ForgeResult<Vec<ForgeRepo>>>;
+}
+
+/// How requests to a forge are authenticated
+#[derive(Debug, Clone)]
+pub enum Auth {
+    Bearer(String),
+    Basic { username: String, password: String },
+}
+
+/// JSON-over-HTTP client shared by the forge implementations
+pub struct ApiClient {
+    kind: ForgeKind,
+    http: reqwest::Client,
+    base_url: String,
+    auth: Auth,
+}
+
+impl ApiClient {
+    pub fn new(kind: ForgeKind, base_url: &str, auth: Auth) -> Self {
+        Self {
+            kind,
+            http: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            auth,
+        }
+    }
+
+    /// Start a request to `path` (relative to the base URL)
+    pub fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let request = self
+            .http
+            .request(method, format!("{}{}", self.base_url, path))
+            .header(reqwest::header::USER_AGENT, "ralphtown")
+            .timeout(REQUEST_TIMEOUT);
+        match &self.auth {
+            Auth::Bearer(token) => request.bearer_auth(token),
+            Auth::Basic { username, password } => request.basic_auth(username, Some(password)),
+        }
+    }
+
+    /// Send a request and decode a JSON response, turning API errors into [`ForgeError::Api`]
+    pub async fn send<T: serde::de::DeserializeOwned>(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> ForgeResult<T> {
+        let response = request
+            .send()
+            .await
+            .map_err(|e| ForgeError::Request {
+                kind: self.kind,
+                message: e.to_string(),
+            })?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let message = match response.json::<serde_json::Value>().await {
+                Ok(body) => api_error_message(&body),
+                Err(_) => None,
+            };
+            return Err(ForgeError::Api {
+                kind: self.kind,
+                status: status.as_u16(),
+                message: message
+                    .unwrap_or_else(|| status.canonical_reason().unwrap_or("Unknown error").to_string()),
+            });
+        }
+
+        response
+            .json()
+            .await
+            .map_err(|e| ForgeError::Request {
+                kind: self.kind,
+                message: e.to_string(),
+            })
+    }
+}
+
+/// Pull a readable message out of a forge's error body
+///
+/// Handles GitHub (`message` plus `errors[].message`), GitLab (`message` as a
+/// string, list, or object, or `error`), and Bitbucket (`error.message`).
+fn api_error_message(body: &serde_json::Value) -> Option<String> {
+    let text = |value: &serde_json::Value| match value {
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+
+    if let Some(message) = body.get("message") {
+        let detail = body
+            .get("errors")
+            .and_then(|errors| errors.get(0))
+            .and_then(|error| error.get("message"))
+            .map(text);
+        return Some(match detail {
+            Some(detail) => format!("{} ({})", text(message), detail),
+            None => text(message),
+        });
+    }
+    body.get("error")
+        .map(|error| error.get("message").map(text).unwrap_or_else(|| text(error)))
+}
+
+/// Read a non-empty config value
+fn config(db: &Database, key: &str) -> ForgeResult<Option<String>> {
+    Ok(db
+        .get_config(key)?
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty()))
+}
+
+/// Host of the configured self-managed GitLab instance, if any
+fn gitlab_host(db: &Database) -> ForgeResult<Option<String>> {
+    Ok(config(db, GITLAB_URL_KEY)?
+        .and_then(|url| split_remote(&format!("{}/x", url.trim_end_matches('/'))))
+        .map(|(host, _)| host))
+}
+
+/// Create a client for a forge from the stored token
+pub fn forge_for(db: &Database, kind: ForgeKind) -> ForgeResult<Box<dyn Forge>> {
+    let token = config(db, kind.token_key())?.ok_or(ForgeError::NoToken(kind))?;
+    Ok(match kind {
+        ForgeKind::GitHub => Box::new(github::GitHub::from_config(db, token)?),
+        ForgeKind::GitLab => Box::new(gitlab::GitLab::from_config(db, token)?),
+        ForgeKind::Bitbucket => Box::new(bitbucket::Bitbucket::from_config(db, token)?),
+    })
+}
+
+/// The forge repository a local repository's `origin` points at
+pub fn remote_repo(db: &Database, repo_path: &Path) -> ForgeResult<RemoteRepo> {
+    let remote_url = GitManager::remote_url(repo_path, REMOTE)?;
+    parse_remote(&remote_url, gitlab_host(db)?.as_deref())
+        .ok_or(ForgeError::UnsupportedRemote(remote_url))
+}
+
+/// Values substituted into the title and body templates
+struct TemplateValues<'a> {
+    prompt: &'a str,
+    session: &'a str,
+    branch: &'a str,
+    commits: &'a [Commit],
+}
+
+/// Replace `{prompt}`, `{prompt_title}`, `{session}`, `{branch}`, and `{commits}` in a template
+fn render_template(template: &str, values: &TemplateValues) -> String {
+    let commits = if values.commits.is_empty() {
+        "_No new commits_".to_string()
+    } else {
+        values
+            .commits
+            .iter()
+            .map(|c| format!("- {} {}", c.short_id, c.message.lines().next().unwrap_or_default()))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    template
+        .replace("{prompt_title}", &prompt_title(values.prompt, values.branch))
+        .replace("{prompt}", values.prompt)
+        .replace("{session}", values.session)
+        .replace("{branch}", values.branch)
+        .replace("{commits}", &commits)
+}
+
+/// First line of the prompt, shortened to fit a title, or the branch name
+fn prompt_title(prompt: &str, branch: &str) -> String {
+    let line = prompt
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or(branch);
+
+    if line.chars().count() <= MAX_TITLE_CHARS {
+        line.to_string()
+    } else {
+        let short: String = line.chars().take(MAX_TITLE_CHARS - 3).collect();
+        format!("{}...", short.trim_end())
+    }
+}
+
+/// A template from config, or the default
+fn template(db: &Database, key: &str, default: &str) -> ForgeResult<String> {
+    Ok(config(db, key)?.unwrap_or_else(|| default.to_string()))
+}
+
+/// Push a session's branch and open a pull request for it
+pub async fn open_pull_request(
+    db: &Database,
+    session_id: Uuid,
+    options: PullRequestOptions,
+) -> ForgeResult<PullRequest> {
+    let session = db.get_session(session_id)?;
+    let repo = db.get_repo(session.repo_id)?;
+    let repo_path = Path::new(&repo.path);
+
+    let remote = remote_repo(db, repo_path)?;
+    let forge = forge_for(db, remote.kind)?;
+
+    let branch = GitManager::current_branch(repo_path)?;
+    let base = match options.base {
+        Some(base) => base,
+        None => forge.default_branch(&remote).await?,
+    };
+    if branch == base {
+        return Err(ForgeError::OnBaseBranch(branch));
+    }
+
+    GitManager::push_branch(repo_path, REMOTE, &branch)?;
+
+    let commits = GitManager::commits_since(repo_path, &format!("{}/{}", REMOTE, base), MAX_COMMITS)?;
+    let prompt = db
+        .list_messages(session_id)?
+        .into_iter()
+        .rev()
+        .find(|message| message.role == MessageRole::User)
+        .map(|message| message.content)
+        .unwrap_or_default();
+    let session_name = session.name.clone().unwrap_or_else(|| session_id.to_string());
+    let values = TemplateValues {
+        prompt: &prompt,
+        session: &session_name,
+        branch: &branch,
+        commits: &commits,
+    };
+
+    let title = match options.title {
+        Some(title) => title,
+        None => render_template(
+            &template(db, PR_TITLE_TEMPLATE_KEY, DEFAULT_TITLE_TEMPLATE)?,
+            &values,
+        ),
+    };
+    let body = match options.body {
+        Some(body) => body,
+        None => render_template(&template(db, PR_BODY_TEMPLATE_KEY, DEFAULT_BODY_TEMPLATE)?, &values),
+    };
+
+    let pull_request = forge
+        .create_pull_request(
+            &remote,
+            &NewPullRequest {
+                title: title.trim(),
+                body: &body,
+                head: &branch,
+                base: &base,
+                draft: options.draft,
+            },
+        )
+        .await?;
+    db.set_session_pr_url(session_id, &pull_request.url)?;
+
+    tracing::info!(
+        "Opened pull request {} for session {}",
+        pull_request.url,
+        session_id
+    );
+    Ok(pull_request)
+}
+
+/// After a successful run, open a pull request (or push to the existing one)
+/// when `auto_pull_request` is enabled
+pub async fn after_run(db: &Database, session_id: Uuid) {
+    let enabled = matches!(db.get_config(AUTO_PR_KEY), Ok(Some(value)) if value == "true");
+    if !enabled {
+        return;
+    }
+
+    let result = match db.get_session(session_id) {
+        // The open pull request picks up new commits once they're pushed
+        Ok(session) if session.pr_url.is_some() => match db.get_repo(session.repo_id) {
+            Ok(repo) => GitManager::current_branch(Path::new(&repo.path))
+                .and_then(|branch| GitManager::push_branch(Path::new(&repo.path), REMOTE, &branch))
+                .map(|_| ())
+                .map_err(ForgeError::from),
+            Err(e) => Err(e.into()),
+        },
+        Ok(_) => open_pull_request(db, session_id, PullRequestOptions::default())
+            .await
+            .map(|_| ()),
+        Err(e) => Err(e.into()),
+    };
+
+    if let Err(e) = result {
+        tracing::warn!("Automatic pull request for session {} failed: {}", session_id, e);
+    }
+}
+
+/// Serve a fake forge API on a local port for tests
+#[cfg(test)]
+pub(crate) async fn serve_fake_api(app: axum::Router) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    format!("http://{}", addr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::Orchestrator;
+    use crate::settings::{GITHUB_API_URL_KEY, GITHUB_TOKEN_KEY};
+    use axum::{extract::Path as AxumPath, routing::get, routing::post, Json, Router};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_remote() {
+        let github = parse_remote("https://github.com/octo/hello.git", None).unwrap();
+        assert_eq!(github.kind, ForgeKind::GitHub);
+        assert_eq!(github.full_name(), "octo/hello");
+        for url in [
+            "https://user@github.com/octo/hello",
+            "git@github.com:octo/hello.git",
+            "ssh://git@github.com/octo/hello.git",
+        ] {
+            assert_eq!(parse_remote(url, None), Some(github.clone()), "{}", url);
+        }
+
+        let gitlab = parse_remote("git@gitlab.com:group/sub/hello.git", None).unwrap();
+        assert_eq!(gitlab.kind, ForgeKind::GitLab);
+        assert_eq!(gitlab.namespace, "group/sub");
+
+        let bitbucket = parse_remote("https://me@bitbucket.org/team/hello.git", None).unwrap();
+        assert_eq!(bitbucket.kind, ForgeKind::Bitbucket);
+        assert_eq!(bitbucket.full_name(), "team/hello");
+
+        // Self-managed GitLab is matched against the configured host
+        let url = "https://code.example.com/team/hello.git";
+        assert_eq!(parse_remote(url, None), None);
+        assert_eq!(
+            parse_remote(url, Some("code.example.com")).map(|r| r.kind),
+            Some(ForgeKind::GitLab)
+        );
+
+        assert_eq!(parse_remote("https://github.com/octo", None), None);
+        assert_eq!(parse_remote("https://github.com/a/b/c", None), None);
+        assert_eq!(parse_remote("/srv/git/hello.git", None), None);
+    }
+
+    #[test]
+    fn test_api_error_message() {
+        let github = serde_json::json!({
+            "message": "Validation Failed",
+            "errors": [{ "message": "A pull request already exists" }],
+        });
+        assert_eq!(
+            api_error_message(&github).unwrap(),
+            "Validation Failed (A pull request already exists)"
+        );
+
+        let gitlab = serde_json::json!({ "message": ["Another open merge request already exists"] });
+        assert!(api_error_message(&gitlab).unwrap().contains("already exists"));
+
+        let bitbucket = serde_json::json!({ "type": "error", "error": { "message": "Not found" } });
+        assert_eq!(api_error_message(&bitbucket).unwrap(), "Not found");
+    }
+
+    #[test]
+    fn test_render_template() {
+        let commits = vec![Commit {
+            id: "abcdef1234".to_string(),
+            short_id: "abcdef1".to_string(),
+            message: "Fix login\n\nDetails".to_string(),
+            author: String::new(),
+            email: String::new(),
+            timestamp: String::new(),
+        }];
+        let values = TemplateValues {
+            prompt: "Fix the login bug\nIt fails on Safari",
+            session: "Login",
+            branch: "fix-login",
+            commits: &commits,
+        };
+
+        assert_eq!(render_template(DEFAULT_TITLE_TEMPLATE, &values), "Fix the login bug");
+        let body = render_template(DEFAULT_BODY_TEMPLATE, &values);
+        assert!(body.starts_with("Fix the login bug\nIt fails on Safari"));
+        assert!(body.contains("- abcdef1 Fix login\n"));
+        assert!(body.ends_with("session Login."));
+
+        let long = "x".repeat(100);
+        assert_eq!(prompt_title(&long, "branch").chars().count(), MAX_TITLE_CHARS);
+        assert_eq!(prompt_title("", "branch"), "branch");
+    }
+
+    #[test]
+    fn test_issue_prompt() {
+        let mut issue = Issue {
+            number: 42,
+            title: "Login fails".to_string(),
+            body: Some("Steps to reproduce\r\n".to_string()),
+            url: "https://github.com/octo/hello/issues/42".to_string(),
+            labels: Vec::new(),
+        };
+        assert_eq!(issue.prompt(), "Fix issue #42: Login fails\n\nSteps to reproduce");
+
+        issue.body = None;
+        assert_eq!(issue.prompt(), "Fix issue #42: Login fails");
+    }
+
+    /// Repo whose origin looks like GitHub but pushes to a local bare repository
+    fn create_repo_with_remote() -> (TempDir, TempDir) {
+        let work_dir = TempDir::new().expect("Failed to create temp dir");
+        let remote_dir = TempDir::new().expect("Failed to create temp dir");
+        git2::Repository::init_bare(remote_dir.path()).expect("Failed to init bare repo");
+
+        let repo = git2::Repository::init(work_dir.path()).expect("Failed to init repo");
+        let mut config = repo.config().expect("Failed to get config");
+        config.set_str("user.name", "Test User").expect("Failed to set user.name");
+        config.set_str("user.email", "test@example.com").expect("Failed to set user.email");
+        repo.remote("origin", "https://github.com/octo/hello.git")
+            .expect("Failed to add remote");
+        config
+            .set_str("remote.origin.pushurl", &remote_dir.path().to_string_lossy())
+            .expect("Failed to set push URL");
+
+        let sig = repo.signature().expect("Failed to create signature");
+        let tree_id = repo.index().expect("Failed to get index").write_tree().expect("Failed to write tree");
+        let tree = repo.find_tree(tree_id).expect("Failed to find tree");
+        let commit = repo
+            .commit(Some("HEAD"), &sig, &sig, "Initial commit", &tree, &[])
+            .expect("Failed to commit");
+        let commit = repo.find_commit(commit).expect("Failed to find commit");
+        repo.branch("feature", &commit, false).expect("Failed to create branch");
+        repo.set_head("refs/heads/feature").expect("Failed to check out branch");
+
+        (work_dir, remote_dir)
+    }
+
+    /// Serve a fake GitHub API that echoes the requested pull request
+    async fn start_fake_github() -> String {
+        let app = Router::new()
+            .route(
+                "/repos/{owner}/{repo}",
+                get(|| async { Json(serde_json::json!({ "default_branch": "main" })) }),
+            )
+            .route(
+                "/repos/{owner}/{repo}/pulls",
+                post(
+                    |AxumPath((owner, repo)): AxumPath<(String, String)>,
+                     Json(body): Json<serde_json::Value>| async move {
+                        assert_eq!(body["head"], "feature");
+                        assert_eq!(body["base"], "main");
+                        assert_eq!(body["title"], "Add a greeting");
+                        Json(serde_json::json!({
+                            "number": 7,
+                            "html_url": format!("https://github.com/{}/{}/pull/7", owner, repo),
+                        }))
+                    },
+                ),
+            );
+        serve_fake_api(app).await
+    }
+
+    #[tokio::test]
+    async fn test_open_pull_request() {
+        let (work_dir, _remote_dir) = create_repo_with_remote();
+        let db = Database::in_memory().unwrap();
+        let repo = db
+            .insert_repo(&work_dir.path().to_string_lossy(), "hello")
+            .unwrap();
+        let session = db.insert_session(repo.id, None, Orchestrator::Ralph).unwrap();
+        db.insert_message(session.id, MessageRole::User, "Add a greeting")
+            .unwrap();
+
+        // Without a token the integration explains what to configure
+        let result = open_pull_request(&db, session.id, PullRequestOptions::default()).await;
+        assert!(matches!(result, Err(ForgeError::NoToken(ForgeKind::GitHub))));
+
+        db.set_config(GITHUB_TOKEN_KEY, "ghp_test").unwrap();
+        db.set_config(GITHUB_API_URL_KEY, &start_fake_github().await)
+            .unwrap();
+
+        let pull_request = open_pull_request(&db, session.id, PullRequestOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(pull_request.number, 7);
+        assert_eq!(
+            db.get_session(session.id).unwrap().pr_url.as_deref(),
+            Some("https://github.com/octo/hello/pull/7")
+        );
+    }
+}
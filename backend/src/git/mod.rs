@@ -1,17 +1,24 @@
 //! Git operations module
 //!
 //! Provides git functionality for repository management:
-//! - Read operations (status, log, branches, diff_stats) using git2 library
+//! - Read operations (status, log, branches, diff_stats, diff_file) using git2 library
 //! - Write operations (pull, push, commit, reset, checkout) using CLI subprocess
+//! - Staging and unstaging individual files using git2 library
+//! - Stashes (push, pop, list, drop) using git2 library
+//! - Linked worktrees, listed using git2 and added or removed using CLI subprocess
 
 use serde::{Deserialize, Serialize};
 use std::cell::RefCell;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, Stdio};
 use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use thiserror::Error;
 use tokio::sync::mpsc;
 
+use crate::sanitize::{self, SanitizeError};
+
 /// Git operation errors
 #[derive(Debug, Error)]
 pub enum GitError {
@@ -26,6 +33,22 @@ pub enum GitError {
 
     #[error("Invalid branch name: {0}")]
     InvalidBranch(String),
+
+    #[error("Invalid argument: {0}")]
+    InvalidArgument(String),
+
+    /// The remote rejected the credentials git offered
+    #[error("{0}")]
+    AuthFailed(CloneError),
+}
+
+impl From<SanitizeError> for GitError {
+    fn from(err: SanitizeError) -> Self {
+        match err {
+            SanitizeError::InvalidBranch(name) => GitError::InvalidBranch(name),
+            other => GitError::InvalidArgument(other.to_string()),
+        }
+    }
 }
 
 pub type GitResult<T> = Result<T, GitError>;
@@ -54,6 +77,61 @@ pub enum CloneError {
 
     #[error("Clone operation failed: {message}")]
     OperationFailed { message: String },
+
+    /// Stopped through its [`CancelToken`]; the partial copy is removed
+    #[error("Clone was cancelled")]
+    Cancelled,
+}
+
+/// Stops an in-flight clone at its next progress update
+///
+/// Clones of a token share its flag, so whoever holds one can cancel the
+/// clone that was given another.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// What to clone besides the default branch's full history
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CloneOptions {
+    /// Branch to check out instead of the remote's default
+    #[serde(default)]
+    pub branch: Option<String>,
+    /// Fetch only this many commits of history
+    #[serde(default)]
+    pub depth: Option<u32>,
+    /// Check out submodules too, recursively
+    #[serde(default)]
+    pub recurse_submodules: bool,
+    /// Run `git lfs pull` afterwards if the repository stores files in LFS
+    #[serde(default)]
+    pub lfs: bool,
+}
+
+impl CloneOptions {
+    /// Check the branch name and depth before cloning
+    pub fn validate(&self) -> GitResult<()> {
+        if let Some(branch) = self.branch.as_deref() {
+            sanitize::branch_name(branch)?;
+        }
+        if self.depth == Some(0) {
+            return Err(GitError::InvalidArgument("Clone depth must be at least 1".to_string()));
+        }
+        Ok(())
+    }
 }
 
 /// Credentials for git clone operations
@@ -77,6 +155,15 @@ struct CredentialState {
     tried_userpass: bool,
 }
 
+/// `credential.helper` value that runs this executable's `credential-helper`
+/// command, or `None` if the executable can't be located
+fn credential_helper() -> Option<String> {
+    let exe = std::env::current_exe().ok()?;
+    let exe = exe.to_str()?;
+    // Git runs helpers starting with `!` through the shell
+    Some(format!("!'{}' credential-helper", exe.replace('\'', "'\\''")))
+}
+
 /// Check if a URL is a GitHub repository
 pub fn is_github_url(url: &str) -> bool {
     url.contains("github.com")
@@ -120,28 +207,12 @@ pub fn classify_clone_error(err: git2::Error, url: &str) -> CloneError {
     match err.class() {
         git2::ErrorClass::Ssh => CloneError::SshAuthFailed {
             message: error_msg,
-            help_steps: vec![
-                "Ensure your SSH key is added to ssh-agent: ssh-add ~/.ssh/id_ed25519".to_string(),
-                "Verify your key is added to GitHub: ssh -T git@github.com".to_string(),
-                "If using a passphrase, the ssh-agent must have the key unlocked".to_string(),
-            ],
+            help_steps: ssh_auth_help_steps(),
             needs_passphrase,
         },
         git2::ErrorClass::Http => CloneError::HttpsAuthFailed {
             message: error_msg,
-            help_steps: if is_github {
-                vec![
-                    "GitHub requires a Personal Access Token (PAT) for HTTPS".to_string(),
-                    "Create a PAT at GitHub Settings > Developer Settings > Tokens".to_string(),
-                    "Use the PAT as password when prompted".to_string(),
-                ]
-            } else {
-                vec![
-                    "HTTPS authentication failed".to_string(),
-                    "Check your username and password".to_string(),
-                    "Some services require an access token instead of password".to_string(),
-                ]
-            },
+            help_steps: https_auth_help_steps(is_github),
             is_github,
         },
         git2::ErrorClass::Net => CloneError::NetworkError {
@@ -153,6 +224,72 @@ pub fn classify_clone_error(err: git2::Error, url: &str) -> CloneError {
     }
 }
 
+/// Recognise an authentication failure in the stderr of a git command that
+/// talked to the remote at `url`
+///
+/// The git CLI reports these as text, so this looks for the messages git and
+/// ssh print. Returns None for any other failure.
+pub fn classify_remote_stderr(stderr: &str, url: &str) -> Option<CloneError> {
+    const SSH_MARKERS: [&str; 3] = ["Permission denied (", "Host key verification failed", "Load key"];
+    const HTTPS_MARKERS: [&str; 5] = [
+        "Authentication failed for",
+        "could not read Username",
+        "could not read Password",
+        "Invalid username or password",
+        "The requested URL returned error: 403",
+    ];
+
+    let message = stderr
+        .lines()
+        .map(str::trim)
+        .find(|line| SSH_MARKERS.iter().chain(&HTTPS_MARKERS).any(|marker| line.contains(marker)))?
+        .trim_start_matches("fatal: ")
+        .to_string();
+    if SSH_MARKERS.iter().any(|marker| message.contains(marker)) {
+        return Some(CloneError::SshAuthFailed {
+            needs_passphrase: stderr.contains("passphrase"),
+            message,
+            help_steps: ssh_auth_help_steps(),
+        });
+    }
+    let is_github = is_github_url(url);
+    Some(CloneError::HttpsAuthFailed {
+        message,
+        help_steps: https_auth_help_steps(is_github),
+        is_github,
+    })
+}
+
+fn ssh_auth_help_steps() -> Vec<String> {
+    vec![
+        "Ensure your SSH key is added to ssh-agent: ssh-add ~/.ssh/id_ed25519".to_string(),
+        "Verify your key is added to GitHub: ssh -T git@github.com".to_string(),
+        "If using a passphrase, the ssh-agent must have the key unlocked".to_string(),
+    ]
+}
+
+fn https_auth_help_steps(is_github: bool) -> Vec<String> {
+    if is_github {
+        vec![
+            "GitHub requires a Personal Access Token (PAT) for HTTPS".to_string(),
+            "Create a PAT at GitHub Settings > Developer Settings > Tokens".to_string(),
+            "Use the PAT as password when prompted".to_string(),
+        ]
+    } else {
+        vec![
+            "HTTPS authentication failed".to_string(),
+            "Check your username and password".to_string(),
+            "Some services require an access token instead of password".to_string(),
+        ]
+    }
+}
+
+/// Whether a gitattributes line sends matching files through the LFS filter
+fn is_lfs_attribute(line: &str) -> bool {
+    let line = line.trim();
+    !line.starts_with('#') && line.split_whitespace().skip(1).any(|attr| attr == "filter=lfs")
+}
+
 use crate::error::AppError;
 
 /// Validate that a repo path exists and is a valid git repository.
@@ -216,6 +353,32 @@ pub struct GitStatus {
     pub staged: Vec<FileStatus>,
     pub unstaged: Vec<FileStatus>,
     pub untracked: Vec<String>,
+    /// Files go through the LFS filter, so they're pointer stubs until
+    /// `git lfs pull` fetches them
+    #[serde(default)]
+    pub lfs_required: bool,
+}
+
+/// Problems with a repository that could get in the way of a run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RepoHealth {
+    /// No warnings
+    pub healthy: bool,
+    /// The directory is gone or no longer a git repository, e.g. it was moved
+    pub stale_path: bool,
+    /// HEAD points at a commit rather than a branch
+    pub detached_head: bool,
+    /// No remote is configured, so there's nowhere to pull from or push to
+    pub missing_remote: bool,
+    /// Uncommitted changes, including untracked files
+    pub dirty: bool,
+    /// Files left conflicted by a merge, rebase, cherry-pick or revert
+    pub conflicts: Vec<String>,
+    /// `merge`, `rebase`, `cherry_pick`, `revert`, `bisect` or `am` while
+    /// one is started and not finished
+    pub operation_in_progress: Option<String>,
+    /// One line per problem, for showing as is
+    pub warnings: Vec<String>,
 }
 
 /// A git commit entry
@@ -229,6 +392,31 @@ pub struct Commit {
     pub timestamp: String,
 }
 
+/// A linked worktree of a repository
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorktreeInfo {
+    pub name: String,
+    pub path: String,
+    /// Checked-out branch; `None` when HEAD is detached or the checkout is gone
+    pub branch: Option<String>,
+    /// Commit HEAD points at
+    pub head: Option<String>,
+    pub locked: bool,
+    /// The worktree's directory is missing, so git would prune it
+    pub prunable: bool,
+}
+
+/// Which commits a log lists
+#[derive(Debug, Clone, Default)]
+pub struct LogFilter {
+    /// Revision to walk back from (branch, tag or commit); HEAD when `None`
+    pub rev: Option<String>,
+    /// Case-insensitive substring of the author's name or email
+    pub author: Option<String>,
+    /// Only commits that change this file or directory
+    pub path: Option<String>,
+}
+
 /// A git branch
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Branch {
@@ -246,6 +434,172 @@ pub struct FileDelta {
     pub removed: usize,
 }
 
+/// Whether a diff line was kept, added or removed
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffLineKind {
+    Context,
+    Added,
+    Removed,
+}
+
+/// A line of a diff hunk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+    /// Line number before the change; `None` for added lines
+    pub old_lineno: Option<u32>,
+    /// Line number after the change; `None` for removed lines
+    pub new_lineno: Option<u32>,
+    /// The line without its trailing newline
+    pub content: String,
+}
+
+/// A run of changed lines with the context around them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffHunk {
+    /// The `@@ -a,b +c,d @@` line
+    pub header: String,
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    pub lines: Vec<DiffLine>,
+}
+
+/// The changes to one file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileDiff {
+    pub path: String,
+    /// For renamed files, the original path
+    pub old_path: Option<String>,
+    /// Binary files have no hunks
+    pub binary: bool,
+    pub hunks: Vec<DiffHunk>,
+}
+
+/// An entry in the stash list
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StashEntry {
+    /// Position in the list; 0 is the most recent
+    pub index: usize,
+    pub message: String,
+    /// Commit holding the stashed changes
+    pub commit: String,
+}
+
+/// A configured remote
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteInfo {
+    pub name: String,
+    pub url: Option<String>,
+    /// URL used for pushing, when set apart from `url`
+    pub push_url: Option<String>,
+}
+
+/// Who commits in a repository are made by, from its own git config
+///
+/// Unset values fall back to the global config, if there is one.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GitIdentity {
+    /// `user.name`
+    #[serde(default)]
+    pub name: Option<String>,
+    /// `user.email`
+    #[serde(default)]
+    pub email: Option<String>,
+}
+
+/// A tag and the commit it points at
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tag {
+    pub name: String,
+    /// Commit the tag points at
+    pub target: String,
+    /// Whether the tag is an annotated tag object rather than a bare ref
+    pub annotated: bool,
+    /// Message of an annotated tag
+    pub message: Option<String>,
+    /// Who created an annotated tag, as "Name <email>"
+    pub tagger: Option<String>,
+}
+
+/// Where a merge or rebase ended up
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeOutcome {
+    /// False when git stopped on conflicts; resolve and stage them, then
+    /// continue or abort
+    pub completed: bool,
+    /// Paths with unresolved conflicts
+    pub conflicts: Vec<String>,
+    pub output: CommandOutput,
+}
+
+/// A file left with conflicting versions by a merge or rebase
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConflictFile {
+    pub path: String,
+    /// Version in the common ancestor; `None` when both sides added the file
+    pub base: Option<String>,
+    /// Version on the current branch; during a rebase, the branch being
+    /// rebased onto. `None` when this side deleted the file
+    pub ours: Option<String>,
+    /// Version being merged in; during a rebase, the commit being replayed
+    pub theirs: Option<String>,
+    /// A version isn't text, so contents are left out
+    pub binary: bool,
+}
+
+/// How to settle a conflicted file
+#[derive(Debug, Clone)]
+pub enum ConflictResolution {
+    /// Keep our version
+    Ours,
+    /// Keep their version
+    Theirs,
+    /// Replace the file with merged content
+    Content(String),
+}
+
+/// A file as it was in a commit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileAtRevision {
+    pub path: String,
+    /// Commit the revision resolved to
+    pub commit: String,
+    /// Size in bytes
+    pub size: usize,
+    pub binary: bool,
+    /// Text content; left out for binary files and ones over [`MAX_SHOW_BYTES`]
+    pub content: Option<String>,
+}
+
+/// Largest file whose content [`GitManager::show`] returns, in bytes
+pub const MAX_SHOW_BYTES: usize = 1024 * 1024;
+
+/// What a directory entry is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TreeEntryKind {
+    File,
+    Directory,
+    Symlink,
+    Submodule,
+}
+
+/// A file or directory in a listing from [`GitManager::tree`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TreeEntry {
+    pub name: String,
+    /// Path relative to the repository root
+    pub path: String,
+    pub kind: TreeEntryKind,
+    /// Size in bytes; left out for directories and submodules
+    pub size: Option<u64>,
+    /// Git file mode in octal, e.g. `100644`, or `100755` for executables
+    pub mode: String,
+}
+
 /// Result of a git command execution
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CommandOutput {
@@ -254,7 +608,7 @@ pub struct CommandOutput {
     pub stderr: String,
 }
 
-/// Clone progress information from git2 transfer_progress callback
+/// Transfer progress of a clone or fetch, from git2's transfer_progress callback
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CloneProgress {
     /// Number of objects received so far
@@ -275,6 +629,33 @@ pub struct CloneProgress {
 pub struct GitManager;
 
 impl GitManager {
+    /// Working tree status of one path relative to the repository root
+    ///
+    /// `None` when the file is unchanged or ignored, and for directories and
+    /// paths git has no record of, which don't have a single status.
+    pub fn path_status(repo_path: &Path, path: &str) -> GitResult<Option<FileStatusType>> {
+        let repo = git2::Repository::open(repo_path)
+            .map_err(|e| GitError::NotARepo(e.message().to_string()))?;
+        let Ok(status) = repo.status_file(Path::new(path)) else {
+            return Ok(None);
+        };
+
+        let status = if status.is_wt_new() {
+            FileStatusType::Untracked
+        } else if status.is_index_new() {
+            FileStatusType::Added
+        } else if status.is_wt_deleted() || status.is_index_deleted() {
+            FileStatusType::Deleted
+        } else if status.is_wt_renamed() || status.is_index_renamed() {
+            FileStatusType::Renamed
+        } else if status.is_wt_modified() || status.is_index_modified() {
+            FileStatusType::Modified
+        } else {
+            return Ok(None);
+        };
+        Ok(Some(status))
+    }
+
     /// Get repository status using git2
     pub fn status(repo_path: &Path) -> GitResult<GitStatus> {
         let repo = git2::Repository::open(repo_path)
@@ -361,11 +742,124 @@ impl GitManager {
             staged,
             unstaged,
             untracked,
+            lfs_required: Self::uses_lfs(&repo),
+        })
+    }
+
+    /// The checked-out branch, with how far it is ahead of and behind its
+    /// upstream as of the last fetch (zeros when it has no upstream)
+    pub fn ahead_behind(repo_path: &Path) -> GitResult<(String, usize, usize)> {
+        let repo = git2::Repository::open(repo_path)
+            .map_err(|e| GitError::NotARepo(e.message().to_string()))?;
+        let branch = Self::get_current_branch(&repo)?;
+        let (ahead, behind) = Self::get_ahead_behind(&repo)?;
+        Ok((branch, ahead, behind))
+    }
+
+    /// Check a repository for anything that could get in the way of a run
+    ///
+    /// Never fails: a path that can't be opened is reported as stale.
+    pub fn health(repo_path: &Path) -> RepoHealth {
+        let Ok(repo) = git2::Repository::open(repo_path) else {
+            return RepoHealth {
+                stale_path: true,
+                warnings: vec![format!("{} is missing or not a git repository", repo_path.display())],
+                ..Default::default()
+            };
+        };
+
+        let detached_head = repo.head_detached().unwrap_or(false);
+        let missing_remote = repo.remotes().map(|remotes| remotes.is_empty()).unwrap_or(true);
+        let dirty = repo
+            .statuses(Some(git2::StatusOptions::new().include_untracked(true)))
+            .map(|statuses| !statuses.is_empty())
+            .unwrap_or(false);
+        let conflicts = Self::conflicted_paths(repo_path).unwrap_or_default();
+        let operation_in_progress = match repo.state() {
+            git2::RepositoryState::Clean => None,
+            git2::RepositoryState::Merge => Some("merge"),
+            git2::RepositoryState::Revert | git2::RepositoryState::RevertSequence => Some("revert"),
+            git2::RepositoryState::CherryPick | git2::RepositoryState::CherryPickSequence => {
+                Some("cherry_pick")
+            }
+            git2::RepositoryState::Bisect => Some("bisect"),
+            git2::RepositoryState::ApplyMailbox => Some("am"),
+            state if Self::is_rebasing(state) => Some("rebase"),
+            _ => None,
+        };
+
+        let mut warnings = Vec::new();
+        if detached_head {
+            warnings.push("HEAD is detached; commits won't be on a branch".to_string());
+        }
+        if missing_remote {
+            warnings.push("No remote is configured".to_string());
+        }
+        if !conflicts.is_empty() {
+            warnings.push(format!("{} file(s) have unresolved conflicts", conflicts.len()));
+        } else if dirty {
+            warnings.push("There are uncommitted changes".to_string());
+        }
+        if let Some(operation) = operation_in_progress {
+            warnings.push(format!("A {} is in progress", operation.replace('_', "-")));
+        }
+
+        RepoHealth {
+            healthy: warnings.is_empty(),
+            stale_path: false,
+            detached_head,
+            missing_remote,
+            dirty,
+            conflicts,
+            operation_in_progress: operation_in_progress.map(str::to_string),
+            warnings,
+        }
+    }
+
+    /// Whether the repository stores files in Git LFS
+    ///
+    /// Looks for a `filter=lfs` attribute in the top-level `.gitattributes`
+    /// or `.git/info/attributes`; nested attribute files aren't read.
+    pub fn lfs_required(repo_path: &Path) -> GitResult<bool> {
+        let repo = git2::Repository::open(repo_path)
+            .map_err(|e| GitError::NotARepo(e.message().to_string()))?;
+        Ok(Self::uses_lfs(&repo))
+    }
+
+    fn uses_lfs(repo: &git2::Repository) -> bool {
+        let files = [
+            repo.workdir().map(|dir| dir.join(".gitattributes")),
+            Some(repo.path().join("info").join("attributes")),
+        ];
+        files.into_iter().flatten().any(|file| {
+            std::fs::read_to_string(file).is_ok_and(|content| content.lines().any(is_lfs_attribute))
         })
     }
 
     /// Get recent commit log using git2
     pub fn log(repo_path: &Path, limit: usize) -> GitResult<Vec<Commit>> {
+        Self::log_page(repo_path, 0, limit).map(|(commits, _)| commits)
+    }
+
+    /// Get `limit` commits starting `skip` commits back from HEAD, along with
+    /// the number of commits reachable from HEAD
+    pub fn log_page(repo_path: &Path, skip: usize, limit: usize) -> GitResult<(Vec<Commit>, usize)> {
+        Self::log_filtered(repo_path, &LogFilter::default(), skip, limit)
+    }
+
+    /// Get `limit` of the commits matching `filter`, skipping the first
+    /// `skip`, along with the number of matching commits
+    ///
+    /// Only the returned commits are formatted; without an author or path
+    /// filter the rest of the history is just counted.
+    pub fn log_filtered(
+        repo_path: &Path,
+        filter: &LogFilter,
+        skip: usize,
+        limit: usize,
+    ) -> GitResult<(Vec<Commit>, usize)> {
+        let path = filter.path.as_deref().map(sanitize::relative_path).transpose()?;
+        let author = filter.author.as_deref().map(str::to_lowercase);
         let repo = git2::Repository::open(repo_path)
             .map_err(|e| GitError::NotARepo(e.message().to_string()))?;
 
@@ -373,10 +867,89 @@ impl GitManager {
             .revwalk()
             .map_err(|e| GitError::OperationFailed(e.message().to_string()))?;
 
-        // Start from HEAD
+        // Start from the requested revision, or HEAD
+        match filter.rev.as_deref() {
+            Some(rev) => {
+                let start = repo
+                    .revparse_single(rev)
+                    .and_then(|object| object.peel_to_commit())
+                    .map_err(|e| GitError::InvalidArgument(format!("Unknown revision {}: {}", rev, e.message())))?;
+                revwalk.push(start.id())
+            }
+            None => revwalk.push_head(),
+        }
+        .map_err(|e| GitError::OperationFailed(e.message().to_string()))?;
+
+        let mut commits = Vec::new();
+        let mut total = 0;
+        for oid in revwalk {
+            let oid = oid.map_err(|e| GitError::OperationFailed(e.message().to_string()))?;
+            let in_page = total >= skip && commits.len() < limit;
+            if !in_page && author.is_none() && path.is_none() {
+                total += 1;
+                continue;
+            }
+            let commit = repo
+                .find_commit(oid)
+                .map_err(|e| GitError::OperationFailed(e.message().to_string()))?;
+            if let Some(author) = &author {
+                let signature = commit.author();
+                let name = signature.name().unwrap_or_default().to_lowercase();
+                let email = signature.email().unwrap_or_default().to_lowercase();
+                if !name.contains(author.as_str()) && !email.contains(author.as_str()) {
+                    continue;
+                }
+            }
+            if let Some(path) = path
+                && !Self::commit_touches(&commit, Path::new(path))
+            {
+                continue;
+            }
+
+            total += 1;
+            if in_page {
+                commits.push(Self::commit_info(oid, &commit));
+            }
+        }
+
+        Ok((commits, total))
+    }
+
+    /// Whether a commit changes `path`, as `git log -- <path>` decides: a
+    /// merge counts only when it differs from every parent
+    fn commit_touches(commit: &git2::Commit, path: &Path) -> bool {
+        let entry_id = |commit: &git2::Commit| {
+            commit
+                .tree()
+                .ok()
+                .and_then(|tree| tree.get_path(path).ok())
+                .map(|entry| entry.id())
+        };
+        let id = entry_id(commit);
+        if commit.parent_count() == 0 {
+            return id.is_some();
+        }
+        commit.parents().all(|parent| entry_id(&parent) != id)
+    }
+
+    /// Commits reachable from HEAD but not from `base` (e.g. "origin/main"), newest first
+    ///
+    /// If `base` can't be resolved every commit on HEAD is returned.
+    pub fn commits_since(repo_path: &Path, base: &str, limit: usize) -> GitResult<Vec<Commit>> {
+        let repo = git2::Repository::open(repo_path)
+            .map_err(|e| GitError::NotARepo(e.message().to_string()))?;
+
+        let mut revwalk = repo
+            .revwalk()
+            .map_err(|e| GitError::OperationFailed(e.message().to_string()))?;
         revwalk
             .push_head()
             .map_err(|e| GitError::OperationFailed(e.message().to_string()))?;
+        if let Ok(base) = repo.revparse_single(base) {
+            revwalk
+                .hide(base.id())
+                .map_err(|e| GitError::OperationFailed(e.message().to_string()))?;
+        }
 
         let mut commits = Vec::new();
         for oid in revwalk.take(limit) {
@@ -384,26 +957,202 @@ impl GitManager {
             let commit = repo
                 .find_commit(oid)
                 .map_err(|e| GitError::OperationFailed(e.message().to_string()))?;
-
-            let author = commit.author();
-            let time = commit.time();
-            let timestamp = chrono::DateTime::from_timestamp(time.seconds(), 0)
-                .map(|dt| dt.to_rfc3339())
-                .unwrap_or_default();
-
-            commits.push(Commit {
-                id: oid.to_string(),
-                short_id: oid.to_string()[..7.min(oid.to_string().len())].to_string(),
-                message: commit.message().unwrap_or("").trim().to_string(),
-                author: author.name().unwrap_or("").to_string(),
-                email: author.email().unwrap_or("").to_string(),
-                timestamp,
-            });
+            commits.push(Self::commit_info(oid, &commit));
         }
 
         Ok(commits)
     }
 
+    /// Name of the checked-out branch; fails when HEAD is detached
+    pub fn current_branch(repo_path: &Path) -> GitResult<String> {
+        let repo = git2::Repository::open(repo_path)
+            .map_err(|e| GitError::NotARepo(e.message().to_string()))?;
+        let head = repo
+            .head()
+            .map_err(|e| GitError::OperationFailed(e.message().to_string()))?;
+
+        match head.shorthand() {
+            Some(name) if head.is_branch() => Ok(name.to_string()),
+            _ => Err(GitError::InvalidBranch("HEAD is detached".to_string())),
+        }
+    }
+
+    /// URL of a configured remote
+    pub fn remote_url(repo_path: &Path, remote: &str) -> GitResult<String> {
+        let repo = git2::Repository::open(repo_path)
+            .map_err(|e| GitError::NotARepo(e.message().to_string()))?;
+        let remote = repo
+            .find_remote(remote)
+            .map_err(|e| GitError::InvalidArgument(e.message().to_string()))?;
+
+        remote
+            .url()
+            .map(String::from)
+            .ok_or_else(|| GitError::InvalidArgument("Remote URL is not valid UTF-8".to_string()))
+    }
+
+    /// Configured remotes, sorted by name
+    pub fn remotes(repo_path: &Path) -> GitResult<Vec<RemoteInfo>> {
+        let repo = git2::Repository::open(repo_path)
+            .map_err(|e| GitError::NotARepo(e.message().to_string()))?;
+        let names = repo
+            .remotes()
+            .map_err(|e| GitError::OperationFailed(e.message().to_string()))?;
+
+        let mut remotes = names
+            .iter()
+            .flatten()
+            .map(|name| Self::remote_info(&repo, name))
+            .collect::<GitResult<Vec<_>>>()?;
+        remotes.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(remotes)
+    }
+
+    /// Add a remote with the default fetch refspec
+    pub fn add_remote(repo_path: &Path, name: &str, url: &str) -> GitResult<RemoteInfo> {
+        let name = sanitize::remote_name(name)?;
+        let url = sanitize::remote_url(url)?;
+        let repo = git2::Repository::open(repo_path)
+            .map_err(|e| GitError::NotARepo(e.message().to_string()))?;
+        repo.remote(name, url).map_err(|e| match e.code() {
+            git2::ErrorCode::Exists => GitError::InvalidArgument(format!("Remote already exists: {}", name)),
+            _ => GitError::InvalidArgument(e.message().to_string()),
+        })?;
+        Self::remote_info(&repo, name)
+    }
+
+    /// Change a remote's URL; `push` changes only the URL pushes go to
+    pub fn set_remote_url(repo_path: &Path, name: &str, url: &str, push: bool) -> GitResult<RemoteInfo> {
+        let url = sanitize::remote_url(url)?;
+        let repo = git2::Repository::open(repo_path)
+            .map_err(|e| GitError::NotARepo(e.message().to_string()))?;
+        // Fails for unknown remotes, which set_url would otherwise create
+        Self::remote_info(&repo, name)?;
+        let updated = if push {
+            repo.remote_set_pushurl(name, Some(url))
+        } else {
+            repo.remote_set_url(name, url)
+        };
+        updated.map_err(|e| GitError::InvalidArgument(e.message().to_string()))?;
+        Self::remote_info(&repo, name)
+    }
+
+    /// Remove a remote along with its remote-tracking branches
+    pub fn remove_remote(repo_path: &Path, name: &str) -> GitResult<()> {
+        let repo = git2::Repository::open(repo_path)
+            .map_err(|e| GitError::NotARepo(e.message().to_string()))?;
+        repo.remote_delete(name).map_err(|e| match e.code() {
+            git2::ErrorCode::NotFound => GitError::InvalidArgument(format!("No such remote: {}", name)),
+            _ => GitError::OperationFailed(e.message().to_string()),
+        })
+    }
+
+    /// The `user.name` and `user.email` set in the repository's own config
+    pub fn identity(repo_path: &Path) -> GitResult<GitIdentity> {
+        let config = Self::local_config(repo_path)?;
+        Ok(GitIdentity {
+            name: config.get_string("user.name").ok(),
+            email: config.get_string("user.email").ok(),
+        })
+    }
+
+    /// Set the repository's own `user.name` and `user.email`
+    ///
+    /// A missing or blank value is removed, so commits fall back to the
+    /// global config for it.
+    pub fn set_identity(repo_path: &Path, identity: &GitIdentity) -> GitResult<GitIdentity> {
+        let mut config = Self::local_config(repo_path)?;
+        let failed = |e: git2::Error| GitError::OperationFailed(e.message().to_string());
+        for (key, field, value) in [
+            ("user.name", "Name", &identity.name),
+            ("user.email", "Email", &identity.email),
+        ] {
+            match value.as_deref().map(str::trim).filter(|value| !value.is_empty()) {
+                Some(value) => {
+                    let value = sanitize::identity(field, value)?;
+                    config.set_str(key, value).map_err(failed)?;
+                }
+                None => {
+                    if let Err(e) = config.remove(key)
+                        && e.code() != git2::ErrorCode::NotFound
+                    {
+                        return Err(failed(e));
+                    }
+                }
+            }
+        }
+        Self::identity(repo_path)
+    }
+
+    fn local_config(repo_path: &Path) -> GitResult<git2::Config> {
+        let repo = git2::Repository::open(repo_path)
+            .map_err(|e| GitError::NotARepo(e.message().to_string()))?;
+        repo.config()
+            .and_then(|config| config.open_level(git2::ConfigLevel::Local))
+            .map_err(|e| GitError::OperationFailed(e.message().to_string()))
+    }
+
+    fn remote_info(repo: &git2::Repository, name: &str) -> GitResult<RemoteInfo> {
+        let remote = repo.find_remote(name).map_err(|e| match e.code() {
+            git2::ErrorCode::NotFound => GitError::InvalidArgument(format!("No such remote: {}", name)),
+            _ => GitError::InvalidArgument(e.message().to_string()),
+        })?;
+        Ok(RemoteInfo {
+            name: name.to_string(),
+            url: remote.url().map(String::from),
+            push_url: remote.pushurl().map(String::from),
+        })
+    }
+
+    /// Paths of the repository's linked worktrees
+    pub fn worktree_paths(repo_path: &Path) -> GitResult<Vec<PathBuf>> {
+        let repo = git2::Repository::open(repo_path)
+            .map_err(|e| GitError::NotARepo(e.message().to_string()))?;
+        let names = repo
+            .worktrees()
+            .map_err(|e| GitError::OperationFailed(e.message().to_string()))?;
+
+        Ok(names
+            .iter()
+            .flatten()
+            .filter_map(|name| repo.find_worktree(name).ok())
+            .map(|worktree| worktree.path().to_path_buf())
+            .collect())
+    }
+
+    /// The repository's linked worktrees
+    pub fn worktrees(repo_path: &Path) -> GitResult<Vec<WorktreeInfo>> {
+        let repo = git2::Repository::open(repo_path)
+            .map_err(|e| GitError::NotARepo(e.message().to_string()))?;
+        let names = repo
+            .worktrees()
+            .map_err(|e| GitError::OperationFailed(e.message().to_string()))?;
+
+        let mut worktrees = Vec::new();
+        for name in names.iter().flatten() {
+            let Ok(worktree) = repo.find_worktree(name) else {
+                continue;
+            };
+            let checkout = worktree
+                .validate()
+                .and_then(|()| git2::Repository::open_from_worktree(&worktree))
+                .ok();
+            let head = checkout.as_ref().and_then(|checkout| checkout.head().ok());
+            worktrees.push(WorktreeInfo {
+                name: name.to_string(),
+                path: worktree.path().to_string_lossy().to_string(),
+                branch: head
+                    .as_ref()
+                    .filter(|head| head.is_branch())
+                    .and_then(|head| head.shorthand().map(String::from)),
+                head: head.as_ref().and_then(|head| head.target()).map(|oid| oid.to_string()),
+                locked: matches!(worktree.is_locked(), Ok(git2::WorktreeLockStatus::Locked(_))),
+                prunable: checkout.is_none(),
+            });
+        }
+        Ok(worktrees)
+    }
+
     /// List branches using git2
     pub fn branches(repo_path: &Path) -> GitResult<Vec<Branch>> {
         let repo = git2::Repository::open(repo_path)
@@ -462,18 +1211,28 @@ impl GitManager {
 
     /// Get diff statistics for uncommitted changes
     pub fn diff_stats(repo_path: &Path) -> GitResult<Vec<FileDelta>> {
+        Self::diff_stats_since(repo_path, None)
+    }
+
+    /// Get diff statistics between `base` (a revision, HEAD when `None`) and
+    /// the working tree, covering both commits made since and uncommitted changes
+    ///
+    /// If `base` can't be resolved the diff is taken against HEAD.
+    pub fn diff_stats_since(repo_path: &Path, base: Option<&str>) -> GitResult<Vec<FileDelta>> {
         let repo = git2::Repository::open(repo_path)
             .map_err(|e| GitError::NotARepo(e.message().to_string()))?;
 
         let mut deltas = Vec::new();
 
-        // Get HEAD tree
-        let head = repo.head().ok();
-        let head_tree = head.as_ref().and_then(|h| h.peel_to_tree().ok());
+        // Get the base tree
+        let base_tree = base
+            .and_then(|base| repo.revparse_single(base).ok())
+            .and_then(|object| object.peel_to_tree().ok())
+            .or_else(|| repo.head().ok().and_then(|h| h.peel_to_tree().ok()));
 
-        // Diff against HEAD (includes both staged and unstaged)
+        // Diff against the base (includes both staged and unstaged)
         let diff = repo
-            .diff_tree_to_workdir_with_index(head_tree.as_ref(), None)
+            .diff_tree_to_workdir_with_index(base_tree.as_ref(), None)
             .map_err(|e| GitError::OperationFailed(e.message().to_string()))?;
 
         let stats = diff
@@ -517,70 +1276,273 @@ impl GitManager {
         Ok(deltas)
     }
 
-    // --- Clone operation ---
-
-    /// Clone a repository from URL to destination path
+    /// Get the unified diff of one file
     ///
-    /// This is a synchronous operation. Callers should use `tokio::task::spawn_blocking`
-    /// to avoid blocking the async runtime.
-    pub fn clone(url: &str, dest: &Path) -> Result<git2::Repository, CloneError> {
-        git2::build::RepoBuilder::new()
-            .clone(url, dest)
-            .map_err(|e| classify_clone_error(e, url))
-    }
+    /// With `staged` the index is compared with HEAD, otherwise the working
+    /// tree with the index; untracked files show up as entirely added. A file
+    /// without changes has no hunks.
+    pub fn diff_file(repo_path: &Path, path: &str, staged: bool) -> GitResult<FileDiff> {
+        let path = sanitize::relative_path(path)?;
+        let repo = git2::Repository::open(repo_path)
+            .map_err(|e| GitError::NotARepo(e.message().to_string()))?;
 
-    /// Clone a repository with progress reporting
-    ///
+        let mut opts = git2::DiffOptions::new();
+        opts.pathspec(path).disable_pathspec_match(true);
+        let diff = if staged {
+            let head_tree = repo.head().ok().and_then(|h| h.peel_to_tree().ok());
+            repo.diff_tree_to_index(head_tree.as_ref(), None, Some(&mut opts))
+        } else {
+            opts.include_untracked(true).show_untracked_content(true);
+            repo.diff_index_to_workdir(None, Some(&mut opts))
+        }
+        .map_err(|e| GitError::OperationFailed(e.message().to_string()))?;
+
+        let mut file = FileDiff {
+            path: path.to_string(),
+            old_path: None,
+            binary: false,
+            hunks: Vec::new(),
+        };
+        let Some(delta) = diff.get_delta(0) else {
+            return Ok(file);
+        };
+        if delta.status() == git2::Delta::Renamed {
+            file.old_path = delta.old_file().path().map(|p| p.to_string_lossy().to_string());
+        }
+
+        // Whether a file is binary is only known once its content is loaded
+        let patch = git2::Patch::from_diff(&diff, 0)
+            .map_err(|e| GitError::OperationFailed(e.message().to_string()))?;
+        file.binary = patch.as_ref().is_none_or(|patch| patch.delta().flags().is_binary());
+        let Some(patch) = patch.filter(|_| !file.binary) else {
+            return Ok(file);
+        };
+        for hunk_idx in 0..patch.num_hunks() {
+            let (hunk, line_count) = patch
+                .hunk(hunk_idx)
+                .map_err(|e| GitError::OperationFailed(e.message().to_string()))?;
+            let mut lines = Vec::with_capacity(line_count);
+            for line_idx in 0..line_count {
+                let line = patch
+                    .line_in_hunk(hunk_idx, line_idx)
+                    .map_err(|e| GitError::OperationFailed(e.message().to_string()))?;
+                let kind = match line.origin() {
+                    '+' => DiffLineKind::Added,
+                    '-' => DiffLineKind::Removed,
+                    ' ' => DiffLineKind::Context,
+                    // "No newline at end of file" markers
+                    _ => continue,
+                };
+                let content = String::from_utf8_lossy(line.content());
+                lines.push(DiffLine {
+                    kind,
+                    old_lineno: line.old_lineno(),
+                    new_lineno: line.new_lineno(),
+                    content: content.trim_end_matches(['\n', '\r']).to_string(),
+                });
+            }
+            file.hunks.push(DiffHunk {
+                header: String::from_utf8_lossy(hunk.header()).trim_end().to_string(),
+                old_start: hunk.old_start(),
+                old_lines: hunk.old_lines(),
+                new_start: hunk.new_start(),
+                new_lines: hunk.new_lines(),
+                lines,
+            });
+        }
+        Ok(file)
+    }
+
+    // --- Clone operation ---
+
+    /// Clone a repository from URL to destination path
+    ///
+    /// This is a synchronous operation. Callers should use `tokio::task::spawn_blocking`
+    /// to avoid blocking the async runtime.
+    pub fn clone(url: &str, dest: &Path) -> Result<git2::Repository, CloneError> {
+        Self::clone_with_options(url, dest, &CloneOptions::default())
+    }
+
+    /// Clone a repository with a branch, depth or submodules other than the defaults
+    ///
+    /// This is a synchronous operation. Callers should use `tokio::task::spawn_blocking`
+    /// to avoid blocking the async runtime.
+    pub fn clone_with_options(
+        url: &str,
+        dest: &Path,
+        options: &CloneOptions,
+    ) -> Result<git2::Repository, CloneError> {
+        Self::clone_repo(url, dest, options, &CancelToken::new(), git2::RemoteCallbacks::new)
+    }
+
+    /// Clone a repository with progress reporting
+    ///
     /// This is a synchronous operation. Callers should use `tokio::task::spawn_blocking`
     /// to avoid blocking the async runtime.
     ///
     /// Progress updates are sent via the provided mpsc::Sender. Uses try_send() to
     /// drop updates if the channel is full, providing natural throttling.
+    /// Cancelling `cancel` aborts the clone at the next update.
     pub fn clone_with_progress(
         url: &str,
         dest: &Path,
+        options: &CloneOptions,
         progress_tx: mpsc::Sender<CloneProgress>,
+        cancel: &CancelToken,
     ) -> Result<git2::Repository, CloneError> {
-        let mut callbacks = git2::RemoteCallbacks::new();
-
-        callbacks.transfer_progress(move |stats| {
-            let progress = CloneProgress {
-                received_objects: stats.received_objects(),
-                total_objects: stats.total_objects(),
-                received_bytes: stats.received_bytes(),
-                indexed_objects: stats.indexed_objects(),
-                total_deltas: stats.total_deltas(),
-                indexed_deltas: stats.indexed_deltas(),
-            };
-            // Use try_send to drop updates if channel is full (natural throttling)
-            // This prevents backpressure from blocking the git operation
-            let _ = progress_tx.try_send(progress);
-            true // continue cloning
-        });
-
-        let mut fetch_options = git2::FetchOptions::new();
-        fetch_options.remote_callbacks(callbacks);
-
-        git2::build::RepoBuilder::new()
-            .fetch_options(fetch_options)
-            .clone(url, dest)
-            .map_err(|e| classify_clone_error(e, url))
+        Self::clone_repo(url, dest, options, cancel, || {
+            let progress_tx = progress_tx.clone();
+            let cancel = cancel.clone();
+            let mut callbacks = git2::RemoteCallbacks::new();
+
+            callbacks.transfer_progress(move |stats| {
+                let progress = CloneProgress {
+                    received_objects: stats.received_objects(),
+                    total_objects: stats.total_objects(),
+                    received_bytes: stats.received_bytes(),
+                    indexed_objects: stats.indexed_objects(),
+                    total_deltas: stats.total_deltas(),
+                    indexed_deltas: stats.indexed_deltas(),
+                };
+                // Use try_send to drop updates if channel is full (natural throttling)
+                // This prevents backpressure from blocking the git operation
+                let _ = progress_tx.try_send(progress);
+                !cancel.is_cancelled() // returning false aborts the clone
+            });
+            callbacks
+        })
     }
 
     /// Clone a repository with optional credentials and progress reporting
     ///
     /// This is a synchronous operation. Callers should use `tokio::task::spawn_blocking`
-    /// to avoid blocking the async runtime.
-    ///
-    /// The credential callback uses state tracking to prevent infinite loops when
-    /// libgit2 repeatedly requests credentials. Each auth method is tried once.
+    /// to avoid blocking the async runtime. Cancelling `cancel` aborts the clone
+    /// as for [`Self::clone_with_progress`].
     pub fn clone_with_credentials(
         url: &str,
         dest: &Path,
+        options: &CloneOptions,
         credentials: Option<CloneCredentials>,
         progress_tx: mpsc::Sender<CloneProgress>,
+        cancel: &CancelToken,
     ) -> Result<git2::Repository, CloneError> {
-        let creds = credentials.unwrap_or_default();
+        let credentials = credentials.unwrap_or_default();
+        Self::clone_repo(url, dest, options, cancel, || {
+            Self::remote_callbacks(credentials.clone(), progress_tx.clone(), cancel.clone())
+        })
+    }
+
+    /// Clone with `options`, asking `callbacks` for a fresh set of remote
+    /// callbacks for the repository and each submodule
+    fn clone_repo(
+        url: &str,
+        dest: &Path,
+        options: &CloneOptions,
+        cancel: &CancelToken,
+        callbacks: impl Fn() -> git2::RemoteCallbacks<'static>,
+    ) -> Result<git2::Repository, CloneError> {
+        options.validate().map_err(|e| CloneError::OperationFailed {
+            message: e.to_string(),
+        })?;
+
+        let mut fetch_options = git2::FetchOptions::new();
+        fetch_options.remote_callbacks(callbacks());
+        if let Some(depth) = options.depth {
+            fetch_options.depth(i32::try_from(depth).unwrap_or(i32::MAX));
+        }
+        let mut builder = git2::build::RepoBuilder::new();
+        builder.fetch_options(fetch_options);
+        if let Some(branch) = options.branch.as_deref() {
+            builder.branch(branch);
+        }
+        let cloned = builder.clone(url, dest).and_then(|repo| {
+            if options.recurse_submodules {
+                Self::update_submodules(&repo, &callbacks)?;
+            }
+            Ok(repo)
+        });
+
+        match cloned {
+            Ok(repo) => {
+                // libgit2 doesn't run the LFS filter, so without this the
+                // checkout holds pointer stubs; the clone stands either way
+                if options.lfs
+                    && Self::uses_lfs(&repo)
+                    && let Some(workdir) = repo.workdir()
+                    && let Err(e) = Self::lfs_pull(workdir)
+                {
+                    tracing::warn!("git lfs pull failed after cloning {}: {}", url, e);
+                }
+                Ok(repo)
+            }
+            Err(_) if cancel.is_cancelled() => {
+                // libgit2 removes what it cloned, but not submodules checked
+                // out after the clone itself finished
+                if dest.exists() && let Err(e) = std::fs::remove_dir_all(dest) {
+                    tracing::warn!("Failed to remove cancelled clone {:?}: {}", dest, e);
+                }
+                Err(CloneError::Cancelled)
+            }
+            Err(e) => Err(classify_clone_error(e, url)),
+        }
+    }
+
+    /// Check out every submodule, and theirs in turn
+    fn update_submodules(
+        repo: &git2::Repository,
+        callbacks: &impl Fn() -> git2::RemoteCallbacks<'static>,
+    ) -> Result<(), git2::Error> {
+        for mut submodule in repo.submodules()? {
+            let mut fetch_options = git2::FetchOptions::new();
+            fetch_options.remote_callbacks(callbacks());
+            let mut update_options = git2::SubmoduleUpdateOptions::new();
+            update_options.fetch(fetch_options);
+            submodule.update(true, Some(&mut update_options))?;
+            Self::update_submodules(&submodule.open()?, callbacks)?;
+        }
+        Ok(())
+    }
+
+    /// Fetch from a remote of an existing repository, reporting progress
+    ///
+    /// Uses the remote's configured refspecs and the same authentication as
+    /// [`Self::clone_with_credentials`]; failures are classified the same way.
+    /// This is a synchronous operation. Callers should use
+    /// `tokio::task::spawn_blocking` to avoid blocking the async runtime.
+    pub fn fetch_with_progress(
+        repo_path: &Path,
+        remote: &str,
+        credentials: Option<CloneCredentials>,
+        progress_tx: mpsc::Sender<CloneProgress>,
+    ) -> Result<(), CloneError> {
+        let repo = git2::Repository::open(repo_path).map_err(|e| CloneError::OperationFailed {
+            message: format!("Not a git repository: {}", e.message()),
+        })?;
+        let mut remote = repo.find_remote(remote).map_err(|e| CloneError::OperationFailed {
+            message: e.message().to_string(),
+        })?;
+        let url = remote.url().unwrap_or_default().to_string();
+
+        let mut fetch_options = git2::FetchOptions::new();
+        fetch_options.remote_callbacks(Self::remote_callbacks(
+            credentials.unwrap_or_default(),
+            progress_tx,
+            CancelToken::new(),
+        ));
+        remote
+            .fetch(&[] as &[&str], Some(&mut fetch_options), None)
+            .map_err(|e| classify_clone_error(e, &url))
+    }
+
+    /// Callbacks that report transfer progress and answer credential requests
+    ///
+    /// The credential callback uses state tracking to prevent infinite loops when
+    /// libgit2 repeatedly requests credentials. Each auth method is tried once.
+    fn remote_callbacks(
+        creds: CloneCredentials,
+        progress_tx: mpsc::Sender<CloneProgress>,
+        cancel: CancelToken,
+    ) -> git2::RemoteCallbacks<'static> {
         let state = Rc::new(RefCell::new(CredentialState::default()));
         let state_clone = Rc::clone(&state);
 
@@ -604,7 +1566,7 @@ impl GitManager {
             };
             // Use try_send to drop updates if channel is full (natural throttling)
             let _ = progress_tx.try_send(progress);
-            true // continue cloning
+            !cancel.is_cancelled() // returning false aborts the transfer
         });
 
         // Credential callback with state tracking
@@ -659,29 +1621,467 @@ impl GitManager {
             Err(git2::Error::from_str("all authentication methods failed"))
         });
 
-        let mut fetch_options = git2::FetchOptions::new();
-        fetch_options.remote_callbacks(callbacks);
+        callbacks
+    }
 
-        git2::build::RepoBuilder::new()
-            .fetch_options(fetch_options)
-            .clone(url, dest)
-            .map_err(|e| classify_clone_error(e, url))
+    /// Create a repository in `path` and commit everything in it, returning the commit id
+    ///
+    /// The commit uses the configured git identity, or a Ralphtown one when
+    /// none is set, since a fresh machine often has no `user.name`.
+    pub fn init_with_commit(path: &Path, message: &str) -> GitResult<String> {
+        let message = sanitize::commit_message(message)?;
+        let failed = |e: git2::Error| GitError::OperationFailed(e.message().to_string());
+
+        let repo = git2::Repository::init(path).map_err(failed)?;
+        let mut index = repo.index().map_err(failed)?;
+        index
+            .add_all(["*"], git2::IndexAddOption::DEFAULT, None)
+            .map_err(failed)?;
+        index.write().map_err(failed)?;
+        let tree = repo.find_tree(index.write_tree().map_err(failed)?).map_err(failed)?;
+        let signature = repo
+            .signature()
+            .or_else(|_| git2::Signature::now("Ralphtown", "ralphtown@localhost"))
+            .map_err(failed)?;
+        let oid = repo
+            .commit(Some("HEAD"), &signature, &signature, message, &tree, &[])
+            .map_err(failed)?;
+        Ok(oid.to_string())
+    }
+
+    // --- Staging using git2 ---
+
+    /// Stage files, like `git add -- <paths>`
+    ///
+    /// Paths are relative to the repository root; a directory stages
+    /// everything under it. Deleted files are staged as deletions.
+    pub fn stage(repo_path: &Path, paths: &[String]) -> GitResult<()> {
+        let paths = Self::pathspecs(paths)?;
+        let repo = git2::Repository::open(repo_path)
+            .map_err(|e| GitError::NotARepo(e.message().to_string()))?;
+        let mut index = repo
+            .index()
+            .map_err(|e| GitError::OperationFailed(e.message().to_string()))?;
+
+        index
+            .add_all(paths.iter().copied(), git2::IndexAddOption::DEFAULT, None)
+            .and_then(|()| index.update_all(paths.iter().copied(), None))
+            .and_then(|()| index.write())
+            .map_err(|e| GitError::OperationFailed(e.message().to_string()))
+    }
+
+    /// Unstage files, like `git reset -- <paths>`, keeping their changes in
+    /// the working tree
+    pub fn unstage(repo_path: &Path, paths: &[String]) -> GitResult<()> {
+        let paths = Self::pathspecs(paths)?;
+        let repo = git2::Repository::open(repo_path)
+            .map_err(|e| GitError::NotARepo(e.message().to_string()))?;
+
+        // Before the first commit there's nothing to reset to, so the
+        // files are taken out of the index
+        let head = repo.head().ok().and_then(|h| h.peel(git2::ObjectType::Commit).ok());
+        repo.reset_default(head.as_ref(), paths.iter().copied())
+            .map_err(|e| GitError::OperationFailed(e.message().to_string()))
+    }
+
+    fn pathspecs(paths: &[String]) -> GitResult<Vec<&str>> {
+        if paths.is_empty() {
+            return Err(GitError::InvalidArgument("No paths given".to_string()));
+        }
+        paths
+            .iter()
+            .map(|path| sanitize::relative_path(path).map_err(GitError::from))
+            .collect()
+    }
+
+    /// A file's content at `rev` (default: HEAD), like `git show <rev>:<path>`
+    pub fn show(repo_path: &Path, path: &str, rev: Option<&str>) -> GitResult<FileAtRevision> {
+        let path = sanitize::relative_path(path)?;
+        let repo = git2::Repository::open(repo_path)
+            .map_err(|e| GitError::NotARepo(e.message().to_string()))?;
+        let rev = rev.unwrap_or("HEAD");
+        let commit = repo
+            .revparse_single(rev)
+            .and_then(|object| object.peel_to_commit())
+            .map_err(|e| GitError::InvalidArgument(format!("Unknown revision {}: {}", rev, e.message())))?;
+        let tree = commit
+            .tree()
+            .map_err(|e| GitError::OperationFailed(e.message().to_string()))?;
+        let entry = tree
+            .get_path(Path::new(path))
+            .map_err(|_| GitError::InvalidArgument(format!("{} doesn't exist at {}", path, rev)))?;
+        let blob = entry
+            .to_object(&repo)
+            .and_then(|object| object.peel_to_blob())
+            .map_err(|_| GitError::InvalidArgument(format!("{} isn't a file at {}", path, rev)))?;
+
+        let binary = blob.is_binary();
+        let content = match std::str::from_utf8(blob.content()) {
+            Ok(content) if !binary && blob.size() <= MAX_SHOW_BYTES => Some(content.to_string()),
+            _ => None,
+        };
+        Ok(FileAtRevision {
+            path: path.to_string(),
+            commit: commit.id().to_string(),
+            size: blob.size(),
+            binary,
+            content,
+        })
+    }
+
+    /// The files and directories in `path` (default: the root), directories first
+    ///
+    /// With a `rev` the listing comes from that commit's tree. Without one it
+    /// comes from the working directory, untracked files included but not
+    /// ignored ones or `.git`.
+    pub fn tree(
+        repo_path: &Path,
+        rev: Option<&str>,
+        path: Option<&str>,
+    ) -> GitResult<Vec<TreeEntry>> {
+        let path = path.map(sanitize::relative_path).transpose()?;
+        let repo = git2::Repository::open(repo_path)
+            .map_err(|e| GitError::NotARepo(e.message().to_string()))?;
+        let mut entries = match rev {
+            Some(rev) => Self::tree_at(&repo, rev, path)?,
+            None => Self::tree_in_workdir(&repo, path)?,
+        };
+        entries.sort_by_key(|entry| (entry.kind != TreeEntryKind::Directory, entry.name.clone()));
+        Ok(entries)
+    }
+
+    fn tree_at(
+        repo: &git2::Repository,
+        rev: &str,
+        path: Option<&str>,
+    ) -> GitResult<Vec<TreeEntry>> {
+        let failed = |e: git2::Error| GitError::OperationFailed(e.message().to_string());
+        let commit = repo
+            .revparse_single(rev)
+            .and_then(|object| object.peel_to_commit())
+            .map_err(|e| GitError::InvalidArgument(format!("Unknown revision {}: {}", rev, e.message())))?;
+        let mut tree = commit.tree().map_err(failed)?;
+        if let Some(path) = path {
+            let entry = tree
+                .get_path(Path::new(path))
+                .map_err(|_| GitError::InvalidArgument(format!("{} doesn't exist at {}", path, rev)))?;
+            tree = entry
+                .to_object(repo)
+                .and_then(|object| object.peel_to_tree())
+                .map_err(|_| GitError::InvalidArgument(format!("{} isn't a directory at {}", path, rev)))?;
+        }
+
+        let odb = repo.odb().map_err(failed)?;
+        tree.iter()
+            .map(|entry| {
+                let name = String::from_utf8_lossy(entry.name_bytes()).to_string();
+                let mode = entry.filemode() as u32;
+                let kind = match entry.kind() {
+                    Some(git2::ObjectType::Tree) => TreeEntryKind::Directory,
+                    Some(git2::ObjectType::Commit) => TreeEntryKind::Submodule,
+                    _ if mode == 0o120000 => TreeEntryKind::Symlink,
+                    _ => TreeEntryKind::File,
+                };
+                let size = match kind {
+                    TreeEntryKind::File | TreeEntryKind::Symlink => {
+                        Some(odb.read_header(entry.id()).map_err(failed)?.0 as u64)
+                    }
+                    _ => None,
+                };
+                Ok(TreeEntry {
+                    path: Self::join_tree_path(path, &name),
+                    name,
+                    kind,
+                    size,
+                    mode: format!("{:06o}", mode),
+                })
+            })
+            .collect()
+    }
+
+    fn tree_in_workdir(repo: &git2::Repository, path: Option<&str>) -> GitResult<Vec<TreeEntry>> {
+        let workdir = repo.workdir().ok_or_else(|| {
+            GitError::InvalidArgument("Repository has no working directory".to_string())
+        })?;
+        let dir = workdir.join(path.unwrap_or_default());
+        let display = path.unwrap_or(".");
+        // A symlinked directory could lead out of the repository
+        let inside = match (dir.canonicalize(), workdir.canonicalize()) {
+            (Ok(dir), Ok(workdir)) => dir.starts_with(workdir),
+            _ => false,
+        };
+        if !inside || !dir.is_dir() {
+            return Err(GitError::InvalidArgument(format!("{} isn't a directory", display)));
+        }
+        let read = std::fs::read_dir(&dir)
+            .map_err(|e| GitError::OperationFailed(format!("Failed to read {}: {}", display, e)))?;
+
+        let mut entries = Vec::new();
+        for item in read.flatten() {
+            let name = item.file_name().to_string_lossy().to_string();
+            let Ok(metadata) = item.path().symlink_metadata() else {
+                continue;
+            };
+            let path = Self::join_tree_path(path, &name);
+            if name == ".git" || repo.is_path_ignored(&path).unwrap_or(false) {
+                continue;
+            }
+            let (kind, mode) = if metadata.file_type().is_symlink() {
+                (TreeEntryKind::Symlink, 0o120000)
+            } else if metadata.is_dir() && item.path().join(".git").exists() {
+                (TreeEntryKind::Submodule, 0o160000)
+            } else if metadata.is_dir() {
+                (TreeEntryKind::Directory, 0o040000)
+            } else if Self::is_executable(&metadata) {
+                (TreeEntryKind::File, 0o100755)
+            } else {
+                (TreeEntryKind::File, 0o100644)
+            };
+            let size = matches!(kind, TreeEntryKind::File | TreeEntryKind::Symlink)
+                .then(|| metadata.len());
+            entries.push(TreeEntry {
+                name,
+                path,
+                kind,
+                size,
+                mode: format!("{:06o}", mode),
+            });
+        }
+        Ok(entries)
+    }
+
+    fn join_tree_path(dir: Option<&str>, name: &str) -> String {
+        match dir {
+            Some(dir) => format!("{}/{}", dir, name),
+            None => name.to_string(),
+        }
+    }
+
+    #[cfg(unix)]
+    fn is_executable(metadata: &std::fs::Metadata) -> bool {
+        use std::os::unix::fs::PermissionsExt;
+        metadata.permissions().mode() & 0o111 != 0
+    }
+
+    #[cfg(not(unix))]
+    fn is_executable(_metadata: &std::fs::Metadata) -> bool {
+        false
+    }
+
+    // --- Stashes using git2 ---
+
+    /// List stashed changes, most recent first
+    pub fn stash_list(repo_path: &Path) -> GitResult<Vec<StashEntry>> {
+        let mut repo = git2::Repository::open(repo_path)
+            .map_err(|e| GitError::NotARepo(e.message().to_string()))?;
+
+        let mut entries = Vec::new();
+        repo.stash_foreach(|index, message, oid| {
+            entries.push(StashEntry {
+                index,
+                message: message.to_string(),
+                commit: oid.to_string(),
+            });
+            true
+        })
+        .map_err(|e| GitError::OperationFailed(e.message().to_string()))?;
+        Ok(entries)
+    }
+
+    /// Stash the working tree's changes, and untracked files too if asked,
+    /// leaving it clean
+    pub fn stash_push(repo_path: &Path, message: Option<&str>, include_untracked: bool) -> GitResult<StashEntry> {
+        let message = message.map(sanitize::commit_message).transpose()?;
+        let mut repo = git2::Repository::open(repo_path)
+            .map_err(|e| GitError::NotARepo(e.message().to_string()))?;
+        let signature = repo
+            .signature()
+            .or_else(|_| git2::Signature::now("Ralphtown", "ralphtown@localhost"))
+            .map_err(|e| GitError::OperationFailed(e.message().to_string()))?;
+
+        let mut flags = git2::StashFlags::DEFAULT;
+        if include_untracked {
+            flags |= git2::StashFlags::INCLUDE_UNTRACKED;
+        }
+        repo.stash_save2(&signature, message, Some(flags))
+            .map_err(|e| match e.code() {
+                git2::ErrorCode::NotFound => GitError::InvalidArgument("No local changes to stash".to_string()),
+                _ => GitError::OperationFailed(e.message().to_string()),
+            })?;
+        Self::stash_list(repo_path)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| GitError::OperationFailed("The stash is empty after stashing".to_string()))
+    }
+
+    /// Apply a stash to the working tree and drop it
+    ///
+    /// The stash is kept when its changes conflict with the working tree's.
+    pub fn stash_pop(repo_path: &Path, index: usize) -> GitResult<()> {
+        let mut repo = git2::Repository::open(repo_path)
+            .map_err(|e| GitError::NotARepo(e.message().to_string()))?;
+        repo.stash_pop(index, None).map_err(Self::stash_error)
+    }
+
+    /// Throw a stash away
+    pub fn stash_drop(repo_path: &Path, index: usize) -> GitResult<()> {
+        let mut repo = git2::Repository::open(repo_path)
+            .map_err(|e| GitError::NotARepo(e.message().to_string()))?;
+        repo.stash_drop(index).map_err(Self::stash_error)
+    }
+
+    fn stash_error(e: git2::Error) -> GitError {
+        match e.code() {
+            git2::ErrorCode::NotFound => GitError::InvalidArgument(format!("No such stash: {}", e.message())),
+            git2::ErrorCode::Conflict | git2::ErrorCode::MergeConflict => GitError::InvalidArgument(format!(
+                "The stash conflicts with local changes: {}",
+                e.message()
+            )),
+            _ => GitError::OperationFailed(e.message().to_string()),
+        }
+    }
+
+    /// All tags, sorted by name
+    pub fn tags(repo_path: &Path) -> GitResult<Vec<Tag>> {
+        let repo = git2::Repository::open(repo_path)
+            .map_err(|e| GitError::NotARepo(e.message().to_string()))?;
+        let names = repo
+            .tag_names(None)
+            .map_err(|e| GitError::OperationFailed(e.message().to_string()))?;
+
+        names
+            .iter()
+            .flatten()
+            .map(|name| {
+                let object = repo
+                    .revparse_single(&format!("refs/tags/{}", name))
+                    .map_err(|e| GitError::OperationFailed(e.message().to_string()))?;
+                Ok(Self::tag_info(name, &object))
+            })
+            .collect()
+    }
+
+    /// Tag `target` (default: HEAD) as `name`; with a message the tag is
+    /// annotated, otherwise lightweight
+    pub fn create_tag(
+        repo_path: &Path,
+        name: &str,
+        target: Option<&str>,
+        message: Option<&str>,
+    ) -> GitResult<Tag> {
+        // Tag names follow the same ref name rules as branches
+        let name = sanitize::branch_name(name)?;
+        let message = message.map(sanitize::commit_message).transpose()?;
+        let repo = git2::Repository::open(repo_path)
+            .map_err(|e| GitError::NotARepo(e.message().to_string()))?;
+        let target = target.unwrap_or("HEAD");
+        let object = repo
+            .revparse_single(target)
+            .map_err(|e| GitError::InvalidArgument(format!("Unknown revision {}: {}", target, e.message())))?;
+
+        let created = match message {
+            Some(message) => {
+                let signature = repo
+                    .signature()
+                    .or_else(|_| git2::Signature::now("Ralphtown", "ralphtown@localhost"))
+                    .map_err(|e| GitError::OperationFailed(e.message().to_string()))?;
+                repo.tag(name, &object, &signature, message, false)
+            }
+            None => repo.tag_lightweight(name, &object, false),
+        };
+        created.map_err(|e| match e.code() {
+            git2::ErrorCode::Exists => GitError::InvalidArgument(format!("Tag already exists: {}", name)),
+            _ => GitError::OperationFailed(e.message().to_string()),
+        })?;
+
+        let object = repo
+            .revparse_single(&format!("refs/tags/{}", name))
+            .map_err(|e| GitError::OperationFailed(e.message().to_string()))?;
+        Ok(Self::tag_info(name, &object))
+    }
+
+    /// Delete a local tag; a pushed tag stays on the remote
+    pub fn delete_tag(repo_path: &Path, name: &str) -> GitResult<()> {
+        let repo = git2::Repository::open(repo_path)
+            .map_err(|e| GitError::NotARepo(e.message().to_string()))?;
+        repo.tag_delete(name).map_err(|e| match e.code() {
+            git2::ErrorCode::NotFound => GitError::InvalidArgument(format!("No such tag: {}", name)),
+            _ => GitError::OperationFailed(e.message().to_string()),
+        })
+    }
+
+    fn tag_info(name: &str, object: &git2::Object) -> Tag {
+        let tag = object.as_tag();
+        let target = object
+            .peel(git2::ObjectType::Commit)
+            .map(|commit| commit.id())
+            .unwrap_or_else(|_| object.id());
+        Tag {
+            name: name.to_string(),
+            target: target.to_string(),
+            annotated: tag.is_some(),
+            message: tag.and_then(|tag| tag.message()).map(|message| message.trim_end().to_string()),
+            tagger: tag.and_then(|tag| tag.tagger()).map(|tagger| tagger.to_string()),
+        }
     }
 
     // --- Write operations using CLI subprocess ---
 
     /// Execute git pull
+    ///
+    /// A rejected login is returned as [`GitError::AuthFailed`]; other
+    /// failures are reported in the output.
     pub fn pull(repo_path: &Path) -> GitResult<CommandOutput> {
-        Self::run_git_command(repo_path, &["pull"])
+        let output = Self::run_remote_git_command(repo_path, &["pull"])?;
+        Self::check_remote_auth(repo_path, None, output)
+    }
+
+    /// Fetch LFS files for the checked-out commit, replacing their pointer stubs
+    ///
+    /// Needs `git-lfs` installed. A rejected login is returned as
+    /// [`GitError::AuthFailed`], other failures as [`GitError::CommandFailed`].
+    pub fn lfs_pull(repo_path: &Path) -> GitResult<()> {
+        let output = Self::run_remote_git_command(repo_path, &["lfs", "pull"])?;
+        Self::check(Self::check_remote_auth(repo_path, None, output)?)
     }
 
     /// Execute git push
+    ///
+    /// A rejected login is returned as [`GitError::AuthFailed`]; other
+    /// failures are reported in the output.
     pub fn push(repo_path: &Path) -> GitResult<CommandOutput> {
-        Self::run_git_command(repo_path, &["push"])
+        let output = Self::run_remote_git_command(repo_path, &["push"])?;
+        Self::check_remote_auth(repo_path, None, output)
+    }
+
+    /// Push a branch and set its upstream, failing if git reports an error
+    pub fn push_branch(repo_path: &Path, remote: &str, branch: &str) -> GitResult<CommandOutput> {
+        let branch = sanitize::branch_name(branch)?;
+        let remote = sanitize::branch_name(remote)?;
+        let output = Self::run_remote_git_command(repo_path, &["push", "-u", remote, branch])?;
+        let output = Self::check_remote_auth(repo_path, Some(remote), output)?;
+        if !output.success {
+            return Err(GitError::CommandFailed(output.stderr.trim().to_string()));
+        }
+        Ok(output)
+    }
+
+    /// Push a tag to a remote, failing if git reports an error
+    pub fn push_tag(repo_path: &Path, remote: &str, name: &str) -> GitResult<CommandOutput> {
+        let name = sanitize::branch_name(name)?;
+        let remote = sanitize::branch_name(remote)?;
+        let refspec = format!("refs/tags/{}", name);
+        let output = Self::run_remote_git_command(repo_path, &["push", remote, &refspec])?;
+        let output = Self::check_remote_auth(repo_path, Some(remote), output)?;
+        if !output.success {
+            return Err(GitError::CommandFailed(output.stderr.trim().to_string()));
+        }
+        Ok(output)
     }
 
     /// Execute git commit with message
     pub fn commit(repo_path: &Path, message: &str) -> GitResult<CommandOutput> {
+        let message = sanitize::commit_message(message)?;
         Self::run_git_command(repo_path, &["commit", "-m", message])
     }
 
@@ -692,11 +2092,388 @@ impl GitManager {
 
     /// Execute git checkout to switch branch
     pub fn checkout(repo_path: &Path, branch: &str) -> GitResult<CommandOutput> {
-        // Validate branch name (basic sanity check)
-        if branch.contains("..") || branch.starts_with('-') || branch.contains('\0') {
-            return Err(GitError::InvalidBranch(branch.to_string()));
+        let branch = sanitize::branch_name(branch)?;
+        // "--" stops git from treating the name as a path if no such branch exists
+        Self::run_git_command(repo_path, &["checkout", branch, "--"])
+    }
+
+    /// Check out `branch` in a new linked worktree at `path` (git worktree add)
+    ///
+    /// A branch that doesn't exist yet is created from `base`, or from HEAD.
+    pub fn worktree_add(repo_path: &Path, path: &Path, branch: &str, base: Option<&str>) -> GitResult<()> {
+        let branch = sanitize::branch_name(branch)?;
+        let base = base.map(sanitize::branch_name).transpose()?;
+        let path = sanitize::repo_path(path)?
+            .to_str()
+            .ok_or_else(|| GitError::InvalidArgument("Worktree path is not valid UTF-8".to_string()))?;
+
+        let repo = git2::Repository::open(repo_path)
+            .map_err(|e| GitError::NotARepo(e.message().to_string()))?;
+        let exists = repo.find_branch(branch, git2::BranchType::Local).is_ok();
+        let args = match (exists, base) {
+            (true, Some(_)) => {
+                return Err(GitError::InvalidArgument(format!(
+                    "Branch {} already exists, so it can't start from another branch",
+                    branch
+                )));
+            }
+            (true, None) => vec!["worktree", "add", path, branch],
+            (false, None) => vec!["worktree", "add", "-b", branch, path],
+            (false, Some(base)) => vec!["worktree", "add", "-b", branch, path, base],
+        };
+        Self::check(Self::run_git_command(repo_path, &args)?)
+    }
+
+    /// Remove the linked worktree at `path` (git worktree remove)
+    ///
+    /// Git refuses to remove a worktree with uncommitted changes unless
+    /// `force` is set. A worktree whose directory is already gone is pruned.
+    pub fn worktree_remove(repo_path: &Path, path: &Path, force: bool) -> GitResult<()> {
+        if !path.exists() {
+            return Self::check(Self::run_git_command(repo_path, &["worktree", "prune"])?);
+        }
+        let path = sanitize::repo_path(path)?
+            .to_str()
+            .ok_or_else(|| GitError::InvalidArgument("Worktree path is not valid UTF-8".to_string()))?;
+        let mut args = vec!["worktree", "remove"];
+        if force {
+            args.push("--force");
+        }
+        args.push(path);
+        Self::check(Self::run_git_command(repo_path, &args)?)
+    }
+
+    /// Merge `branch` into the current branch
+    ///
+    /// Stopping on conflicts isn't an error: the outcome lists the conflicted
+    /// paths. Other failures, e.g. local changes in the way, are.
+    pub fn merge(repo_path: &Path, branch: &str) -> GitResult<MergeOutcome> {
+        let branch = sanitize::branch_name(branch)?;
+        let output = Self::run_git_command(repo_path, &["merge", "--no-edit", branch])?;
+        Self::merge_outcome(repo_path, output)
+    }
+
+    /// Commit a merge whose conflicts have been resolved and staged
+    pub fn merge_continue(repo_path: &Path) -> GitResult<MergeOutcome> {
+        Self::ensure_state(repo_path, "merge", |state| state == git2::RepositoryState::Merge)?;
+        let output = Self::run_git_command(repo_path, &["commit", "--no-edit"])?;
+        Self::merge_outcome(repo_path, output)
+    }
+
+    /// Give up on a merge, restoring the branch as it was
+    pub fn merge_abort(repo_path: &Path) -> GitResult<()> {
+        Self::ensure_state(repo_path, "merge", |state| state == git2::RepositoryState::Merge)?;
+        Self::check(Self::run_git_command(repo_path, &["merge", "--abort"])?)
+    }
+
+    /// Replay the current branch's commits on top of `onto`
+    ///
+    /// Like [`Self::merge`], stopping on conflicts is reported in the outcome.
+    pub fn rebase(repo_path: &Path, onto: &str) -> GitResult<MergeOutcome> {
+        let onto = sanitize::branch_name(onto)?;
+        let output = Self::run_git_command(repo_path, &["rebase", onto])?;
+        Self::merge_outcome(repo_path, output)
+    }
+
+    /// Carry on with a rebase once the current commit's conflicts are
+    /// resolved and staged; it may stop again on a later commit
+    pub fn rebase_continue(repo_path: &Path) -> GitResult<MergeOutcome> {
+        Self::ensure_state(repo_path, "rebase", Self::is_rebasing)?;
+        let output = Self::run_git_command(repo_path, &["rebase", "--continue"])?;
+        Self::merge_outcome(repo_path, output)
+    }
+
+    /// Give up on a rebase, restoring the branch as it was
+    pub fn rebase_abort(repo_path: &Path) -> GitResult<()> {
+        Self::ensure_state(repo_path, "rebase", Self::is_rebasing)?;
+        Self::check(Self::run_git_command(repo_path, &["rebase", "--abort"])?)
+    }
+
+    /// Apply the change `commit` made onto the current branch as a new commit
+    ///
+    /// Like [`Self::merge`], stopping on conflicts is reported in the outcome.
+    /// A cherry-pick that fails for another reason, e.g. because the change
+    /// is already on the branch, is abandoned rather than left in progress.
+    pub fn cherry_pick(repo_path: &Path, commit: &str) -> GitResult<MergeOutcome> {
+        let commit = Self::resolve_commit(repo_path, commit)?;
+        let output = Self::run_git_command(repo_path, &["cherry-pick", &commit])?;
+        let outcome = Self::merge_outcome(repo_path, output);
+        if outcome.is_err() {
+            // Fails harmlessly when git didn't leave one in progress
+            let _ = Self::cherry_pick_abort(repo_path);
+        }
+        outcome
+    }
+
+    /// Commit a cherry-pick once its conflicts are resolved and staged
+    pub fn cherry_pick_continue(repo_path: &Path) -> GitResult<MergeOutcome> {
+        Self::ensure_state(repo_path, "cherry-pick", Self::is_cherry_picking)?;
+        let output = Self::run_git_command(repo_path, &["cherry-pick", "--continue"])?;
+        Self::merge_outcome(repo_path, output)
+    }
+
+    /// Give up on a cherry-pick, restoring the branch as it was
+    pub fn cherry_pick_abort(repo_path: &Path) -> GitResult<()> {
+        Self::ensure_state(repo_path, "cherry-pick", Self::is_cherry_picking)?;
+        Self::check(Self::run_git_command(repo_path, &["cherry-pick", "--abort"])?)
+    }
+
+    fn is_cherry_picking(state: git2::RepositoryState) -> bool {
+        matches!(
+            state,
+            git2::RepositoryState::CherryPick | git2::RepositoryState::CherryPickSequence
+        )
+    }
+
+    /// Undo the change `commit` made with a new commit on the current branch
+    ///
+    /// With `no_commit` the undo is only staged, to be reviewed or combined
+    /// with other changes before committing. Conflicts are reported as for
+    /// [`Self::cherry_pick`], and other failures abandon the revert.
+    pub fn revert(repo_path: &Path, commit: &str, no_commit: bool) -> GitResult<MergeOutcome> {
+        let commit = Self::resolve_commit(repo_path, commit)?;
+        let mode = if no_commit { "--no-commit" } else { "--no-edit" };
+        let output = Self::run_git_command(repo_path, &["revert", mode, &commit])?;
+        let outcome = Self::merge_outcome(repo_path, output);
+        if outcome.is_err() {
+            // Fails harmlessly when git didn't leave one in progress
+            let _ = Self::revert_abort(repo_path);
+        }
+        outcome
+    }
+
+    /// Commit a revert once its conflicts are resolved and staged
+    pub fn revert_continue(repo_path: &Path) -> GitResult<MergeOutcome> {
+        Self::ensure_state(repo_path, "revert", Self::is_reverting)?;
+        let output = Self::run_git_command(repo_path, &["revert", "--continue"])?;
+        Self::merge_outcome(repo_path, output)
+    }
+
+    /// Give up on a revert, restoring the branch as it was
+    pub fn revert_abort(repo_path: &Path) -> GitResult<()> {
+        Self::ensure_state(repo_path, "revert", Self::is_reverting)?;
+        Self::check(Self::run_git_command(repo_path, &["revert", "--abort"])?)
+    }
+
+    fn is_reverting(state: git2::RepositoryState) -> bool {
+        matches!(
+            state,
+            git2::RepositoryState::Revert | git2::RepositoryState::RevertSequence
+        )
+    }
+
+    /// Start `git archive` writing a gzipped tarball of `rev` to its stdout
+    ///
+    /// Returns the commit `rev` resolved to and the running process, whose
+    /// stdout can be streamed as it's produced. Files are placed under
+    /// `prefix/`, and `export-ignore` attributes are honoured. The process is
+    /// killed if it's dropped before finishing.
+    pub fn archive(
+        repo_path: &Path,
+        rev: &str,
+        prefix: &str,
+    ) -> GitResult<(String, tokio::process::Child)> {
+        let repo_path = sanitize::repo_path(repo_path)?;
+        let commit = Self::resolve_commit(repo_path, rev)?;
+        let prefix = format!("--prefix={}/", prefix);
+        let args = ["archive", "--format=tar.gz", &prefix, &commit];
+        let child = tokio::process::Command::new("git")
+            .current_dir(repo_path)
+            .args(sanitize::git_args(&args)?)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| GitError::CommandFailed(format!("Failed to run git archive: {}", e)))?;
+        Ok((commit, child))
+    }
+
+    /// Full id of the commit `rev` names, so git is never handed user input
+    fn resolve_commit(repo_path: &Path, rev: &str) -> GitResult<String> {
+        let repo = git2::Repository::open(repo_path)
+            .map_err(|e| GitError::NotARepo(e.message().to_string()))?;
+        repo.revparse_single(rev)
+            .and_then(|object| object.peel_to_commit())
+            .map(|commit| commit.id().to_string())
+            .map_err(|e| {
+                GitError::InvalidArgument(format!("Unknown commit {}: {}", rev, e.message()))
+            })
+    }
+
+    fn is_rebasing(state: git2::RepositoryState) -> bool {
+        matches!(
+            state,
+            git2::RepositoryState::Rebase
+                | git2::RepositoryState::RebaseInteractive
+                | git2::RepositoryState::RebaseMerge
+                | git2::RepositoryState::ApplyMailboxOrRebase
+        )
+    }
+
+    /// Fail unless the repository is in the middle of `operation`
+    fn ensure_state(
+        repo_path: &Path,
+        operation: &str,
+        in_progress: impl Fn(git2::RepositoryState) -> bool,
+    ) -> GitResult<()> {
+        let repo = git2::Repository::open(repo_path)
+            .map_err(|e| GitError::NotARepo(e.message().to_string()))?;
+        if !in_progress(repo.state()) {
+            return Err(GitError::InvalidArgument(format!("No {} in progress", operation)));
+        }
+        Ok(())
+    }
+
+    /// Conflicted files with the base, our and their versions
+    pub fn conflicts(repo_path: &Path) -> GitResult<Vec<ConflictFile>> {
+        let repo = git2::Repository::open(repo_path)
+            .map_err(|e| GitError::NotARepo(e.message().to_string()))?;
+        let index = repo
+            .index()
+            .map_err(|e| GitError::OperationFailed(e.message().to_string()))?;
+        let conflicts = index
+            .conflicts()
+            .map_err(|e| GitError::OperationFailed(e.message().to_string()))?;
+
+        let mut files = Vec::new();
+        for conflict in conflicts {
+            let conflict = conflict.map_err(|e| GitError::OperationFailed(e.message().to_string()))?;
+            let Some(path) = [&conflict.our, &conflict.their, &conflict.ancestor]
+                .into_iter()
+                .flatten()
+                .next()
+                .map(|entry| String::from_utf8_lossy(&entry.path).to_string())
+            else {
+                continue;
+            };
+
+            let mut binary = false;
+            let mut text = |entry: &Option<git2::IndexEntry>| -> GitResult<Option<String>> {
+                let Some(entry) = entry else {
+                    return Ok(None);
+                };
+                let blob = repo
+                    .find_blob(entry.id)
+                    .map_err(|e| GitError::OperationFailed(e.message().to_string()))?;
+                match std::str::from_utf8(blob.content()) {
+                    Ok(content) if !blob.is_binary() => Ok(Some(content.to_string())),
+                    _ => {
+                        binary = true;
+                        Ok(None)
+                    }
+                }
+            };
+            let base = text(&conflict.ancestor)?;
+            let ours = text(&conflict.our)?;
+            let theirs = text(&conflict.their)?;
+            // With one version binary there's nothing to merge by hand
+            let keep = |content: Option<String>| content.filter(|_| !binary);
+            files.push(ConflictFile {
+                path,
+                base: keep(base),
+                ours: keep(ours),
+                theirs: keep(theirs),
+                binary,
+            });
+        }
+        Ok(files)
+    }
+
+    /// Settle a conflicted file in the working tree and mark it resolved
+    ///
+    /// Keeping a side that deleted the file deletes it.
+    pub fn resolve_conflict(repo_path: &Path, path: &str, resolution: ConflictResolution) -> GitResult<()> {
+        let path = Path::new(sanitize::relative_path(path)?);
+        let repo = git2::Repository::open(repo_path)
+            .map_err(|e| GitError::NotARepo(e.message().to_string()))?;
+        let mut index = repo
+            .index()
+            .map_err(|e| GitError::OperationFailed(e.message().to_string()))?;
+        let conflict = index.conflict_get(path).map_err(|e| match e.code() {
+            git2::ErrorCode::NotFound => {
+                GitError::InvalidArgument(format!("{} has no conflict", path.display()))
+            }
+            _ => GitError::OperationFailed(e.message().to_string()),
+        })?;
+
+        let side = |entry: Option<git2::IndexEntry>| {
+            entry
+                .map(|entry| {
+                    repo.find_blob(entry.id)
+                        .map(|blob| blob.content().to_vec())
+                        .map_err(|e| GitError::OperationFailed(e.message().to_string()))
+                })
+                .transpose()
+        };
+        let content = match resolution {
+            ConflictResolution::Ours => side(conflict.our)?,
+            ConflictResolution::Theirs => side(conflict.their)?,
+            ConflictResolution::Content(content) => Some(content.into_bytes()),
+        };
+
+        let file = repo_path.join(path);
+        let written = match content {
+            Some(content) => std::fs::write(&file, content),
+            None => std::fs::remove_file(&file).or_else(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => Ok(()),
+                _ => Err(e),
+            }),
+        };
+        written.map_err(|e| GitError::OperationFailed(format!("Failed to write {}: {}", path.display(), e)))?;
+
+        // Staging the file, or its removal, clears the conflict
+        let staged = if file.exists() {
+            index.add_path(path)
+        } else {
+            index.remove_path(path)
+        };
+        staged
+            .and_then(|()| index.write())
+            .map_err(|e| GitError::OperationFailed(e.message().to_string()))
+    }
+
+    /// Report a merge-like command: done, stopped on conflicts, or failed
+    fn merge_outcome(repo_path: &Path, output: CommandOutput) -> GitResult<MergeOutcome> {
+        let conflicts = Self::conflicted_paths(repo_path)?;
+        if !output.success && conflicts.is_empty() {
+            return Err(GitError::CommandFailed(output.stderr.trim().to_string()));
+        }
+        Ok(MergeOutcome {
+            completed: output.success && conflicts.is_empty(),
+            conflicts,
+            output,
+        })
+    }
+
+    /// Paths with conflict entries in the index
+    fn conflicted_paths(repo_path: &Path) -> GitResult<Vec<String>> {
+        let repo = git2::Repository::open(repo_path)
+            .map_err(|e| GitError::NotARepo(e.message().to_string()))?;
+        let index = repo
+            .index()
+            .map_err(|e| GitError::OperationFailed(e.message().to_string()))?;
+        let conflicts = index
+            .conflicts()
+            .map_err(|e| GitError::OperationFailed(e.message().to_string()))?;
+
+        let mut paths = Vec::new();
+        for conflict in conflicts {
+            let conflict = conflict.map_err(|e| GitError::OperationFailed(e.message().to_string()))?;
+            if let Some(entry) = conflict.our.or(conflict.their).or(conflict.ancestor) {
+                paths.push(String::from_utf8_lossy(&entry.path).to_string());
+            }
+        }
+        Ok(paths)
+    }
+
+    /// Turn an unsuccessful command into an error carrying what git printed
+    fn check(output: CommandOutput) -> GitResult<()> {
+        if output.success {
+            Ok(())
+        } else {
+            Err(GitError::CommandFailed(output.stderr.trim().to_string()))
         }
-        Self::run_git_command(repo_path, &["checkout", branch])
     }
 
     /// Stage all changes (git add -A)
@@ -706,6 +2483,23 @@ impl GitManager {
 
     // --- Helper methods ---
 
+    fn commit_info(oid: git2::Oid, commit: &git2::Commit) -> Commit {
+        let author = commit.author();
+        let time = commit.time();
+        let timestamp = chrono::DateTime::from_timestamp(time.seconds(), 0)
+            .map(|dt| dt.to_rfc3339())
+            .unwrap_or_default();
+
+        Commit {
+            id: oid.to_string(),
+            short_id: oid.to_string()[..7.min(oid.to_string().len())].to_string(),
+            message: commit.message().unwrap_or("").trim().to_string(),
+            author: author.name().unwrap_or("").to_string(),
+            email: author.email().unwrap_or("").to_string(),
+            timestamp,
+        }
+    }
+
     fn get_current_branch(repo: &git2::Repository) -> GitResult<String> {
         let head = repo
             .head()
@@ -749,9 +2543,72 @@ impl GitManager {
     }
 
     fn run_git_command(repo_path: &Path, args: &[&str]) -> GitResult<CommandOutput> {
-        let output = Command::new("git")
+        let repo_path = sanitize::repo_path(repo_path)?;
+        let args = sanitize::git_args(args)?;
+
+        // Commands that would open an editor, e.g. rebase --continue, keep
+        // the message git prepared
+        Self::output(
+            Command::new("git")
+                .current_dir(repo_path)
+                .args(args)
+                .env("GIT_EDITOR", "true"),
+        )
+    }
+
+    /// Run a git command that talks to a remote
+    ///
+    /// Nobody can answer a prompt from the server, so prompting is turned off
+    /// and `ralphtown credential-helper` is added after any helpers the user
+    /// configured, supplying stored forge tokens for HTTPS remotes.
+    fn run_remote_git_command(repo_path: &Path, args: &[&str]) -> GitResult<CommandOutput> {
+        let repo_path = sanitize::repo_path(repo_path)?;
+        let args = sanitize::git_args(args)?;
+
+        let mut command = Command::new("git");
+        if let Some(helper) = credential_helper() {
+            command.arg("-c").arg(format!("credential.helper={}", helper));
+        }
+        command
             .current_dir(repo_path)
             .args(args)
+            .env("GIT_TERMINAL_PROMPT", "0")
+            .stdin(Stdio::null());
+        Self::output(&mut command)
+    }
+
+    /// Turn a remote command that failed to log in into [`GitError::AuthFailed`]
+    ///
+    /// `remote` defaults to the current branch's upstream remote, or origin;
+    /// its URL decides which HTTPS help steps apply.
+    fn check_remote_auth(
+        repo_path: &Path,
+        remote: Option<&str>,
+        output: CommandOutput,
+    ) -> GitResult<CommandOutput> {
+        if output.success {
+            return Ok(output);
+        }
+        let remote = match remote {
+            Some(remote) => remote.to_string(),
+            None => git2::Repository::open(repo_path)
+                .ok()
+                .and_then(|repo| {
+                    let head = repo.head().ok()?;
+                    let remote = repo.branch_upstream_remote(head.name()?).ok()?;
+                    remote.as_str().map(String::from)
+                })
+                .unwrap_or_else(|| "origin".to_string()),
+        };
+        let url = Self::remote_url(repo_path, &remote).unwrap_or_default();
+        match classify_remote_stderr(&output.stderr, &url) {
+            Some(err) => Err(GitError::AuthFailed(err)),
+            None => Ok(output),
+        }
+    }
+
+    fn output(command: &mut Command) -> GitResult<CommandOutput> {
+        let output = command
             .output()
             .map_err(|e| GitError::CommandFailed(format!("Failed to run git: {}", e)))?;
 
@@ -845,6 +2702,101 @@ mod tests {
         assert_eq!(status.unstaged[0].status, FileStatusType::Modified);
     }
 
+    #[test]
+    fn test_stage_and_unstage() {
+        let (temp_dir, _repo) = create_test_repo();
+        fs::write(temp_dir.path().join("a.txt"), "a").unwrap();
+        fs::create_dir(temp_dir.path().join("src")).unwrap();
+        fs::write(temp_dir.path().join("src/b.txt"), "b").unwrap();
+        fs::write(temp_dir.path().join("c.txt"), "c").unwrap();
+
+        GitManager::stage(temp_dir.path(), &["a.txt".to_string(), "src".to_string()]).unwrap();
+        let status = GitManager::status(temp_dir.path()).unwrap();
+        let mut staged: Vec<_> = status.staged.iter().map(|f| f.path.as_str()).collect();
+        staged.sort();
+        assert_eq!(staged, ["a.txt", "src/b.txt"]);
+        assert_eq!(status.untracked, ["c.txt"]);
+
+        GitManager::unstage(temp_dir.path(), &["a.txt".to_string()]).unwrap();
+        let status = GitManager::status(temp_dir.path()).unwrap();
+        assert_eq!(status.staged.len(), 1);
+        assert_eq!(status.staged[0].path, "src/b.txt");
+
+        assert!(matches!(GitManager::stage(temp_dir.path(), &[]), Err(GitError::InvalidArgument(_))));
+        assert!(matches!(
+            GitManager::unstage(temp_dir.path(), &["/etc/passwd".to_string()]),
+            Err(GitError::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn test_worktree_add_list_remove() {
+        let (temp_dir, _repo) = create_test_repo();
+        let worktrees_dir = TempDir::new().unwrap();
+        let path = worktrees_dir.path().join("feature");
+
+        GitManager::worktree_add(temp_dir.path(), &path, "feature", None).unwrap();
+        assert!(path.join(".git").exists());
+        let worktrees = GitManager::worktrees(temp_dir.path()).unwrap();
+        assert_eq!(worktrees.len(), 1);
+        assert_eq!(worktrees[0].branch.as_deref(), Some("feature"));
+        assert!(!worktrees[0].prunable);
+
+        // A branch can only be checked out once
+        let other = worktrees_dir.path().join("other");
+        assert!(matches!(
+            GitManager::worktree_add(temp_dir.path(), &other, "feature", None),
+            Err(GitError::CommandFailed(_))
+        ));
+        assert!(GitManager::worktree_add(temp_dir.path(), &other, "-x", None).is_err());
+
+        // Uncommitted changes need force
+        fs::write(path.join("wip.txt"), "wip").unwrap();
+        assert!(GitManager::worktree_remove(temp_dir.path(), &path, false).is_err());
+        GitManager::worktree_remove(temp_dir.path(), &path, true).unwrap();
+        assert!(!path.exists());
+        assert!(GitManager::worktrees(temp_dir.path()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_stash_push_pop_drop() {
+        let (temp_dir, repo) = create_test_repo();
+        let file_path = temp_dir.path().join("tracked.txt");
+        fs::write(&file_path, "initial").expect("Failed to write file");
+        let mut index = repo.index().expect("Failed to get index");
+        index.add_path(Path::new("tracked.txt")).expect("Failed to add file");
+        index.write().expect("Failed to write index");
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = repo.signature().unwrap();
+        let parent = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, "Add file", &tree, &[&parent]).unwrap();
+
+        assert!(matches!(
+            GitManager::stash_push(temp_dir.path(), None, false),
+            Err(GitError::InvalidArgument(_))
+        ));
+
+        fs::write(&file_path, "first").unwrap();
+        GitManager::stash_push(temp_dir.path(), Some("first"), false).unwrap();
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "initial");
+        fs::write(&file_path, "second").unwrap();
+        fs::write(temp_dir.path().join("new.txt"), "new").unwrap();
+        let entry = GitManager::stash_push(temp_dir.path(), Some("second"), true).unwrap();
+        assert_eq!(entry.index, 0);
+        assert!(entry.message.contains("second"));
+        assert!(!temp_dir.path().join("new.txt").exists());
+
+        let stashes = GitManager::stash_list(temp_dir.path()).unwrap();
+        assert_eq!(stashes.len(), 2);
+        assert!(stashes[1].message.contains("first"));
+
+        GitManager::stash_pop(temp_dir.path(), 1).unwrap();
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "first");
+        GitManager::stash_drop(temp_dir.path(), 0).unwrap();
+        assert!(GitManager::stash_list(temp_dir.path()).unwrap().is_empty());
+        assert!(matches!(GitManager::stash_drop(temp_dir.path(), 0), Err(GitError::InvalidArgument(_))));
+    }
+
     #[test]
     fn test_log() {
         let (temp_dir, _repo) = create_test_repo();
@@ -855,6 +2807,417 @@ mod tests {
         assert_eq!(commits[0].message, "Initial commit");
     }
 
+    #[test]
+    fn test_log_filtered() {
+        let (temp_dir, repo) = create_test_repo();
+        let commit_file = |name: &str, author: &str, message: &str| {
+            fs::write(temp_dir.path().join(name), message).unwrap();
+            let mut index = repo.index().unwrap();
+            index.add_path(Path::new(name)).unwrap();
+            index.write().unwrap();
+            let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+            let sig = git2::Signature::now(author, &format!("{}@example.com", author.to_lowercase())).unwrap();
+            let parent = repo.head().unwrap().peel_to_commit().unwrap();
+            repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &[&parent]).unwrap();
+        };
+        commit_file("a.txt", "Alice", "a1");
+        commit_file("b.txt", "Bob", "b1");
+        commit_file("a.txt", "Bob", "a2");
+
+        let filter = |rev: Option<&str>, author: Option<&str>, path: Option<&str>| LogFilter {
+            rev: rev.map(String::from),
+            author: author.map(String::from),
+            path: path.map(String::from),
+        };
+        let messages = |filter: LogFilter, skip: usize, limit: usize| {
+            let (commits, total) = GitManager::log_filtered(temp_dir.path(), &filter, skip, limit).unwrap();
+            (commits.into_iter().map(|c| c.message).collect::<Vec<_>>(), total)
+        };
+
+        assert_eq!(messages(filter(None, None, None), 1, 2), (vec!["b1".to_string(), "a1".to_string()], 4));
+        assert_eq!(messages(filter(None, Some("bob"), None), 0, 10).1, 2);
+        assert_eq!(messages(filter(None, None, Some("a.txt")), 0, 1), (vec!["a2".to_string()], 2));
+        assert_eq!(messages(filter(None, Some("BOB@"), Some("a.txt")), 0, 10), (vec!["a2".to_string()], 1));
+        assert_eq!(messages(filter(Some("HEAD~1"), None, None), 0, 1), (vec!["b1".to_string()], 3));
+
+        assert!(matches!(
+            GitManager::log_filtered(temp_dir.path(), &filter(Some("nope"), None, None), 0, 10),
+            Err(GitError::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn test_push_branch_and_commits_since() {
+        let (temp_dir, repo) = create_test_repo();
+        let remote_dir = TempDir::new().expect("Failed to create temp dir");
+        git2::Repository::init_bare(remote_dir.path()).expect("Failed to init bare repo");
+        repo.remote("origin", &remote_dir.path().to_string_lossy())
+            .expect("Failed to add remote");
+
+        let branch = GitManager::current_branch(temp_dir.path()).expect("Failed to get branch");
+        GitManager::push_branch(temp_dir.path(), "origin", &branch).expect("Failed to push");
+
+        // A new commit on top of the pushed branch is the only one ahead of it
+        let sig = repo.signature().expect("Failed to create signature");
+        let parent = repo.head().expect("Failed to get HEAD").peel_to_commit().expect("Failed to peel to commit");
+        let tree = parent.tree().expect("Failed to get tree");
+        repo.commit(Some("HEAD"), &sig, &sig, "Second commit", &tree, &[&parent])
+            .expect("Failed to commit");
+
+        let base = format!("origin/{}", branch);
+        let commits = GitManager::commits_since(temp_dir.path(), &base, 10).expect("Failed to list commits");
+        assert_eq!(commits.len(), 1);
+        assert_eq!(commits[0].message, "Second commit");
+
+        let url = GitManager::remote_url(temp_dir.path(), "origin").expect("Failed to get remote");
+        assert_eq!(url, remote_dir.path().to_string_lossy());
+    }
+
+    #[test]
+    fn test_tags() {
+        let (temp_dir, repo) = create_test_repo();
+        let head = repo.head().unwrap().peel_to_commit().unwrap().id().to_string();
+
+        let light = GitManager::create_tag(temp_dir.path(), "v1.0", None, None).unwrap();
+        assert!(!light.annotated);
+        assert_eq!(light.target, head);
+        let annotated = GitManager::create_tag(temp_dir.path(), "v1.1", Some("HEAD"), Some("Release 1.1\n")).unwrap();
+        assert!(annotated.annotated);
+        assert_eq!(annotated.target, head);
+        assert_eq!(annotated.message.as_deref(), Some("Release 1.1"));
+        assert_eq!(annotated.tagger.as_deref(), Some("Test User <test@example.com>"));
+
+        assert!(matches!(
+            GitManager::create_tag(temp_dir.path(), "v1.0", None, None),
+            Err(GitError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            GitManager::create_tag(temp_dir.path(), "v2", Some("no-such-rev"), None),
+            Err(GitError::InvalidArgument(_))
+        ));
+
+        let names: Vec<_> = GitManager::tags(temp_dir.path()).unwrap().into_iter().map(|t| t.name).collect();
+        assert_eq!(names, ["v1.0", "v1.1"]);
+
+        let remote_dir = TempDir::new().expect("Failed to create temp dir");
+        let remote = git2::Repository::init_bare(remote_dir.path()).expect("Failed to init bare repo");
+        repo.remote("origin", &remote_dir.path().to_string_lossy()).unwrap();
+        GitManager::push_tag(temp_dir.path(), "origin", "v1.1").expect("Failed to push tag");
+        assert!(remote.find_reference("refs/tags/v1.1").is_ok());
+
+        GitManager::delete_tag(temp_dir.path(), "v1.0").unwrap();
+        assert!(matches!(
+            GitManager::delete_tag(temp_dir.path(), "v1.0"),
+            Err(GitError::InvalidArgument(_))
+        ));
+        assert_eq!(GitManager::tags(temp_dir.path()).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_remote_management() {
+        let (temp_dir, _repo) = create_test_repo();
+        let path = temp_dir.path();
+
+        let origin = GitManager::add_remote(path, "origin", "https://example.com/old.git").unwrap();
+        assert_eq!(origin.url.as_deref(), Some("https://example.com/old.git"));
+        assert!(origin.push_url.is_none());
+        GitManager::add_remote(path, "upstream", "git@github.com:owner/repo.git").unwrap();
+        assert!(matches!(
+            GitManager::add_remote(path, "origin", "https://example.com/other.git"),
+            Err(GitError::InvalidArgument(_))
+        ));
+        assert!(GitManager::add_remote(path, "evil", "ext::sh -c id").is_err());
+
+        let origin = GitManager::set_remote_url(path, "origin", "https://example.com/new.git", false).unwrap();
+        assert_eq!(origin.url.as_deref(), Some("https://example.com/new.git"));
+        let origin = GitManager::set_remote_url(path, "origin", "git@example.com:new.git", true).unwrap();
+        assert_eq!(origin.url.as_deref(), Some("https://example.com/new.git"));
+        assert_eq!(origin.push_url.as_deref(), Some("git@example.com:new.git"));
+        assert!(GitManager::set_remote_url(path, "missing", "https://example.com/x.git", false).is_err());
+
+        GitManager::remove_remote(path, "upstream").unwrap();
+        assert!(GitManager::remove_remote(path, "upstream").is_err());
+        let names: Vec<_> = GitManager::remotes(path).unwrap().into_iter().map(|r| r.name).collect();
+        assert_eq!(names, ["origin"]);
+    }
+
+    #[test]
+    fn test_identity() {
+        let (temp_dir, repo) = create_test_repo();
+        let path = temp_dir.path();
+        assert_eq!(GitManager::identity(path).unwrap().name.as_deref(), Some("Test User"));
+
+        let mut identity = GitIdentity {
+            name: Some(" Ralph Bot ".to_string()),
+            email: Some("bot@example.com".to_string()),
+        };
+        let updated = GitManager::set_identity(path, &identity).unwrap();
+        assert_eq!(updated.name.as_deref(), Some("Ralph Bot"));
+        assert_eq!(repo.signature().unwrap().email(), Some("bot@example.com"));
+        identity.email = None;
+        assert!(GitManager::set_identity(path, &identity).unwrap().email.is_none());
+
+        let invalid = GitIdentity {
+            name: Some("Evil <x@y>".to_string()),
+            email: None,
+        };
+        assert!(matches!(
+            GitManager::set_identity(path, &invalid),
+            Err(GitError::InvalidArgument(_))
+        ));
+        assert_eq!(GitManager::identity(path).unwrap().name.as_deref(), Some("Ralph Bot"));
+    }
+
+    #[test]
+    fn test_merge_and_rebase_conflicts() {
+        let (temp_dir, repo) = create_test_repo();
+        let path = temp_dir.path();
+        let commit_file = |content: &str, message: &str| {
+            fs::write(path.join("shared.txt"), content).unwrap();
+            let mut index = repo.index().unwrap();
+            // The git CLI may have changed the index since it was loaded
+            index.read(true).unwrap();
+            index.add_path(Path::new("shared.txt")).unwrap();
+            index.write().unwrap();
+            let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+            let sig = repo.signature().unwrap();
+            let parent = repo.head().unwrap().peel_to_commit().unwrap();
+            repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &[&parent]).unwrap();
+        };
+        let main = GitManager::current_branch(path).unwrap();
+
+        let base = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.branch("feature", &base, false).unwrap();
+        commit_file("main\n", "Main change");
+        GitManager::checkout(path, "feature").unwrap();
+        commit_file("feature\n", "Feature change");
+        GitManager::checkout(path, &main).unwrap();
+
+        assert!(matches!(GitManager::merge_abort(path), Err(GitError::InvalidArgument(_))));
+        let outcome = GitManager::merge(path, "feature").unwrap();
+        assert!(!outcome.completed);
+        assert_eq!(outcome.conflicts, ["shared.txt"]);
+        // Continuing before the conflict is resolved stops again
+        assert_eq!(GitManager::merge_continue(path).unwrap().conflicts, ["shared.txt"]);
+        GitManager::merge_abort(path).unwrap();
+        assert_eq!(repo.state(), git2::RepositoryState::Clean);
+        assert_eq!(fs::read_to_string(path.join("shared.txt")).unwrap(), "main\n");
+
+        GitManager::checkout(path, "feature").unwrap();
+        let outcome = GitManager::rebase(path, &main).unwrap();
+        assert_eq!(outcome.conflicts, ["shared.txt"]);
+        fs::write(path.join("shared.txt"), "both\n").unwrap();
+        GitManager::stage(path, &["shared.txt".to_string()]).unwrap();
+        let outcome = GitManager::rebase_continue(path).unwrap();
+        assert!(outcome.completed);
+        assert!(outcome.conflicts.is_empty());
+        assert_eq!(GitManager::log(path, 10).unwrap()[0].message.trim(), "Feature change");
+        assert!(matches!(GitManager::rebase_abort(path), Err(GitError::InvalidArgument(_))));
+    }
+
+    #[test]
+    fn test_cherry_pick() {
+        let (temp_dir, repo) = create_test_repo();
+        let path = temp_dir.path();
+        let commit_file = |name: &str, content: &str| {
+            fs::write(path.join(name), content).unwrap();
+            let mut index = repo.index().unwrap();
+            index.read(true).unwrap();
+            index.add_path(Path::new(name)).unwrap();
+            index.write().unwrap();
+            let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+            let sig = repo.signature().unwrap();
+            let parent = repo.head().unwrap().peel_to_commit().unwrap();
+            repo.commit(Some("HEAD"), &sig, &sig, name, &tree, &[&parent]).unwrap().to_string()
+        };
+        let main = GitManager::current_branch(path).unwrap();
+        repo.branch("agent", &repo.head().unwrap().peel_to_commit().unwrap(), false)
+            .unwrap();
+        commit_file("shared.txt", "main\n");
+        GitManager::checkout(path, "agent").unwrap();
+        let good = commit_file("good.txt", "good\n");
+        let clashing = commit_file("shared.txt", "agent\n");
+        GitManager::checkout(path, &main).unwrap();
+
+        let outcome = GitManager::cherry_pick(path, &good[..7]).unwrap();
+        assert!(outcome.completed);
+        assert_eq!(fs::read_to_string(path.join("good.txt")).unwrap(), "good\n");
+        assert_eq!(GitManager::log(path, 1).unwrap()[0].message.trim(), "good.txt");
+
+        // Already on the branch, so there's nothing to pick
+        assert!(GitManager::cherry_pick(path, &good).is_err());
+        assert_eq!(repo.state(), git2::RepositoryState::Clean);
+
+        let outcome = GitManager::cherry_pick(path, &clashing).unwrap();
+        assert_eq!(outcome.conflicts, ["shared.txt"]);
+        GitManager::cherry_pick_abort(path).unwrap();
+        assert_eq!(fs::read_to_string(path.join("shared.txt")).unwrap(), "main\n");
+        assert!(matches!(GitManager::cherry_pick(path, "nope"), Err(GitError::InvalidArgument(_))));
+        assert!(matches!(
+            GitManager::cherry_pick_continue(path),
+            Err(GitError::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn test_revert() {
+        let (temp_dir, repo) = create_test_repo();
+        let path = temp_dir.path();
+        let commit_file = |content: &str| {
+            fs::write(path.join("notes.txt"), content).unwrap();
+            let mut index = repo.index().unwrap();
+            index.read(true).unwrap();
+            index.add_path(Path::new("notes.txt")).unwrap();
+            index.write().unwrap();
+            let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+            let sig = repo.signature().unwrap();
+            let parent = repo.head().unwrap().peel_to_commit().unwrap();
+            repo.commit(Some("HEAD"), &sig, &sig, content, &tree, &[&parent]).unwrap().to_string()
+        };
+        let added = commit_file("first\n");
+
+        // Only staged, so HEAD stays where it was
+        let outcome = GitManager::revert(path, &added, true).unwrap();
+        assert!(outcome.completed);
+        assert!(!path.join("notes.txt").exists());
+        assert_eq!(GitManager::log(path, 1).unwrap()[0].id, added);
+        assert_eq!(GitManager::status(path).unwrap().staged.len(), 1);
+        GitManager::reset_hard(path).unwrap();
+
+        let outcome = GitManager::revert(path, &added, false).unwrap();
+        assert!(outcome.completed);
+        assert!(!path.join("notes.txt").exists());
+        assert!(GitManager::log(path, 1).unwrap()[0].message.starts_with("Revert"));
+        assert_eq!(repo.state(), git2::RepositoryState::Clean);
+
+        // Undoing a change later commits build on conflicts
+        let edited = commit_file("second\n");
+        commit_file("third\n");
+        let outcome = GitManager::revert(path, &edited, false).unwrap();
+        assert_eq!(outcome.conflicts, ["notes.txt"]);
+        GitManager::revert_abort(path).unwrap();
+        assert_eq!(fs::read_to_string(path.join("notes.txt")).unwrap(), "third\n");
+        assert!(matches!(
+            GitManager::revert(path, "nope", false),
+            Err(GitError::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn test_resolve_conflicts() {
+        let (temp_dir, repo) = create_test_repo();
+        let path = temp_dir.path();
+        let commit_files = |content: &str, message: &str| {
+            let mut index = repo.index().unwrap();
+            index.read(true).unwrap();
+            for name in ["a.txt", "b.txt"] {
+                fs::write(path.join(name), content).unwrap();
+                index.add_path(Path::new(name)).unwrap();
+            }
+            index.write().unwrap();
+            let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+            let sig = repo.signature().unwrap();
+            let parent = repo.head().unwrap().peel_to_commit().unwrap();
+            repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &[&parent]).unwrap();
+        };
+        let main = GitManager::current_branch(path).unwrap();
+        commit_files("base\n", "Base");
+        repo.branch("feature", &repo.head().unwrap().peel_to_commit().unwrap(), false)
+            .unwrap();
+        commit_files("ours\n", "Ours");
+        GitManager::checkout(path, "feature").unwrap();
+        commit_files("theirs\n", "Theirs");
+        GitManager::checkout(path, &main).unwrap();
+        assert_eq!(GitManager::merge(path, "feature").unwrap().conflicts, ["a.txt", "b.txt"]);
+
+        let conflicts = GitManager::conflicts(path).unwrap();
+        assert_eq!(conflicts.len(), 2);
+        assert_eq!(conflicts[0].path, "a.txt");
+        assert_eq!(conflicts[0].base.as_deref(), Some("base\n"));
+        assert_eq!(conflicts[0].ours.as_deref(), Some("ours\n"));
+        assert_eq!(conflicts[0].theirs.as_deref(), Some("theirs\n"));
+        assert!(!conflicts[0].binary);
+
+        GitManager::resolve_conflict(path, "a.txt", ConflictResolution::Theirs).unwrap();
+        assert_eq!(fs::read_to_string(path.join("a.txt")).unwrap(), "theirs\n");
+        GitManager::resolve_conflict(path, "b.txt", ConflictResolution::Content("both\n".to_string())).unwrap();
+        assert_eq!(fs::read_to_string(path.join("b.txt")).unwrap(), "both\n");
+        assert!(GitManager::conflicts(path).unwrap().is_empty());
+        assert!(matches!(
+            GitManager::resolve_conflict(path, "a.txt", ConflictResolution::Ours),
+            Err(GitError::InvalidArgument(_))
+        ));
+
+        assert!(GitManager::merge_continue(path).unwrap().completed);
+    }
+
+    #[test]
+    fn test_show() {
+        let (temp_dir, repo) = create_test_repo();
+        let path = temp_dir.path();
+        let first = repo.head().unwrap().peel_to_commit().unwrap().id().to_string();
+        fs::create_dir(path.join("src")).unwrap();
+        fs::write(path.join("src/lib.rs"), "fn main() {}\n").unwrap();
+        GitManager::stage(path, &["src".to_string()]).unwrap();
+        GitManager::commit(path, "Add lib").unwrap();
+        fs::write(path.join("src/lib.rs"), "changed\n").unwrap();
+
+        let file = GitManager::show(path, "src/lib.rs", None).unwrap();
+        assert_eq!(file.content.as_deref(), Some("fn main() {}\n"));
+        assert_eq!(file.size, 13);
+        assert!(!file.binary);
+        assert_ne!(file.commit, first);
+
+        assert!(matches!(
+            GitManager::show(path, "src/lib.rs", Some(&first)),
+            Err(GitError::InvalidArgument(_))
+        ));
+        assert!(matches!(GitManager::show(path, "src", None), Err(GitError::InvalidArgument(_))));
+        assert!(matches!(
+            GitManager::show(path, "src/lib.rs", Some("no-such-rev")),
+            Err(GitError::InvalidArgument(_))
+        ));
+        assert!(GitManager::show(path, "../etc/passwd", None).is_err());
+    }
+
+    #[test]
+    fn test_tree() {
+        let (temp_dir, _repo) = create_test_repo();
+        let path = temp_dir.path();
+        fs::create_dir(path.join("src")).unwrap();
+        fs::write(path.join("src/lib.rs"), "fn main() {}\n").unwrap();
+        fs::write(path.join(".gitignore"), "target/\n").unwrap();
+        GitManager::stage(path, &["src".to_string(), ".gitignore".to_string()]).unwrap();
+        GitManager::commit(path, "Add lib").unwrap();
+        fs::write(path.join("notes.txt"), "untracked").unwrap();
+        fs::create_dir(path.join("target")).unwrap();
+
+        let paths = |rev: Option<&str>| {
+            let entries = GitManager::tree(path, rev, None).unwrap();
+            entries.into_iter().map(|entry| entry.path).collect::<Vec<_>>()
+        };
+        // Ignored files aren't listed
+        assert_eq!(paths(None), ["src", ".gitignore", "notes.txt"]);
+        assert_eq!(paths(Some("HEAD")), ["src", ".gitignore"]);
+
+        let entries = GitManager::tree(path, Some("HEAD"), Some("src/")).unwrap();
+        assert_eq!(entries[0].path, "src/lib.rs");
+        assert_eq!(entries[0].kind, TreeEntryKind::File);
+        assert_eq!(entries[0].size, Some(13));
+        assert_eq!(entries[0].mode, "100644");
+        assert_eq!(GitManager::tree(path, None, Some("src")).unwrap()[0].size, Some(13));
+
+        assert!(matches!(
+            GitManager::tree(path, Some("HEAD"), Some("src/lib.rs")),
+            Err(GitError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            GitManager::tree(path, None, Some("missing")),
+            Err(GitError::InvalidArgument(_))
+        ));
+        assert!(GitManager::tree(path, None, Some("../")).is_err());
+    }
+
     #[test]
     fn test_branches() {
         let (temp_dir, _repo) = create_test_repo();
@@ -908,6 +3271,39 @@ mod tests {
         assert!(delta.added > 0 || delta.removed > 0);
     }
 
+    #[test]
+    fn test_diff_file() {
+        let (temp_dir, repo) = create_test_repo();
+        let file_path = temp_dir.path().join("lib.rs");
+        fs::write(&file_path, "one\ntwo\nthree\n").unwrap();
+
+        // Untracked files are entirely added
+        let diff = GitManager::diff_file(temp_dir.path(), "lib.rs", false).unwrap();
+        assert_eq!(diff.hunks.len(), 1);
+        assert_eq!(diff.hunks[0].lines.len(), 3);
+        assert!(diff.hunks[0].lines.iter().all(|l| l.kind == DiffLineKind::Added));
+
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("lib.rs")).unwrap();
+        index.write().unwrap();
+        assert!(GitManager::diff_file(temp_dir.path(), "lib.rs", false).unwrap().hunks.is_empty());
+        assert_eq!(GitManager::diff_file(temp_dir.path(), "lib.rs", true).unwrap().hunks.len(), 1);
+
+        fs::write(&file_path, "one\n2\nthree\n").unwrap();
+        let diff = GitManager::diff_file(temp_dir.path(), "lib.rs", false).unwrap();
+        let lines = &diff.hunks[0].lines;
+        assert_eq!(diff.hunks[0].header, "@@ -1,3 +1,3 @@");
+        assert_eq!(lines.len(), 4);
+        assert_eq!((lines[1].kind, lines[1].old_lineno, lines[1].new_lineno), (DiffLineKind::Removed, Some(2), None));
+        assert_eq!((lines[2].kind, lines[2].content.as_str()), (DiffLineKind::Added, "2"));
+        assert_eq!((lines[2].old_lineno, lines[2].new_lineno), (None, Some(2)));
+
+        assert!(matches!(
+            GitManager::diff_file(temp_dir.path(), "../lib.rs", false),
+            Err(GitError::InvalidArgument(_))
+        ));
+    }
+
     #[test]
     fn test_not_a_repo() {
         let temp_dir = TempDir::new().expect("Failed to create temp dir");
@@ -987,6 +3383,154 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_clone_with_options() {
+        let (source_dir, source_repo) = create_test_repo();
+        let head = source_repo.head().unwrap().peel_to_commit().unwrap();
+        source_repo.branch("feature", &head, false).expect("Failed to create branch");
+        let url = format!("file://{}", source_dir.path().display());
+        let dest_dir = TempDir::new().expect("Failed to create dest temp dir");
+
+        let options = CloneOptions {
+            branch: Some("feature".to_string()),
+            ..Default::default()
+        };
+        let cloned = GitManager::clone_with_options(&url, &dest_dir.path().join("feature"), &options)
+            .expect("Clone should succeed");
+        assert_eq!(cloned.head().unwrap().shorthand(), Some("feature"));
+
+        let missing = CloneOptions {
+            branch: Some("missing".to_string()),
+            ..Default::default()
+        };
+        assert!(GitManager::clone_with_options(&url, &dest_dir.path().join("missing"), &missing).is_err());
+
+        // Bad options are refused before anything is created
+        let shallowest = CloneOptions {
+            depth: Some(0),
+            ..Default::default()
+        };
+        assert!(matches!(shallowest.validate(), Err(GitError::InvalidArgument(_))));
+        let dest = dest_dir.path().join("zero");
+        assert!(GitManager::clone_with_options(&url, &dest, &shallowest).is_err());
+        assert!(!dest.exists());
+    }
+
+    #[test]
+    fn test_health() {
+        let (temp_dir, repo) = create_test_repo();
+        let health = GitManager::health(temp_dir.path());
+        assert!(health.missing_remote);
+        assert!(!health.healthy);
+        assert!(!health.dirty && !health.detached_head && !health.stale_path);
+
+        repo.remote("origin", "https://example.com/repo.git").unwrap();
+        assert!(GitManager::health(temp_dir.path()).healthy);
+
+        fs::write(temp_dir.path().join("new.txt"), "new").unwrap();
+        let head = repo.head().unwrap().target().unwrap();
+        repo.set_head_detached(head).unwrap();
+        let health = GitManager::health(temp_dir.path());
+        assert!(health.dirty && health.detached_head);
+        assert_eq!(health.warnings.len(), 2);
+
+        let gone = temp_dir.path().join("missing");
+        let health = GitManager::health(&gone);
+        assert!(health.stale_path && !health.healthy);
+    }
+
+    #[test]
+    fn test_lfs_detection() {
+        let (temp_dir, _repo) = create_test_repo();
+        assert!(!GitManager::lfs_required(temp_dir.path()).unwrap());
+
+        fs::write(temp_dir.path().join(".gitattributes"), "# *.bin filter=lfs\n*.txt text\n").unwrap();
+        assert!(!GitManager::status(temp_dir.path()).unwrap().lfs_required);
+
+        fs::write(
+            temp_dir.path().join(".gitattributes"),
+            "*.psd filter=lfs diff=lfs merge=lfs -text\n",
+        )
+        .unwrap();
+        assert!(GitManager::lfs_required(temp_dir.path()).unwrap());
+        assert!(GitManager::status(temp_dir.path()).unwrap().lfs_required);
+    }
+
+    #[test]
+    fn test_clone_cancelled() {
+        let (source_dir, _source_repo) = create_test_repo();
+        let dest_dir = TempDir::new().expect("Failed to create dest temp dir");
+        let clone_dest = dest_dir.path().join("cloned-repo");
+
+        let cancel = CancelToken::new();
+        cancel.cancel();
+        let (tx, _rx) = mpsc::channel(32);
+        let result = GitManager::clone_with_progress(
+            &format!("file://{}", source_dir.path().display()),
+            &clone_dest,
+            &CloneOptions::default(),
+            tx,
+            &cancel,
+        );
+        assert!(matches!(result, Err(CloneError::Cancelled)));
+        assert!(!clone_dest.exists());
+    }
+
+    #[test]
+    fn test_fetch_with_progress() {
+        let (source_dir, source_repo) = create_test_repo();
+        let dest_dir = TempDir::new().expect("Failed to create dest temp dir");
+        let clone_dest = dest_dir.path().join("cloned-repo");
+        GitManager::clone(&format!("file://{}", source_dir.path().display()), &clone_dest)
+            .expect("Clone should succeed");
+
+        // A commit the clone doesn't have yet
+        let sig = source_repo.signature().expect("Failed to create signature");
+        let parent = source_repo.head().unwrap().peel_to_commit().unwrap();
+        let new_head = source_repo
+            .commit(Some("HEAD"), &sig, &sig, "Second commit", &parent.tree().unwrap(), &[&parent])
+            .expect("Failed to commit");
+        let branch = source_repo.head().unwrap().shorthand().unwrap().to_string();
+
+        let (tx, _rx) = mpsc::channel(32);
+        GitManager::fetch_with_progress(&clone_dest, "origin", None, tx).expect("Fetch should succeed");
+
+        let cloned = git2::Repository::open(&clone_dest).unwrap();
+        let fetched = cloned.refname_to_id(&format!("refs/remotes/origin/{}", branch)).unwrap();
+        assert_eq!(fetched, new_head);
+
+        assert!(matches!(
+            GitManager::fetch_with_progress(&clone_dest, "upstream", None, mpsc::channel(1).0),
+            Err(CloneError::OperationFailed { .. })
+        ));
+    }
+
+    #[test]
+    fn test_classify_remote_stderr() {
+        let ssh = "git@github.com: Permission denied (publickey).\nfatal: Could not read from remote repository.\n";
+        match classify_remote_stderr(ssh, "git@github.com:owner/repo.git") {
+            Some(CloneError::SshAuthFailed { message, help_steps, .. }) => {
+                assert_eq!(message, "git@github.com: Permission denied (publickey).");
+                assert!(!help_steps.is_empty());
+            }
+            other => panic!("Expected SSH auth failure, got {:?}", other),
+        }
+
+        let https = "fatal: could not read Username for 'https://github.com': terminal prompts disabled\n";
+        match classify_remote_stderr(https, "https://github.com/owner/repo.git") {
+            Some(CloneError::HttpsAuthFailed { is_github, .. }) => assert!(is_github),
+            other => panic!("Expected HTTPS auth failure, got {:?}", other),
+        }
+
+        let rejected = " ! [rejected]        main -> main (fetch first)\nerror: failed to push some refs\n";
+        assert!(classify_remote_stderr(rejected, "https://github.com/owner/repo.git").is_none());
+
+        // Failures other than logging in still come back as output
+        let (temp_dir, _repo) = create_test_repo();
+        let output = GitManager::push(temp_dir.path()).expect("Push should report its failure");
+        assert!(!output.success);
+    }
+
     #[test]
     fn test_validate_repo_path_nonexistent() {
         let result = validate_repo_path(Path::new("/nonexistent/path/to/repo"));
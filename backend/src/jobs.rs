@@ -0,0 +1,268 @@
+//! Background jobs
+//!
+//! Long operations such as clones and directory scans run as jobs: the
+//! request that starts one returns right away, and the job's state, progress,
+//! and result are kept in the `jobs` table for clients to poll. A few jobs run
+//! at a time and the rest wait their turn. Cancelling a queued job drops it; a
+//! running job is asked to stop and does so at its next checkpoint.
+//!
+//! Jobs don't survive a restart: any left queued or running are marked failed
+//! when the server starts.
+
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tokio::sync::Semaphore;
+use uuid::Uuid;
+
+use crate::db::models::{Job, JobKind, JobState};
+use crate::db::{Database, DbResult};
+
+/// Jobs run at the same time; the rest stay queued
+pub const MAX_RUNNING_JOBS: usize = 2;
+
+/// Least time between progress writes to the database
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How a job's work ended other than successfully
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobError {
+    Failed(String),
+    /// The job noticed a cancel request and stopped
+    Cancelled,
+}
+
+impl From<String> for JobError {
+    fn from(message: String) -> Self {
+        JobError::Failed(message)
+    }
+}
+
+/// Handle a running job's work uses to report progress and check for cancellation
+pub struct JobContext {
+    id: Uuid,
+    db: Arc<Database>,
+    last_progress: Mutex<Option<Instant>>,
+}
+
+impl JobContext {
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    /// Record progress; updates closer together than a quarter second are dropped
+    pub async fn progress(&self, fraction: Option<f64>, message: &str) {
+        {
+            let mut last = self.last_progress.lock().unwrap();
+            if last.is_some_and(|last| last.elapsed() < PROGRESS_INTERVAL) {
+                return;
+            }
+            *last = Some(Instant::now());
+        }
+
+        let (id, message) = (self.id, message.to_string());
+        let fraction = fraction.map(|fraction| fraction.clamp(0.0, 1.0));
+        let updated = self
+            .db
+            .call(move |db| db.update_job_progress(id, fraction, Some(&message)))
+            .await;
+        if let Err(e) = updated {
+            tracing::warn!("Failed to record progress of job {}: {}", self.id, e);
+        }
+    }
+
+    /// Whether someone asked for the job to stop
+    pub async fn is_cancelled(&self) -> bool {
+        let id = self.id;
+        self.db
+            .call(move |db| db.get_job(id))
+            .await
+            .map(|job| job.cancel_requested)
+            .unwrap_or(false)
+    }
+}
+
+/// Runs jobs in the background, a few at a time
+#[derive(Clone)]
+pub struct JobQueue {
+    db: Arc<Database>,
+    slots: Arc<Semaphore>,
+}
+
+impl JobQueue {
+    pub fn new(db: Arc<Database>) -> Self {
+        Self {
+            db,
+            slots: Arc::new(Semaphore::new(MAX_RUNNING_JOBS)),
+        }
+    }
+
+    /// Fail jobs a previous run of the server left unfinished
+    pub async fn recover(&self) {
+        let failed = self
+            .db
+            .call(|db| db.fail_unfinished_jobs("The server restarted before the job finished"))
+            .await;
+        match failed {
+            Ok(0) => {}
+            Ok(count) => tracing::info!("Marked {} interrupted jobs as failed", count),
+            Err(e) => tracing::warn!("Failed to clean up interrupted jobs: {}", e),
+        }
+    }
+
+    /// Queue `work`, returning the job as queued
+    pub async fn submit<F, Fut>(
+        &self,
+        kind: JobKind,
+        owner_id: Option<Uuid>,
+        description: &str,
+        work: F,
+    ) -> DbResult<Job>
+    where
+        F: FnOnce(JobContext) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<serde_json::Value, JobError>> + Send + 'static,
+    {
+        let description = description.to_string();
+        let job = self
+            .db
+            .call(move |db| db.insert_job(kind, owner_id, &description))
+            .await?;
+        let id = job.id;
+        let db = self.db.clone();
+        let slots = self.slots.clone();
+
+        tokio::spawn(async move {
+            let Ok(_slot) = slots.acquire_owned().await else {
+                return;
+            };
+            match db.call(move |db| db.start_job(id)).await {
+                Ok(true) => {}
+                // Cancelled while it waited
+                Ok(false) => return,
+                Err(e) => {
+                    tracing::warn!("Failed to start job {}: {}", id, e);
+                    return;
+                }
+            }
+
+            let context = JobContext {
+                id,
+                db: db.clone(),
+                last_progress: Mutex::new(None),
+            };
+            // Run the work as its own task so a panic still finishes the job
+            let outcome = tokio::spawn(work(context))
+                .await
+                .unwrap_or_else(|e| Err(JobError::Failed(format!("Job panicked: {}", e))));
+
+            let finished = db
+                .call(move |db| match outcome {
+                    Ok(result) => db.finish_job(id, JobState::Done, Some(&result), None),
+                    Err(JobError::Failed(error)) => {
+                        tracing::warn!("Job {} failed: {}", id, error);
+                        db.finish_job(id, JobState::Failed, None, Some(&error))
+                    }
+                    Err(JobError::Cancelled) => db.finish_job(id, JobState::Cancelled, None, None),
+                })
+                .await;
+            if let Err(e) = finished {
+                tracing::warn!("Failed to record outcome of job {}: {}", id, e);
+            }
+        });
+
+        Ok(job)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn wait_until_finished(db: &Database, id: Uuid) -> Job {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                let job = db.get_job(id).unwrap();
+                if job.state.is_finished() {
+                    return job;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("job did not finish")
+    }
+
+    #[tokio::test]
+    async fn test_jobs_run_and_record_outcomes() {
+        let db = Arc::new(Database::in_memory().unwrap());
+        let queue = JobQueue::new(db.clone());
+
+        let done = queue
+            .submit(JobKind::Scan, None, "scan", |context| async move {
+                context.progress(Some(2.0), "almost").await;
+                Ok(serde_json::json!({ "found": 3 }))
+            })
+            .await
+            .unwrap();
+        assert_eq!(done.state, JobState::Queued);
+        let failed = queue
+            .submit(JobKind::Scan, None, "scan", |_| async { Err(JobError::from("boom".to_string())) })
+            .await
+            .unwrap();
+
+        let done = wait_until_finished(&db, done.id).await;
+        assert_eq!(done.state, JobState::Done);
+        assert_eq!(done.result, Some(serde_json::json!({ "found": 3 })));
+        assert_eq!(done.progress, Some(1.0));
+        assert!(done.started_at.is_some() && done.finished_at.is_some());
+
+        let failed = wait_until_finished(&db, failed.id).await;
+        assert_eq!(failed.state, JobState::Failed);
+        assert_eq!(failed.error.as_deref(), Some("boom"));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_queued_and_running_jobs() {
+        let db = Arc::new(Database::in_memory().unwrap());
+        let queue = JobQueue::new(db.clone());
+
+        // Fill every slot with jobs that wait to be cancelled
+        let mut running = Vec::new();
+        for _ in 0..MAX_RUNNING_JOBS {
+            let job = queue
+                .submit(JobKind::Scan, None, "wait", |context| async move {
+                    while !context.is_cancelled().await {
+                        tokio::time::sleep(Duration::from_millis(10)).await;
+                    }
+                    Err(JobError::Cancelled)
+                })
+                .await
+                .unwrap();
+            running.push(job);
+        }
+        let queued = queue
+            .submit(JobKind::Scan, None, "never runs", |_| async { Ok(serde_json::Value::Null) })
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        assert_eq!(db.cancel_job(queued.id).unwrap().state, JobState::Cancelled);
+        for job in &running {
+            let job = db.cancel_job(job.id).unwrap();
+            assert_eq!(job.state, JobState::Running);
+            assert!(job.cancel_requested);
+        }
+        for job in running {
+            assert_eq!(wait_until_finished(&db, job.id).await.state, JobState::Cancelled);
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let queued = db.get_job(queued.id).unwrap();
+        assert_eq!((queued.state, queued.started_at), (JobState::Cancelled, None));
+
+        // Anything still unfinished at startup is failed
+        let stale = db.insert_job(JobKind::Clone, None, "stale").unwrap();
+        queue.recover().await;
+        assert_eq!(db.get_job(stale.id).unwrap().state, JobState::Failed);
+    }
+}
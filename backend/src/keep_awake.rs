@@ -0,0 +1,184 @@
+//! Keeping the machine awake during runs
+//!
+//! While any session is running, the server holds a sleep inhibitor so a
+//! laptop left alone overnight doesn't suspend in the middle of a run. The
+//! inhibitor is taken when the first run starts and let go when the last one
+//! ends:
+//! - macOS: a `caffeinate -i` power assertion tied to the server's pid
+//! - Linux: a `systemd-inhibit` sleep/idle lock
+//! - Windows: `SetThreadExecutionState` on a thread that lives as long as
+//!   the inhibitor
+//!
+//! Displays may still turn off; only system sleep is prevented. When the
+//! platform tool isn't available runs go ahead without it.
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+use uuid::Uuid;
+
+/// Holds a sleep inhibitor while sessions are running
+#[derive(Clone, Default)]
+pub struct KeepAwake {
+    inner: Arc<Mutex<Inner>>,
+}
+
+#[derive(Default)]
+struct Inner {
+    /// Sessions currently running
+    running: HashSet<Uuid>,
+    inhibitor: Option<Inhibitor>,
+}
+
+impl KeepAwake {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep the machine awake for a session's run
+    pub fn hold(&self, session_id: Uuid) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.running.insert(session_id);
+        if inner.inhibitor.is_none() {
+            inner.inhibitor = Inhibitor::start();
+            if inner.inhibitor.is_some() {
+                tracing::info!("Preventing system sleep while sessions run");
+            }
+        }
+    }
+
+    /// A session's run ended; let the machine sleep once no run is left
+    pub fn release(&self, session_id: Uuid) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.running.remove(&session_id);
+        if inner.running.is_empty() && inner.inhibitor.take().is_some() {
+            tracing::info!("No sessions running; allowing system sleep again");
+        }
+    }
+
+    /// Number of runs the machine is being kept awake for
+    pub fn running(&self) -> usize {
+        self.inner.lock().unwrap().running.len()
+    }
+}
+
+/// A platform sleep inhibitor, released when dropped
+enum Inhibitor {
+    /// A helper process holding the inhibitor until it's killed
+    #[cfg_attr(target_os = "windows", allow(dead_code))]
+    Process(std::process::Child),
+    /// A thread holding the execution state until the sender is dropped
+    #[cfg(target_os = "windows")]
+    Thread(std::sync::mpsc::Sender<()>),
+}
+
+impl Inhibitor {
+    #[cfg(target_os = "macos")]
+    fn start() -> Option<Self> {
+        // `-w` ends the assertion if the server dies without releasing it
+        let mut cmd = std::process::Command::new("caffeinate");
+        cmd.arg("-i").arg("-w").arg(std::process::id().to_string());
+        Self::spawn(cmd)
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    fn start() -> Option<Self> {
+        // `tail --pid` ends the lock if the server dies without releasing it
+        let mut cmd = std::process::Command::new("systemd-inhibit");
+        cmd.args([
+            "--what=sleep:idle",
+            "--who=Ralphtown",
+            "--why=Sessions are running",
+            "--mode=block",
+            "tail",
+            "-f",
+            "/dev/null",
+            "--pid",
+        ])
+        .arg(std::process::id().to_string());
+        Self::spawn(cmd)
+    }
+
+    #[cfg(target_os = "windows")]
+    fn start() -> Option<Self> {
+        const ES_CONTINUOUS: u32 = 0x8000_0000;
+        const ES_SYSTEM_REQUIRED: u32 = 0x0000_0001;
+
+        #[link(name = "kernel32")]
+        unsafe extern "system" {
+            fn SetThreadExecutionState(flags: u32) -> u32;
+        }
+
+        // The execution state belongs to the thread that set it, so a
+        // dedicated thread holds it until told to stop
+        let (stop, stopped) = std::sync::mpsc::channel::<()>();
+        let spawned = std::thread::Builder::new()
+            .name("keep-awake".to_string())
+            .spawn(move || {
+                // SAFETY: SetThreadExecutionState only reads its flags argument
+                unsafe { SetThreadExecutionState(ES_CONTINUOUS | ES_SYSTEM_REQUIRED) };
+                // Returns once the sender is dropped
+                let _ = stopped.recv();
+                // SAFETY: as above
+                unsafe { SetThreadExecutionState(ES_CONTINUOUS) };
+            });
+        match spawned {
+            Ok(_) => Some(Inhibitor::Thread(stop)),
+            Err(e) => {
+                tracing::warn!("Failed to prevent system sleep: {}", e);
+                None
+            }
+        }
+    }
+
+    #[cfg_attr(target_os = "windows", allow(dead_code))]
+    fn spawn(mut cmd: std::process::Command) -> Option<Self> {
+        cmd.stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null());
+        match cmd.spawn() {
+            Ok(child) => Some(Inhibitor::Process(child)),
+            Err(e) => {
+                tracing::warn!("Failed to prevent system sleep: {}", e);
+                None
+            }
+        }
+    }
+}
+
+impl Drop for Inhibitor {
+    fn drop(&mut self) {
+        match self {
+            Inhibitor::Process(child) => {
+                let _ = child.kill();
+                let _ = child.wait();
+            }
+            // Dropping the sender wakes the thread, which restores the state
+            #[cfg(target_os = "windows")]
+            Inhibitor::Thread(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_held_until_the_last_run_ends() {
+        let keep_awake = KeepAwake::new();
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+
+        keep_awake.hold(a);
+        keep_awake.hold(a);
+        keep_awake.hold(b);
+        assert_eq!(keep_awake.running(), 2);
+
+        keep_awake.release(a);
+        assert_eq!(keep_awake.running(), 1);
+        keep_awake.release(b);
+        keep_awake.release(b);
+        assert_eq!(keep_awake.running(), 0);
+        assert!(keep_awake.inner.lock().unwrap().inhibitor.is_none());
+    }
+}
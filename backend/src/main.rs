@@ -1,21 +1,59 @@
+pub mod activity;
+pub mod analytics;
+pub mod artifacts;
+pub mod attachments;
 mod assets;
 pub mod api;
+pub mod bundle;
+pub mod cron;
 pub mod db;
+pub mod discovery;
 mod error;
+pub mod failure;
+pub mod forge;
 pub mod git;
+pub mod jobs;
+pub mod keep_awake;
+pub mod maintenance;
+pub mod mcp;
+mod middleware;
+pub mod notify;
+pub mod orchestrator;
+pub mod push;
 pub mod ralph;
+pub mod readiness;
+pub mod repo_sync;
+pub mod runtime;
+pub mod sanitize;
 pub mod service;
+pub mod settings;
+pub mod snapshot;
+pub mod storage;
+pub mod templates;
+pub mod terminal;
+pub mod tls;
+pub mod uploads;
+pub mod users;
+pub mod webhooks;
+pub mod workers;
 pub mod ws;
 
-use axum::{routing::get, Json, Router};
-use clap::{Parser, Subcommand};
+use axum::{
+    extract::DefaultBodyLimit,
+    middleware::{from_fn, from_fn_with_state},
+    routing::get,
+    Json, Router,
+};
+use clap::{Args, Parser, Subcommand};
+use std::net::{IpAddr, SocketAddr};
 use serde::{Deserialize, Serialize};
-use tower_http::cors::{Any, CorsLayer};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 use api::AppState;
 use db::Database;
+use runtime::{ListenAddr, RuntimeState};
 use service::ServiceController;
+use settings::Settings;
 
 pub use error::{AppError, AppResult};
 pub use ralph::RalphManager;
@@ -34,7 +72,7 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Start the Ralphtown server (default if no command given)
-    Serve,
+    Serve(ServeArgs),
 
     /// Install Ralphtown as a system service
     Install,
@@ -50,8 +88,110 @@ enum Commands {
 
     /// Show the current service status
     Status,
+
+    /// Open the running server in a browser
+    Open,
+
+    /// Find Ralphtown servers on the local network
+    Discover(DiscoverArgs),
+
+    /// Serve MCP over stdio by forwarding to a running Ralphtown server
+    Mcp(McpArgs),
+
+    /// Run sessions assigned by a Ralphtown server on this machine
+    Worker(WorkerArgs),
+
+    /// Answer git credential requests from stored forge tokens (run by git)
+    #[command(hide = true)]
+    CredentialHelper(CredentialHelperArgs),
+}
+
+#[derive(Args, Default)]
+struct ServeArgs {
+    /// Addresses to listen on, as IP or IP:PORT; repeat or separate with
+    /// commas for several. Anything other than loopback requires `auth_token`
+    #[arg(long, value_name = "ADDR", value_delimiter = ',')]
+    listen: Vec<ListenAddr>,
+
+    /// Port to listen on (overrides the `port` config key)
+    #[arg(long)]
+    port: Option<u16>,
+
+    /// Use the next free port if the port is taken (overrides the `port_fallback` config key)
+    #[arg(long, value_name = "BOOL", num_args = 0..=1, default_missing_value = "true")]
+    port_fallback: Option<bool>,
+
+    /// Reject all mutating requests (overrides the `read_only` config key)
+    #[arg(long, value_name = "BOOL", num_args = 0..=1, default_missing_value = "true")]
+    read_only: Option<bool>,
+
+    /// Proxy non-API requests to a frontend dev server (e.g. http://localhost:5173)
+    #[arg(long, value_name = "URL", value_parser = parse_http_url)]
+    dev_frontend: Option<String>,
+}
+
+#[derive(Args)]
+struct DiscoverArgs {
+    /// Seconds to listen for servers
+    #[arg(long, value_name = "SECS", default_value_t = 3)]
+    timeout: u64,
+}
+
+#[derive(Args)]
+struct McpArgs {
+    /// Base URL of the Ralphtown server, including any base path
+    #[arg(long, value_name = "URL", default_value = "http://127.0.0.1:3000", value_parser = parse_http_url)]
+    url: String,
+
+    /// API token, if the server requires one (defaults to $RALPHTOWN_TOKEN)
+    #[arg(long, value_name = "TOKEN")]
+    token: Option<String>,
+
+    /// Accept the server's certificate even if it's self-signed
+    #[arg(long)]
+    insecure: bool,
+}
+
+#[derive(Args)]
+struct WorkerArgs {
+    /// Base URL of the Ralphtown server, including any base path
+    #[arg(long, value_name = "URL", value_parser = parse_http_url)]
+    server: String,
+
+    /// Administrator API token, if the server requires one (defaults to $RALPHTOWN_TOKEN)
+    #[arg(long, value_name = "TOKEN")]
+    token: Option<String>,
+
+    /// Name shown in the server's worker list (defaults to the host name)
+    #[arg(long)]
+    name: Option<String>,
+
+    /// Directory holding this machine's clones (defaults to ~/ralphtown)
+    #[arg(long, value_name = "DIR")]
+    repos_dir: Option<std::path::PathBuf>,
+
+    /// Accept the server's certificate even if it's self-signed
+    #[arg(long)]
+    insecure: bool,
+}
+
+#[derive(Args)]
+struct CredentialHelperArgs {
+    /// Operation requested by git: `get`, `store`, or `erase`
+    operation: String,
+}
+
+/// Accept an http(s) base URL, without a trailing slash
+fn parse_http_url(value: &str) -> Result<String, String> {
+    if !(value.starts_with("http://") || value.starts_with("https://")) {
+        return Err("must be an http:// or https:// URL".to_string());
+    }
+    Ok(value.trim_end_matches('/').to_string())
 }
 
+/// How long open requests get to finish after a shutdown signal
+const SHUTDOWN_GRACE: std::time::Duration = std::time::Duration::from_secs(10);
+
 #[derive(Serialize, Deserialize)]
 pub struct HealthResponse {
     pub status: String,
@@ -64,22 +204,77 @@ async fn health_check() -> Json<HealthResponse> {
 }
 
 pub fn create_app(state: AppState) -> Router {
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
+    let cors = middleware::cors::cors_layer(&state.settings.cors);
+    let base_path = state.settings.base_path.clone();
+    let body_limit = state.settings.body_limits.request_bytes;
 
-    Router::new()
+    let app = Router::new()
         .route("/api/health", get(health_check))
+        .nest("/api", api::health::router())
         .nest("/api", api::repos::router())
+        .nest("/api", api::templates::router())
         .nest("/api", api::sessions::router())
+        .nest("/api", api::compare::router())
+        .nest("/api", api::bundles::router())
+        .nest("/api", api::prompts::router())
+        .nest("/api", api::activity::router())
+        .nest("/api", api::search::router())
+        .nest("/api", api::analytics::router())
+        .nest("/api", api::artifacts::router())
+        .nest("/api", api::uploads::router())
+        .nest("/api", api::attachments::router())
+        .nest("/api", api::bookmarks::router())
         .nest("/api", api::git::router())
+        .nest("/api", api::worktrees::router())
+        .nest("/api", api::jobs::router())
+        .nest("/api", api::forge::router())
         .nest("/api", api::config::router())
         .nest("/api", api::service::router())
+        .nest("/api", api::auth::router())
+        .nest("/api", api::batch::router())
+        .nest("/api", api::users::router())
+        .nest("/api", api::webhooks::router())
+        .nest("/api", api::triggers::router())
+        .nest("/api", api::schedules::router())
+        .nest("/api", api::snapshots::router())
+        .nest("/api", api::storage::router())
+        .nest("/api", api::mcp::router())
+        .nest("/api", api::terminals::router())
+        .nest("/api", api::admin::router())
+        .nest("/api", api::workers::router())
         .nest("/api", ws::router())
-        .with_state(state)
+        .route_layer(from_fn_with_state(
+            state.clone(),
+            middleware::rate_limit::limit_expensive,
+        ))
+        .route_layer(from_fn_with_state(
+            state.clone(),
+            middleware::read_only::reject_mutations,
+        ))
+        .route_layer(from_fn(middleware::auth::require_write_access))
         .fallback(assets::serve_frontend)
+        // Routes that take large bodies set their own limit
+        .layer(DefaultBodyLimit::max(body_limit))
+        .with_state(state.clone())
+        .layer(from_fn(middleware::csrf::verify_csrf))
+        .layer(from_fn_with_state(
+            state.clone(),
+            middleware::auth::require_auth,
+        ))
+        .layer(from_fn_with_state(
+            state.clone(),
+            middleware::exposure::tag_exposed,
+        ))
+        .layer(from_fn_with_state(
+            state,
+            middleware::cors::reject_credentialed_cross_origin,
+        ))
         .layer(cors)
+        .layer(middleware::compression::compression_layer());
+
+    let app = middleware::api_version::mount(app);
+    let app = api::batch::mount(app);
+    middleware::base_path::mount(&base_path, app)
 }
 
 /// Create app with in-memory database (for testing)
@@ -91,39 +286,382 @@ pub fn create_test_app() -> Router {
 
 #[tokio::main]
 async fn main() {
+    let cli = Cli::parse();
+
+    // The MCP bridge and credential helper reply on stdout, so they log to stderr
+    let stdio = matches!(
+        cli.command,
+        Some(Commands::Mcp(_) | Commands::CredentialHelper(_))
+    );
     tracing_subscriber::registry()
-        .with(tracing_subscriber::fmt::layer())
+        .with((!stdio).then(tracing_subscriber::fmt::layer))
+        .with(stdio.then(|| tracing_subscriber::fmt::layer().with_writer(std::io::stderr)))
         .init();
 
-    let cli = Cli::parse();
-
-    match cli.command.unwrap_or(Commands::Serve) {
-        Commands::Serve => run_server().await,
+    match cli
+        .command
+        .unwrap_or_else(|| Commands::Serve(ServeArgs::default()))
+    {
+        Commands::Serve(args) => run_server(args).await,
         Commands::Install => handle_install(),
         Commands::Uninstall => handle_uninstall(),
         Commands::Start => handle_start(),
         Commands::Stop => handle_stop(),
-        Commands::Status => handle_status(),
+        Commands::Status => handle_status().await,
+        Commands::Open => handle_open().await,
+        Commands::Discover(args) => handle_discover(args).await,
+        Commands::Mcp(args) => run_mcp(args).await,
+        Commands::Worker(args) => run_worker(args).await,
+        Commands::CredentialHelper(args) => run_credential_helper(args),
+    }
+}
+
+/// Reply to a git credential request
+///
+/// Only `get` is answered; tokens are managed through the config API, so
+/// `store` and `erase` are ignored. Any failure leaves the reply empty, which
+/// lets git fall through to its next helper or fail.
+fn run_credential_helper(args: CredentialHelperArgs) {
+    use std::io::{Read, Write};
+
+    if args.operation != "get" {
+        return;
+    }
+    let mut input = String::new();
+    if std::io::stdin().read_to_string(&mut input).is_err() {
+        return;
+    }
+    let Some(db_path) = Database::default_path().ok().filter(|path| path.exists()) else {
+        return;
+    };
+    let request = forge::credentials::CredentialRequest::parse(&input);
+    let credential = Database::new(db_path)
+        .map_err(|e| e.to_string())
+        .and_then(|db| forge::credentials::lookup(&db, &request).map_err(|e| e.to_string()));
+    match credential {
+        Ok(Some(credential)) => {
+            let _ = std::io::stdout().write_all(credential.to_helper_output().as_bytes());
+        }
+        Ok(None) => {}
+        Err(e) => tracing::warn!("Failed to look up git credentials: {}", e),
     }
 }
 
-async fn run_server() {
+async fn run_mcp(args: McpArgs) {
+    let token = args
+        .token
+        .or_else(|| std::env::var("RALPHTOWN_TOKEN").ok())
+        .filter(|token| !token.is_empty());
+
+    if let Err(e) = mcp::run_stdio(&args.url, token.as_deref(), args.insecure).await {
+        eprintln!("✗ {}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn run_worker(args: WorkerArgs) {
+    let token = args
+        .token
+        .or_else(|| std::env::var("RALPHTOWN_TOKEN").ok())
+        .filter(|token| !token.is_empty());
+    let name = args
+        .name
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "worker".to_string());
+    let Some(repos_dir) = args.repos_dir.or(settings::StorageSettings::default().clone_root) else {
+        eprintln!("✗ Could not determine home directory; pass --repos-dir");
+        std::process::exit(1);
+    };
+
+    let options = workers::client::WorkerOptions {
+        server: args.server,
+        token,
+        name,
+        repos_dir,
+        insecure: args.insecure,
+    };
+    if let Err(e) = workers::client::run(options).await {
+        eprintln!("✗ {}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn run_server(args: ServeArgs) {
     // Initialize database
     let db_path = Database::default_path().expect("Failed to determine database path");
     tracing::info!("Using database at: {:?}", db_path);
 
+    let data_dir = db_path.parent().map(|dir| dir.to_path_buf());
     let db = Database::new(db_path).expect("Failed to initialize database");
-    let state = AppState::new(db);
+    let mut settings = match Settings::load(&db) {
+        Ok(settings) => settings,
+        Err(e) => {
+            eprintln!("✗ Invalid settings: {}", e);
+            std::process::exit(1);
+        }
+    };
+    if !args.listen.is_empty() {
+        settings.listen_addrs = args.listen;
+    }
+    if let Some(port) = args.port {
+        settings.port = port;
+    }
+    if let Some(port_fallback) = args.port_fallback {
+        settings.port_fallback = port_fallback;
+    }
+    if let Some(read_only) = args.read_only {
+        settings.read_only = read_only;
+    }
+    settings.dev_frontend = args.dev_frontend;
+    settings.data_dir = data_dir;
+
+    // Refuse to expose an unauthenticated server to the network
+    if settings.is_exposed() && !settings.auth.is_enabled() {
+        let exposed: Vec<String> = settings
+            .listen_addrs
+            .iter()
+            .filter(|addr| !addr.ip.is_loopback())
+            .map(|addr| addr.to_string())
+            .collect();
+        eprintln!(
+            "✗ Refusing to listen on {} without authentication",
+            exposed.join(", ")
+        );
+        eprintln!(
+            "  Set an auth token first: PUT /api/config/{} with at least {} characters",
+            settings::AUTH_TOKEN_KEY,
+            settings::MIN_AUTH_TOKEN_LEN
+        );
+        std::process::exit(1);
+    }
 
-    let app = create_app(state);
+    let tls_settings = settings.tls.clone();
+    let listeners = match runtime::bind_all(&settings.listen_addrs, settings.port, settings.port_fallback) {
+        Ok(listeners) => listeners,
+        Err((addr, e)) => {
+            eprintln!("✗ Failed to listen on {}: {}", addr, e);
+            if e.kind() == std::io::ErrorKind::AddrInUse && !settings.port_fallback {
+                eprintln!("  Pick another port with --port, or pass --port-fallback to use the next free one");
+            }
+            std::process::exit(1);
+        }
+    };
+    let addrs: Vec<SocketAddr> = listeners
+        .iter()
+        .map(|listener| listener.local_addr().expect("Bound listener has an address"))
+        .collect();
+    let data_dir = settings.data_dir.clone();
+    let exposed = settings.is_exposed();
+    let mdns = settings.mdns;
+    let base_path = settings.base_path.clone();
+    if settings.read_only {
+        tracing::info!("Read-only mode enabled; mutating requests will be rejected");
+    }
+    if let Some(dev_frontend) = &settings.dev_frontend {
+        tracing::info!("Proxying frontend requests to {}", dev_frontend);
+    }
+    let state = AppState::with_settings(db, settings);
+    // Jobs and sessions left unfinished by the last run are cleaned up before
+    // any request can queue new ones
+    state.jobs.recover().await;
+    match state.db.call(|db| db.reset_queued_sessions()).await {
+        Ok(0) => {}
+        Ok(count) => tracing::info!("Returned {} queued sessions to idle", count),
+        Err(e) => tracing::warn!("Failed to clean up queued sessions: {}", e),
+    }
+    let orphaned = state.ralph_manager.reconcile_orphans(&state.db).await;
+    if orphaned > 0 {
+        tracing::info!("Marked {} sessions left running by the last run as failed", orphaned);
+    }
+    let readiness = state.readiness.clone();
+
+    let app = create_app(state.clone());
+    let scheme = if tls_settings.enabled { "https" } else { "http" };
+
+    // Let phones and other machines on the network find the server through
+    // its first network address; the advertisement lasts as long as this
+    // function keeps it
+    let advertised = addrs.iter().find(|addr| !addr.ip().is_loopback()).filter(|_| mdns);
+    let _advertisement = advertised.and_then(|addr| {
+        discovery::advertise(*addr, tls_settings.enabled, &base_path)
+            .inspect_err(|e| tracing::warn!("Failed to advertise on the local network: {}", e))
+            .ok()
+    });
+
+    // Tell `status`, `open` and the health check where the server really is
+    if let Some(data_dir) = &data_dir {
+        let runtime_state = RuntimeState::new(&addrs, tls_settings.enabled, &base_path);
+        if let Err(e) = runtime_state.write(data_dir) {
+            tracing::warn!("Failed to write the runtime state file: {}", e);
+        }
+    }
 
-    let listener = tokio::net::TcpListener::bind("127.0.0.1:3000")
-        .await
-        .unwrap();
+    // Flipped to true when a shutdown signal arrives; every listener drains
+    let (stop, stopped) = tokio::sync::watch::channel(false);
+    let mut servers = tokio::task::JoinSet::new();
+    if tls_settings.enabled {
+        let tls_config = match tls::resolve_paths(&tls_settings) {
+            Ok(paths) => {
+                tracing::info!("Using TLS certificate at: {:?}", paths.cert);
+                tls::load_config(&paths).await
+            }
+            Err(e) => Err(e),
+        };
+        let tls_config = match tls_config {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("✗ Failed to set up TLS: {}", e);
+                std::process::exit(1);
+            }
+        };
+
+        for (listener, addr) in listeners.into_iter().zip(&addrs) {
+            tracing::info!("Ralphtown server listening on https://{}{}/", addr, base_path);
+            let handle = axum_server::Handle::new();
+            let mut stopped = stopped.clone();
+            let stopper = handle.clone();
+            tokio::spawn(async move {
+                let _ = stopped.wait_for(|stop| *stop).await;
+                stopper.graceful_shutdown(Some(SHUTDOWN_GRACE));
+            });
+            let server = axum_server::from_tcp_rustls(listener, tls_config.clone())
+                .handle(handle)
+                .serve(app.clone().into_make_service_with_connect_info::<SocketAddr>());
+            servers.spawn(server);
+        }
+    } else {
+        for (listener, addr) in listeners.into_iter().zip(&addrs) {
+            listener
+                .set_nonblocking(true)
+                .expect("Failed to make the listener non-blocking");
+            let listener = tokio::net::TcpListener::from_std(listener).unwrap();
+
+            tracing::info!("Ralphtown server listening on http://{}{}/", addr, base_path);
+            let mut stopped = stopped.clone();
+            let server = axum::serve(
+                listener,
+                app.clone().into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .with_graceful_shutdown(async move {
+                let _ = stopped.wait_for(|stop| *stop).await;
+            });
+            servers.spawn(server.into_future());
+        }
+    }
+    readiness.listeners_bound();
+    print_listening(scheme, &addrs, exposed, &base_path);
+
+    state.maintenance.start();
+    api::schedules::start(state.clone());
+    readiness.recovery_finished();
+
+    // Serve until a listener fails or a shutdown signal arrives
+    tokio::select! {
+        Some(result) = servers.join_next() => {
+            if let Err(e) = result.expect("Server task panicked") {
+                eprintln!("✗ Server failed: {}", e);
+                std::process::exit(1);
+            }
+        }
+        _ = shutdown_signal() => {}
+    }
 
-    tracing::info!("Ralphtown server listening on http://127.0.0.1:3000");
+    // Fail readiness first so load balancers stop sending traffic, then stop
+    // the runs while their subscribers are still connected, then give open
+    // requests a moment to finish
+    readiness.shutting_down();
+    tracing::info!("Stopping runs; waiting up to {}s for them to exit", SHUTDOWN_GRACE.as_secs());
+    let stopped_runs = state
+        .ralph_manager
+        .shutdown(&state.db, &state.connections, SHUTDOWN_GRACE)
+        .await;
+    if stopped_runs > 0 {
+        tracing::info!("Stopped {} runs", stopped_runs);
+    }
+    tracing::info!("Shutting down; waiting up to {}s for open requests", SHUTDOWN_GRACE.as_secs());
+    let _ = stop.send(true);
+    let drained = tokio::time::timeout(SHUTDOWN_GRACE, async {
+        while servers.join_next().await.is_some() {}
+    })
+    .await;
+    if drained.is_err() {
+        tracing::warn!("Closing connections still open after {}s", SHUTDOWN_GRACE.as_secs());
+    }
+}
 
-    axum::serve(listener, app).await.unwrap();
+/// Wait for Ctrl-C, or SIGTERM from a service manager or container runtime
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::warn!("Failed to listen for Ctrl-C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::warn!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => tracing::info!("Received Ctrl-C"),
+        _ = terminate => tracing::info!("Received SIGTERM"),
+    }
+}
+
+/// Print the URLs an exposed server can be reached at
+fn print_listening(scheme: &str, addrs: &[SocketAddr], exposed: bool, base_path: &str) {
+    if !exposed {
+        return;
+    }
+    println!("⚠ Ralphtown is reachable from the network (authentication required)");
+    for addr in addrs {
+        if addr.ip().is_loopback() {
+            println!("  {}://{}{}/", scheme, addr, base_path);
+        } else {
+            print_exposed_urls(scheme, *addr, base_path);
+        }
+    }
+}
+
+/// Print the URLs other machines can use to reach an exposed address
+fn print_exposed_urls(scheme: &str, addr: SocketAddr, base_path: &str) {
+    let ips: Vec<IpAddr> = if addr.ip().is_unspecified() {
+        match if_addrs::get_if_addrs() {
+            Ok(ifaces) => ifaces
+                .iter()
+                .filter(|iface| !iface.is_loopback())
+                .map(|iface| iface.ip())
+                .filter(|ip| ip.is_ipv4() == addr.ip().is_ipv4())
+                .collect(),
+            Err(e) => {
+                tracing::warn!("Failed to list network interfaces: {}", e);
+                Vec::new()
+            }
+        }
+    } else {
+        vec![addr.ip()]
+    };
+
+    for ip in ips {
+        println!(
+            "  {}://{}{}/",
+            scheme,
+            SocketAddr::new(ip, addr.port()),
+            base_path
+        );
+    }
 }
 
 fn handle_install() {
@@ -154,12 +692,18 @@ fn handle_uninstall() {
     }
 }
 
+/// Where the server last said it was running, if it's running
+fn runtime_state() -> Option<RuntimeState> {
+    let db_path = Database::default_path().ok()?;
+    RuntimeState::read(db_path.parent()?)
+}
+
 fn handle_start() {
     let controller = ServiceController::new();
     match controller.start() {
         Ok(()) => {
             println!("✓ Ralphtown service started");
-            println!("  Server available at http://127.0.0.1:3000");
+            println!("  Run 'ralphtown status' to see where it's listening");
         }
         Err(e) => {
             eprintln!("✗ Failed to start service: {}", e);
@@ -181,14 +725,26 @@ fn handle_stop() {
     }
 }
 
-fn handle_status() {
+async fn handle_status() {
     let controller = ServiceController::new();
     let status = controller.status();
 
     match status {
         ServiceStatus::Running => {
             println!("● Ralphtown service is running");
-            println!("  Server available at http://127.0.0.1:3000");
+            match runtime_state() {
+                Some(state) if state.is_healthy().await => {
+                    println!("  Server available at:");
+                    for url in &state.urls {
+                        println!("    {}", url);
+                    }
+                }
+                Some(state) => {
+                    println!("  Server is not responding at {}", state.url);
+                    println!("  It may still be starting; check the service logs if it doesn't come up");
+                }
+                None => println!("  Server available at {}", runtime::default_url()),
+            }
         }
         ServiceStatus::Stopped => {
             println!("○ Ralphtown service is stopped");
@@ -204,6 +760,64 @@ fn handle_status() {
     }
 }
 
+async fn handle_open() {
+    let url = match runtime_state() {
+        Some(state) if state.is_healthy().await => state.url,
+        Some(state) => {
+            eprintln!("✗ Ralphtown is not responding at {}", state.url);
+            eprintln!("  Run 'ralphtown start' or 'ralphtown serve' first");
+            std::process::exit(1);
+        }
+        None => runtime::default_url(),
+    };
+
+    #[cfg(target_os = "macos")]
+    let result = std::process::Command::new("open").arg(&url).status();
+    #[cfg(target_os = "windows")]
+    let result = std::process::Command::new("cmd")
+        .args(["/C", "start", "", &url])
+        .status();
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let result = std::process::Command::new("xdg-open").arg(&url).status();
+
+    match result {
+        Ok(status) if status.success() => println!("✓ Opened {}", url),
+        _ => {
+            eprintln!("✗ Could not open a browser");
+            eprintln!("  Ralphtown is at {}", url);
+            std::process::exit(1);
+        }
+    }
+}
+
+async fn handle_discover(args: DiscoverArgs) {
+    let timeout = std::time::Duration::from_secs(args.timeout);
+    let found = tokio::task::spawn_blocking(move || discovery::discover(timeout)).await;
+    let instances = match found {
+        Ok(Ok(instances)) => instances,
+        Ok(Err(e)) => {
+            eprintln!("✗ Failed to search the network: {}", e);
+            std::process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("✗ Discovery task failed: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if instances.is_empty() {
+        println!("○ No Ralphtown servers found on the local network");
+        println!("  Servers are advertised when started with --listen on a network address");
+        return;
+    }
+    for instance in instances {
+        println!("● {}", instance.name);
+        for url in &instance.urls {
+            println!("  {}", url);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
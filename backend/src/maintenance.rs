@@ -0,0 +1,471 @@
+//! Background maintenance
+//!
+//! Housekeeping runs as a set of named jobs, each on its own interval:
+//!
+//! - `retention`: deletes finished webhook deliveries and workspace snapshots
+//!   older than `retention_days`
+//! - `output_retention`: deletes session output older than
+//!   `output_retention_days` or beyond `output_max_lines` per session; off
+//!   until one of them is set
+//! - `temp_cleanup`: removes snapshot directories left behind by interrupted
+//!   captures or by deleting a repository
+//! - `repo_validation`: checks that every registered repository still exists
+//!   and is a git repository
+//! - `aggregate_refresh`: folds new activity and output into the analytics
+//!   totals (see [`crate::analytics`])
+//! - `auto_fetch`: fetches every repository's `origin` and updates its
+//!   ahead/behind counts (see [`crate::repo_sync`]); off unless given an
+//!   interval
+//!
+//! Jobs run on the blocking pool; a scheduled run that overruns its interval
+//! delays the next one rather than overlapping it. The outcome of each job's
+//! latest run is kept in memory for `GET /api/admin/jobs`.
+
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::analytics;
+use crate::db::models::PrunedOutput;
+use crate::db::Database;
+use crate::repo_sync::{self, RepoSyncCache};
+use crate::settings::MaintenanceSettings;
+use crate::snapshot::SnapshotStore;
+use crate::ws::ConnectionManager;
+
+pub const RETENTION: &str = "retention";
+pub const OUTPUT_RETENTION: &str = "output_retention";
+pub const TEMP_CLEANUP: &str = "temp_cleanup";
+pub const REPO_VALIDATION: &str = "repo_validation";
+pub const AGGREGATE_REFRESH: &str = "aggregate_refresh";
+pub const AUTO_FETCH: &str = "auto_fetch";
+
+/// Every standard job, in the order they are listed
+pub const JOB_NAMES: [&str; 6] = [
+    RETENTION,
+    OUTPUT_RETENTION,
+    TEMP_CLEANUP,
+    REPO_VALIDATION,
+    AGGREGATE_REFRESH,
+    AUTO_FETCH,
+];
+
+/// Snapshot directories this recent are left alone, since a capture may still be writing them
+const ORPHAN_GRACE: Duration = Duration::from_secs(60 * 60);
+
+/// Work done by a job, returning a short summary of what it did
+type JobFn = Arc<dyn Fn() -> Result<String, String> + Send + Sync>;
+
+/// How a job's run ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobOutcome {
+    Succeeded,
+    Failed,
+}
+
+/// A job and its latest run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobStatus {
+    pub name: String,
+    pub description: String,
+    /// Seconds between runs; `None` when the job is turned off
+    pub interval_secs: Option<u64>,
+    pub running: bool,
+    pub runs: u64,
+    pub failures: u64,
+    pub last_started_at: Option<DateTime<Utc>>,
+    pub last_finished_at: Option<DateTime<Utc>>,
+    pub last_duration_ms: Option<u64>,
+    pub last_outcome: Option<JobOutcome>,
+    /// Summary of the last run, or why it failed
+    pub last_message: Option<String>,
+    pub next_run_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone)]
+struct Job {
+    name: &'static str,
+    interval: Duration,
+    run: JobFn,
+}
+
+/// Runs maintenance jobs on their intervals
+#[derive(Clone, Default)]
+pub struct Scheduler {
+    jobs: Vec<Job>,
+    statuses: Arc<Mutex<Vec<JobStatus>>>,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The standard jobs, with intervals overridden from settings
+    ///
+    /// `auto_fetch` records counts in `repo_sync` and announces changes on
+    /// the global channel of `connections`.
+    pub fn standard(
+        db: Arc<Database>,
+        snapshots: Option<SnapshotStore>,
+        repo_sync: RepoSyncCache,
+        connections: ConnectionManager,
+        settings: &MaintenanceSettings,
+    ) -> Self {
+        let interval = |name: &str, default: Duration| {
+            settings.intervals.get(name).copied().unwrap_or(default)
+        };
+        let retention = settings.retention;
+        let output_retention = settings.output_retention;
+        let output_max_lines = settings.output_max_lines;
+
+        let mut scheduler = Self::new();
+        {
+            let (db, snapshots) = (db.clone(), snapshots.clone());
+            scheduler = scheduler.with_job(
+                RETENTION,
+                "Delete old webhook deliveries and snapshots",
+                interval(RETENTION, Duration::from_secs(6 * 60 * 60)),
+                move || match retention {
+                    Some(retention) => prune_expired(&db, snapshots.as_ref(), retention),
+                    None => Ok("Retention is off; nothing deleted".to_string()),
+                },
+            );
+        }
+        {
+            let db = db.clone();
+            scheduler = scheduler.with_job(
+                OUTPUT_RETENTION,
+                "Delete old session output",
+                interval(OUTPUT_RETENTION, Duration::from_secs(60 * 60)),
+                move || {
+                    if output_retention.is_none() && output_max_lines.is_none() {
+                        return Ok("Output retention is off; nothing deleted".to_string());
+                    }
+                    let pruned = prune_output(&db, output_retention, output_max_lines)?;
+                    Ok(format!(
+                        "Deleted {} output lines, freeing {} bytes for reuse",
+                        pruned.deleted, pruned.bytes_freed
+                    ))
+                },
+            );
+        }
+        {
+            let db = db.clone();
+            scheduler = scheduler.with_job(
+                TEMP_CLEANUP,
+                "Remove orphaned snapshot files",
+                interval(TEMP_CLEANUP, Duration::from_secs(60 * 60)),
+                move || match &snapshots {
+                    Some(snapshots) => remove_orphaned_snapshots(&db, snapshots),
+                    None => Ok("Snapshots are not enabled".to_string()),
+                },
+            );
+        }
+        {
+            let db = db.clone();
+            scheduler = scheduler.with_job(
+                REPO_VALIDATION,
+                "Check that registered repositories still exist",
+                interval(REPO_VALIDATION, Duration::from_secs(30 * 60)),
+                move || validate_repos(&db),
+            );
+        }
+        {
+            let db = db.clone();
+            scheduler = scheduler.with_job(
+                AGGREGATE_REFRESH,
+                "Update the analytics daily totals",
+                interval(AGGREGATE_REFRESH, analytics::REFRESH_INTERVAL),
+                move || {
+                    db.refresh_daily_stats()
+                        .map(|()| "Daily totals are up to date".to_string())
+                        .map_err(|e| e.to_string())
+                },
+            );
+        }
+        scheduler.with_job(
+            AUTO_FETCH,
+            "Fetch repositories and update their ahead/behind counts",
+            interval(AUTO_FETCH, Duration::ZERO),
+            move || repo_sync::fetch_all(&db, &repo_sync, &connections),
+        )
+    }
+
+    /// Add a job; a zero interval registers it without ever running it
+    pub fn with_job(
+        mut self,
+        name: &'static str,
+        description: &str,
+        interval: Duration,
+        run: impl Fn() -> Result<String, String> + Send + Sync + 'static,
+    ) -> Self {
+        self.statuses.lock().unwrap().push(JobStatus {
+            name: name.to_string(),
+            description: description.to_string(),
+            interval_secs: (!interval.is_zero()).then_some(interval.as_secs()),
+            running: false,
+            runs: 0,
+            failures: 0,
+            last_started_at: None,
+            last_finished_at: None,
+            last_duration_ms: None,
+            last_outcome: None,
+            last_message: None,
+            next_run_at: None,
+        });
+        self.jobs.push(Job {
+            name,
+            interval,
+            run: Arc::new(run),
+        });
+        self
+    }
+
+    /// Run every enabled job in the background, starting now
+    pub fn start(&self) {
+        for job in self.jobs.iter().filter(|job| !job.interval.is_zero()) {
+            let scheduler = self.clone();
+            let job = job.clone();
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(job.interval);
+                interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+                loop {
+                    interval.tick().await;
+                    scheduler.run_job(&job).await;
+                }
+            });
+        }
+    }
+
+    /// Run a job once right away, returning its status afterwards
+    pub async fn run(&self, name: &str) -> Option<JobStatus> {
+        let job = self.jobs.iter().find(|job| job.name == name)?.clone();
+        self.run_job(&job).await;
+        self.status(name)
+    }
+
+    /// Every job's status, in the order they were added
+    pub fn statuses(&self) -> Vec<JobStatus> {
+        self.statuses.lock().unwrap().clone()
+    }
+
+    fn status(&self, name: &str) -> Option<JobStatus> {
+        self.statuses
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|status| status.name == name)
+            .cloned()
+    }
+
+    fn update(&self, name: &str, update: impl FnOnce(&mut JobStatus)) {
+        if let Some(status) = self
+            .statuses
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .find(|status| status.name == name)
+        {
+            update(status);
+        }
+    }
+
+    async fn run_job(&self, job: &Job) {
+        let started_at = Utc::now();
+        self.update(job.name, |status| {
+            status.running = true;
+            status.last_started_at = Some(started_at);
+        });
+
+        let started = Instant::now();
+        let run = job.run.clone();
+        let result = tokio::task::spawn_blocking(move || run())
+            .await
+            .unwrap_or_else(|e| Err(format!("Job panicked: {}", e)));
+        let elapsed = started.elapsed();
+
+        match &result {
+            Ok(message) => tracing::debug!("Maintenance job {}: {}", job.name, message),
+            Err(e) => tracing::warn!("Maintenance job {} failed: {}", job.name, e),
+        }
+        let interval = job.interval;
+        self.update(job.name, |status| {
+            status.running = false;
+            status.runs += 1;
+            status.last_finished_at = Some(Utc::now());
+            status.last_duration_ms = Some(elapsed.as_millis() as u64);
+            status.next_run_at = (!interval.is_zero())
+                .then(|| chrono::Duration::from_std(interval).ok())
+                .flatten()
+                .map(|interval| started_at + interval);
+            match result {
+                Ok(message) => {
+                    status.last_outcome = Some(JobOutcome::Succeeded);
+                    status.last_message = Some(message);
+                }
+                Err(message) => {
+                    status.failures += 1;
+                    status.last_outcome = Some(JobOutcome::Failed);
+                    status.last_message = Some(message);
+                }
+            }
+        });
+    }
+}
+
+/// When things kept for `retention` were written, at the latest, to be deleted now
+pub fn cutoff(retention: Duration) -> Result<DateTime<Utc>, String> {
+    chrono::Duration::from_std(retention)
+        .ok()
+        .and_then(|retention| Utc::now().checked_sub_signed(retention))
+        .ok_or_else(|| "Retention period is too long".to_string())
+}
+
+/// Delete finished webhook deliveries and snapshots older than `retention`
+fn prune_expired(
+    db: &Database,
+    snapshots: Option<&SnapshotStore>,
+    retention: Duration,
+) -> Result<String, String> {
+    let cutoff = cutoff(retention)?;
+
+    let deliveries = db.prune_webhook_deliveries(cutoff).map_err(|e| e.to_string())?;
+    let mut removed_snapshots = 0;
+    if let Some(store) = snapshots {
+        for snapshot in db.list_snapshots_before(cutoff).map_err(|e| e.to_string())? {
+            store.remove(db, snapshot.id).map_err(|e| e.to_string())?;
+            removed_snapshots += 1;
+        }
+    }
+
+    Ok(format!(
+        "Deleted {} webhook deliveries and {} snapshots",
+        deliveries, removed_snapshots
+    ))
+}
+
+/// Delete session output older than `max_age`, and all but the newest
+/// `max_lines` lines of each session
+pub fn prune_output(
+    db: &Database,
+    max_age: Option<Duration>,
+    max_lines: Option<u64>,
+) -> Result<PrunedOutput, String> {
+    let before = max_age.map(cutoff).transpose()?;
+    db.prune_output_logs(before, max_lines).map_err(|e| e.to_string())
+}
+
+/// Remove snapshot directories with no snapshot on record
+fn remove_orphaned_snapshots(db: &Database, store: &SnapshotStore) -> Result<String, String> {
+    let known = db.list_snapshot_ids().map_err(|e| e.to_string())?;
+    let now = SystemTime::now();
+
+    let mut removed = 0;
+    for (id, modified) in store.stored().map_err(|e| e.to_string())? {
+        let settled = now.duration_since(modified).is_ok_and(|age| age >= ORPHAN_GRACE);
+        if settled && !known.contains(&id) {
+            store.remove_files(id);
+            removed += 1;
+        }
+    }
+
+    Ok(format!("Removed {} orphaned snapshots", removed))
+}
+
+/// Report registered repositories that are missing or no longer git repositories
+fn validate_repos(db: &Database) -> Result<String, String> {
+    let repos = db.list_repos().map_err(|e| e.to_string())?;
+
+    let invalid: Vec<String> = repos
+        .iter()
+        .filter(|repo| crate::git::validate_repo_path(Path::new(&repo.path)).is_err())
+        .map(|repo| format!("{} ({})", repo.name, repo.path))
+        .collect();
+    if invalid.is_empty() {
+        return Ok(format!("All {} repositories are valid", repos.len()));
+    }
+
+    tracing::warn!("Repositories no longer usable: {}", invalid.join(", "));
+    Ok(format!(
+        "{} of {} repositories are missing or not git repositories: {}",
+        invalid.len(),
+        repos.len(),
+        invalid.join(", ")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::{DeliveryStatus, Orchestrator, SnapshotReason};
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_run_records_status() {
+        let scheduler = Scheduler::new()
+            .with_job("ok", "Succeeds", Duration::from_secs(60), || Ok("done".to_string()))
+            .with_job("broken", "Fails", Duration::ZERO, || Err("nope".to_string()));
+
+        let ok = scheduler.run("ok").await.unwrap();
+        assert_eq!((ok.runs, ok.failures), (1, 0));
+        assert_eq!(ok.last_outcome, Some(JobOutcome::Succeeded));
+        assert_eq!(ok.last_message.as_deref(), Some("done"));
+        assert!(ok.next_run_at.is_some());
+
+        let broken = scheduler.run("broken").await.unwrap();
+        assert_eq!((broken.runs, broken.failures), (1, 1));
+        assert_eq!(broken.last_outcome, Some(JobOutcome::Failed));
+        assert_eq!(broken.interval_secs, None);
+        assert!(scheduler.run("missing").await.is_none());
+    }
+
+    #[test]
+    fn test_prune_and_cleanup() {
+        let data = TempDir::new().unwrap();
+        let work = TempDir::new().unwrap();
+        git2::Repository::init(work.path()).unwrap();
+        std::fs::write(work.path().join("notes.txt"), "draft").unwrap();
+
+        let db = Database::in_memory().unwrap();
+        let repo = db.insert_repo(&work.path().to_string_lossy(), "repo").unwrap();
+        let session = db.insert_session(repo.id, None, Orchestrator::Ralph).unwrap();
+        let store = SnapshotStore::in_data_dir(data.path());
+        let snapshot = store
+            .capture(&db, session.id, repo.id, work.path(), SnapshotReason::PreRun)
+            .unwrap();
+        let webhook = db.insert_webhook("https://example.com", "secret", &[], true).unwrap();
+        let pending = db.insert_webhook_delivery(webhook.id, "test", &serde_json::json!({})).unwrap();
+        let delivered = db.insert_webhook_delivery(webhook.id, "test", &serde_json::json!({})).unwrap();
+        db.update_webhook_delivery(delivered.id, DeliveryStatus::Delivered, 1, Some(200), None)
+            .unwrap();
+
+        // Nothing is old enough yet
+        prune_expired(&db, Some(&store), Duration::from_secs(60)).unwrap();
+        assert!(db.get_snapshot(snapshot.id).is_ok());
+
+        std::thread::sleep(Duration::from_millis(10));
+        prune_expired(&db, Some(&store), Duration::ZERO).unwrap();
+        assert!(db.get_snapshot(snapshot.id).is_err());
+        assert!(store.stored().unwrap().is_empty());
+        assert!(db.get_webhook_delivery(pending.id).is_ok());
+        assert!(db.get_webhook_delivery(delivered.id).is_err());
+
+        // Files whose record is gone are only removed once they've settled
+        let orphan = store
+            .capture(&db, session.id, repo.id, work.path(), SnapshotReason::PreRun)
+            .unwrap();
+        db.delete_snapshot(orphan.id).unwrap();
+        assert_eq!(
+            remove_orphaned_snapshots(&db, &store).unwrap(),
+            "Removed 0 orphaned snapshots"
+        );
+        assert_eq!(store.stored().unwrap().len(), 1);
+
+        assert_eq!(validate_repos(&db).unwrap(), "All 1 repositories are valid");
+    }
+}
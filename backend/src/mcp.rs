@@ -0,0 +1,87 @@
+//! Stdio bridge to the MCP endpoint
+//!
+//! Many MCP clients launch servers as subprocesses and talk JSON-RPC over
+//! stdin and stdout. `ralphtown mcp` forwards each message to a running
+//! server's `/api/mcp` endpoint and writes the replies back, so sessions
+//! started this way run in that server and show up in its UI.
+
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+/// Forward JSON-RPC messages between stdio and `{url}/api/mcp` until stdin closes
+pub async fn run_stdio(url: &str, token: Option<&str>, insecure: bool) -> Result<(), String> {
+    let endpoint = format!("{}/api/mcp", url.trim_end_matches('/'));
+    let client = reqwest::Client::builder()
+        .danger_accept_invalid_certs(insecure)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let mut stdout = tokio::io::stdout();
+
+    while let Some(line) = lines
+        .next_line()
+        .await
+        .map_err(|e| format!("Failed to read stdin: {}", e))?
+    {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(reply) = forward(&client, &endpoint, token, line).await {
+            stdout
+                .write_all(format!("{}\n", reply).as_bytes())
+                .await
+                .and(stdout.flush().await)
+                .map_err(|e| format!("Failed to write stdout: {}", e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Send one message, returning the reply to print (if any)
+///
+/// When the server can't be reached or rejects the request, requests get a
+/// JSON-RPC error so the client isn't left waiting.
+async fn forward(
+    client: &reqwest::Client,
+    endpoint: &str,
+    token: Option<&str>,
+    message: String,
+) -> Option<String> {
+    let id = serde_json::from_str::<Value>(&message)
+        .ok()
+        .and_then(|message| message.get("id").cloned());
+
+    let mut request = client
+        .post(endpoint)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(reqwest::header::ACCEPT, "application/json, text/event-stream")
+        .body(message);
+    if let Some(token) = token {
+        request = request.bearer_auth(token);
+    }
+
+    let error = match request.send().await {
+        Ok(response) if response.status() == reqwest::StatusCode::ACCEPTED => return None,
+        Ok(response) if response.status().is_success() => match response.text().await {
+            Ok(body) => return Some(body.trim().to_string()),
+            Err(e) => format!("Failed to read response from Ralphtown: {}", e),
+        },
+        Ok(response) => {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            format!("Ralphtown returned {}: {}", status, body.trim())
+        }
+        Err(e) => format!("Failed to reach Ralphtown at {}: {}", endpoint, e),
+    };
+
+    tracing::warn!("{}", error);
+    id.map(|id| {
+        json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": -32603, "message": error },
+        })
+        .to_string()
+    })
+}
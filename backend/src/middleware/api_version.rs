@@ -0,0 +1,226 @@
+//! API versioning
+//!
+//! Every route is served under `/api/v1` as well as the unversioned `/api`,
+//! which stays an alias of the current version. Versioned requests are
+//! rewritten to the unversioned path before routing, so handlers and the other
+//! middleware see a single set of paths. API responses carry
+//! `X-Ralphtown-API-Version`, and routes listed in [`DEPRECATIONS`] also carry
+//! `Deprecation` and `Sunset` headers (RFC 9745, RFC 8594) so clients get
+//! warning before a breaking change to a response shape.
+
+use axum::{
+    extract::Request,
+    http::{HeaderMap, HeaderValue, Uri},
+    response::{IntoResponse, Response},
+    Router,
+};
+use tower::ServiceExt;
+
+use crate::error::AppError;
+
+/// Version served under `/api/v1` and the unversioned `/api`
+pub const CURRENT_VERSION: u32 = 1;
+
+/// Response header naming the API version that handled the request
+pub const VERSION_HEADER: &str = "x-ralphtown-api-version";
+
+/// A route slated for removal or an incompatible change
+#[derive(Debug, Clone, Copy)]
+pub struct Deprecation {
+    /// Route as registered on the router, without the version (e.g. "/api/sessions/{id}/output")
+    pub route: &'static str,
+    /// When the route was deprecated, as a Unix timestamp
+    pub deprecated_at: i64,
+    /// HTTP date after which the route may change or disappear
+    pub sunset: Option<&'static str>,
+    /// Path of the replacement, if any
+    pub successor: Option<&'static str>,
+}
+
+/// Routes currently deprecated
+pub const DEPRECATIONS: &[Deprecation] = &[];
+
+/// Serve `app` under both `/api` and `/api/v{CURRENT_VERSION}`
+pub fn mount(app: Router) -> Router {
+    Router::new().fallback(move |req: Request| route_versioned(app.clone(), req))
+}
+
+/// Strip the version from API paths, then tag the response with version headers
+async fn route_versioned(app: Router, mut req: Request) -> Response {
+    let path = req.uri().path();
+    let api_path = match strip_version(path) {
+        Ok(Some(api_path)) => api_path,
+        Ok(None) => {
+            return match app.oneshot(req).await {
+                Ok(response) => response,
+                Err(never) => match never {},
+            };
+        }
+        Err(version) => {
+            return AppError::NotFound(format!(
+                "API version {} is not supported; the current version is v{}",
+                version, CURRENT_VERSION
+            ))
+            .into_response();
+        }
+    };
+
+    if api_path != path {
+        let path_and_query = match req.uri().query() {
+            Some(query) => format!("{}?{}", api_path, query),
+            None => api_path.clone(),
+        };
+        let mut parts = req.uri().clone().into_parts();
+        parts.path_and_query = match path_and_query.parse() {
+            Ok(path_and_query) => Some(path_and_query),
+            Err(_) => {
+                return AppError::BadRequest("Invalid request path".to_string()).into_response();
+            }
+        };
+        *req.uri_mut() = Uri::from_parts(parts).expect("only the path was replaced");
+    }
+
+    let mut response = match app.oneshot(req).await {
+        Ok(response) => response,
+        Err(never) => match never {},
+    };
+    add_version_headers(response.headers_mut(), &api_path, DEPRECATIONS);
+    response
+}
+
+/// Map a request path to its unversioned API path
+///
+/// Returns `Ok(None)` for non-API paths and `Err(version)` for API versions
+/// that don't exist.
+fn strip_version(path: &str) -> Result<Option<String>, String> {
+    let Some(rest) = path.strip_prefix("/api") else {
+        return Ok(None);
+    };
+    if !(rest.is_empty() || rest.starts_with('/')) {
+        return Ok(None);
+    }
+
+    let (segment, tail) = match rest.trim_start_matches('/').split_once('/') {
+        Some((segment, tail)) => (segment, format!("/{}", tail)),
+        None => (rest.trim_start_matches('/'), String::new()),
+    };
+    let version = segment
+        .strip_prefix('v')
+        .filter(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()));
+
+    match version {
+        Some(n) if n == CURRENT_VERSION.to_string() => Ok(Some(format!("/api{}", tail))),
+        Some(_) => Err(segment.to_string()),
+        None => Ok(Some(path.to_string())),
+    }
+}
+
+/// Whether `path` matches a registered route pattern such as "/api/sessions/{id}"
+fn route_matches(route: &str, path: &str) -> bool {
+    let mut route = route.split('/');
+    let mut path = path.split('/');
+    loop {
+        match (route.next(), path.next()) {
+            (None, None) => return true,
+            (Some(expected), Some(actual)) => {
+                let param = expected.starts_with('{') && expected.ends_with('}');
+                if !((param && !actual.is_empty()) || expected == actual) {
+                    return false;
+                }
+            }
+            _ => return false,
+        }
+    }
+}
+
+/// Add the version header, and deprecation headers when `api_path` is deprecated
+fn add_version_headers(headers: &mut HeaderMap, api_path: &str, deprecations: &[Deprecation]) {
+    headers.insert(VERSION_HEADER, HeaderValue::from(CURRENT_VERSION));
+
+    let Some(deprecation) = deprecations
+        .iter()
+        .find(|deprecation| route_matches(deprecation.route, api_path))
+    else {
+        return;
+    };
+
+    if let Ok(value) = HeaderValue::from_str(&format!("@{}", deprecation.deprecated_at)) {
+        headers.insert("deprecation", value);
+    }
+    if let Some(sunset) = deprecation.sunset.and_then(|s| HeaderValue::from_str(s).ok()) {
+        headers.insert("sunset", sunset);
+    }
+    if let Some(successor) = deprecation
+        .successor
+        .and_then(|s| HeaderValue::from_str(&format!("<{}>; rel=\"successor-version\"", s)).ok())
+    {
+        headers.append(axum::http::header::LINK, successor);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::AppState;
+    use crate::create_app;
+    use crate::db::Database;
+    use axum::http::StatusCode;
+    use axum_test::TestServer;
+
+    #[test]
+    fn test_strip_version() {
+        assert_eq!(strip_version("/api/v1/repos"), Ok(Some("/api/repos".to_string())));
+        assert_eq!(strip_version("/api/v1"), Ok(Some("/api".to_string())));
+        assert_eq!(strip_version("/api/repos"), Ok(Some("/api/repos".to_string())));
+        assert_eq!(strip_version("/api/v2/repos"), Err("v2".to_string()));
+        // Only whole segments count
+        assert_eq!(strip_version("/api/views"), Ok(Some("/api/views".to_string())));
+        assert_eq!(strip_version("/apix/v1"), Ok(None));
+        assert_eq!(strip_version("/assets/app.js"), Ok(None));
+    }
+
+    #[test]
+    fn test_deprecation_headers() {
+        let deprecations = [Deprecation {
+            route: "/api/sessions/{id}/output",
+            deprecated_at: 1_767_225_600,
+            sunset: Some("Wed, 01 Jul 2026 00:00:00 GMT"),
+            successor: Some("/api/v2/sessions/{id}/output"),
+        }];
+
+        let mut headers = HeaderMap::new();
+        add_version_headers(&mut headers, "/api/sessions/abc/output", &deprecations);
+        assert_eq!(headers[VERSION_HEADER], "1");
+        assert_eq!(headers["deprecation"], "@1767225600");
+        assert_eq!(headers["sunset"], "Wed, 01 Jul 2026 00:00:00 GMT");
+        assert_eq!(
+            headers["link"],
+            "</api/v2/sessions/{id}/output>; rel=\"successor-version\""
+        );
+
+        let mut headers = HeaderMap::new();
+        add_version_headers(&mut headers, "/api/sessions/abc", &deprecations);
+        assert!(!headers.contains_key("deprecation"));
+    }
+
+    #[tokio::test]
+    async fn test_versioned_routes_alias_current_api() {
+        let db = Database::in_memory().expect("Failed to create test database");
+        db.insert_repo("/path/to/repo", "my-repo").unwrap();
+        let server = TestServer::new(create_app(AppState::new(db))).unwrap();
+
+        let response = server.get("/api/v1/repos").await;
+        response.assert_status_ok();
+        assert_eq!(response.header(VERSION_HEADER), "1");
+        assert_eq!(response.json::<serde_json::Value>()["items"][0]["name"], "my-repo");
+
+        let response = server.get("/api/repos").await;
+        response.assert_status_ok();
+        assert_eq!(response.header(VERSION_HEADER), "1");
+
+        server
+            .get("/api/v9/repos")
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,301 @@
+//! Bearer token authentication
+//!
+//! When an `auth_token` is configured every `/api` request except the health
+//! check and login must present it, either as `Authorization: Bearer <token>`,
+//! as the session cookie set by `POST /api/auth/session`, or as an
+//! `access_token` query parameter (for WebSocket and EventSource clients,
+//! which cannot set headers).
+//!
+//! Besides the configured token, each user's own API token is accepted; the
+//! resulting [`CurrentUser`] is stored in the request extensions for handlers.
+//! Whether that user may make the change a request asks for is checked by
+//! [`require_write_access`] once the request has been routed.
+
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap, Method},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use super::read_only::{route, MUTATING_GET_ROUTES};
+use crate::api::AppState;
+use crate::error::AppError;
+use crate::users::{hash_token, CurrentUser};
+
+/// Paths that never require authentication
+///
+/// The trigger hook checks its own per-trigger token instead.
+const PUBLIC_API_PATHS: [&str; 6] = [
+    "/api/health",
+    "/api/health/live",
+    "/api/health/ready",
+    "/api/auth/session",
+    "/api/ui-config",
+    "/api/hooks/trigger",
+];
+
+/// Paths viewers may change, since they only affect their own account
+const SELF_SERVICE_PATHS: [&str; 1] = ["/api/users/me/preferences"];
+
+/// Cookie holding the auth token for browser sessions
+pub const SESSION_COOKIE: &str = "ralphtown_session";
+
+/// Query parameter accepted in place of the Authorization header
+const TOKEN_QUERY_PARAM: &str = "access_token";
+
+/// Reject unauthenticated API requests when a token is configured
+pub async fn require_auth(State(state): State<AppState>, mut req: Request, next: Next) -> Response {
+    if !state.settings.auth.is_enabled() {
+        return next.run(req).await;
+    }
+
+    let path = req.uri().path();
+    // Frontend assets stay public so the UI can load and prompt for a token
+    if !path.starts_with("/api/") || PUBLIC_API_PATHS.contains(&path) {
+        return next.run(req).await;
+    }
+
+    let user = match request_token(&req) {
+        Some(token) => match authenticate(&state, &token).await {
+            Some(user) => user,
+            None => {
+                return AppError::Unauthorized("Invalid auth token".to_string()).into_response();
+            }
+        },
+        None => {
+            return AppError::Unauthorized("Authentication required".to_string()).into_response();
+        }
+    };
+
+    req.extensions_mut().insert(user);
+    next.run(req).await
+}
+
+/// Reject requests that change state from users with read-only access
+///
+/// A route layer, so routes are matched by template like in read-only mode.
+/// Requests without a [`CurrentUser`] come from a server without auth.
+pub async fn require_write_access(req: Request, next: Next) -> Response {
+    let Some(user) = req.extensions().get::<CurrentUser>() else {
+        return next.run(req).await;
+    };
+
+    let route = route(&req);
+    let mutating = match *req.method() {
+        Method::GET | Method::HEAD | Method::OPTIONS => MUTATING_GET_ROUTES.contains(&route),
+        _ => !SELF_SERVICE_PATHS.contains(&route),
+    };
+    if mutating && !user.can_write() {
+        return AppError::Forbidden(format!("User '{}' has read-only access", user.username))
+            .into_response();
+    }
+
+    next.run(req).await
+}
+
+/// Resolve a token to the configured administrator or a user account
+pub async fn authenticate(state: &AppState, token: &str) -> Option<CurrentUser> {
+    let expected = state.settings.auth.token.as_deref()?;
+    if constant_time_eq(token.as_bytes(), expected.as_bytes()) {
+        return Some(CurrentUser::root());
+    }
+
+    let token_hash = hash_token(token);
+    state
+        .db
+        .call(move |db| db.get_user_by_token_hash(&token_hash))
+        .await
+        .ok()
+        .map(CurrentUser::from)
+}
+
+/// Extract the token from the Authorization header, session cookie or query string
+fn request_token(req: &Request) -> Option<String> {
+    bearer_token(req.headers())
+        .map(String::from)
+        .or_else(|| cookie(req.headers(), SESSION_COOKIE).map(String::from))
+        .or_else(|| {
+            req.uri().query().and_then(|query| {
+                query.split('&').find_map(|pair| {
+                    let (key, value) = pair.split_once('=')?;
+                    (key == TOKEN_QUERY_PARAM).then(|| value.to_string())
+                })
+            })
+        })
+}
+
+/// The token from an `Authorization: Bearer` header; other schemes are ignored
+pub fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::trim)
+}
+
+/// Look up a cookie value by name
+pub fn cookie<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .find_map(|pair| {
+            let (key, value) = pair.trim().split_once('=')?;
+            (key == name).then_some(value)
+        })
+}
+
+/// Compare two byte strings without short-circuiting on the first mismatch
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_app;
+    use crate::db::models::UserRole;
+    use crate::db::Database;
+    use crate::settings::{AuthSettings, Settings};
+    use axum::http::StatusCode;
+    use axum_test::TestServer;
+
+    const TOKEN: &str = "0123456789abcdef";
+
+    fn create_test_server(token: Option<&str>) -> TestServer {
+        let db = Database::in_memory().expect("Failed to create test database");
+        let settings = Settings {
+            auth: AuthSettings {
+                token: token.map(String::from),
+            },
+            ..Settings::default()
+        };
+        TestServer::new(create_app(AppState::with_settings(db, settings)))
+            .expect("Failed to create test server")
+    }
+
+    #[tokio::test]
+    async fn test_open_when_no_token_configured() {
+        let server = create_test_server(None);
+
+        server.get("/api/repos").await.assert_status_ok();
+    }
+
+    #[tokio::test]
+    async fn test_requires_token_when_configured() {
+        let server = create_test_server(Some(TOKEN));
+
+        let response = server.get("/api/repos").await;
+        response.assert_status(StatusCode::UNAUTHORIZED);
+
+        let response = server
+            .get("/api/repos")
+            .add_header(header::AUTHORIZATION, "Bearer wrong-token-value")
+            .await;
+        response.assert_status(StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_accepts_header_and_query_token() {
+        let server = create_test_server(Some(TOKEN));
+
+        server
+            .get("/api/repos")
+            .add_header(header::AUTHORIZATION, format!("Bearer {}", TOKEN))
+            .await
+            .assert_status_ok();
+
+        server
+            .get(&format!("/api/repos?access_token={}", TOKEN))
+            .await
+            .assert_status_ok();
+    }
+
+    #[tokio::test]
+    async fn test_accepts_session_cookie() {
+        let server = create_test_server(Some(TOKEN));
+
+        server
+            .get("/api/repos")
+            .add_header(header::COOKIE, format!("theme=dark; {}={}", SESSION_COOKIE, TOKEN))
+            .await
+            .assert_status_ok();
+    }
+
+    #[tokio::test]
+    async fn test_accepts_user_token() {
+        let db = Database::in_memory().expect("Failed to create test database");
+        db.insert_user("alice", UserRole::Member, &hash_token("alice-token-value"))
+            .unwrap();
+        db.insert_user("vera", UserRole::Viewer, &hash_token("vera-token-value"))
+            .unwrap();
+        let settings = Settings {
+            auth: AuthSettings {
+                token: Some(TOKEN.to_string()),
+            },
+            ..Settings::default()
+        };
+        let server = TestServer::new(create_app(AppState::with_settings(db, settings)))
+            .expect("Failed to create test server");
+
+        server
+            .get("/api/repos")
+            .add_header(header::AUTHORIZATION, "Bearer alice-token-value")
+            .await
+            .assert_status_ok();
+
+        // Viewers can read but not write
+        server
+            .get("/api/repos")
+            .add_header(header::AUTHORIZATION, "Bearer vera-token-value")
+            .await
+            .assert_status_ok();
+        server
+            .put("/api/config/theme")
+            .add_header(header::AUTHORIZATION, "Bearer vera-token-value")
+            .json(&serde_json::json!({ "value": "dark" }))
+            .await
+            .assert_status(StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_viewers_cannot_start_fetches() {
+        let db = Database::in_memory().expect("Failed to create test database");
+        db.insert_user("vera", UserRole::Viewer, &hash_token("vera-token-value"))
+            .unwrap();
+        let repo = db.insert_repo("/path/to/repo", "repo").unwrap();
+        let settings = Settings {
+            auth: AuthSettings {
+                token: Some(TOKEN.to_string()),
+            },
+            ..Settings::default()
+        };
+        let server = TestServer::new(create_app(AppState::with_settings(db, settings)))
+            .expect("Failed to create test server");
+
+        server
+            .get(&format!("/api/git/{}/fetch-progress", repo.id))
+            .add_header(header::AUTHORIZATION, "Bearer vera-token-value")
+            .await
+            .assert_status(StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_health_is_public() {
+        let server = create_test_server(Some(TOKEN));
+
+        server.get("/api/health").await.assert_status_ok();
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"abc", b"abc"));
+        assert!(!constant_time_eq(b"abc", b"abd"));
+        assert!(!constant_time_eq(b"abc", b"abcd"));
+    }
+}
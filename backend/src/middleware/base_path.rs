@@ -0,0 +1,118 @@
+//! Base path for reverse-proxy deployments
+//!
+//! With `base_path` set (e.g. "/ralphtown") the whole app lives under that
+//! prefix. The prefix is stripped before routing, so handlers and the other
+//! middleware see the same paths as when the app is served from the root.
+
+use axum::{
+    extract::Request,
+    http::Uri,
+    response::{IntoResponse, Redirect, Response},
+    Router,
+};
+use tower::ServiceExt;
+
+use crate::error::AppError;
+
+/// Serve `app` under `base_path`, or unchanged when the base path is empty
+pub fn mount(base_path: &str, app: Router) -> Router {
+    if base_path.is_empty() {
+        return app;
+    }
+
+    let base_path = base_path.to_string();
+    Router::new().fallback(move |req: Request| strip_base_path(base_path.clone(), app.clone(), req))
+}
+
+/// Forward a request to the app with the base path removed from its URI
+async fn strip_base_path(base_path: String, app: Router, mut req: Request) -> Response {
+    let path = req.uri().path();
+    let rest = match path.strip_prefix(base_path.as_str()) {
+        Some("") => "/",
+        Some(rest) if rest.starts_with('/') => rest,
+        _ if path == "/" => {
+            return Redirect::temporary(&format!("{}/", base_path)).into_response();
+        }
+        _ => {
+            return AppError::NotFound(format!("Ralphtown is served under {}/", base_path))
+                .into_response();
+        }
+    };
+
+    let path_and_query = match req.uri().query() {
+        Some(query) => format!("{}?{}", rest, query),
+        None => rest.to_string(),
+    };
+    let mut parts = req.uri().clone().into_parts();
+    parts.path_and_query = match path_and_query.parse() {
+        Ok(path_and_query) => Some(path_and_query),
+        Err(_) => return AppError::BadRequest("Invalid request path".to_string()).into_response(),
+    };
+    *req.uri_mut() = Uri::from_parts(parts).expect("only the path was replaced");
+
+    match app.oneshot(req).await {
+        Ok(response) => response,
+        Err(never) => match never {},
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::AppState;
+    use crate::create_app;
+    use crate::db::Database;
+    use crate::settings::Settings;
+    use axum::http::{header, StatusCode};
+    use axum_test::TestServer;
+
+    fn create_test_server(base_path: &str) -> TestServer {
+        let db = Database::in_memory().expect("Failed to create test database");
+        let settings = Settings {
+            base_path: base_path.to_string(),
+            ..Settings::default()
+        };
+        TestServer::new(create_app(AppState::with_settings(db, settings))).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_routes_are_served_under_base_path() {
+        let server = create_test_server("/ralphtown");
+
+        server.get("/ralphtown/api/health").await.assert_status_ok();
+        server.get("/ralphtown/api/repos?limit=1").await.assert_status_ok();
+        server
+            .get("/api/health")
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+        server
+            .get("/ralphtownx/api/health")
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_root_redirects_to_base_path() {
+        let server = create_test_server("/ralphtown");
+
+        let response = server.get("/").await;
+        response.assert_status(StatusCode::TEMPORARY_REDIRECT);
+        response.assert_header(header::LOCATION, "/ralphtown/");
+    }
+
+    #[tokio::test]
+    async fn test_route_guards_see_stripped_paths() {
+        let db = Database::in_memory().expect("Failed to create test database");
+        let settings = Settings {
+            base_path: "/ralphtown".to_string(),
+            read_only: true,
+            ..Settings::default()
+        };
+        let server = TestServer::new(create_app(AppState::with_settings(db, settings))).unwrap();
+
+        server
+            .put("/ralphtown/api/config/theme")
+            .json(&serde_json::json!({ "value": "dark" }))
+            .await
+            .assert_status(StatusCode::FORBIDDEN);
+    }
+}
@@ -0,0 +1,80 @@
+//! Response compression
+//!
+//! Session output and log listings can be multi-megabyte JSON, so responses
+//! are gzip or brotli compressed when the client accepts it. Small bodies,
+//! images, archives, and event streams (which must reach the client as each
+//! event is written) are sent as-is.
+
+use tower_http::compression::predicate::{NotForContentType, Predicate, SizeAbove};
+use tower_http::compression::CompressionLayer;
+
+/// Smallest body worth compressing, in bytes
+const MIN_SIZE: u16 = 1024;
+
+/// Build the compression layer
+pub fn compression_layer() -> CompressionLayer<impl Predicate> {
+    let predicate = SizeAbove::new(MIN_SIZE)
+        .and(NotForContentType::IMAGES)
+        .and(NotForContentType::SSE)
+        .and(NotForContentType::GRPC)
+        .and(NotForContentType::const_new("application/gzip"))
+        .and(NotForContentType::const_new("application/zip"));
+
+    CompressionLayer::new()
+        .gzip(true)
+        .br(true)
+        .compress_when(predicate)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::AppState;
+    use crate::create_app;
+    use crate::db::Database;
+    use axum::http::header;
+    use axum_test::TestServer;
+
+    fn create_test_server() -> TestServer {
+        let db = Database::in_memory().expect("Failed to create test database");
+        for i in 0..50 {
+            db.insert_repo(&format!("/path/to/repo-{}", i), &format!("repo-{}", i))
+                .unwrap();
+        }
+        TestServer::new(create_app(AppState::new(db))).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_large_responses_are_compressed() {
+        let server = create_test_server();
+
+        let response = server
+            .get("/api/repos")
+            .add_header(header::ACCEPT_ENCODING, "gzip")
+            .await;
+        response.assert_status_ok();
+        assert_eq!(response.header(header::CONTENT_ENCODING), "gzip");
+
+        let response = server
+            .get("/api/repos")
+            .add_header(header::ACCEPT_ENCODING, "br")
+            .await;
+        assert_eq!(response.header(header::CONTENT_ENCODING), "br");
+
+        // Without Accept-Encoding the body is sent as-is
+        let response = server.get("/api/repos").await;
+        assert!(response.maybe_header(header::CONTENT_ENCODING).is_none());
+        assert_eq!(response.json::<serde_json::Value>()["total"], 50);
+    }
+
+    #[tokio::test]
+    async fn test_small_responses_are_not_compressed() {
+        let server = create_test_server();
+
+        let response = server
+            .get("/api/health")
+            .add_header(header::ACCEPT_ENCODING, "gzip")
+            .await;
+        response.assert_status_ok();
+        assert!(response.maybe_header(header::CONTENT_ENCODING).is_none());
+    }
+}
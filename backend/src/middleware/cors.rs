@@ -0,0 +1,216 @@
+//! Cross-origin request policy
+//!
+//! Builds the CORS layer from [`CorsSettings`] and rejects credentialed
+//! cross-origin requests unless they have been explicitly enabled.
+
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderName, HeaderValue, Method},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, CorsLayer};
+
+use crate::api::AppState;
+use crate::error::AppError;
+use crate::settings::{is_wildcard, CorsSettings};
+
+/// Build a CORS layer from settings
+pub fn cors_layer(settings: &CorsSettings) -> CorsLayer {
+    let origins = if is_wildcard(&settings.allowed_origins) {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(
+            settings
+                .allowed_origins
+                .iter()
+                .filter_map(|o| HeaderValue::from_str(o).ok()),
+        )
+    };
+
+    let methods = if is_wildcard(&settings.allowed_methods) {
+        AllowMethods::any()
+    } else {
+        AllowMethods::list(
+            settings
+                .allowed_methods
+                .iter()
+                .filter_map(|m| Method::from_bytes(m.as_bytes()).ok()),
+        )
+    };
+
+    let headers = if is_wildcard(&settings.allowed_headers) {
+        AllowHeaders::any()
+    } else {
+        AllowHeaders::list(
+            settings
+                .allowed_headers
+                .iter()
+                .filter_map(|h| HeaderName::from_bytes(h.as_bytes()).ok()),
+        )
+    };
+
+    CorsLayer::new()
+        .allow_origin(origins)
+        .allow_methods(methods)
+        .allow_headers(headers)
+        .allow_credentials(settings.allow_credentials)
+}
+
+/// Reject cross-origin requests that carry cookies or an Authorization header
+/// unless `cors_allow_credentials` is enabled
+pub async fn reject_credentialed_cross_origin(
+    State(state): State<AppState>,
+    req: Request,
+    next: Next,
+) -> Response {
+    if !state.settings.cors.allow_credentials && is_cross_origin(&req) && has_credentials(&req) {
+        return AppError::Forbidden(
+            "Credentialed cross-origin requests are not allowed".to_string(),
+        )
+        .into_response();
+    }
+
+    next.run(req).await
+}
+
+/// Check whether the request's Origin differs from the host it was sent to
+fn is_cross_origin(req: &Request) -> bool {
+    let Some(origin) = req.headers().get(header::ORIGIN).and_then(|v| v.to_str().ok()) else {
+        return false;
+    };
+    let host = req.headers().get(header::HOST).and_then(|v| v.to_str().ok());
+
+    // Opaque origins ("null") and origins without a scheme are always foreign
+    match (origin.split_once("://"), host) {
+        (Some((_, origin_host)), Some(host)) => !origin_host.eq_ignore_ascii_case(host),
+        _ => true,
+    }
+}
+
+/// Check whether the request carries ambient credentials
+fn has_credentials(req: &Request) -> bool {
+    req.headers().contains_key(header::COOKIE) || req.headers().contains_key(header::AUTHORIZATION)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_app;
+    use crate::db::Database;
+    use crate::settings::Settings;
+    use axum::http::StatusCode;
+    use axum_test::TestServer;
+
+    fn create_test_server(cors: CorsSettings) -> TestServer {
+        let db = Database::in_memory().expect("Failed to create test database");
+        let state = AppState::with_settings(
+            db,
+            Settings {
+                cors,
+                ..Settings::default()
+            },
+        );
+        TestServer::new(create_app(state)).expect("Failed to create test server")
+    }
+
+    #[tokio::test]
+    async fn test_default_allows_localhost_origin() {
+        let server = create_test_server(CorsSettings::default());
+
+        let response = server
+            .get("/api/health")
+            .add_header(header::ORIGIN, "http://localhost:5173")
+            .await;
+
+        response.assert_status_ok();
+        assert_eq!(
+            response.header(header::ACCESS_CONTROL_ALLOW_ORIGIN),
+            "http://localhost:5173"
+        );
+    }
+
+    /// Preflight a cross-origin request from the Vite dev server
+    async fn preflight(server: &TestServer, method: &str, path: &str) -> axum_test::TestResponse {
+        server
+            .method(Method::OPTIONS, path)
+            .add_header(header::ORIGIN, "http://localhost:5173")
+            .add_header(header::ACCESS_CONTROL_REQUEST_METHOD, method)
+            .add_header(header::ACCESS_CONTROL_REQUEST_HEADERS, "content-type,x-csrf-token")
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_default_preflight_allows_csrf_header() {
+        let server = create_test_server(CorsSettings::default());
+
+        let response = preflight(&server, "PUT", "/api/config/theme").await;
+
+        response.assert_status_ok();
+        let allowed = response.header(header::ACCESS_CONTROL_ALLOW_HEADERS);
+        assert!(allowed.to_str().unwrap().contains("x-csrf-token"));
+    }
+
+    #[tokio::test]
+    async fn test_default_preflight_allows_session_patches() {
+        let server = create_test_server(CorsSettings::default());
+        let id = uuid::Uuid::new_v4();
+
+        for route in ["archive", "metadata"] {
+            let path = format!("/api/sessions/{}/{}", id, route);
+            let response = preflight(&server, "PATCH", &path).await;
+
+            response.assert_status_ok();
+            let allowed = response.header(header::ACCESS_CONTROL_ALLOW_METHODS);
+            assert!(allowed.to_str().unwrap().contains("PATCH"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_default_ignores_foreign_origin() {
+        let server = create_test_server(CorsSettings::default());
+
+        let response = server
+            .get("/api/health")
+            .add_header(header::ORIGIN, "https://evil.example")
+            .await;
+
+        assert!(response
+            .maybe_header(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_rejects_credentialed_cross_origin_by_default() {
+        let server = create_test_server(CorsSettings::default());
+
+        let response = server
+            .get("/api/health")
+            .add_header(header::ORIGIN, "https://evil.example")
+            .add_header(header::COOKIE, "session=abc")
+            .await;
+
+        response.assert_status(StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_allows_credentialed_cross_origin_when_enabled() {
+        let server = create_test_server(CorsSettings {
+            allowed_origins: vec!["https://ui.example".to_string()],
+            allow_credentials: true,
+            ..CorsSettings::default()
+        });
+
+        let response = server
+            .get("/api/health")
+            .add_header(header::ORIGIN, "https://ui.example")
+            .add_header(header::COOKIE, "session=abc")
+            .await;
+
+        response.assert_status_ok();
+        assert_eq!(
+            response.header(header::ACCESS_CONTROL_ALLOW_CREDENTIALS),
+            "true"
+        );
+    }
+}
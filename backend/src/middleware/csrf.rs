@@ -0,0 +1,149 @@
+//! CSRF protection for cookie-authenticated requests
+//!
+//! Browsers attach the session cookie to requests from any page, and the
+//! server sits on a predictable localhost port, so state-changing requests
+//! authenticated by cookie must echo the CSRF cookie in the `X-CSRF-Token`
+//! header (double-submit). Requests using a bearer token are not affected
+//! since a foreign page cannot make the browser add one.
+
+use axum::{
+    extract::Request,
+    http::{HeaderName, Method},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use uuid::Uuid;
+
+use super::auth::{bearer_token, constant_time_eq, cookie, SESSION_COOKIE};
+use crate::error::AppError;
+
+/// Cookie holding the CSRF token; readable by scripts so the UI can echo it
+pub const CSRF_COOKIE: &str = "ralphtown_csrf";
+
+/// Header state-changing requests must echo the CSRF cookie in
+pub const CSRF_HEADER: HeaderName = HeaderName::from_static("x-csrf-token");
+
+/// Generate a new random CSRF token
+pub fn new_token() -> String {
+    Uuid::new_v4().simple().to_string()
+}
+
+/// Require a matching CSRF token on state-changing cookie-authenticated requests
+pub async fn verify_csrf(req: Request, next: Next) -> Response {
+    if is_safe_method(req.method()) || !is_cookie_authenticated(&req) {
+        return next.run(req).await;
+    }
+
+    let expected = cookie(req.headers(), CSRF_COOKIE);
+    let provided = req.headers().get(CSRF_HEADER).and_then(|v| v.to_str().ok());
+
+    match (expected, provided) {
+        (Some(expected), Some(provided))
+            if !expected.is_empty() && constant_time_eq(expected.as_bytes(), provided.as_bytes()) =>
+        {
+            next.run(req).await
+        }
+        _ => AppError::Forbidden("Missing or invalid CSRF token".to_string()).into_response(),
+    }
+}
+
+/// Methods that must not change state and are never checked
+fn is_safe_method(method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+}
+
+/// Whether the request relies on the session cookie rather than a bearer token
+///
+/// Only a Bearer token takes precedence over the cookie in authentication, so
+/// any other Authorization header leaves the request cookie-authenticated.
+fn is_cookie_authenticated(req: &Request) -> bool {
+    bearer_token(req.headers()).is_none() && cookie(req.headers(), SESSION_COOKIE).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::AppState;
+    use crate::create_app;
+    use crate::db::Database;
+    use crate::settings::{AuthSettings, Settings};
+    use axum::http::{header, StatusCode};
+    use axum_test::TestServer;
+
+    const TOKEN: &str = "0123456789abcdef";
+
+    fn create_test_server() -> TestServer {
+        let db = Database::in_memory().expect("Failed to create test database");
+        let settings = Settings {
+            auth: AuthSettings {
+                token: Some(TOKEN.to_string()),
+            },
+            ..Settings::default()
+        };
+        TestServer::new(create_app(AppState::with_settings(db, settings)))
+            .expect("Failed to create test server")
+    }
+
+    #[tokio::test]
+    async fn test_cookie_request_without_csrf_is_rejected() {
+        let server = create_test_server();
+
+        let response = server
+            .put("/api/config/theme")
+            .add_header(header::COOKIE, format!("{}={}", SESSION_COOKIE, TOKEN))
+            .json(&serde_json::json!({ "value": "dark" }))
+            .await;
+
+        response.assert_status(StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_cookie_request_with_csrf_is_allowed() {
+        let server = create_test_server();
+        let csrf = new_token();
+
+        let response = server
+            .put("/api/config/theme")
+            .add_header(
+                header::COOKIE,
+                format!("{}={}; {}={}", SESSION_COOKIE, TOKEN, CSRF_COOKIE, csrf),
+            )
+            .add_header(CSRF_HEADER, csrf)
+            .json(&serde_json::json!({ "value": "dark" }))
+            .await;
+
+        response.assert_status_ok();
+    }
+
+    #[tokio::test]
+    async fn test_cookie_request_with_other_authorization_is_checked() {
+        let server = create_test_server();
+
+        let response = server
+            .put("/api/config/theme")
+            .add_header(header::AUTHORIZATION, "Basic dXNlcjpwYXNz")
+            .add_header(header::COOKIE, format!("{}={}", SESSION_COOKIE, TOKEN))
+            .json(&serde_json::json!({ "value": "dark" }))
+            .await;
+
+        response.assert_status(StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_bearer_and_safe_requests_are_exempt() {
+        let server = create_test_server();
+
+        server
+            .put("/api/config/theme")
+            .add_header(header::AUTHORIZATION, format!("Bearer {}", TOKEN))
+            .json(&serde_json::json!({ "value": "dark" }))
+            .await
+            .assert_status_ok();
+
+        server
+            .get("/api/config")
+            .add_header(header::COOKIE, format!("{}={}", SESSION_COOKIE, TOKEN))
+            .await
+            .assert_status_ok();
+    }
+}
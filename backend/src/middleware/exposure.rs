@@ -0,0 +1,68 @@
+//! Network exposure warning
+//!
+//! When the server listens on a non-loopback address every response is tagged
+//! with a warning header so clients and proxies can tell the instance is
+//! reachable from other machines.
+
+use axum::{
+    extract::{Request, State},
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::api::AppState;
+
+/// Header added to responses while the server is exposed to the network
+pub const EXPOSURE_WARNING_HEADER: HeaderName = HeaderName::from_static("x-ralphtown-warning");
+
+/// Warning text sent in [`EXPOSURE_WARNING_HEADER`]
+const EXPOSURE_WARNING: &str = "server is reachable from the network";
+
+/// Tag responses with a warning header when listening beyond loopback
+pub async fn tag_exposed(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    let mut response = next.run(req).await;
+
+    if state.settings.is_exposed() {
+        response
+            .headers_mut()
+            .insert(EXPOSURE_WARNING_HEADER, HeaderValue::from_static(EXPOSURE_WARNING));
+    }
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_app;
+    use crate::db::Database;
+    use crate::settings::Settings;
+    use axum_test::TestServer;
+
+    fn create_test_server(listen_addr: &str) -> TestServer {
+        let db = Database::in_memory().expect("Failed to create test database");
+        let settings = Settings {
+            listen_addrs: vec![listen_addr.parse().unwrap()],
+            ..Settings::default()
+        };
+        TestServer::new(create_app(AppState::with_settings(db, settings)))
+            .expect("Failed to create test server")
+    }
+
+    #[tokio::test]
+    async fn test_no_warning_on_loopback() {
+        let server = create_test_server("127.0.0.1");
+
+        let response = server.get("/api/health").await;
+        assert!(response.maybe_header(EXPOSURE_WARNING_HEADER).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_warning_when_exposed() {
+        let server = create_test_server("0.0.0.0");
+
+        let response = server.get("/api/health").await;
+        assert_eq!(response.header(EXPOSURE_WARNING_HEADER), EXPOSURE_WARNING);
+    }
+}
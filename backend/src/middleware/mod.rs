@@ -0,0 +1,11 @@
+//! HTTP middleware applied to the application router
+
+pub mod api_version;
+pub mod auth;
+pub mod base_path;
+pub mod compression;
+pub mod cors;
+pub mod csrf;
+pub mod exposure;
+pub mod rate_limit;
+pub mod read_only;
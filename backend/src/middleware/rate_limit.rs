@@ -0,0 +1,271 @@
+//! Per-client rate limiting for expensive endpoints
+//!
+//! Clone, scan, run and git write endpoints spawn processes or touch the
+//! network, so each client gets a token bucket of `rate_limit_burst` requests
+//! that refills at `rate_limit_per_minute`. Clients are identified by their
+//! peer IP address.
+
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{ConnectInfo, MatchedPath, Request, State},
+    http::{header, HeaderValue, Method},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::api::AppState;
+use crate::error::AppError;
+use crate::settings::RateLimitSettings;
+
+/// Routes that are rate limited, as registered on the router
+const LIMITED_ROUTES: [&str; 32] = [
+    "/api/repos/clone",
+    "/api/repos/clone-progress",
+    "/api/repos/scan",
+    "/api/sessions/{id}/run",
+    "/api/sessions/{id}/git/pull",
+    "/api/sessions/{id}/git/push",
+    "/api/sessions/{id}/git/commit",
+    "/api/sessions/{id}/git/reset",
+    "/api/sessions/{id}/git/checkout",
+    "/api/git/{repo_id}/stash/{index}",
+    "/api/git/{repo_id}/stash/{index}/pop",
+    "/api/git/{repo_id}/stage",
+    "/api/git/{repo_id}/unstage",
+    "/api/git/{repo_id}/fetch-progress",
+    "/api/git/{repo_id}/tags/{name}/push",
+    "/api/git/{repo_id}/remotes/{name}",
+    "/api/git/{repo_id}/merge",
+    "/api/git/{repo_id}/merge/continue",
+    "/api/git/{repo_id}/merge/abort",
+    "/api/git/{repo_id}/rebase",
+    "/api/git/{repo_id}/rebase/continue",
+    "/api/git/{repo_id}/rebase/abort",
+    "/api/git/{repo_id}/cherry-pick",
+    "/api/git/{repo_id}/cherry-pick/continue",
+    "/api/git/{repo_id}/cherry-pick/abort",
+    "/api/git/{repo_id}/revert",
+    "/api/git/{repo_id}/revert/continue",
+    "/api/git/{repo_id}/revert/abort",
+    "/api/sessions/{id}/pull-request",
+    "/api/hooks/trigger",
+    "/api/snapshots/{id}/restore",
+    "/api/repos/{id}/terminal",
+];
+
+/// Routes that also serve a cheap read, e.g. a listing that shares its path
+/// with the endpoint that adds to it; only their writes are rate limited
+const LIMITED_WRITE_ROUTES: [&str; 3] = [
+    "/api/git/{repo_id}/stash",
+    "/api/git/{repo_id}/remotes",
+    "/api/git/{repo_id}/identity",
+];
+
+/// Number of tracked clients above which idle buckets are dropped
+const PRUNE_THRESHOLD: usize = 1024;
+
+/// Token buckets keyed by client address
+#[derive(Clone)]
+pub struct RateLimiter {
+    settings: RateLimitSettings,
+    buckets: Arc<Mutex<HashMap<IpAddr, Bucket>>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(settings: RateLimitSettings) -> Self {
+        Self {
+            settings,
+            buckets: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Take a token for a client
+    ///
+    /// Returns how long the client must wait if its bucket is empty.
+    pub fn check(&self, client: IpAddr, now: Instant) -> Result<(), Duration> {
+        if !self.settings.enabled {
+            return Ok(());
+        }
+
+        let capacity = f64::from(self.settings.burst);
+        let per_second = f64::from(self.settings.per_minute) / 60.0;
+
+        let mut buckets = self.buckets.lock().unwrap();
+
+        if buckets.len() >= PRUNE_THRESHOLD {
+            buckets.retain(|_, bucket| refill(*bucket, now, capacity, per_second) < capacity);
+        }
+
+        let bucket = buckets.entry(client).or_insert(Bucket {
+            tokens: capacity,
+            updated: now,
+        });
+        bucket.tokens = refill(*bucket, now, capacity, per_second);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / per_second))
+        }
+    }
+}
+
+/// Tokens in a bucket after refilling it up to `now`
+fn refill(bucket: Bucket, now: Instant, capacity: f64, per_second: f64) -> f64 {
+    let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+    (bucket.tokens + elapsed * per_second).min(capacity)
+}
+
+/// Reject requests to expensive endpoints once a client exceeds its limit
+pub async fn limit_expensive(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    let limited = req.extensions().get::<MatchedPath>().is_some_and(|path| {
+        LIMITED_ROUTES.contains(&path.as_str())
+            || (req.method() != Method::GET && LIMITED_WRITE_ROUTES.contains(&path.as_str()))
+    });
+    if !limited {
+        return next.run(req).await;
+    }
+
+    // Without connection info (e.g. in tests) all requests share one bucket
+    let client = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip())
+        .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+
+    match state.rate_limiter.check(client, Instant::now()) {
+        Ok(()) => next.run(req).await,
+        Err(retry_after) => {
+            let secs = retry_after.as_secs_f64().ceil() as u64;
+            tracing::warn!("Rate limited {} on {}", client, req.uri().path());
+
+            let mut response = AppError::TooManyRequests(format!(
+                "Too many requests, retry in {} seconds",
+                secs
+            ))
+            .into_response();
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(secs));
+            response
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_app;
+    use crate::db::Database;
+    use crate::settings::Settings;
+    use axum::http::StatusCode;
+    use axum_test::TestServer;
+
+    fn settings(burst: u32, per_minute: u32) -> RateLimitSettings {
+        RateLimitSettings {
+            enabled: true,
+            burst,
+            per_minute,
+        }
+    }
+
+    #[test]
+    fn test_burst_then_refill() {
+        let limiter = RateLimiter::new(settings(2, 60));
+        let client = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        let start = Instant::now();
+
+        assert!(limiter.check(client, start).is_ok());
+        assert!(limiter.check(client, start).is_ok());
+        let retry_after = limiter.check(client, start).unwrap_err();
+        assert_eq!(retry_after.as_secs_f64().ceil() as u64, 1);
+
+        // One token per second at 60 per minute
+        assert!(limiter.check(client, start + Duration::from_secs(1)).is_ok());
+        assert!(limiter.check(client, start + Duration::from_secs(1)).is_err());
+    }
+
+    #[test]
+    fn test_clients_are_limited_separately() {
+        let limiter = RateLimiter::new(settings(1, 1));
+        let now = Instant::now();
+
+        assert!(limiter.check("10.0.0.1".parse().unwrap(), now).is_ok());
+        assert!(limiter.check("10.0.0.1".parse().unwrap(), now).is_err());
+        assert!(limiter.check("10.0.0.2".parse().unwrap(), now).is_ok());
+    }
+
+    #[test]
+    fn test_disabled_never_limits() {
+        let limiter = RateLimiter::new(RateLimitSettings {
+            enabled: false,
+            ..settings(1, 1)
+        });
+        let now = Instant::now();
+
+        for _ in 0..5 {
+            assert!(limiter.check(IpAddr::V4(Ipv4Addr::LOCALHOST), now).is_ok());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_limits_expensive_routes_only() {
+        let db = Database::in_memory().expect("Failed to create test database");
+        let state = AppState::with_settings(
+            db,
+            Settings {
+                rate_limit: settings(1, 1),
+                ..Settings::default()
+            },
+        );
+        let server = TestServer::new(create_app(state)).expect("Failed to create test server");
+
+        let first = server.post("/api/repos/scan").json(&serde_json::json!({})).await;
+        assert_ne!(first.status_code(), StatusCode::TOO_MANY_REQUESTS);
+
+        let second = server.post("/api/repos/scan").json(&serde_json::json!({})).await;
+        second.assert_status(StatusCode::TOO_MANY_REQUESTS);
+        assert!(second.maybe_header(header::RETRY_AFTER).is_some());
+
+        // Cheap endpoints are unaffected
+        server.get("/api/repos").await.assert_status_ok();
+        server.get("/api/repos").await.assert_status_ok();
+    }
+
+    #[tokio::test]
+    async fn test_limits_writes_but_not_reads_of_shared_routes() {
+        let db = Database::in_memory().expect("Failed to create test database");
+        let state = AppState::with_settings(
+            db,
+            Settings {
+                rate_limit: settings(1, 1),
+                ..Settings::default()
+            },
+        );
+        let server = TestServer::new(create_app(state)).expect("Failed to create test server");
+        let url = format!("/api/git/{}/stash", uuid::Uuid::new_v4());
+
+        let first = server.post(&url).json(&serde_json::json!({})).await;
+        assert_ne!(first.status_code(), StatusCode::TOO_MANY_REQUESTS);
+        server
+            .post(&url)
+            .json(&serde_json::json!({}))
+            .await
+            .assert_status(StatusCode::TOO_MANY_REQUESTS);
+
+        // Listing stashes shares the path but isn't a write
+        assert_ne!(server.get(&url).await.status_code(), StatusCode::TOO_MANY_REQUESTS);
+    }
+}
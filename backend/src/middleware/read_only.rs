@@ -0,0 +1,122 @@
+//! Read-only mode
+//!
+//! With `read_only` set (or `--read-only` on the command line) every request
+//! that could change state is refused, so an instance can be used as a demo or
+//! to browse history from another machine without risk.
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::Method,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::api::AppState;
+use crate::error::AppError;
+
+/// Mutating routes that remain available in read-only mode
+///
+/// MCP messages are all POSTs; its tools that change state check read-only
+/// mode themselves. Each request in a batch is checked on its own.
+const ALLOWED_ROUTES: [&str; 3] = ["/api/auth/session", "/api/mcp", "/api/batch"];
+
+/// GET routes that nevertheless change state
+pub const MUTATING_GET_ROUTES: [&str; 2] = [
+    "/api/repos/clone-progress",
+    "/api/git/{repo_id}/fetch-progress",
+];
+
+/// The route template a request matched, e.g. `/api/git/{repo_id}/fetch-progress`,
+/// or its path when no route did
+///
+/// Only set once the router has picked a route, so callers must run as route layers.
+pub fn route(req: &Request) -> &str {
+    req.extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str())
+        .unwrap_or_else(|| req.uri().path())
+}
+
+/// Reject mutating requests while the server is read-only
+pub async fn reject_mutations(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    if !state.settings.read_only {
+        return next.run(req).await;
+    }
+
+    let route = route(&req);
+    let mutating = match *req.method() {
+        Method::GET | Method::HEAD | Method::OPTIONS => MUTATING_GET_ROUTES.contains(&route),
+        _ => !ALLOWED_ROUTES.contains(&route),
+    };
+
+    if mutating {
+        return AppError::Forbidden(format!(
+            "Ralphtown is in read-only mode; {} {} is disabled",
+            req.method(),
+            req.uri().path()
+        ))
+        .into_response();
+    }
+
+    next.run(req).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_app;
+    use crate::db::Database;
+    use crate::settings::Settings;
+    use axum::http::StatusCode;
+    use axum_test::TestServer;
+
+    fn create_test_server(read_only: bool) -> TestServer {
+        let db = Database::in_memory().expect("Failed to create test database");
+        let settings = Settings {
+            read_only,
+            ..Settings::default()
+        };
+        TestServer::new(create_app(AppState::with_settings(db, settings)))
+            .expect("Failed to create test server")
+    }
+
+    #[tokio::test]
+    async fn test_rejects_mutations_when_read_only() {
+        let server = create_test_server(true);
+
+        let response = server
+            .put("/api/config/theme")
+            .json(&serde_json::json!({ "value": "dark" }))
+            .await;
+        response.assert_status(StatusCode::FORBIDDEN);
+
+        let response = server
+            .delete(&format!("/api/sessions/{}", uuid::Uuid::new_v4()))
+            .await;
+        response.assert_status(StatusCode::FORBIDDEN);
+
+        let response = server
+            .get("/api/repos/clone-progress?url=https://example.com/repo.git")
+            .await;
+        response.assert_status(StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_allows_reads_when_read_only() {
+        let server = create_test_server(true);
+
+        server.get("/api/repos").await.assert_status_ok();
+        server.get("/api/config").await.assert_status_ok();
+    }
+
+    #[tokio::test]
+    async fn test_allows_mutations_by_default() {
+        let server = create_test_server(false);
+
+        server
+            .put("/api/config/theme")
+            .json(&serde_json::json!({ "value": "dark" }))
+            .await
+            .assert_status_ok();
+    }
+}
@@ -0,0 +1,202 @@
+//! Desktop notifications
+//!
+//! When `desktop_notifications` is set, the server pops up a notification on
+//! its own desktop as a session finishes or fails, so long autonomous runs can
+//! be left alone. Notifications are sent through the platform's own tooling
+//! (`notify-send`, `osascript`, or a PowerShell toast), and users can opt out
+//! of notifications for their sessions. The same notifications are pushed to
+//! phones when ntfy or Gotify is configured (see [`crate::push`]).
+
+use tokio::process::Command;
+use uuid::Uuid;
+
+use crate::db::models::SessionStatus;
+use crate::db::Database;
+use crate::push;
+
+/// A notification ready to show
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    pub title: String,
+    pub body: String,
+}
+
+/// Sends desktop notifications for finished sessions
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Notifier {
+    enabled: bool,
+}
+
+impl Notifier {
+    pub fn new(enabled: bool) -> Self {
+        Self { enabled }
+    }
+
+    /// Notify that a session finished, unless its owner opted out
+    ///
+    /// Desktop notifications are shown only when enabled for the server; push
+    /// notifications go to whichever destinations are configured.
+    pub async fn session_finished(&self, db: &Database, session_id: Uuid, status: SessionStatus) {
+        let Some(notification) = session_notification(db, session_id, status).await else {
+            return;
+        };
+        if self.enabled {
+            tokio::spawn(show(notification.clone()));
+        }
+        tokio::spawn(push::send(db.clone(), notification, status));
+    }
+}
+
+/// Build the notification for a finished session
+///
+/// Returns `None` for statuses that don't warrant one, or when the session's
+/// owner has turned notifications off.
+pub async fn session_notification(
+    db: &Database,
+    session_id: Uuid,
+    status: SessionStatus,
+) -> Option<Notification> {
+    let title = match status {
+        SessionStatus::Completed => "Ralph session completed",
+        SessionStatus::Error => "Ralph session failed",
+        _ => return None,
+    };
+
+    let body = db
+        .call(move |db| {
+            let session = db.get_session(session_id)?;
+            if let Some(owner_id) = session.owner_id
+                && db
+                    .get_user(owner_id)
+                    .is_ok_and(|owner| !owner.desktop_notifications)
+            {
+                return Ok(None);
+            }
+
+            let repo = db
+                .get_repo(session.repo_id)
+                .map(|repo| repo.name)
+                .unwrap_or_else(|_| "Unknown repo".to_string());
+            Ok(Some(match session.name {
+                Some(name) => format!("{}: {}", repo, name),
+                None => repo,
+            }))
+        })
+        .await
+        .ok()
+        .flatten()?;
+
+    Some(Notification {
+        title: title.to_string(),
+        body,
+    })
+}
+
+/// Show a notification using the platform's notification tool
+async fn show(notification: Notification) {
+    let Some(mut cmd) = notification_command(&notification) else {
+        tracing::debug!("Desktop notifications are not supported on this platform");
+        return;
+    };
+
+    match cmd.output().await {
+        Ok(output) if output.status.success() => {}
+        Ok(output) => tracing::warn!(
+            "Desktop notification failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ),
+        Err(e) => tracing::warn!("Failed to send desktop notification: {}", e),
+    }
+}
+
+/// Build the command that shows a notification
+///
+/// Title and body are passed as arguments or environment variables, never
+/// spliced into a script, so session names can't inject commands.
+#[cfg(target_os = "macos")]
+fn notification_command(notification: &Notification) -> Option<Command> {
+    let mut cmd = Command::new("osascript");
+    cmd.args([
+        "-e",
+        "on run argv",
+        "-e",
+        "display notification (item 2 of argv) with title (item 1 of argv)",
+        "-e",
+        "end run",
+        &notification.title,
+        &notification.body,
+    ]);
+    Some(cmd)
+}
+
+#[cfg(target_os = "windows")]
+fn notification_command(notification: &Notification) -> Option<Command> {
+    const SCRIPT: &str = r#"
+[Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] | Out-Null
+$template = [Windows.UI.Notifications.ToastNotificationManager]::GetTemplateContent([Windows.UI.Notifications.ToastTemplateType]::ToastText02)
+$text = $template.GetElementsByTagName('text')
+$text.Item(0).AppendChild($template.CreateTextNode($env:RALPHTOWN_TITLE)) | Out-Null
+$text.Item(1).AppendChild($template.CreateTextNode($env:RALPHTOWN_BODY)) | Out-Null
+[Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier('Ralphtown').Show([Windows.UI.Notifications.ToastNotification]::new($template))
+"#;
+    let mut cmd = Command::new("powershell");
+    cmd.args(["-NoProfile", "-NonInteractive", "-Command", SCRIPT])
+        .env("RALPHTOWN_TITLE", &notification.title)
+        .env("RALPHTOWN_BODY", &notification.body);
+    Some(cmd)
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn notification_command(notification: &Notification) -> Option<Command> {
+    let mut cmd = Command::new("notify-send");
+    cmd.args(["--app-name=Ralphtown", "--", &notification.title, &notification.body]);
+    Some(cmd)
+}
+
+#[cfg(not(any(unix, target_os = "windows")))]
+fn notification_command(_notification: &Notification) -> Option<Command> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::{Orchestrator, UserRole};
+
+    #[tokio::test]
+    async fn test_session_notification() {
+        let db = Database::in_memory().expect("Failed to create test database");
+        let repo = db.insert_repo("/path/to/repo", "my-repo").unwrap();
+        let session = db
+            .insert_session(repo.id, Some("Fix login"), Orchestrator::Ralph)
+            .unwrap();
+
+        assert_eq!(
+            session_notification(&db, session.id, SessionStatus::Completed).await,
+            Some(Notification {
+                title: "Ralph session completed".to_string(),
+                body: "my-repo: Fix login".to_string(),
+            })
+        );
+        assert_eq!(
+            session_notification(&db, session.id, SessionStatus::Error).await.map(|n| n.title),
+            Some("Ralph session failed".to_string())
+        );
+        assert_eq!(session_notification(&db, session.id, SessionStatus::Running).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_owner_can_opt_out() {
+        let db = Database::in_memory().expect("Failed to create test database");
+        let user = db.insert_user("alice", UserRole::Member, "hash").unwrap();
+        let repo = db.insert_repo("/path/to/repo", "my-repo").unwrap();
+        let session = db
+            .insert_session_for(repo.id, None, Orchestrator::Ralph, Some(user.id))
+            .unwrap();
+
+        assert!(session_notification(&db, session.id, SessionStatus::Completed).await.is_some());
+
+        db.set_user_desktop_notifications(user.id, false).unwrap();
+        assert!(session_notification(&db, session.id, SessionStatus::Completed).await.is_none());
+    }
+}
@@ -0,0 +1,291 @@
+//! Orchestrators declared in configuration
+//!
+//! Besides the built-in adapters (see [`crate::ralph::adapter`]), any CLI
+//! that takes a prompt can be used as an orchestrator by declaring it in the
+//! `orchestrators` config key as a JSON array:
+//!
+//! ```json
+//! [{
+//!   "id": "opencode",
+//!   "name": "OpenCode",
+//!   "command": ["opencode", "run", "{prompt}"],
+//!   "parser": "plain",
+//!   "completed_pattern": "^Done",
+//!   "error_pattern": "^Error:",
+//!   "iteration_pattern": "^--- Round (\\d+)"
+//! }]
+//! ```
+//!
+//! With the `json` parser, the `channel_field` of each line (`type` unless
+//! set) tells what kind of output it is: `tool_use`, `tool_call`,
+//! `tool_result`, or anything else starting with `tool` is a tool call,
+//! `system` is system output, and anything else is the agent's prose.
+//!
+//! Declared orchestrators become selectable when creating sessions after the
+//! next restart. Runs use the same process tracking, output capture, and
+//! cancellation as Ralph.
+
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::db::models::{Orchestrator, OutputChannel, SessionStatus};
+use crate::ralph::OrchestratorAdapter;
+
+/// Placeholder in a command template replaced with the run's prompt
+pub const PROMPT_PLACEHOLDER: &str = "{prompt}";
+
+/// How an orchestrator's output lines are turned into session output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputParser {
+    /// Each line is shown as-is
+    #[default]
+    Plain,
+    /// Lines are JSON objects; the text field is shown, other lines as-is
+    Json,
+}
+
+/// An orchestrator declared in configuration
+#[derive(Debug, Clone)]
+pub struct OrchestratorPlugin {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    /// Program followed by its arguments, with `{prompt}` placeholders
+    pub command: Vec<String>,
+    pub parser: OutputParser,
+    /// Field holding the text of JSON output lines
+    pub text_field: String,
+    /// Field of JSON output lines naming their kind, which picks their channel
+    pub channel_field: String,
+    /// Output line that marks the run as completed, whatever the exit code
+    pub completed_pattern: Option<Regex>,
+    /// Output line that marks the run as failed, whatever the exit code
+    pub error_pattern: Option<Regex>,
+    /// Output line that starts a new iteration; its first group is the number
+    pub iteration_pattern: Option<Regex>,
+}
+
+/// Declaration as written in the `orchestrators` config key
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PluginConfig {
+    id: String,
+    name: Option<String>,
+    description: Option<String>,
+    command: Vec<String>,
+    #[serde(default)]
+    parser: OutputParser,
+    text_field: Option<String>,
+    channel_field: Option<String>,
+    completed_pattern: Option<String>,
+    error_pattern: Option<String>,
+    iteration_pattern: Option<String>,
+}
+
+/// Parse the `orchestrators` config value
+pub fn parse_plugins(value: &str) -> Result<Vec<OrchestratorPlugin>, String> {
+    if value.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let configs: Vec<PluginConfig> =
+        serde_json::from_str(value).map_err(|e| format!("expected a JSON array of orchestrators: {}", e))?;
+
+    let mut plugins: Vec<OrchestratorPlugin> = Vec::with_capacity(configs.len());
+    for config in configs {
+        if !matches!(Orchestrator::from_str(&config.id), Ok(Orchestrator::Plugin(_))) {
+            return Err(format!(
+                "'{}' is not a valid orchestrator id (use lowercase letters, digits, '-' and '_', and not a built-in name)",
+                config.id
+            ));
+        }
+        if plugins.iter().any(|plugin| plugin.id == config.id) {
+            return Err(format!("orchestrator '{}' is declared twice", config.id));
+        }
+        if config.command.first().is_none_or(|program| program.trim().is_empty()) {
+            return Err(format!("orchestrator '{}' has no command", config.id));
+        }
+        if !config.command.iter().any(|arg| arg.contains(PROMPT_PLACEHOLDER)) {
+            return Err(format!(
+                "the command of orchestrator '{}' must include {}",
+                config.id, PROMPT_PLACEHOLDER
+            ));
+        }
+
+        let pattern = |pattern: Option<String>| {
+            pattern
+                .map(|p| Regex::new(&p))
+                .transpose()
+                .map_err(|e| format!("invalid pattern for orchestrator '{}': {}", config.id, e))
+        };
+        plugins.push(OrchestratorPlugin {
+            name: config.name.unwrap_or_else(|| config.id.clone()),
+            completed_pattern: pattern(config.completed_pattern)?,
+            error_pattern: pattern(config.error_pattern)?,
+            iteration_pattern: pattern(config.iteration_pattern)?,
+            id: config.id,
+            description: config.description,
+            command: config.command,
+            parser: config.parser,
+            text_field: config.text_field.unwrap_or_else(|| "text".to_string()),
+            channel_field: config.channel_field.unwrap_or_else(|| "type".to_string()),
+        });
+    }
+
+    Ok(plugins)
+}
+
+impl OrchestratorAdapter for OrchestratorPlugin {
+    fn program(&self) -> &str {
+        &self.command[0]
+    }
+
+    fn args(&self, prompt: &str) -> Vec<String> {
+        self.command[1..]
+            .iter()
+            .map(|arg| arg.replace(PROMPT_PLACEHOLDER, prompt))
+            .collect()
+    }
+
+    fn install_help(&self) -> Vec<String> {
+        vec![
+            format!("Install {}", self.program()),
+            format!(
+                "Or fix the command of the '{}' orchestrator in the orchestrators setting",
+                self.id
+            ),
+            "Restart Ralphtown after changing the setting".to_string(),
+        ]
+    }
+
+    fn parse_line(&self, line: String) -> (String, Option<OutputChannel>) {
+        let OutputParser::Json = self.parser else {
+            return (line, None);
+        };
+        let Ok(Value::Object(fields)) = serde_json::from_str::<Value>(&line) else {
+            return (line, None);
+        };
+        let channel = match fields.get(&self.channel_field) {
+            Some(Value::String(kind)) => Some(channel_for_kind(kind)),
+            _ => None,
+        };
+        match fields.get(&self.text_field) {
+            Some(Value::String(text)) => (text.clone(), channel),
+            _ => (line, channel),
+        }
+    }
+
+    fn iteration_for(&self, line: &str) -> Option<Option<u32>> {
+        self.iteration_pattern.as_ref().and_then(|p| iteration_number(p, line))
+    }
+
+    fn status_for(&self, line: &str) -> Option<SessionStatus> {
+        if self.error_pattern.as_ref().is_some_and(|p| p.is_match(line)) {
+            Some(SessionStatus::Error)
+        } else if self.completed_pattern.as_ref().is_some_and(|p| p.is_match(line)) {
+            Some(SessionStatus::Completed)
+        } else {
+            None
+        }
+    }
+}
+
+/// Match an iteration pattern against a line, reading the number from its first group
+pub fn iteration_number(pattern: &Regex, line: &str) -> Option<Option<u32>> {
+    let captures = pattern.captures(line)?;
+    Some(captures.get(1).and_then(|m| m.as_str().parse().ok()))
+}
+
+/// Channel of a JSON output line with the given kind
+fn channel_for_kind(kind: &str) -> OutputChannel {
+    let kind = kind.to_ascii_lowercase();
+    if kind.starts_with("tool") {
+        OutputChannel::ToolCall
+    } else if kind == "system" {
+        OutputChannel::System
+    } else {
+        OutputChannel::AssistantText
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_plugins() {
+        let plugins = parse_plugins(
+            r#"[{
+                "id": "opencode",
+                "name": "OpenCode",
+                "command": ["opencode", "run", "{prompt}"],
+                "error_pattern": "^Error:"
+            }]"#,
+        )
+        .unwrap();
+        assert_eq!(plugins.len(), 1);
+        assert_eq!(plugins[0].program(), "opencode");
+        assert_eq!(plugins[0].args("fix it"), vec!["run", "fix it"]);
+        assert_eq!(plugins[0].parser, OutputParser::Plain);
+
+        assert!(parse_plugins("").unwrap().is_empty());
+        assert!(parse_plugins("{}").is_err());
+        assert!(parse_plugins(r#"[{"id": "ralph", "command": ["x", "{prompt}"]}]"#).is_err());
+        assert!(parse_plugins(r#"[{"id": "aider", "command": ["aider", "{prompt}"]}]"#).is_err());
+        assert!(parse_plugins(r#"[{"id": "Bad Id", "command": ["x", "{prompt}"]}]"#).is_err());
+        assert!(parse_plugins(r#"[{"id": "x", "command": ["x"]}]"#).is_err());
+        assert!(parse_plugins(r#"[{"id": "x", "command": []}]"#).is_err());
+        assert!(parse_plugins(
+            r#"[{"id": "x", "command": ["x", "{prompt}"]}, {"id": "x", "command": ["y", "{prompt}"]}]"#
+        )
+        .is_err());
+        assert!(
+            parse_plugins(r#"[{"id": "x", "command": ["x", "{prompt}"], "error_pattern": "("}]"#)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_output_parsing_and_status() {
+        let plugin = parse_plugins(
+            r#"[{
+                "id": "agent",
+                "command": ["agent", "{prompt}"],
+                "parser": "json",
+                "text_field": "message",
+                "completed_pattern": "done",
+                "error_pattern": "failed",
+                "iteration_pattern": "^round (\\d+)"
+            }]"#,
+        )
+        .unwrap()
+        .remove(0);
+
+        assert_eq!(plugin.parse_line(r#"{"message": "hello"}"#.to_string()), ("hello".to_string(), None));
+        assert_eq!(
+            plugin.parse_line(r#"{"other": 1}"#.to_string()),
+            (r#"{"other": 1}"#.to_string(), None)
+        );
+        assert_eq!(plugin.parse_line("not json".to_string()), ("not json".to_string(), None));
+        assert_eq!(
+            plugin.parse_line(r#"{"type": "tool_use", "message": "read src/main.rs"}"#.to_string()),
+            ("read src/main.rs".to_string(), Some(OutputChannel::ToolCall))
+        );
+        assert_eq!(
+            plugin.parse_line(r#"{"type": "system", "message": "model: x"}"#.to_string()).1,
+            Some(OutputChannel::System)
+        );
+        assert_eq!(
+            plugin.parse_line(r#"{"type": "assistant", "message": "Done."}"#.to_string()).1,
+            Some(OutputChannel::AssistantText)
+        );
+
+        assert_eq!(plugin.status_for("all done"), Some(SessionStatus::Completed));
+        assert_eq!(plugin.status_for("done, but failed"), Some(SessionStatus::Error));
+        assert_eq!(plugin.status_for("working"), None);
+
+        assert_eq!(plugin.iteration_for("round 3 of 10"), Some(Some(3)));
+        assert_eq!(plugin.iteration_for("still on round 3"), None);
+    }
+}
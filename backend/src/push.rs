@@ -0,0 +1,204 @@
+//! Push notifications through ntfy and Gotify
+//!
+//! Session completions and failures can be pushed to phones without an
+//! app-store app: ntfy delivers to anyone subscribed to a topic (including
+//! through its installable web app), and Gotify to a self-hosted server. The
+//! destinations are read from config each time a notification is sent, so
+//! they can be changed without a restart. Delivery is best effort; failures
+//! are logged and not retried.
+
+use std::time::Duration;
+
+use crate::db::models::SessionStatus;
+use crate::db::{Database, DbResult};
+use crate::notify::Notification;
+use crate::settings::{GOTIFY_TOKEN_KEY, GOTIFY_URL_KEY, NTFY_TOKEN_KEY, NTFY_URL_KEY};
+
+/// How long a single push request may take
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A configured push destination
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PushTarget {
+    /// An ntfy topic URL, with an access token for protected topics
+    Ntfy { url: String, token: Option<String> },
+    /// A Gotify server and application token
+    Gotify { url: String, token: String },
+}
+
+impl PushTarget {
+    fn name(&self) -> &'static str {
+        match self {
+            PushTarget::Ntfy { .. } => "ntfy",
+            PushTarget::Gotify { .. } => "Gotify",
+        }
+    }
+
+    /// Build the request delivering `notification`
+    ///
+    /// Failures are sent at a higher priority so they stand out on the phone.
+    fn request(
+        &self,
+        client: &reqwest::Client,
+        notification: &Notification,
+        failed: bool,
+    ) -> reqwest::RequestBuilder {
+        match self {
+            PushTarget::Ntfy { url, token } => {
+                let request = client
+                    .post(url)
+                    .header("Title", &notification.title)
+                    .header("Priority", if failed { "high" } else { "default" })
+                    .header("Tags", if failed { "x" } else { "white_check_mark" })
+                    .body(notification.body.clone());
+                match token {
+                    Some(token) => request.bearer_auth(token),
+                    None => request,
+                }
+            }
+            PushTarget::Gotify { url, token } => client
+                .post(format!("{}/message", url.trim_end_matches('/')))
+                .header("X-Gotify-Key", token)
+                .json(&serde_json::json!({
+                    "title": notification.title,
+                    "message": notification.body,
+                    "priority": if failed { 8 } else { 5 },
+                })),
+        }
+    }
+}
+
+/// A config value, treating blank values as unset
+fn config(db: &Database, key: &str) -> DbResult<Option<String>> {
+    Ok(db
+        .get_config(key)?
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty()))
+}
+
+/// The push destinations currently configured
+///
+/// Gotify is only used once both its URL and application token are set.
+pub fn targets(db: &Database) -> DbResult<Vec<PushTarget>> {
+    let mut targets = Vec::new();
+    if let Some(url) = config(db, NTFY_URL_KEY)? {
+        targets.push(PushTarget::Ntfy {
+            url,
+            token: config(db, NTFY_TOKEN_KEY)?,
+        });
+    }
+    if let (Some(url), Some(token)) = (config(db, GOTIFY_URL_KEY)?, config(db, GOTIFY_TOKEN_KEY)?) {
+        targets.push(PushTarget::Gotify { url, token });
+    }
+    Ok(targets)
+}
+
+/// Push a finished session's notification to every configured destination
+pub async fn send(db: Database, notification: Notification, status: SessionStatus) {
+    let targets = match db.call(targets).await {
+        Ok(targets) => targets,
+        Err(e) => {
+            tracing::warn!("Failed to read push notification settings: {}", e);
+            return;
+        }
+    };
+    if targets.is_empty() {
+        return;
+    }
+
+    let client = match reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(e) => {
+            tracing::warn!("Failed to create push notification client: {}", e);
+            return;
+        }
+    };
+
+    let failed = status == SessionStatus::Error;
+    for target in targets {
+        match target.request(&client, &notification, failed).send().await {
+            Ok(response) if response.status().is_success() => {}
+            Ok(response) => tracing::warn!(
+                "{} push notification failed with status {}",
+                target.name(),
+                response.status()
+            ),
+            Err(e) => tracing::warn!("Failed to send {} push notification: {}", target.name(), e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::forge::serve_fake_api;
+    use axum::{body::Bytes, http::HeaderMap, routing::post, Router};
+
+    #[test]
+    fn test_targets_from_config() {
+        let db = Database::in_memory().expect("Failed to create test database");
+        assert!(targets(&db).unwrap().is_empty());
+
+        db.set_config(NTFY_URL_KEY, "https://ntfy.sh/ralph-alerts").unwrap();
+        db.set_config(GOTIFY_URL_KEY, "https://gotify.example.com").unwrap();
+        assert_eq!(
+            targets(&db).unwrap(),
+            vec![PushTarget::Ntfy {
+                url: "https://ntfy.sh/ralph-alerts".to_string(),
+                token: None,
+            }]
+        );
+
+        // Gotify needs an application token as well as a URL
+        db.set_config(GOTIFY_TOKEN_KEY, "app-token").unwrap();
+        assert_eq!(targets(&db).unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_send_to_ntfy_and_gotify() {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(4);
+        let app = Router::new()
+            .route(
+                "/alerts",
+                post({
+                    let tx = tx.clone();
+                    |headers: HeaderMap, body: Bytes| async move {
+                        tx.send(("ntfy", headers, body)).await.unwrap();
+                    }
+                }),
+            )
+            .route(
+                "/message",
+                post(|headers: HeaderMap, body: Bytes| async move {
+                    tx.send(("gotify", headers, body)).await.unwrap();
+                }),
+            );
+        let url = serve_fake_api(app).await;
+
+        let db = Database::in_memory().expect("Failed to create test database");
+        db.set_config(NTFY_URL_KEY, &format!("{}/alerts", url)).unwrap();
+        db.set_config(NTFY_TOKEN_KEY, "tk_secret").unwrap();
+        db.set_config(GOTIFY_URL_KEY, &format!("{}/", url)).unwrap();
+        db.set_config(GOTIFY_TOKEN_KEY, "app-token").unwrap();
+
+        let notification = Notification {
+            title: "Ralph session failed".to_string(),
+            body: "my-repo: Fix login".to_string(),
+        };
+        send(db, notification, SessionStatus::Error).await;
+
+        let (target, headers, body) = rx.recv().await.unwrap();
+        assert_eq!(target, "ntfy");
+        assert_eq!(headers["title"], "Ralph session failed");
+        assert_eq!(headers["priority"], "high");
+        assert_eq!(headers["authorization"], "Bearer tk_secret");
+        assert_eq!(&body[..], b"my-repo: Fix login");
+
+        let (target, headers, body) = rx.recv().await.unwrap();
+        assert_eq!(target, "gotify");
+        assert_eq!(headers["x-gotify-key"], "app-token");
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["message"], "my-repo: Fix login");
+        assert_eq!(body["priority"], 8);
+    }
+}
@@ -0,0 +1,317 @@
+//! Orchestrator adapters
+//!
+//! An adapter knows how to drive one orchestrator's CLI: the command line
+//! that runs a prompt, what its output lines mean, and what its exit code
+//! says about the run. Runs look up the adapter for their session's
+//! orchestrator; besides the built-in ones below, each orchestrator declared
+//! in configuration is its own adapter (see [`crate::orchestrator`]).
+
+use std::sync::{Arc, OnceLock};
+
+use regex::Regex;
+use serde_json::Value;
+
+use crate::db::models::{Orchestrator, OutputChannel, SessionStatus};
+use crate::orchestrator::iteration_number;
+
+/// How to run an orchestrator and read its output
+pub trait OrchestratorAdapter: Send + Sync {
+    /// Program the command runs
+    fn program(&self) -> &str;
+
+    /// Arguments for a run with `prompt`
+    fn args(&self, prompt: &str) -> Vec<String>;
+
+    /// What to do when the program can't be found
+    fn install_help(&self) -> Vec<String>;
+
+    /// Text to show for an output line, and its channel when the line tells
+    fn parse_line(&self, line: String) -> (String, Option<OutputChannel>) {
+        (line, None)
+    }
+
+    /// Whether an output line starts a new iteration, and its number if it has one
+    fn iteration_for(&self, _line: &str) -> Option<Option<u32>> {
+        None
+    }
+
+    /// Status an output line settles the run on, whatever the exit code
+    fn status_for(&self, _line: &str) -> Option<SessionStatus> {
+        None
+    }
+
+    /// Status of a run its output didn't settle, from its exit code (`None`
+    /// when a signal ended it)
+    fn exit_status(&self, code: Option<i32>) -> SessionStatus {
+        match code {
+            Some(0) => SessionStatus::Completed,
+            _ => SessionStatus::Error,
+        }
+    }
+
+    /// Whether the program can be found
+    fn is_installed(&self) -> bool {
+        which::which(self.program()).is_ok()
+    }
+}
+
+/// The adapter for a built-in orchestrator; `None` for configured ones
+pub fn builtin(orchestrator: &Orchestrator) -> Option<Arc<dyn OrchestratorAdapter>> {
+    match orchestrator {
+        // GSD and Gastown have no CLI of their own yet and run Ralph
+        Orchestrator::Ralph | Orchestrator::Gsd | Orchestrator::Gastown => Some(Arc::new(Ralph)),
+        Orchestrator::Claude => Some(Arc::new(Claude)),
+        Orchestrator::Aider => Some(Arc::new(Aider)),
+        Orchestrator::Gemini => Some(Arc::new(Gemini)),
+        Orchestrator::Plugin(_) => None,
+    }
+}
+
+/// `ralph run --autonomous`
+pub struct Ralph;
+
+impl OrchestratorAdapter for Ralph {
+    fn program(&self) -> &str {
+        "ralph"
+    }
+
+    /// "--prompt=" keeps prompts starting with '-' from being parsed as flags
+    fn args(&self, prompt: &str) -> Vec<String> {
+        vec![
+            "run".to_string(),
+            "--autonomous".to_string(),
+            format!("--prompt={}", prompt),
+        ]
+    }
+
+    fn install_help(&self) -> Vec<String> {
+        vec![
+            "Install ralph: cargo install ralph".to_string(),
+            "Or download from release page".to_string(),
+            "Ensure ~/.cargo/bin is in your PATH".to_string(),
+            "Restart your terminal after installation".to_string(),
+        ]
+    }
+
+    /// Ralph's iterations start at lines like `=== Iteration 3 ===`
+    fn iteration_for(&self, line: &str) -> Option<Option<u32>> {
+        static PATTERN: OnceLock<Regex> = OnceLock::new();
+        let pattern = PATTERN
+            .get_or_init(|| Regex::new(r"(?i)^\W*iteration\s+#?(\d+)\b").expect("pattern is valid"));
+        iteration_number(pattern, line)
+    }
+}
+
+/// Claude Code in print mode, streaming JSON events
+pub struct Claude;
+
+impl OrchestratorAdapter for Claude {
+    fn program(&self) -> &str {
+        "claude"
+    }
+
+    /// "--" keeps prompts starting with '-' from being parsed as flags
+    fn args(&self, prompt: &str) -> Vec<String> {
+        [
+            "--print",
+            "--output-format",
+            "stream-json",
+            "--verbose",
+            "--dangerously-skip-permissions",
+            "--",
+            prompt,
+        ]
+        .map(String::from)
+        .to_vec()
+    }
+
+    fn install_help(&self) -> Vec<String> {
+        vec![
+            "Install Claude Code: npm install -g @anthropic-ai/claude-code".to_string(),
+            "Run claude once to sign in".to_string(),
+            "Restart Ralphtown so it finds claude in PATH".to_string(),
+        ]
+    }
+
+    /// Each event is a JSON object: assistant messages hold text and tool
+    /// calls, user messages hold tool results, and the last event is the result
+    fn parse_line(&self, line: String) -> (String, Option<OutputChannel>) {
+        let Some(event) = claude_event(&line) else {
+            return (line, None);
+        };
+        match event.get("type").and_then(Value::as_str) {
+            Some("assistant" | "user") => {
+                let blocks = event
+                    .get("message")
+                    .and_then(|message| message.get("content"))
+                    .and_then(Value::as_array);
+                let mut texts = Vec::new();
+                let mut tool = false;
+                for block in blocks.into_iter().flatten() {
+                    match block.get("type").and_then(Value::as_str) {
+                        Some("text") => {
+                            texts.extend(block.get("text").and_then(Value::as_str).map(String::from));
+                        }
+                        Some("tool_use") => {
+                            tool = true;
+                            let name = block.get("name").and_then(Value::as_str).unwrap_or("tool");
+                            let input = block.get("input").map(Value::to_string).unwrap_or_default();
+                            texts.push(format!("{} {}", name, input));
+                        }
+                        Some("tool_result") => {
+                            tool = true;
+                            texts.push(match block.get("content") {
+                                Some(Value::String(content)) => content.clone(),
+                                Some(content) => content.to_string(),
+                                None => String::new(),
+                            });
+                        }
+                        _ => {}
+                    }
+                }
+                if texts.is_empty() {
+                    return (line, None);
+                }
+                let channel = if tool {
+                    OutputChannel::ToolCall
+                } else {
+                    OutputChannel::AssistantText
+                };
+                (texts.join("\n"), Some(channel))
+            }
+            Some("result") => {
+                let text = event.get("result").and_then(Value::as_str).unwrap_or_default();
+                (text.to_string(), Some(OutputChannel::System))
+            }
+            Some(kind) => {
+                let subtype = event.get("subtype").and_then(Value::as_str).unwrap_or_default();
+                (format!("{} {}", kind, subtype).trim_end().to_string(), Some(OutputChannel::System))
+            }
+            None => (line, None),
+        }
+    }
+
+    /// Each assistant turn is an iteration
+    fn iteration_for(&self, line: &str) -> Option<Option<u32>> {
+        let event = claude_event(line)?;
+        (event.get("type").and_then(Value::as_str) == Some("assistant")).then_some(None)
+    }
+
+    fn status_for(&self, line: &str) -> Option<SessionStatus> {
+        let event = claude_event(line)?;
+        if event.get("type").and_then(Value::as_str) != Some("result") {
+            return None;
+        }
+        match event.get("is_error").and_then(Value::as_bool) {
+            Some(true) => Some(SessionStatus::Error),
+            _ => Some(SessionStatus::Completed),
+        }
+    }
+}
+
+/// A line of Claude Code's output as a JSON event, if it is one
+fn claude_event(line: &str) -> Option<serde_json::Map<String, Value>> {
+    match serde_json::from_str::<Value>(line) {
+        Ok(Value::Object(event)) => Some(event),
+        _ => None,
+    }
+}
+
+/// Aider, applying its edits without asking
+pub struct Aider;
+
+impl OrchestratorAdapter for Aider {
+    fn program(&self) -> &str {
+        "aider"
+    }
+
+    fn args(&self, prompt: &str) -> Vec<String> {
+        vec![
+            "--yes-always".to_string(),
+            "--no-pretty".to_string(),
+            "--no-stream".to_string(),
+            format!("--message={}", prompt),
+        ]
+    }
+
+    fn install_help(&self) -> Vec<String> {
+        vec![
+            "Install aider: python -m pip install aider-install && aider-install".to_string(),
+            "Set the API key of your model provider, e.g. ANTHROPIC_API_KEY".to_string(),
+            "Restart Ralphtown so it finds aider in PATH".to_string(),
+        ]
+    }
+}
+
+/// Gemini CLI, non-interactive with every tool call approved
+pub struct Gemini;
+
+impl OrchestratorAdapter for Gemini {
+    fn program(&self) -> &str {
+        "gemini"
+    }
+
+    fn args(&self, prompt: &str) -> Vec<String> {
+        vec!["--yolo".to_string(), format!("--prompt={}", prompt)]
+    }
+
+    fn install_help(&self) -> Vec<String> {
+        vec![
+            "Install Gemini CLI: npm install -g @google/gemini-cli".to_string(),
+            "Run gemini once to sign in, or set GEMINI_API_KEY".to_string(),
+            "Restart Ralphtown so it finds gemini in PATH".to_string(),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_command_lines() {
+        let ralph = builtin(&Orchestrator::Ralph).unwrap();
+        assert_eq!(ralph.program(), "ralph");
+        assert_eq!(ralph.args("-fix"), vec!["run", "--autonomous", "--prompt=-fix"]);
+        assert_eq!(ralph.iteration_for("=== Iteration 2 ==="), Some(Some(2)));
+
+        let claude = builtin(&Orchestrator::Claude).unwrap();
+        assert_eq!(claude.args("-fix").last().map(String::as_str), Some("-fix"));
+        assert_eq!(builtin(&Orchestrator::Aider).unwrap().program(), "aider");
+        assert_eq!(builtin(&Orchestrator::Gemini).unwrap().args("go"), vec!["--yolo", "--prompt=go"]);
+        assert!(builtin(&Orchestrator::Plugin("mine".to_string())).is_none());
+
+        assert_eq!(ralph.exit_status(Some(0)), SessionStatus::Completed);
+        assert_eq!(ralph.exit_status(Some(2)), SessionStatus::Error);
+        assert_eq!(ralph.exit_status(None), SessionStatus::Error);
+    }
+
+    #[test]
+    fn test_claude_stream_json() {
+        let claude = Claude;
+        let text = r#"{"type":"assistant","message":{"content":[{"type":"text","text":"Reading the tests"}]}}"#;
+        assert_eq!(
+            claude.parse_line(text.to_string()),
+            ("Reading the tests".to_string(), Some(OutputChannel::AssistantText))
+        );
+        assert_eq!(claude.iteration_for(text), Some(None));
+
+        let tool = r#"{"type":"assistant","message":{"content":[{"type":"tool_use","name":"Read","input":{"file_path":"src/lib.rs"}}]}}"#;
+        assert_eq!(
+            claude.parse_line(tool.to_string()),
+            (r#"Read {"file_path":"src/lib.rs"}"#.to_string(), Some(OutputChannel::ToolCall))
+        );
+
+        let init = r#"{"type":"system","subtype":"init","model":"x"}"#;
+        assert_eq!(claude.parse_line(init.to_string()), ("system init".to_string(), Some(OutputChannel::System)));
+        assert_eq!(claude.status_for(init), None);
+
+        let done = r#"{"type":"result","subtype":"success","is_error":false,"result":"All tests pass"}"#;
+        assert_eq!(claude.parse_line(done.to_string()).0, "All tests pass");
+        assert_eq!(claude.status_for(done), Some(SessionStatus::Completed));
+        let failed = r#"{"type":"result","subtype":"error_max_turns","is_error":true}"#;
+        assert_eq!(claude.status_for(failed), Some(SessionStatus::Error));
+
+        assert_eq!(claude.parse_line("plain".to_string()), ("plain".to_string(), None));
+    }
+}
@@ -0,0 +1,126 @@
+//! Structured events in run output
+//!
+//! Orchestrators that stream machine-readable JSON, such as Claude Code with
+//! `--output-format stream-json`, print one event per line. An assistant
+//! event holds the text the agent wrote and the tools it called; those are
+//! kept as the session's messages rather than as output lines. Calls to the
+//! tools that change files are recorded as file edits.
+
+use serde_json::{json, Value};
+
+use crate::db::models::MessageKind;
+
+/// Tools that change the file named in their input
+const EDIT_TOOLS: [&str; 4] = ["Edit", "MultiEdit", "Write", "NotebookEdit"];
+
+/// Something the agent did, as reported in its output
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum RunEvent {
+    /// Text the agent wrote
+    Assistant(String),
+    /// A tool the agent called, with its input
+    ToolCall { name: String, input: Value },
+    /// A file the agent changed with a tool
+    FileEdit { path: String, tool: String },
+}
+
+impl RunEvent {
+    /// The event as a message's kind, text and details
+    pub(super) fn into_message(self) -> (MessageKind, String, Option<Value>) {
+        match self {
+            RunEvent::Assistant(text) => (MessageKind::Text, text, None),
+            RunEvent::ToolCall { name, input } => {
+                let data = json!({ "name": name, "input": input });
+                (MessageKind::ToolCall, name, Some(data))
+            }
+            RunEvent::FileEdit { path, tool } => {
+                let data = json!({ "path": path, "tool": tool });
+                (MessageKind::FileEdit, path, Some(data))
+            }
+        }
+    }
+}
+
+/// Events an assistant event line holds; `None` when the line isn't one
+pub(super) fn parse(line: &str) -> Option<Vec<RunEvent>> {
+    let Ok(Value::Object(event)) = serde_json::from_str::<Value>(line) else {
+        return None;
+    };
+    if event.get("type").and_then(Value::as_str) != Some("assistant") {
+        return None;
+    }
+    let blocks = event
+        .get("message")
+        .and_then(|message| message.get("content"))
+        .and_then(Value::as_array)?;
+
+    let events: Vec<RunEvent> = blocks
+        .iter()
+        .filter_map(|block| match block.get("type").and_then(Value::as_str) {
+            Some("text") => {
+                let text = block.get("text").and_then(Value::as_str)?.trim();
+                (!text.is_empty()).then(|| RunEvent::Assistant(text.to_string()))
+            }
+            Some("tool_use") => {
+                let name = block.get("name").and_then(Value::as_str).unwrap_or("tool").to_string();
+                let input = block.get("input").cloned().unwrap_or(Value::Null);
+                let path = input
+                    .get("file_path")
+                    .or_else(|| input.get("notebook_path"))
+                    .and_then(Value::as_str);
+                match path {
+                    Some(path) if EDIT_TOOLS.contains(&name.as_str()) => Some(RunEvent::FileEdit {
+                        path: path.to_string(),
+                        tool: name,
+                    }),
+                    _ => Some(RunEvent::ToolCall { name, input }),
+                }
+            }
+            _ => None,
+        })
+        .collect();
+    (!events.is_empty()).then_some(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_events() {
+        let line = r#"{"type":"assistant","message":{"content":[
+            {"type":"text","text":"Fixing the test"},
+            {"type":"tool_use","name":"Bash","input":{"command":"cargo test"}},
+            {"type":"tool_use","name":"Edit","input":{"file_path":"src/lib.rs","old_string":"a","new_string":"b"}}
+        ]}}"#
+            .replace('\n', "");
+        assert_eq!(
+            parse(&line),
+            Some(vec![
+                RunEvent::Assistant("Fixing the test".to_string()),
+                RunEvent::ToolCall {
+                    name: "Bash".to_string(),
+                    input: json!({ "command": "cargo test" }),
+                },
+                RunEvent::FileEdit {
+                    path: "src/lib.rs".to_string(),
+                    tool: "Edit".to_string(),
+                },
+            ])
+        );
+
+        let (kind, content, data) = RunEvent::FileEdit {
+            path: "src/lib.rs".to_string(),
+            tool: "Write".to_string(),
+        }
+        .into_message();
+        assert_eq!((kind, content.as_str()), (MessageKind::FileEdit, "src/lib.rs"));
+        assert_eq!(data, Some(json!({ "path": "src/lib.rs", "tool": "Write" })));
+
+        // Tool results, the final result and plain text stay output
+        assert_eq!(parse(r#"{"type":"user","message":{"content":[{"type":"tool_result","content":"ok"}]}}"#), None);
+        assert_eq!(parse(r#"{"type":"result","result":"Done"}"#), None);
+        assert_eq!(parse(r#"{"type":"assistant","message":{"content":[{"type":"thinking"}]}}"#), None);
+        assert_eq!(parse("=== Iteration 1 ==="), None);
+    }
+}
@@ -0,0 +1,202 @@
+//! Resource limits for runs
+//!
+//! An agent can loop without end, leak memory, or print until the disk is
+//! full. While a run goes it's checked against the configured limits: how
+//! long it has been running, how much memory its process group holds, and
+//! how much output it has written. A run over a limit is terminated and ends
+//! in error, with the limit as its failure.
+//!
+//! A run that writes no output for a while may be hung. It's reported as
+//! stalled, and, when configured, stopped the same way with a stalled failure.
+//!
+//! Memory is read from `/proc`, so the memory limit only applies on Linux.
+
+use std::time::Duration;
+
+use crate::ws::messages::ResourceLimit;
+
+/// How often a run is checked against its limits
+pub(super) const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+const MB: u64 = 1024 * 1024;
+
+/// Limits every local run is held to; `None` for no limit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RunLimits {
+    /// Longest a run may go
+    pub max_duration: Option<Duration>,
+    /// Most resident memory a run's processes may hold together, in bytes
+    pub max_memory_bytes: Option<u64>,
+    /// Most output a run may write, in bytes
+    pub max_output_bytes: Option<u64>,
+    /// Longest a run may go without writing output before it's stalled
+    pub stall_after: Option<Duration>,
+    /// Stop stalled runs rather than only warning about them
+    pub stop_stalled: bool,
+}
+
+/// A limit a run went over
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LimitExceeded {
+    pub limit: ResourceLimit,
+    /// The limit, in seconds for duration and in bytes otherwise
+    pub max: u64,
+    /// What the run reached, in the same unit
+    pub value: u64,
+}
+
+impl LimitExceeded {
+    /// What happened, for the session's output and failure
+    pub fn message(&self) -> String {
+        match self.limit {
+            ResourceLimit::Duration => format!(
+                "Run stopped after {} seconds, over its limit of {} seconds",
+                self.value, self.max
+            ),
+            ResourceLimit::Memory => format!(
+                "Run stopped using {} MB of memory, over its limit of {} MB",
+                self.value / MB,
+                self.max / MB
+            ),
+            ResourceLimit::Output => format!(
+                "Run stopped after writing {} MB of output, over its limit of {} MB",
+                self.value / MB,
+                self.max / MB
+            ),
+        }
+    }
+}
+
+impl RunLimits {
+    /// Whether no limit is set
+    pub fn is_empty(&self) -> bool {
+        self.max_duration.is_none()
+            && self.max_memory_bytes.is_none()
+            && self.max_output_bytes.is_none()
+            && self.stall_after.is_none()
+    }
+
+    /// Whether a run that last wrote output `idle` ago has stalled
+    pub(super) fn is_stalled(&self, idle: Duration) -> bool {
+        self.stall_after.is_some_and(|after| idle >= after)
+    }
+
+    /// The first limit a run is over, if any
+    ///
+    /// `pid` leads the run's process group. Reading memory scans `/proc`, so
+    /// call this off the async executor.
+    pub(super) fn check(&self, elapsed: Duration, pid: Option<u32>, output_bytes: u64) -> Option<LimitExceeded> {
+        if let Some(max) = self.max_duration
+            && elapsed > max
+        {
+            return Some(LimitExceeded {
+                limit: ResourceLimit::Duration,
+                max: max.as_secs(),
+                value: elapsed.as_secs(),
+            });
+        }
+        if let Some(max) = self.max_output_bytes
+            && output_bytes > max
+        {
+            return Some(LimitExceeded {
+                limit: ResourceLimit::Output,
+                max,
+                value: output_bytes,
+            });
+        }
+        if let Some(max) = self.max_memory_bytes
+            && let Some(used) = pid.and_then(group_memory_bytes)
+            && used > max
+        {
+            return Some(LimitExceeded {
+                limit: ResourceLimit::Memory,
+                max,
+                value: used,
+            });
+        }
+        None
+    }
+}
+
+/// Resident memory of the processes in a process group, in bytes
+#[cfg(target_os = "linux")]
+fn group_memory_bytes(pgid: u32) -> Option<u64> {
+    // SAFETY: sysconf only reads a system constant
+    let page_size = u64::try_from(unsafe { libc::sysconf(libc::_SC_PAGESIZE) }).ok()?;
+    let mut pages = 0;
+    for entry in std::fs::read_dir("/proc").ok()?.flatten() {
+        // Processes can exit while they're being read
+        let Ok(stat) = std::fs::read_to_string(entry.path().join("stat")) else {
+            continue;
+        };
+        // The command name may hold spaces and parentheses; the other fields
+        // follow its last ')', starting from the third: state, ppid, pgrp, ..., rss
+        let Some((_, fields)) = stat.rsplit_once(')') else {
+            continue;
+        };
+        let fields: Vec<&str> = fields.split_whitespace().collect();
+        if fields.get(2).and_then(|pgrp| pgrp.parse::<u32>().ok()) == Some(pgid) {
+            pages += fields.get(21).and_then(|rss| rss.parse::<u64>().ok()).unwrap_or(0);
+        }
+    }
+    Some(pages * page_size)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn group_memory_bytes(_pgid: u32) -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_limit_exceeded() {
+        let limits = RunLimits {
+            max_duration: Some(Duration::from_secs(60)),
+            max_memory_bytes: None,
+            max_output_bytes: Some(10 * MB),
+            ..Default::default()
+        };
+        assert_eq!(limits.check(Duration::from_secs(30), None, MB), None);
+
+        let exceeded = limits.check(Duration::from_secs(61), None, 20 * MB).unwrap();
+        assert_eq!(exceeded.limit, ResourceLimit::Duration);
+        assert_eq!((exceeded.max, exceeded.value), (60, 61));
+
+        let exceeded = limits.check(Duration::from_secs(30), None, 20 * MB).unwrap();
+        assert_eq!(exceeded.limit, ResourceLimit::Output);
+        assert_eq!(exceeded.message(), "Run stopped after writing 20 MB of output, over its limit of 10 MB");
+
+        assert!(RunLimits::default().is_empty());
+        assert!(!limits.is_empty());
+    }
+
+    #[test]
+    fn test_stalled() {
+        let limits = RunLimits {
+            stall_after: Some(Duration::from_secs(300)),
+            ..Default::default()
+        };
+        assert!(!limits.is_empty());
+        assert!(!limits.is_stalled(Duration::from_secs(299)));
+        assert!(limits.is_stalled(Duration::from_secs(300)));
+        assert!(!RunLimits::default().is_stalled(Duration::MAX));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_group_memory_of_own_group() {
+        // SAFETY: getpgrp has no preconditions
+        let pgid = unsafe { libc::getpgrp() } as u32;
+        assert!(group_memory_bytes(pgid).unwrap() > 0);
+
+        let limits = RunLimits {
+            max_memory_bytes: Some(1),
+            ..Default::default()
+        };
+        let exceeded = limits.check(Duration::ZERO, Some(pgid), 0).unwrap();
+        assert_eq!(exceeded.limit, ResourceLimit::Memory);
+    }
+}
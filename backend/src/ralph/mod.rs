@@ -1,46 +1,373 @@
 //! Ralph process manager - spawns and tracks ralph CLI processes
 
-use std::collections::HashMap;
-use std::process::Stdio;
+pub mod adapter;
+mod events;
+mod limits;
+mod output;
+mod process;
+mod sandbox;
+mod usage;
+mod watch;
+mod workdir;
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 
-use tokio::io::{AsyncBufReadExt, BufReader};
-use tokio::process::{Child, Command};
-use tokio::sync::RwLock;
+use serde::{Deserialize, Serialize};
+use tokio::io::BufReader;
+use tokio::sync::{Mutex, RwLock};
 use uuid::Uuid;
 
-use crate::db::models::{OutputStream as DbOutputStream, SessionStatus as DbSessionStatus};
+use crate::activity;
+use crate::artifacts::{self, ArtifactPatterns};
+use crate::db::models::{
+    ActivityKind, MessageRole, OutputChannel as DbOutputChannel, OutputStream as DbOutputStream, PipelineStep, Repo, SessionStatus as DbSessionStatus,
+    SnapshotReason, StepStatus, TokenUsage,
+};
 use crate::db::Database;
+use crate::failure::{self, Failure, FailureReason};
+use crate::forge;
+use crate::git::GitManager;
+use crate::attachments::AttachmentStore;
+use crate::keep_awake::KeepAwake;
+use crate::notify::Notifier;
+use crate::orchestrator::OrchestratorPlugin;
+use crate::sanitize;
+use crate::snapshot::SnapshotStore;
+use crate::webhooks::{EventType, WebhookDispatcher};
+use crate::workers::{ToWorker, WorkerRegistry};
 use crate::ws::messages::{OutputStream, ServerMessage, SessionStatus as WsSessionStatus};
 use crate::ws::ConnectionManager;
+pub use adapter::OrchestratorAdapter;
+pub use limits::RunLimits;
+pub use output::{read_bounded_line, OutputLimits};
+pub use sandbox::Sandbox;
+pub use workdir::WorkingDir;
+use process::{ProcessInput, RunProcess, Spawned};
+use events::RunEvent;
+use sandbox::ContainerRun;
+use watch::RunWatcher;
+
+/// How long runs get to exit after SIGKILL when the server shuts down
+const SHUTDOWN_KILL_WAIT: Duration = Duration::from_secs(2);
+
+/// How often shutdown checks whether the runs it stopped are over
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(100);
 
 /// Active process handle with metadata
 struct ProcessHandle {
-    child: Child,
+    process: RunProcess,
+    /// Where follow-up input goes while the process runs
+    input: Option<Arc<Mutex<ProcessInput>>>,
     repo_id: Uuid,
+    repo_path: PathBuf,
+    started_at: SystemTime,
+    /// Whether the process runs in a container
+    sandboxed: bool,
+    /// What the run's output has said so far
+    outcome: Arc<std::sync::Mutex<RunOutcome>>,
+    /// Reports file changes while the process runs; dropped with the handle
+    _watcher: Option<RunWatcher>,
+}
+
+/// A run handed to a remote worker
+struct RemoteRun {
+    worker_id: Uuid,
+    repo_id: Uuid,
+    adapter: Arc<dyn OrchestratorAdapter>,
+    outcome: Arc<std::sync::Mutex<RunOutcome>>,
+}
+
+/// A run waiting for its repository, or for a free run slot
+struct QueuedRun {
+    session_id: Uuid,
+    /// When the run was requested relative to the others, across repositories
+    order: u64,
+    repo: Repo,
+    options: RunOptions,
+    prompt: String,
+    db: Arc<Database>,
+    connections: ConnectionManager,
+}
+
+/// Where a run happens
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RunTarget {
+    /// Directly on this machine
+    #[default]
+    Local,
+    /// In a container on this machine (see [`Sandbox`])
+    Sandbox,
+    /// On a connected worker
+    Worker(Uuid),
+}
+
+impl RunTarget {
+    /// Whether the run takes one of this machine's run slots
+    fn is_local(&self) -> bool {
+        !matches!(self, RunTarget::Worker(_))
+    }
+}
+
+/// Where and how a requested run happens
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RunOptions {
+    pub target: RunTarget,
+    /// Where the process starts, instead of the repository's root
+    pub working_dir: Option<WorkingDir>,
+}
+
+/// How a requested run went
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunStart {
+    Started,
+    /// Waiting for the repository's earlier runs or a free run slot;
+    /// position 1 is next in the repository
+    Queued { position: usize },
+}
+
+/// A run waiting to start
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueueEntry {
+    pub session_id: Uuid,
+    pub repo_id: Uuid,
+    /// Place in the repository's queue; 1 is next
+    pub position: usize,
+}
+
+/// What a run's output has said about how it ended so far
+#[derive(Debug, Default, Clone)]
+struct RunOutcome {
+    /// Status settled by the orchestrator's output patterns
+    verdict: Option<DbSessionStatus>,
+    /// First known failure signature seen on stderr
+    failure: Option<Failure>,
 }
 
 /// Inner state for RalphManager
 struct RalphManagerInner {
     /// Map of session_id -> active process handle
     processes: HashMap<Uuid, ProcessHandle>,
+    /// Map of session_id -> run on a remote worker
+    remote: HashMap<Uuid, RemoteRun>,
     /// Set of repo_ids with running processes (for 1-instance-per-repo constraint)
     active_repos: HashMap<Uuid, Uuid>, // repo_id -> session_id
+    /// Runs waiting for their repository, per repo_id, in the order they start
+    queues: HashMap<Uuid, VecDeque<QueuedRun>>,
+    /// Order the next queued run is given
+    next_order: u64,
+    /// Map of session_id -> where the steps of its pipeline run, while it has one going
+    pipelines: HashMap<Uuid, RunOptions>,
+    /// Set once the server is stopping; no more runs start
+    shutting_down: bool,
+}
+
+impl RalphManagerInner {
+    /// Whether a local run has to wait for a slot under `max` runs at once
+    ///
+    /// Runs already waiting for a slot go first, even if one just came free.
+    fn is_full(&self, max: Option<usize>) -> bool {
+        let Some(max) = max else {
+            return false;
+        };
+        self.processes.len() >= max
+            || self.queues.iter().any(|(repo_id, queue)| {
+                !self.active_repos.contains_key(repo_id)
+                    && queue.front().is_some_and(|run| run.options.target.is_local())
+            })
+    }
+
+    /// Take a session's run out of its queue, with the sessions still waiting
+    /// behind it in the same queue
+    fn dequeue(&mut self, session_id: Uuid) -> Option<(QueuedRun, Vec<Uuid>)> {
+        let (repo_id, index) = self.queues.iter().find_map(|(repo_id, queue)| {
+            queue
+                .iter()
+                .position(|run| run.session_id == session_id)
+                .map(|index| (*repo_id, index))
+        })?;
+        let queue = self.queues.get_mut(&repo_id)?;
+        let run = queue.remove(index)?;
+        let waiting = queue.iter().map(|run| run.session_id).collect();
+        if queue.is_empty() {
+            self.queues.remove(&repo_id);
+        }
+        Some((run, waiting))
+    }
 }
 
 /// Manages spawning and tracking of ralph CLI processes
 #[derive(Clone)]
 pub struct RalphManager {
     inner: Arc<RwLock<RalphManagerInner>>,
+    notifier: Notifier,
+    webhooks: Option<WebhookDispatcher>,
+    /// Orchestrators declared in configuration
+    plugins: Arc<Vec<OrchestratorPlugin>>,
+    /// Where the workspace is saved before each run
+    snapshots: Option<SnapshotStore>,
+    /// Files recorded as artifacts after each run
+    artifacts: Option<ArtifactPatterns>,
+    /// Connected workers sessions can be run on
+    workers: Option<WorkerRegistry>,
+    /// How long output lines may get
+    output_limits: OutputLimits,
+    /// Keeps the machine from sleeping while runs are going
+    keep_awake: Option<KeepAwake>,
+    /// Session attachments, copied into a context directory for each run
+    attachments: Option<AttachmentStore>,
+    /// Run local processes on a pseudo-terminal instead of pipes
+    pty: bool,
+    /// How long local runs may go, and how much memory and output they may use
+    limits: RunLimits,
+    /// Most local runs going at once; more wait in their queues
+    max_concurrent_runs: Option<usize>,
+    /// Container sandboxed runs go in, when configured
+    sandbox: Option<Sandbox>,
 }
 
 impl RalphManager {
     pub fn new() -> Self {
+        Self::with_notifier(Notifier::default())
+    }
+
+    /// Create a manager that reports finished sessions through `notifier`
+    pub fn with_notifier(notifier: Notifier) -> Self {
         Self {
             inner: Arc::new(RwLock::new(RalphManagerInner {
                 processes: HashMap::new(),
+                remote: HashMap::new(),
                 active_repos: HashMap::new(),
+                queues: HashMap::new(),
+                next_order: 0,
+                pipelines: HashMap::new(),
+                shutting_down: false,
             })),
+            notifier,
+            webhooks: None,
+            plugins: Arc::new(Vec::new()),
+            snapshots: None,
+            artifacts: None,
+            workers: None,
+            output_limits: OutputLimits::default(),
+            keep_awake: None,
+            attachments: None,
+            pty: false,
+            limits: RunLimits::default(),
+            max_concurrent_runs: None,
+            sandbox: None,
+        }
+    }
+
+    /// Report session status changes to webhooks
+    pub fn with_webhooks(mut self, webhooks: WebhookDispatcher) -> Self {
+        self.webhooks = Some(webhooks);
+        self
+    }
+
+    /// Run sessions on configured orchestrators with their declared commands
+    pub fn with_plugins(mut self, plugins: Vec<OrchestratorPlugin>) -> Self {
+        self.plugins = Arc::new(plugins);
+        self
+    }
+
+    /// Snapshot the workspace before each run
+    pub fn with_snapshots(mut self, snapshots: SnapshotStore) -> Self {
+        self.snapshots = Some(snapshots);
+        self
+    }
+
+    /// Record files matching `patterns` that a run wrote as its artifacts
+    pub fn with_artifacts(mut self, patterns: ArtifactPatterns) -> Self {
+        self.artifacts = Some(patterns);
+        self
+    }
+
+    /// Truncate and split long output lines according to `limits`
+    pub fn with_output_limits(mut self, limits: OutputLimits) -> Self {
+        self.output_limits = limits;
+        self
+    }
+
+    /// Prevent system sleep while any session is running
+    pub fn with_keep_awake(mut self, keep_awake: KeepAwake) -> Self {
+        self.keep_awake = Some(keep_awake);
+        self
+    }
+
+    /// Give runs the files attached to their session
+    pub fn with_attachments(mut self, attachments: AttachmentStore) -> Self {
+        self.attachments = Some(attachments);
+        self
+    }
+
+    /// Run local processes on a pseudo-terminal, keeping their colors and
+    /// terminal output
+    pub fn with_pty(mut self) -> Self {
+        self.pty = true;
+        self
+    }
+
+    /// Stop local runs that go over `limits`
+    pub fn with_run_limits(mut self, limits: RunLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Queue local runs once `max` are going at once
+    pub fn with_max_concurrent_runs(mut self, max: usize) -> Self {
+        self.max_concurrent_runs = Some(max);
+        self
+    }
+
+    /// Most local runs going at once, if limited
+    pub fn max_concurrent_runs(&self) -> Option<usize> {
+        self.max_concurrent_runs
+    }
+
+    /// Allow runs in containers started from `sandbox`
+    pub fn with_sandbox(mut self, sandbox: Sandbox) -> Self {
+        self.sandbox = Some(sandbox);
+        self
+    }
+
+    /// Allow sessions to run on the workers in `workers`
+    pub fn with_workers(mut self, workers: WorkerRegistry) -> Self {
+        self.workers = Some(workers);
+        self
+    }
+
+    /// Record a session status change in the activity feed and deliver it to webhooks
+    async fn status_changed(
+        &self,
+        db: &Database,
+        session_id: Uuid,
+        repo_id: Uuid,
+        status: DbSessionStatus,
+        summary: &str,
+    ) {
+        let kind = match status {
+            DbSessionStatus::Running => ActivityKind::SessionStarted,
+            _ => ActivityKind::SessionFinished,
+        };
+        activity::record_async(db, kind, repo_id, Some(session_id), summary).await;
+
+        if let Some(webhooks) = &self.webhooks {
+            webhooks
+                .emit(
+                    EventType::SessionStatus,
+                    serde_json::json!({
+                        "session_id": session_id,
+                        "repo_id": repo_id,
+                        "status": status.as_str(),
+                    }),
+                )
+                .await;
         }
     }
 
@@ -59,15 +386,477 @@ impl RalphManager {
     /// Check if a session has a running process
     pub async fn is_session_running(&self, session_id: Uuid) -> bool {
         let inner = self.inner.read().await;
-        inner.processes.contains_key(&session_id)
+        inner.processes.contains_key(&session_id) || inner.remote.contains_key(&session_id)
+    }
+
+    /// A queued session's place in its repository's queue; 1 is next
+    pub async fn queue_position(&self, session_id: Uuid) -> Option<usize> {
+        let inner = self.inner.read().await;
+        inner.queues.values().find_map(|queue| {
+            queue
+                .iter()
+                .position(|run| run.session_id == session_id)
+                .map(|index| index + 1)
+        })
+    }
+
+    /// Every run waiting to start, in the order they were requested
+    pub async fn queued_runs(&self) -> Vec<QueueEntry> {
+        let inner = self.inner.read().await;
+        let mut runs: Vec<(u64, QueueEntry)> = inner
+            .queues
+            .iter()
+            .flat_map(|(repo_id, queue)| {
+                queue.iter().enumerate().map(|(index, run)| {
+                    let session = QueueEntry {
+                        session_id: run.session_id,
+                        repo_id: *repo_id,
+                        position: index + 1,
+                    };
+                    (run.order, session)
+                })
+            })
+            .collect();
+        runs.sort_by_key(|(order, _)| *order);
+        runs.into_iter().map(|(_, session)| session).collect()
+    }
+
+    /// Sessions waiting for a repository, next first
+    pub async fn queued_sessions(&self, repo_id: Uuid) -> Vec<Uuid> {
+        let inner = self.inner.read().await;
+        inner
+            .queues
+            .get(&repo_id)
+            .map(|queue| queue.iter().map(|run| run.session_id).collect())
+            .unwrap_or_default()
+    }
+
+    /// Start a run now, or queue it behind the repository's current one
+    ///
+    /// Local runs also queue while the most allowed at once are going. Queued
+    /// runs start in the order they were requested as the runs ahead of them
+    /// end. The prompt is checked up front so a bad request fails now rather
+    /// than when its turn comes.
+    pub async fn run_or_queue(
+        &self,
+        session_id: Uuid,
+        repo: &Repo,
+        options: RunOptions,
+        prompt: &str,
+        db: Arc<Database>,
+        connections: ConnectionManager,
+    ) -> Result<RunStart, RalphError> {
+        sanitize::prompt(prompt).map_err(|e| RalphError::InvalidArgument(e.to_string()))?;
+        if options.target == RunTarget::Sandbox && self.sandbox.is_none() {
+            return Err(RalphError::InvalidArgument(
+                "Sandboxed runs need a container image; set sandbox_image".to_string(),
+            ));
+        }
+        // A worker has its own checkout, which this machine's paths don't name
+        if matches!(options.target, RunTarget::Worker(_)) && options.working_dir.is_some() {
+            return Err(RalphError::InvalidArgument(
+                "Runs on a worker start at the repository's root".to_string(),
+            ));
+        }
+
+        loop {
+            let position = {
+                let mut inner = self.inner.write().await;
+                if inner.shutting_down {
+                    return Err(RalphError::ShuttingDown);
+                }
+                let running = inner.processes.contains_key(&session_id)
+                    || inner.remote.contains_key(&session_id)
+                    || inner
+                        .queues
+                        .values()
+                        .flatten()
+                        .any(|run| run.session_id == session_id);
+                if running {
+                    return Err(RalphError::SessionAlreadyRunning(session_id));
+                }
+                // Runs already waiting go first, even if the repository just
+                // became free
+                let waiting = inner.queues.get(&repo.id).is_some_and(|queue| !queue.is_empty());
+                let full = options.target.is_local() && inner.is_full(self.max_concurrent_runs);
+                if inner.active_repos.contains_key(&repo.id) || waiting || full {
+                    let order = inner.next_order;
+                    inner.next_order += 1;
+                    let queue = inner.queues.entry(repo.id).or_default();
+                    queue.push_back(QueuedRun {
+                        session_id,
+                        order,
+                        repo: repo.clone(),
+                        options: options.clone(),
+                        prompt: prompt.to_string(),
+                        db: db.clone(),
+                        connections: connections.clone(),
+                    });
+                    Some(queue.len())
+                } else {
+                    None
+                }
+            };
+
+            if let Some(position) = position {
+                self.queued(session_id, position, &db, &connections).await;
+                return Ok(RunStart::Queued { position });
+            }
+            match self
+                .start(session_id, repo, &options, prompt, db.clone(), connections.clone())
+                .await
+            {
+                // Another run took the repository or the last slot in the
+                // meantime; wait for it
+                Err(RalphError::RepoBusy(_) | RalphError::AtCapacity(_)) => continue,
+                started => return started.map(|()| RunStart::Started),
+            }
+        }
+    }
+
+    /// Run a session's prompts one after another, or queue the first
+    ///
+    /// Each step starts only once the one before it completed; a step that
+    /// fails or is cancelled skips the rest. Every prompt is checked up front.
+    pub async fn run_pipeline(
+        &self,
+        session_id: Uuid,
+        repo: &Repo,
+        options: RunOptions,
+        prompts: Vec<String>,
+        db: Arc<Database>,
+        connections: ConnectionManager,
+    ) -> Result<RunStart, RalphError> {
+        for prompt in &prompts {
+            sanitize::prompt(prompt).map_err(|e| RalphError::InvalidArgument(e.to_string()))?;
+        }
+        let Some(first) = prompts.first().cloned() else {
+            return Err(RalphError::InvalidArgument("A pipeline needs at least one prompt".to_string()));
+        };
+
+        {
+            let mut inner = self.inner.write().await;
+            if inner.shutting_down {
+                return Err(RalphError::ShuttingDown);
+            }
+            let running = inner.processes.contains_key(&session_id)
+                || inner.remote.contains_key(&session_id)
+                || inner.queues.values().flatten().any(|run| run.session_id == session_id);
+            if running || inner.pipelines.contains_key(&session_id) {
+                return Err(RalphError::SessionAlreadyRunning(session_id));
+            }
+            inner.pipelines.insert(session_id, options.clone());
+        }
+        let steps = prompts.clone();
+        if let Err(e) = db.call(move |db| db.set_pipeline(session_id, &steps)).await {
+            self.inner.write().await.pipelines.remove(&session_id);
+            return Err(RalphError::SpawnFailed(format!("Failed to save the pipeline: {}", e)));
+        }
+
+        let started = self
+            .run_or_queue(session_id, repo, options, &first, db.clone(), connections.clone())
+            .await;
+        if started.is_err() {
+            self.step_finished(session_id, DbSessionStatus::Error, &db, &connections)
+                .await;
+        }
+        started
+    }
+
+    /// Start a run here, in a container, or on a worker
+    async fn start(
+        &self,
+        session_id: Uuid,
+        repo: &Repo,
+        options: &RunOptions,
+        prompt: &str,
+        db: Arc<Database>,
+        connections: ConnectionManager,
+    ) -> Result<(), RalphError> {
+        match options.target {
+            RunTarget::Worker(worker_id) => {
+                self.run_on_worker(session_id, repo, worker_id, prompt, db, connections)
+                    .await
+            }
+            RunTarget::Local | RunTarget::Sandbox => {
+                self.run(session_id, repo, options, prompt, db, connections)
+                    .await
+            }
+        }
+    }
+
+    /// Mark a session as queued and tell everyone who's listening
+    async fn queued(
+        &self,
+        session_id: Uuid,
+        position: usize,
+        db: &Database,
+        connections: &ConnectionManager,
+    ) {
+        let status = DbSessionStatus::Queued;
+        if let Err(e) = db.call(move |db| db.update_session_status(session_id, status)).await {
+            tracing::error!("Failed to update session status: {}", e);
+        }
+        connections
+            .broadcast(
+                session_id,
+                ServerMessage::Status {
+                    session_id,
+                    status: WsSessionStatus::Queued,
+                },
+            )
+            .await;
+        connections
+            .broadcast(session_id, ServerMessage::QueuePosition { session_id, position })
+            .await;
+    }
+
+    /// Tell the sessions still waiting where they now are in the queue
+    async fn announce_positions(&self, waiting: &[Uuid], connections: &ConnectionManager) {
+        for (index, session_id) in waiting.iter().enumerate() {
+            connections
+                .broadcast(
+                    *session_id,
+                    ServerMessage::QueuePosition {
+                        session_id: *session_id,
+                        position: index + 1,
+                    },
+                )
+                .await;
+        }
+    }
+
+    /// Start the queued runs that can go now that a run has ended
+    fn start_next(&self) {
+        let manager = self.clone();
+        // Boxed so this future's type doesn't refer back to the run that
+        // finished, which calls this
+        let task: Pin<Box<dyn Future<Output = ()> + Send>> =
+            Box::pin(async move { manager.start_queued().await });
+        tokio::spawn(task);
+    }
+
+    /// Start queued runs, earliest requested first, while their repositories
+    /// are free and there are slots for them
+    async fn start_queued(&self) {
+        loop {
+            let (next, waiting) = {
+                let mut inner = self.inner.write().await;
+                if inner.shutting_down {
+                    return;
+                }
+                let full = inner.processes.len() >= self.max_concurrent_runs.unwrap_or(usize::MAX);
+                let repo_id = inner
+                    .queues
+                    .iter()
+                    .filter(|(repo_id, _)| !inner.active_repos.contains_key(repo_id))
+                    .filter_map(|(repo_id, queue)| queue.front().map(|run| (*repo_id, run)))
+                    .filter(|(_, run)| !run.options.target.is_local() || !full)
+                    .min_by_key(|(_, run)| run.order)
+                    .map(|(repo_id, _)| repo_id);
+                let Some(repo_id) = repo_id else {
+                    return;
+                };
+                let Some(queue) = inner.queues.get_mut(&repo_id) else {
+                    return;
+                };
+                let next = queue.pop_front();
+                let waiting: Vec<Uuid> = queue.iter().map(|run| run.session_id).collect();
+                if queue.is_empty() {
+                    inner.queues.remove(&repo_id);
+                }
+                (next, waiting)
+            };
+            let Some(next) = next else {
+                return;
+            };
+            self.announce_positions(&waiting, &next.connections).await;
+
+            let started = self
+                .start(
+                    next.session_id,
+                    &next.repo,
+                    &next.options,
+                    &next.prompt,
+                    next.db.clone(),
+                    next.connections.clone(),
+                )
+                .await;
+            match started {
+                Ok(()) => continue,
+                // Taken by a run that didn't queue; its end starts this one
+                Err(RalphError::RepoBusy(_) | RalphError::AtCapacity(_)) => {
+                    let mut inner = self.inner.write().await;
+                    inner.queues.entry(next.repo.id).or_default().push_front(next);
+                    return;
+                }
+                // The run can't start; report why and move on to the next
+                Err(e) => {
+                    tracing::warn!(
+                        "Queued run for session {} failed to start: {}",
+                        next.session_id,
+                        e
+                    );
+                    let line = OutputLine {
+                        content: format!("Queued run failed to start: {}", e),
+                        channel: DbOutputChannel::System,
+                        iteration: None,
+                        usage: None,
+                        events: None,
+                    };
+                    record_output(
+                        &next.db,
+                        &next.connections,
+                        &self.output_limits,
+                        next.session_id,
+                        DbOutputStream::Stderr,
+                        line,
+                    )
+                    .await;
+                    let session_id = next.session_id;
+                    let failed = next
+                        .db
+                        .call(move |db| db.update_session_status(session_id, DbSessionStatus::Error))
+                        .await;
+                    if let Err(e) = failed {
+                        tracing::error!("Failed to update session status: {}", e);
+                    }
+                    next.connections
+                        .broadcast(
+                            next.session_id,
+                            ServerMessage::Status {
+                                session_id: next.session_id,
+                                status: WsSessionStatus::Error,
+                            },
+                        )
+                        .await;
+                }
+            }
+        }
+    }
+
+    /// The adapter for the orchestrator a session runs on
+    async fn adapter_for(
+        &self,
+        db: &Database,
+        session_id: Uuid,
+    ) -> Result<Arc<dyn OrchestratorAdapter>, RalphError> {
+        let orchestrator = db
+            .call(move |db| db.get_session(session_id))
+            .await
+            .map(|session| session.orchestrator)
+            .unwrap_or_default();
+        if let Some(adapter) = adapter::builtin(&orchestrator) {
+            return Ok(adapter);
+        }
+        let plugin = self
+            .plugins
+            .iter()
+            .find(|plugin| plugin.id == orchestrator.as_str())
+            .cloned()
+            .ok_or_else(|| {
+                RalphError::InvalidArgument(format!(
+                    "Orchestrator '{}' is no longer configured",
+                    orchestrator.as_str()
+                ))
+            })?;
+        Ok(Arc::new(plugin))
+    }
+
+    /// Environment variables a session's runs are started with
+    async fn env_for(
+        &self,
+        db: &Database,
+        session_id: Uuid,
+    ) -> Result<BTreeMap<String, String>, RalphError> {
+        db.call(move |db| db.get_session_env(session_id))
+            .await
+            .map_err(|e| RalphError::SpawnFailed(format!("Failed to read the session's environment: {}", e)))
+    }
+
+    /// Copy a session's attachments into a context directory for its run
+    ///
+    /// `None` when the session has no attachments, or no store to keep them in.
+    async fn context_dir(&self, session_id: Uuid, db: &Database) -> Result<Option<PathBuf>, RalphError> {
+        let Some(store) = self.attachments.clone() else {
+            return Ok(None);
+        };
+        let attachments = db
+            .call(move |db| db.list_attachments(session_id))
+            .await
+            .map_err(|e| RalphError::SpawnFailed(format!("Failed to list attachments: {}", e)))?;
+        if attachments.is_empty() {
+            return Ok(None);
+        }
+        tokio::task::spawn_blocking(move || store.materialize(session_id, &attachments))
+            .await
+            .map_err(|e| RalphError::SpawnFailed(format!("Attachment task failed: {}", e)))?
+            .map(Some)
+            .map_err(|e| RalphError::SpawnFailed(format!("Failed to prepare attachments: {}", e)))
+    }
+
+    /// Remove the context directory of a session's run, if it had one
+    fn discard_context(&self, session_id: Uuid) {
+        if let Some(store) = &self.attachments {
+            store.discard_context(session_id);
+        }
+    }
+
+    /// Mark a session as running and tell everyone who's listening
+    async fn started(
+        &self,
+        session_id: Uuid,
+        repo_id: Uuid,
+        prompt: &str,
+        db: &Database,
+        connections: &ConnectionManager,
+    ) {
+        if let Some(keep_awake) = &self.keep_awake {
+            keep_awake.hold(session_id);
+        }
+        let run_prompt = prompt.to_string();
+        let recorded = db.call(move |db| {
+            if let Err(e) = db.update_session_status(session_id, DbSessionStatus::Running) {
+                tracing::error!("Failed to update session status: {}", e);
+            }
+            // A new run starts without the last one's failure
+            if let Err(e) = db.set_session_failure(session_id, None) {
+                tracing::warn!("Failed to clear the session's failure: {}", e);
+            }
+            if let Err(e) = db.insert_run(session_id, &run_prompt) {
+                tracing::warn!("Failed to record the run: {}", e);
+            }
+            Ok(())
+        });
+        if let Err(e) = recorded.await {
+            tracing::error!("Failed to record the run's start: {}", e);
+        }
+        if self.inner.read().await.pipelines.contains_key(&session_id) {
+            match db.call(move |db| db.start_pipeline_step(session_id)).await {
+                Ok(step) => self.announce_steps(session_id, step.as_slice(), db, connections).await,
+                Err(e) => tracing::warn!("Failed to start the pipeline step: {}", e),
+            }
+        }
+        self.status_changed(db, session_id, repo_id, DbSessionStatus::Running, prompt)
+            .await;
+
+        connections
+            .broadcast(
+                session_id,
+                ServerMessage::Status {
+                    session_id,
+                    status: WsSessionStatus::Running,
+                },
+            )
+            .await;
     }
 
     /// Spawn a ralph process for a session
     ///
     /// # Arguments
     /// * `session_id` - The session to run ralph for
-    /// * `repo_id` - The repository ID
-    /// * `repo_path` - Filesystem path to the repository
+    /// * `repo` - The repository to run in
+    /// * `options` - Whether to run in a container, and where the process starts
     /// * `prompt` - The prompt to send to ralph
     /// * `db` - Database for updating session status
     /// * `connections` - Connection manager for broadcasting output
@@ -77,70 +866,141 @@ impl RalphManager {
     pub async fn run(
         &self,
         session_id: Uuid,
-        repo_id: Uuid,
-        repo_path: &str,
+        repo: &Repo,
+        options: &RunOptions,
         prompt: &str,
         db: Arc<Database>,
         connections: ConnectionManager,
     ) -> Result<(), RalphError> {
+        let repo_id = repo.id;
+        let sandboxed = options.target == RunTarget::Sandbox;
+        let sandbox = match (sandboxed, &self.sandbox) {
+            (false, _) => None,
+            (true, Some(sandbox)) => Some(sandbox),
+            (true, None) => {
+                return Err(RalphError::InvalidArgument(
+                    "Sandboxed runs need a container image; set sandbox_image".to_string(),
+                ));
+            }
+        };
+
         // Check if repo already has a running process
         if self.is_repo_busy(repo_id).await {
             return Err(RalphError::RepoBusy(repo_id));
         }
+        if let Some(max) = self.max_concurrent_runs
+            && self.inner.read().await.processes.len() >= max
+        {
+            return Err(RalphError::AtCapacity(max));
+        }
 
         // Check if session already has a running process
         if self.is_session_running(session_id).await {
             return Err(RalphError::SessionAlreadyRunning(session_id));
         }
 
-        // Build the command
-        let mut cmd = Command::new("ralph");
-        cmd.arg("run")
-            .arg("--autonomous")
-            .arg("--prompt")
-            .arg(prompt)
-            .current_dir(repo_path)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .stdin(Stdio::null());
+        let prompt =
+            sanitize::prompt(prompt).map_err(|e| RalphError::InvalidArgument(e.to_string()))?;
+        let repo_path = sanitize::repo_path(Path::new(&repo.path))
+            .map_err(|e| RalphError::InvalidArgument(e.to_string()))?;
+        // The checkout the agent works in, and where in it the process starts
+        let (workspace, subdir) = match &options.working_dir {
+            Some(dir) => (dir.workspace(), dir.subdir()),
+            None => (repo_path, Path::new("")),
+        };
+        let cwd = workspace.join(subdir);
 
-        // On Unix, set up process group for signal handling
-        #[cfg(unix)]
-        {
-            #[allow(unused_imports)]
-            use std::os::unix::process::CommandExt;
-            // SAFETY: setpgid is safe to call in pre_exec, it's a standard
-            // POSIX function that sets the process group for signal handling
-            unsafe {
-                cmd.pre_exec(|| {
-                    // Set this process as the process group leader
-                    // This allows us to send signals to the entire process group
-                    libc::setpgid(0, 0);
-                    Ok(())
-                });
-            }
-        }
-
-        // Spawn the process
-        let mut child = cmd.spawn().map_err(|e| {
-            if e.kind() == std::io::ErrorKind::NotFound {
+        // Each orchestrator has its own command line and output format
+        let adapter = self.adapter_for(&db, session_id).await?;
+        let env = self.env_for(&db, session_id).await?;
+
+        // Save the workspace so the run can be rolled back; a failed snapshot
+        // shouldn't keep the run from starting
+        if let Some(snapshots) = self.snapshots.clone() {
+            let db = db.clone();
+            let path = repo_path.to_path_buf();
+            let captured = tokio::task::spawn_blocking(move || {
+                snapshots.capture(&db, session_id, repo_id, &path, SnapshotReason::PreRun)
+            })
+            .await;
+            match captured {
+                Ok(Ok(snapshot)) => tracing::debug!(
+                    "Saved snapshot {} of {} files before run",
+                    snapshot.id,
+                    snapshot.file_count
+                ),
+                Ok(Err(e)) => tracing::warn!("Failed to snapshot workspace before run: {}", e),
+                Err(e) => tracing::warn!("Snapshot task failed: {}", e),
+            }
+        }
+
+        // Attached files are copied where the agent can read them, and the
+        // prompt says where
+        let context_dir = self.context_dir(session_id, &db).await?;
+        let prompt_with_context = match &context_dir {
+            Some(dir) => format!("{}\n\nAttached files are in {}", prompt, dir.display()),
+            None => prompt.to_string(),
+        };
+
+        let mut command: Vec<String> = std::iter::once(adapter.program().to_string())
+            .chain(adapter.args(&prompt_with_context))
+            .collect();
+        if let Some(sandbox) = sandbox {
+            let run = ContainerRun {
+                session_id,
+                repo_path: workspace,
+                subdir,
+                context_dir: context_dir.as_deref(),
+                env: &env,
+                max_memory_bytes: self.limits.max_memory_bytes,
+                tty: self.pty,
+            };
+            command = sandbox.command(&run, command);
+        }
+        let (program, args) = command.split_first().expect("command has a program");
+        let started_at = SystemTime::now();
+        let spawned = if self.pty {
+            process::spawn_pty(program, args, &cwd, &env, context_dir.as_deref())
+        } else {
+            process::spawn_piped(program, args, &cwd, &env, context_dir.as_deref())
+        };
+        if spawned.is_err() {
+            self.discard_context(session_id);
+        }
+        let Spawned {
+            process,
+            input,
+            outputs,
+        } = spawned.map_err(|e| {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                RalphError::SpawnFailed(e.to_string())
+            } else if sandboxed {
                 RalphError::NotFound {
-                    message: "ralph CLI not found in PATH".to_string(),
+                    message: format!("{} not found in PATH", program),
                     help_steps: vec![
-                        "Install ralph: cargo install ralph".to_string(),
-                        "Or download from release page".to_string(),
-                        "Ensure ~/.cargo/bin is in your PATH".to_string(),
-                        "Restart your terminal after installation".to_string(),
+                        "Install Docker or Podman".to_string(),
+                        "Or set sandbox_runtime to a container runtime that's installed".to_string(),
                     ],
                 }
             } else {
-                RalphError::SpawnFailed(e.to_string())
+                RalphError::NotFound {
+                    message: format!("{} not found in PATH", program),
+                    help_steps: adapter.install_help(),
+                }
             }
         })?;
+        let input = input.map(|input| Arc::new(Mutex::new(input)));
+        let pid = process.id();
+        let outcome = Arc::new(std::sync::Mutex::new(RunOutcome::default()));
 
-        // Take stdout and stderr handles
-        let stdout = child.stdout.take().expect("stdout was configured");
-        let stderr = child.stderr.take().expect("stderr was configured");
+        // Stream file changes to subscribers while the process runs
+        let watcher = match RunWatcher::start(session_id, workspace, connections.clone()) {
+            Ok(watcher) => Some(watcher),
+            Err(e) => {
+                tracing::warn!("Failed to watch {} for changes: {}", workspace.display(), e);
+                None
+            }
+        };
 
         // Register the process
         {
@@ -148,158 +1008,675 @@ impl RalphManager {
             inner.processes.insert(
                 session_id,
                 ProcessHandle {
-                    child,
+                    process,
+                    input,
                     repo_id,
+                    repo_path: repo_path.to_path_buf(),
+                    started_at,
+                    sandboxed,
+                    outcome: outcome.clone(),
+                    _watcher: watcher,
                 },
             );
             inner.active_repos.insert(repo_id, session_id);
         }
 
-        // Update session status to running
-        if let Err(e) = db.update_session_status(session_id, DbSessionStatus::Running) {
-            tracing::error!("Failed to update session status: {}", e);
+        self.started(session_id, repo_id, prompt, &db, &connections).await;
+
+        // Spawn tasks to read the output streams
+        let manager_clone = self.clone();
+        let db_clone = db.clone();
+        let connections_clone = connections.clone();
+        let output_bytes = Arc::new(AtomicU64::new(0));
+
+        // Check the run against its resource limits while it goes
+        let enforcer = (!self.limits.is_empty()).then(|| {
+            let manager = self.clone();
+            let output_bytes = output_bytes.clone();
+            let outcome = outcome.clone();
+            let db = db.clone();
+            let connections = connections.clone();
+            tokio::spawn(async move {
+                manager
+                    .enforce_limits(session_id, pid, output_bytes, outcome, db, connections)
+                    .await
+            })
+        });
+
+        tokio::spawn(async move {
+            let limits = manager_clone.output_limits;
+
+            // Read each output stream a line at a time
+            let readers: Vec<_> = outputs
+                .into_iter()
+                .map(|(stream, output)| {
+                    let db = db_clone.clone();
+                    let connections = connections_clone.clone();
+                    let adapter = adapter.clone();
+                    let outcome = outcome.clone();
+                    let output_bytes = output_bytes.clone();
+                    tokio::spawn(async move {
+                        let mut reader = BufReader::new(output);
+                        while let Ok(Some((line, dropped))) =
+                            read_bounded_line(&mut reader, limits.max_line_bytes).await
+                        {
+                            output_bytes.fetch_add((line.len() + dropped) as u64, Ordering::Relaxed);
+                            let line = read_line(
+                                adapter.as_ref(),
+                                &outcome,
+                                stream,
+                                limits.truncate(line, dropped),
+                            );
+                            record_output(&db, &connections, &limits, session_id, stream, line)
+                                .await;
+                        }
+                    })
+                })
+                .collect();
+
+            // Wait for every reader to finish
+            futures::future::join_all(readers).await;
+            if let Some(enforcer) = enforcer {
+                enforcer.abort();
+            }
+
+            // Process has finished - wait for exit status and cleanup
+            let outcome = outcome.lock().unwrap().clone();
+            manager_clone
+                .handle_process_exit(
+                    session_id,
+                    repo_id,
+                    adapter.as_ref(),
+                    outcome,
+                    db_clone,
+                    connections_clone,
+                )
+                .await;
+        });
+
+        Ok(())
+    }
+
+    /// Stop a local run once it goes over one of its resource limits
+    ///
+    /// The run's output settles it as an error, with the limit as its failure.
+    /// A run that writes nothing for too long is reported as stalled once
+    /// each time it goes quiet, and stopped when stalled runs are.
+    async fn enforce_limits(
+        &self,
+        session_id: Uuid,
+        pid: Option<u32>,
+        output_bytes: Arc<AtomicU64>,
+        outcome: Arc<std::sync::Mutex<RunOutcome>>,
+        db: Arc<Database>,
+        connections: ConnectionManager,
+    ) {
+        let run_limits = self.limits;
+        let started = Instant::now();
+        let mut interval = tokio::time::interval(limits::CHECK_INTERVAL);
+        let mut last_written = 0;
+        let mut last_output = started;
+        let mut warned = false;
+        let exceeded = loop {
+            interval.tick().await;
+            let written = output_bytes.load(Ordering::Relaxed);
+            if written != last_written {
+                last_written = written;
+                last_output = Instant::now();
+                warned = false;
+            }
+            let idle = last_output.elapsed();
+            if !warned && run_limits.is_stalled(idle) {
+                warned = true;
+                self.stalled(session_id, idle, &outcome, &db, &connections).await;
+                if run_limits.stop_stalled {
+                    self.terminate(session_id, pid).await;
+                    return;
+                }
+            }
+            let checked =
+                tokio::task::spawn_blocking(move || run_limits.check(started.elapsed(), pid, written)).await;
+            match checked {
+                Ok(Some(exceeded)) => break exceeded,
+                Ok(None) => {}
+                Err(e) => {
+                    tracing::warn!("Resource limit check failed: {}", e);
+                    return;
+                }
+            }
+        };
+
+        let message = exceeded.message();
+        tracing::warn!("Session {}: {}", session_id, message);
+        {
+            let mut outcome = outcome.lock().unwrap();
+            outcome.verdict = Some(DbSessionStatus::Error);
+            outcome.failure = Some(Failure::new(FailureReason::LimitExceeded, &message));
         }
+        let line = OutputLine {
+            content: message,
+            channel: DbOutputChannel::System,
+            iteration: None,
+            usage: None,
+            events: None,
+        };
+        record_output(&db, &connections, &self.output_limits, session_id, DbOutputStream::Stderr, line)
+            .await;
+        connections
+            .broadcast(
+                session_id,
+                ServerMessage::LimitExceeded {
+                    session_id,
+                    limit: exceeded.limit,
+                    max: exceeded.max,
+                    value: exceeded.value,
+                },
+            )
+            .await;
 
-        // Broadcast status update
+        self.terminate(session_id, pid).await;
+    }
+
+    /// Report a run that has gone quiet, settling it as stalled when stalled
+    /// runs are stopped
+    async fn stalled(
+        &self,
+        session_id: Uuid,
+        idle: Duration,
+        outcome: &std::sync::Mutex<RunOutcome>,
+        db: &Database,
+        connections: &ConnectionManager,
+    ) {
+        let stopping = self.limits.stop_stalled;
+        let message = if stopping {
+            format!("Run stopped after {} seconds without output", idle.as_secs())
+        } else {
+            format!("No output for {} seconds; the run may be stalled", idle.as_secs())
+        };
+        tracing::warn!("Session {}: {}", session_id, message);
+        if stopping {
+            let mut outcome = outcome.lock().unwrap();
+            outcome.verdict = Some(DbSessionStatus::Error);
+            outcome.failure = Some(Failure::new(FailureReason::Stalled, &message));
+        }
+        let line = OutputLine {
+            content: message,
+            channel: DbOutputChannel::System,
+            iteration: None,
+            usage: None,
+            events: None,
+        };
+        record_output(db, connections, &self.output_limits, session_id, DbOutputStream::Stderr, line)
+            .await;
         connections
             .broadcast(
                 session_id,
-                ServerMessage::Status {
+                ServerMessage::Stalled {
                     session_id,
-                    status: WsSessionStatus::Running,
+                    idle_secs: idle.as_secs(),
+                    stopping,
                 },
             )
             .await;
+    }
 
-        // Spawn tasks to read stdout and stderr
-        let manager_clone = self.clone();
-        let db_clone = db.clone();
-        let connections_clone = connections.clone();
+    /// Handle process exit - cleanup and update status
+    ///
+    /// `outcome` is what the run's output said about how it ended; otherwise
+    /// the adapter reads the exit code.
+    async fn handle_process_exit(
+        &self,
+        session_id: Uuid,
+        repo_id: Uuid,
+        adapter: &dyn OrchestratorAdapter,
+        outcome: RunOutcome,
+        db: Arc<Database>,
+        connections: ConnectionManager,
+    ) {
+        // Get the exit status
+        let (exit_status, run) = {
+            let mut inner = self.inner.write().await;
+            if let Some(mut handle) = inner.processes.remove(&session_id) {
+                inner.active_repos.remove(&repo_id);
+                // Wait for the child to fully exit
+                let exit_status = handle.process.wait().await;
+                (exit_status, Some((handle.repo_path, handle.started_at)))
+            } else {
+                (None, None)
+            }
+        };
 
-        tokio::spawn(async move {
-            let stdout_connections = connections_clone.clone();
-            let stderr_connections = connections_clone.clone();
-            let stdout_db = db_clone.clone();
-            let stderr_db = db_clone.clone();
-
-            // Spawn stdout reader
-            let stdout_handle = tokio::spawn({
-                let session_id = session_id;
-                async move {
-                    let reader = BufReader::new(stdout);
-                    let mut lines = reader.lines();
-                    while let Ok(Some(line)) = lines.next_line().await {
-                        // Persist to database
-                        if let Err(e) =
-                            stdout_db.insert_output_log(session_id, DbOutputStream::Stdout, &line)
-                        {
-                            tracing::warn!("Failed to persist stdout output: {}", e);
-                        }
+        // Determine final status based on exit code, unless the output settled it
+        let final_status = match (exit_status, outcome.verdict) {
+            (None, _) => DbSessionStatus::Error,
+            (Some(_), Some(status)) => status,
+            (Some(code), None) => adapter.exit_status(code),
+        };
 
-                        // Broadcast to WebSocket subscribers
-                        stdout_connections
-                            .broadcast(
-                                session_id,
-                                ServerMessage::Output {
-                                    session_id,
-                                    stream: OutputStream::Stdout,
-                                    content: line,
-                                },
-                            )
-                            .await;
-                    }
+        if let Some(code) = exit_status
+            && let Err(e) = db.call(move |db| db.set_run_exit_code(session_id, code)).await
+        {
+            tracing::warn!("Failed to record the run's exit code: {}", e);
+        }
+
+        // Record what the run left behind before reporting that it finished
+        if let (Some(patterns), Some((repo_path, started_at))) = (self.artifacts.clone(), run) {
+            let db = db.clone();
+            let recorded = tokio::task::spawn_blocking(move || {
+                artifacts::record(&db, session_id, repo_id, &repo_path, &patterns, started_at)
+            })
+            .await;
+            match recorded {
+                Ok(Ok(found)) if !found.is_empty() => {
+                    tracing::debug!("Recorded {} artifacts for session {}", found.len(), session_id)
                 }
-            });
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => tracing::warn!("Failed to record artifacts: {}", e),
+                Err(e) => tracing::warn!("Artifact task failed: {}", e),
+            }
+        }
 
-            // Spawn stderr reader
-            let stderr_handle = tokio::spawn({
-                let session_id = session_id;
-                async move {
-                    let reader = BufReader::new(stderr);
-                    let mut lines = reader.lines();
-                    while let Ok(Some(line)) = lines.next_line().await {
-                        // Persist to database
-                        if let Err(e) =
-                            stderr_db.insert_output_log(session_id, DbOutputStream::Stderr, &line)
-                        {
-                            tracing::warn!("Failed to persist stderr output: {}", e);
-                        }
+        self.finished(session_id, repo_id, final_status, outcome.failure, &db, &connections)
+            .await;
+    }
 
-                        // Broadcast to WebSocket subscribers
-                        stderr_connections
-                            .broadcast(
-                                session_id,
-                                ServerMessage::Output {
-                                    session_id,
-                                    stream: OutputStream::Stderr,
-                                    content: line,
-                                },
-                            )
-                            .await;
-                    }
-                }
-            });
+    /// Record a finished run's final status and tell everyone who's listening
+    ///
+    /// `failure` is kept only when the run ended in error.
+    async fn finished(
+        &self,
+        session_id: Uuid,
+        repo_id: Uuid,
+        final_status: DbSessionStatus,
+        failure: Option<Failure>,
+        db: &Arc<Database>,
+        connections: &ConnectionManager,
+    ) {
+        if let Some(keep_awake) = &self.keep_awake {
+            keep_awake.release(session_id);
+        }
+        self.discard_context(session_id);
 
-            // Wait for both readers to finish
-            let _ = tokio::join!(stdout_handle, stderr_handle);
+        // Update database
+        let recorded = db.call(move |db| {
+            if let Err(e) = db.update_session_status(session_id, final_status) {
+                tracing::error!("Failed to update session status: {}", e);
+            }
+            if final_status == DbSessionStatus::Error
+                && let Some(failure) = &failure
+                && let Err(e) = db.set_session_failure(session_id, Some(failure))
+            {
+                tracing::warn!("Failed to record the session's failure: {}", e);
+            }
+            if let Err(e) = db.end_iteration(session_id) {
+                tracing::warn!("Failed to end the last iteration: {}", e);
+            }
+            if let Err(e) = db.finish_run(session_id, final_status) {
+                tracing::warn!("Failed to finish the run: {}", e);
+            }
+            Ok(())
+        });
+        if let Err(e) = recorded.await {
+            tracing::error!("Failed to record the run's end: {}", e);
+        }
+        let next_step = self.step_finished(session_id, final_status, db, connections).await;
 
-            // Process has finished - wait for exit status and cleanup
-            manager_clone
-                .handle_process_exit(session_id, repo_id, db_clone, connections_clone)
-                .await;
+        // Broadcast final status
+        connections
+            .broadcast(
+                session_id,
+                ServerMessage::Status {
+                    session_id,
+                    status: final_status.into(),
+                },
+            )
+            .await;
+
+        tracing::info!(
+            "Ralph process for session {} finished with status: {:?}",
+            session_id,
+            final_status
+        );
+
+        self.status_changed(db, session_id, repo_id, final_status, final_status.as_str())
+            .await;
+
+        // The session is only done once its pipeline is
+        if let Some((options, prompt)) = next_step {
+            self.continue_pipeline(session_id, repo_id, options, prompt, db.clone(), connections.clone());
+            self.start_next();
+            return;
+        }
+        self.notifier.session_finished(db, session_id, final_status).await;
+
+        if final_status == DbSessionStatus::Completed {
+            forge::after_run(db, session_id).await;
+        }
+
+        self.start_next();
+    }
+
+    /// End the running step of a session's pipeline, if it has one going,
+    /// as its run ended with `status`
+    ///
+    /// Returns where to run the next step, and its prompt, when the pipeline
+    /// goes on.
+    async fn step_finished(
+        &self,
+        session_id: Uuid,
+        status: DbSessionStatus,
+        db: &Database,
+        connections: &ConnectionManager,
+    ) -> Option<(RunOptions, String)> {
+        let options = self.inner.read().await.pipelines.get(&session_id)?.clone();
+        let status = StepStatus::ended_as(status);
+        match db.call(move |db| db.finish_pipeline_step(session_id, status)).await {
+            Ok(changed) => self.announce_steps(session_id, &changed, db, connections).await,
+            Err(e) => tracing::warn!("Failed to finish the pipeline step: {}", e),
+        }
+
+        let next = match status {
+            StepStatus::Completed => db
+                .call(move |db| db.next_pipeline_step(session_id))
+                .await
+                .unwrap_or_else(|e| {
+                    tracing::warn!("Failed to find the next pipeline step: {}", e);
+                    None
+                }),
+            _ => None,
+        };
+        match next {
+            Some(step) => Some((options, step.prompt)),
+            None => {
+                self.inner.write().await.pipelines.remove(&session_id);
+                None
+            }
+        }
+    }
+
+    /// Run the next step of a session's pipeline, or queue it
+    fn continue_pipeline(
+        &self,
+        session_id: Uuid,
+        repo_id: Uuid,
+        options: RunOptions,
+        prompt: String,
+        db: Arc<Database>,
+        connections: ConnectionManager,
+    ) {
+        let manager = self.clone();
+        // Boxed so this future's type doesn't refer back to the run that
+        // finished, which calls this
+        let task: Pin<Box<dyn Future<Output = ()> + Send>> = Box::pin(async move {
+            let started = match db.call(move |db| db.get_repo(repo_id)).await {
+                Ok(repo) => {
+                    manager
+                        .run_or_queue(session_id, &repo, options, &prompt, db.clone(), connections.clone())
+                        .await
+                }
+                Err(e) => Err(RalphError::SpawnFailed(e.to_string())),
+            };
+            match started {
+                Ok(_) => {}
+                // A run started on the session in the meantime takes over
+                // from the pipeline, or the server is stopping
+                Err(RalphError::SessionAlreadyRunning(_) | RalphError::ShuttingDown) => {
+                    manager
+                        .step_finished(session_id, DbSessionStatus::Cancelled, &db, &connections)
+                        .await;
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to start the next step of session {}'s pipeline: {}", session_id, e);
+                    let line = OutputLine {
+                        content: format!("The next step of the pipeline couldn't start: {}", e),
+                        channel: DbOutputChannel::System,
+                        iteration: None,
+                        usage: None,
+                        events: None,
+                    };
+                    record_output(&db, &connections, &manager.output_limits, session_id, DbOutputStream::Stderr, line)
+                        .await;
+                    manager
+                        .finished(session_id, repo_id, DbSessionStatus::Error, None, &db, &connections)
+                        .await;
+                }
+            }
         });
+        tokio::spawn(task);
+    }
+
+    /// Tell everyone who's listening that steps of a session's pipeline changed
+    async fn announce_steps(
+        &self,
+        session_id: Uuid,
+        changed: &[PipelineStep],
+        db: &Database,
+        connections: &ConnectionManager,
+    ) {
+        if changed.is_empty() {
+            return;
+        }
+        let steps = match db.call(move |db| db.list_pipeline_steps(session_id)).await {
+            Ok(steps) => steps.len() as u32,
+            Err(e) => {
+                tracing::warn!("Failed to count the pipeline's steps: {}", e);
+                return;
+            }
+        };
+        for step in changed {
+            connections
+                .broadcast(
+                    session_id,
+                    ServerMessage::PipelineStep {
+                        session_id,
+                        step: step.step,
+                        steps,
+                        status: step.status.into(),
+                    },
+                )
+                .await;
+        }
+    }
+
+    /// Run a session on a connected worker instead of locally
+    ///
+    /// The worker runs the same command a local run would, in its own clone
+    /// of the repository; the local clone only supplies its directory name and
+    /// origin. Snapshots, file change events and artifacts need the files, so
+    /// they're local-only.
+    pub async fn run_on_worker(
+        &self,
+        session_id: Uuid,
+        repo: &Repo,
+        worker_id: Uuid,
+        prompt: &str,
+        db: Arc<Database>,
+        connections: ConnectionManager,
+    ) -> Result<(), RalphError> {
+        let workers = self
+            .workers
+            .as_ref()
+            .filter(|workers| workers.get(worker_id).is_some())
+            .ok_or(RalphError::WorkerUnavailable(worker_id))?;
+        let repo_id = repo.id;
+        if self.is_repo_busy(repo_id).await {
+            return Err(RalphError::RepoBusy(repo_id));
+        }
+        if self.is_session_running(session_id).await {
+            return Err(RalphError::SessionAlreadyRunning(session_id));
+        }
+
+        let prompt =
+            sanitize::prompt(prompt).map_err(|e| RalphError::InvalidArgument(e.to_string()))?;
+        let adapter = self.adapter_for(&db, session_id).await?;
+        let env = self.env_for(&db, session_id).await?;
+        let repo_path = Path::new(&repo.path);
+        let name = repo_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .ok_or_else(|| RalphError::InvalidArgument("Repository path has no name".to_string()))?;
+        let assignment = ToWorker::Run {
+            session_id,
+            repo: name,
+            url: GitManager::remote_url(repo_path, "origin").ok(),
+            command: std::iter::once(adapter.program().to_string())
+                .chain(adapter.args(prompt))
+                .collect(),
+            env,
+        };
+
+        {
+            let mut inner = self.inner.write().await;
+            workers
+                .send(worker_id, assignment)
+                .map_err(|_| RalphError::WorkerUnavailable(worker_id))?;
+            inner.remote.insert(
+                session_id,
+                RemoteRun {
+                    worker_id,
+                    repo_id,
+                    adapter,
+                    outcome: Arc::new(std::sync::Mutex::new(RunOutcome::default())),
+                },
+            );
+            inner.active_repos.insert(repo_id, session_id);
+        }
+
+        self.started(session_id, repo_id, prompt, &db, &connections).await;
+        Ok(())
+    }
+
+    /// Record a line of output a worker sent for one of its runs
+    pub async fn worker_output(
+        &self,
+        worker_id: Uuid,
+        session_id: Uuid,
+        stream: OutputStream,
+        line: String,
+        db: &Database,
+        connections: &ConnectionManager,
+    ) {
+        let stream = match stream {
+            OutputStream::Stdout => DbOutputStream::Stdout,
+            OutputStream::Stderr => DbOutputStream::Stderr,
+        };
+        let line = {
+            let inner = self.inner.read().await;
+            match inner.remote.get(&session_id) {
+                Some(run) if run.worker_id == worker_id => {
+                    let line = self.output_limits.truncate(line, 0);
+                    read_line(run.adapter.as_ref(), &run.outcome, stream, line)
+                }
+                // Output that arrives after a run was cancelled is dropped
+                _ => return,
+            }
+        };
 
-        Ok(())
+        record_output(db, connections, &self.output_limits, session_id, stream, line).await;
     }
 
-    /// Handle process exit - cleanup and update status
-    async fn handle_process_exit(
+    /// Finish a run a worker reported as ended
+    pub async fn worker_exited(
         &self,
+        worker_id: Uuid,
         session_id: Uuid,
-        repo_id: Uuid,
+        success: bool,
         db: Arc<Database>,
         connections: ConnectionManager,
     ) {
-        // Get the exit status
-        let exit_status = {
+        let run = {
             let mut inner = self.inner.write().await;
-            if let Some(mut handle) = inner.processes.remove(&session_id) {
-                inner.active_repos.remove(&repo_id);
-                // Wait for the child to fully exit
-                handle.child.wait().await.ok()
-            } else {
-                None
+            match inner.remote.get(&session_id) {
+                Some(run) if run.worker_id == worker_id => {}
+                _ => return,
             }
+            let run = inner.remote.remove(&session_id).expect("run was just found");
+            inner.active_repos.remove(&run.repo_id);
+            run
+        };
+        if let Some(workers) = &self.workers {
+            workers.finished(worker_id, session_id);
+        }
+
+        let outcome = run.outcome.lock().unwrap().clone();
+        let final_status = match (success, outcome.verdict) {
+            (true, Some(status)) => status,
+            (true, None) => DbSessionStatus::Completed,
+            (false, _) => DbSessionStatus::Error,
         };
+        self.finished(session_id, run.repo_id, final_status, outcome.failure, &db, &connections)
+            .await;
+    }
 
-        // Determine final status based on exit code
-        let final_status = match exit_status {
-            Some(status) if status.success() => DbSessionStatus::Completed,
-            Some(_) => DbSessionStatus::Error,
-            None => DbSessionStatus::Error,
+    /// Fail the runs of a worker that disconnected
+    pub async fn worker_disconnected(&self, worker_id: Uuid, db: Arc<Database>, connections: ConnectionManager) {
+        let runs: Vec<(Uuid, Uuid)> = {
+            let mut inner = self.inner.write().await;
+            let sessions: Vec<Uuid> = inner
+                .remote
+                .iter()
+                .filter(|(_, run)| run.worker_id == worker_id)
+                .map(|(session_id, _)| *session_id)
+                .collect();
+            sessions
+                .into_iter()
+                .filter_map(|session_id| {
+                    let run = inner.remote.remove(&session_id)?;
+                    inner.active_repos.remove(&run.repo_id);
+                    Some((session_id, run.repo_id))
+                })
+                .collect()
         };
 
-        // Update database
-        if let Err(e) = db.update_session_status(session_id, final_status) {
-            tracing::error!("Failed to update session status: {}", e);
+        for (session_id, repo_id) in runs {
+            let message = "Lost connection to the worker running this session".to_string();
+            let line = OutputLine {
+                content: message,
+                channel: DbOutputChannel::System,
+                iteration: None,
+                usage: None,
+                events: None,
+            };
+            record_output(&db, &connections, &self.output_limits, session_id, DbOutputStream::Stderr, line)
+                .await;
+            self.finished(session_id, repo_id, DbSessionStatus::Error, None, &db, &connections)
+                .await;
         }
+    }
 
-        // Broadcast final status
-        connections
-            .broadcast(
-                session_id,
-                ServerMessage::Status {
-                    session_id,
-                    status: final_status.into(),
-                },
-            )
-            .await;
+    /// Send a line of follow-up input to a running session's process
+    ///
+    /// A newline is added if the text doesn't end with one. Only local runs
+    /// take input.
+    pub async fn send_input(&self, session_id: Uuid, text: &str) -> Result<(), RalphError> {
+        let text =
+            sanitize::prompt(text).map_err(|e| RalphError::InvalidArgument(e.to_string()))?;
+        let input = {
+            let inner = self.inner.read().await;
+            if inner.remote.contains_key(&session_id) {
+                return Err(RalphError::InvalidArgument(format!(
+                    "Session {} runs on a worker, which doesn't take input",
+                    session_id
+                )));
+            }
+            inner
+                .processes
+                .get(&session_id)
+                .and_then(|handle| handle.input.clone())
+                .ok_or(RalphError::NotRunning(session_id))?
+        };
 
-        tracing::info!(
-            "Ralph process for session {} finished with status: {:?}",
-            session_id,
-            final_status
-        );
+        let mut line = text.to_string();
+        if !line.ends_with('\n') {
+            line.push('\n');
+        }
+        // Locked for the whole line so concurrent input doesn't interleave
+        input
+            .lock()
+            .await
+            .write(line.into_bytes())
+            .await
+            .map_err(|e| RalphError::InputFailed(e.to_string()))
     }
 
     /// Cancel a running ralph process
@@ -309,15 +1686,79 @@ impl RalphManager {
         db: Arc<Database>,
         connections: ConnectionManager,
     ) -> Result<(), RalphError> {
+        // A queued run never started, so there's nothing to stop
+        let queued = self.inner.write().await.dequeue(session_id);
+        if let Some((_, waiting)) = queued {
+            let status = DbSessionStatus::Cancelled;
+            if let Err(e) = db.call(move |db| db.update_session_status(session_id, status)).await {
+                tracing::error!("Failed to update session status: {}", e);
+            }
+            connections
+                .broadcast(
+                    session_id,
+                    ServerMessage::Status {
+                        session_id,
+                        status: WsSessionStatus::Cancelled,
+                    },
+                )
+                .await;
+            self.announce_positions(&waiting, &connections).await;
+            self.step_finished(session_id, DbSessionStatus::Cancelled, &db, &connections)
+                .await;
+            return Ok(());
+        }
+
+        // A worker stops the process itself; the run is over as far as we're concerned
+        let remote = {
+            let mut inner = self.inner.write().await;
+            let run = inner.remote.remove(&session_id);
+            if let Some(run) = &run {
+                inner.active_repos.remove(&run.repo_id);
+            }
+            run
+        };
+        if let Some(run) = remote {
+            if let Some(workers) = &self.workers
+                && let Err(e) = workers.send(run.worker_id, ToWorker::Cancel { session_id })
+            {
+                tracing::warn!("Failed to cancel session {} on its worker: {}", session_id, e);
+            }
+            self.cancelled(session_id, run.repo_id, &db, &connections).await;
+            return Ok(());
+        }
+
         let (child_id, repo_id) = {
             let inner = self.inner.read().await;
             if let Some(handle) = inner.processes.get(&session_id) {
-                (handle.child.id(), handle.repo_id)
+                (handle.process.id(), handle.repo_id)
             } else {
                 return Err(RalphError::NotRunning(session_id));
             }
         };
 
+        self.terminate(session_id, child_id).await;
+
+        // Remove from tracking and update status
+        {
+            let mut inner = self.inner.write().await;
+            inner.processes.remove(&session_id);
+            inner.active_repos.remove(&repo_id);
+        }
+
+        self.cancelled(session_id, repo_id, &db, &connections).await;
+        Ok(())
+    }
+
+    /// Stop a local run's process and everything it started
+    ///
+    /// `child_id` leads the run's process group.
+    #[cfg_attr(not(unix), allow(unused_variables))]
+    async fn terminate(&self, session_id: Uuid, child_id: Option<u32>) {
+        let sandboxed = {
+            let inner = self.inner.read().await;
+            inner.processes.get(&session_id).is_some_and(|handle| handle.sandboxed)
+        };
+
         // Send SIGTERM to the process group on Unix
         #[cfg(unix)]
         {
@@ -349,21 +1790,41 @@ impl RalphManager {
         {
             let mut inner = self.inner.write().await;
             if let Some(handle) = inner.processes.get_mut(&session_id) {
-                let _ = handle.child.kill().await;
+                handle.process.kill().await;
             }
         }
 
-        // Remove from tracking and update status
-        {
-            let mut inner = self.inner.write().await;
-            inner.processes.remove(&session_id);
-            inner.active_repos.remove(&repo_id);
+        // The container outlives the runtime's client when that's killed
+        if sandboxed && let Some(sandbox) = &self.sandbox {
+            sandbox.remove(session_id).await;
         }
+    }
+
+    /// Record a cancelled run and tell everyone who's listening
+    async fn cancelled(&self, session_id: Uuid, repo_id: Uuid, db: &Database, connections: &ConnectionManager) {
+        if let Some(keep_awake) = &self.keep_awake {
+            keep_awake.release(session_id);
+        }
+        self.discard_context(session_id);
 
         // Update database
-        if let Err(e) = db.update_session_status(session_id, DbSessionStatus::Cancelled) {
-            tracing::error!("Failed to update session status: {}", e);
+        let recorded = db.call(move |db| {
+            if let Err(e) = db.update_session_status(session_id, DbSessionStatus::Cancelled) {
+                tracing::error!("Failed to update session status: {}", e);
+            }
+            if let Err(e) = db.end_iteration(session_id) {
+                tracing::warn!("Failed to end the last iteration: {}", e);
+            }
+            if let Err(e) = db.finish_run(session_id, DbSessionStatus::Cancelled) {
+                tracing::warn!("Failed to finish the run: {}", e);
+            }
+            Ok(())
+        });
+        if let Err(e) = recorded.await {
+            tracing::error!("Failed to record the run's end: {}", e);
         }
+        self.step_finished(session_id, DbSessionStatus::Cancelled, db, connections)
+            .await;
 
         // Broadcast status
         connections
@@ -377,14 +1838,390 @@ impl RalphManager {
             .await;
 
         tracing::info!("Ralph process for session {} cancelled", session_id);
+        self.status_changed(
+            db,
+            session_id,
+            repo_id,
+            DbSessionStatus::Cancelled,
+            DbSessionStatus::Cancelled.as_str(),
+        )
+        .await;
 
-        Ok(())
+        self.start_next();
+    }
+
+    /// Stop every run before the server exits
+    ///
+    /// No run starts from then on, and queued sessions go back to idle. Local
+    /// runs get SIGTERM, and SIGKILL if they're still going after `timeout`;
+    /// runs on workers are cancelled there. Stopped sessions end cancelled,
+    /// with a line of output saying why. Returns how many runs were stopped.
+    pub async fn shutdown(&self, db: &Arc<Database>, connections: &ConnectionManager, timeout: Duration) -> usize {
+        let (queued, local, remote) = {
+            let mut inner = self.inner.write().await;
+            inner.shutting_down = true;
+            let queued: Vec<Uuid> = inner
+                .queues
+                .drain()
+                .flat_map(|(_, queue)| queue)
+                .map(|run| run.session_id)
+                .collect();
+            let local: Vec<(Uuid, Option<u32>, bool)> = inner
+                .processes
+                .iter()
+                .map(|(session_id, handle)| {
+                    // Stopping decides how the run ended, whatever its exit code
+                    let mut outcome = handle.outcome.lock().unwrap();
+                    outcome.verdict = Some(DbSessionStatus::Cancelled);
+                    outcome.failure = None;
+                    (*session_id, handle.process.id(), handle.sandboxed)
+                })
+                .collect();
+            let remote: Vec<(Uuid, RemoteRun)> = inner.remote.drain().collect();
+            for (_, run) in &remote {
+                inner.active_repos.remove(&run.repo_id);
+            }
+            (queued, local, remote)
+        };
+
+        for session_id in queued {
+            let status = DbSessionStatus::Idle;
+            if let Err(e) = db.call(move |db| db.update_session_status(session_id, status)).await {
+                tracing::error!("Failed to update session status: {}", e);
+            }
+            self.step_finished(session_id, DbSessionStatus::Cancelled, db, connections)
+                .await;
+            connections
+                .broadcast(
+                    session_id,
+                    ServerMessage::Status {
+                        session_id,
+                        status: WsSessionStatus::Idle,
+                    },
+                )
+                .await;
+        }
+
+        let message = "The server shut down while this session was running; its run was stopped";
+        let stopped = local.len() + remote.len();
+        for (session_id, run) in remote {
+            if let Some(workers) = &self.workers
+                && let Err(e) = workers.send(run.worker_id, ToWorker::Cancel { session_id })
+            {
+                tracing::warn!("Failed to cancel session {} on its worker: {}", session_id, e);
+            }
+            self.shutdown_notice(session_id, message, db, connections).await;
+            self.cancelled(session_id, run.repo_id, db, connections).await;
+        }
+
+        for (session_id, pid, _) in &local {
+            self.shutdown_notice(*session_id, message, db, connections).await;
+            self.signal_run(*session_id, *pid, false).await;
+        }
+        // Each run's own exit handling records how it ended
+        if !self.wait_for_runs(timeout).await {
+            for (session_id, pid, _) in &local {
+                self.signal_run(*session_id, *pid, true).await;
+            }
+            self.wait_for_runs(SHUTDOWN_KILL_WAIT).await;
+        }
+        if let Some(sandbox) = &self.sandbox {
+            for (session_id, _, sandboxed) in &local {
+                if *sandboxed {
+                    sandbox.remove(*session_id).await;
+                }
+            }
+        }
+
+        // Runs whose exit still hasn't been handled are recorded here
+        let left: Vec<(Uuid, Uuid)> = {
+            let mut inner = self.inner.write().await;
+            let left: Vec<(Uuid, Uuid)> = inner
+                .processes
+                .drain()
+                .map(|(session_id, handle)| (session_id, handle.repo_id))
+                .collect();
+            for (_, repo_id) in &left {
+                inner.active_repos.remove(repo_id);
+            }
+            left
+        };
+        for (session_id, repo_id) in left {
+            tracing::warn!("Run of session {} didn't exit in time", session_id);
+            self.cancelled(session_id, repo_id, db, connections).await;
+        }
+        stopped
+    }
+
+    /// Record why a run stopped in its session's output
+    async fn shutdown_notice(&self, session_id: Uuid, message: &str, db: &Database, connections: &ConnectionManager) {
+        let line = OutputLine {
+            content: message.to_string(),
+            channel: DbOutputChannel::System,
+            iteration: None,
+            usage: None,
+            events: None,
+        };
+        record_output(db, connections, &self.output_limits, session_id, DbOutputStream::Stderr, line)
+            .await;
+    }
+
+    /// Send SIGTERM, or SIGKILL when `kill`, to a local run's process group
+    ///
+    /// Without process groups the process is killed either way.
+    #[allow(unused_variables)]
+    async fn signal_run(&self, session_id: Uuid, pid: Option<u32>, kill: bool) {
+        #[cfg(unix)]
+        {
+            use nix::sys::signal::{killpg, Signal};
+            use nix::unistd::Pid;
+
+            let signal = if kill { Signal::SIGKILL } else { Signal::SIGTERM };
+            if let Some(pid) = pid
+                && let Err(e) = killpg(Pid::from_raw(pid as i32), signal)
+            {
+                tracing::warn!("Failed to send {:?} to process group: {}", signal, e);
+            }
+        }
+
+        #[cfg(not(unix))]
+        {
+            let mut inner = self.inner.write().await;
+            if let Some(handle) = inner.processes.get_mut(&session_id) {
+                handle.process.kill().await;
+            }
+        }
+    }
+
+    /// Wait up to `timeout` for every local run to be over; whether they all are
+    async fn wait_for_runs(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if self.inner.read().await.processes.is_empty() {
+                return true;
+            }
+            if Instant::now() >= deadline {
+                return false;
+            }
+            tokio::time::sleep(SHUTDOWN_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Fail sessions a previous run of the server left running
+    ///
+    /// Their processes went away with the server, or can no longer be
+    /// followed, so the sessions would otherwise show as running forever.
+    /// Each gets a line of output saying what happened. Returns how many
+    /// sessions were failed.
+    pub async fn reconcile_orphans(&self, db: &Database) -> usize {
+        let sessions = match db.call(|db| db.list_sessions()).await {
+            Ok(sessions) => sessions,
+            Err(e) => {
+                tracing::warn!("Failed to look for orphaned sessions: {}", e);
+                return 0;
+            }
+        };
+        let live = self.active_sessions().await;
+        let orphaned: Vec<_> = sessions
+            .into_iter()
+            .filter(|session| session.status == DbSessionStatus::Running && !live.contains(&session.id))
+            .collect();
+
+        let message = "The server stopped while this session was running; its run was lost";
+        for session in &orphaned {
+            tracing::warn!(
+                "Session {} was running when the server stopped; marking it as failed",
+                session.id
+            );
+            let session_id = session.id;
+            let failed = db.call(move |db| {
+                if let Err(e) = db.insert_channel_output_log(
+                    session_id,
+                    DbOutputStream::Stderr,
+                    DbOutputChannel::System,
+                    message,
+                    false,
+                ) {
+                    tracing::warn!("Failed to persist stderr output: {}", e);
+                }
+                if let Err(e) = db.update_session_status(session_id, DbSessionStatus::Error) {
+                    tracing::error!("Failed to update session status: {}", e);
+                }
+                if let Err(e) = db.end_iteration(session_id) {
+                    tracing::warn!("Failed to end the last iteration: {}", e);
+                }
+                if let Err(e) = db.finish_run(session_id, DbSessionStatus::Error) {
+                    tracing::warn!("Failed to finish the run: {}", e);
+                }
+                if let Err(e) = db.finish_pipeline_step(session_id, StepStatus::Error) {
+                    tracing::warn!("Failed to finish the pipeline step: {}", e);
+                }
+                Ok(())
+            });
+            if let Err(e) = failed.await {
+                tracing::error!("Failed to fail orphaned session {}: {}", session_id, e);
+            }
+            self.status_changed(db, session_id, session.repo_id, DbSessionStatus::Error, message)
+                .await;
+        }
+        orphaned.len()
     }
 
     /// Get list of active sessions
     pub async fn active_sessions(&self) -> Vec<Uuid> {
         let inner = self.inner.read().await;
-        inner.processes.keys().copied().collect()
+        inner.processes.keys().chain(inner.remote.keys()).copied().collect()
+    }
+
+    /// Number of runs going on this machine
+    pub async fn local_run_count(&self) -> usize {
+        self.inner.read().await.processes.len()
+    }
+}
+
+/// A line of output ready to be recorded
+struct OutputLine {
+    content: String,
+    channel: DbOutputChannel,
+    /// Set when the line starts a new iteration, with its number if it has one
+    iteration: Option<Option<u32>>,
+    /// Tokens and cost the line reported
+    usage: Option<TokenUsage>,
+    /// What the agent did, when the line is a structured event; recorded as
+    /// messages instead of output
+    events: Option<Vec<RunEvent>>,
+}
+
+/// Apply an orchestrator's output parser and status patterns to a line
+///
+/// An error match is final; a completed match holds unless an error follows.
+/// Lines on stderr stay on the stderr channel whatever the parser says, and
+/// the first one matching a known failure signature is kept as the failure.
+fn read_line(
+    adapter: &dyn OrchestratorAdapter,
+    outcome: &std::sync::Mutex<RunOutcome>,
+    stream: DbOutputStream,
+    line: String,
+) -> OutputLine {
+    let default = DbOutputChannel::for_stream(stream);
+    if stream == DbOutputStream::Stderr {
+        let mut outcome = outcome.lock().unwrap();
+        if outcome.failure.is_none() {
+            outcome.failure = failure::classify(&line);
+        }
+    }
+    if let Some(status) = adapter.status_for(&line) {
+        let mut outcome = outcome.lock().unwrap();
+        if outcome.verdict != Some(DbSessionStatus::Error) {
+            outcome.verdict = Some(status);
+        }
+    }
+    let iteration = adapter.iteration_for(&line);
+    let (usage, events) = match stream {
+        DbOutputStream::Stdout => (usage::parse(&line), events::parse(&line)),
+        DbOutputStream::Stderr => (None, None),
+    };
+    let (content, channel) = adapter.parse_line(line);
+    OutputLine {
+        content,
+        channel: match stream {
+            DbOutputStream::Stdout => channel.unwrap_or(default),
+            DbOutputStream::Stderr => default,
+        },
+        iteration,
+        usage,
+        events,
+    }
+}
+
+/// Persist a line of session output and broadcast it to subscribers
+///
+/// Lines longer than the chunk size go out in pieces, each but the last
+/// marked as continuing. A structured event is recorded as messages instead.
+async fn record_output(
+    db: &Database,
+    connections: &ConnectionManager,
+    limits: &OutputLimits,
+    session_id: Uuid,
+    stream: DbOutputStream,
+    line: OutputLine,
+) {
+    if let Some(events) = line.events {
+        // The iteration starts with whatever output comes next
+        if let Some(number) = line.iteration {
+            let started = db.call(move |db| db.start_iteration_at_next_output(session_id, number));
+            if let Err(e) = started.await {
+                tracing::warn!("Failed to record iteration: {}", e);
+            }
+        }
+        for event in events {
+            let (kind, content, data) = event.into_message();
+            let inserted = db.call(move |db| {
+                db.insert_typed_message(session_id, MessageRole::Assistant, kind, &content, data.as_ref())
+            });
+            match inserted.await {
+                Ok(message) => {
+                    connections
+                        .broadcast(session_id, ServerMessage::Message { session_id, message })
+                        .await;
+                }
+                Err(e) => tracing::warn!("Failed to persist {} message: {}", kind.as_str(), e),
+            }
+        }
+    } else {
+        let chunks = limits.chunks(line.content);
+        let count = chunks.len();
+        for (index, content) in chunks.into_iter().enumerate() {
+            let continues = index + 1 < count;
+            let (channel, iteration, text) = (line.channel, line.iteration, content.clone());
+            let inserted = db.call(move |db| {
+                let log = db.insert_channel_output_log(session_id, stream, channel, &text, continues)?;
+                if index == 0
+                    && let Some(number) = iteration
+                    && let Err(e) = db.start_iteration(session_id, number, log.id)
+                {
+                    tracing::warn!("Failed to record iteration: {}", e);
+                }
+                Ok(())
+            });
+            if let Err(e) = inserted.await {
+                tracing::warn!("Failed to persist {} output: {}", stream.as_str(), e);
+            }
+            connections
+                .broadcast(
+                    session_id,
+                    ServerMessage::Output {
+                        session_id,
+                        stream: stream.into(),
+                        channel: line.channel.into(),
+                        content,
+                        continues,
+                    },
+                )
+                .await;
+        }
+    }
+
+    if let Some(usage) = line.usage {
+        match db.call(move |db| db.add_run_usage(session_id, &usage)).await {
+            Ok(Some(totals)) => {
+                connections
+                    .broadcast(
+                        session_id,
+                        ServerMessage::Cost {
+                            session_id,
+                            input_tokens: totals.input_tokens,
+                            output_tokens: totals.output_tokens,
+                            cache_read_tokens: totals.cache_read_tokens,
+                            cache_write_tokens: totals.cache_write_tokens,
+                            cost_usd: totals.cost_usd,
+                        },
+                    )
+                    .await;
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Failed to record token usage: {}", e),
+        }
     }
 }
 
@@ -403,12 +2240,27 @@ pub enum RalphError {
     #[error("Session {0} already has a running process")]
     SessionAlreadyRunning(Uuid),
 
+    #[error("{0} runs are already going, the most allowed at once")]
+    AtCapacity(usize),
+
     #[error("Failed to spawn ralph process: {0}")]
     SpawnFailed(String),
 
     #[error("Session {0} has no running process")]
     NotRunning(Uuid),
 
+    #[error("Invalid argument: {0}")]
+    InvalidArgument(String),
+
+    #[error("Worker {0} is not connected")]
+    WorkerUnavailable(Uuid),
+
+    #[error("Failed to send input: {0}")]
+    InputFailed(String),
+
+    #[error("The server is shutting down")]
+    ShuttingDown,
+
     #[error("ralph CLI not found: {message}")]
     NotFound {
         message: String,
@@ -419,6 +2271,7 @@ pub enum RalphError {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::db::models::{MessageKind, Orchestrator};
 
     #[tokio::test]
     async fn test_manager_creation() {
@@ -441,4 +2294,282 @@ mod tests {
 
         assert!(!manager.is_session_running(session_id).await);
     }
+
+    #[tokio::test]
+    async fn test_iterations_from_output() {
+        let db = Database::in_memory().unwrap();
+        let connections = ConnectionManager::new();
+        let repo = db.insert_repo("/tmp/repo", "repo").unwrap();
+        let session = db.insert_session(repo.id, None, Orchestrator::Ralph).unwrap();
+        let outcome = std::sync::Mutex::new(RunOutcome::default());
+
+        for line in [
+            "Starting run",
+            "=== Iteration 1 ===",
+            "Reading the tests",
+            "Fixed the parser",
+            "=== Iteration 2 ===",
+            "All tests pass",
+        ] {
+            let line = read_line(&adapter::Ralph, &outcome, DbOutputStream::Stdout, line.to_string());
+            record_output(&db, &connections, &OutputLimits::default(), session.id, DbOutputStream::Stdout, line)
+                .await;
+        }
+        db.end_iteration(session.id).unwrap();
+
+        let iterations = db.list_iterations(session.id).unwrap();
+        assert_eq!(iterations.len(), 2);
+        assert_eq!(iterations[0].number, 1);
+        assert_eq!(iterations[0].summary.as_deref(), Some("Fixed the parser"));
+        assert_eq!(iterations[0].output_cursor, "1");
+        assert_eq!(iterations[0].last_output_id, Some(iterations[1].first_output_id - 1));
+        assert_eq!(iterations[1].number, 2);
+        assert_eq!(iterations[1].summary.as_deref(), Some("All tests pass"));
+        assert!(iterations.iter().all(|i| i.ended_at.is_some()));
+    }
+
+    #[tokio::test]
+    async fn test_events_become_messages() {
+        let db = Database::in_memory().unwrap();
+        let connections = ConnectionManager::new();
+        let repo = db.insert_repo("/tmp/repo", "repo").unwrap();
+        let session = db.insert_session(repo.id, None, Orchestrator::Claude).unwrap();
+        let outcome = std::sync::Mutex::new(RunOutcome::default());
+
+        for line in [
+            r#"{"type":"system","subtype":"init"}"#,
+            r#"{"type":"assistant","message":{"content":[{"type":"text","text":"Fixing it"},{"type":"tool_use","name":"Write","input":{"file_path":"src/main.rs","content":"fn main() {}"}}]}}"#,
+            r#"{"type":"user","message":{"content":[{"type":"tool_result","content":"File written"}]}}"#,
+        ] {
+            let line = read_line(&adapter::Claude, &outcome, DbOutputStream::Stdout, line.to_string());
+            record_output(&db, &connections, &OutputLimits::default(), session.id, DbOutputStream::Stdout, line)
+                .await;
+        }
+
+        let messages = db.list_messages(session.id).unwrap();
+        assert_eq!(
+            messages.iter().map(|m| (m.kind, m.content.as_str())).collect::<Vec<_>>(),
+            [(MessageKind::Text, "Fixing it"), (MessageKind::FileEdit, "src/main.rs")]
+        );
+        assert!(messages.iter().all(|m| m.role == MessageRole::Assistant));
+
+        // Only the lines that aren't agent events are output, and the
+        // iteration the event started begins after them
+        let output = db.list_output_logs(session.id, None, None, None).unwrap();
+        assert_eq!(
+            output.iter().map(|log| log.content.as_str()).collect::<Vec<_>>(),
+            ["system init", "File written"]
+        );
+        let iterations = db.list_iterations(session.id).unwrap();
+        assert_eq!(iterations[0].first_output_id, output[1].id);
+    }
+
+    #[tokio::test]
+    async fn test_orphaned_sessions_fail_on_startup() {
+        let manager = RalphManager::new();
+        let db = Database::in_memory().unwrap();
+        let repo = db.insert_repo("/tmp/orphan-repo", "repo").unwrap();
+        let orphan = db.insert_session(repo.id, None, Orchestrator::Ralph).unwrap();
+        db.update_session_status(orphan.id, DbSessionStatus::Running).unwrap();
+        let finished = db.insert_session(repo.id, None, Orchestrator::Ralph).unwrap();
+        db.update_session_status(finished.id, DbSessionStatus::Completed).unwrap();
+
+        assert_eq!(manager.reconcile_orphans(&db).await, 1);
+        assert_eq!(db.get_session(orphan.id).unwrap().status, DbSessionStatus::Error);
+        assert_eq!(db.get_session(finished.id).unwrap().status, DbSessionStatus::Completed);
+        let logs = db.list_output_logs(orphan.id, None, None, None).unwrap();
+        assert!(logs[0].content.contains("server stopped"));
+
+        assert_eq!(manager.reconcile_orphans(&db).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_runs_queue_behind_busy_repo() {
+        let manager = RalphManager::new();
+        let db = Arc::new(Database::in_memory().unwrap());
+        let connections = ConnectionManager::new();
+        let repo = db.insert_repo("/tmp/queued-repo", "repo").unwrap();
+        let first = db.insert_session(repo.id, None, Orchestrator::Ralph).unwrap();
+        let second = db.insert_session(repo.id, None, Orchestrator::Ralph).unwrap();
+        let running = Uuid::new_v4();
+        manager.inner.write().await.active_repos.insert(repo.id, running);
+
+        for (session, position) in [(&first, 1), (&second, 2)] {
+            let started = manager
+                .run_or_queue(session.id, &repo, RunOptions::default(), "fix it", db.clone(), connections.clone())
+                .await
+                .unwrap();
+            assert_eq!(started, RunStart::Queued { position });
+        }
+        assert_eq!(db.get_session(first.id).unwrap().status, DbSessionStatus::Queued);
+        assert!(matches!(
+            manager
+                .run_or_queue(first.id, &repo, RunOptions::default(), "again", db.clone(), connections.clone())
+                .await,
+            Err(RalphError::SessionAlreadyRunning(_))
+        ));
+        assert_eq!(manager.queued_sessions(repo.id).await, vec![first.id, second.id]);
+
+        manager.cancel(first.id, db.clone(), connections.clone()).await.unwrap();
+        assert_eq!(db.get_session(first.id).unwrap().status, DbSessionStatus::Cancelled);
+        assert_eq!(manager.queue_position(first.id).await, None);
+        assert_eq!(manager.queue_position(second.id).await, Some(1));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_runs_queue_at_concurrency_limit() {
+        let manager = RalphManager::new().with_max_concurrent_runs(1);
+        let db = Arc::new(Database::in_memory().unwrap());
+        let connections = ConnectionManager::new();
+        let repo = db.insert_repo("/tmp/ralphtown-missing-repo", "repo").unwrap();
+        let session = db.insert_session(repo.id, None, Orchestrator::Ralph).unwrap();
+
+        // Another repository's run takes the only slot
+        let spawned = process::spawn_piped("sleep", &["30".to_string()], Path::new("/tmp"), &BTreeMap::new(), None).unwrap();
+        let running = Uuid::new_v4();
+        manager.inner.write().await.processes.insert(
+            running,
+            ProcessHandle {
+                process: spawned.process,
+                input: None,
+                repo_id: Uuid::new_v4(),
+                repo_path: PathBuf::from("/tmp"),
+                started_at: SystemTime::now(),
+                sandboxed: false,
+                outcome: Default::default(),
+                _watcher: None,
+            },
+        );
+
+        let started = manager
+            .run_or_queue(session.id, &repo, RunOptions::default(), "fix it", db.clone(), connections.clone())
+            .await
+            .unwrap();
+        assert_eq!(started, RunStart::Queued { position: 1 });
+        assert_eq!(
+            manager.queued_runs().await,
+            vec![QueueEntry {
+                session_id: session.id,
+                repo_id: repo.id,
+                position: 1,
+            }]
+        );
+
+        // Once the slot is free the queued run gets its turn, and fails
+        // here without a real repository
+        let mut handle = manager.inner.write().await.processes.remove(&running).unwrap();
+        handle.process.kill().await;
+        manager.start_queued().await;
+        assert!(manager.queued_runs().await.is_empty());
+        assert_eq!(db.get_session(session.id).unwrap().status, DbSessionStatus::Error);
+    }
+
+    #[tokio::test]
+    async fn test_stalled_run_settles_as_error() {
+        let manager = RalphManager::new().with_run_limits(RunLimits {
+            stall_after: Some(Duration::from_secs(60)),
+            stop_stalled: true,
+            ..Default::default()
+        });
+        let db = Database::in_memory().unwrap();
+        let connections = ConnectionManager::new();
+        let repo = db.insert_repo("/tmp/repo", "repo").unwrap();
+        let session = db.insert_session(repo.id, None, Orchestrator::Ralph).unwrap();
+        let outcome = std::sync::Mutex::new(RunOutcome::default());
+
+        manager
+            .stalled(session.id, Duration::from_secs(61), &outcome, &db, &connections)
+            .await;
+        let outcome = outcome.into_inner().unwrap();
+        assert_eq!(outcome.verdict, Some(DbSessionStatus::Error));
+        assert_eq!(outcome.failure.map(|f| f.reason), Some(FailureReason::Stalled));
+        let logs = db.list_output_logs(session.id, None, None, None).unwrap();
+        assert_eq!(logs[0].content, "Run stopped after 61 seconds without output");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_shutdown_stops_runs() {
+        let manager = RalphManager::new();
+        let db = Arc::new(Database::in_memory().unwrap());
+        let connections = ConnectionManager::new();
+        let repo = db.insert_repo("/tmp/ralphtown-missing-repo", "repo").unwrap();
+        let session = db.insert_session(repo.id, None, Orchestrator::Ralph).unwrap();
+        db.update_session_status(session.id, DbSessionStatus::Running).unwrap();
+
+        // The process ignores SIGTERM, and nothing handles its exit
+        let spawned = process::spawn_piped(
+            "sh",
+            &["-c".to_string(), "trap '' TERM; sleep 30".to_string()],
+            Path::new("/tmp"),
+            &BTreeMap::new(),
+            None,
+        )
+        .unwrap();
+        manager.inner.write().await.processes.insert(
+            session.id,
+            ProcessHandle {
+                process: spawned.process,
+                input: None,
+                repo_id: repo.id,
+                repo_path: PathBuf::from("/tmp"),
+                started_at: SystemTime::now(),
+                sandboxed: false,
+                outcome: Default::default(),
+                _watcher: None,
+            },
+        );
+
+        let stopped = manager
+            .shutdown(&db, &connections, Duration::from_millis(200))
+            .await;
+        assert_eq!(stopped, 1);
+        assert!(manager.active_sessions().await.is_empty());
+        assert_eq!(db.get_session(session.id).unwrap().status, DbSessionStatus::Cancelled);
+        let logs = db.list_output_logs(session.id, None, None, None).unwrap();
+        assert!(logs[0].content.contains("server shut down"));
+
+        // No run starts once the server is stopping
+        let other = db.insert_session(repo.id, None, Orchestrator::Ralph).unwrap();
+        assert!(matches!(
+            manager
+                .run_or_queue(other.id, &repo, RunOptions::default(), "fix it", db.clone(), connections.clone())
+                .await,
+            Err(RalphError::ShuttingDown)
+        ));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_input_reaches_process_stdin() {
+        use tokio::io::AsyncBufReadExt;
+
+        let manager = RalphManager::new();
+        let session_id = Uuid::new_v4();
+        assert!(matches!(
+            manager.send_input(session_id, "continue").await,
+            Err(RalphError::NotRunning(_))
+        ));
+
+        let mut spawned = process::spawn_piped("cat", &[], Path::new("/tmp"), &BTreeMap::new(), None).unwrap();
+        let (_, stdout) = spawned.outputs.remove(0);
+        manager.inner.write().await.processes.insert(
+            session_id,
+            ProcessHandle {
+                process: spawned.process,
+                input: spawned.input.map(|input| Arc::new(Mutex::new(input))),
+                repo_id: Uuid::new_v4(),
+                repo_path: PathBuf::from("/tmp"),
+                started_at: SystemTime::now(),
+                sandboxed: false,
+                outcome: Default::default(),
+                _watcher: None,
+            },
+        );
+
+        manager.send_input(session_id, "use the second approach").await.unwrap();
+        let mut lines = BufReader::new(stdout).lines();
+        assert_eq!(lines.next_line().await.unwrap().as_deref(), Some("use the second approach"));
+    }
 }
@@ -0,0 +1,161 @@
+//! Size limits for session output lines
+//!
+//! Agents sometimes print a single line of several megabytes, such as a
+//! base64 blob or minified JSON. Lines are read without holding more than the
+//! longest allowed line in memory: whatever is past `max_line_bytes` is
+//! dropped and a truncation marker says how much. What's kept is stored and
+//! streamed in pieces of at most `chunk_bytes`, each marked as continuing in
+//! the next, so no single row or WebSocket frame gets large.
+
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+
+/// Default longest line kept, in bytes
+pub const DEFAULT_MAX_LINE_BYTES: usize = 1024 * 1024;
+
+/// Default largest piece a long line is stored and streamed in, in bytes
+pub const DEFAULT_CHUNK_BYTES: usize = 64 * 1024;
+
+/// How long output lines may get
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputLimits {
+    /// Longest line kept; the rest is dropped
+    pub max_line_bytes: usize,
+    /// Largest piece a line is stored and streamed in
+    pub chunk_bytes: usize,
+}
+
+impl Default for OutputLimits {
+    fn default() -> Self {
+        Self {
+            max_line_bytes: DEFAULT_MAX_LINE_BYTES,
+            chunk_bytes: DEFAULT_CHUNK_BYTES,
+        }
+    }
+}
+
+impl OutputLimits {
+    /// Cut a line down to the longest allowed, marking what was dropped
+    ///
+    /// `dropped` counts bytes already left out while reading the line.
+    pub fn truncate(&self, mut line: String, mut dropped: usize) -> String {
+        if line.len() > self.max_line_bytes {
+            let end = floor_char_boundary(&line, self.max_line_bytes);
+            dropped += line.len() - end;
+            line.truncate(end);
+        }
+        if dropped > 0 {
+            line.push_str(&format!(" [... {} bytes truncated]", dropped));
+        }
+        line
+    }
+
+    /// Split a line into pieces of at most `chunk_bytes`, on character boundaries
+    pub fn chunks(&self, line: String) -> Vec<String> {
+        if line.len() <= self.chunk_bytes {
+            return vec![line];
+        }
+        let mut chunks = Vec::new();
+        let mut rest = line.as_str();
+        while !rest.is_empty() {
+            let mut end = floor_char_boundary(rest, self.chunk_bytes);
+            if end == 0 {
+                // A piece smaller than one character still has to make progress
+                end = rest.chars().next().map_or(rest.len(), char::len_utf8);
+            }
+            let (chunk, tail) = rest.split_at(end);
+            chunks.push(chunk.to_string());
+            rest = tail;
+        }
+        chunks
+    }
+}
+
+/// Largest index up to `index` that falls on a character boundary
+fn floor_char_boundary(s: &str, index: usize) -> usize {
+    if index >= s.len() {
+        return s.len();
+    }
+    (0..=index).rev().find(|i| s.is_char_boundary(*i)).unwrap_or(0)
+}
+
+/// Read a line, keeping at most `max` bytes of it
+///
+/// Returns the kept text and how many bytes past `max` were skipped, or None
+/// at the end of input. Invalid UTF-8 is replaced rather than ending the read.
+pub async fn read_bounded_line<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    max: usize,
+) -> std::io::Result<Option<(String, usize)>> {
+    let mut kept = Vec::new();
+    let mut dropped = 0;
+    let mut read_any = false;
+    loop {
+        let available = reader.fill_buf().await?;
+        if available.is_empty() {
+            if !read_any {
+                return Ok(None);
+            }
+            break;
+        }
+        read_any = true;
+        let newline = available.iter().position(|b| *b == b'\n');
+        let line_part = &available[..newline.unwrap_or(available.len())];
+        let take = line_part.len().min(max.saturating_sub(kept.len()));
+        kept.extend_from_slice(&line_part[..take]);
+        dropped += line_part.len() - take;
+        let consumed = newline.map_or(available.len(), |i| i + 1);
+        reader.consume(consumed);
+        if newline.is_some() {
+            break;
+        }
+    }
+    if kept.last() == Some(&b'\r') {
+        kept.pop();
+    }
+    Ok(Some((String::from_utf8_lossy(&kept).into_owned(), dropped)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::BufReader;
+
+    #[tokio::test]
+    async fn test_bounded_lines() {
+        let input = format!("short\r\n{}\nlast", "x".repeat(100));
+        let mut reader = BufReader::with_capacity(16, input.as_bytes());
+
+        assert_eq!(
+            read_bounded_line(&mut reader, 10).await.unwrap(),
+            Some(("short".to_string(), 0))
+        );
+        assert_eq!(
+            read_bounded_line(&mut reader, 10).await.unwrap(),
+            Some(("x".repeat(10), 90))
+        );
+        assert_eq!(
+            read_bounded_line(&mut reader, 10).await.unwrap(),
+            Some(("last".to_string(), 0))
+        );
+        assert_eq!(read_bounded_line(&mut reader, 10).await.unwrap(), None);
+    }
+
+    #[test]
+    fn test_truncate_and_chunk() {
+        let limits = OutputLimits {
+            max_line_bytes: 8,
+            chunk_bytes: 3,
+        };
+        assert_eq!(limits.truncate("short".to_string(), 0), "short");
+        assert_eq!(
+            limits.truncate("abcdefghij".to_string(), 5),
+            "abcdefgh [... 7 bytes truncated]"
+        );
+        // Multi-byte characters are never split
+        assert_eq!(limits.truncate("ééééé".to_string(), 0), "éééé [... 2 bytes truncated]");
+
+        assert_eq!(limits.chunks("abc".to_string()), ["abc"]);
+        assert_eq!(limits.chunks("abcdefg".to_string()), ["abc", "def", "g"]);
+        assert_eq!(limits.chunks("éééé".to_string()), ["é", "é", "é", "é"]);
+    }
+}
@@ -0,0 +1,264 @@
+//! Spawning run processes, on pipes or on a pseudo-terminal
+//!
+//! By default a run's stdout and stderr are separate pipes. CLIs notice
+//! that they aren't talking to a terminal, though: they drop colors, buffer
+//! output in blocks, or hide progress. In PTY mode the process gets a
+//! pseudo-terminal instead, and everything it writes, ANSI escape sequences
+//! included, arrives as one stream that's recorded as stdout.
+
+use std::collections::BTreeMap;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::Arc;
+
+use portable_pty::{native_pty_system, ChildKiller, CommandBuilder, PtySize};
+use tokio::io::{AsyncRead, AsyncWriteExt};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::oneshot;
+
+use crate::attachments::CONTEXT_DIR_ENV;
+use crate::db::models::OutputStream as DbOutputStream;
+
+/// Terminal size PTY runs see
+const PTY_COLS: u16 = 120;
+const PTY_ROWS: u16 = 40;
+
+/// Bytes buffered between the PTY reader thread and the output reader
+const PTY_BUFFER_BYTES: usize = 64 * 1024;
+
+/// A spawned run: the process, its input, and its output streams
+pub(super) struct Spawned {
+    pub process: RunProcess,
+    pub input: Option<ProcessInput>,
+    pub outputs: Vec<(DbOutputStream, Box<dyn AsyncRead + Send + Unpin>)>,
+}
+
+/// A running process, on pipes or on a pseudo-terminal
+pub(super) enum RunProcess {
+    Piped(Child),
+    Pty(PtyChild),
+}
+
+impl RunProcess {
+    /// Process ID, which is also its process group ID
+    pub fn id(&self) -> Option<u32> {
+        match self {
+            RunProcess::Piped(child) => child.id(),
+            RunProcess::Pty(child) => child.pid,
+        }
+    }
+
+    /// Wait for the process to exit
+    ///
+    /// `None` if its exit couldn't be read; otherwise its exit code, which is
+    /// `None` when a signal ended it.
+    pub async fn wait(&mut self) -> Option<Option<i32>> {
+        match self {
+            RunProcess::Piped(child) => child.wait().await.ok().map(|status| status.code()),
+            RunProcess::Pty(child) => child.exit.take()?.await.ok(),
+        }
+    }
+
+    /// Kill the process outright
+    #[cfg_attr(unix, allow(dead_code))]
+    pub async fn kill(&mut self) {
+        match self {
+            RunProcess::Piped(child) => {
+                let _ = child.kill().await;
+            }
+            RunProcess::Pty(child) => {
+                let _ = child.killer.kill();
+            }
+        }
+    }
+}
+
+/// A process attached to a pseudo-terminal
+pub(super) struct PtyChild {
+    pid: Option<u32>,
+    killer: Box<dyn ChildKiller + Send + Sync>,
+    /// Its exit code, sent once it exits
+    exit: Option<oneshot::Receiver<Option<i32>>>,
+}
+
+/// Where follow-up input to a run is written
+pub(super) enum ProcessInput {
+    Pipe(ChildStdin),
+    Pty(Arc<std::sync::Mutex<Box<dyn Write + Send>>>),
+}
+
+impl ProcessInput {
+    pub async fn write(&mut self, bytes: Vec<u8>) -> io::Result<()> {
+        match self {
+            ProcessInput::Pipe(stdin) => {
+                stdin.write_all(&bytes).await?;
+                stdin.flush().await
+            }
+            ProcessInput::Pty(writer) => {
+                // The PTY only offers blocking writes, which wait while the
+                // process isn't reading
+                let writer = writer.clone();
+                tokio::task::spawn_blocking(move || {
+                    let mut writer = writer.lock().unwrap();
+                    writer.write_all(&bytes)?;
+                    writer.flush()
+                })
+                .await
+                .map_err(io::Error::other)?
+            }
+        }
+    }
+}
+
+/// Start `program` with its output on pipes, and `env` added to its environment
+pub(super) fn spawn_piped(
+    program: &str,
+    args: &[String],
+    dir: &Path,
+    env: &BTreeMap<String, String>,
+    context_dir: Option<&Path>,
+) -> io::Result<Spawned> {
+    let mut cmd = Command::new(program);
+    cmd.args(args)
+        .current_dir(dir)
+        .envs(env)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .stdin(Stdio::piped());
+    if let Some(dir) = context_dir {
+        cmd.env(CONTEXT_DIR_ENV, dir);
+    }
+
+    // On Unix, set up process group for signal handling
+    #[cfg(unix)]
+    {
+        #[allow(unused_imports)]
+        use std::os::unix::process::CommandExt;
+        // SAFETY: setpgid is safe to call in pre_exec, it's a standard
+        // POSIX function that sets the process group for signal handling
+        unsafe {
+            cmd.pre_exec(|| {
+                // Set this process as the process group leader
+                // This allows us to send signals to the entire process group
+                libc::setpgid(0, 0);
+                Ok(())
+            });
+        }
+    }
+
+    let mut child = cmd.spawn()?;
+    let stdout = child.stdout.take().expect("stdout was configured");
+    let stderr = child.stderr.take().expect("stderr was configured");
+    let input = child.stdin.take().map(ProcessInput::Pipe);
+    Ok(Spawned {
+        process: RunProcess::Piped(child),
+        input,
+        outputs: vec![
+            (DbOutputStream::Stdout, Box::new(stdout)),
+            (DbOutputStream::Stderr, Box::new(stderr)),
+        ],
+    })
+}
+
+/// Start `program` on a pseudo-terminal, with `env` added to its environment
+///
+/// The process leads its own session, so signalling its process group
+/// reaches everything it started.
+pub(super) fn spawn_pty(
+    program: &str,
+    args: &[String],
+    dir: &Path,
+    env: &BTreeMap<String, String>,
+    context_dir: Option<&Path>,
+) -> io::Result<Spawned> {
+    // The PTY reports a missing program only as text; look it up first so
+    // it's reported like a missing program on pipes
+    which::which(program).map_err(|e| io::Error::new(io::ErrorKind::NotFound, e))?;
+
+    let pair = native_pty_system()
+        .openpty(PtySize {
+            rows: PTY_ROWS,
+            cols: PTY_COLS,
+            pixel_width: 0,
+            pixel_height: 0,
+        })
+        .map_err(pty_error)?;
+    let mut cmd = CommandBuilder::new(program);
+    cmd.args(args);
+    cmd.cwd(dir);
+    cmd.env("TERM", "xterm-256color");
+    for (name, value) in env {
+        cmd.env(name, value);
+    }
+    if let Some(dir) = context_dir {
+        cmd.env(CONTEXT_DIR_ENV, dir);
+    }
+    let mut child = pair.slave.spawn_command(cmd).map_err(pty_error)?;
+    // The process holds its own end; keeping ours would hide its exit from the reader
+    drop(pair.slave);
+    let mut reader = pair.master.try_clone_reader().map_err(pty_error)?;
+    let writer = pair.master.take_writer().map_err(pty_error)?;
+
+    let pid = child.process_id();
+    let killer = child.clone_killer();
+    let (exit_tx, exit_rx) = oneshot::channel();
+    tokio::task::spawn_blocking(move || {
+        // A PTY child killed by a signal reports exit code 1
+        let code = child.wait().ok().map(|status| status.exit_code() as i32);
+        let _ = exit_tx.send(code);
+    });
+
+    // The PTY only offers blocking reads; a thread copies them into a pipe
+    // the output reader can await
+    let (output, mut sink) = tokio::io::duplex(PTY_BUFFER_BYTES);
+    let runtime = tokio::runtime::Handle::current();
+    std::thread::spawn(move || {
+        let mut buf = [0u8; 8192];
+        loop {
+            match reader.read(&mut buf) {
+                // Reads fail with EIO once the process is gone
+                Ok(0) | Err(_) => break,
+                Ok(read) => {
+                    if runtime.block_on(sink.write_all(&buf[..read])).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+
+    Ok(Spawned {
+        process: RunProcess::Pty(PtyChild {
+            pid,
+            killer,
+            exit: Some(exit_rx),
+        }),
+        input: Some(ProcessInput::Pty(Arc::new(std::sync::Mutex::new(writer)))),
+        outputs: vec![(DbOutputStream::Stdout, Box::new(output))],
+    })
+}
+
+fn pty_error(e: impl std::fmt::Display) -> io::Error {
+    io::Error::other(e.to_string())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+
+    #[tokio::test]
+    async fn test_pty_run_sees_a_terminal() {
+        let args = vec!["-c".to_string(), "test -t 1 && printf \"\\033[32m$MODE\\033[0m\"".to_string()];
+        let env = BTreeMap::from([("MODE".to_string(), "tty".to_string())]);
+        let mut spawned = spawn_pty("sh", &args, Path::new("/tmp"), &env, None).unwrap();
+        let (stream, mut output) = spawned.outputs.pop().unwrap();
+        assert_eq!(stream, DbOutputStream::Stdout);
+
+        let mut seen = Vec::new();
+        output.read_to_end(&mut seen).await.unwrap();
+        assert_eq!(String::from_utf8_lossy(&seen), "\u{1b}[32mtty\u{1b}[0m");
+        assert_eq!(spawned.process.wait().await, Some(Some(0)));
+    }
+}
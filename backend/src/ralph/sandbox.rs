@@ -0,0 +1,153 @@
+//! Running orchestrators inside a container
+//!
+//! A sandboxed run starts the orchestrator in a throwaway Docker or Podman
+//! container instead of directly on the host. The repository is bind-mounted
+//! at `/workspace`, so the agent can change the code but nothing else on the
+//! host: commands it runs stay inside the container. The image has to provide
+//! the orchestrator's CLI.
+//!
+//! Files are written as the server's user, so the repository's ownership
+//! doesn't change.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use uuid::Uuid;
+
+use crate::attachments::CONTEXT_DIR_ENV;
+
+/// Where the repository is mounted inside the container
+const WORKSPACE: &str = "/workspace";
+
+/// Container runtimes looked for when none is configured, in order
+const RUNTIMES: [&str; 2] = ["docker", "podman"];
+
+/// The container runtime and image sandboxed runs use
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sandbox {
+    runtime: String,
+    image: String,
+}
+
+/// What a sandboxed run needs from the host
+pub(super) struct ContainerRun<'a> {
+    pub session_id: Uuid,
+    pub repo_path: &'a Path,
+    /// Where in the repository the command starts
+    pub subdir: &'a Path,
+    /// Attachments directory, mounted read-only at the same path
+    pub context_dir: Option<&'a Path>,
+    /// Variables passed through from the runtime's environment
+    pub env: &'a BTreeMap<String, String>,
+    pub max_memory_bytes: Option<u64>,
+    /// Give the container a terminal
+    pub tty: bool,
+}
+
+impl Sandbox {
+    /// Run in `image` with `runtime`, or the first of Docker and Podman
+    /// that's installed when `runtime` is `None`
+    pub fn new(runtime: Option<String>, image: String) -> Self {
+        let runtime = runtime.unwrap_or_else(|| {
+            RUNTIMES
+                .into_iter()
+                .find(|runtime| which::which(runtime).is_ok())
+                .unwrap_or(RUNTIMES[0])
+                .to_string()
+        });
+        Self { runtime, image }
+    }
+
+    /// Name of a session's container, so it can be stopped by name
+    pub(super) fn container_name(session_id: Uuid) -> String {
+        format!("ralphtown-{}", session_id)
+    }
+
+    /// Command line that runs `command` inside a new container
+    pub(super) fn command(&self, run: &ContainerRun, command: Vec<String>) -> Vec<String> {
+        let workdir = if run.subdir.as_os_str().is_empty() {
+            WORKSPACE.to_string()
+        } else {
+            format!("{}/{}", WORKSPACE, run.subdir.display())
+        };
+        let mut args = vec![
+            self.runtime.clone(),
+            "run".to_string(),
+            "--rm".to_string(),
+            "--interactive".to_string(),
+            "--init".to_string(),
+            format!("--name={}", Self::container_name(run.session_id)),
+            format!("--volume={}:{}", run.repo_path.display(), WORKSPACE),
+            format!("--workdir={}", workdir),
+        ];
+        if run.tty {
+            args.push("--tty".to_string());
+        }
+        #[cfg(unix)]
+        {
+            // SAFETY: getuid and getgid have no preconditions
+            let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+            args.push(format!("--user={}:{}", uid, gid));
+        }
+        if let Some(max) = run.max_memory_bytes {
+            args.push(format!("--memory={}", max));
+        }
+        if let Some(dir) = run.context_dir {
+            args.push(format!("--volume={}:{}:ro", dir.display(), dir.display()));
+            args.push(format!("--env={}", CONTEXT_DIR_ENV));
+        }
+        // Only names go on the command line; values come from the runtime's
+        // environment, so secrets don't show up in the process list
+        args.extend(run.env.keys().map(|name| format!("--env={}", name)));
+        args.push(self.image.clone());
+        args.extend(command);
+        args
+    }
+
+    /// Remove a session's container, if it's still there
+    ///
+    /// Killing the runtime's client doesn't stop the container it started.
+    pub(super) async fn remove(&self, session_id: Uuid) {
+        let removed = tokio::process::Command::new(&self.runtime)
+            .args(["rm", "--force", &Self::container_name(session_id)])
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status()
+            .await;
+        if let Err(e) = removed {
+            tracing::warn!("Failed to remove the container of session {}: {}", session_id, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_container_command() {
+        let sandbox = Sandbox::new(Some("podman".to_string()), "agents:latest".to_string());
+        let session_id = Uuid::new_v4();
+        let env = BTreeMap::from([("ANTHROPIC_API_KEY".to_string(), "secret".to_string())]);
+        let run = ContainerRun {
+            session_id,
+            repo_path: Path::new("/home/me/app"),
+            subdir: Path::new("packages/web"),
+            context_dir: None,
+            env: &env,
+            max_memory_bytes: Some(1024),
+            tty: false,
+        };
+        let command = sandbox.command(&run, vec!["ralph".to_string(), "run".to_string()]);
+
+        assert_eq!(command[..3], ["podman", "run", "--rm"]);
+        assert!(command.contains(&"--volume=/home/me/app:/workspace".to_string()));
+        assert!(command.contains(&"--workdir=/workspace/packages/web".to_string()));
+        assert!(command.contains(&format!("--name=ralphtown-{}", session_id)));
+        assert!(command.contains(&"--memory=1024".to_string()));
+        assert!(command.contains(&"--env=ANTHROPIC_API_KEY".to_string()));
+        assert!(!command.iter().any(|arg| arg.contains("secret")));
+        assert!(!command.contains(&"--tty".to_string()));
+        assert_eq!(command[command.len() - 3..], ["agents:latest", "ralph", "run"]);
+    }
+}
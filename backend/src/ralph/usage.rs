@@ -0,0 +1,108 @@
+//! Token counts and cost reported in run output
+//!
+//! Claude Code ends each invocation with a `result` event that holds the
+//! tokens it used and what they cost. Ralph runs Claude once per iteration and
+//! passes those events through, and other orchestrators print plain lines
+//! such as `Input tokens: 1,234` or `Total cost: $0.05`. Each report counts
+//! toward the run's totals.
+
+use std::sync::OnceLock;
+
+use regex::Regex;
+use serde_json::Value;
+
+use crate::db::models::TokenUsage;
+
+/// Usage a line of output reports, if any
+pub(super) fn parse(line: &str) -> Option<TokenUsage> {
+    match serde_json::from_str::<Value>(line) {
+        Ok(Value::Object(event)) => from_result_event(&event),
+        _ => from_text(line),
+    }
+}
+
+/// Usage in a Claude Code `result` event
+fn from_result_event(event: &serde_json::Map<String, Value>) -> Option<TokenUsage> {
+    if event.get("type").and_then(Value::as_str) != Some("result") {
+        return None;
+    }
+    let tokens = |name: &str| {
+        event
+            .get("usage")
+            .and_then(|usage| usage.get(name))
+            .and_then(Value::as_u64)
+            .unwrap_or(0)
+    };
+    let usage = TokenUsage {
+        input_tokens: tokens("input_tokens"),
+        output_tokens: tokens("output_tokens"),
+        cache_read_tokens: tokens("cache_read_input_tokens"),
+        cache_write_tokens: tokens("cache_creation_input_tokens"),
+        cost_usd: event
+            .get("total_cost_usd")
+            .or_else(|| event.get("cost_usd"))
+            .and_then(Value::as_f64)
+            .unwrap_or(0.0),
+    };
+    (!usage.is_empty()).then_some(usage)
+}
+
+/// Usage in plain `name: value` lines; several can share a line
+fn from_text(line: &str) -> Option<TokenUsage> {
+    static TOKENS: OnceLock<Regex> = OnceLock::new();
+    static COST: OnceLock<Regex> = OnceLock::new();
+    let tokens = TOKENS.get_or_init(|| {
+        Regex::new(r"(?i)\b(input|output|cache[ _]read|cache[ _]write)[ _]tokens\s*[:=]\s*(\d[\d,]*)")
+            .expect("pattern is valid")
+    });
+    let cost = COST.get_or_init(|| {
+        Regex::new(r"(?i)\b(?:total[ _])?cost(?:[ _]usd)?\s*[:=]\s*\$?(\d+(?:\.\d+)?)").expect("pattern is valid")
+    });
+
+    let mut usage = TokenUsage::default();
+    for found in tokens.captures_iter(line) {
+        let Ok(count) = found[2].replace(',', "").parse::<u64>() else {
+            continue;
+        };
+        match found[1].to_lowercase().replace('_', " ").as_str() {
+            "input" => usage.input_tokens += count,
+            "output" => usage.output_tokens += count,
+            "cache read" => usage.cache_read_tokens += count,
+            _ => usage.cache_write_tokens += count,
+        }
+    }
+    if let Some(found) = cost.captures(line)
+        && let Ok(cost) = found[1].parse::<f64>()
+    {
+        usage.cost_usd = cost;
+    }
+    (!usage.is_empty()).then_some(usage)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_usage() {
+        let result = r#"{"type":"result","subtype":"success","total_cost_usd":0.125,"usage":{"input_tokens":10,"cache_read_input_tokens":2000,"output_tokens":300}}"#;
+        assert_eq!(
+            parse(result),
+            Some(TokenUsage {
+                input_tokens: 10,
+                output_tokens: 300,
+                cache_read_tokens: 2000,
+                cache_write_tokens: 0,
+                cost_usd: 0.125,
+            })
+        );
+        assert_eq!(parse(r#"{"type":"assistant","message":{"usage":{"input_tokens":10}}}"#), None);
+
+        let usage = parse("Iteration 2: input_tokens=1,234 output_tokens=56 total cost: $0.07").unwrap();
+        assert_eq!((usage.input_tokens, usage.output_tokens, usage.cost_usd), (1234, 56, 0.07));
+        assert_eq!(parse("Cost: $1.50").unwrap().cost_usd, 1.5);
+
+        assert_eq!(parse("Counting the tokens in the input"), None);
+        assert_eq!(parse("Cost: unknown"), None);
+    }
+}
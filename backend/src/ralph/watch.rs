@@ -0,0 +1,202 @@
+//! Filesystem watching during runs
+//!
+//! While a session runs, changes under its repository are sent to the
+//! session's subscribers as `FileChanged` messages. Changes are gathered for a
+//! short window and merged per path, so a tool rewriting a file many times in
+//! a row produces one message.
+
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+
+use notify::event::{EventKind, ModifyKind};
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::ws::messages::{FileChangeKind, ServerMessage};
+use crate::ws::ConnectionManager;
+
+/// How long changes are gathered before being sent
+const BATCH_WINDOW: Duration = Duration::from_millis(200);
+
+/// Directories whose changes are not reported
+const IGNORED_DIRS: &[&str] = &[".git", "node_modules", "target"];
+
+/// Watches a repository for the duration of a run; dropping it stops watching
+pub struct RunWatcher {
+    _watcher: RecommendedWatcher,
+}
+
+impl RunWatcher {
+    /// Start reporting changes under `repo_path` to the session's subscribers
+    pub fn start(
+        session_id: Uuid,
+        repo_path: &Path,
+        connections: ConnectionManager,
+    ) -> notify::Result<Self> {
+        // Some platforms report canonical paths, so watch the canonical root
+        let root = repo_path
+            .canonicalize()
+            .unwrap_or_else(|_| repo_path.to_path_buf());
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| match event {
+            Ok(event) => {
+                let _ = tx.send(event);
+            }
+            Err(e) => tracing::debug!("File watcher error: {}", e),
+        })?;
+        watcher.watch(&root, RecursiveMode::Recursive)?;
+
+        // Ends once the watcher is dropped and its sender with it
+        tokio::spawn(async move {
+            while let Some(event) = rx.recv().await {
+                let mut changes = BTreeMap::new();
+                collect(&root, &event, &mut changes);
+
+                let window = tokio::time::sleep(BATCH_WINDOW);
+                tokio::pin!(window);
+                loop {
+                    tokio::select! {
+                        _ = &mut window => break,
+                        event = rx.recv() => match event {
+                            Some(event) => collect(&root, &event, &mut changes),
+                            None => break,
+                        },
+                    }
+                }
+
+                for (path, kind) in changes {
+                    connections
+                        .broadcast(
+                            session_id,
+                            ServerMessage::FileChanged {
+                                session_id,
+                                path,
+                                kind,
+                            },
+                        )
+                        .await;
+                }
+            }
+        });
+
+        Ok(Self { _watcher: watcher })
+    }
+}
+
+/// Merge the changes an event reports into `changes`, keyed by relative path
+fn collect(root: &Path, event: &Event, changes: &mut BTreeMap<String, FileChangeKind>) {
+    for path in &event.paths {
+        let kind = match event.kind {
+            EventKind::Create(_) => FileChangeKind::Created,
+            EventKind::Remove(_) => FileChangeKind::Removed,
+            // A rename reports the old name, the new name, or both
+            EventKind::Modify(ModifyKind::Name(_)) if path.exists() => FileChangeKind::Created,
+            EventKind::Modify(ModifyKind::Name(_)) => FileChangeKind::Removed,
+            EventKind::Modify(ModifyKind::Metadata(_)) => continue,
+            EventKind::Modify(_) => FileChangeKind::Modified,
+            _ => continue,
+        };
+        let Some(relative) = relative_path(root, path) else {
+            continue;
+        };
+
+        // A file created and then written within the window is still new
+        let merged = match (changes.get(&relative), kind) {
+            (Some(FileChangeKind::Created), FileChangeKind::Modified) => FileChangeKind::Created,
+            _ => kind,
+        };
+        changes.insert(relative, merged);
+    }
+}
+
+/// `path` relative to `root` with `/` separators, or `None` if it is outside
+/// the root or in an ignored directory
+fn relative_path(root: &Path, path: &Path) -> Option<String> {
+    let relative: PathBuf = path.strip_prefix(root).ok()?.to_path_buf();
+    let mut parts = Vec::new();
+    for component in relative.components() {
+        let Component::Normal(part) = component else {
+            return None;
+        };
+        let part = part.to_str()?;
+        if IGNORED_DIRS.contains(&part) {
+            return None;
+        }
+        parts.push(part);
+    }
+    if parts.is_empty() {
+        return None;
+    }
+    Some(parts.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{CreateKind, DataChange, MetadataKind};
+    use tempfile::TempDir;
+
+    fn event(kind: EventKind, path: &Path) -> Event {
+        Event::new(kind).add_path(path.to_path_buf())
+    }
+
+    #[test]
+    fn test_collect() {
+        let root = Path::new("/repo");
+        let mut changes = BTreeMap::new();
+
+        collect(root, &event(EventKind::Create(CreateKind::File), &root.join("src/new.rs")), &mut changes);
+        collect(
+            root,
+            &event(EventKind::Modify(ModifyKind::Data(DataChange::Content)), &root.join("src/new.rs")),
+            &mut changes,
+        );
+        collect(
+            root,
+            &event(EventKind::Modify(ModifyKind::Data(DataChange::Content)), &root.join("README.md")),
+            &mut changes,
+        );
+        collect(
+            root,
+            &event(EventKind::Modify(ModifyKind::Metadata(MetadataKind::Permissions)), &root.join("run.sh")),
+            &mut changes,
+        );
+        collect(root, &event(EventKind::Create(CreateKind::File), &root.join(".git/index.lock")), &mut changes);
+        collect(root, &event(EventKind::Create(CreateKind::File), Path::new("/elsewhere/file")), &mut changes);
+
+        assert_eq!(
+            changes.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("README.md".to_string(), FileChangeKind::Modified),
+                ("src/new.rs".to_string(), FileChangeKind::Created),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_watcher_broadcasts_changes() {
+        let repo = TempDir::new().unwrap();
+        let connections = ConnectionManager::new();
+        let connection_id = Uuid::new_v4();
+        let session_id = Uuid::new_v4();
+        connections.register_connection(connection_id).await;
+        let mut receiver = connections.subscribe(connection_id, session_id).await;
+
+        let _watcher = RunWatcher::start(session_id, repo.path(), connections.clone()).unwrap();
+        std::fs::write(repo.path().join("notes.txt"), "hello").unwrap();
+
+        let message = tokio::time::timeout(Duration::from_secs(5), receiver.recv())
+            .await
+            .expect("no file change reported")
+            .unwrap();
+        match message {
+            ServerMessage::FileChanged { path, kind, .. } => {
+                assert_eq!(path, "notes.txt");
+                assert_eq!(kind, FileChangeKind::Created);
+            }
+            other => panic!("Unexpected message: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,129 @@
+//! Where a run's process starts
+//!
+//! Runs start at the repository's root unless they ask for another
+//! directory: a subdirectory, so in a monorepo the agent stays in one
+//! package, or one of the repository's linked worktrees. The directory has to
+//! exist and resolve, symlinks followed, to somewhere inside the repository or
+//! a worktree.
+
+use std::path::{Path, PathBuf};
+
+use super::RalphError;
+use crate::git::GitManager;
+use crate::sanitize;
+
+/// A directory checked to be inside a repository or one of its worktrees
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkingDir {
+    /// The repository's root, or the worktree's
+    workspace: PathBuf,
+    /// Where the process starts, relative to `workspace`
+    subdir: PathBuf,
+}
+
+impl WorkingDir {
+    /// Check `requested`, a path relative to the repository's root or an
+    /// absolute path inside the repository or one of its worktrees
+    pub fn resolve(repo_path: &Path, requested: &str) -> Result<Self, RalphError> {
+        let invalid = |message: String| RalphError::InvalidArgument(message);
+        let canonical = |path: &Path| {
+            path.canonicalize()
+                .map_err(|e| invalid(format!("Working directory {}: {}", requested, e)))
+        };
+
+        let root = canonical(repo_path)?;
+        let path = if Path::new(requested).is_absolute() {
+            canonical(sanitize::repo_path(Path::new(requested)).map_err(|e| invalid(e.to_string()))?)?
+        } else {
+            canonical(&root.join(sanitize::relative_path(requested).map_err(|e| invalid(e.to_string()))?))?
+        };
+        if !path.is_dir() {
+            return Err(invalid(format!("Working directory {} is not a directory", requested)));
+        }
+
+        // A worktree can sit inside the repository; the innermost match is
+        // the checkout the run works in
+        let worktrees = GitManager::worktree_paths(&root).unwrap_or_else(|e| {
+            tracing::warn!("Failed to list the worktrees of {}: {}", root.display(), e);
+            Vec::new()
+        });
+        let workspace = worktrees
+            .iter()
+            .filter_map(|worktree| worktree.canonicalize().ok())
+            .chain(std::iter::once(root))
+            .filter(|workspace| path.starts_with(workspace))
+            .max_by_key(|workspace| workspace.components().count())
+            .ok_or_else(|| {
+                invalid(format!(
+                    "Working directory {} is outside the repository and its worktrees",
+                    requested
+                ))
+            })?;
+
+        let subdir = path
+            .strip_prefix(&workspace)
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        Ok(Self { workspace, subdir })
+    }
+
+    /// The repository's root, or the worktree's, that the run works in
+    pub fn workspace(&self) -> &Path {
+        &self.workspace
+    }
+
+    /// Where the process starts, relative to [`Self::workspace`]
+    pub fn subdir(&self) -> &Path {
+        &self.subdir
+    }
+
+    /// Where the process starts
+    pub fn path(&self) -> PathBuf {
+        self.workspace.join(&self.subdir)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_resolve_working_dir() {
+        let temp = TempDir::new().unwrap();
+        let repo_path = temp.path().join("app");
+        let repo = git2::Repository::init(&repo_path).unwrap();
+        std::fs::create_dir_all(repo_path.join("packages/web")).unwrap();
+        std::fs::write(repo_path.join("README.md"), "app").unwrap();
+        let root = repo_path.canonicalize().unwrap();
+
+        let dir = WorkingDir::resolve(&repo_path, "packages/web/").unwrap();
+        assert_eq!(dir.workspace(), root);
+        assert_eq!(dir.subdir(), Path::new("packages/web"));
+        let absolute = root.join("packages");
+        assert_eq!(WorkingDir::resolve(&repo_path, absolute.to_str().unwrap()).unwrap().subdir(), Path::new("packages"));
+
+        // Linked worktrees are allowed too, wherever they are
+        let sig = git2::Signature::now("Test", "test@example.com").unwrap();
+        let tree = repo.find_tree(repo.index().unwrap().write_tree().unwrap()).unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, "Initial commit", &tree, &[]).unwrap();
+        let worktree_path = temp.path().join("app-feature");
+        repo.worktree("feature", &worktree_path, None).unwrap();
+        let dir = WorkingDir::resolve(&repo_path, worktree_path.to_str().unwrap()).unwrap();
+        assert_eq!(dir.workspace(), worktree_path.canonicalize().unwrap());
+        assert_eq!(dir.subdir(), Path::new(""));
+
+        for invalid in ["../elsewhere", "missing", "README.md", "/tmp", ""] {
+            assert!(
+                matches!(WorkingDir::resolve(&repo_path, invalid), Err(RalphError::InvalidArgument(_))),
+                "{}",
+                invalid
+            );
+        }
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(temp.path(), repo_path.join("escape")).unwrap();
+            assert!(WorkingDir::resolve(&repo_path, "escape").is_err());
+        }
+    }
+}
@@ -0,0 +1,100 @@
+//! Startup and shutdown progress for readiness probes
+//!
+//! The server answers requests before it's done starting: listeners are
+//! serving while recovery from the previous run is still going on. Container
+//! orchestrators need to tell that apart from a server that's ready for
+//! traffic, and from one that's draining after SIGTERM. `Readiness` records
+//! each step as it happens; `/api/health/ready` reports it together with a
+//! check that the database schema is current.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+/// What the server has got through so far; shared by every clone
+#[derive(Debug, Clone, Default)]
+pub struct Readiness {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    listeners_bound: AtomicBool,
+    recovery_finished: AtomicBool,
+    shutting_down: AtomicBool,
+}
+
+/// A snapshot of the startup steps, as reported by the readiness probe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReadinessChecks {
+    /// The database answers and its schema is current
+    pub database: bool,
+    pub listeners: bool,
+    /// Interrupted work from the previous run has been cleaned up
+    pub recovery: bool,
+    /// The server received a shutdown signal and is draining
+    pub shutting_down: bool,
+}
+
+impl ReadinessChecks {
+    /// Whether the server should receive traffic
+    pub fn is_ready(&self) -> bool {
+        self.database && self.listeners && self.recovery && !self.shutting_down
+    }
+}
+
+impl Readiness {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every listener is bound and serving
+    pub fn listeners_bound(&self) {
+        self.inner.listeners_bound.store(true, Ordering::SeqCst);
+    }
+
+    /// Startup recovery is done
+    pub fn recovery_finished(&self) {
+        self.inner.recovery_finished.store(true, Ordering::SeqCst);
+    }
+
+    /// A shutdown signal arrived; stop taking new traffic
+    pub fn shutting_down(&self) {
+        self.inner.shutting_down.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.inner.shutting_down.load(Ordering::SeqCst)
+    }
+
+    /// The startup steps, with the database check done by the caller
+    pub fn checks(&self, database: bool) -> ReadinessChecks {
+        ReadinessChecks {
+            database,
+            listeners: self.inner.listeners_bound.load(Ordering::SeqCst),
+            recovery: self.inner.recovery_finished.load(Ordering::SeqCst),
+            shutting_down: self.is_shutting_down(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ready_between_startup_and_shutdown() {
+        let readiness = Readiness::new();
+        assert!(!readiness.checks(true).is_ready());
+
+        readiness.clone().listeners_bound();
+        assert!(!readiness.checks(true).is_ready());
+        readiness.recovery_finished();
+        assert!(readiness.checks(true).is_ready());
+        assert!(!readiness.checks(false).is_ready());
+
+        readiness.shutting_down();
+        assert!(!readiness.checks(true).is_ready());
+    }
+}
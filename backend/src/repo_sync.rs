@@ -0,0 +1,173 @@
+//! Ahead/behind counts for registered repositories
+//!
+//! The `auto_fetch` maintenance job fetches each repository's `origin` and
+//! records how far its checked-out branch is ahead of and behind its
+//! upstream. Counts that changed are sent on the WebSocket global channel as
+//! `repo_sync` messages, so a dashboard can show "behind origin by N" without
+//! refreshing; `GET /api/repos/sync` returns the latest counts.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::git::GitManager;
+use crate::ws::{ConnectionManager, ServerMessage};
+
+/// A repository's branch as of its last background fetch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepoSync {
+    pub repo_id: Uuid,
+    /// Checked-out branch
+    pub branch: String,
+    /// Commits not yet pushed to the upstream
+    pub ahead: usize,
+    /// Commits on the upstream not yet pulled
+    pub behind: usize,
+    pub fetched_at: DateTime<Utc>,
+    /// Why the last fetch failed; the counts are then from an earlier one
+    pub error: Option<String>,
+}
+
+impl RepoSync {
+    fn same_counts(&self, other: &RepoSync) -> bool {
+        (&self.branch, self.ahead, self.behind, &self.error)
+            == (&other.branch, other.ahead, other.behind, &other.error)
+    }
+}
+
+/// Latest counts by repository, kept in memory
+#[derive(Clone, Default)]
+pub struct RepoSyncCache {
+    entries: Arc<Mutex<HashMap<Uuid, RepoSync>>>,
+}
+
+impl RepoSyncCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, repo_id: Uuid) -> Option<RepoSync> {
+        self.entries.lock().unwrap().get(&repo_id).cloned()
+    }
+
+    pub fn all(&self) -> Vec<RepoSync> {
+        self.entries.lock().unwrap().values().cloned().collect()
+    }
+
+    /// Record a repository's counts, returning whether they changed
+    fn update(&self, sync: RepoSync) -> bool {
+        let mut entries = self.entries.lock().unwrap();
+        let changed = entries
+            .get(&sync.repo_id)
+            .is_none_or(|previous| !previous.same_counts(&sync));
+        entries.insert(sync.repo_id, sync);
+        changed
+    }
+}
+
+/// Fetch every registered repository with an `origin` and record its counts,
+/// broadcasting the ones that changed
+pub fn fetch_all(
+    db: &Database,
+    cache: &RepoSyncCache,
+    connections: &ConnectionManager,
+) -> Result<String, String> {
+    let repos = db.list_repos().map_err(|e| e.to_string())?;
+    let known: Vec<Uuid> = repos.iter().map(|repo| repo.id).collect();
+    cache.entries.lock().unwrap().retain(|id, _| known.contains(id));
+
+    let (mut fetched, mut failed, mut changed) = (0, 0, 0);
+    for repo in repos {
+        let path = Path::new(&repo.path);
+        let has_origin = GitManager::remotes(path)
+            .is_ok_and(|remotes| remotes.iter().any(|remote| remote.name == "origin"));
+        if !has_origin {
+            continue;
+        }
+
+        // Nobody watches a background fetch's progress
+        let error = match GitManager::fetch_with_progress(path, "origin", None, mpsc::channel(1).0) {
+            Ok(()) => None,
+            Err(e) => Some(e.to_string()),
+        };
+        if error.is_some() {
+            failed += 1;
+        } else {
+            fetched += 1;
+        }
+        let Ok((branch, ahead, behind)) = GitManager::ahead_behind(path) else {
+            continue;
+        };
+
+        let sync = RepoSync {
+            repo_id: repo.id,
+            branch,
+            ahead,
+            behind,
+            fetched_at: Utc::now(),
+            error,
+        };
+        if cache.update(sync.clone()) {
+            connections.broadcast_global(ServerMessage::RepoSync(sync));
+            changed += 1;
+        }
+    }
+
+    Ok(format!(
+        "Fetched {} repositories, {} failed; {} changed",
+        fetched, failed, changed
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn commit(repo: &git2::Repository, message: &str) {
+        let sig = git2::Signature::now("Test", "test@example.com").unwrap();
+        let tree = repo.find_tree(repo.index().unwrap().write_tree().unwrap()).unwrap();
+        let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &parents).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_fetch_all_broadcasts_changes() {
+        let source_dir = TempDir::new().unwrap();
+        let source = git2::Repository::init(source_dir.path()).unwrap();
+        commit(&source, "Initial commit");
+        let clone_dir = TempDir::new().unwrap();
+        GitManager::clone(&format!("file://{}", source_dir.path().display()), clone_dir.path()).unwrap();
+
+        let db = Database::in_memory().unwrap();
+        let repo = db.insert_repo(&clone_dir.path().to_string_lossy(), "clone").unwrap();
+        let cache = RepoSyncCache::new();
+        let connections = ConnectionManager::new();
+        let mut rx = connections.subscribe_global();
+
+        fetch_all(&db, &cache, &connections).unwrap();
+        assert_eq!(cache.get(repo.id).unwrap().behind, 0);
+        assert!(matches!(rx.try_recv(), Ok(ServerMessage::RepoSync(_))));
+
+        // Nothing changed, so nothing is sent
+        fetch_all(&db, &cache, &connections).unwrap();
+        assert!(rx.try_recv().is_err());
+
+        commit(&source, "Upstream work");
+        fetch_all(&db, &cache, &connections).unwrap();
+        let sync = cache.get(repo.id).unwrap();
+        assert_eq!((sync.ahead, sync.behind), (0, 1));
+        assert!(sync.error.is_none());
+        match rx.try_recv() {
+            Ok(ServerMessage::RepoSync(sent)) => assert_eq!(sent.behind, 1),
+            other => panic!("Expected a repo_sync message, got {:?}", other),
+        }
+    }
+}
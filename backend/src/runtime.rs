@@ -0,0 +1,272 @@
+//! Where the running server can be reached
+//!
+//! The server listens on one or more addresses, each with its own port or
+//! the configured one. A port may be taken, in which case the server can move
+//! on to the next free one. Whatever addresses it ends up bound to are written to
+//! `runtime.json` in the data directory, so `ralphtown status`, `ralphtown
+//! open` and the service health check link to the server that's actually
+//! running rather than the configured port. The file is rewritten on every
+//! start; a file left by a server that's gone is caught by the health check.
+
+use std::fs;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Port the server listens on unless configured otherwise
+pub const DEFAULT_PORT: u16 = 3000;
+
+/// Ports tried after the configured one when falling back
+pub const FALLBACK_ATTEMPTS: u16 = 20;
+
+/// Name of the state file in the data directory
+const STATE_FILE: &str = "runtime.json";
+
+/// How long the health check waits for the server
+const HEALTH_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// An address to listen on, optionally with its own port
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ListenAddr {
+    pub ip: IpAddr,
+    /// Port for this address; the configured port when unset
+    pub port: Option<u16>,
+}
+
+impl ListenAddr {
+    /// The socket address to bind, using `default_port` unless one was given
+    pub fn socket_addr(&self, default_port: u16) -> SocketAddr {
+        SocketAddr::new(self.ip, self.port.unwrap_or(default_port))
+    }
+}
+
+impl From<IpAddr> for ListenAddr {
+    fn from(ip: IpAddr) -> Self {
+        Self { ip, port: None }
+    }
+}
+
+impl FromStr for ListenAddr {
+    type Err = String;
+
+    /// Accepts `127.0.0.1`, `127.0.0.1:3000`, `::1`, `[::1]`, and `[::1]:3000`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Ok(addr) = s.parse::<SocketAddr>() {
+            return Ok(Self {
+                ip: addr.ip(),
+                port: Some(addr.port()),
+            });
+        }
+        let ip = s.strip_prefix('[').and_then(|s| s.strip_suffix(']')).unwrap_or(s);
+        ip.parse::<IpAddr>()
+            .map(Self::from)
+            .map_err(|_| format!("'{}' is not an IP address or IP:port", s))
+    }
+}
+
+impl std::fmt::Display for ListenAddr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.port {
+            Some(port) => write!(f, "{}", SocketAddr::new(self.ip, port)),
+            None => write!(f, "{}", self.ip),
+        }
+    }
+}
+
+/// What a running server wrote about itself
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuntimeState {
+    /// Addresses the server is bound to
+    pub addrs: Vec<SocketAddr>,
+    /// URL to open the app at, including the scheme and base path; a
+    /// loopback one when the server listens on loopback
+    pub url: String,
+    /// URLs for every address, in the order of `addrs`
+    pub urls: Vec<String>,
+    pub pid: u32,
+    pub started_at: DateTime<Utc>,
+}
+
+impl RuntimeState {
+    /// State for a server bound to `addrs`, which must not be empty
+    pub fn new(addrs: &[SocketAddr], tls: bool, base_path: &str) -> Self {
+        let urls: Vec<String> = addrs.iter().map(|addr| url_for(*addr, tls, base_path)).collect();
+        let primary = addrs
+            .iter()
+            .position(|addr| addr.ip().is_loopback() || addr.ip().is_unspecified())
+            .unwrap_or(0);
+        Self {
+            addrs: addrs.to_vec(),
+            url: urls[primary].clone(),
+            urls,
+            pid: std::process::id(),
+            started_at: Utc::now(),
+        }
+    }
+
+    /// The state file in a data directory
+    pub fn path(data_dir: &Path) -> PathBuf {
+        data_dir.join(STATE_FILE)
+    }
+
+    /// Write the state file, replacing the one a previous start left
+    pub fn write(&self, data_dir: &Path) -> io::Result<()> {
+        fs::create_dir_all(data_dir)?;
+        let json = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        // Write then rename so readers never see half a file
+        let path = Self::path(data_dir);
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, json)?;
+        fs::rename(tmp, path)
+    }
+
+    /// Read the state file, if a server has written one
+    pub fn read(data_dir: &Path) -> Option<Self> {
+        let contents = fs::read_to_string(Self::path(data_dir)).ok()?;
+        match serde_json::from_str(&contents) {
+            Ok(state) => Some(state),
+            Err(e) => {
+                tracing::warn!("Ignoring invalid {}: {}", STATE_FILE, e);
+                None
+            }
+        }
+    }
+
+    /// URL of the server's health endpoint
+    pub fn health_url(&self) -> String {
+        format!("{}/api/health", self.url.trim_end_matches('/'))
+    }
+
+    /// Whether the server this file describes answers its health check
+    ///
+    /// Self-signed certificates are accepted, since the check only asks
+    /// whether something is listening, not who.
+    pub async fn is_healthy(&self) -> bool {
+        let client = reqwest::Client::builder()
+            .timeout(HEALTH_TIMEOUT)
+            .danger_accept_invalid_certs(true)
+            .build();
+        let Ok(client) = client else {
+            return false;
+        };
+        match client.get(self.health_url()).send().await {
+            Ok(response) => response.status().is_success(),
+            Err(_) => false,
+        }
+    }
+}
+
+/// URL to reach a server bound to `addr`
+///
+/// Servers bound to every interface are linked through loopback.
+pub fn url_for(addr: SocketAddr, tls: bool, base_path: &str) -> String {
+    let scheme = if tls { "https" } else { "http" };
+    let ip = match addr.ip() {
+        ip if ip.is_unspecified() && ip.is_ipv4() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+        ip if ip.is_unspecified() => IpAddr::V6(std::net::Ipv6Addr::LOCALHOST),
+        ip => ip,
+    };
+    format!("{}://{}{}/", scheme, SocketAddr::new(ip, addr.port()), base_path)
+}
+
+/// URL the server is expected at when no state file says otherwise
+pub fn default_url() -> String {
+    url_for(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), DEFAULT_PORT), false, "")
+}
+
+/// Bind every address, each on its own port or `default_port`
+///
+/// Fails with the address that couldn't be bound; listeners already bound
+/// are closed again.
+pub fn bind_all(
+    addrs: &[ListenAddr],
+    default_port: u16,
+    fallback: bool,
+) -> Result<Vec<TcpListener>, (SocketAddr, io::Error)> {
+    addrs
+        .iter()
+        .map(|addr| {
+            let addr = addr.socket_addr(default_port);
+            bind(addr.ip(), addr.port(), fallback).map_err(|e| (addr, e))
+        })
+        .collect()
+}
+
+/// Bind to `ip:port`, or with `fallback` to the first free port after it
+///
+/// Only a port already in use moves on to the next one; any other error is
+/// returned as is.
+pub fn bind(ip: IpAddr, port: u16, fallback: bool) -> io::Result<TcpListener> {
+    let attempts = if fallback { FALLBACK_ATTEMPTS } else { 0 };
+    let mut last_error = None;
+    for offset in 0..=attempts {
+        let Some(port) = port.checked_add(offset) else {
+            break;
+        };
+        match TcpListener::bind(SocketAddr::new(ip, port)) {
+            Ok(listener) => {
+                if offset > 0 {
+                    tracing::warn!("Port {} is in use; listening on {} instead", port - offset, port);
+                }
+                return Ok(listener);
+            }
+            Err(e) if e.kind() == io::ErrorKind::AddrInUse => last_error = Some(e),
+            Err(e) => return Err(e),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| io::Error::from(io::ErrorKind::AddrInUse)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_bind_falls_back_to_a_free_port() {
+        let localhost = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        let taken = TcpListener::bind(SocketAddr::new(localhost, 0)).unwrap();
+        let port = taken.local_addr().unwrap().port();
+
+        let err = bind(localhost, port, false).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+
+        let listener = bind(localhost, port, true).unwrap();
+        assert_ne!(listener.local_addr().unwrap().port(), port);
+    }
+
+    #[test]
+    fn test_parse_listen_addr() {
+        let parse = |s: &str| s.parse::<ListenAddr>().map(|addr| addr.to_string());
+        assert_eq!(parse("127.0.0.1"), Ok("127.0.0.1".to_string()));
+        assert_eq!(parse("127.0.0.1:3000"), Ok("127.0.0.1:3000".to_string()));
+        assert_eq!(parse("::1"), Ok("::1".to_string()));
+        assert_eq!(parse("[::1]"), Ok("::1".to_string()));
+        assert_eq!(parse("[::1]:3000"), Ok("[::1]:3000".to_string()));
+        assert!(parse("localhost").is_err());
+
+        let addr: ListenAddr = "::".parse().unwrap();
+        assert_eq!(addr.socket_addr(3000), "[::]:3000".parse().unwrap());
+    }
+
+    #[test]
+    fn test_state_file() {
+        let dir = TempDir::new().unwrap();
+        assert!(RuntimeState::read(dir.path()).is_none());
+
+        let addrs: Vec<SocketAddr> = vec!["192.168.1.5:3000".parse().unwrap(), "0.0.0.0:3001".parse().unwrap()];
+        let state = RuntimeState::new(&addrs, true, "/ralphtown");
+        assert_eq!(state.url, "https://127.0.0.1:3001/ralphtown/");
+        assert_eq!(state.urls[0], "https://192.168.1.5:3000/ralphtown/");
+        assert_eq!(state.health_url(), "https://127.0.0.1:3001/ralphtown/api/health");
+
+        state.write(dir.path()).unwrap();
+        assert_eq!(RuntimeState::read(dir.path()), Some(state));
+    }
+}
@@ -0,0 +1,307 @@
+//! Argument sanitization for spawned processes
+//!
+//! Every user-supplied string that ends up on a `ralph` or `git` command line
+//! passes through here first. Values are validated rather than rewritten:
+//! control characters are rejected, option-like values are refused where they
+//! would be parsed as flags, and only an allowlist of git subcommands may run.
+
+use std::path::Path;
+
+use thiserror::Error;
+
+/// Git subcommands the server is allowed to spawn
+pub const ALLOWED_GIT_SUBCOMMANDS: [&str; 13] = [
+    "add", "archive", "checkout", "cherry-pick", "commit", "lfs", "merge", "pull", "push", "rebase",
+    "reset", "revert", "worktree",
+];
+
+/// Longest prompt accepted, in bytes
+pub const MAX_PROMPT_LEN: usize = 100_000;
+
+/// Longest commit message accepted, in bytes
+pub const MAX_COMMIT_MESSAGE_LEN: usize = 10_000;
+
+/// Rejected command arguments
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SanitizeError {
+    #[error("{0} must not be empty")]
+    Empty(&'static str),
+
+    #[error("{0} contains control characters")]
+    ControlCharacter(&'static str),
+
+    #[error("{field} is longer than {max} bytes")]
+    TooLong { field: &'static str, max: usize },
+
+    #[error("Invalid branch name: {0}")]
+    InvalidBranch(String),
+
+    #[error("Invalid path: {0}")]
+    InvalidPath(String),
+
+    #[error("Invalid remote: {0}")]
+    InvalidRemote(String),
+
+    #[error("Git subcommand not allowed: {0}")]
+    DisallowedSubcommand(String),
+
+    #[error("Invalid environment variable name: {0}")]
+    InvalidEnvName(String),
+
+    #[error("Invalid identity: {0}")]
+    InvalidIdentity(String),
+}
+
+pub type SanitizeResult<T> = Result<T, SanitizeError>;
+
+/// Validate a prompt passed to `ralph run`
+///
+/// Newlines and tabs are allowed since prompts are free-form text.
+pub fn prompt(value: &str) -> SanitizeResult<&str> {
+    text("Prompt", value, MAX_PROMPT_LEN)
+}
+
+/// Validate a commit message passed to `git commit`
+pub fn commit_message(value: &str) -> SanitizeResult<&str> {
+    text("Commit message", value, MAX_COMMIT_MESSAGE_LEN)
+}
+
+/// Validate a branch name passed to `git checkout`
+///
+/// Uses git's own ref name rules and additionally rejects names that would be
+/// parsed as options.
+pub fn branch_name(value: &str) -> SanitizeResult<&str> {
+    reject_control("Branch name", value)?;
+    let valid = !value.is_empty()
+        && !value.starts_with('-')
+        && git2::Branch::name_is_valid(value).unwrap_or(false);
+    if !valid {
+        return Err(SanitizeError::InvalidBranch(value.to_string()));
+    }
+    Ok(value)
+}
+
+/// Validate a repository path used as a working directory
+pub fn repo_path(value: &Path) -> SanitizeResult<&Path> {
+    let display = value.display().to_string();
+    if value.to_str().is_some_and(has_control) {
+        return Err(SanitizeError::InvalidPath(display));
+    }
+    if !value.is_absolute() {
+        return Err(SanitizeError::InvalidPath(format!("{} is not absolute", display)));
+    }
+    Ok(value)
+}
+
+/// Validate a remote name, e.g. `origin`
+pub fn remote_name(value: &str) -> SanitizeResult<&str> {
+    reject_control("Remote name", value)?;
+    let valid = !value.starts_with('-') && git2::Remote::is_valid_name(value);
+    if !valid {
+        return Err(SanitizeError::InvalidRemote(format!("{:?} is not a valid name", value)));
+    }
+    Ok(value)
+}
+
+/// Validate a remote URL
+///
+/// Besides option-like values, `ext::` URLs are refused: git runs them as
+/// commands.
+pub fn remote_url(value: &str) -> SanitizeResult<&str> {
+    reject_control("Remote URL", value)?;
+    if value.trim().is_empty() {
+        return Err(SanitizeError::Empty("Remote URL"));
+    }
+    if value.starts_with('-') || value.starts_with("ext::") {
+        return Err(SanitizeError::InvalidRemote(format!("{} is not an allowed URL", value)));
+    }
+    Ok(value)
+}
+
+/// Validate a path relative to a repository's root
+///
+/// The path must stay inside the repository: absolute paths and `..`
+/// components are refused. A trailing slash is dropped.
+pub fn relative_path(value: &str) -> SanitizeResult<&str> {
+    reject_control("Path", value)?;
+    let value = value.trim_end_matches('/');
+    if value.trim().is_empty() {
+        return Err(SanitizeError::Empty("Path"));
+    }
+    if Path::new(value).is_absolute() || value.starts_with(['/', '\\']) {
+        return Err(SanitizeError::InvalidPath(format!("{} is not relative", value)));
+    }
+    if value.split(['/', '\\']).any(|part| part == "..") {
+        return Err(SanitizeError::InvalidPath(format!("{} leaves the repository", value)));
+    }
+    Ok(value)
+}
+
+/// Validate a commit author's `user.name` or `user.email`
+///
+/// Git writes these as `name <email>` in commits, so angle brackets are
+/// refused as well as control characters.
+pub fn identity<'a>(field: &'static str, value: &'a str) -> SanitizeResult<&'a str> {
+    reject_control(field, value)?;
+    if value.trim().is_empty() {
+        return Err(SanitizeError::Empty(field));
+    }
+    if value.contains(['<', '>']) {
+        return Err(SanitizeError::InvalidIdentity(format!("{} can't contain < or >", field)));
+    }
+    Ok(value)
+}
+
+/// Validate an environment variable set for a run
+///
+/// Names are letters, digits and underscores, not starting with a digit.
+pub fn env_var(name: &str, value: &str) -> SanitizeResult<()> {
+    let valid = name.chars().next().is_some_and(|c| !c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        return Err(SanitizeError::InvalidEnvName(name.to_string()));
+    }
+    // Newlines are fine in values, but NUL can't be passed to a process
+    if value.contains('\0') {
+        return Err(SanitizeError::ControlCharacter("Environment variable value"));
+    }
+    Ok(())
+}
+
+/// Check that a git invocation uses an allowed subcommand
+pub fn git_args<'a>(args: &'a [&'a str]) -> SanitizeResult<&'a [&'a str]> {
+    match args.first() {
+        Some(subcommand) if ALLOWED_GIT_SUBCOMMANDS.contains(subcommand) => {}
+        other => {
+            return Err(SanitizeError::DisallowedSubcommand(
+                other.copied().unwrap_or_default().to_string(),
+            ));
+        }
+    }
+    for arg in args {
+        reject_control("Git argument", arg)?;
+    }
+    Ok(args)
+}
+
+/// Validate free-form text that may span lines
+fn text<'a>(field: &'static str, value: &'a str, max: usize) -> SanitizeResult<&'a str> {
+    if value.trim().is_empty() {
+        return Err(SanitizeError::Empty(field));
+    }
+    if value.len() > max {
+        return Err(SanitizeError::TooLong { field, max });
+    }
+    if value
+        .chars()
+        .any(|c| c.is_control() && !matches!(c, '\n' | '\r' | '\t'))
+    {
+        return Err(SanitizeError::ControlCharacter(field));
+    }
+    Ok(value)
+}
+
+/// Reject any control character, including newlines
+fn reject_control<'a>(field: &'static str, value: &'a str) -> SanitizeResult<&'a str> {
+    if has_control(value) {
+        return Err(SanitizeError::ControlCharacter(field));
+    }
+    Ok(value)
+}
+
+fn has_control(value: &str) -> bool {
+    value.chars().any(char::is_control)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prompt_allows_multiline_text() {
+        assert!(prompt("- fix the bug\n\tthen add tests").is_ok());
+        assert_eq!(prompt("   "), Err(SanitizeError::Empty("Prompt")));
+        assert_eq!(
+            prompt("hello\u{1b}[31m"),
+            Err(SanitizeError::ControlCharacter("Prompt"))
+        );
+        assert!(prompt(&"a".repeat(MAX_PROMPT_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn test_commit_message_rejects_nul() {
+        assert!(commit_message("Fix bug\n\nDetails").is_ok());
+        assert!(commit_message("Fix\0bug").is_err());
+    }
+
+    #[test]
+    fn test_branch_name() {
+        assert!(branch_name("feature/login").is_ok());
+        assert!(branch_name("-f").is_err());
+        assert!(branch_name("--orphan").is_err());
+        assert!(branch_name("a..b").is_err());
+        assert!(branch_name("name with space").is_err());
+        assert!(branch_name("bad\nname").is_err());
+        assert!(branch_name("").is_err());
+    }
+
+    #[test]
+    fn test_repo_path() {
+        assert!(repo_path(Path::new("/home/user/repo")).is_ok());
+        assert!(repo_path(Path::new("relative/repo")).is_err());
+        assert!(repo_path(Path::new("/tmp/a\nb")).is_err());
+    }
+
+    #[test]
+    fn test_relative_path() {
+        assert_eq!(relative_path("docs/TODO.md"), Ok("docs/TODO.md"));
+        assert_eq!(relative_path("src/"), Ok("src"));
+        assert!(relative_path("/etc/passwd").is_err());
+        assert!(relative_path("../other/repo").is_err());
+        assert!(relative_path("docs/../../x").is_err());
+        assert!(relative_path("").is_err());
+        assert!(relative_path("a\nb").is_err());
+    }
+
+    #[test]
+    fn test_remote_name_and_url() {
+        assert!(remote_name("upstream").is_ok());
+        assert!(remote_name("-u").is_err());
+        assert!(remote_name("two words").is_err());
+        assert!(remote_url("git@github.com:owner/repo.git").is_ok());
+        assert!(remote_url("https://example.com/repo.git").is_ok());
+        assert!(remote_url("--upload-pack=sh").is_err());
+        assert!(remote_url("ext::sh -c touch% /tmp/x").is_err());
+        assert!(remote_url("").is_err());
+    }
+
+    #[test]
+    fn test_identity() {
+        assert!(identity("Name", "Ada Lovelace").is_ok());
+        assert!(identity("Email", "ada@example.com").is_ok());
+        assert!(identity("Email", "ada@example.com> <x").is_err());
+        assert!(identity("Name", "Ada\nLovelace").is_err());
+        assert_eq!(identity("Name", " "), Err(SanitizeError::Empty("Name")));
+    }
+
+    #[test]
+    fn test_env_var() {
+        assert!(env_var("ANTHROPIC_API_KEY", "sk-123").is_ok());
+        assert!(env_var("_PROXY", "multi\nline").is_ok());
+        assert!(env_var("1ST", "x").is_err());
+        assert!(env_var("MY-VAR", "x").is_err());
+        assert!(env_var("", "x").is_err());
+        assert!(env_var("VAR", "a\0b").is_err());
+    }
+
+    #[test]
+    fn test_git_subcommand_allowlist() {
+        assert!(git_args(&["commit", "-m", "msg"]).is_ok());
+        assert_eq!(
+            git_args(&["config", "core.sshCommand", "sh"]),
+            Err(SanitizeError::DisallowedSubcommand("config".to_string()))
+        );
+        assert!(git_args(&["-c", "core.pager=sh", "log"]).is_err());
+        assert!(git_args(&[]).is_err());
+    }
+}
@@ -0,0 +1,1145 @@
+//! Server settings loaded at startup
+//!
+//! Settings are read from the `config` table so they can be managed through the
+//! existing config API, falling back to safe defaults when a key is not set.
+//! Changes take effect the next time the server starts.
+
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use axum::http::{HeaderName, HeaderValue, Method};
+use thiserror::Error;
+
+use crate::artifacts::ArtifactPatterns;
+use crate::db::models::Orchestrator;
+use crate::db::{Database, DbError};
+use crate::maintenance;
+use crate::orchestrator::{self, OrchestratorPlugin};
+use crate::ralph::{OutputLimits, RunLimits, Sandbox};
+use crate::runtime::{ListenAddr, DEFAULT_PORT};
+
+/// Comma-separated list of origins allowed to make cross-origin requests ("*" for any)
+pub const CORS_ALLOWED_ORIGINS_KEY: &str = "cors_allowed_origins";
+/// Comma-separated list of HTTP methods allowed for cross-origin requests ("*" for any)
+pub const CORS_ALLOWED_METHODS_KEY: &str = "cors_allowed_methods";
+/// Comma-separated list of request headers allowed for cross-origin requests ("*" for any)
+pub const CORS_ALLOWED_HEADERS_KEY: &str = "cors_allowed_headers";
+/// Whether cross-origin requests may carry cookies or Authorization headers
+pub const CORS_ALLOW_CREDENTIALS_KEY: &str = "cors_allow_credentials";
+
+/// Whether the server should serve HTTPS instead of plain HTTP
+pub const TLS_ENABLED_KEY: &str = "tls_enabled";
+/// Path to a PEM-encoded certificate chain
+pub const TLS_CERT_PATH_KEY: &str = "tls_cert_path";
+/// Path to a PEM-encoded private key
+pub const TLS_KEY_PATH_KEY: &str = "tls_key_path";
+/// Whether to generate a self-signed certificate when no cert/key paths are configured
+pub const TLS_SELF_SIGNED_KEY: &str = "tls_self_signed";
+
+/// Bearer token required on API requests; unset disables authentication
+pub const AUTH_TOKEN_KEY: &str = "auth_token";
+
+/// Minimum accepted length for the auth token
+pub const MIN_AUTH_TOKEN_LEN: usize = 16;
+
+/// Port the server listens on
+pub const PORT_KEY: &str = "port";
+
+/// Move on to the next free port when the configured one is taken
+pub const PORT_FALLBACK_KEY: &str = "port_fallback";
+
+/// Reject every mutating request (runs, git writes, deletes, service ops)
+pub const READ_ONLY_KEY: &str = "read_only";
+
+/// Advertise the server over mDNS when it listens on the network
+pub const MDNS_KEY: &str = "mdns";
+
+/// Keep the server machine from sleeping while sessions run
+pub const KEEP_AWAKE_KEY: &str = "keep_awake";
+
+/// Run sessions on a pseudo-terminal so they produce terminal output
+pub const RUN_IN_PTY_KEY: &str = "run_in_pty";
+
+/// Show a desktop notification on the server machine when a session finishes
+pub const DESKTOP_NOTIFICATIONS_KEY: &str = "desktop_notifications";
+
+/// ntfy topic URL to push session notifications to (e.g. "https://ntfy.sh/my-topic");
+/// on public servers the topic name is the only protection, so it's treated as a secret
+pub const NTFY_URL_KEY: &str = "ntfy_url";
+/// Access token for a protected ntfy topic
+pub const NTFY_TOKEN_KEY: &str = "ntfy_token";
+/// Gotify server URL to push session notifications to
+pub const GOTIFY_URL_KEY: &str = "gotify_url";
+/// Gotify application token
+pub const GOTIFY_TOKEN_KEY: &str = "gotify_token";
+
+/// Path prefix the app is served under when behind a reverse proxy (e.g. "/ralphtown")
+pub const BASE_PATH_KEY: &str = "base_path";
+
+/// Whether expensive endpoints (clone, scan, run, git writes) are rate limited
+pub const RATE_LIMIT_ENABLED_KEY: &str = "rate_limit_enabled";
+/// Requests a single client may make to expensive endpoints in a burst
+pub const RATE_LIMIT_BURST_KEY: &str = "rate_limit_burst";
+/// Sustained requests per minute a single client may make to expensive endpoints
+pub const RATE_LIMIT_PER_MINUTE_KEY: &str = "rate_limit_per_minute";
+
+/// GitHub personal access token used for pull requests, issues, and the repo picker
+pub const GITHUB_TOKEN_KEY: &str = "github_token";
+/// GitHub REST API base URL (for GitHub Enterprise); defaults to api.github.com
+pub const GITHUB_API_URL_KEY: &str = "github_api_url";
+/// GitLab personal access token
+pub const GITLAB_TOKEN_KEY: &str = "gitlab_token";
+/// GitLab instance URL (for self-managed GitLab); defaults to gitlab.com
+pub const GITLAB_URL_KEY: &str = "gitlab_url";
+/// Bitbucket access token, or app password when `bitbucket_username` is set
+pub const BITBUCKET_TOKEN_KEY: &str = "bitbucket_token";
+/// Bitbucket username that an app password belongs to
+pub const BITBUCKET_USERNAME_KEY: &str = "bitbucket_username";
+/// Bitbucket REST API base URL; defaults to api.bitbucket.org/2.0
+pub const BITBUCKET_API_URL_KEY: &str = "bitbucket_api_url";
+/// Open a pull request automatically when a run completes successfully
+pub const AUTO_PR_KEY: &str = "auto_pull_request";
+/// Template for generated pull request titles
+pub const PR_TITLE_TEMPLATE_KEY: &str = "pr_title_template";
+/// Template for generated pull request bodies
+pub const PR_BODY_TEMPLATE_KEY: &str = "pr_body_template";
+
+/// Key session bundles are signed with, and imported bundles must be signed with
+/// (see [`crate::bundle`]); instances sharing bundles set the same key
+pub const BUNDLE_SIGNING_KEY_KEY: &str = "bundle_signing_key";
+
+/// Directory repositories are cloned into; defaults to ~/ralphtown
+pub const CLONE_ROOT_KEY: &str = "clone_root";
+/// Disk space clones may use in total, in megabytes; unset or 0 for no limit
+pub const CLONE_QUOTA_MB_KEY: &str = "clone_quota_mb";
+
+/// JSON array of orchestrators declared in configuration (see [`crate::orchestrator`])
+pub const ORCHESTRATORS_KEY: &str = "orchestrators";
+
+/// Comma-separated globs of files recorded as run artifacts (see [`crate::artifacts`]);
+/// an empty value turns detection off
+pub const ARTIFACT_PATTERNS_KEY: &str = "artifact_patterns";
+
+/// Longest output line kept, in kilobytes; the rest is dropped with a marker (0 for no limit)
+pub const OUTPUT_MAX_LINE_KB_KEY: &str = "output_max_line_kb";
+/// Largest piece long output lines are stored and streamed in, in kilobytes
+pub const OUTPUT_CHUNK_KB_KEY: &str = "output_chunk_kb";
+
+/// Longest a local run may go, in minutes; unset or 0 for no limit
+pub const RUN_TIMEOUT_MINUTES_KEY: &str = "run_timeout_minutes";
+/// Most memory a local run's processes may hold together, in megabytes; unset
+/// or 0 for no limit
+pub const RUN_MAX_MEMORY_MB_KEY: &str = "run_max_memory_mb";
+/// Most output a local run may write, in megabytes; unset or 0 for no limit
+pub const RUN_MAX_OUTPUT_MB_KEY: &str = "run_max_output_mb";
+/// Longest a local run may go without output before it's reported as
+/// stalled, in minutes; unset or 0 to not watch for stalls
+pub const RUN_STALL_MINUTES_KEY: &str = "run_stall_minutes";
+/// Stop stalled runs, ending them in error, rather than only warning
+pub const RUN_STALL_STOP_KEY: &str = "run_stall_stop";
+/// Most local runs going at once, with more queued; unset or 0 for no limit
+pub const MAX_CONCURRENT_RUNS_KEY: &str = "max_concurrent_runs";
+
+/// Container image sandboxed runs start from; sandboxed runs are refused when unset
+pub const SANDBOX_IMAGE_KEY: &str = "sandbox_image";
+/// Container runtime for sandboxed runs, "docker" or "podman"; unset to use
+/// whichever is installed
+pub const SANDBOX_RUNTIME_KEY: &str = "sandbox_runtime";
+
+/// Largest request body accepted, in kilobytes
+pub const REQUEST_BODY_MAX_KB_KEY: &str = "request_body_max_kb";
+/// Largest upload request accepted, in megabytes
+pub const UPLOAD_MAX_MB_KEY: &str = "upload_max_mb";
+
+/// Days finished webhook deliveries and workspace snapshots are kept; 0 keeps them forever
+pub const RETENTION_DAYS_KEY: &str = "retention_days";
+/// Days session output is kept before the `output_retention` job deletes it; 0 keeps it forever
+pub const OUTPUT_RETENTION_DAYS_KEY: &str = "output_retention_days";
+/// Most output lines kept for each session, oldest deleted first; 0 keeps them all
+pub const OUTPUT_MAX_LINES_KEY: &str = "output_max_lines";
+/// Comma-separated `job=minutes` overrides for maintenance job intervals (see
+/// [`crate::maintenance`]); 0 minutes turns a job off
+pub const MAINTENANCE_INTERVALS_KEY: &str = "maintenance_intervals";
+
+/// Errors that can occur while loading settings
+#[derive(Debug, Error)]
+pub enum SettingsError {
+    #[error("Failed to read settings: {0}")]
+    Db(#[from] DbError),
+
+    #[error("Invalid value for '{key}': {message}")]
+    InvalidValue { key: String, message: String },
+}
+
+pub type SettingsResult<T> = Result<T, SettingsError>;
+
+/// All server settings
+#[derive(Debug, Clone)]
+pub struct Settings {
+    pub cors: CorsSettings,
+    pub tls: TlsSettings,
+    pub auth: AuthSettings,
+    pub rate_limit: RateLimitSettings,
+    pub storage: StorageSettings,
+    /// Reject all mutating requests
+    pub read_only: bool,
+    /// Notify on the desktop when sessions finish
+    pub desktop_notifications: bool,
+    /// Prevent system sleep while sessions run
+    pub keep_awake: bool,
+    /// Run local sessions on a pseudo-terminal instead of pipes
+    pub run_in_pty: bool,
+    /// Advertise the server on the local network when it's exposed
+    pub mdns: bool,
+    /// Path prefix the app is mounted under; empty when served from the root
+    pub base_path: String,
+    /// Addresses the server binds to (set from the command line, not the config table)
+    pub listen_addrs: Vec<ListenAddr>,
+    /// Port the server binds to on addresses that don't name their own
+    pub port: u16,
+    /// Try the following ports when `port` is taken
+    pub port_fallback: bool,
+    /// Frontend dev server that non-API requests are proxied to (command line only)
+    pub dev_frontend: Option<String>,
+    /// Orchestrators declared in configuration
+    pub orchestrators: Vec<OrchestratorPlugin>,
+    /// Files recorded as artifacts after each run
+    pub artifacts: ArtifactPatterns,
+    pub maintenance: MaintenanceSettings,
+    /// How long session output lines may get
+    pub output: OutputLimits,
+    /// Resource limits local runs are stopped at
+    pub run_limits: RunLimits,
+    /// Most local runs going at once; `None` for no limit
+    pub max_concurrent_runs: Option<usize>,
+    /// Container sandboxed runs go in; `None` when no image is configured
+    pub sandbox: Option<Sandbox>,
+    pub body_limits: BodyLimits,
+    /// Directory for server data such as workspace snapshots (set at startup,
+    /// not from the config table); snapshots are disabled when unset
+    pub data_dir: Option<PathBuf>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            cors: CorsSettings::default(),
+            tls: TlsSettings::default(),
+            auth: AuthSettings::default(),
+            rate_limit: RateLimitSettings::default(),
+            storage: StorageSettings::default(),
+            read_only: false,
+            desktop_notifications: false,
+            keep_awake: true,
+            run_in_pty: false,
+            mdns: true,
+            base_path: String::new(),
+            listen_addrs: vec![IpAddr::V4(Ipv4Addr::LOCALHOST).into()],
+            port: DEFAULT_PORT,
+            port_fallback: false,
+            dev_frontend: None,
+            orchestrators: Vec::new(),
+            artifacts: ArtifactPatterns::default(),
+            maintenance: MaintenanceSettings::default(),
+            output: OutputLimits::default(),
+            run_limits: RunLimits::default(),
+            max_concurrent_runs: None,
+            sandbox: None,
+            body_limits: BodyLimits::default(),
+            data_dir: None,
+        }
+    }
+}
+
+impl Settings {
+    /// Load settings from the database config table
+    pub fn load(db: &Database) -> SettingsResult<Self> {
+        Ok(Self {
+            cors: CorsSettings::load(db)?,
+            tls: TlsSettings::load(db)?,
+            auth: AuthSettings::load(db)?,
+            rate_limit: RateLimitSettings::load(db)?,
+            storage: StorageSettings::load(db)?,
+            read_only: get_bool(db, READ_ONLY_KEY)?.unwrap_or(false),
+            port: get_port(db)?,
+            port_fallback: get_bool(db, PORT_FALLBACK_KEY)?.unwrap_or(false),
+            desktop_notifications: get_bool(db, DESKTOP_NOTIFICATIONS_KEY)?.unwrap_or(false),
+            keep_awake: get_bool(db, KEEP_AWAKE_KEY)?.unwrap_or(true),
+            run_in_pty: get_bool(db, RUN_IN_PTY_KEY)?.unwrap_or(false),
+            mdns: get_bool(db, MDNS_KEY)?.unwrap_or(true),
+            base_path: get_base_path(db)?,
+            orchestrators: get_orchestrators(db)?,
+            artifacts: get_artifact_patterns(db)?,
+            maintenance: MaintenanceSettings::load(db)?,
+            output: get_output_limits(db)?,
+            run_limits: get_run_limits(db)?,
+            max_concurrent_runs: get_u32(db, MAX_CONCURRENT_RUNS_KEY)?
+                .filter(|max| *max > 0)
+                .map(|max| max as usize),
+            sandbox: get_sandbox(db)?,
+            body_limits: BodyLimits::load(db)?,
+            ..Self::default()
+        })
+    }
+
+    /// A configured orchestrator by id
+    pub fn plugin(&self, id: &str) -> Option<&OrchestratorPlugin> {
+        self.orchestrators.iter().find(|plugin| plugin.id == id)
+    }
+
+    /// Whether new sessions may use `orchestrator`
+    pub fn orchestrator_available(&self, orchestrator: &Orchestrator) -> bool {
+        match orchestrator {
+            Orchestrator::Plugin(id) => self.plugin(id).is_some(),
+            builtin => builtin.is_available(),
+        }
+    }
+
+    /// Whether the server is reachable from other machines
+    pub fn is_exposed(&self) -> bool {
+        self.listen_addrs.iter().any(|addr| !addr.ip.is_loopback())
+    }
+}
+
+/// Cross-origin resource sharing policy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorsSettings {
+    /// Origins allowed to call the API; a single "*" allows any origin
+    pub allowed_origins: Vec<String>,
+    /// HTTP methods allowed for cross-origin requests; a single "*" allows any method
+    pub allowed_methods: Vec<String>,
+    /// Request headers allowed for cross-origin requests; a single "*" allows any header
+    pub allowed_headers: Vec<String>,
+    /// Whether credentialed (cookie/Authorization) cross-origin requests are accepted
+    pub allow_credentials: bool,
+}
+
+impl Default for CorsSettings {
+    /// Localhost-only policy covering the embedded UI and the Vite dev server
+    fn default() -> Self {
+        Self {
+            allowed_origins: vec![
+                "http://localhost:3000".to_string(),
+                "http://127.0.0.1:3000".to_string(),
+                "http://localhost:5173".to_string(),
+                "http://127.0.0.1:5173".to_string(),
+            ],
+            allowed_methods: ["GET", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"]
+                .iter()
+                .map(|m| m.to_string())
+                .collect(),
+            allowed_headers: ["content-type", "authorization", "x-csrf-token"]
+                .iter()
+                .map(|h| h.to_string())
+                .collect(),
+            allow_credentials: false,
+        }
+    }
+}
+
+impl CorsSettings {
+    /// Load CORS settings, using defaults for any key that is not set
+    pub fn load(db: &Database) -> SettingsResult<Self> {
+        let defaults = Self::default();
+
+        let settings = Self {
+            allowed_origins: get_list(db, CORS_ALLOWED_ORIGINS_KEY)?
+                .unwrap_or(defaults.allowed_origins),
+            allowed_methods: get_list(db, CORS_ALLOWED_METHODS_KEY)?
+                .unwrap_or(defaults.allowed_methods),
+            allowed_headers: get_list(db, CORS_ALLOWED_HEADERS_KEY)?
+                .unwrap_or(defaults.allowed_headers),
+            allow_credentials: get_bool(db, CORS_ALLOW_CREDENTIALS_KEY)?
+                .unwrap_or(defaults.allow_credentials),
+        };
+
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    /// Check that the settings form a policy browsers will accept
+    pub fn validate(&self) -> SettingsResult<()> {
+        if self.allowed_origins.is_empty() {
+            return Err(invalid(CORS_ALLOWED_ORIGINS_KEY, "at least one origin is required"));
+        }
+
+        if !is_wildcard(&self.allowed_origins) {
+            for origin in &self.allowed_origins {
+                if HeaderValue::from_str(origin).is_err() || !origin.contains("://") {
+                    return Err(invalid(CORS_ALLOWED_ORIGINS_KEY, format!("invalid origin '{}'", origin)));
+                }
+            }
+        }
+        if !is_wildcard(&self.allowed_methods) {
+            for method in &self.allowed_methods {
+                if Method::from_bytes(method.as_bytes()).is_err() {
+                    return Err(invalid(CORS_ALLOWED_METHODS_KEY, format!("invalid method '{}'", method)));
+                }
+            }
+        }
+        if !is_wildcard(&self.allowed_headers) {
+            for header in &self.allowed_headers {
+                if HeaderName::from_bytes(header.as_bytes()).is_err() {
+                    return Err(invalid(CORS_ALLOWED_HEADERS_KEY, format!("invalid header '{}'", header)));
+                }
+            }
+        }
+
+        // Browsers refuse wildcards on credentialed requests, so reject the combination up front
+        if self.allow_credentials {
+            for (key, values) in [
+                (CORS_ALLOWED_ORIGINS_KEY, &self.allowed_origins),
+                (CORS_ALLOWED_METHODS_KEY, &self.allowed_methods),
+                (CORS_ALLOWED_HEADERS_KEY, &self.allowed_headers),
+            ] {
+                if is_wildcard(values) {
+                    return Err(invalid(
+                        key,
+                        format!("'*' cannot be used when {} is enabled", CORS_ALLOW_CREDENTIALS_KEY),
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// HTTPS serving options
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsSettings {
+    /// Serve HTTPS instead of HTTP
+    pub enabled: bool,
+    /// Certificate chain path; generated when unset and `self_signed` is enabled
+    pub cert_path: Option<PathBuf>,
+    /// Private key path; generated when unset and `self_signed` is enabled
+    pub key_path: Option<PathBuf>,
+    /// Generate a self-signed certificate on first run if no paths are configured
+    pub self_signed: bool,
+}
+
+impl Default for TlsSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            cert_path: None,
+            key_path: None,
+            self_signed: true,
+        }
+    }
+}
+
+impl TlsSettings {
+    /// Load TLS settings, using defaults for any key that is not set
+    pub fn load(db: &Database) -> SettingsResult<Self> {
+        let defaults = Self::default();
+
+        let settings = Self {
+            enabled: get_bool(db, TLS_ENABLED_KEY)?.unwrap_or(defaults.enabled),
+            cert_path: get_path(db, TLS_CERT_PATH_KEY)?,
+            key_path: get_path(db, TLS_KEY_PATH_KEY)?,
+            self_signed: get_bool(db, TLS_SELF_SIGNED_KEY)?.unwrap_or(defaults.self_signed),
+        };
+
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    /// Check that certificate and key paths are configured together
+    pub fn validate(&self) -> SettingsResult<()> {
+        match (&self.cert_path, &self.key_path) {
+            (Some(_), None) => Err(invalid(
+                TLS_KEY_PATH_KEY,
+                format!("required when {} is set", TLS_CERT_PATH_KEY),
+            )),
+            (None, Some(_)) => Err(invalid(
+                TLS_CERT_PATH_KEY,
+                format!("required when {} is set", TLS_KEY_PATH_KEY),
+            )),
+            (None, None) if self.enabled && !self.self_signed => Err(invalid(
+                TLS_CERT_PATH_KEY,
+                format!("required when {} is disabled", TLS_SELF_SIGNED_KEY),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// API authentication
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuthSettings {
+    /// Bearer token clients must present; `None` leaves the API open
+    pub token: Option<String>,
+}
+
+impl AuthSettings {
+    /// Load auth settings, treating an empty token as unset
+    pub fn load(db: &Database) -> SettingsResult<Self> {
+        let token = db
+            .get_config(AUTH_TOKEN_KEY)?
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty());
+
+        if token.as_ref().is_some_and(|t| t.len() < MIN_AUTH_TOKEN_LEN) {
+            return Err(invalid(
+                AUTH_TOKEN_KEY,
+                format!("must be at least {} characters", MIN_AUTH_TOKEN_LEN),
+            ));
+        }
+
+        Ok(Self { token })
+    }
+
+    /// Whether requests must be authenticated
+    pub fn is_enabled(&self) -> bool {
+        self.token.is_some()
+    }
+}
+
+/// Per-client limits for expensive endpoints
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimitSettings {
+    /// Apply the limits at all
+    pub enabled: bool,
+    /// Requests allowed back to back before throttling starts
+    pub burst: u32,
+    /// Sustained request rate once the burst is used up
+    pub per_minute: u32,
+}
+
+impl Default for RateLimitSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            burst: 10,
+            per_minute: 30,
+        }
+    }
+}
+
+impl RateLimitSettings {
+    /// Load rate limit settings, using defaults for any key that is not set
+    pub fn load(db: &Database) -> SettingsResult<Self> {
+        let defaults = Self::default();
+
+        let settings = Self {
+            enabled: get_bool(db, RATE_LIMIT_ENABLED_KEY)?.unwrap_or(defaults.enabled),
+            burst: get_u32(db, RATE_LIMIT_BURST_KEY)?.unwrap_or(defaults.burst),
+            per_minute: get_u32(db, RATE_LIMIT_PER_MINUTE_KEY)?.unwrap_or(defaults.per_minute),
+        };
+
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    /// Check that enabled limits allow at least some traffic
+    pub fn validate(&self) -> SettingsResult<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        if self.burst == 0 {
+            return Err(invalid(RATE_LIMIT_BURST_KEY, "must be at least 1"));
+        }
+        if self.per_minute == 0 {
+            return Err(invalid(RATE_LIMIT_PER_MINUTE_KEY, "must be at least 1"));
+        }
+        Ok(())
+    }
+}
+
+/// Where clones go and how much space they may take
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageSettings {
+    /// Directory clones are created in; `None` when no home directory is known
+    pub clone_root: Option<PathBuf>,
+    /// Total size the clone root may grow to, in bytes; `None` for no limit
+    pub clone_quota_bytes: Option<u64>,
+}
+
+impl Default for StorageSettings {
+    fn default() -> Self {
+        Self {
+            clone_root: dirs::home_dir().map(|home| home.join("ralphtown")),
+            clone_quota_bytes: None,
+        }
+    }
+}
+
+impl StorageSettings {
+    /// Load storage settings, using defaults for any key that is not set
+    pub fn load(db: &Database) -> SettingsResult<Self> {
+        let defaults = Self::default();
+
+        Ok(Self {
+            clone_root: get_path(db, CLONE_ROOT_KEY)?.or(defaults.clone_root),
+            clone_quota_bytes: get_u32(db, CLONE_QUOTA_MB_KEY)?
+                .filter(|mb| *mb > 0)
+                .map(|mb| u64::from(mb) * 1024 * 1024),
+        })
+    }
+}
+
+/// How large request bodies may be
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BodyLimits {
+    /// Largest body of any request other than uploads and bundle imports
+    pub request_bytes: usize,
+    /// Largest upload request, counting all of its files
+    pub upload_bytes: u64,
+}
+
+impl Default for BodyLimits {
+    fn default() -> Self {
+        Self {
+            request_bytes: 2 * 1024 * 1024,
+            upload_bytes: 100 * 1024 * 1024,
+        }
+    }
+}
+
+impl BodyLimits {
+    /// Load body limits, using defaults for any key that is not set
+    pub fn load(db: &Database) -> SettingsResult<Self> {
+        let defaults = Self::default();
+
+        let request_bytes = match get_u32(db, REQUEST_BODY_MAX_KB_KEY)? {
+            None => defaults.request_bytes,
+            Some(0) => return Err(invalid(REQUEST_BODY_MAX_KB_KEY, "must be at least 1")),
+            Some(kb) => kb as usize * 1024,
+        };
+        let upload_bytes = match get_u32(db, UPLOAD_MAX_MB_KEY)? {
+            None => defaults.upload_bytes,
+            Some(0) => return Err(invalid(UPLOAD_MAX_MB_KEY, "must be at least 1")),
+            Some(mb) => u64::from(mb) * 1024 * 1024,
+        };
+        Ok(Self {
+            request_bytes,
+            upload_bytes,
+        })
+    }
+}
+
+/// Background maintenance policy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MaintenanceSettings {
+    /// How long finished webhook deliveries and snapshots are kept; `None` keeps them forever
+    pub retention: Option<Duration>,
+    /// How long session output is kept; `None` keeps it forever
+    pub output_retention: Option<Duration>,
+    /// Most output lines kept for each session; `None` keeps them all
+    pub output_max_lines: Option<u64>,
+    /// Interval overrides by job name; a zero interval turns the job off
+    pub intervals: HashMap<String, Duration>,
+}
+
+impl Default for MaintenanceSettings {
+    fn default() -> Self {
+        Self {
+            retention: Some(Duration::from_secs(30 * 24 * 60 * 60)),
+            output_retention: None,
+            output_max_lines: None,
+            intervals: HashMap::new(),
+        }
+    }
+}
+
+impl MaintenanceSettings {
+    /// Load maintenance settings, using defaults for any key that is not set
+    pub fn load(db: &Database) -> SettingsResult<Self> {
+        let defaults = Self::default();
+
+        let retention = match get_u32(db, RETENTION_DAYS_KEY)? {
+            None => defaults.retention,
+            Some(0) => None,
+            Some(days) => Some(Duration::from_secs(u64::from(days) * 24 * 60 * 60)),
+        };
+        let output_retention = get_u32(db, OUTPUT_RETENTION_DAYS_KEY)?
+            .filter(|days| *days > 0)
+            .map(|days| Duration::from_secs(u64::from(days) * 24 * 60 * 60));
+        let output_max_lines = get_u32(db, OUTPUT_MAX_LINES_KEY)?
+            .filter(|lines| *lines > 0)
+            .map(u64::from);
+
+        let mut intervals = HashMap::new();
+        for entry in get_list(db, MAINTENANCE_INTERVALS_KEY)?.unwrap_or_default() {
+            let parsed = entry.split_once('=').and_then(|(job, minutes)| {
+                let job = job.trim();
+                let minutes: u64 = minutes.trim().parse().ok()?;
+                maintenance::JOB_NAMES
+                    .contains(&job)
+                    .then(|| (job.to_string(), Duration::from_secs(minutes * 60)))
+            });
+            let Some((job, interval)) = parsed else {
+                return Err(invalid(
+                    MAINTENANCE_INTERVALS_KEY,
+                    format!(
+                        "expected job=minutes with a job among {}, got '{}'",
+                        maintenance::JOB_NAMES.join(", "),
+                        entry
+                    ),
+                ));
+            };
+            intervals.insert(job, interval);
+        }
+
+        Ok(Self {
+            retention,
+            output_retention,
+            output_max_lines,
+            intervals,
+        })
+    }
+}
+
+/// Check whether a list is the single "*" wildcard
+pub fn is_wildcard(values: &[String]) -> bool {
+    values.len() == 1 && values[0] == "*"
+}
+
+/// Build an invalid value error
+fn invalid(key: &str, message: impl Into<String>) -> SettingsError {
+    SettingsError::InvalidValue {
+        key: key.to_string(),
+        message: message.into(),
+    }
+}
+
+/// Read a comma-separated list value
+fn get_list(db: &Database, key: &str) -> SettingsResult<Option<Vec<String>>> {
+    Ok(db.get_config(key)?.map(|value| {
+        value
+            .split(',')
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .map(String::from)
+            .collect()
+    }))
+}
+
+/// Read a filesystem path value, treating an empty string as unset
+fn get_path(db: &Database, key: &str) -> SettingsResult<Option<PathBuf>> {
+    Ok(db
+        .get_config(key)?
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from))
+}
+
+/// Read a boolean value ("true"/"false", "1"/"0", "yes"/"no")
+fn get_bool(db: &Database, key: &str) -> SettingsResult<Option<bool>> {
+    match db.get_config(key)? {
+        None => Ok(None),
+        Some(value) => match value.trim().to_lowercase().as_str() {
+            "true" | "1" | "yes" => Ok(Some(true)),
+            "false" | "0" | "no" => Ok(Some(false)),
+            _ => Err(invalid(key, format!("expected a boolean, got '{}'", value))),
+        },
+    }
+}
+
+/// Read the orchestrators declared in configuration
+fn get_orchestrators(db: &Database) -> SettingsResult<Vec<OrchestratorPlugin>> {
+    match db.get_config(ORCHESTRATORS_KEY)? {
+        None => Ok(Vec::new()),
+        Some(value) => {
+            orchestrator::parse_plugins(&value).map_err(|message| invalid(ORCHESTRATORS_KEY, message))
+        }
+    }
+}
+
+/// Read the artifact globs, using the defaults when the key is not set
+fn get_artifact_patterns(db: &Database) -> SettingsResult<ArtifactPatterns> {
+    match get_list(db, ARTIFACT_PATTERNS_KEY)? {
+        None => Ok(ArtifactPatterns::default()),
+        Some(patterns) => {
+            ArtifactPatterns::parse(&patterns).map_err(|message| invalid(ARTIFACT_PATTERNS_KEY, message))
+        }
+    }
+}
+
+/// Read the output line limits, using the defaults for keys that are not set
+fn get_output_limits(db: &Database) -> SettingsResult<OutputLimits> {
+    let defaults = OutputLimits::default();
+    let max_line_bytes = match get_u32(db, OUTPUT_MAX_LINE_KB_KEY)? {
+        None => defaults.max_line_bytes,
+        Some(0) => usize::MAX,
+        Some(kb) => kb as usize * 1024,
+    };
+    let chunk_bytes = match get_u32(db, OUTPUT_CHUNK_KB_KEY)? {
+        None => defaults.chunk_bytes,
+        Some(0) => return Err(invalid(OUTPUT_CHUNK_KB_KEY, "must be at least 1")),
+        Some(kb) => kb as usize * 1024,
+    };
+    Ok(OutputLimits {
+        max_line_bytes,
+        chunk_bytes,
+    })
+}
+
+/// Read the run resource limits; unset or 0 is no limit
+fn get_run_limits(db: &Database) -> SettingsResult<RunLimits> {
+    const MB: u64 = 1024 * 1024;
+    let limit = |key: &str| -> SettingsResult<Option<u64>> {
+        Ok(get_u32(db, key)?.filter(|value| *value > 0).map(u64::from))
+    };
+    Ok(RunLimits {
+        max_duration: limit(RUN_TIMEOUT_MINUTES_KEY)?.map(|minutes| Duration::from_secs(minutes * 60)),
+        max_memory_bytes: limit(RUN_MAX_MEMORY_MB_KEY)?.map(|mb| mb * MB),
+        max_output_bytes: limit(RUN_MAX_OUTPUT_MB_KEY)?.map(|mb| mb * MB),
+        stall_after: limit(RUN_STALL_MINUTES_KEY)?.map(|minutes| Duration::from_secs(minutes * 60)),
+        stop_stalled: get_bool(db, RUN_STALL_STOP_KEY)?.unwrap_or(false),
+    })
+}
+
+fn get_port(db: &Database) -> SettingsResult<u16> {
+    match get_u32(db, PORT_KEY)? {
+        None => Ok(DEFAULT_PORT),
+        Some(port @ 1..=65535) => Ok(port as u16),
+        Some(_) => Err(invalid(PORT_KEY, "must be between 1 and 65535")),
+    }
+}
+
+/// Read the base path, normalized to a leading slash and no trailing slash
+fn get_base_path(db: &Database) -> SettingsResult<String> {
+    let Some(value) = db.get_config(BASE_PATH_KEY)? else {
+        return Ok(String::new());
+    };
+    let trimmed = value.trim().trim_matches('/');
+    if trimmed.is_empty() {
+        return Ok(String::new());
+    }
+
+    let valid = trimmed.split('/').all(|segment| {
+        !segment.is_empty()
+            && segment != "."
+            && segment != ".."
+            && segment
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '~'))
+    });
+    if !valid {
+        return Err(invalid(
+            BASE_PATH_KEY,
+            format!("'{}' is not a valid URL path prefix", value),
+        ));
+    }
+    Ok(format!("/{}", trimmed))
+}
+
+/// Read the sandbox settings; no sandbox without an image
+fn get_sandbox(db: &Database) -> SettingsResult<Option<Sandbox>> {
+    let runtime = match db.get_config(SANDBOX_RUNTIME_KEY)?.as_deref().map(str::trim) {
+        None | Some("") => None,
+        Some(runtime @ ("docker" | "podman")) => Some(runtime.to_string()),
+        Some(other) => {
+            return Err(invalid(
+                SANDBOX_RUNTIME_KEY,
+                format!("expected 'docker' or 'podman', got '{}'", other),
+            ));
+        }
+    };
+    let image = db
+        .get_config(SANDBOX_IMAGE_KEY)?
+        .map(|image| image.trim().to_string())
+        .filter(|image| !image.is_empty());
+    Ok(image.map(|image| Sandbox::new(runtime, image)))
+}
+
+/// Read a non-negative integer value
+fn get_u32(db: &Database, key: &str) -> SettingsResult<Option<u32>> {
+    match db.get_config(key)? {
+        None => Ok(None),
+        Some(value) => value
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|_| invalid(key, format!("expected a non-negative integer, got '{}'", value))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults_when_unset() {
+        let db = Database::in_memory().expect("Failed to create test database");
+
+        let settings = Settings::load(&db).expect("Failed to load settings");
+        assert_eq!(settings.cors, CorsSettings::default());
+        assert!(!settings.cors.allow_credentials);
+        assert!(settings
+            .cors
+            .allowed_origins
+            .iter()
+            .all(|o| o.contains("localhost") || o.contains("127.0.0.1")));
+    }
+
+    #[test]
+    fn test_load_port() {
+        let db = Database::in_memory().expect("Failed to create test database");
+        let settings = Settings::load(&db).unwrap();
+        assert_eq!((settings.port, settings.port_fallback), (DEFAULT_PORT, false));
+
+        db.set_config(PORT_KEY, "8080").unwrap();
+        db.set_config(PORT_FALLBACK_KEY, "true").unwrap();
+        let settings = Settings::load(&db).unwrap();
+        assert_eq!((settings.port, settings.port_fallback), (8080, true));
+
+        for value in ["0", "70000", "http"] {
+            db.set_config(PORT_KEY, value).unwrap();
+            assert!(Settings::load(&db).is_err());
+        }
+    }
+
+    #[test]
+    fn test_load_cors_from_config() {
+        let db = Database::in_memory().expect("Failed to create test database");
+        db.set_config(CORS_ALLOWED_ORIGINS_KEY, "https://a.example, https://b.example")
+            .unwrap();
+        db.set_config(CORS_ALLOWED_METHODS_KEY, "GET,POST").unwrap();
+        db.set_config(CORS_ALLOW_CREDENTIALS_KEY, "true").unwrap();
+
+        let cors = CorsSettings::load(&db).expect("Failed to load settings");
+        assert_eq!(cors.allowed_origins, vec!["https://a.example", "https://b.example"]);
+        assert_eq!(cors.allowed_methods, vec!["GET", "POST"]);
+        assert_eq!(cors.allowed_headers, CorsSettings::default().allowed_headers);
+        assert!(cors.allow_credentials);
+    }
+
+    #[test]
+    fn test_rejects_wildcard_with_credentials() {
+        let db = Database::in_memory().expect("Failed to create test database");
+        db.set_config(CORS_ALLOWED_ORIGINS_KEY, "*").unwrap();
+        db.set_config(CORS_ALLOW_CREDENTIALS_KEY, "true").unwrap();
+
+        let result = CorsSettings::load(&db);
+        assert!(matches!(result, Err(SettingsError::InvalidValue { .. })));
+    }
+
+    #[test]
+    fn test_load_tls_from_config() {
+        let db = Database::in_memory().expect("Failed to create test database");
+        db.set_config(TLS_ENABLED_KEY, "true").unwrap();
+        db.set_config(TLS_CERT_PATH_KEY, "/etc/ralphtown/cert.pem").unwrap();
+        db.set_config(TLS_KEY_PATH_KEY, "/etc/ralphtown/key.pem").unwrap();
+
+        let tls = TlsSettings::load(&db).expect("Failed to load settings");
+        assert!(tls.enabled);
+        assert_eq!(tls.cert_path, Some(PathBuf::from("/etc/ralphtown/cert.pem")));
+        assert_eq!(tls.key_path, Some(PathBuf::from("/etc/ralphtown/key.pem")));
+    }
+
+    #[test]
+    fn test_tls_requires_cert_and_key_together() {
+        let db = Database::in_memory().expect("Failed to create test database");
+        db.set_config(TLS_CERT_PATH_KEY, "/etc/ralphtown/cert.pem").unwrap();
+
+        let result = TlsSettings::load(&db);
+        assert!(matches!(result, Err(SettingsError::InvalidValue { key, .. }) if key == TLS_KEY_PATH_KEY));
+    }
+
+    #[test]
+    fn test_tls_requires_paths_without_self_signed() {
+        let db = Database::in_memory().expect("Failed to create test database");
+        db.set_config(TLS_ENABLED_KEY, "true").unwrap();
+        db.set_config(TLS_SELF_SIGNED_KEY, "false").unwrap();
+
+        let result = TlsSettings::load(&db);
+        assert!(matches!(result, Err(SettingsError::InvalidValue { .. })));
+    }
+
+    #[test]
+    fn test_load_auth_token() {
+        let db = Database::in_memory().expect("Failed to create test database");
+        assert!(!AuthSettings::load(&db).unwrap().is_enabled());
+
+        db.set_config(AUTH_TOKEN_KEY, "  0123456789abcdef  ").unwrap();
+        let auth = AuthSettings::load(&db).expect("Failed to load settings");
+        assert_eq!(auth.token.as_deref(), Some("0123456789abcdef"));
+
+        db.set_config(AUTH_TOKEN_KEY, "short").unwrap();
+        assert!(AuthSettings::load(&db).is_err());
+    }
+
+    #[test]
+    fn test_is_exposed() {
+        let mut settings = Settings::default();
+        assert!(!settings.is_exposed());
+
+        settings.listen_addrs = vec!["127.0.0.1".parse().unwrap(), "[::1]:3000".parse().unwrap()];
+        assert!(!settings.is_exposed());
+
+        settings.listen_addrs.push("0.0.0.0".parse().unwrap());
+        assert!(settings.is_exposed());
+    }
+
+    #[test]
+    fn test_load_rate_limit() {
+        let db = Database::in_memory().expect("Failed to create test database");
+        assert_eq!(
+            RateLimitSettings::load(&db).unwrap(),
+            RateLimitSettings::default()
+        );
+
+        db.set_config(RATE_LIMIT_BURST_KEY, "3").unwrap();
+        db.set_config(RATE_LIMIT_PER_MINUTE_KEY, "6").unwrap();
+        let rate_limit = RateLimitSettings::load(&db).expect("Failed to load settings");
+        assert_eq!(rate_limit.burst, 3);
+        assert_eq!(rate_limit.per_minute, 6);
+
+        db.set_config(RATE_LIMIT_BURST_KEY, "0").unwrap();
+        assert!(RateLimitSettings::load(&db).is_err());
+
+        db.set_config(RATE_LIMIT_BURST_KEY, "lots").unwrap();
+        assert!(RateLimitSettings::load(&db).is_err());
+    }
+
+    #[test]
+    fn test_load_base_path() {
+        let db = Database::in_memory().expect("Failed to create test database");
+        assert_eq!(Settings::load(&db).unwrap().base_path, "");
+
+        db.set_config(BASE_PATH_KEY, "ralphtown/").unwrap();
+        assert_eq!(Settings::load(&db).unwrap().base_path, "/ralphtown");
+
+        db.set_config(BASE_PATH_KEY, "/").unwrap();
+        assert_eq!(Settings::load(&db).unwrap().base_path, "");
+
+        db.set_config(BASE_PATH_KEY, "/a/../b").unwrap();
+        assert!(Settings::load(&db).is_err());
+
+        db.set_config(BASE_PATH_KEY, "/tools?x").unwrap();
+        assert!(Settings::load(&db).is_err());
+    }
+
+    #[test]
+    fn test_load_read_only() {
+        let db = Database::in_memory().expect("Failed to create test database");
+        assert!(!Settings::load(&db).unwrap().read_only);
+
+        db.set_config(READ_ONLY_KEY, "true").unwrap();
+        assert!(Settings::load(&db).unwrap().read_only);
+    }
+
+    #[test]
+    fn test_load_storage() {
+        let db = Database::in_memory().expect("Failed to create test database");
+        assert_eq!(Settings::load(&db).unwrap().storage.clone_quota_bytes, None);
+
+        db.set_config(CLONE_ROOT_KEY, "/srv/clones").unwrap();
+        db.set_config(CLONE_QUOTA_MB_KEY, "2").unwrap();
+        let storage = Settings::load(&db).unwrap().storage;
+        assert_eq!(storage.clone_root, Some(PathBuf::from("/srv/clones")));
+        assert_eq!(storage.clone_quota_bytes, Some(2 * 1024 * 1024));
+
+        db.set_config(CLONE_QUOTA_MB_KEY, "0").unwrap();
+        assert_eq!(Settings::load(&db).unwrap().storage.clone_quota_bytes, None);
+
+        db.set_config(CLONE_QUOTA_MB_KEY, "lots").unwrap();
+        assert!(Settings::load(&db).is_err());
+    }
+
+    #[test]
+    fn test_load_artifact_patterns() {
+        let db = Database::in_memory().expect("Failed to create test database");
+        assert!(Settings::load(&db).unwrap().artifacts.matches("reports/summary.md"));
+
+        db.set_config(ARTIFACT_PATTERNS_KEY, "out/**, *.log").unwrap();
+        let artifacts = Settings::load(&db).unwrap().artifacts;
+        assert!(artifacts.matches("out/report.html"));
+        assert!(!artifacts.matches("reports/summary.md"));
+
+        db.set_config(ARTIFACT_PATTERNS_KEY, "").unwrap();
+        assert!(Settings::load(&db).unwrap().artifacts.is_empty());
+
+        db.set_config(ARTIFACT_PATTERNS_KEY, "../escape/*").unwrap();
+        assert!(Settings::load(&db).is_err());
+    }
+
+    #[test]
+    fn test_load_maintenance_settings() {
+        let db = Database::in_memory().expect("Failed to create test database");
+        assert_eq!(Settings::load(&db).unwrap().maintenance, MaintenanceSettings::default());
+
+        db.set_config(RETENTION_DAYS_KEY, "0").unwrap();
+        db.set_config(OUTPUT_RETENTION_DAYS_KEY, "7").unwrap();
+        db.set_config(OUTPUT_MAX_LINES_KEY, "5000").unwrap();
+        db.set_config(MAINTENANCE_INTERVALS_KEY, "retention=120, repo_validation=0").unwrap();
+        let maintenance = Settings::load(&db).unwrap().maintenance;
+        assert_eq!(maintenance.retention, None);
+        assert_eq!(maintenance.output_retention, Some(Duration::from_secs(7 * 24 * 60 * 60)));
+        assert_eq!(maintenance.output_max_lines, Some(5000));
+        assert_eq!(maintenance.intervals["retention"], Duration::from_secs(7200));
+        assert_eq!(maintenance.intervals["repo_validation"], Duration::ZERO);
+
+        for invalid in ["retention", "retention=soon", "defrag=5"] {
+            db.set_config(MAINTENANCE_INTERVALS_KEY, invalid).unwrap();
+            assert!(Settings::load(&db).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_load_output_limits() {
+        let db = Database::in_memory().expect("Failed to create test database");
+        assert_eq!(Settings::load(&db).unwrap().output, OutputLimits::default());
+
+        db.set_config(OUTPUT_MAX_LINE_KB_KEY, "0").unwrap();
+        db.set_config(OUTPUT_CHUNK_KB_KEY, "16").unwrap();
+        let output = Settings::load(&db).unwrap().output;
+        assert_eq!(output.max_line_bytes, usize::MAX);
+        assert_eq!(output.chunk_bytes, 16 * 1024);
+
+        db.set_config(OUTPUT_CHUNK_KB_KEY, "0").unwrap();
+        assert!(Settings::load(&db).is_err());
+    }
+
+    #[test]
+    fn test_load_run_limits() {
+        let db = Database::in_memory().expect("Failed to create test database");
+        assert!(Settings::load(&db).unwrap().run_limits.is_empty());
+
+        db.set_config(RUN_TIMEOUT_MINUTES_KEY, "90").unwrap();
+        db.set_config(RUN_MAX_MEMORY_MB_KEY, "0").unwrap();
+        db.set_config(RUN_MAX_OUTPUT_MB_KEY, "50").unwrap();
+        let limits = Settings::load(&db).unwrap().run_limits;
+        assert_eq!(limits.max_duration, Some(Duration::from_secs(90 * 60)));
+        assert_eq!(limits.max_memory_bytes, None);
+        assert_eq!(limits.max_output_bytes, Some(50 * 1024 * 1024));
+        assert_eq!((limits.stall_after, limits.stop_stalled), (None, false));
+
+        db.set_config(RUN_STALL_MINUTES_KEY, "10").unwrap();
+        db.set_config(RUN_STALL_STOP_KEY, "true").unwrap();
+        let limits = Settings::load(&db).unwrap().run_limits;
+        assert_eq!((limits.stall_after, limits.stop_stalled), (Some(Duration::from_secs(600)), true));
+
+        db.set_config(RUN_TIMEOUT_MINUTES_KEY, "soon").unwrap();
+        assert!(Settings::load(&db).is_err());
+        db.set_config(RUN_TIMEOUT_MINUTES_KEY, "0").unwrap();
+
+        assert_eq!(Settings::load(&db).unwrap().max_concurrent_runs, None);
+        db.set_config(MAX_CONCURRENT_RUNS_KEY, "3").unwrap();
+        assert_eq!(Settings::load(&db).unwrap().max_concurrent_runs, Some(3));
+
+        assert_eq!(Settings::load(&db).unwrap().sandbox, None);
+        db.set_config(SANDBOX_IMAGE_KEY, "agents:latest").unwrap();
+        db.set_config(SANDBOX_RUNTIME_KEY, "podman").unwrap();
+        assert_eq!(
+            Settings::load(&db).unwrap().sandbox,
+            Some(Sandbox::new(Some("podman".to_string()), "agents:latest".to_string()))
+        );
+        db.set_config(SANDBOX_RUNTIME_KEY, "lxc").unwrap();
+        assert!(Settings::load(&db).is_err());
+    }
+
+    #[test]
+    fn test_rejects_invalid_bool() {
+        let db = Database::in_memory().expect("Failed to create test database");
+        db.set_config(CORS_ALLOW_CREDENTIALS_KEY, "maybe").unwrap();
+
+        let result = CorsSettings::load(&db);
+        assert!(matches!(result, Err(SettingsError::InvalidValue { .. })));
+    }
+}
@@ -0,0 +1,432 @@
+//! Workspace snapshots
+//!
+//! Before each run the repository's uncommitted work is copied into the data
+//! directory, so whatever an orchestrator does to the working tree can be
+//! undone. A snapshot holds the commit HEAD pointed at, a copy of every
+//! modified or untracked file, and the list of tracked files that were
+//! deleted. Snapshots live outside of git: taking one never touches the
+//! index, stash, or history, and restoring one rewrites the working tree
+//! without moving HEAD or any branch.
+//!
+//! Ignored files (build output, dependencies) are neither captured nor
+//! removed on restore.
+
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+
+use chrono::Utc;
+use git2::build::CheckoutBuilder;
+use git2::{Repository, ResetType, Status, StatusOptions};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::db::models::{Snapshot, SnapshotReason};
+use crate::db::{Database, DbError};
+
+/// Largest snapshot taken, in bytes of copied files
+pub const MAX_SNAPSHOT_BYTES: u64 = 512 * 1024 * 1024;
+
+/// Name of the file listing a snapshot's contents
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// Directory within a snapshot holding the copied files
+const FILES_DIR: &str = "files";
+
+/// Snapshot errors
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    #[error("Git error: {0}")]
+    Git(#[from] git2::Error),
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Db(#[from] DbError),
+
+    #[error("Uncommitted changes exceed the {} MiB snapshot limit", MAX_SNAPSHOT_BYTES / 1024 / 1024)]
+    TooLarge,
+
+    #[error("Repository has no working directory")]
+    BareRepository,
+
+    #[error("Snapshot {0} is damaged: {1}")]
+    Damaged(Uuid, String),
+}
+
+pub type SnapshotResult<T> = Result<T, SnapshotError>;
+
+/// Contents of a snapshot, stored next to its files
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Manifest {
+    /// Files copied into the snapshot, relative to the repository root
+    files: Vec<String>,
+    /// Tracked files missing from the working tree
+    deleted: Vec<String>,
+}
+
+/// Snapshots stored under a directory, one subdirectory per snapshot
+#[derive(Debug, Clone)]
+pub struct SnapshotStore {
+    root: PathBuf,
+}
+
+impl SnapshotStore {
+    /// Store snapshots in `root`
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    /// Store snapshots in the `snapshots` directory of the server's data directory
+    pub fn in_data_dir(data_dir: &Path) -> Self {
+        Self::new(data_dir.join("snapshots"))
+    }
+
+    /// Directory holding a snapshot's manifest and files
+    fn dir(&self, id: Uuid) -> PathBuf {
+        self.root.join(id.to_string())
+    }
+
+    /// Copy the uncommitted state of the repository at `repo_path`
+    pub fn capture(
+        &self,
+        db: &Database,
+        session_id: Uuid,
+        repo_id: Uuid,
+        repo_path: &Path,
+        reason: SnapshotReason,
+    ) -> SnapshotResult<Snapshot> {
+        let repo = Repository::open(repo_path)?;
+        let workdir = repo.workdir().ok_or(SnapshotError::BareRepository)?.to_path_buf();
+        let head_commit = repo
+            .head()
+            .ok()
+            .and_then(|head| head.target())
+            .map(|oid| oid.to_string());
+
+        let id = Uuid::new_v4();
+        let dir = self.dir(id);
+        let written = write_snapshot(&repo, &workdir, &dir);
+        let (manifest, size_bytes) = match written {
+            Ok(written) => written,
+            Err(e) => {
+                let _ = fs::remove_dir_all(&dir);
+                return Err(e);
+            }
+        };
+
+        let snapshot = Snapshot {
+            id,
+            session_id,
+            repo_id,
+            reason,
+            head_commit,
+            file_count: manifest.files.len() as u32,
+            deleted_count: manifest.deleted.len() as u32,
+            size_bytes,
+            created_at: Utc::now(),
+        };
+        if let Err(e) = db.insert_snapshot(&snapshot) {
+            let _ = fs::remove_dir_all(&dir);
+            return Err(e.into());
+        }
+
+        Ok(snapshot)
+    }
+
+    /// Put the working tree back to how it was when `snapshot` was taken
+    ///
+    /// The current state is captured first, so a restore can itself be
+    /// undone; that safety snapshot is returned.
+    pub fn restore(
+        &self,
+        db: &Database,
+        snapshot: &Snapshot,
+        repo_path: &Path,
+    ) -> SnapshotResult<Snapshot> {
+        let dir = self.dir(snapshot.id);
+        let manifest = read_manifest(snapshot.id, &dir)?;
+
+        let safety = self.capture(
+            db,
+            snapshot.session_id,
+            snapshot.repo_id,
+            repo_path,
+            SnapshotReason::PreRestore,
+        )?;
+
+        let repo = Repository::open(repo_path)?;
+        let workdir = repo.workdir().ok_or(SnapshotError::BareRepository)?.to_path_buf();
+
+        // Reset the working tree to the snapshot's commit (or to nothing for a
+        // repository that had no commits), dropping untracked files
+        let tree = match &snapshot.head_commit {
+            Some(oid) => repo
+                .find_commit(git2::Oid::from_str(oid)?)
+                .map_err(|_| {
+                    SnapshotError::Damaged(
+                        snapshot.id,
+                        format!("commit {} is no longer in the repository", oid),
+                    )
+                })?
+                .tree()?,
+            None => {
+                let empty = repo.treebuilder(None)?.write()?;
+                repo.find_tree(empty)?
+            }
+        };
+        repo.checkout_tree(
+            tree.as_object(),
+            Some(CheckoutBuilder::new().force().remove_untracked(true)),
+        )?;
+
+        let files = dir.join(FILES_DIR);
+        for path in &manifest.files {
+            let target = workdir.join(path);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::copy(files.join(path), &target)?;
+        }
+        for path in &manifest.deleted {
+            let target = workdir.join(path);
+            if target.exists() {
+                fs::remove_file(target)?;
+            }
+        }
+
+        // Checking out the tree staged it; leave the index matching HEAD so
+        // restored changes show up as unstaged edits
+        match repo.head().ok().and_then(|head| head.peel_to_commit().ok()) {
+            Some(head) => repo.reset(head.as_object(), ResetType::Mixed, None)?,
+            None => {
+                let mut index = repo.index()?;
+                index.clear()?;
+                index.write()?;
+            }
+        }
+
+        Ok(safety)
+    }
+
+    /// Delete a snapshot and its files
+    pub fn remove(&self, db: &Database, id: Uuid) -> SnapshotResult<()> {
+        db.delete_snapshot(id)?;
+        self.remove_files(id);
+        Ok(())
+    }
+
+    /// Delete a snapshot's files, e.g. after its session was deleted
+    pub fn remove_files(&self, id: Uuid) {
+        let dir = self.dir(id);
+        if dir.exists()
+            && let Err(e) = fs::remove_dir_all(&dir)
+        {
+            tracing::warn!("Failed to remove snapshot {}: {}", id, e);
+        }
+    }
+
+    /// Snapshot directories on disk with their modification times, whether or
+    /// not the database still knows about them
+    pub fn stored(&self) -> std::io::Result<Vec<(Uuid, SystemTime)>> {
+        let entries = match fs::read_dir(&self.root) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+
+        let mut stored = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            let id = entry.file_name().to_str().and_then(|name| Uuid::parse_str(name).ok());
+            if let Some(id) = id
+                && entry.file_type()?.is_dir()
+            {
+                stored.push((id, entry.metadata()?.modified()?));
+            }
+        }
+        Ok(stored)
+    }
+}
+
+/// Copy the working tree's changes into `dir`, returning the manifest and bytes copied
+fn write_snapshot(repo: &Repository, workdir: &Path, dir: &Path) -> SnapshotResult<(Manifest, u64)> {
+    let statuses = repo.statuses(Some(
+        StatusOptions::new()
+            .include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .include_ignored(false)
+            .exclude_submodules(true),
+    ))?;
+
+    let files = dir.join(FILES_DIR);
+    fs::create_dir_all(&files)?;
+
+    let mut manifest = Manifest::default();
+    let mut size_bytes = 0u64;
+    for entry in statuses.iter() {
+        let status = entry.status();
+        let Some(path) = entry.path() else {
+            tracing::warn!("Skipping non-UTF-8 path in snapshot");
+            continue;
+        };
+        if status.intersects(Status::CONFLICTED | Status::IGNORED) {
+            continue;
+        }
+
+        let source = workdir.join(path);
+        match fs::symlink_metadata(&source) {
+            Ok(metadata) if metadata.is_file() => {
+                size_bytes += metadata.len();
+                if size_bytes > MAX_SNAPSHOT_BYTES {
+                    return Err(SnapshotError::TooLarge);
+                }
+                let target = files.join(path);
+                if let Some(parent) = target.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::copy(&source, &target)?;
+                manifest.files.push(path.to_string());
+            }
+            // Symlinks and other special files aren't captured
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                manifest.deleted.push(path.to_string());
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    fs::write(
+        dir.join(MANIFEST_FILE),
+        serde_json::to_vec(&manifest).expect("manifest serializes"),
+    )?;
+    Ok((manifest, size_bytes))
+}
+
+/// Read and check a snapshot's manifest
+fn read_manifest(id: Uuid, dir: &Path) -> SnapshotResult<Manifest> {
+    let contents = fs::read(dir.join(MANIFEST_FILE))
+        .map_err(|e| SnapshotError::Damaged(id, format!("manifest unreadable: {}", e)))?;
+    let manifest: Manifest = serde_json::from_slice(&contents)
+        .map_err(|e| SnapshotError::Damaged(id, format!("invalid manifest: {}", e)))?;
+
+    // Never write outside of the working tree
+    for path in manifest.files.iter().chain(&manifest.deleted) {
+        let relative = Path::new(path)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)));
+        if !relative {
+            return Err(SnapshotError::Damaged(id, format!("invalid path {}", path)));
+        }
+    }
+
+    Ok(manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::Orchestrator;
+    use tempfile::TempDir;
+
+    fn commit_all(repo: &Repository, message: &str) {
+        let mut index = repo.index().unwrap();
+        index
+            .add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)
+            .unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = git2::Signature::now("Test", "test@example.com").unwrap();
+        let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &parents)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_capture_and_restore() {
+        let work = TempDir::new().unwrap();
+        let data = TempDir::new().unwrap();
+        let repo = Repository::init(work.path()).unwrap();
+        let path = work.path();
+        fs::write(path.join("kept.txt"), "original").unwrap();
+        fs::write(path.join("removed.txt"), "tracked").unwrap();
+        fs::write(path.join(".gitignore"), "ignored.txt\n").unwrap();
+        commit_all(&repo, "initial");
+
+        // Dirty state before the run
+        fs::write(path.join("kept.txt"), "edited").unwrap();
+        fs::remove_file(path.join("removed.txt")).unwrap();
+        fs::create_dir(path.join("notes")).unwrap();
+        fs::write(path.join("notes/todo.md"), "- item").unwrap();
+        fs::write(path.join("ignored.txt"), "local").unwrap();
+
+        let db = Database::in_memory().unwrap();
+        let db_repo = db.insert_repo(&path.to_string_lossy(), "repo").unwrap();
+        let session = db.insert_session(db_repo.id, None, Orchestrator::Ralph).unwrap();
+        let store = SnapshotStore::in_data_dir(data.path());
+        let snapshot = store
+            .capture(&db, session.id, db_repo.id, path, SnapshotReason::PreRun)
+            .unwrap();
+        assert_eq!(snapshot.file_count, 2);
+        assert_eq!(snapshot.deleted_count, 1);
+        assert!(snapshot.head_commit.is_some());
+
+        // The run edits files and commits
+        fs::write(path.join("kept.txt"), "agent").unwrap();
+        fs::write(path.join("removed.txt"), "back").unwrap();
+        fs::write(path.join("new.rs"), "fn main() {}").unwrap();
+        commit_all(&repo, "agent work");
+        fs::write(path.join("scratch.txt"), "tmp").unwrap();
+
+        let safety = store.restore(&db, &snapshot, path).unwrap();
+        assert_eq!(safety.reason, SnapshotReason::PreRestore);
+
+        assert_eq!(fs::read_to_string(path.join("kept.txt")).unwrap(), "edited");
+        assert!(!path.join("removed.txt").exists());
+        assert!(!path.join("new.rs").exists());
+        assert!(!path.join("scratch.txt").exists());
+        assert_eq!(fs::read_to_string(path.join("notes/todo.md")).unwrap(), "- item");
+        assert_eq!(fs::read_to_string(path.join("ignored.txt")).unwrap(), "local");
+
+        // History is untouched
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.message(), Some("agent work"));
+
+        // Restoring the safety snapshot brings the run's work back
+        store.restore(&db, &safety, path).unwrap();
+        assert_eq!(fs::read_to_string(path.join("kept.txt")).unwrap(), "agent");
+        assert!(path.join("new.rs").exists());
+        assert!(path.join("scratch.txt").exists());
+
+        store.remove(&db, snapshot.id).unwrap();
+        assert!(!data.path().join("snapshots").join(snapshot.id.to_string()).exists());
+        assert!(matches!(db.get_snapshot(snapshot.id), Err(DbError::NotFound)));
+    }
+
+    #[test]
+    fn test_capture_repository_without_commits() {
+        let work = TempDir::new().unwrap();
+        let data = TempDir::new().unwrap();
+        Repository::init(work.path()).unwrap();
+        fs::write(work.path().join("draft.txt"), "first").unwrap();
+
+        let db = Database::in_memory().unwrap();
+        let repo = db.insert_repo(&work.path().to_string_lossy(), "repo").unwrap();
+        let session = db.insert_session(repo.id, None, Orchestrator::Ralph).unwrap();
+        let store = SnapshotStore::in_data_dir(data.path());
+        let snapshot = store
+            .capture(&db, session.id, repo.id, work.path(), SnapshotReason::PreRun)
+            .unwrap();
+        assert_eq!(snapshot.head_commit, None);
+
+        fs::write(work.path().join("draft.txt"), "second").unwrap();
+        fs::write(work.path().join("other.txt"), "extra").unwrap();
+        store.restore(&db, &snapshot, work.path()).unwrap();
+        assert_eq!(fs::read_to_string(work.path().join("draft.txt")).unwrap(), "first");
+        assert!(!work.path().join("other.txt").exists());
+    }
+}
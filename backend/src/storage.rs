@@ -0,0 +1,144 @@
+//! Disk usage of the clone root
+//!
+//! Repositories cloned through Ralphtown all live under one directory (the
+//! clone root). An optional quota caps its total size: a clone is refused up
+//! front when the root is already full, and removed again if it pushed the
+//! root over the quota.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::settings::StorageSettings;
+
+/// Current usage of the clone root
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CloneRootUsage {
+    pub path: String,
+    pub used_bytes: u64,
+    /// Configured limit; null when unlimited
+    pub quota_bytes: Option<u64>,
+    /// Space left under the quota; null when unlimited
+    pub remaining_bytes: Option<u64>,
+}
+
+/// A clone that doesn't fit within the quota
+#[derive(Debug, Error)]
+#[error("Clone storage quota exceeded: {} of {} MiB used", used_bytes / 1024 / 1024, quota_bytes / 1024 / 1024)]
+pub struct QuotaExceeded {
+    pub used_bytes: u64,
+    pub quota_bytes: u64,
+}
+
+impl CloneRootUsage {
+    /// Measure the clone root described by `settings`
+    pub fn measure(root: &Path, settings: &StorageSettings) -> io::Result<Self> {
+        let used_bytes = dir_size(root)?;
+        Ok(Self {
+            path: root.to_string_lossy().to_string(),
+            used_bytes,
+            quota_bytes: settings.clone_quota_bytes,
+            remaining_bytes: settings
+                .clone_quota_bytes
+                .map(|quota| quota.saturating_sub(used_bytes)),
+        })
+    }
+
+    /// Fail if there is no room left for another clone
+    pub fn check_room(&self) -> Result<(), QuotaExceeded> {
+        self.check(|used, quota| used >= quota)
+    }
+
+    /// Fail if usage is over the quota, e.g. after a clone finished
+    pub fn check_within(&self) -> Result<(), QuotaExceeded> {
+        self.check(|used, quota| used > quota)
+    }
+
+    fn check(&self, exceeded: impl Fn(u64, u64) -> bool) -> Result<(), QuotaExceeded> {
+        match self.quota_bytes {
+            Some(quota_bytes) if exceeded(self.used_bytes, quota_bytes) => Err(QuotaExceeded {
+                used_bytes: self.used_bytes,
+                quota_bytes,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl QuotaExceeded {
+    /// What the user can do about it
+    pub fn help_steps(&self) -> Vec<String> {
+        vec![
+            "Delete repositories you no longer need from the clone directory".to_string(),
+            format!(
+                "Or raise the limit with PUT /api/config/{} and restart Ralphtown",
+                crate::settings::CLONE_QUOTA_MB_KEY
+            ),
+        ]
+    }
+}
+
+/// Total size of the regular files under `path`, without following symlinks
+///
+/// A missing directory counts as empty.
+pub fn dir_size(path: &Path) -> io::Result<u64> {
+    let entries = match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+
+    let mut total = 0;
+    for entry in entries {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            total += dir_size(&entry.path())?;
+        } else if file_type.is_file() {
+            total += entry.metadata()?.len();
+        }
+    }
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_usage_and_quota() {
+        let root = TempDir::new().unwrap();
+        fs::create_dir_all(root.path().join("repo/.git")).unwrap();
+        fs::write(root.path().join("repo/README.md"), vec![b'x'; 600]).unwrap();
+        fs::write(root.path().join("repo/.git/HEAD"), vec![b'x'; 400]).unwrap();
+
+        let settings = StorageSettings {
+            clone_root: Some(root.path().to_path_buf()),
+            clone_quota_bytes: Some(1500),
+        };
+        let usage = CloneRootUsage::measure(root.path(), &settings).unwrap();
+        assert_eq!(usage.used_bytes, 1000);
+        assert_eq!(usage.remaining_bytes, Some(500));
+        assert!(usage.check_room().is_ok());
+
+        fs::write(root.path().join("other.bin"), vec![b'x'; 500]).unwrap();
+        let usage = CloneRootUsage::measure(root.path(), &settings).unwrap();
+        assert_eq!(usage.remaining_bytes, Some(0));
+        assert!(usage.check_room().is_err());
+        assert!(usage.check_within().is_ok());
+
+        let unlimited = StorageSettings {
+            clone_quota_bytes: None,
+            ..settings
+        };
+        let usage = CloneRootUsage::measure(root.path(), &unlimited).unwrap();
+        assert_eq!(usage.remaining_bytes, None);
+        assert!(usage.check_room().is_ok());
+
+        assert_eq!(dir_size(&root.path().join("missing")).unwrap(), 0);
+    }
+}
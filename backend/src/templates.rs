@@ -0,0 +1,215 @@
+//! Project templates
+//!
+//! New projects start from a template: its files are copied into a fresh
+//! directory that then becomes a git repository with a single commit. Besides
+//! the built-in `empty` template (just a README), every subdirectory of the
+//! `templates` directory in the server's data directory is a template named
+//! after it. A template may describe itself in a `template.json` file, which
+//! isn't copied:
+//!
+//! ```json
+//! { "description": "Axum API with a health check", "prompt": "Add a /users endpoint" }
+//! ```
+//!
+//! The prompt is used for the project's first session when none is given.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Id of the template that's always available
+pub const EMPTY_TEMPLATE: &str = "empty";
+
+/// File describing a template, left out of projects created from it
+const TEMPLATE_FILE: &str = "template.json";
+
+/// Template errors
+#[derive(Debug, Error)]
+pub enum TemplateError {
+    #[error("Template not found: {0}")]
+    NotFound(String),
+
+    #[error("Template '{0}' has an invalid template.json: {1}")]
+    Invalid(String, String),
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+pub type TemplateResult<T> = Result<T, TemplateError>;
+
+/// A template new projects can be created from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Template {
+    pub id: String,
+    pub description: Option<String>,
+    /// Prompt for the project's first session
+    pub prompt: Option<String>,
+    /// Whether the template is built in rather than read from the templates directory
+    pub builtin: bool,
+}
+
+/// Description as written in `template.json`
+#[derive(Debug, Default, Deserialize)]
+struct TemplateFile {
+    description: Option<String>,
+    prompt: Option<String>,
+}
+
+/// Templates stored under a directory, one subdirectory per template
+#[derive(Debug, Clone)]
+pub struct TemplateStore {
+    root: Option<PathBuf>,
+}
+
+impl TemplateStore {
+    /// Read templates from `root`; only the built-in template exists without one
+    pub fn new(root: Option<PathBuf>) -> Self {
+        Self { root }
+    }
+
+    /// Read templates from the `templates` directory of the server's data directory
+    pub fn in_data_dir(data_dir: Option<&Path>) -> Self {
+        Self::new(data_dir.map(|dir| dir.join("templates")))
+    }
+
+    fn empty() -> Template {
+        Template {
+            id: EMPTY_TEMPLATE.to_string(),
+            description: Some("A README and nothing else".to_string()),
+            prompt: None,
+            builtin: true,
+        }
+    }
+
+    /// Every template, built-in first and the rest by id
+    pub fn list(&self) -> TemplateResult<Vec<Template>> {
+        let mut templates = Vec::new();
+        if let Some(root) = self.root.as_deref().filter(|root| root.is_dir()) {
+            for entry in fs::read_dir(root)? {
+                let entry = entry?;
+                let Some(id) = entry.file_name().to_str().map(String::from) else {
+                    continue;
+                };
+                if id.starts_with('.') || id == EMPTY_TEMPLATE || !entry.file_type()?.is_dir() {
+                    continue;
+                }
+                templates.push(Self::read(&id, &entry.path())?);
+            }
+        }
+        templates.sort_by(|a, b| a.id.cmp(&b.id));
+        templates.insert(0, Self::empty());
+        Ok(templates)
+    }
+
+    /// A template by id
+    pub fn get(&self, id: &str) -> TemplateResult<Template> {
+        if id == EMPTY_TEMPLATE {
+            return Ok(Self::empty());
+        }
+        let dir = self.dir(id)?;
+        Self::read(id, &dir)
+    }
+
+    /// Directory of a template in the templates directory
+    fn dir(&self, id: &str) -> TemplateResult<PathBuf> {
+        let not_found = || TemplateError::NotFound(id.to_string());
+        // Ids are plain directory names; anything else could reach outside the root
+        if id.starts_with('.') || Path::new(id).file_name() != Some(std::ffi::OsStr::new(id)) {
+            return Err(not_found());
+        }
+        let dir = self.root.as_ref().ok_or_else(not_found)?.join(id);
+        if !dir.is_dir() {
+            return Err(not_found());
+        }
+        Ok(dir)
+    }
+
+    fn read(id: &str, dir: &Path) -> TemplateResult<Template> {
+        let file = match fs::read_to_string(dir.join(TEMPLATE_FILE)) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .map_err(|e| TemplateError::Invalid(id.to_string(), e.to_string()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => TemplateFile::default(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Template {
+            id: id.to_string(),
+            description: file.description,
+            prompt: file.prompt,
+            builtin: false,
+        })
+    }
+
+    /// Write a template's files into `dest`, which must not exist yet
+    ///
+    /// `name` is the project's name, used as the built-in README's title.
+    pub fn apply(&self, id: &str, name: &str, dest: &Path) -> TemplateResult<()> {
+        if id == EMPTY_TEMPLATE {
+            fs::create_dir_all(dest)?;
+            fs::write(dest.join("README.md"), format!("# {}\n", name))?;
+            return Ok(());
+        }
+        let source = self.dir(id)?;
+        copy_dir(&source, dest, true)
+    }
+}
+
+/// Copy a directory tree, leaving out `.git` and, at the top, `template.json`
+///
+/// Symlinks are skipped so a template can't pull in files from elsewhere.
+fn copy_dir(source: &Path, dest: &Path, top: bool) -> TemplateResult<()> {
+    fs::create_dir_all(dest)?;
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        if name == ".git" || (top && name == TEMPLATE_FILE) {
+            continue;
+        }
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            copy_dir(&entry.path(), &dest.join(&name), false)?;
+        } else if file_type.is_file() {
+            fs::copy(entry.path(), dest.join(&name))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_templates() {
+        let data = TempDir::new().unwrap();
+        let root = data.path().join("templates");
+        fs::create_dir_all(root.join("api/src")).unwrap();
+        fs::write(root.join("api/src/main.rs"), "fn main() {}\n").unwrap();
+        fs::write(
+            root.join("api/template.json"),
+            r#"{ "description": "An API", "prompt": "Add a health check" }"#,
+        )
+        .unwrap();
+        fs::create_dir_all(root.join("api/.git")).unwrap();
+        fs::create_dir_all(root.join("bare")).unwrap();
+
+        let store = TemplateStore::in_data_dir(Some(data.path()));
+        let ids: Vec<String> = store.list().unwrap().into_iter().map(|t| t.id).collect();
+        assert_eq!(ids, ["empty", "api", "bare"]);
+        assert_eq!(store.get("api").unwrap().prompt.as_deref(), Some("Add a health check"));
+        assert!(matches!(store.get("../templates"), Err(TemplateError::NotFound(_))));
+
+        let dest = data.path().join("project");
+        store.apply("api", "project", &dest).unwrap();
+        assert!(dest.join("src/main.rs").exists());
+        assert!(!dest.join("template.json").exists());
+        assert!(!dest.join(".git").exists());
+
+        let dest = data.path().join("plain");
+        TemplateStore::new(None).apply(EMPTY_TEMPLATE, "plain", &dest).unwrap();
+        assert_eq!(fs::read_to_string(dest.join("README.md")).unwrap(), "# plain\n");
+    }
+}
@@ -0,0 +1,309 @@
+//! Web terminals
+//!
+//! A terminal is the user's shell running in a pseudo-terminal with a
+//! repository as its working directory. Output is kept in a short scrollback
+//! and fanned out to every attached WebSocket, so a page reload picks up where
+//! it left off. Terminals live until their shell exits or they are closed;
+//! they do not survive a server restart.
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+use portable_pty::{native_pty_system, ChildKiller, CommandBuilder, MasterPty, PtySize};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+/// Most terminals open at once across all users
+pub const MAX_TERMINALS: usize = 8;
+
+/// Bytes of recent output replayed to a newly attached socket
+const SCROLLBACK_BYTES: usize = 64 * 1024;
+
+/// Terminal errors
+#[derive(Debug, Error)]
+pub enum TerminalError {
+    #[error("Failed to start terminal: {0}")]
+    Spawn(String),
+
+    #[error("Too many open terminals (at most {MAX_TERMINALS}); close one first")]
+    TooMany,
+
+    #[error("Terminal I/O failed: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Something that happened in a terminal
+#[derive(Debug, Clone)]
+pub enum TerminalEvent {
+    /// Bytes the shell wrote, including ANSI escape sequences
+    Output(Vec<u8>),
+    /// The shell exited; `None` if its status couldn't be read
+    Exited(Option<u32>),
+}
+
+/// Public description of a terminal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerminalInfo {
+    pub id: Uuid,
+    pub repo_id: Uuid,
+    pub owner_id: Option<Uuid>,
+    pub cols: u16,
+    pub rows: u16,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Output shared between the reader thread and attaching sockets
+struct Output {
+    scrollback: Vec<u8>,
+    events: broadcast::Sender<TerminalEvent>,
+    exit: Option<Option<u32>>,
+}
+
+/// A running terminal
+pub struct Terminal {
+    info: Mutex<TerminalInfo>,
+    master: Mutex<Box<dyn MasterPty + Send>>,
+    writer: Mutex<Box<dyn Write + Send>>,
+    killer: Mutex<Box<dyn ChildKiller + Send + Sync>>,
+    output: Mutex<Output>,
+}
+
+impl Terminal {
+    pub fn info(&self) -> TerminalInfo {
+        self.info.lock().unwrap().clone()
+    }
+
+    /// Send keystrokes to the shell
+    pub fn write(&self, input: &[u8]) -> Result<(), TerminalError> {
+        let mut writer = self.writer.lock().unwrap();
+        writer.write_all(input)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Change the size the shell sees
+    pub fn resize(&self, cols: u16, rows: u16) -> Result<(), TerminalError> {
+        self.master
+            .lock()
+            .unwrap()
+            .resize(pty_size(cols, rows))
+            .map_err(|e| TerminalError::Io(std::io::Error::other(e.to_string())))?;
+        let mut info = self.info.lock().unwrap();
+        info.cols = cols;
+        info.rows = rows;
+        Ok(())
+    }
+
+    /// Recent output followed by a stream of everything after it
+    ///
+    /// If the shell already exited, its exit is the first event received.
+    pub fn attach(&self) -> (Vec<u8>, broadcast::Receiver<TerminalEvent>) {
+        let output = self.output.lock().unwrap();
+        let receiver = output.events.subscribe();
+        if let Some(code) = output.exit {
+            let _ = output.events.send(TerminalEvent::Exited(code));
+        }
+        (output.scrollback.clone(), receiver)
+    }
+
+    fn kill(&self) {
+        if let Err(e) = self.killer.lock().unwrap().kill() {
+            tracing::debug!("Failed to kill terminal shell: {}", e);
+        }
+    }
+}
+
+fn spawn_error(e: impl std::fmt::Display) -> TerminalError {
+    TerminalError::Spawn(e.to_string())
+}
+
+fn pty_size(cols: u16, rows: u16) -> PtySize {
+    PtySize {
+        rows,
+        cols,
+        pixel_width: 0,
+        pixel_height: 0,
+    }
+}
+
+/// Tracks the open terminals
+#[derive(Clone, Default)]
+pub struct TerminalManager {
+    terminals: Arc<Mutex<HashMap<Uuid, Arc<Terminal>>>>,
+}
+
+impl TerminalManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start the user's shell in `repo_path`
+    pub fn spawn(
+        &self,
+        repo_id: Uuid,
+        repo_path: &Path,
+        owner_id: Option<Uuid>,
+        cols: u16,
+        rows: u16,
+    ) -> Result<TerminalInfo, TerminalError> {
+        if self.terminals.lock().unwrap().len() >= MAX_TERMINALS {
+            return Err(TerminalError::TooMany);
+        }
+
+        let pair = native_pty_system()
+            .openpty(pty_size(cols, rows))
+            .map_err(spawn_error)?;
+        let mut cmd = CommandBuilder::new_default_prog();
+        cmd.cwd(repo_path);
+        cmd.env("TERM", "xterm-256color");
+        let mut child = pair.slave.spawn_command(cmd).map_err(spawn_error)?;
+        // The shell holds its own end; keeping ours would hide its exit from the reader
+        drop(pair.slave);
+        let mut reader = pair.master.try_clone_reader().map_err(spawn_error)?;
+        let writer = pair.master.take_writer().map_err(spawn_error)?;
+
+        let info = TerminalInfo {
+            id: Uuid::new_v4(),
+            repo_id,
+            owner_id,
+            cols,
+            rows,
+            created_at: Utc::now(),
+        };
+        let (events, _) = broadcast::channel(256);
+        let terminal = Arc::new(Terminal {
+            info: Mutex::new(info.clone()),
+            master: Mutex::new(pair.master),
+            writer: Mutex::new(writer),
+            killer: Mutex::new(child.clone_killer()),
+            output: Mutex::new(Output {
+                scrollback: Vec::new(),
+                events,
+                exit: None,
+            }),
+        });
+        self.terminals
+            .lock()
+            .unwrap()
+            .insert(info.id, terminal.clone());
+
+        // The PTY only offers blocking reads
+        let terminals = self.terminals.clone();
+        let id = info.id;
+        std::thread::spawn(move || {
+            let mut buf = [0u8; 8192];
+            loop {
+                match reader.read(&mut buf) {
+                    Ok(0) | Err(_) => break,
+                    Ok(read) => {
+                        let mut output = terminal.output.lock().unwrap();
+                        output.scrollback.extend_from_slice(&buf[..read]);
+                        let excess = output.scrollback.len().saturating_sub(SCROLLBACK_BYTES);
+                        output.scrollback.drain(..excess);
+                        let _ = output.events.send(TerminalEvent::Output(buf[..read].to_vec()));
+                    }
+                }
+            }
+
+            let code = child.wait().ok().map(|status| status.exit_code());
+            let mut output = terminal.output.lock().unwrap();
+            output.exit = Some(code);
+            let _ = output.events.send(TerminalEvent::Exited(code));
+            drop(output);
+            terminals.lock().unwrap().remove(&id);
+            tracing::info!("Terminal {} exited with {:?}", id, code);
+        });
+
+        Ok(info)
+    }
+
+    /// An open terminal by ID
+    pub fn get(&self, id: Uuid) -> Option<Arc<Terminal>> {
+        self.terminals.lock().unwrap().get(&id).cloned()
+    }
+
+    /// Every open terminal, oldest first
+    pub fn list(&self) -> Vec<TerminalInfo> {
+        let mut terminals: Vec<_> = self
+            .terminals
+            .lock()
+            .unwrap()
+            .values()
+            .map(|terminal| terminal.info())
+            .collect();
+        terminals.sort_by_key(|info| info.created_at);
+        terminals
+    }
+
+    /// Kill a terminal's shell; it is removed once the shell has exited
+    pub fn close(&self, id: Uuid) -> bool {
+        match self.get(id) {
+            Some(terminal) => {
+                terminal.kill();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    async fn read_until(
+        receiver: &mut broadcast::Receiver<TerminalEvent>,
+        seen: &mut String,
+        needle: &str,
+    ) {
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while !seen.contains(needle) {
+                match receiver.recv().await.unwrap() {
+                    TerminalEvent::Output(bytes) => seen.push_str(&String::from_utf8_lossy(&bytes)),
+                    TerminalEvent::Exited(_) => break,
+                }
+            }
+        })
+        .await
+        .expect("terminal output timed out");
+    }
+
+    #[tokio::test]
+    async fn test_terminal_runs_commands_in_repo() {
+        let repo = TempDir::new().unwrap();
+        std::fs::write(repo.path().join("marker.txt"), "").unwrap();
+        let manager = TerminalManager::new();
+
+        let info = manager.spawn(Uuid::new_v4(), repo.path(), None, 80, 24).unwrap();
+        let terminal = manager.get(info.id).unwrap();
+        let (_, mut receiver) = terminal.attach();
+
+        terminal.write(b"ls; echo done-$((40 + 2))\n").unwrap();
+        let mut seen = String::new();
+        read_until(&mut receiver, &mut seen, "done-42").await;
+        assert!(seen.contains("marker.txt"));
+
+        terminal.resize(100, 30).unwrap();
+        assert_eq!(manager.list()[0].cols, 100);
+
+        assert!(manager.close(info.id));
+        let exited = tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                if let TerminalEvent::Exited(_) = receiver.recv().await.unwrap() {
+                    break;
+                }
+            }
+        })
+        .await;
+        assert!(exited.is_ok());
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(manager.get(info.id).is_none());
+    }
+}
@@ -0,0 +1,175 @@
+//! HTTPS support
+//!
+//! Resolves the certificate and key to serve with, generating a self-signed
+//! certificate in the data directory on first run when none is configured.
+
+use std::path::{Path, PathBuf};
+
+use axum_server::tls_rustls::RustlsConfig;
+use thiserror::Error;
+
+use crate::settings::TlsSettings;
+
+/// Hostnames the generated self-signed certificate is valid for
+const SELF_SIGNED_NAMES: [&str; 3] = ["localhost", "127.0.0.1", "::1"];
+
+/// TLS setup errors
+#[derive(Debug, Error)]
+pub enum TlsError {
+    #[error("Failed to determine data directory")]
+    NoDataDir,
+
+    #[error("Certificate file not found: {0}")]
+    MissingFile(PathBuf),
+
+    #[error("Failed to generate self-signed certificate: {0}")]
+    Generate(String),
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Certificate and key file locations
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsPaths {
+    pub cert: PathBuf,
+    pub key: PathBuf,
+}
+
+impl TlsPaths {
+    /// Default location for the generated self-signed certificate
+    pub fn default_self_signed() -> Result<Self, TlsError> {
+        let data_dir = dirs::data_dir().ok_or(TlsError::NoDataDir)?;
+        Ok(Self::in_dir(&data_dir.join("ralphtown").join("tls")))
+    }
+
+    /// Certificate and key paths inside a directory
+    pub fn in_dir(dir: &Path) -> Self {
+        Self {
+            cert: dir.join("cert.pem"),
+            key: dir.join("key.pem"),
+        }
+    }
+}
+
+/// Resolve the certificate to serve, generating a self-signed one if needed
+///
+/// Configured paths must already exist. When no paths are configured the
+/// self-signed certificate is created once and reused on later starts.
+pub fn resolve_paths(settings: &TlsSettings) -> Result<TlsPaths, TlsError> {
+    if let (Some(cert), Some(key)) = (&settings.cert_path, &settings.key_path) {
+        let paths = TlsPaths {
+            cert: cert.clone(),
+            key: key.clone(),
+        };
+        for path in [&paths.cert, &paths.key] {
+            if !path.exists() {
+                return Err(TlsError::MissingFile(path.clone()));
+            }
+        }
+        return Ok(paths);
+    }
+
+    let paths = TlsPaths::default_self_signed()?;
+    ensure_self_signed(&paths)?;
+    Ok(paths)
+}
+
+/// Generate a self-signed certificate unless both files already exist
+///
+/// Returns true if a new certificate was written.
+pub fn ensure_self_signed(paths: &TlsPaths) -> Result<bool, TlsError> {
+    if paths.cert.exists() && paths.key.exists() {
+        return Ok(false);
+    }
+
+    let names: Vec<String> = SELF_SIGNED_NAMES.iter().map(|n| n.to_string()).collect();
+    let certified = rcgen::generate_simple_self_signed(names)
+        .map_err(|e| TlsError::Generate(e.to_string()))?;
+
+    for path in [&paths.cert, &paths.key] {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+    }
+
+    std::fs::write(&paths.cert, certified.cert.pem())?;
+    write_private(&paths.key, certified.key_pair.serialize_pem().as_bytes())?;
+
+    tracing::info!("Generated self-signed certificate at {:?}", paths.cert);
+    Ok(true)
+}
+
+/// Load a rustls server config from PEM files
+pub async fn load_config(paths: &TlsPaths) -> Result<RustlsConfig, TlsError> {
+    // Only the ring provider is compiled in; installing twice is harmless
+    let _ = rustls::crypto::ring::default_provider().install_default();
+
+    Ok(RustlsConfig::from_pem_file(&paths.cert, &paths.key).await?)
+}
+
+/// Write a file readable only by the current user
+fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::io::Write;
+        use std::os::unix::fs::OpenOptionsExt;
+
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(path)?;
+        file.write_all(contents)
+    }
+
+    #[cfg(not(unix))]
+    {
+        std::fs::write(path, contents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_generates_self_signed_once() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let paths = TlsPaths::in_dir(&temp_dir.path().join("tls"));
+
+        assert!(ensure_self_signed(&paths).expect("Failed to generate certificate"));
+        let cert = std::fs::read_to_string(&paths.cert).expect("Failed to read cert");
+        assert!(cert.contains("BEGIN CERTIFICATE"));
+        let key = std::fs::read_to_string(&paths.key).expect("Failed to read key");
+        assert!(key.contains("PRIVATE KEY"));
+
+        // Second call reuses the existing files
+        assert!(!ensure_self_signed(&paths).expect("Failed to check certificate"));
+        assert_eq!(std::fs::read_to_string(&paths.cert).unwrap(), cert);
+    }
+
+    #[test]
+    fn test_configured_paths_must_exist() {
+        let settings = TlsSettings {
+            enabled: true,
+            cert_path: Some(PathBuf::from("/nonexistent/cert.pem")),
+            key_path: Some(PathBuf::from("/nonexistent/key.pem")),
+            self_signed: false,
+        };
+
+        let result = resolve_paths(&settings);
+        assert!(matches!(result, Err(TlsError::MissingFile(_))));
+    }
+
+    #[tokio::test]
+    async fn test_load_generated_config() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let paths = TlsPaths::in_dir(temp_dir.path());
+        ensure_self_signed(&paths).expect("Failed to generate certificate");
+
+        assert!(load_config(&paths).await.is_ok());
+    }
+}
@@ -0,0 +1,109 @@
+//! Uploaded prompt and context files
+//!
+//! Prompts and the documents they refer to can be too large to send inline
+//! in a run request, and too large for a command line. They're uploaded
+//! first, streamed to `uploads/<id>/<filename>` in the data directory, and
+//! then referenced by id: a run can take its prompt from an upload, and list
+//! other uploads as context files the agent reads from disk.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::db::models::Upload;
+
+/// Name used when an upload didn't give a usable one
+const DEFAULT_FILENAME: &str = "upload";
+
+/// Longest file name kept, in characters
+const MAX_FILENAME_CHARS: usize = 200;
+
+/// Upload errors
+#[derive(Debug, Error)]
+pub enum UploadError {
+    #[error("Upload {0} is not UTF-8 text")]
+    NotText(Uuid),
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+pub type UploadResult<T> = Result<T, UploadError>;
+
+/// Uploaded files under a directory, one subdirectory per upload
+#[derive(Debug, Clone)]
+pub struct UploadStore {
+    root: PathBuf,
+}
+
+impl UploadStore {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    /// Store uploads in the `uploads` directory of the server's data directory
+    pub fn in_data_dir(data_dir: &Path) -> Self {
+        Self::new(data_dir.join("uploads"))
+    }
+
+    /// Directory holding an upload's file
+    pub fn dir(&self, id: Uuid) -> PathBuf {
+        self.root.join(id.to_string())
+    }
+
+    /// Where an upload's file is stored
+    pub fn path(&self, upload: &Upload) -> PathBuf {
+        self.dir(upload.id).join(&upload.filename)
+    }
+
+    /// An upload's contents as text
+    pub fn read_text(&self, upload: &Upload) -> UploadResult<String> {
+        let bytes = fs::read(self.path(upload))?;
+        String::from_utf8(bytes).map_err(|_| UploadError::NotText(upload.id))
+    }
+
+    /// Delete an upload's file
+    pub fn remove(&self, id: Uuid) -> UploadResult<()> {
+        match fs::remove_dir_all(self.dir(id)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Reduce a client-supplied file name to a plain, safe file name
+///
+/// Directories are dropped (browsers on Windows may send full paths), as are
+/// control characters; names that are empty or only dots are replaced.
+pub fn safe_filename(name: Option<&str>) -> String {
+    let name = name.unwrap_or_default();
+    let name = name.rsplit(['/', '\\']).next().unwrap_or_default();
+    let name: String = name
+        .chars()
+        .filter(|c| !c.is_control())
+        .take(MAX_FILENAME_CHARS)
+        .collect();
+    let name = name.trim();
+    if name.is_empty() || name.chars().all(|c| c == '.') {
+        DEFAULT_FILENAME.to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_safe_filename() {
+        assert_eq!(safe_filename(Some("spec.md")), "spec.md");
+        assert_eq!(safe_filename(Some("../../etc/passwd")), "passwd");
+        assert_eq!(safe_filename(Some(r"C:\Users\me\notes.txt")), "notes.txt");
+        assert_eq!(safe_filename(Some("a\nb.txt")), "ab.txt");
+        assert_eq!(safe_filename(Some("..")), DEFAULT_FILENAME);
+        assert_eq!(safe_filename(None), DEFAULT_FILENAME);
+    }
+}
@@ -0,0 +1,200 @@
+//! Users and ownership rules
+//!
+//! The configured `auth_token` acts as a built-in administrator. Additional
+//! users get their own API tokens (stored only as SHA-256 hashes) and a role:
+//! admins see and manage everything, members manage their own repos and
+//! sessions, and viewers can read everything but change nothing. Resources
+//! without an owner are shared with every user.
+
+use axum::{extract::FromRequestParts, http::request::Parts};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::db::models::{User, UserRole};
+use crate::error::{AppError, AppResult};
+
+/// The user a request is made on behalf of
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CurrentUser {
+    /// `None` for the built-in administrator (the `auth_token`, or no auth at all)
+    pub id: Option<Uuid>,
+    pub username: String,
+    pub role: UserRole,
+}
+
+impl CurrentUser {
+    /// The built-in administrator
+    pub fn root() -> Self {
+        Self {
+            id: None,
+            username: "admin".to_string(),
+            role: UserRole::Admin,
+        }
+    }
+
+    pub fn is_admin(&self) -> bool {
+        self.role == UserRole::Admin
+    }
+
+    /// Whether the user may make any changes at all
+    pub fn can_write(&self) -> bool {
+        self.role != UserRole::Viewer
+    }
+
+    /// Whether a resource with this owner is visible to the user
+    pub fn can_view(&self, owner_id: Option<Uuid>) -> bool {
+        match self.role {
+            UserRole::Admin | UserRole::Viewer => true,
+            UserRole::Member => owner_id.is_none() || owner_id == self.id,
+        }
+    }
+
+    /// The owner a query has to be limited to, besides shared resources, to
+    /// return only what [`Self::can_view`] allows; `None` when that's everything
+    pub fn visible_owner(&self) -> Option<Uuid> {
+        match self.role {
+            UserRole::Admin | UserRole::Viewer => None,
+            // A member without an id owns nothing, which the nil UUID matches
+            UserRole::Member => Some(self.id.unwrap_or_default()),
+        }
+    }
+
+    /// Whether a resource with this owner may be changed by the user
+    pub fn can_modify(&self, owner_id: Option<Uuid>) -> bool {
+        match self.role {
+            UserRole::Admin => true,
+            UserRole::Member => owner_id.is_some() && owner_id == self.id,
+            UserRole::Viewer => false,
+        }
+    }
+
+    /// Fail with 404 unless the resource is visible, so existence isn't leaked
+    pub fn check_view(&self, owner_id: Option<Uuid>, what: &str) -> AppResult<()> {
+        if self.can_view(owner_id) {
+            Ok(())
+        } else {
+            Err(AppError::NotFound(format!("{} not found", what)))
+        }
+    }
+
+    /// Fail unless the resource may be changed by the user
+    pub fn check_modify(&self, owner_id: Option<Uuid>, what: &str) -> AppResult<()> {
+        self.check_view(owner_id, what)?;
+        if self.can_modify(owner_id) {
+            Ok(())
+        } else {
+            Err(AppError::Forbidden(format!(
+                "{} belongs to another user",
+                what
+            )))
+        }
+    }
+
+    /// Fail unless the user is an administrator
+    pub fn require_admin(&self) -> AppResult<()> {
+        if self.is_admin() {
+            Ok(())
+        } else {
+            Err(AppError::Forbidden(
+                "Administrator access required".to_string(),
+            ))
+        }
+    }
+}
+
+impl From<User> for CurrentUser {
+    fn from(user: User) -> Self {
+        Self {
+            id: Some(user.id),
+            username: user.username,
+            role: user.role,
+        }
+    }
+}
+
+/// Read the user set by the auth middleware
+///
+/// Routers mounted without the middleware (unit tests) act as the built-in
+/// administrator, matching a server with authentication disabled.
+impl<S: Send + Sync> FromRequestParts<S> for CurrentUser {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(parts
+            .extensions
+            .get::<CurrentUser>()
+            .cloned()
+            .unwrap_or_else(CurrentUser::root))
+    }
+}
+
+/// Generate a new random API token
+pub fn generate_token() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+/// Hash an API token for storage and lookup
+pub fn hash_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(role: UserRole) -> CurrentUser {
+        CurrentUser {
+            id: Some(Uuid::new_v4()),
+            username: "user".to_string(),
+            role,
+        }
+    }
+
+    #[test]
+    fn test_member_sees_own_and_shared() {
+        let member = user(UserRole::Member);
+        let other = Some(Uuid::new_v4());
+
+        assert!(member.can_view(None));
+        assert!(member.can_view(member.id));
+        assert!(!member.can_view(other));
+        assert_eq!(member.visible_owner(), member.id);
+
+        assert!(member.can_modify(member.id));
+        assert!(!member.can_modify(None));
+        assert!(!member.can_modify(other));
+    }
+
+    #[test]
+    fn test_viewer_reads_everything_changes_nothing() {
+        let viewer = user(UserRole::Viewer);
+
+        assert!(viewer.can_view(Some(Uuid::new_v4())));
+        assert_eq!(viewer.visible_owner(), None);
+        assert!(!viewer.can_modify(viewer.id));
+        assert!(!viewer.can_write());
+    }
+
+    #[test]
+    fn test_check_view_hides_existence() {
+        let member = user(UserRole::Member);
+
+        let result = member.check_modify(Some(Uuid::new_v4()), "Session");
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+
+        let result = member.check_modify(None, "Session");
+        assert!(matches!(result, Err(AppError::Forbidden(_))));
+    }
+
+    #[test]
+    fn test_hash_token() {
+        let token = generate_token();
+        assert_eq!(token.len(), 64);
+        assert_eq!(hash_token(&token), hash_token(&token));
+        assert_eq!(hash_token(&token).len(), 64);
+        assert_ne!(hash_token(&token), hash_token(&generate_token()));
+    }
+}
@@ -0,0 +1,373 @@
+//! Outgoing webhooks
+//!
+//! Lifecycle events (session status changes, finished clones, pushes) are
+//! POSTed as JSON to every enabled webhook subscribed to them. Each request is
+//! signed with HMAC-SHA256 over the body using the webhook's secret, retried
+//! with exponential backoff, and recorded in the delivery log so failures can
+//! be inspected and replayed.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use hmac::{Hmac, KeyInit, Mac};
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::db::models::{DeliveryStatus, Webhook, WebhookDelivery};
+use crate::db::{Database, DbResult};
+
+/// Header carrying `sha256=<hex HMAC of the body>`
+pub const SIGNATURE_HEADER: &str = "x-ralphtown-signature";
+/// Header carrying the event type
+pub const EVENT_HEADER: &str = "x-ralphtown-event";
+/// Header carrying the delivery ID
+pub const DELIVERY_HEADER: &str = "x-ralphtown-delivery";
+
+/// How long a single delivery attempt may take
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Lifecycle events that can be delivered to webhooks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventType {
+    /// A session started, finished, failed, or was cancelled
+    SessionStatus,
+    /// A repository finished cloning
+    CloneCompleted,
+    /// Commits were pushed from a session's repository
+    GitPush,
+}
+
+impl EventType {
+    pub const ALL: [EventType; 3] = [
+        EventType::SessionStatus,
+        EventType::CloneCompleted,
+        EventType::GitPush,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventType::SessionStatus => "session.status",
+            EventType::CloneCompleted => "clone.completed",
+            EventType::GitPush => "git.push",
+        }
+    }
+
+    /// Whether `name` is a known event type
+    pub fn is_valid(name: &str) -> bool {
+        Self::ALL.iter().any(|event| event.as_str() == name)
+    }
+}
+
+/// How failed deliveries are retried
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Total attempts, including the first
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubled for each retry after that
+    pub base_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            base_delay: Duration::from_secs(2),
+        }
+    }
+}
+
+/// Delivers events to the configured webhooks
+#[derive(Clone)]
+pub struct WebhookDispatcher {
+    db: Arc<Database>,
+    client: reqwest::Client,
+    retry: RetryPolicy,
+}
+
+impl WebhookDispatcher {
+    pub fn new(db: Arc<Database>) -> Self {
+        Self {
+            db,
+            client: reqwest::Client::new(),
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    /// Use a different retry policy
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Send an event to every webhook subscribed to it, in the background
+    pub async fn emit(&self, event: EventType, data: serde_json::Value) {
+        let webhooks = match self.db.call(|db| db.list_webhooks()).await {
+            Ok(webhooks) => webhooks,
+            Err(e) => {
+                tracing::error!("Failed to load webhooks: {}", e);
+                return;
+            }
+        };
+
+        let payload = serde_json::json!({
+            "event": event.as_str(),
+            "timestamp": Utc::now(),
+            "data": data,
+        });
+
+        for webhook in webhooks
+            .into_iter()
+            .filter(|webhook| webhook.accepts(event.as_str()))
+        {
+            let (webhook_id, payload) = (webhook.id, payload.clone());
+            match self
+                .db
+                .call(move |db| db.insert_webhook_delivery(webhook_id, event.as_str(), &payload))
+                .await
+            {
+                Ok(delivery) => self.spawn_delivery(webhook, delivery),
+                Err(e) => tracing::error!("Failed to record webhook delivery: {}", e),
+            }
+        }
+    }
+
+    /// Send a logged delivery's payload again, as a new delivery
+    pub async fn replay(&self, delivery_id: Uuid) -> DbResult<WebhookDelivery> {
+        let (webhook, delivery) = self
+            .db
+            .call(move |db| {
+                let original = db.get_webhook_delivery(delivery_id)?;
+                let webhook = db.get_webhook(original.webhook_id)?;
+                let delivery =
+                    db.insert_webhook_delivery(webhook.id, &original.event, &original.payload)?;
+                Ok((webhook, delivery))
+            })
+            .await?;
+
+        self.spawn_delivery(webhook, delivery.clone());
+        Ok(delivery)
+    }
+
+    fn spawn_delivery(&self, webhook: Webhook, delivery: WebhookDelivery) {
+        let dispatcher = self.clone();
+        tokio::spawn(async move { dispatcher.deliver(webhook, delivery).await });
+    }
+
+    /// Attempt a delivery until it succeeds or runs out of retries
+    async fn deliver(&self, webhook: Webhook, delivery: WebhookDelivery) {
+        let body = delivery.payload.to_string();
+        let signature = sign(&webhook.secret, body.as_bytes());
+
+        for attempt in 1..=self.retry.max_attempts {
+            let result = self
+                .client
+                .post(&webhook.url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header(EVENT_HEADER, &delivery.event)
+                .header(DELIVERY_HEADER, delivery.id.to_string())
+                .header(SIGNATURE_HEADER, &signature)
+                .body(body.clone())
+                .timeout(REQUEST_TIMEOUT)
+                .send()
+                .await;
+
+            let (response_status, error) = match result {
+                Ok(response) if response.status().is_success() => {
+                    self.record(
+                        delivery.id,
+                        DeliveryStatus::Delivered,
+                        attempt,
+                        Some(response.status().as_u16()),
+                        None,
+                    )
+                    .await;
+                    return;
+                }
+                Ok(response) => (
+                    Some(response.status().as_u16()),
+                    format!("Endpoint responded with {}", response.status()),
+                ),
+                Err(e) => (None, e.to_string()),
+            };
+
+            if attempt == self.retry.max_attempts {
+                tracing::warn!(
+                    "Webhook delivery {} to {} failed after {} attempts: {}",
+                    delivery.id,
+                    webhook.url,
+                    attempt,
+                    error
+                );
+                self.record(
+                    delivery.id,
+                    DeliveryStatus::Failed,
+                    attempt,
+                    response_status,
+                    Some(&error),
+                )
+                .await;
+                return;
+            }
+
+            self.record(
+                delivery.id,
+                DeliveryStatus::Pending,
+                attempt,
+                response_status,
+                Some(&error),
+            )
+            .await;
+            tokio::time::sleep(self.retry.base_delay * 2u32.pow(attempt - 1)).await;
+        }
+    }
+
+    async fn record(
+        &self,
+        id: Uuid,
+        status: DeliveryStatus,
+        attempts: u32,
+        response_status: Option<u16>,
+        error: Option<&str>,
+    ) {
+        let error = error.map(String::from);
+        if let Err(e) = self
+            .db
+            .call(move |db| {
+                db.update_webhook_delivery(id, status, attempts, response_status, error.as_deref())
+            })
+            .await
+        {
+            tracing::error!("Failed to update webhook delivery {}: {}", id, e);
+        }
+    }
+}
+
+/// Compute the signature header value for a request body
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    let hex: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    format!("sha256={}", hex)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Bytes, http::HeaderMap, http::StatusCode, routing::post, Router};
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Start a local endpoint that fails `failures` times, then records the request
+    async fn start_endpoint(
+        failures: u32,
+    ) -> (String, tokio::sync::mpsc::Receiver<(HeaderMap, Bytes)>) {
+        let (tx, rx) = tokio::sync::mpsc::channel(8);
+        let calls = Arc::new(AtomicU32::new(0));
+        let app = Router::new().route(
+            "/hook",
+            post(move |headers: HeaderMap, body: Bytes| {
+                let tx = tx.clone();
+                let calls = calls.clone();
+                async move {
+                    if calls.fetch_add(1, Ordering::SeqCst) < failures {
+                        return StatusCode::INTERNAL_SERVER_ERROR;
+                    }
+                    tx.send((headers, body)).await.unwrap();
+                    StatusCode::OK
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        (url, rx)
+    }
+
+    fn dispatcher(db: Arc<Database>) -> WebhookDispatcher {
+        WebhookDispatcher::new(db).with_retry_policy(RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(10),
+        })
+    }
+
+    async fn wait_for_status(db: &Database, id: Uuid, status: DeliveryStatus) -> WebhookDelivery {
+        for _ in 0..200 {
+            let delivery = db.get_webhook_delivery(id).unwrap();
+            if delivery.status == status {
+                return delivery;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("delivery {} never reached {:?}", id, status);
+    }
+
+    #[test]
+    fn test_sign() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_signed_delivery_with_retries() {
+        let db = Arc::new(Database::in_memory().unwrap());
+        let (url, mut rx) = start_endpoint(1).await;
+        let webhook = db
+            .insert_webhook(&url, "s3cret", &["session.status".to_string()], true)
+            .unwrap();
+
+        dispatcher(db.clone())
+            .emit(
+                EventType::SessionStatus,
+                serde_json::json!({ "status": "completed" }),
+            )
+            .await;
+
+        let (headers, body) = rx.recv().await.unwrap();
+        assert_eq!(headers[EVENT_HEADER], "session.status");
+        assert_eq!(headers[SIGNATURE_HEADER], sign("s3cret", &body).as_str());
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["data"]["status"], "completed");
+
+        let delivery_id: Uuid = headers[DELIVERY_HEADER].to_str().unwrap().parse().unwrap();
+        let delivery = wait_for_status(&db, delivery_id, DeliveryStatus::Delivered).await;
+        assert_eq!(delivery.attempts, 2);
+        assert_eq!(delivery.webhook_id, webhook.id);
+    }
+
+    #[tokio::test]
+    async fn test_event_filter_and_replay() {
+        let db = Arc::new(Database::in_memory().unwrap());
+        let (url, mut rx) = start_endpoint(3).await;
+        let webhook = db
+            .insert_webhook(&url, "s3cret", &["git.push".to_string()], true)
+            .unwrap();
+        let dispatcher = dispatcher(db.clone());
+
+        // Filtered out
+        dispatcher.emit(EventType::CloneCompleted, serde_json::json!({})).await;
+        // Fails every attempt
+        dispatcher
+            .emit(EventType::GitPush, serde_json::json!({ "branch": "main" }))
+            .await;
+
+        let deliveries = db.list_webhook_deliveries(webhook.id, 10).unwrap();
+        assert_eq!(deliveries.len(), 1);
+        let failed = wait_for_status(&db, deliveries[0].id, DeliveryStatus::Failed).await;
+        assert_eq!(failed.attempts, 3);
+        assert_eq!(failed.response_status, Some(500));
+
+        let replayed = dispatcher.replay(failed.id).await.unwrap();
+        let (_, body) = rx.recv().await.unwrap();
+        assert_eq!(serde_json::from_slice::<serde_json::Value>(&body).unwrap(), failed.payload);
+        wait_for_status(&db, replayed.id, DeliveryStatus::Delivered).await;
+    }
+}
@@ -0,0 +1,452 @@
+//! Worker mode (`ralphtown worker`)
+//!
+//! Connects to a Ralphtown server, waits for runs assigned to it, and runs
+//! them in its own clones under the repos directory, cloning a repository from
+//! its origin the first time it's needed. Output is streamed back line by line.
+//! When the connection drops, runs in progress are stopped (the server has
+//! already marked them failed) and the worker reconnects.
+
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::{SinkExt, StreamExt};
+use tokio::io::{AsyncRead, BufReader};
+use tokio::process::Command;
+use tokio::sync::{mpsc, oneshot};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::{header, HeaderValue};
+use tokio_tungstenite::tungstenite::{Error as WsError, Message};
+use tokio_tungstenite::Connector;
+use uuid::Uuid;
+
+use crate::git::GitManager;
+use crate::ralph::{read_bounded_line, OutputLimits};
+use crate::ws::messages::OutputStream;
+
+use super::{FromWorker, ToWorker};
+
+/// Wait before reconnecting after the connection drops
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// How a worker connects and where it keeps its clones
+pub struct WorkerOptions {
+    /// Base URL of the server, including any base path
+    pub server: String,
+    pub token: Option<String>,
+    /// Name shown in the server's worker list
+    pub name: String,
+    pub repos_dir: PathBuf,
+    /// Accept the server's certificate even if it's self-signed
+    pub insecure: bool,
+}
+
+/// Why a connection ended
+enum Disconnect {
+    /// The server refused the worker; retrying won't help
+    Rejected(String),
+    Lost(String),
+}
+
+/// WebSocket URL workers connect to, given the server's base URL
+fn connect_url(server: &str) -> String {
+    let server = server.trim_end_matches('/');
+    let server = match server.split_once("://") {
+        Some(("https", rest)) => format!("wss://{}", rest),
+        Some((_, rest)) => format!("ws://{}", rest),
+        None => format!("ws://{}", server),
+    };
+    format!("{}/api/workers/connect", server)
+}
+
+/// Serve the server's runs until the server refuses the worker
+pub async fn run(options: WorkerOptions) -> Result<(), String> {
+    // Only the ring provider is compiled in; installing twice is harmless
+    let _ = rustls::crypto::ring::default_provider().install_default();
+
+    let url = connect_url(&options.server);
+    loop {
+        match serve(&options, &url).await {
+            Disconnect::Rejected(message) => return Err(message),
+            Disconnect::Lost(message) => tracing::warn!(
+                "{}; reconnecting in {}s",
+                message,
+                RECONNECT_DELAY.as_secs()
+            ),
+        }
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+/// Handle one connection to the server
+async fn serve(options: &WorkerOptions, url: &str) -> Disconnect {
+    let mut request = match url.into_client_request() {
+        Ok(request) => request,
+        Err(e) => return Disconnect::Rejected(format!("Invalid server URL {}: {}", url, e)),
+    };
+    if let Some(token) = &options.token {
+        match HeaderValue::from_str(&format!("Bearer {}", token)) {
+            Ok(value) => request.headers_mut().insert(header::AUTHORIZATION, value),
+            Err(_) => return Disconnect::Rejected("Invalid token".to_string()),
+        };
+    }
+    let connector = options
+        .insecure
+        .then(|| Connector::Rustls(Arc::new(insecure::client_config())));
+
+    let socket = match tokio_tungstenite::connect_async_tls_with_config(request, None, false, connector).await {
+        Ok((socket, _)) => socket,
+        Err(WsError::Http(response)) if matches!(response.status().as_u16(), 401 | 403) => {
+            return Disconnect::Rejected(format!(
+                "The server refused the worker ({}); workers need an administrator token",
+                response.status()
+            ));
+        }
+        Err(e) => return Disconnect::Lost(format!("Failed to connect to {}: {}", url, e)),
+    };
+    let (mut sink, mut stream) = socket.split();
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<FromWorker>();
+    let writer = tokio::spawn(async move {
+        while let Some(message) = rx.recv().await {
+            let json = match serde_json::to_string(&message) {
+                Ok(json) => json,
+                Err(e) => {
+                    tracing::error!("Failed to serialize message: {}", e);
+                    continue;
+                }
+            };
+            if sink.send(Message::text(json)).await.is_err() {
+                break;
+            }
+        }
+    });
+    let _ = tx.send(FromWorker::Hello {
+        name: options.name.clone(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+    });
+
+    // Dropping a run's sender stops it, so runs end with the connection
+    let mut runs: HashMap<Uuid, oneshot::Sender<()>> = HashMap::new();
+    let disconnect = loop {
+        let text = match stream.next().await {
+            Some(Ok(Message::Text(text))) => text,
+            Some(Ok(Message::Close(_))) | None => {
+                break Disconnect::Lost("The server closed the connection".to_string());
+            }
+            Some(Ok(_)) => continue,
+            Some(Err(e)) => break Disconnect::Lost(format!("Connection error: {}", e)),
+        };
+
+        match serde_json::from_str::<ToWorker>(text.as_str()) {
+            Ok(ToWorker::Welcome { worker_id }) => {
+                tracing::info!("Registered with {} as worker {}", options.server, worker_id);
+            }
+            Ok(ToWorker::Run {
+                session_id,
+                repo,
+                url,
+                command,
+                env,
+            }) => {
+                tracing::info!("Running session {} in {}", session_id, repo);
+                runs.retain(|_, cancel| !cancel.is_closed());
+                let (cancel_tx, cancel_rx) = oneshot::channel();
+                runs.insert(session_id, cancel_tx);
+                let assignment = Assignment {
+                    session_id,
+                    repo,
+                    url,
+                    command,
+                    env,
+                };
+                tokio::spawn(assignment.run(options.repos_dir.clone(), tx.clone(), cancel_rx));
+            }
+            Ok(ToWorker::Cancel { session_id }) => {
+                if let Some(cancel) = runs.remove(&session_id) {
+                    tracing::info!("Cancelling session {}", session_id);
+                    let _ = cancel.send(());
+                }
+            }
+            Err(e) => tracing::warn!("Ignoring unrecognized message from server: {}", e),
+        }
+    };
+
+    writer.abort();
+    disconnect
+}
+
+/// Kills a run's process group when the run is dropped before it exits
+struct ProcessGroup(Option<u32>);
+
+impl Drop for ProcessGroup {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let Some(pid) = self.0 {
+            use nix::sys::signal::{killpg, Signal};
+            use nix::unistd::Pid;
+
+            let _ = killpg(Pid::from_raw(pid as i32), Signal::SIGKILL);
+        }
+    }
+}
+
+/// A run assigned by the server
+struct Assignment {
+    session_id: Uuid,
+    repo: String,
+    url: Option<String>,
+    command: Vec<String>,
+    env: BTreeMap<String, String>,
+}
+
+impl Assignment {
+    /// Run to completion, or until cancelled, and report how it ended
+    async fn run(
+        self,
+        repos_dir: PathBuf,
+        tx: mpsc::UnboundedSender<FromWorker>,
+        cancel: oneshot::Receiver<()>,
+    ) {
+        let session_id = self.session_id;
+        // Dropping the run kills its process; the server already knows it stopped
+        let success = tokio::select! {
+            success = self.execute(&repos_dir, &tx) => success,
+            _ = cancel => return,
+        };
+        let _ = tx.send(FromWorker::Exited { session_id, success });
+    }
+
+    fn report(&self, tx: &mpsc::UnboundedSender<FromWorker>, stream: OutputStream, content: String) {
+        let _ = tx.send(FromWorker::Output {
+            session_id: self.session_id,
+            stream,
+            content,
+        });
+    }
+
+    async fn execute(&self, repos_dir: &Path, tx: &mpsc::UnboundedSender<FromWorker>) -> bool {
+        let dir = match self.checkout(repos_dir, tx).await {
+            Ok(dir) => dir,
+            Err(message) => {
+                self.report(tx, OutputStream::Stderr, message);
+                return false;
+            }
+        };
+        let Some((program, args)) = self.command.split_first() else {
+            self.report(tx, OutputStream::Stderr, "The server sent an empty command".to_string());
+            return false;
+        };
+
+        let mut command = Command::new(program);
+        command
+            .args(args)
+            .current_dir(&dir)
+            .envs(&self.env)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        // Run in its own process group so cancelling also stops what it spawned
+        #[cfg(unix)]
+        command.process_group(0);
+
+        let mut child = match command.spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                self.report(tx, OutputStream::Stderr, format!("Failed to start {}: {}", program, e));
+                return false;
+            }
+        };
+
+        let mut group = ProcessGroup(child.id());
+        let stdout = child.stdout.take().expect("stdout was configured");
+        let stderr = child.stderr.take().expect("stderr was configured");
+        tokio::join!(
+            self.forward(stdout, OutputStream::Stdout, tx),
+            self.forward(stderr, OutputStream::Stderr, tx)
+        );
+        let success = child.wait().await.is_ok_and(|status| status.success());
+        group.0 = None;
+        success
+    }
+
+    /// Send each line read from a process's output
+    ///
+    /// Lines are cut at the default limit here; the server applies its own
+    /// limits on top.
+    async fn forward(
+        &self,
+        reader: impl AsyncRead + Unpin,
+        stream: OutputStream,
+        tx: &mpsc::UnboundedSender<FromWorker>,
+    ) {
+        let limits = OutputLimits::default();
+        let mut reader = BufReader::new(reader);
+        while let Ok(Some((line, dropped))) = read_bounded_line(&mut reader, limits.max_line_bytes).await {
+            self.report(tx, stream, limits.truncate(line, dropped));
+        }
+    }
+
+    /// The worker's clone of the repository, cloned first if needed
+    async fn checkout(&self, repos_dir: &Path, tx: &mpsc::UnboundedSender<FromWorker>) -> Result<PathBuf, String> {
+        // The name must not reach outside the repos directory
+        if Path::new(&self.repo).file_name() != Some(OsStr::new(&self.repo)) {
+            return Err(format!("Invalid repository name: {}", self.repo));
+        }
+        let dir = repos_dir.join(&self.repo);
+        if dir.exists() {
+            return Ok(dir);
+        }
+
+        let Some(url) = self.url.clone() else {
+            return Err(format!(
+                "No clone of {} in {} and the repository has no origin remote to clone",
+                self.repo,
+                repos_dir.display()
+            ));
+        };
+        self.report(tx, OutputStream::Stdout, format!("Cloning {} into {}", url, dir.display()));
+        std::fs::create_dir_all(repos_dir)
+            .map_err(|e| format!("Failed to create {}: {}", repos_dir.display(), e))?;
+        let dest = dir.clone();
+        tokio::task::spawn_blocking(move || GitManager::clone(&url, &dest))
+            .await
+            .map_err(|e| format!("Clone task failed: {}", e))?
+            .map_err(|e| format!("Failed to clone: {}", e))?;
+        Ok(dir)
+    }
+}
+
+/// Certificate checks turned off for `--insecure`
+mod insecure {
+    use std::sync::Arc;
+
+    use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+    use rustls::crypto::{verify_tls12_signature, verify_tls13_signature, CryptoProvider};
+    use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+    use rustls::{ClientConfig, DigitallySignedStruct, SignatureScheme};
+
+    /// Accepts any certificate, while still checking handshake signatures
+    #[derive(Debug)]
+    struct AcceptAnyCertificate(Arc<CryptoProvider>);
+
+    impl ServerCertVerifier for AcceptAnyCertificate {
+        fn verify_server_cert(
+            &self,
+            _end_entity: &CertificateDer<'_>,
+            _intermediates: &[CertificateDer<'_>],
+            _server_name: &ServerName<'_>,
+            _ocsp_response: &[u8],
+            _now: UnixTime,
+        ) -> Result<ServerCertVerified, rustls::Error> {
+            Ok(ServerCertVerified::assertion())
+        }
+
+        fn verify_tls12_signature(
+            &self,
+            message: &[u8],
+            cert: &CertificateDer<'_>,
+            dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, rustls::Error> {
+            verify_tls12_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+        }
+
+        fn verify_tls13_signature(
+            &self,
+            message: &[u8],
+            cert: &CertificateDer<'_>,
+            dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, rustls::Error> {
+            verify_tls13_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+        }
+
+        fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+            self.0.signature_verification_algorithms.supported_schemes()
+        }
+    }
+
+    pub fn client_config() -> ClientConfig {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        ClientConfig::builder()
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(AcceptAnyCertificate(provider)))
+            .with_no_client_auth()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_connect_url() {
+        assert_eq!(
+            connect_url("https://box.local:3000/ralph/"),
+            "wss://box.local:3000/ralph/api/workers/connect"
+        );
+        assert_eq!(connect_url("http://10.0.0.2:3000"), "ws://10.0.0.2:3000/api/workers/connect");
+    }
+
+    async fn execute(repos_dir: &Path, repo: &str, command: &[&str]) -> (bool, Vec<FromWorker>) {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let assignment = Assignment {
+            session_id: Uuid::new_v4(),
+            repo: repo.to_string(),
+            url: None,
+            command: command.iter().map(|arg| arg.to_string()).collect(),
+            env: BTreeMap::new(),
+        };
+        let success = assignment.execute(repos_dir, &tx).await;
+        drop(tx);
+        let mut messages = Vec::new();
+        while let Some(message) = rx.recv().await {
+            messages.push(message);
+        }
+        (success, messages)
+    }
+
+    fn lines(messages: &[FromWorker], wanted: OutputStream) -> Vec<&str> {
+        messages
+            .iter()
+            .filter_map(|message| match message {
+                FromWorker::Output { stream, content, .. } if *stream == wanted => Some(content.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_execute_streams_output() {
+        let repos_dir = TempDir::new().unwrap();
+        std::fs::create_dir(repos_dir.path().join("app")).unwrap();
+
+        let (success, messages) =
+            execute(repos_dir.path(), "app", &["sh", "-c", "pwd; echo oops >&2; exit 3"]).await;
+        assert!(!success);
+        let stdout = lines(&messages, OutputStream::Stdout);
+        assert!(stdout[0].ends_with("/app"), "{:?}", stdout);
+        assert_eq!(lines(&messages, OutputStream::Stderr), vec!["oops"]);
+
+        let (success, _) = execute(repos_dir.path(), "app", &["true"]).await;
+        assert!(success);
+    }
+
+    #[tokio::test]
+    async fn test_execute_needs_a_clone() {
+        let repos_dir = TempDir::new().unwrap();
+
+        let (success, messages) = execute(repos_dir.path(), "missing", &["true"]).await;
+        assert!(!success);
+        assert!(lines(&messages, OutputStream::Stderr)[0].starts_with("No clone of missing"));
+
+        let (success, messages) = execute(repos_dir.path(), "..", &["true"]).await;
+        assert!(!success);
+        assert_eq!(lines(&messages, OutputStream::Stderr), vec!["Invalid repository name: .."]);
+    }
+}
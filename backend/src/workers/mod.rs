@@ -0,0 +1,197 @@
+//! Remote workers
+//!
+//! A worker is another machine running `ralphtown worker`, connected to this
+//! server over a WebSocket (`/api/workers/connect`). Sessions can be run on a
+//! worker instead of locally: the server sends the worker the command to run
+//! and the repository to run it in, and the worker runs it against its own
+//! clone and streams the output back. The server records the output and status
+//! exactly as for local runs (see [`crate::ralph::RalphManager::run_on_worker`]),
+//! so the UI can't tell the difference.
+//!
+//! This module holds the wire protocol and the registry of connected workers;
+//! the worker side lives in [`client`].
+
+pub mod client;
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::ws::messages::OutputStream;
+
+/// Messages the server sends a worker
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ToWorker {
+    /// Registration accepted
+    Welcome { worker_id: Uuid },
+    /// Run `command` in the worker's clone of a repository
+    Run {
+        session_id: Uuid,
+        /// Directory name of the repository, looked up in the worker's repos directory
+        repo: String,
+        /// Where to clone the repository from if the worker has no clone yet
+        url: Option<String>,
+        /// Program and arguments
+        command: Vec<String>,
+        /// Environment variables added to the program's environment
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        env: BTreeMap<String, String>,
+    },
+    /// Stop a run
+    Cancel { session_id: Uuid },
+}
+
+/// Messages a worker sends the server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FromWorker {
+    /// First message after connecting
+    Hello { name: String, version: String },
+    /// A line of output from a run
+    Output {
+        session_id: Uuid,
+        stream: OutputStream,
+        content: String,
+    },
+    /// A run ended; `success` is false when it couldn't start or exited non-zero
+    Exited { session_id: Uuid, success: bool },
+}
+
+/// A connected worker
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkerInfo {
+    pub id: Uuid,
+    pub name: String,
+    /// Ralphtown version the worker runs
+    pub version: String,
+    pub connected_at: DateTime<Utc>,
+    /// Sessions currently running on the worker
+    pub sessions: Vec<Uuid>,
+}
+
+struct Worker {
+    info: WorkerInfo,
+    sender: mpsc::UnboundedSender<ToWorker>,
+}
+
+/// Errors talking to workers
+#[derive(Debug, thiserror::Error)]
+pub enum WorkerError {
+    #[error("Worker {0} is not connected")]
+    NotConnected(Uuid),
+}
+
+/// Workers currently connected to this server
+#[derive(Clone, Default)]
+pub struct WorkerRegistry {
+    workers: Arc<Mutex<HashMap<Uuid, Worker>>>,
+}
+
+impl WorkerRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a worker, returning its info and the channel its messages arrive on
+    pub fn register(&self, name: &str, version: &str) -> (WorkerInfo, mpsc::UnboundedReceiver<ToWorker>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let info = WorkerInfo {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            version: version.to_string(),
+            connected_at: Utc::now(),
+            sessions: Vec::new(),
+        };
+        self.workers.lock().unwrap().insert(
+            info.id,
+            Worker {
+                info: info.clone(),
+                sender,
+            },
+        );
+        (info, receiver)
+    }
+
+    /// Remove a worker that disconnected
+    pub fn unregister(&self, id: Uuid) {
+        self.workers.lock().unwrap().remove(&id);
+    }
+
+    /// Connected workers, oldest connection first
+    pub fn list(&self) -> Vec<WorkerInfo> {
+        let mut workers: Vec<WorkerInfo> = self
+            .workers
+            .lock()
+            .unwrap()
+            .values()
+            .map(|worker| worker.info.clone())
+            .collect();
+        workers.sort_by_key(|worker| worker.connected_at);
+        workers
+    }
+
+    pub fn get(&self, id: Uuid) -> Option<WorkerInfo> {
+        self.workers.lock().unwrap().get(&id).map(|worker| worker.info.clone())
+    }
+
+    /// Send a worker a message, keeping track of the sessions it runs
+    pub fn send(&self, id: Uuid, message: ToWorker) -> Result<(), WorkerError> {
+        let mut workers = self.workers.lock().unwrap();
+        let worker = workers.get_mut(&id).ok_or(WorkerError::NotConnected(id))?;
+        match &message {
+            ToWorker::Run { session_id, .. } => worker.info.sessions.push(*session_id),
+            ToWorker::Cancel { session_id } => worker.info.sessions.retain(|s| s != session_id),
+            ToWorker::Welcome { .. } => {}
+        }
+        worker.sender.send(message).map_err(|_| WorkerError::NotConnected(id))
+    }
+
+    /// Note that a session stopped running on a worker
+    pub fn finished(&self, id: Uuid, session_id: Uuid) {
+        if let Some(worker) = self.workers.lock().unwrap().get_mut(&id) {
+            worker.info.sessions.retain(|s| *s != session_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry() {
+        let registry = WorkerRegistry::new();
+        let (info, mut receiver) = registry.register("builder", "0.1.0");
+        assert_eq!(registry.list().len(), 1);
+
+        let session_id = Uuid::new_v4();
+        registry
+            .send(
+                info.id,
+                ToWorker::Run {
+                    session_id,
+                    repo: "app".to_string(),
+                    url: None,
+                    command: vec!["ralph".to_string()],
+                    env: BTreeMap::new(),
+                },
+            )
+            .unwrap();
+        assert!(matches!(receiver.try_recv(), Ok(ToWorker::Run { .. })));
+        assert_eq!(registry.get(info.id).unwrap().sessions, vec![session_id]);
+        registry.finished(info.id, session_id);
+        assert!(registry.get(info.id).unwrap().sessions.is_empty());
+
+        registry.unregister(info.id);
+        assert!(registry.list().is_empty());
+        assert!(matches!(
+            registry.send(info.id, ToWorker::Cancel { session_id }),
+            Err(WorkerError::NotConnected(_))
+        ));
+    }
+}
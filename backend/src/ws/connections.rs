@@ -12,6 +12,8 @@ const CHANNEL_CAPACITY: usize = 256;
 #[derive(Clone)]
 pub struct ConnectionManager {
     inner: Arc<RwLock<ConnectionManagerInner>>,
+    /// Messages about the server as a whole rather than one session
+    global: broadcast::Sender<ServerMessage>,
 }
 
 struct ConnectionManagerInner {
@@ -28,6 +30,7 @@ impl ConnectionManager {
                 session_channels: HashMap::new(),
                 connection_subscriptions: HashMap::new(),
             })),
+            global: broadcast::channel(CHANNEL_CAPACITY).0,
         }
     }
 
@@ -108,6 +111,17 @@ impl ConnectionManager {
             .clone()
     }
 
+    /// Receive messages sent on the global channel
+    pub fn subscribe_global(&self) -> broadcast::Receiver<ServerMessage> {
+        self.global.subscribe()
+    }
+
+    /// Send a message to every connection subscribed to the global channel
+    pub fn broadcast_global(&self, message: ServerMessage) {
+        // Ignore send errors (no receivers)
+        let _ = self.global.send(message);
+    }
+
     /// Check if a session has any subscribers
     pub async fn has_subscribers(&self, session_id: Uuid) -> bool {
         let inner = self.inner.read().await;
@@ -127,7 +141,7 @@ impl Default for ConnectionManager {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::ws::messages::OutputStream;
+    use crate::ws::messages::{OutputChannel, OutputStream};
 
     #[tokio::test]
     async fn test_subscribe_and_broadcast() {
@@ -141,6 +155,8 @@ mod tests {
         let msg = ServerMessage::Output {
             session_id,
             stream: OutputStream::Stdout,
+            channel: OutputChannel::AssistantText,
+            continues: false,
             content: "Hello".to_string(),
         };
 
@@ -185,6 +201,8 @@ mod tests {
         let msg = ServerMessage::Output {
             session_id,
             stream: OutputStream::Stdout,
+            channel: OutputChannel::AssistantText,
+            continues: false,
             content: "Hello both".to_string(),
         };
 
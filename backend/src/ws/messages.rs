@@ -5,12 +5,20 @@ use uuid::Uuid;
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum ClientMessage {
-    /// Subscribe to output from a session
-    Subscribe { session_id: Uuid },
+    /// Subscribe to output from a session, optionally only on some channels
+    Subscribe {
+        session_id: Uuid,
+        #[serde(default)]
+        channels: Option<Vec<OutputChannel>>,
+    },
     /// Unsubscribe from a session
     Unsubscribe { session_id: Uuid },
     /// Cancel a running session
     Cancel { session_id: Uuid },
+    /// Send follow-up input to a running session's process
+    Input { session_id: Uuid, text: String },
+    /// Receive messages about repositories, such as `repo_sync`
+    SubscribeGlobal,
     /// Ping to keep connection alive
     Ping,
 }
@@ -23,17 +31,74 @@ pub enum ServerMessage {
     Subscribed { session_id: Uuid },
     /// Acknowledgment of unsubscription
     Unsubscribed { session_id: Uuid },
+    /// Acknowledgment of a global subscription
+    SubscribedGlobal,
+    /// A repository's ahead/behind counts changed after a background fetch
+    RepoSync(crate::repo_sync::RepoSync),
     /// Output line from a session (stdout or stderr)
     Output {
         session_id: Uuid,
         stream: OutputStream,
+        channel: OutputChannel,
         content: String,
+        /// The line was too long for one message and continues in the next
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        continues: bool,
     },
     /// Session status changed
     Status {
         session_id: Uuid,
         status: SessionStatus,
     },
+    /// A queued session moved in its repository's queue; 1 is next
+    QueuePosition { session_id: Uuid, position: usize },
+    /// A file in the repository changed while the session was running
+    FileChanged {
+        session_id: Uuid,
+        /// Path relative to the repository root
+        path: String,
+        kind: FileChangeKind,
+    },
+    /// A run went over a resource limit and is being stopped
+    LimitExceeded {
+        session_id: Uuid,
+        limit: ResourceLimit,
+        /// The limit, in seconds for `duration` and in bytes otherwise
+        max: u64,
+        /// What the run reached, in the same unit
+        value: u64,
+    },
+    /// A run has written no output for a while and may be hung
+    Stalled {
+        session_id: Uuid,
+        /// How long since its last output, in seconds
+        idle_secs: u64,
+        /// Whether the run is being stopped for it
+        stopping: bool,
+    },
+    /// A run's output reported tokens used; the run's totals so far
+    Cost {
+        session_id: Uuid,
+        input_tokens: u64,
+        output_tokens: u64,
+        cache_read_tokens: u64,
+        cache_write_tokens: u64,
+        cost_usd: f64,
+    },
+    /// A step of the session's pipeline started or ended
+    PipelineStep {
+        session_id: Uuid,
+        /// Place in the pipeline; 1 runs first
+        step: u32,
+        /// How many steps the pipeline has
+        steps: u32,
+        status: StepStatus,
+    },
+    /// The agent wrote a message, called a tool, or edited a file
+    Message {
+        session_id: Uuid,
+        message: crate::db::models::Message,
+    },
     /// Error message
     Error { message: String },
     /// Pong response to ping
@@ -48,21 +113,76 @@ pub enum OutputStream {
     Stderr,
 }
 
+/// Output channel type
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputChannel {
+    AssistantText,
+    ToolCall,
+    System,
+    Stderr,
+}
+
+/// How a file changed
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FileChangeKind {
+    Created,
+    Modified,
+    Removed,
+}
+
+/// A resource limit runs are held to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ResourceLimit {
+    Duration,
+    Memory,
+    Output,
+}
+
 /// Session status for WebSocket updates
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum SessionStatus {
     Idle,
+    Queued,
+    Running,
+    Completed,
+    Error,
+    Cancelled,
+}
+
+/// Pipeline step status for WebSocket updates
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StepStatus {
+    Pending,
     Running,
     Completed,
     Error,
     Cancelled,
+    Skipped,
+}
+
+impl From<crate::db::models::StepStatus> for StepStatus {
+    fn from(status: crate::db::models::StepStatus) -> Self {
+        match status {
+            crate::db::models::StepStatus::Pending => StepStatus::Pending,
+            crate::db::models::StepStatus::Running => StepStatus::Running,
+            crate::db::models::StepStatus::Completed => StepStatus::Completed,
+            crate::db::models::StepStatus::Error => StepStatus::Error,
+            crate::db::models::StepStatus::Cancelled => StepStatus::Cancelled,
+            crate::db::models::StepStatus::Skipped => StepStatus::Skipped,
+        }
+    }
 }
 
 impl From<crate::db::models::SessionStatus> for SessionStatus {
     fn from(status: crate::db::models::SessionStatus) -> Self {
         match status {
             crate::db::models::SessionStatus::Idle => SessionStatus::Idle,
+            crate::db::models::SessionStatus::Queued => SessionStatus::Queued,
             crate::db::models::SessionStatus::Running => SessionStatus::Running,
             crate::db::models::SessionStatus::Completed => SessionStatus::Completed,
             crate::db::models::SessionStatus::Error => SessionStatus::Error,
@@ -80,6 +200,17 @@ impl From<crate::db::models::OutputStream> for OutputStream {
     }
 }
 
+impl From<crate::db::models::OutputChannel> for OutputChannel {
+    fn from(channel: crate::db::models::OutputChannel) -> Self {
+        match channel {
+            crate::db::models::OutputChannel::AssistantText => OutputChannel::AssistantText,
+            crate::db::models::OutputChannel::ToolCall => OutputChannel::ToolCall,
+            crate::db::models::OutputChannel::System => OutputChannel::System,
+            crate::db::models::OutputChannel::Stderr => OutputChannel::Stderr,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -88,6 +219,7 @@ mod tests {
     fn test_client_message_serialize() {
         let msg = ClientMessage::Subscribe {
             session_id: Uuid::nil(),
+            channels: None,
         };
         let json = serde_json::to_string(&msg).unwrap();
         assert!(json.contains("\"type\":\"subscribe\""));
@@ -99,11 +231,14 @@ mod tests {
         let msg = ServerMessage::Output {
             session_id: Uuid::nil(),
             stream: OutputStream::Stdout,
+            channel: OutputChannel::ToolCall,
             content: "Hello".to_string(),
+            continues: false,
         };
         let json = serde_json::to_string(&msg).unwrap();
         assert!(json.contains("\"type\":\"output\""));
         assert!(json.contains("\"stream\":\"stdout\""));
+        assert!(json.contains("\"channel\":\"tool_call\""));
     }
 
     #[test]
@@ -111,5 +246,12 @@ mod tests {
         let json = r#"{"type":"subscribe","session_id":"00000000-0000-0000-0000-000000000000"}"#;
         let msg: ClientMessage = serde_json::from_str(json).unwrap();
         matches!(msg, ClientMessage::Subscribe { .. });
+
+        let json = r#"{"type":"subscribe","session_id":"00000000-0000-0000-0000-000000000000","channels":["tool_call"]}"#;
+        let msg: ClientMessage = serde_json::from_str(json).unwrap();
+        assert!(matches!(
+            msg,
+            ClientMessage::Subscribe { channels: Some(channels), .. } if channels == [OutputChannel::ToolCall]
+        ));
     }
 }
@@ -15,9 +15,11 @@ use futures::SinkExt;
 use uuid::Uuid;
 
 pub use connections::ConnectionManager;
-pub use messages::{ClientMessage, OutputStream, ServerMessage, SessionStatus};
+pub use messages::{ClientMessage, OutputChannel, OutputStream, ServerMessage, SessionStatus};
 
 use crate::api::AppState;
+use crate::db::models::MessageRole;
+use crate::users::CurrentUser;
 
 /// Create the WebSocket router
 pub fn router() -> Router<AppState> {
@@ -25,12 +27,16 @@ pub fn router() -> Router<AppState> {
 }
 
 /// WebSocket upgrade handler
-async fn ws_handler(ws: WebSocketUpgrade, State(state): State<AppState>) -> impl IntoResponse {
-    ws.on_upgrade(move |socket| handle_socket(socket, state))
+async fn ws_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    user: CurrentUser,
+) -> impl IntoResponse {
+    ws.on_upgrade(move |socket| handle_socket(socket, state, user))
 }
 
 /// Handle an individual WebSocket connection
-async fn handle_socket(socket: WebSocket, state: AppState) {
+async fn handle_socket(socket: WebSocket, state: AppState, user: CurrentUser) {
     let connection_id = Uuid::new_v4();
     tracing::info!("WebSocket connection established: {}", connection_id);
 
@@ -40,6 +46,7 @@ async fn handle_socket(socket: WebSocket, state: AppState) {
 
     // Use a channel to send messages from multiple sources to the WebSocket
     let (tx, mut ws_rx) = tokio::sync::mpsc::channel::<ServerMessage>(256);
+    let mut subscribed_global = false;
 
     // Task to forward from mpsc channel to WebSocket
     let sender_task = tokio::spawn(async move {
@@ -83,20 +90,40 @@ async fn handle_socket(socket: WebSocket, state: AppState) {
                 };
 
                 match client_msg {
-                    ClientMessage::Subscribe { session_id } => {
+                    ClientMessage::Subscribe { session_id, channels } => {
                         tracing::info!(
                             "Connection {} subscribing to session {}",
                             connection_id,
                             session_id
                         );
 
+                        let visible = session_owner(&state, session_id)
+                            .await
+                            .is_some_and(|owner| user.can_view(owner));
+                        if !visible {
+                            let _ = tx
+                                .send(ServerMessage::Error {
+                                    message: format!("Session not found: {}", session_id),
+                                })
+                                .await;
+                            continue;
+                        }
+
                         // Get a receiver for this session's broadcast channel
                         let mut rx = state.connections.subscribe(connection_id, session_id).await;
 
-                        // Spawn a task to forward messages from this subscription
+                        // Spawn a task to forward messages from this subscription,
+                        // leaving out output on channels the client didn't ask for
                         let tx_inner = tx.clone();
                         tokio::spawn(async move {
                             while let Ok(msg) = rx.recv().await {
+                                if let (ServerMessage::Output { channel, .. }, Some(channels)) =
+                                    (&msg, &channels)
+                                    && !channels.is_empty()
+                                    && !channels.contains(channel)
+                                {
+                                    continue;
+                                }
                                 if tx_inner.send(msg).await.is_err() {
                                     break;
                                 }
@@ -128,6 +155,28 @@ async fn handle_socket(socket: WebSocket, state: AppState) {
                             session_id
                         );
 
+                        if state.settings.read_only {
+                            let _ = tx
+                                .send(ServerMessage::Error {
+                                    message: "Cannot cancel: server is in read-only mode"
+                                        .to_string(),
+                                })
+                                .await;
+                            continue;
+                        }
+
+                        let allowed = session_owner(&state, session_id)
+                            .await
+                            .is_some_and(|owner| user.can_modify(owner));
+                        if !allowed {
+                            let _ = tx
+                                .send(ServerMessage::Error {
+                                    message: format!("Cannot cancel session {}", session_id),
+                                })
+                                .await;
+                            continue;
+                        }
+
                         // Cancel the running ralph process
                         if let Err(e) = state
                             .ralph_manager
@@ -143,6 +192,81 @@ async fn handle_socket(socket: WebSocket, state: AppState) {
                         }
                     }
 
+                    ClientMessage::Input { session_id, text } => {
+                        if state.settings.read_only {
+                            let _ = tx
+                                .send(ServerMessage::Error {
+                                    message: "Cannot send input: server is in read-only mode"
+                                        .to_string(),
+                                })
+                                .await;
+                            continue;
+                        }
+
+                        let allowed = session_owner(&state, session_id)
+                            .await
+                            .is_some_and(|owner| user.can_modify(owner));
+                        if !allowed {
+                            let _ = tx
+                                .send(ServerMessage::Error {
+                                    message: format!("Cannot send input to session {}", session_id),
+                                })
+                                .await;
+                            continue;
+                        }
+
+                        // Kept as a user message once the process has it
+                        match state.ralph_manager.send_input(session_id, &text).await {
+                            Ok(()) => {
+                                let recorded = state
+                                    .db
+                                    .call(move |db| {
+                                        db.insert_message(session_id, MessageRole::User, &text)
+                                    })
+                                    .await;
+                                if let Err(e) = recorded {
+                                    tracing::warn!("Failed to record input as a message: {}", e);
+                                }
+                            }
+                            Err(e) => {
+                                let _ = tx
+                                    .send(ServerMessage::Error {
+                                        message: format!("Failed to send input: {}", e),
+                                    })
+                                    .await;
+                            }
+                        }
+                    }
+
+                    ClientMessage::SubscribeGlobal => {
+                        if !subscribed_global {
+                            subscribed_global = true;
+                            let mut rx = state.connections.subscribe_global();
+                            let (tx_inner, state) = (tx.clone(), state.clone());
+                            let user = user.clone();
+                            // Only repositories the user can see are reported
+                            tokio::spawn(async move {
+                                while let Ok(msg) = rx.recv().await {
+                                    if let ServerMessage::RepoSync(sync) = &msg {
+                                        let repo_id = sync.repo_id;
+                                        let visible = state
+                                            .db
+                                            .call(move |db| db.get_repo(repo_id))
+                                            .await
+                                            .is_ok_and(|repo| user.can_view(repo.owner_id));
+                                        if !visible {
+                                            continue;
+                                        }
+                                    }
+                                    if tx_inner.send(msg).await.is_err() {
+                                        break;
+                                    }
+                                }
+                            });
+                        }
+                        let _ = tx.send(ServerMessage::SubscribedGlobal).await;
+                    }
+
                     ClientMessage::Ping => {
                         let _ = tx.send(ServerMessage::Pong).await;
                     }
@@ -163,3 +287,13 @@ async fn handle_socket(socket: WebSocket, state: AppState) {
     state.connections.unregister_connection(connection_id).await;
     tracing::info!("WebSocket connection closed: {}", connection_id);
 }
+
+/// Look up a session's owner, or `None` if the session doesn't exist
+async fn session_owner(state: &AppState, session_id: Uuid) -> Option<Option<Uuid>> {
+    state
+        .db
+        .call(move |db| db.get_session(session_id))
+        .await
+        .ok()
+        .map(|session| session.owner_id)
+}
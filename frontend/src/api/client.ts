@@ -1,36 +1,117 @@
 // API client with fetch wrappers and error handling
 
 import type {
+  Iteration,
+  PipelineStep,
+  Run,
+  SessionStats,
+  OutputChannel,
   Repo,
+  RepoDetails,
+  QueuedSession,
+  RepoHealth,
+  RepoSync,
+  Bookmark,
+  CreateBookmarkRequest,
+  Worktree,
+  WorktreeEntry,
+  CreateWorktreeRequest,
+  UpdateBookmarkRequest,
   AddRepoRequest,
   ScanRequest,
   ScanResponse,
   CloneRepoRequest,
   CloneRepoResponse,
+  Template,
+  CreateProjectRequest,
+  CreateProjectResponse,
   Session,
   SessionDetails,
+  RunQueue,
+  Message,
+  Page,
   CreateSessionRequest,
+  ArchiveSessionRequest,
+  AddTagRequest,
+  TagCount,
   RunSessionRequest,
   RunSessionResponse,
+  CancelResult,
   CancelSessionResponse,
-  OutputResponse,
+  SessionInputRequest,
+  SessionEnv,
+  OutputLog,
+  Snapshot,
+  RestoreSnapshotResponse,
+  StorageResponse,
+  PromptHistoryEntry,
+  ActivityEvent,
+  SearchResults,
+  DailyStatsResponse,
+  Artifact,
+  Attachment,
+  Upload,
+  TerminalInfo,
+  JobStatus,
+  PrunedOutput,
+  WorkerInfo,
+  Job,
+  JobRequest,
+  ListJobsParams,
+  CreateTerminalRequest,
+  CompareResponse,
   GitStatusResponse,
-  GitLogResponse,
+  GitLogParams,
+  Commit,
   GitBranchesResponse,
   GitDiffResponse,
   GitCommandResponse,
   CommitRequest,
   ResetRequest,
   CheckoutRequest,
+  FileDiff,
+  FileAtRevision,
+  TreeEntry,
+  GitStatus,
+  StashEntry,
+  StashRequest,
+  Tag,
+  CreateTagRequest,
+  RemoteInfo,
+  GitIdentity,
+  AddRemoteRequest,
+  MergeOutcome,
+  MergeOperation,
+  ConflictFile,
+  ResolveConflictRequest,
+  SetRemoteUrlRequest,
+  CommandOutput,
   ConfigResponse,
   UpdateConfigRequest,
   ConfigValueResponse,
   SetConfigValueRequest,
   BackendsResponse,
   PresetsResponse,
+  UiConfigResponse,
 } from "./types";
 
-const API_BASE = "/api";
+// Path prefix when the app is served under a subpath, e.g. behind a proxy
+export const BASE_PATH = window.__RALPHTOWN_BASE_PATH__ ?? "";
+export const API_BASE = `${BASE_PATH}/api`;
+
+// Cookie the server sets alongside the session cookie, and the header
+// state-changing requests must echo it in
+const CSRF_COOKIE = "ralphtown_csrf";
+const CSRF_HEADER = "X-CSRF-Token";
+
+// The CSRF header for a cookie-authenticated browser session, if any
+export function csrfHeaders(): Record<string, string> {
+  const token = document.cookie
+    .split(";")
+    .map((pair) => pair.trim().split("="))
+    .find(([name]) => name === CSRF_COOKIE)?.[1];
+  return token ? { [CSRF_HEADER]: token } : {};
+}
 
 export class ApiError extends Error {
   constructor(
@@ -47,10 +128,13 @@ async function request<T>(
   path: string,
   options: RequestInit = {}
 ): Promise<T> {
+  // Form bodies need the browser to set the content type with its boundary
+  const isForm = options.body instanceof FormData;
   const response = await fetch(`${API_BASE}${path}`, {
     ...options,
     headers: {
-      "Content-Type": "application/json",
+      ...(isForm ? {} : { "Content-Type": "application/json" }),
+      ...csrfHeaders(),
       ...options.headers,
     },
   });
@@ -69,10 +153,24 @@ async function request<T>(
   return JSON.parse(text) as T;
 }
 
+// Fetch every page of a paginated listing
+async function requestAll<T>(path: string): Promise<T[]> {
+  const items: T[] = [];
+  let cursor: string | null = null;
+  do {
+    const separator = path.includes("?") ? "&" : "?";
+    const query = cursor ? `${separator}cursor=${encodeURIComponent(cursor)}` : "";
+    const page: Page<T> = await request<Page<T>>(`${path}${query}`);
+    items.push(...page.items);
+    cursor = page.next_cursor;
+  } while (cursor);
+  return items;
+}
+
 // --- Repos ---
 
 export async function listRepos(): Promise<Repo[]> {
-  return request<Repo[]>("/repos");
+  return requestAll<Repo>("/repos");
 }
 
 export async function addRepo(req: AddRepoRequest): Promise<Repo> {
@@ -82,10 +180,70 @@ export async function addRepo(req: AddRepoRequest): Promise<Repo> {
   });
 }
 
+export async function getRepo(id: string): Promise<RepoDetails> {
+  return request<RepoDetails>(`/repos/${id}`);
+}
+
+export async function getRepoQueue(id: string): Promise<QueuedSession[]> {
+  return request<QueuedSession[]>(`/repos/${id}/queue`);
+}
+
+export async function listRepoSync(): Promise<RepoSync[]> {
+  return request<RepoSync[]>("/repos/sync");
+}
+
+export async function getRepoHealth(id: string): Promise<RepoHealth> {
+  return request<RepoHealth>(`/repos/${id}/health`);
+}
+
+// A .tar.gz of the repository's committed files at a ref (default: HEAD)
+export function repoArchiveUrl(id: string, ref?: string): string {
+  const params = ref ? `?${new URLSearchParams({ ref })}` : "";
+  return `${API_BASE}/repos/${id}/archive${params}`;
+}
+
 export async function deleteRepo(id: string): Promise<void> {
   await request<void>(`/repos/${id}`, { method: "DELETE" });
 }
 
+export async function listBookmarks(repoId: string): Promise<Bookmark[]> {
+  return request<Bookmark[]>(`/repos/${repoId}/bookmarks`);
+}
+
+export async function createBookmark(repoId: string, req: CreateBookmarkRequest): Promise<Bookmark> {
+  return request<Bookmark>(`/repos/${repoId}/bookmarks`, {
+    method: "POST",
+    body: JSON.stringify(req),
+  });
+}
+
+export async function updateBookmark(id: string, req: UpdateBookmarkRequest): Promise<Bookmark> {
+  return request<Bookmark>(`/bookmarks/${id}`, {
+    method: "PUT",
+    body: JSON.stringify(req),
+  });
+}
+
+export async function deleteBookmark(id: string): Promise<void> {
+  await request<void>(`/bookmarks/${id}`, { method: "DELETE" });
+}
+
+export async function listWorktrees(repoId: string): Promise<WorktreeEntry[]> {
+  return request<WorktreeEntry[]>(`/repos/${repoId}/worktrees`);
+}
+
+export async function createWorktree(repoId: string, req: CreateWorktreeRequest): Promise<Worktree> {
+  return request<Worktree>(`/repos/${repoId}/worktrees`, {
+    method: "POST",
+    body: JSON.stringify(req),
+  });
+}
+
+export async function deleteWorktree(id: string, force = false): Promise<void> {
+  const query = force ? "?force=true" : "";
+  await request<void>(`/worktrees/${id}${query}`, { method: "DELETE" });
+}
+
 export async function scanRepos(req: ScanRequest): Promise<ScanResponse> {
   return request<ScanResponse>("/repos/scan", {
     method: "POST",
@@ -100,16 +258,81 @@ export async function cloneRepo(req: CloneRepoRequest): Promise<CloneRepoRespons
   });
 }
 
+export async function cancelClone(jobId: string): Promise<void> {
+  await request<void>(`/repos/clone/${jobId}`, { method: "DELETE" });
+}
+
+// --- Templates ---
+
+export async function listTemplates(): Promise<Template[]> {
+  return request<Template[]>("/templates");
+}
+
+export async function createProject(req: CreateProjectRequest): Promise<CreateProjectResponse> {
+  return request<CreateProjectResponse>("/projects", {
+    method: "POST",
+    body: JSON.stringify(req),
+  });
+}
+
 // --- Sessions ---
 
-export async function listSessions(): Promise<Session[]> {
-  return request<Session[]>("/sessions");
+export async function listSessions(tag?: string): Promise<Session[]> {
+  return requestAll<Session>(tag ? `/sessions?tag=${encodeURIComponent(tag)}` : "/sessions");
+}
+
+export async function patchSessionMetadata(
+  id: string,
+  patch: Record<string, unknown>
+): Promise<Session> {
+  return request<Session>(`/sessions/${id}/metadata`, {
+    method: "PATCH",
+    body: JSON.stringify(patch),
+  });
+}
+
+export async function listSessionTags(): Promise<TagCount[]> {
+  return request<TagCount[]>("/sessions/tags");
+}
+
+export async function addSessionTag(id: string, tag: string): Promise<string[]> {
+  const req: AddTagRequest = { tag };
+  return request<string[]>(`/sessions/${id}/tags`, {
+    method: "POST",
+    body: JSON.stringify(req),
+  });
+}
+
+export async function removeSessionTag(id: string, tag: string): Promise<string[]> {
+  return request<string[]>(`/sessions/${id}/tags/${encodeURIComponent(tag)}`, {
+    method: "DELETE",
+  });
+}
+
+export async function listArchivedSessions(): Promise<Session[]> {
+  return requestAll<Session>("/sessions/archived");
+}
+
+export async function getRunQueue(): Promise<RunQueue> {
+  return request<RunQueue>("/sessions/queue");
 }
 
 export async function getSession(id: string): Promise<SessionDetails> {
   return request<SessionDetails>(`/sessions/${id}`);
 }
 
+export async function listSessionMessages(
+  id: string,
+  params?: { limit?: number; cursor?: string }
+): Promise<Page<Message>> {
+  const searchParams = new URLSearchParams();
+  if (params?.limit) searchParams.set("limit", String(params.limit));
+  if (params?.cursor) searchParams.set("cursor", params.cursor);
+
+  const query = searchParams.toString();
+  return request<Page<Message>>(`/sessions/${id}/messages${query ? `?${query}` : ""}`);
+}
+
 export async function createSession(req: CreateSessionRequest): Promise<Session> {
   return request<Session>("/sessions", {
     method: "POST",
@@ -121,6 +344,14 @@ export async function deleteSession(id: string): Promise<void> {
   await request<void>(`/sessions/${id}`, { method: "DELETE" });
 }
 
+export async function archiveSession(id: string, archived: boolean): Promise<Session> {
+  const req: ArchiveSessionRequest = { archived };
+  return request<Session>(`/sessions/${id}/archive`, {
+    method: "PATCH",
+    body: JSON.stringify(req),
+  });
+}
+
 export async function runSession(
   id: string,
   req: RunSessionRequest
@@ -137,17 +368,231 @@ export async function cancelSession(id: string): Promise<CancelSessionResponse>
   });
 }
 
+export async function cancelAllSessions(): Promise<CancelResult[]> {
+  return request<CancelResult[]>("/sessions/cancel-all", {
+    method: "POST",
+  });
+}
+
+export async function sendSessionInput(id: string, req: SessionInputRequest): Promise<Message> {
+  return request<Message>(`/sessions/${id}/input`, {
+    method: "POST",
+    body: JSON.stringify(req),
+  });
+}
+
+export async function getSessionEnv(id: string): Promise<SessionEnv> {
+  return request<SessionEnv>(`/sessions/${id}/env`);
+}
+
+export async function setSessionEnv(id: string, req: SessionEnv): Promise<SessionEnv> {
+  return request<SessionEnv>(`/sessions/${id}/env`, {
+    method: "PUT",
+    body: JSON.stringify(req),
+  });
+}
+
 export async function getSessionOutput(
   id: string,
-  params?: { stream?: "stdout" | "stderr"; limit?: number; offset?: number }
-): Promise<OutputResponse> {
+  params?: {
+    stream?: "stdout" | "stderr";
+    channels?: OutputChannel[];
+    limit?: number;
+    cursor?: string;
+  }
+): Promise<Page<OutputLog>> {
   const searchParams = new URLSearchParams();
   if (params?.stream) searchParams.set("stream", params.stream);
+  if (params?.channels?.length) searchParams.set("channel", params.channels.join(","));
+  if (params?.limit) searchParams.set("limit", String(params.limit));
+  if (params?.cursor) searchParams.set("cursor", params.cursor);
+
+  const query = searchParams.toString();
+  return request<Page<OutputLog>>(`/sessions/${id}/output${query ? `?${query}` : ""}`);
+}
+
+export async function listIterations(id: string): Promise<Iteration[]> {
+  return request<Iteration[]>(`/sessions/${id}/iterations`);
+}
+
+export async function listSessionRuns(id: string): Promise<Run[]> {
+  return request<Run[]>(`/sessions/${id}/runs`);
+}
+
+export async function getSessionStats(id: string): Promise<SessionStats> {
+  return request<SessionStats>(`/sessions/${id}/stats`);
+}
+
+export async function listPipelineSteps(id: string): Promise<PipelineStep[]> {
+  return request<PipelineStep[]>(`/sessions/${id}/pipeline`);
+}
+
+export async function compareSessions(a: string, b: string): Promise<CompareResponse> {
+  const searchParams = new URLSearchParams({ a, b });
+  return request<CompareResponse>(`/sessions/compare?${searchParams}`);
+}
+
+export function sessionBundleUrl(id: string): string {
+  return `${API_BASE}/sessions/${id}/bundle`;
+}
+
+export async function importSessionBundle(repoId: string, bundle: Blob): Promise<Session> {
+  const searchParams = new URLSearchParams({ repo_id: repoId });
+  return request<Session>(`/sessions/import?${searchParams}`, {
+    method: "POST",
+    headers: { "Content-Type": "application/gzip" },
+    body: bundle,
+  });
+}
+
+// --- Storage ---
+
+export async function getStorage(): Promise<StorageResponse> {
+  return request<StorageResponse>("/storage");
+}
+
+// --- Prompts ---
+
+export async function getRecentPrompts(
+  repoId: string,
+  params?: { q?: string; limit?: number }
+): Promise<Page<PromptHistoryEntry>> {
+  const searchParams = new URLSearchParams({ repo_id: repoId });
+  if (params?.q) searchParams.set("q", params.q);
   if (params?.limit) searchParams.set("limit", String(params.limit));
-  if (params?.offset) searchParams.set("offset", String(params.offset));
+  return request<Page<PromptHistoryEntry>>(`/prompts/recent?${searchParams}`);
+}
+
+// --- Activity ---
+
+export async function listActivity(params?: { limit?: number; cursor?: string }): Promise<Page<ActivityEvent>> {
+  const searchParams = new URLSearchParams();
+  if (params?.limit) searchParams.set("limit", String(params.limit));
+  if (params?.cursor) searchParams.set("cursor", params.cursor);
 
   const query = searchParams.toString();
-  return request<OutputResponse>(`/sessions/${id}/output${query ? `?${query}` : ""}`);
+  return request<Page<ActivityEvent>>(`/activity${query ? `?${query}` : ""}`);
+}
+
+// --- Search ---
+
+export async function search(q: string, params?: { limit?: number }): Promise<SearchResults> {
+  const searchParams = new URLSearchParams({ q });
+  if (params?.limit) searchParams.set("limit", String(params.limit));
+  return request<SearchResults>(`/search?${searchParams}`);
+}
+
+// --- Analytics ---
+
+export async function getDailyStats(params?: {
+  repoId?: string;
+  from?: string;
+  to?: string;
+}): Promise<DailyStatsResponse> {
+  const searchParams = new URLSearchParams();
+  if (params?.repoId) searchParams.set("repo_id", params.repoId);
+  if (params?.from) searchParams.set("from", params.from);
+  if (params?.to) searchParams.set("to", params.to);
+
+  const query = searchParams.toString();
+  return request<DailyStatsResponse>(`/analytics/daily${query ? `?${query}` : ""}`);
+}
+
+// --- Artifacts ---
+
+export async function listArtifacts(sessionId: string): Promise<Artifact[]> {
+  return requestAll<Artifact>(`/sessions/${sessionId}/artifacts`);
+}
+
+export function artifactDownloadUrl(id: string): string {
+  return `${API_BASE}/artifacts/${id}/download`;
+}
+
+// --- Uploads ---
+
+export async function uploadFiles(files: File[]): Promise<Upload[]> {
+  const form = new FormData();
+  for (const file of files) {
+    form.append("file", file, file.name);
+  }
+  return request<Upload[]>("/uploads", {
+    method: "POST",
+    body: form,
+  });
+}
+
+export async function deleteUpload(id: string): Promise<void> {
+  return request<void>(`/uploads/${id}`, {
+    method: "DELETE",
+  });
+}
+
+// --- Attachments ---
+
+export async function listAttachments(sessionId: string): Promise<Attachment[]> {
+  return request<Attachment[]>(`/sessions/${sessionId}/attachments`);
+}
+
+export async function attachFiles(sessionId: string, files: File[]): Promise<Attachment[]> {
+  const form = new FormData();
+  for (const file of files) {
+    form.append("file", file, file.name);
+  }
+  return request<Attachment[]>(`/sessions/${sessionId}/attachments`, {
+    method: "POST",
+    body: form,
+  });
+}
+
+export async function deleteAttachment(id: string): Promise<void> {
+  return request<void>(`/attachments/${id}`, {
+    method: "DELETE",
+  });
+}
+
+export function attachmentDownloadUrl(id: string): string {
+  return `${API_BASE}/attachments/${id}/download`;
+}
+
+// --- Terminals ---
+
+export async function createTerminal(
+  repoId: string,
+  req: CreateTerminalRequest = {}
+): Promise<TerminalInfo> {
+  return request<TerminalInfo>(`/repos/${repoId}/terminal`, {
+    method: "POST",
+    body: JSON.stringify(req),
+  });
+}
+
+export async function listTerminals(): Promise<TerminalInfo[]> {
+  return request<TerminalInfo[]>("/terminals");
+}
+
+export async function closeTerminal(id: string): Promise<void> {
+  await request<void>(`/terminals/${id}`, { method: "DELETE" });
+}
+
+export function terminalSocketUrl(id: string): string {
+  const protocol = window.location.protocol === "https:" ? "wss:" : "ws:";
+  return `${protocol}//${window.location.host}${API_BASE}/terminals/${id}/ws`;
+}
+
+// --- Snapshots ---
+
+export async function listSnapshots(sessionId: string): Promise<Snapshot[]> {
+  return requestAll<Snapshot>(`/sessions/${sessionId}/snapshots`);
+}
+
+export async function restoreSnapshot(id: string): Promise<RestoreSnapshotResponse> {
+  return request<RestoreSnapshotResponse>(`/snapshots/${id}/restore`, {
+    method: "POST",
+  });
+}
+
+export async function deleteSnapshot(id: string): Promise<void> {
+  await request<void>(`/snapshots/${id}`, { method: "DELETE" });
 }
 
 // --- Git ---
@@ -158,10 +603,16 @@ export async function getGitStatus(sessionId: string): Promise<GitStatusResponse
 
 export async function getGitLog(
   sessionId: string,
-  limit?: number
-): Promise<GitLogResponse> {
-  const query = limit ? `?limit=${limit}` : "";
-  return request<GitLogResponse>(`/sessions/${sessionId}/git/log${query}`);
+  params: GitLogParams = {}
+): Promise<Page<Commit>> {
+  const query = new URLSearchParams();
+  if (params.limit) query.set("limit", String(params.limit));
+  if (params.cursor) query.set("cursor", params.cursor);
+  if (params.rev) query.set("rev", params.rev);
+  if (params.author) query.set("author", params.author);
+  if (params.path) query.set("path", params.path);
+  const suffix = query.toString() ? `?${query}` : "";
+  return request<Page<Commit>>(`/sessions/${sessionId}/git/log${suffix}`);
 }
 
 export async function getGitBranches(sessionId: string): Promise<GitBranchesResponse> {
@@ -214,6 +665,187 @@ export async function gitCheckout(
   });
 }
 
+export async function getFileDiff(repoId: string, path: string, staged = false): Promise<FileDiff> {
+  const params = new URLSearchParams({ path });
+  if (staged) params.set("staged", "true");
+  return request<FileDiff>(`/git/${repoId}/diff?${params}`);
+}
+
+// Without a ref this lists the working directory
+export async function listTree(repoId: string, path?: string, ref?: string): Promise<TreeEntry[]> {
+  const params = new URLSearchParams();
+  if (path) params.set("path", path);
+  if (ref) params.set("ref", ref);
+  return request<TreeEntry[]>(`/git/${repoId}/tree?${params}`);
+}
+
+export async function getFileAtRevision(repoId: string, path: string, ref?: string): Promise<FileAtRevision> {
+  const params = new URLSearchParams({ path });
+  if (ref) params.set("ref", ref);
+  return request<FileAtRevision>(`/git/${repoId}/file?${params}`);
+}
+
+export async function stageFiles(repoId: string, paths: string[]): Promise<GitStatus> {
+  return request<GitStatus>(`/git/${repoId}/stage`, {
+    method: "POST",
+    body: JSON.stringify({ paths }),
+  });
+}
+
+export async function unstageFiles(repoId: string, paths: string[]): Promise<GitStatus> {
+  return request<GitStatus>(`/git/${repoId}/unstage`, {
+    method: "POST",
+    body: JSON.stringify({ paths }),
+  });
+}
+
+export async function listStashes(repoId: string): Promise<StashEntry[]> {
+  return request<StashEntry[]>(`/git/${repoId}/stash`);
+}
+
+export async function stashChanges(repoId: string, req: StashRequest = {}): Promise<StashEntry> {
+  return request<StashEntry>(`/git/${repoId}/stash`, {
+    method: "POST",
+    body: JSON.stringify(req),
+  });
+}
+
+export async function popStash(repoId: string, index: number): Promise<StashEntry[]> {
+  return request<StashEntry[]>(`/git/${repoId}/stash/${index}/pop`, {
+    method: "POST",
+  });
+}
+
+export async function dropStash(repoId: string, index: number): Promise<StashEntry[]> {
+  return request<StashEntry[]>(`/git/${repoId}/stash/${index}`, {
+    method: "DELETE",
+  });
+}
+
+export async function listTags(repoId: string): Promise<Tag[]> {
+  return request<Tag[]>(`/git/${repoId}/tags`);
+}
+
+export async function createTag(repoId: string, req: CreateTagRequest): Promise<Tag> {
+  return request<Tag>(`/git/${repoId}/tags`, {
+    method: "POST",
+    body: JSON.stringify(req),
+  });
+}
+
+export async function deleteTag(repoId: string, name: string): Promise<Tag[]> {
+  return request<Tag[]>(`/git/${repoId}/tags/${encodeURIComponent(name)}`, {
+    method: "DELETE",
+  });
+}
+
+export async function pushTag(repoId: string, name: string, remote = "origin"): Promise<CommandOutput> {
+  return request<CommandOutput>(`/git/${repoId}/tags/${encodeURIComponent(name)}/push`, {
+    method: "POST",
+    body: JSON.stringify({ remote }),
+  });
+}
+
+export async function listRemotes(repoId: string): Promise<RemoteInfo[]> {
+  return request<RemoteInfo[]>(`/git/${repoId}/remotes`);
+}
+
+export async function addRemote(repoId: string, req: AddRemoteRequest): Promise<RemoteInfo> {
+  return request<RemoteInfo>(`/git/${repoId}/remotes`, {
+    method: "POST",
+    body: JSON.stringify(req),
+  });
+}
+
+export async function setRemoteUrl(
+  repoId: string,
+  name: string,
+  req: SetRemoteUrlRequest
+): Promise<RemoteInfo> {
+  return request<RemoteInfo>(`/git/${repoId}/remotes/${encodeURIComponent(name)}`, {
+    method: "PUT",
+    body: JSON.stringify(req),
+  });
+}
+
+export async function removeRemote(repoId: string, name: string): Promise<RemoteInfo[]> {
+  return request<RemoteInfo[]>(`/git/${repoId}/remotes/${encodeURIComponent(name)}`, {
+    method: "DELETE",
+  });
+}
+
+export async function getGitIdentity(repoId: string): Promise<GitIdentity> {
+  return request<GitIdentity>(`/git/${repoId}/identity`);
+}
+
+export async function setGitIdentity(repoId: string, identity: GitIdentity): Promise<GitIdentity> {
+  return request<GitIdentity>(`/git/${repoId}/identity`, {
+    method: "PUT",
+    body: JSON.stringify(identity),
+  });
+}
+
+export async function mergeBranch(repoId: string, branch: string): Promise<MergeOutcome> {
+  return request<MergeOutcome>(`/git/${repoId}/merge`, {
+    method: "POST",
+    body: JSON.stringify({ branch }),
+  });
+}
+
+export async function rebaseBranch(repoId: string, onto: string): Promise<MergeOutcome> {
+  return request<MergeOutcome>(`/git/${repoId}/rebase`, {
+    method: "POST",
+    body: JSON.stringify({ onto }),
+  });
+}
+
+export async function cherryPick(repoId: string, commit: string): Promise<MergeOutcome> {
+  return request<MergeOutcome>(`/git/${repoId}/cherry-pick`, {
+    method: "POST",
+    body: JSON.stringify({ commit }),
+  });
+}
+
+export async function revertCommit(
+  repoId: string,
+  commit: string,
+  noCommit = false
+): Promise<MergeOutcome> {
+  return request<MergeOutcome>(`/git/${repoId}/revert`, {
+    method: "POST",
+    body: JSON.stringify({ commit, no_commit: noCommit }),
+  });
+}
+
+export async function continueMerge(
+  repoId: string,
+  operation: MergeOperation
+): Promise<MergeOutcome> {
+  return request<MergeOutcome>(`/git/${repoId}/${operation}/continue`, {
+    method: "POST",
+  });
+}
+
+export async function abortMerge(repoId: string, operation: MergeOperation): Promise<GitStatus> {
+  return request<GitStatus>(`/git/${repoId}/${operation}/abort`, {
+    method: "POST",
+  });
+}
+
+export async function listConflicts(repoId: string): Promise<ConflictFile[]> {
+  return request<ConflictFile[]>(`/git/${repoId}/conflicts`);
+}
+
+export async function resolveConflict(
+  repoId: string,
+  req: ResolveConflictRequest
+): Promise<ConflictFile[]> {
+  return request<ConflictFile[]>(`/git/${repoId}/conflicts`, {
+    method: "POST",
+    body: JSON.stringify(req),
+  });
+}
+
 // --- Config ---
 
 export async function getConfig(): Promise<ConfigResponse> {
@@ -252,3 +884,48 @@ export async function listBackends(): Promise<BackendsResponse> {
 export async function listPresets(): Promise<PresetsResponse> {
   return request<PresetsResponse>("/config/presets");
 }
+
+export async function getUiConfig(): Promise<UiConfigResponse> {
+  return request<UiConfigResponse>("/ui-config");
+}
+
+// --- Jobs ---
+
+export async function createJob(req: JobRequest): Promise<Job> {
+  return request<Job>("/jobs", {
+    method: "POST",
+    body: JSON.stringify(req),
+  });
+}
+
+export async function listJobs(params: ListJobsParams = {}): Promise<Job[]> {
+  const query = new URLSearchParams();
+  if (params.kind) query.set("kind", params.kind);
+  if (params.state) query.set("state", params.state);
+  const suffix = query.toString() ? `?${query}` : "";
+  return requestAll<Job>(`/jobs${suffix}`);
+}
+
+export async function getJob(id: string): Promise<Job> {
+  return request<Job>(`/jobs/${id}`);
+}
+
+export async function cancelJob(id: string): Promise<Job> {
+  return request<Job>(`/jobs/${id}/cancel`, { method: "POST" });
+}
+
+// --- Workers ---
+
+export async function listWorkers(): Promise<WorkerInfo[]> {
+  return request<WorkerInfo[]>("/workers");
+}
+
+// --- Admin ---
+
+export async function listMaintenanceJobs(): Promise<JobStatus[]> {
+  return request<JobStatus[]>("/admin/jobs");
+}
+
+export async function pruneOutput(): Promise<PrunedOutput> {
+  return request<PrunedOutput>("/maintenance/prune", { method: "POST" });
+}
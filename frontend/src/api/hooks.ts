@@ -3,31 +3,89 @@
 import { useQuery, useMutation, useQueryClient } from "@tanstack/react-query";
 import * as api from "./client";
 import type {
+  OutputChannel,
   AddRepoRequest,
   CloneRepoRequest,
+  CreateBookmarkRequest,
+  UpdateBookmarkRequest,
+  CreateWorktreeRequest,
+  CreateProjectRequest,
   CreateSessionRequest,
   RunSessionRequest,
+  SessionInputRequest,
+  SessionEnv,
   CommitRequest,
   ResetRequest,
   CheckoutRequest,
+  GitLogParams,
+  StashRequest,
+  CreateTagRequest,
+  AddRemoteRequest,
+  SetRemoteUrlRequest,
+  GitIdentity,
+  ResolveConflictRequest,
+  MergeOperation,
   UpdateConfigRequest,
   SetConfigValueRequest,
+  CreateTerminalRequest,
+  JobRequest,
+  ListJobsParams,
 } from "./types";
 
 // Query key factories for consistent cache management
 export const queryKeys = {
   repos: ["repos"] as const,
+  repo: (id: string) => ["repos", id] as const,
+  repoQueue: (id: string) => ["repos", id, "queue"] as const,
+  repoHealth: (id: string) => ["repos", id, "health"] as const,
+  repoSync: ["repos", "sync"] as const,
+  worktrees: (repoId: string) => ["repos", repoId, "worktrees"] as const,
+  templates: ["templates"] as const,
   sessions: ["sessions"] as const,
+  runQueue: ["sessions", "queue"] as const,
+  archivedSessions: ["sessions", "archived"] as const,
+  taggedSessions: (tag: string) => ["sessions", "tagged", tag] as const,
+  sessionTags: ["sessions", "tags"] as const,
   session: (id: string) => ["sessions", id] as const,
   sessionOutput: (id: string) => ["sessions", id, "output"] as const,
+  sessionIterations: (id: string) => ["sessions", id, "iterations"] as const,
+  sessionRuns: (id: string) => ["sessions", id, "runs"] as const,
+  sessionPipeline: (id: string) => ["sessions", id, "pipeline"] as const,
+  sessionStats: (id: string) => ["sessions", id, "stats"] as const,
+  sessionEnv: (id: string) => ["sessions", id, "env"] as const,
+  recentPrompts: (repoId: string) => ["prompts", repoId] as const,
+  activity: ["activity"] as const,
+  search: (q: string) => ["search", q] as const,
+  dailyStats: ["analytics", "daily"] as const,
+  artifacts: (sessionId: string) => ["sessions", sessionId, "artifacts"] as const,
+  attachments: (sessionId: string) => ["sessions", sessionId, "attachments"] as const,
+  terminals: ["terminals"] as const,
+  snapshots: (sessionId: string) => ["sessions", sessionId, "snapshots"] as const,
   gitStatus: (sessionId: string) => ["git", sessionId, "status"] as const,
   gitLog: (sessionId: string) => ["git", sessionId, "log"] as const,
   gitBranches: (sessionId: string) => ["git", sessionId, "branches"] as const,
   gitDiff: (sessionId: string) => ["git", sessionId, "diff"] as const,
+  fileDiff: (repoId: string, path: string, staged: boolean) =>
+    ["git", "repos", repoId, "diff", path, staged] as const,
+  fileAtRevision: (repoId: string, path: string, ref: string) =>
+    ["git", "repos", repoId, "file", path, ref] as const,
+  tree: (repoId: string, path: string, ref: string | null) =>
+    ["git", "repos", repoId, "tree", path, ref] as const,
+  stashes: (repoId: string) => ["git", "repos", repoId, "stash"] as const,
+  tags: (repoId: string) => ["git", "repos", repoId, "tags"] as const,
+  remotes: (repoId: string) => ["git", "repos", repoId, "remotes"] as const,
+  gitIdentity: (repoId: string) => ["git", "repos", repoId, "identity"] as const,
+  conflicts: (repoId: string) => ["git", "repos", repoId, "conflicts"] as const,
   config: ["config"] as const,
   configValue: (key: string) => ["config", key] as const,
   backends: ["config", "backends"] as const,
   presets: ["config", "presets"] as const,
+  uiConfig: ["ui-config"] as const,
+  jobs: ["jobs"] as const,
+  jobList: (params: ListJobsParams) => ["jobs", "list", params] as const,
+  job: (id: string) => ["jobs", id] as const,
+  maintenanceJobs: ["admin", "jobs"] as const,
+  workers: ["workers"] as const,
 };
 
 // --- Repos ---
@@ -59,6 +117,99 @@ export function useDeleteRepo() {
   });
 }
 
+export function useRepo(id: string | null) {
+  return useQuery({
+    queryKey: id ? queryKeys.repo(id) : ["repos", "none"],
+    queryFn: () => (id ? api.getRepo(id) : Promise.resolve(null)),
+    enabled: !!id,
+  });
+}
+
+export function useRepoQueue(id: string | null) {
+  return useQuery({
+    queryKey: id ? queryKeys.repoQueue(id) : ["repos", "none", "queue"],
+    queryFn: () => (id ? api.getRepoQueue(id) : Promise.resolve([])),
+    enabled: !!id,
+  });
+}
+
+export function useRepoSync() {
+  return useQuery({
+    queryKey: queryKeys.repoSync,
+    queryFn: api.listRepoSync,
+  });
+}
+
+export function useRepoHealth(id: string | null) {
+  return useQuery({
+    queryKey: id ? queryKeys.repoHealth(id) : ["repos", "none", "health"],
+    queryFn: () => (id ? api.getRepoHealth(id) : Promise.resolve(null)),
+    enabled: !!id,
+  });
+}
+
+export function useCreateBookmark() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: ({ repoId, req }: { repoId: string; req: CreateBookmarkRequest }) =>
+      api.createBookmark(repoId, req),
+    onSuccess: (_, { repoId }) => {
+      queryClient.invalidateQueries({ queryKey: queryKeys.repo(repoId) });
+    },
+  });
+}
+
+export function useUpdateBookmark() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: ({ id, req }: { id: string; repoId: string; req: UpdateBookmarkRequest }) =>
+      api.updateBookmark(id, req),
+    onSuccess: (_, { repoId }) => {
+      queryClient.invalidateQueries({ queryKey: queryKeys.repo(repoId) });
+    },
+  });
+}
+
+export function useDeleteBookmark() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: ({ id }: { id: string; repoId: string }) => api.deleteBookmark(id),
+    onSuccess: (_, { repoId }) => {
+      queryClient.invalidateQueries({ queryKey: queryKeys.repo(repoId) });
+    },
+  });
+}
+
+export function useWorktrees(repoId: string | null) {
+  return useQuery({
+    queryKey: repoId ? queryKeys.worktrees(repoId) : ["repos", "none", "worktrees"],
+    queryFn: () => (repoId ? api.listWorktrees(repoId) : Promise.resolve([])),
+    enabled: !!repoId,
+  });
+}
+
+export function useCreateWorktree() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: ({ repoId, req }: { repoId: string; req: CreateWorktreeRequest }) =>
+      api.createWorktree(repoId, req),
+    onSuccess: (_, { repoId }) => {
+      queryClient.invalidateQueries({ queryKey: queryKeys.worktrees(repoId) });
+    },
+  });
+}
+
+export function useDeleteWorktree() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: ({ id, force }: { id: string; repoId: string; force?: boolean }) =>
+      api.deleteWorktree(id, force),
+    onSuccess: (_, { repoId }) => {
+      queryClient.invalidateQueries({ queryKey: queryKeys.worktrees(repoId) });
+    },
+  });
+}
+
 export function useScanRepos() {
   return useMutation({
     mutationFn: api.scanRepos,
@@ -75,12 +226,53 @@ export function useCloneRepo() {
   });
 }
 
+// --- Templates ---
+
+export function useTemplates() {
+  return useQuery({
+    queryKey: queryKeys.templates,
+    queryFn: api.listTemplates,
+  });
+}
+
+export function useCreateProject() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: (req: CreateProjectRequest) => api.createProject(req),
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: queryKeys.repos });
+      queryClient.invalidateQueries({ queryKey: queryKeys.sessions });
+    },
+  });
+}
+
 // --- Sessions ---
 
-export function useSessions() {
+export function useSessions(tag?: string) {
   return useQuery({
-    queryKey: queryKeys.sessions,
-    queryFn: api.listSessions,
+    queryKey: tag ? queryKeys.taggedSessions(tag) : queryKeys.sessions,
+    queryFn: () => api.listSessions(tag),
+  });
+}
+
+export function useSessionTags() {
+  return useQuery({
+    queryKey: queryKeys.sessionTags,
+    queryFn: api.listSessionTags,
+  });
+}
+
+export function useArchivedSessions() {
+  return useQuery({
+    queryKey: queryKeys.archivedSessions,
+    queryFn: api.listArchivedSessions,
+  });
+}
+
+export function useRunQueue() {
+  return useQuery({
+    queryKey: queryKeys.runQueue,
+    queryFn: api.getRunQueue,
   });
 }
 
@@ -112,6 +304,60 @@ export function useDeleteSession() {
   });
 }
 
+export function useArchiveSession() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: ({ id, archived }: { id: string; archived: boolean }) =>
+      api.archiveSession(id, archived),
+    onSuccess: () => {
+      // Also refreshes the archived list and the session itself
+      queryClient.invalidateQueries({ queryKey: queryKeys.sessions });
+    },
+  });
+}
+
+export function usePatchSessionMetadata() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: ({ id, patch }: { id: string; patch: Record<string, unknown> }) =>
+      api.patchSessionMetadata(id, patch),
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: queryKeys.sessions });
+    },
+  });
+}
+
+export function useAddSessionTag() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: ({ id, tag }: { id: string; tag: string }) => api.addSessionTag(id, tag),
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: queryKeys.sessions });
+    },
+  });
+}
+
+export function useRemoveSessionTag() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: ({ id, tag }: { id: string; tag: string }) => api.removeSessionTag(id, tag),
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: queryKeys.sessions });
+    },
+  });
+}
+
+export function useImportSessionBundle() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: ({ repoId, bundle }: { repoId: string; bundle: Blob }) =>
+      api.importSessionBundle(repoId, bundle),
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: queryKeys.sessions });
+    },
+  });
+}
+
 export function useRunSession() {
   const queryClient = useQueryClient();
   return useMutation({
@@ -120,6 +366,7 @@ export function useRunSession() {
     onSuccess: (_, { id }) => {
       queryClient.invalidateQueries({ queryKey: queryKeys.session(id) });
       queryClient.invalidateQueries({ queryKey: queryKeys.sessions });
+      queryClient.invalidateQueries({ queryKey: ["prompts"] });
     },
   });
 }
@@ -135,9 +382,56 @@ export function useCancelSession() {
   });
 }
 
+export function useCancelAllSessions() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: () => api.cancelAllSessions(),
+    onSuccess: (results) => {
+      for (const { session_id } of results) {
+        queryClient.invalidateQueries({ queryKey: queryKeys.session(session_id) });
+      }
+      queryClient.invalidateQueries({ queryKey: queryKeys.sessions });
+    },
+  });
+}
+
+export function useSendSessionInput() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: ({ id, req }: { id: string; req: SessionInputRequest }) =>
+      api.sendSessionInput(id, req),
+    onSuccess: (_, { id }) => {
+      queryClient.invalidateQueries({ queryKey: queryKeys.session(id) });
+    },
+  });
+}
+
+export function useSessionEnv(id: string | null) {
+  return useQuery({
+    queryKey: id ? queryKeys.sessionEnv(id) : ["sessions", "none", "env"],
+    queryFn: () => (id ? api.getSessionEnv(id) : Promise.resolve(null)),
+    enabled: !!id,
+  });
+}
+
+export function useSetSessionEnv() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: ({ id, req }: { id: string; req: SessionEnv }) => api.setSessionEnv(id, req),
+    onSuccess: (data, { id }) => {
+      queryClient.setQueryData(queryKeys.sessionEnv(id), data);
+    },
+  });
+}
+
 export function useSessionOutput(
   id: string | null,
-  params?: { stream?: "stdout" | "stderr"; limit?: number; offset?: number }
+  params?: {
+    stream?: "stdout" | "stderr";
+    channels?: OutputChannel[];
+    limit?: number;
+    cursor?: string;
+  }
 ) {
   return useQuery({
     queryKey: id ? [...queryKeys.sessionOutput(id), params] : ["output", "none"],
@@ -146,6 +440,189 @@ export function useSessionOutput(
   });
 }
 
+export function useSessionIterations(id: string | null) {
+  return useQuery({
+    queryKey: id ? queryKeys.sessionIterations(id) : ["iterations", "none"],
+    queryFn: () => (id ? api.listIterations(id) : Promise.resolve([])),
+    enabled: !!id,
+  });
+}
+
+export function useSessionRuns(id: string | null) {
+  return useQuery({
+    queryKey: id ? queryKeys.sessionRuns(id) : ["runs", "none"],
+    queryFn: () => (id ? api.listSessionRuns(id) : Promise.resolve([])),
+    enabled: !!id,
+  });
+}
+
+export function useSessionStats(id: string | null) {
+  return useQuery({
+    queryKey: id ? queryKeys.sessionStats(id) : ["stats", "none"],
+    queryFn: () => (id ? api.getSessionStats(id) : Promise.resolve(null)),
+    enabled: !!id,
+  });
+}
+
+export function useSessionPipeline(id: string | null) {
+  return useQuery({
+    queryKey: id ? queryKeys.sessionPipeline(id) : ["pipeline", "none"],
+    queryFn: () => (id ? api.listPipelineSteps(id) : Promise.resolve([])),
+    enabled: !!id,
+  });
+}
+
+// --- Prompts ---
+
+export function useRecentPrompts(repoId: string | null, q?: string) {
+  return useQuery({
+    queryKey: repoId ? [...queryKeys.recentPrompts(repoId), q ?? ""] : ["prompts", "none"],
+    queryFn: () =>
+      repoId ? api.getRecentPrompts(repoId, { q, limit: 5 }) : Promise.resolve(null),
+    enabled: !!repoId,
+  });
+}
+
+// --- Activity ---
+
+export function useActivity(params?: { limit?: number; cursor?: string }) {
+  return useQuery({
+    queryKey: [...queryKeys.activity, params],
+    queryFn: () => api.listActivity(params),
+  });
+}
+
+// --- Search ---
+
+export function useSearch(q: string, params?: { limit?: number }) {
+  return useQuery({
+    queryKey: [...queryKeys.search(q), params],
+    queryFn: () => api.search(q, params),
+    enabled: q.trim().length > 0,
+  });
+}
+
+// --- Analytics ---
+
+export function useDailyStats(params?: { repoId?: string; from?: string; to?: string }) {
+  return useQuery({
+    queryKey: [...queryKeys.dailyStats, params],
+    queryFn: () => api.getDailyStats(params),
+  });
+}
+
+// --- Artifacts ---
+
+export function useArtifacts(sessionId: string | null) {
+  return useQuery({
+    queryKey: sessionId ? queryKeys.artifacts(sessionId) : ["artifacts", "none"],
+    queryFn: () => (sessionId ? api.listArtifacts(sessionId) : Promise.resolve([])),
+    enabled: !!sessionId,
+  });
+}
+
+// --- Attachments ---
+
+export function useAttachments(sessionId: string | null) {
+  return useQuery({
+    queryKey: sessionId ? queryKeys.attachments(sessionId) : ["attachments", "none"],
+    queryFn: () => (sessionId ? api.listAttachments(sessionId) : Promise.resolve([])),
+    enabled: !!sessionId,
+  });
+}
+
+export function useAttachFiles() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: ({ sessionId, files }: { sessionId: string; files: File[] }) =>
+      api.attachFiles(sessionId, files),
+    onSuccess: (_, { sessionId }) => {
+      // Session details list attachments too
+      queryClient.invalidateQueries({ queryKey: queryKeys.session(sessionId) });
+    },
+  });
+}
+
+export function useDeleteAttachment() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: ({ id }: { id: string; sessionId: string }) => api.deleteAttachment(id),
+    onSuccess: (_, { sessionId }) => {
+      queryClient.invalidateQueries({ queryKey: queryKeys.session(sessionId) });
+    },
+  });
+}
+
+// --- Uploads ---
+
+export function useUploadFiles() {
+  return useMutation({
+    mutationFn: (files: File[]) => api.uploadFiles(files),
+  });
+}
+
+// --- Terminals ---
+
+export function useTerminals() {
+  return useQuery({
+    queryKey: queryKeys.terminals,
+    queryFn: api.listTerminals,
+  });
+}
+
+export function useCreateTerminal() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: ({ repoId, req }: { repoId: string; req?: CreateTerminalRequest }) =>
+      api.createTerminal(repoId, req),
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: queryKeys.terminals });
+    },
+  });
+}
+
+export function useCloseTerminal() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: (id: string) => api.closeTerminal(id),
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: queryKeys.terminals });
+    },
+  });
+}
+
+// --- Snapshots ---
+
+export function useSnapshots(sessionId: string | null) {
+  return useQuery({
+    queryKey: sessionId ? queryKeys.snapshots(sessionId) : ["snapshots", "none"],
+    queryFn: () => (sessionId ? api.listSnapshots(sessionId) : Promise.resolve([])),
+    enabled: !!sessionId,
+  });
+}
+
+export function useRestoreSnapshot() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: ({ id }: { id: string; sessionId: string }) => api.restoreSnapshot(id),
+    onSuccess: (_, { sessionId }) => {
+      queryClient.invalidateQueries({ queryKey: queryKeys.snapshots(sessionId) });
+      queryClient.invalidateQueries({ queryKey: queryKeys.gitStatus(sessionId) });
+      queryClient.invalidateQueries({ queryKey: queryKeys.gitDiff(sessionId) });
+    },
+  });
+}
+
+export function useDeleteSnapshot() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: ({ id }: { id: string; sessionId: string }) => api.deleteSnapshot(id),
+    onSuccess: (_, { sessionId }) => {
+      queryClient.invalidateQueries({ queryKey: queryKeys.snapshots(sessionId) });
+    },
+  });
+}
+
 // --- Git ---
 
 export function useGitStatus(sessionId: string | null) {
@@ -156,12 +633,12 @@ export function useGitStatus(sessionId: string | null) {
   });
 }
 
-export function useGitLog(sessionId: string | null, limit?: number) {
+export function useGitLog(sessionId: string | null, params: GitLogParams = {}) {
   return useQuery({
     queryKey: sessionId
-      ? [...queryKeys.gitLog(sessionId), limit]
+      ? [...queryKeys.gitLog(sessionId), params]
       : ["git", "log", "none"],
-    queryFn: () => (sessionId ? api.getGitLog(sessionId, limit) : Promise.resolve(null)),
+    queryFn: () => (sessionId ? api.getGitLog(sessionId, params) : Promise.resolve(null)),
     enabled: !!sessionId,
   });
 }
@@ -241,6 +718,276 @@ export function useGitCheckout() {
   });
 }
 
+export function useFileDiff(repoId: string | null, path: string | null, staged = false) {
+  return useQuery({
+    queryKey:
+      repoId && path ? queryKeys.fileDiff(repoId, path, staged) : ["git", "diff", "none"],
+    queryFn: () =>
+      repoId && path ? api.getFileDiff(repoId, path, staged) : Promise.resolve(null),
+    enabled: !!repoId && !!path,
+  });
+}
+
+// Without a ref this is the file at HEAD, which moves with new commits
+export function useFileAtRevision(repoId: string | null, path: string | null, ref = "HEAD") {
+  return useQuery({
+    queryKey:
+      repoId && path ? queryKeys.fileAtRevision(repoId, path, ref) : ["git", "file", "none"],
+    queryFn: () =>
+      repoId && path ? api.getFileAtRevision(repoId, path, ref) : Promise.resolve(null),
+    enabled: !!repoId && !!path,
+  });
+}
+
+// Without a ref this lists the working directory, e.g. for a file picker
+export function useTree(repoId: string | null, path = "", ref: string | null = null) {
+  return useQuery({
+    queryKey: repoId ? queryKeys.tree(repoId, path, ref) : ["git", "tree", "none"],
+    queryFn: () => (repoId ? api.listTree(repoId, path, ref ?? undefined) : Promise.resolve([])),
+    enabled: !!repoId,
+  });
+}
+
+// Staging changes what every session's status and staged diffs show
+export function useStageFiles() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: ({ repoId, paths }: { repoId: string; paths: string[] }) =>
+      api.stageFiles(repoId, paths),
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["git"] });
+    },
+  });
+}
+
+export function useUnstageFiles() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: ({ repoId, paths }: { repoId: string; paths: string[] }) =>
+      api.unstageFiles(repoId, paths),
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["git"] });
+    },
+  });
+}
+
+export function useStashes(repoId: string | null) {
+  return useQuery({
+    queryKey: repoId ? queryKeys.stashes(repoId) : ["git", "stash", "none"],
+    queryFn: () => (repoId ? api.listStashes(repoId) : Promise.resolve([])),
+    enabled: !!repoId,
+  });
+}
+
+// Stashing changes the working tree, so every session's git queries go stale
+export function useStashChanges() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: ({ repoId, req }: { repoId: string; req?: StashRequest }) =>
+      api.stashChanges(repoId, req),
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["git"] });
+    },
+  });
+}
+
+export function usePopStash() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: ({ repoId, index }: { repoId: string; index: number }) => api.popStash(repoId, index),
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["git"] });
+    },
+  });
+}
+
+export function useDropStash() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: ({ repoId, index }: { repoId: string; index: number }) => api.dropStash(repoId, index),
+    onSuccess: (stashes, { repoId }) => {
+      queryClient.setQueryData(queryKeys.stashes(repoId), stashes);
+    },
+  });
+}
+
+export function useTags(repoId: string | null) {
+  return useQuery({
+    queryKey: repoId ? queryKeys.tags(repoId) : ["git", "tags", "none"],
+    queryFn: () => (repoId ? api.listTags(repoId) : Promise.resolve([])),
+    enabled: !!repoId,
+  });
+}
+
+export function useCreateTag() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: ({ repoId, req }: { repoId: string; req: CreateTagRequest }) => api.createTag(repoId, req),
+    onSuccess: (_, { repoId }) => {
+      queryClient.invalidateQueries({ queryKey: queryKeys.tags(repoId) });
+    },
+  });
+}
+
+export function useDeleteTag() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: ({ repoId, name }: { repoId: string; name: string }) => api.deleteTag(repoId, name),
+    onSuccess: (tags, { repoId }) => {
+      queryClient.setQueryData(queryKeys.tags(repoId), tags);
+    },
+  });
+}
+
+export function usePushTag() {
+  return useMutation({
+    mutationFn: ({ repoId, name, remote }: { repoId: string; name: string; remote?: string }) =>
+      api.pushTag(repoId, name, remote),
+  });
+}
+
+export function useRemotes(repoId: string | null) {
+  return useQuery({
+    queryKey: repoId ? queryKeys.remotes(repoId) : ["git", "remotes", "none"],
+    queryFn: () => (repoId ? api.listRemotes(repoId) : Promise.resolve([])),
+    enabled: !!repoId,
+  });
+}
+
+export function useAddRemote() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: ({ repoId, req }: { repoId: string; req: AddRemoteRequest }) => api.addRemote(repoId, req),
+    onSuccess: (_, { repoId }) => {
+      queryClient.invalidateQueries({ queryKey: queryKeys.remotes(repoId) });
+    },
+  });
+}
+
+export function useSetRemoteUrl() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: ({ repoId, name, req }: { repoId: string; name: string; req: SetRemoteUrlRequest }) =>
+      api.setRemoteUrl(repoId, name, req),
+    onSuccess: (_, { repoId }) => {
+      queryClient.invalidateQueries({ queryKey: queryKeys.remotes(repoId) });
+    },
+  });
+}
+
+export function useGitIdentity(repoId: string | null) {
+  return useQuery({
+    queryKey: repoId ? queryKeys.gitIdentity(repoId) : ["git", "identity", "none"],
+    queryFn: () => (repoId ? api.getGitIdentity(repoId) : Promise.resolve(null)),
+    enabled: !!repoId,
+  });
+}
+
+export function useSetGitIdentity() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: ({ repoId, identity }: { repoId: string; identity: GitIdentity }) =>
+      api.setGitIdentity(repoId, identity),
+    onSuccess: (identity, { repoId }) => {
+      queryClient.setQueryData(queryKeys.gitIdentity(repoId), identity);
+    },
+  });
+}
+
+// Merges and rebases rewrite the working tree, so every git query goes stale
+export function useMergeBranch() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: ({ repoId, branch }: { repoId: string; branch: string }) => api.mergeBranch(repoId, branch),
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["git"] });
+    },
+  });
+}
+
+export function useRebaseBranch() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: ({ repoId, onto }: { repoId: string; onto: string }) => api.rebaseBranch(repoId, onto),
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["git"] });
+    },
+  });
+}
+
+export function useCherryPick() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: ({ repoId, commit }: { repoId: string; commit: string }) => api.cherryPick(repoId, commit),
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["git"] });
+    },
+  });
+}
+
+export function useRevertCommit() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: ({ repoId, commit, noCommit }: { repoId: string; commit: string; noCommit?: boolean }) =>
+      api.revertCommit(repoId, commit, noCommit),
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["git"] });
+    },
+  });
+}
+
+export function useContinueMerge() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: ({ repoId, operation }: { repoId: string; operation: MergeOperation }) =>
+      api.continueMerge(repoId, operation),
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["git"] });
+    },
+  });
+}
+
+export function useAbortMerge() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: ({ repoId, operation }: { repoId: string; operation: MergeOperation }) =>
+      api.abortMerge(repoId, operation),
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["git"] });
+    },
+  });
+}
+
+export function useConflicts(repoId: string | null) {
+  return useQuery({
+    queryKey: repoId ? queryKeys.conflicts(repoId) : ["git", "conflicts", "none"],
+    queryFn: () => (repoId ? api.listConflicts(repoId) : Promise.resolve([])),
+    enabled: !!repoId,
+  });
+}
+
+export function useResolveConflict() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: ({ repoId, req }: { repoId: string; req: ResolveConflictRequest }) =>
+      api.resolveConflict(repoId, req),
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["git"] });
+    },
+  });
+}
+
+// Removing a remote drops its remote-tracking branches too
+export function useRemoveRemote() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: ({ repoId, name }: { repoId: string; name: string }) => api.removeRemote(repoId, name),
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["git"] });
+    },
+  });
+}
+
 // --- Config ---
 
 export function useConfig() {
@@ -303,3 +1050,76 @@ export function usePresets() {
     queryFn: api.listPresets,
   });
 }
+
+export function useUiConfig() {
+  return useQuery({
+    queryKey: queryKeys.uiConfig,
+    queryFn: api.getUiConfig,
+    staleTime: Infinity,
+  });
+}
+
+// --- Jobs ---
+
+export function useJobs(params: ListJobsParams = {}) {
+  return useQuery({
+    queryKey: queryKeys.jobList(params),
+    queryFn: () => api.listJobs(params),
+  });
+}
+
+export function useJob(id: string | null) {
+  return useQuery({
+    queryKey: id ? queryKeys.job(id) : ["jobs", "none"],
+    queryFn: () => (id ? api.getJob(id) : Promise.resolve(null)),
+    enabled: !!id,
+  });
+}
+
+export function useCreateJob() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: (req: JobRequest) => api.createJob(req),
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: queryKeys.jobs });
+    },
+  });
+}
+
+export function useCancelJob() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: (id: string) => api.cancelJob(id),
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: queryKeys.jobs });
+    },
+  });
+}
+
+// --- Workers ---
+
+export function useWorkers() {
+  return useQuery({
+    queryKey: queryKeys.workers,
+    queryFn: api.listWorkers,
+  });
+}
+
+// --- Admin ---
+
+export function useMaintenanceJobs() {
+  return useQuery({
+    queryKey: queryKeys.maintenanceJobs,
+    queryFn: api.listMaintenanceJobs,
+  });
+}
+
+export function usePruneOutput() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: () => api.pruneOutput(),
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: queryKeys.sessions });
+    },
+  });
+}
@@ -1,5 +1,15 @@
 // API types that mirror backend DTOs
 
+// --- Pagination ---
+
+// List endpoints return one page at a time; pass next_cursor back as
+// ?cursor= to fetch the next page, until it is null
+export interface Page<T> {
+  items: T[];
+  total: number;
+  next_cursor: string | null;
+}
+
 // --- Repos ---
 
 export interface Repo {
@@ -10,6 +20,118 @@ export interface Repo {
   updated_at: string;
 }
 
+export type BookmarkGitStatus = "added" | "modified" | "deleted" | "renamed" | "copied" | "untracked";
+
+export interface Bookmark {
+  id: string;
+  repo_id: string;
+  /** Path relative to the repository root */
+  path: string;
+  label: string | null;
+  created_at: string;
+  updated_at: string;
+  /** Whether the path exists in the working tree */
+  exists: boolean;
+  is_dir: boolean;
+  /** Uncommitted change to the file, if any */
+  git_status: BookmarkGitStatus | null;
+}
+
+/** A record of a worktree created through the API */
+export interface Worktree {
+  id: string;
+  repo_id: string;
+  /** Session whose runs work in it */
+  session_id: string | null;
+  branch: string;
+  path: string;
+  created_at: string;
+}
+
+/** A linked worktree as git sees it */
+export interface WorktreeEntry {
+  name: string;
+  path: string;
+  /** null when HEAD is detached or the checkout is gone */
+  branch: string | null;
+  head: string | null;
+  locked: boolean;
+  /** The worktree's directory is missing */
+  prunable: boolean;
+  /** Set for worktrees created through the API */
+  id: string | null;
+  session_id: string | null;
+}
+
+export interface CreateWorktreeRequest {
+  branch: string;
+  /** Branch a new branch starts from (default: HEAD) */
+  base?: string;
+  session_id?: string;
+}
+
+export interface RepoDetails extends Repo {
+  bookmarks: Bookmark[];
+  /** Files are stored in Git LFS and need `git lfs pull` */
+  lfs_required: boolean;
+}
+
+export interface RepoHealth {
+  healthy: boolean;
+  /** The directory is gone or no longer a git repository */
+  stale_path: boolean;
+  detached_head: boolean;
+  missing_remote: boolean;
+  dirty: boolean;
+  conflicts: string[];
+  operation_in_progress: "merge" | "rebase" | "cherry_pick" | "revert" | "bisect" | "am" | null;
+  warnings: string[];
+}
+
+/** A repository's branch as of the last background fetch */
+export interface RepoSync {
+  repo_id: string;
+  branch: string;
+  ahead: number;
+  behind: number;
+  fetched_at: string;
+  /** Why the last fetch failed; the counts are from an earlier one */
+  error: string | null;
+}
+
+export interface QueuedSession {
+  session_id: string;
+  /** 1 is next */
+  position: number;
+}
+
+/** A run waiting to start, across repositories */
+export interface QueueEntry {
+  session_id: string;
+  repo_id: string;
+  /** Place in the repository's queue; 1 is next */
+  position: number;
+}
+
+export interface RunQueue {
+  /** Runs going on the server machine */
+  running: number;
+  max_concurrent_runs: number | null;
+  /** In the order the runs were requested */
+  queued: QueueEntry[];
+}
+
+export interface CreateBookmarkRequest {
+  path: string;
+  label?: string;
+}
+
+export interface UpdateBookmarkRequest {
+  path?: string;
+  /** `null` removes the label */
+  label?: string | null;
+}
+
 export interface AddRepoRequest {
   path: string;
   name?: string;
@@ -29,7 +151,15 @@ export interface ScanResponse {
   found: FoundRepo[];
 }
 
-export interface CloneRepoRequest {
+export interface CloneOptions {
+  branch?: string;
+  depth?: number;
+  recurse_submodules?: boolean;
+  /** Run `git lfs pull` after cloning if the repository uses LFS */
+  lfs?: boolean;
+}
+
+export interface CloneRepoRequest extends CloneOptions {
   url: string;
 }
 
@@ -48,13 +178,43 @@ export interface CloneProgress {
 }
 
 export type CloneProgressEvent =
+  | { type: "started"; job_id: string }
   | { type: "progress"; data: CloneProgress }
   | { type: "complete"; data: { repo: Repo; message: string } }
   | { type: "error"; data: { message: string; help_steps?: string[]; auth_type?: AuthType; can_retry_with_credentials?: boolean } };
 
 // --- Orchestrator ---
 
-export type OrchestratorType = "ralph" | "gsd" | "gastown";
+// Built-in orchestrators, or the id of one declared in server configuration
+export type OrchestratorType = "ralph" | "claude" | "aider" | "gemini" | "gsd" | "gastown" | (string & {});
+
+// --- Templates ---
+
+export interface Template {
+  id: string;
+  description: string | null;
+  /** Prompt for the first session of projects created from it */
+  prompt: string | null;
+  builtin: boolean;
+}
+
+export interface CreateProjectRequest {
+  name: string;
+  /** Defaults to "empty" */
+  template?: string;
+  /** Start a first session right away */
+  start?: boolean;
+  /** Prompt for the first session; defaults to the template's */
+  prompt?: string;
+  orchestrator?: OrchestratorType;
+}
+
+export interface CreateProjectResponse {
+  repo: Repo;
+  /** Id of the first commit */
+  commit: string;
+  session: Session | null;
+}
 
 // --- Errors ---
 
@@ -87,7 +247,7 @@ export interface CloneErrorInfo {
 
 // --- Sessions ---
 
-export type SessionStatus = "idle" | "running" | "completed" | "error" | "cancelled";
+export type SessionStatus = "idle" | "queued" | "running" | "completed" | "error" | "cancelled";
 
 export interface Session {
   id: string;
@@ -95,26 +255,118 @@ export interface Session {
   name: string | null;
   orchestrator: OrchestratorType;
   status: SessionStatus;
+  failure: Failure | null;
+  archived: boolean;
+  archived_at: string | null;
+  tags: string[];
+  metadata: Record<string, unknown>;
   created_at: string;
   updated_at: string;
 }
 
+export type FailureReason =
+  | "missing_api_key"
+  | "authentication_failed"
+  | "rate_limited"
+  | "network_error"
+  | "compile_error"
+  | "limit_exceeded"
+  | "stalled";
+
+export interface Failure {
+  reason: FailureReason;
+  help_steps: string[];
+  line: string;
+}
+
 export interface CreateSessionRequest {
   repo_id: string;
   name?: string;
   orchestrator?: OrchestratorType;
 }
 
+export interface ArchiveSessionRequest {
+  archived: boolean;
+}
+
+export interface AddTagRequest {
+  tag: string;
+}
+
+export interface TagCount {
+  tag: string;
+  sessions: number;
+}
+
 export type MessageRole = "user" | "assistant" | "system";
 
+/** Text, or something the agent did that its output reported */
+export type MessageKind = "text" | "tool_call" | "file_edit";
+
 export interface Message {
   id: string;
   session_id: string;
   role: MessageRole;
+  kind: MessageKind;
   content: string;
+  /** `{ name, input }` for a tool call, `{ path, tool }` for a file edit */
+  data?: Record<string, unknown>;
   created_at: string;
 }
 
+export interface Iteration {
+  id: number;
+  session_id: string;
+  number: number;
+  first_output_id: number;
+  /** Null while the iteration runs */
+  last_output_id: number | null;
+  summary: string | null;
+  /** Pass as `cursor` to the output listing to start at this iteration */
+  output_cursor: string;
+  started_at: string;
+  ended_at: string | null;
+}
+
+export interface Run {
+  id: string;
+  session_id: string;
+  prompt: string;
+  /** "running" until the run ends */
+  status: SessionStatus;
+  /** Null while it runs, for runs on a worker, and when killed by a signal */
+  exit_code: number | null;
+  started_at: string;
+  finished_at: string | null;
+  duration_ms: number | null;
+  usage: TokenUsage;
+}
+
+/** Tokens an agent used and what they cost, as its output reported them */
+export interface TokenUsage {
+  input_tokens: number;
+  output_tokens: number;
+  cache_read_tokens: number;
+  cache_write_tokens: number;
+  cost_usd: number;
+}
+
+export interface SessionStats extends TokenUsage {
+  runs: number;
+}
+
+export type StepStatus = "pending" | "running" | "completed" | "error" | "cancelled" | "skipped";
+
+export interface PipelineStep {
+  session_id: string;
+  /** 1 runs first */
+  step: number;
+  prompt: string;
+  status: StepStatus;
+  started_at: string | null;
+  finished_at: string | null;
+}
+
 export interface SessionDetails {
   id: string;
   repo_id: string;
@@ -124,16 +376,46 @@ export interface SessionDetails {
   created_at: string;
   updated_at: string;
   messages: Message[];
+  /** Files attached to the session, handed to each run */
+  attachments: Attachment[];
+  /** Place in the repository's run queue while queued; 1 is next */
+  queue_position?: number;
 }
 
 export interface RunSessionRequest {
   prompt: string;
+  /** Run on this connected worker instead of on the server */
+  worker_id?: string;
+  /** Run inside a container instead of directly on the server */
+  sandbox?: boolean;
+  /** Upload whose text is added to the prompt */
+  prompt_upload_id?: string;
+  /** Uploads the agent is pointed at as context files */
+  context_upload_ids?: string[];
+  /** Environment variables added to the session's and kept for later runs */
+  env?: Record<string, string>;
+  /** Prompts run after this one, each once the one before it completed */
+  pipeline?: string[];
+  /** Where the process starts: a path relative to the repository's root, or a worktree's absolute path */
+  working_dir?: string;
+}
+
+/** Environment variables a session's runs are started with */
+export interface SessionEnv {
+  env: Record<string, string>;
 }
 
 export interface RunSessionResponse {
   session_id: string;
+  /** "queued" when the repository is busy with another run */
   status: SessionStatus;
   message: string;
+  /** Place in the repository's run queue when queued; 1 is next */
+  queue_position?: number;
+}
+
+export interface SessionInputRequest {
+  text: string;
 }
 
 export interface CancelSessionResponse {
@@ -142,20 +424,244 @@ export interface CancelSessionResponse {
   message: string;
 }
 
+/** How stopping one session's run went */
+export interface CancelResult {
+  session_id: string;
+  cancelled: boolean;
+  error?: string;
+}
+
 export type OutputStream = "stdout" | "stderr";
 
+export type OutputChannel = "assistant_text" | "tool_call" | "system" | "stderr";
+
 export interface OutputLog {
   id: number;
   session_id: string;
   stream: OutputStream;
+  channel: OutputChannel;
   content: string;
+  /** The line was too long for one entry and continues in the next */
+  continues: boolean;
+  created_at: string;
+}
+
+export interface LineChanges {
+  added: number;
+  removed: number;
+}
+
+export interface ChangeSummary {
+  base_commit: string | null;
+  commits: Commit[];
+  files_changed: number;
+  total_added: number;
+  total_removed: number;
+}
+
+export interface SessionSummary {
+  session: Session;
+  prompts: string[];
+  changes: ChangeSummary | null;
+  changes_error: string | null;
+  output_lines: number;
+  error_lines: number;
+  output_tail: string[];
+  errors: string[];
+}
+
+export interface CompareResponse {
+  a: SessionSummary;
+  b: SessionSummary;
+  files: { path: string; a: LineChanges | null; b: LineChanges | null }[];
+}
+
+// --- Storage ---
+
+export interface CloneRootUsage {
+  path: string;
+  used_bytes: number;
+  quota_bytes: number | null;
+  remaining_bytes: number | null;
+}
+
+export interface StorageResponse {
+  clone_root: CloneRootUsage;
+}
+
+// --- Prompts ---
+
+export interface PromptHistoryEntry {
+  id: number;
+  repo_id: string;
+  prompt: string;
+  use_count: number;
+  last_used_at: string;
   created_at: string;
 }
 
-export interface OutputResponse {
+// --- Activity ---
+
+export type ActivityKind =
+  | "session_started"
+  | "session_finished"
+  | "repo_added"
+  | "commit_made"
+  | "clone_completed";
+
+export interface ActivityEvent {
+  id: number;
+  kind: ActivityKind;
+  repo_id: string;
+  session_id: string | null;
+  summary: string;
+  created_at: string;
+}
+
+// --- Search ---
+
+/** Part of a search hit's snippet; highlighted parts are what the query matched */
+export interface SnippetPart {
+  text: string;
+  highlight: boolean;
+}
+
+export interface SessionSearchHit {
   session_id: string;
-  logs: OutputLog[];
-  total: number;
+  repo_id: string;
+  name: string | null;
+  message_matches: number;
+  output_matches: number;
+  last_match_at: string;
+}
+
+export interface MessageSearchHit {
+  message_id: string;
+  session_id: string;
+  role: MessageRole;
+  kind: MessageKind;
+  snippet: SnippetPart[];
+  created_at: string;
+}
+
+export interface OutputSearchHit {
+  output_id: number;
+  session_id: string;
+  stream: OutputStream;
+  channel: OutputChannel;
+  snippet: SnippetPart[];
+  created_at: string;
+}
+
+export interface SearchResults {
+  sessions: SessionSearchHit[];
+  messages: MessageSearchHit[];
+  output: OutputSearchHit[];
+}
+
+// --- Analytics ---
+
+export interface RunStats {
+  runs: number;
+  successes: number;
+  failures: number;
+  cancelled: number;
+  runtime_secs: number;
+  output_lines: number;
+  output_bytes: number;
+}
+
+export interface DayStats extends RunStats {
+  day: string;
+}
+
+export interface DailyStatsResponse {
+  from: string;
+  to: string;
+  repo_id: string | null;
+  days: DayStats[];
+  totals: RunStats;
+}
+
+// --- Uploads ---
+
+export interface Upload {
+  id: string;
+  owner_id: string | null;
+  filename: string;
+  content_type: string | null;
+  size_bytes: number;
+  created_at: string;
+}
+
+// --- Attachments ---
+
+export interface Attachment {
+  id: string;
+  session_id: string;
+  filename: string;
+  content_type: string | null;
+  size_bytes: number;
+  created_at: string;
+}
+
+// --- Artifacts ---
+
+export interface Artifact {
+  id: string;
+  session_id: string;
+  repo_id: string;
+  path: string;
+  size_bytes: number;
+  created_at: string;
+}
+
+// --- Terminals ---
+
+export interface TerminalInfo {
+  id: string;
+  repo_id: string;
+  owner_id: string | null;
+  cols: number;
+  rows: number;
+  created_at: string;
+}
+
+export interface CreateTerminalRequest {
+  cols?: number;
+  rows?: number;
+}
+
+/** Text frames a client may send on a terminal socket; raw input can also go as binary frames */
+export type TerminalClientMessage =
+  | { type: "input"; data: string }
+  | { type: "resize"; cols: number; rows: number };
+
+/** Text frame sent before the socket closes because the shell exited */
+export interface TerminalExitMessage {
+  type: "exit";
+  code: number | null;
+}
+
+// --- Snapshots ---
+
+export type SnapshotReason = "pre_run" | "pre_restore";
+
+export interface Snapshot {
+  id: string;
+  session_id: string;
+  repo_id: string;
+  reason: SnapshotReason;
+  head_commit: string | null;
+  file_count: number;
+  deleted_count: number;
+  size_bytes: number;
+  created_at: string;
+}
+
+export interface RestoreSnapshotResponse {
+  restored: string;
+  safety_snapshot: Snapshot;
 }
 
 // --- Git ---
@@ -167,6 +673,7 @@ export interface GitStatus {
   staged: string[];
   unstaged: string[];
   untracked: string[];
+  lfs_required: boolean;
 }
 
 export interface GitStatusResponse {
@@ -177,6 +684,7 @@ export interface GitStatusResponse {
   staged: string[];
   unstaged: string[];
   untracked: string[];
+  lfs_required: boolean;
 }
 
 export interface Commit {
@@ -186,17 +694,23 @@ export interface Commit {
   time: string;
 }
 
-export interface GitLogResponse {
-  session_id: string;
-  commits: Commit[];
-}
-
 export interface Branch {
   name: string;
   is_current: boolean;
   is_remote: boolean;
 }
 
+export interface GitLogParams {
+  limit?: number;
+  cursor?: string;
+  /** Branch, tag or commit to start from instead of HEAD */
+  rev?: string;
+  /** Part of the author's name or email */
+  author?: string;
+  /** Only commits that change this file or directory */
+  path?: string;
+}
+
 export interface GitBranchesResponse {
   session_id: string;
   branches: Branch[];
@@ -241,6 +755,134 @@ export interface CheckoutRequest {
   branch: string;
 }
 
+export type DiffLineKind = "context" | "added" | "removed";
+
+export interface DiffLine {
+  kind: DiffLineKind;
+  old_lineno: number | null;
+  new_lineno: number | null;
+  content: string;
+}
+
+export interface DiffHunk {
+  header: string;
+  old_start: number;
+  old_lines: number;
+  new_start: number;
+  new_lines: number;
+  lines: DiffLine[];
+}
+
+export interface FileDiff {
+  path: string;
+  old_path: string | null;
+  binary: boolean;
+  hunks: DiffHunk[];
+}
+
+export type TreeEntryKind = "file" | "directory" | "symlink" | "submodule";
+
+export interface TreeEntry {
+  name: string;
+  /** Relative to the repository root */
+  path: string;
+  kind: TreeEntryKind;
+  /** Null for directories and submodules */
+  size: number | null;
+  /** Git file mode in octal, e.g. "100644" */
+  mode: string;
+}
+
+export interface FileAtRevision {
+  path: string;
+  /** Commit the revision resolved to */
+  commit: string;
+  size: number;
+  binary: boolean;
+  /** Left out for binary files and ones over 1 MiB */
+  content: string | null;
+}
+
+export interface StashEntry {
+  /** 0 is the most recent */
+  index: number;
+  message: string;
+  commit: string;
+}
+
+export interface StashRequest {
+  message?: string;
+  include_untracked?: boolean;
+}
+
+export interface Tag {
+  name: string;
+  /** Commit the tag points at */
+  target: string;
+  annotated: boolean;
+  message: string | null;
+  tagger: string | null;
+}
+
+/** Operations that can stop on conflicts, as named in their routes */
+export type MergeOperation = "merge" | "rebase" | "cherry-pick" | "revert";
+
+export interface MergeOutcome {
+  /** False when git stopped on conflicts */
+  completed: boolean;
+  conflicts: string[];
+  output: CommandOutput;
+}
+
+export interface ConflictFile {
+  path: string;
+  /** Common ancestor's version; null when both sides added the file */
+  base: string | null;
+  /** Current branch's version (during a rebase, the branch being rebased onto); null if deleted */
+  ours: string | null;
+  /** Version being merged in (during a rebase, the commit being replayed) */
+  theirs: string | null;
+  /** Contents are left out when a version isn't text */
+  binary: boolean;
+}
+
+/** Accept a side, or give the merged content */
+export type ResolveConflictRequest =
+  | { path: string; accept: "ours" | "theirs" }
+  | { path: string; content: string };
+
+/** The repository's own user.name and user.email; null falls back to the global config */
+export interface GitIdentity {
+  name: string | null;
+  email: string | null;
+}
+
+export interface RemoteInfo {
+  name: string;
+  url: string | null;
+  /** URL pushes go to, when set apart from `url` */
+  push_url: string | null;
+}
+
+export interface AddRemoteRequest {
+  name: string;
+  url: string;
+}
+
+export interface SetRemoteUrlRequest {
+  url: string;
+  /** Change only the push URL */
+  push?: boolean;
+}
+
+export interface CreateTagRequest {
+  name: string;
+  /** Branch, tag or commit to tag (default: HEAD) */
+  target?: string;
+  /** Makes the tag annotated */
+  message?: string;
+}
+
 // --- Config ---
 
 export interface ConfigResponse {
@@ -280,20 +922,156 @@ export interface PresetsResponse {
   presets: Preset[];
 }
 
+// --- UI Config ---
+
+export interface UiOrchestrator {
+  id: string;
+  name: string;
+  description: string | null;
+  available: boolean;
+}
+
+export interface UiConfigResponse {
+  version: string;
+  base_path: string;
+  api_base: string;
+  api_version: number;
+  ws_path: string;
+  features: {
+    auth: boolean;
+    tls: boolean;
+    read_only: boolean;
+    rate_limit: boolean;
+    desktop_notifications: boolean;
+    /** Runs can be sandboxed in a container */
+    sandbox: boolean;
+  };
+  orchestrators: UiOrchestrator[];
+  limits: {
+    max_prompt_bytes: number;
+    max_commit_message_bytes: number;
+    rate_limit_burst: number | null;
+    rate_limit_per_minute: number | null;
+    max_concurrent_runs: number | null;
+  };
+}
+
 // --- WebSocket Messages ---
 
 // Client → Server messages
 export type WsClientMessage =
-  | { type: "subscribe"; session_id: string }
+  | { type: "subscribe"; session_id: string; channels?: OutputChannel[] }
   | { type: "unsubscribe"; session_id: string }
   | { type: "cancel"; session_id: string }
+  | { type: "input"; session_id: string; text: string }
+  | { type: "subscribe_global" }
   | { type: "ping" };
 
+export type FileChangeKind = "created" | "modified" | "removed";
+
+export type ResourceLimit = "duration" | "memory" | "output";
+
 // Server → Client messages
 export type WsServerMessage =
   | { type: "subscribed"; session_id: string }
   | { type: "unsubscribed"; session_id: string }
-  | { type: "output"; session_id: string; stream: OutputStream; content: string }
+  | { type: "subscribed_global" }
+  | ({ type: "repo_sync" } & RepoSync)
+  | {
+      type: "output";
+      session_id: string;
+      stream: OutputStream;
+      channel: OutputChannel;
+      content: string;
+      continues?: boolean;
+    }
   | { type: "status"; session_id: string; status: SessionStatus }
+  | { type: "queue_position"; session_id: string; position: number }
+  | { type: "file_changed"; session_id: string; path: string; kind: FileChangeKind }
+  | {
+      type: "limit_exceeded";
+      session_id: string;
+      limit: ResourceLimit;
+      // Seconds for duration, bytes otherwise
+      max: number;
+      value: number;
+    }
+  | { type: "stalled"; session_id: string; idle_secs: number; stopping: boolean }
+  | ({ type: "cost"; session_id: string } & TokenUsage)
+  | { type: "pipeline_step"; session_id: string; step: number; steps: number; status: StepStatus }
+  | { type: "message"; session_id: string; message: Message }
   | { type: "error"; message: string }
   | { type: "pong" };
+
+// --- Jobs ---
+
+export type JobKind = "clone" | "scan" | "maintenance";
+
+export type JobState = "queued" | "running" | "done" | "failed" | "cancelled";
+
+export interface Job {
+  id: string;
+  kind: JobKind;
+  state: JobState;
+  owner_id: string | null;
+  description: string;
+  /** Fraction complete between 0 and 1, when the job can tell */
+  progress: number | null;
+  progress_message: string | null;
+  /** Repo for clones, ScanResponse for scans, JobStatus for maintenance */
+  result: unknown | null;
+  error: string | null;
+  cancel_requested: boolean;
+  created_at: string;
+  started_at: string | null;
+  finished_at: string | null;
+}
+
+export type JobRequest =
+  | { kind: "clone"; url: string; credentials?: CredentialRequest }
+  | ({ kind: "scan" } & ScanRequest)
+  | { kind: "maintenance"; job: string };
+
+export interface ListJobsParams {
+  kind?: JobKind;
+  state?: JobState;
+}
+
+// --- Workers ---
+
+export interface WorkerInfo {
+  id: string;
+  name: string;
+  /** Ralphtown version the worker runs */
+  version: string;
+  connected_at: string;
+  /** Sessions currently running on the worker */
+  sessions: string[];
+}
+
+// --- Admin ---
+
+export type JobOutcome = "succeeded" | "failed";
+
+export interface JobStatus {
+  name: string;
+  description: string;
+  /** Seconds between runs; null when the job is turned off */
+  interval_secs: number | null;
+  running: boolean;
+  runs: number;
+  failures: number;
+  last_started_at: string | null;
+  last_finished_at: string | null;
+  last_duration_ms: number | null;
+  last_outcome: JobOutcome | null;
+  last_message: string | null;
+  next_run_at: string | null;
+}
+
+export interface PrunedOutput {
+  /** Output lines deleted */
+  deleted: number;
+  /** Bytes of pages added to the database's free list; reused before the file grows, but not returned to disk */
+  bytes_freed: number;
+}
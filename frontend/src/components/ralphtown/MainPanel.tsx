@@ -135,7 +135,7 @@ export function MainPanel({ activeInstance, onStartSession, onSendMessage, onCan
             />
 
             {/* Prompt Input */}
-            <PromptInput onSubmit={handleSubmit} />
+            <PromptInput onSubmit={handleSubmit} repoId={selectedRepo?.id} />
           </div>
         </main>
       )}
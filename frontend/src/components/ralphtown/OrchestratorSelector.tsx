@@ -1,4 +1,4 @@
-import { Bot, Sparkles, Users } from "lucide-react";
+import { Bot, Gem, Puzzle, Sparkles, Terminal, Users, Wand2 } from "lucide-react";
 import {
   Select,
   SelectContent,
@@ -7,6 +7,7 @@ import {
   SelectValue,
 } from "@/components/ui/select";
 import { Badge } from "@/components/ui/badge";
+import { useUiConfig } from "@/api/hooks";
 import type { OrchestratorType } from "@/api/types";
 
 interface OrchestratorInfo {
@@ -25,6 +26,27 @@ const ORCHESTRATORS: OrchestratorInfo[] = [
     available: true,
     icon: <Bot className="h-4 w-4" />,
   },
+  {
+    id: "claude",
+    name: "Claude Code",
+    description: "Anthropic's coding agent",
+    available: false,
+    icon: <Wand2 className="h-4 w-4" />,
+  },
+  {
+    id: "aider",
+    name: "Aider",
+    description: "AI pair programming in the terminal",
+    available: false,
+    icon: <Terminal className="h-4 w-4" />,
+  },
+  {
+    id: "gemini",
+    name: "Gemini CLI",
+    description: "Google's coding agent",
+    available: false,
+    icon: <Gem className="h-4 w-4" />,
+  },
   {
     id: "gsd",
     name: "GSD",
@@ -47,7 +69,26 @@ interface OrchestratorSelectorProps {
 }
 
 export function OrchestratorSelector({ value, onChange }: OrchestratorSelectorProps) {
-  const selectedOrchestrator = ORCHESTRATORS.find((o) => o.id === value);
+  const { data: uiConfig } = useUiConfig();
+  const reported = uiConfig?.orchestrators ?? [];
+
+  // Built-in orchestrators are available when the server found their CLI
+  const builtins = ORCHESTRATORS.map((o) => ({
+    ...o,
+    available: reported.find((r) => r.id === o.id)?.available ?? o.available,
+  }));
+  // Orchestrators declared in the server's configuration
+  const configured: OrchestratorInfo[] = reported
+    .filter((o) => !ORCHESTRATORS.some((builtin) => builtin.id === o.id))
+    .map((o) => ({
+      id: o.id,
+      name: o.name,
+      description: o.description ?? "Configured orchestrator",
+      available: o.available,
+      icon: <Puzzle className="h-4 w-4" />,
+    }));
+  const orchestrators = [...builtins, ...configured];
+  const selectedOrchestrator = orchestrators.find((o) => o.id === value);
 
   return (
     <Select value={value} onValueChange={(v) => onChange(v as OrchestratorType)}>
@@ -62,7 +103,7 @@ export function OrchestratorSelector({ value, onChange }: OrchestratorSelectorPr
         </SelectValue>
       </SelectTrigger>
       <SelectContent>
-        {ORCHESTRATORS.map((orchestrator) => (
+        {orchestrators.map((orchestrator) => (
           <SelectItem
             key={orchestrator.id}
             value={orchestrator.id}
@@ -73,7 +114,7 @@ export function OrchestratorSelector({ value, onChange }: OrchestratorSelectorPr
               <span>{orchestrator.name}</span>
               {!orchestrator.available && (
                 <Badge variant="secondary" className="ml-1 text-[10px] px-1.5 py-0">
-                  Coming Soon
+                  {reported.some((r) => r.id === orchestrator.id) ? "Not Installed" : "Coming Soon"}
                 </Badge>
               )}
             </span>
@@ -1,5 +1,5 @@
 import { useState } from "react";
-import { Image, ArrowUp, ChevronDown } from "lucide-react";
+import { Image, ArrowUp, ChevronDown, History } from "lucide-react";
 import { Button } from "@/components/ui/button";
 import { Textarea } from "@/components/ui/textarea";
 import {
@@ -9,14 +9,20 @@ import {
   DropdownMenuTrigger,
 } from "@/components/ui/dropdown-menu";
 import { availableModels, quickActions } from "@/constants";
+import { useRecentPrompts } from "@/api/hooks";
 
 interface PromptInputProps {
   onSubmit: (prompt: string, model: string) => void;
+  /** Repository whose previously used prompts are suggested while typing */
+  repoId?: string;
 }
 
-export function PromptInput({ onSubmit }: PromptInputProps) {
+export function PromptInput({ onSubmit, repoId }: PromptInputProps) {
   const [prompt, setPrompt] = useState("");
   const [selectedModel, setSelectedModel] = useState(availableModels[0]);
+  const query = prompt.trim();
+  const { data: recentPrompts } = useRecentPrompts(query ? repoId ?? null : null, query);
+  const suggestions = (recentPrompts?.items ?? []).filter((entry) => entry.prompt !== prompt);
 
   const handleSubmit = () => {
     if (prompt.trim()) {
@@ -43,6 +49,23 @@ export function PromptInput({ onSubmit }: PromptInputProps) {
           className="min-h-[100px] border-0 bg-transparent resize-none focus-visible:ring-0 text-base px-4 py-3"
         />
 
+        {/* Previously used prompts matching what's typed */}
+        {suggestions.length > 0 && (
+          <div className="border-t border-border py-1">
+            {suggestions.map((entry) => (
+              <button
+                key={entry.id}
+                type="button"
+                className="flex w-full items-center gap-2 px-4 py-1.5 text-left text-sm text-muted-foreground hover:bg-accent hover:text-foreground"
+                onClick={() => setPrompt(entry.prompt)}
+              >
+                <History className="h-3.5 w-3.5 shrink-0" />
+                <span className="truncate">{entry.prompt}</span>
+              </button>
+            ))}
+          </div>
+        )}
+
         {/* Bottom bar */}
         <div className="flex items-center justify-between px-3 py-2 border-t border-border">
           <DropdownMenu>
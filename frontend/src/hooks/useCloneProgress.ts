@@ -1,7 +1,8 @@
 import { useRef, useCallback, useEffect } from "react";
 import { useQueryClient } from "@tanstack/react-query";
 import { queryKeys } from "@/api/hooks";
-import type { CloneProgress, Repo, CredentialRequest, AuthType } from "@/api/types";
+import { API_BASE, cancelClone, csrfHeaders } from "@/api/client";
+import type { CloneOptions, CloneProgress, Repo, CredentialRequest, AuthType } from "@/api/types";
 
 export interface UseCloneProgressOptions {
   onProgress: (progress: CloneProgress) => void;
@@ -10,15 +11,23 @@ export interface UseCloneProgressOptions {
 }
 
 export interface UseCloneProgressReturn {
-  startClone: (url: string) => void;
-  startCloneWithCredentials: (url: string, credentials: CredentialRequest) => void;
+  startClone: (url: string, cloneOptions?: CloneOptions) => void;
+  startCloneWithCredentials: (
+    url: string,
+    credentials: CredentialRequest,
+    cloneOptions?: CloneOptions
+  ) => void;
+  /** Stop listening; the clone carries on in the background */
   cancel: () => void;
+  /** Stop the clone itself; the server removes what it had cloned */
+  abort: () => Promise<void>;
 }
 
 export function useCloneProgress(
   options: UseCloneProgressOptions
 ): UseCloneProgressReturn {
   const eventSourceRef = useRef<EventSource | null>(null);
+  const jobIdRef = useRef<string | null>(null);
   const queryClient = useQueryClient();
 
   // Store callbacks in refs to avoid stale closures
@@ -39,17 +48,37 @@ export function useCloneProgress(
     }
   }, []);
 
+  // The stream ends with a clone_error once the server has stopped the clone
+  const abort = useCallback(async () => {
+    if (jobIdRef.current) {
+      await cancelClone(jobIdRef.current);
+      jobIdRef.current = null;
+    }
+  }, []);
+
   const startClone = useCallback(
-    (url: string) => {
+    (url: string, cloneOptions: CloneOptions = {}) => {
       // Close any existing EventSource
       cancel();
 
       // Create new EventSource connection
-      const encodedUrl = encodeURIComponent(url);
+      const params = new URLSearchParams({ url });
+      if (cloneOptions.branch) params.set("branch", cloneOptions.branch);
+      if (cloneOptions.depth) params.set("depth", String(cloneOptions.depth));
+      if (cloneOptions.recurse_submodules) params.set("recurse_submodules", "true");
+      if (cloneOptions.lfs) params.set("lfs", "true");
       const eventSource = new EventSource(
-        `/api/repos/clone-progress?url=${encodedUrl}`
+        `${API_BASE}/repos/clone-progress?${params}`
       );
 
+      eventSource.addEventListener("started", (event) => {
+        try {
+          jobIdRef.current = (JSON.parse((event as MessageEvent).data) as { job_id: string }).job_id;
+        } catch (e) {
+          console.error("Failed to parse started message:", e);
+        }
+      });
+
       // Handle progress messages (default message event)
       eventSource.onmessage = (event) => {
         try {
@@ -120,14 +149,14 @@ export function useCloneProgress(
 
   // Start clone with credentials via POST (for retry after auth failure)
   const startCloneWithCredentials = useCallback(
-    async (url: string, credentials: CredentialRequest) => {
+    async (url: string, credentials: CredentialRequest, cloneOptions: CloneOptions = {}) => {
       cancel();
 
       try {
-        const response = await fetch("/api/repos/clone-progress", {
+        const response = await fetch(`${API_BASE}/repos/clone-progress`, {
           method: "POST",
-          headers: { "Content-Type": "application/json" },
-          body: JSON.stringify({ url, credentials }),
+          headers: { "Content-Type": "application/json", ...csrfHeaders() },
+          body: JSON.stringify({ url, credentials, ...cloneOptions }),
         });
 
         if (!response.ok || !response.body) {
@@ -152,7 +181,9 @@ export function useCloneProgress(
             if (dataMatch) {
               try {
                 const data = JSON.parse(dataMatch[1]);
-                if (data.type === "progress") {
+                if (data.type === "started") {
+                  jobIdRef.current = data.job_id;
+                } else if (data.type === "progress") {
                   onProgressRef.current(data.data);
                 } else if (data.type === "complete") {
                   // Wait for repos to refetch before calling onComplete
@@ -188,5 +219,5 @@ export function useCloneProgress(
     };
   }, [cancel]);
 
-  return { startClone, startCloneWithCredentials, cancel };
+  return { startClone, startCloneWithCredentials, cancel, abort };
 }
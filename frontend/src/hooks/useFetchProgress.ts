@@ -0,0 +1,109 @@
+import { useRef, useCallback, useEffect } from "react";
+import { useQueryClient } from "@tanstack/react-query";
+import { API_BASE } from "@/api/client";
+import type { CloneProgress, AuthType } from "@/api/types";
+
+export interface UseFetchProgressOptions {
+  onProgress: (progress: CloneProgress) => void;
+  onComplete: (message: string) => void;
+  onError: (message: string, helpSteps?: string[], authType?: AuthType) => void;
+}
+
+export interface UseFetchProgressReturn {
+  startFetch: (repoId: string, remote?: string) => void;
+  cancel: () => void;
+}
+
+export function useFetchProgress(
+  options: UseFetchProgressOptions
+): UseFetchProgressReturn {
+  const eventSourceRef = useRef<EventSource | null>(null);
+  const queryClient = useQueryClient();
+
+  // Store callbacks in refs to avoid stale closures
+  const onProgressRef = useRef(options.onProgress);
+  const onCompleteRef = useRef(options.onComplete);
+  const onErrorRef = useRef(options.onError);
+
+  useEffect(() => {
+    onProgressRef.current = options.onProgress;
+    onCompleteRef.current = options.onComplete;
+    onErrorRef.current = options.onError;
+  }, [options.onProgress, options.onComplete, options.onError]);
+
+  const cancel = useCallback(() => {
+    if (eventSourceRef.current) {
+      eventSourceRef.current.close();
+      eventSourceRef.current = null;
+    }
+  }, []);
+
+  const startFetch = useCallback(
+    (repoId: string, remote = "origin") => {
+      cancel();
+
+      const eventSource = new EventSource(
+        `${API_BASE}/git/${repoId}/fetch-progress?remote=${encodeURIComponent(remote)}`
+      );
+
+      // Handle progress messages (default message event)
+      eventSource.onmessage = (event) => {
+        try {
+          onProgressRef.current(JSON.parse(event.data) as CloneProgress);
+        } catch (e) {
+          console.error("Failed to parse progress message:", e);
+        }
+      };
+
+      // Track if we received a final event to avoid spurious error handling
+      let receivedFinalEvent = false;
+
+      eventSource.addEventListener("complete", async (event) => {
+        receivedFinalEvent = true;
+        eventSource.close();
+        eventSourceRef.current = null;
+        const data = JSON.parse((event as MessageEvent).data) as { message: string };
+        // Remote branches and ahead/behind counts have changed
+        await queryClient.invalidateQueries({ queryKey: ["git"] });
+        onCompleteRef.current(data.message);
+      });
+
+      eventSource.addEventListener("fetch_error", (event) => {
+        receivedFinalEvent = true;
+        try {
+          const data = JSON.parse((event as MessageEvent).data) as {
+            message: string;
+            help_steps?: string[];
+            auth_type?: AuthType;
+          };
+          onErrorRef.current(data.message, data.help_steps, data.auth_type);
+        } catch {
+          onErrorRef.current("Fetch failed");
+        }
+        eventSource.close();
+        eventSourceRef.current = null;
+      });
+
+      // Handle connection-level errors (browser's built-in error event)
+      eventSource.addEventListener("error", () => {
+        if (!receivedFinalEvent) {
+          onErrorRef.current("Connection to the server was lost");
+        }
+        eventSource.close();
+        eventSourceRef.current = null;
+      });
+
+      eventSourceRef.current = eventSource;
+    },
+    [cancel, queryClient]
+  );
+
+  // Cleanup on unmount
+  useEffect(() => {
+    return () => {
+      cancel();
+    };
+  }, [cancel]);
+
+  return { startFetch, cancel };
+}
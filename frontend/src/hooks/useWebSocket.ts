@@ -4,9 +4,17 @@ import type {
   WsServerMessage,
   OutputStream,
   SessionStatus,
+  FileChangeKind,
+  Message,
+  ResourceLimit,
+  StepStatus,
+  TokenUsage,
+  RepoSync,
 } from "@/api/types";
+import { API_BASE } from "@/api/client";
 
-const WS_URL = "ws://localhost:3000/api/ws";
+const WS_PROTOCOL = window.location.protocol === "https:" ? "wss:" : "ws:";
+const WS_URL = `${WS_PROTOCOL}//${window.location.host}${API_BASE}/ws`;
 const RECONNECT_INTERVAL = 3000;
 const PING_INTERVAL = 30000;
 
@@ -19,6 +27,15 @@ export interface OutputLine {
 export interface UseWebSocketOptions {
   onOutput?: (sessionId: string, line: OutputLine) => void;
   onStatus?: (sessionId: string, status: SessionStatus) => void;
+  onQueuePosition?: (sessionId: string, position: number) => void;
+  onFileChanged?: (sessionId: string, path: string, kind: FileChangeKind) => void;
+  onLimitExceeded?: (sessionId: string, limit: ResourceLimit, max: number, value: number) => void;
+  onStalled?: (sessionId: string, idleSecs: number, stopping: boolean) => void;
+  onCost?: (sessionId: string, totals: TokenUsage) => void;
+  onPipelineStep?: (sessionId: string, step: number, steps: number, status: StepStatus) => void;
+  onMessage?: (sessionId: string, message: Message) => void;
+  /** Subscribes to the global channel; called when a background fetch changes a repository's counts */
+  onRepoSync?: (sync: RepoSync) => void;
   onError?: (message: string) => void;
 }
 
@@ -27,6 +44,7 @@ export interface UseWebSocketReturn {
   subscribe: (sessionId: string) => void;
   unsubscribe: (sessionId: string) => void;
   cancel: (sessionId: string) => void;
+  sendInput: (sessionId: string, text: string) => void;
 }
 
 export function useWebSocket(options: UseWebSocketOptions = {}): UseWebSocketReturn {
@@ -39,13 +57,41 @@ export function useWebSocket(options: UseWebSocketOptions = {}): UseWebSocketRet
   // Store callbacks in refs to avoid reconnection on callback changes
   const onOutputRef = useRef(options.onOutput);
   const onStatusRef = useRef(options.onStatus);
+  const onQueuePositionRef = useRef(options.onQueuePosition);
+  const onFileChangedRef = useRef(options.onFileChanged);
+  const onLimitExceededRef = useRef(options.onLimitExceeded);
+  const onStalledRef = useRef(options.onStalled);
+  const onCostRef = useRef(options.onCost);
+  const onPipelineStepRef = useRef(options.onPipelineStep);
+  const onMessageRef = useRef(options.onMessage);
+  const onRepoSyncRef = useRef(options.onRepoSync);
   const onErrorRef = useRef(options.onError);
 
   useEffect(() => {
     onOutputRef.current = options.onOutput;
     onStatusRef.current = options.onStatus;
+    onQueuePositionRef.current = options.onQueuePosition;
+    onFileChangedRef.current = options.onFileChanged;
+    onLimitExceededRef.current = options.onLimitExceeded;
+    onStalledRef.current = options.onStalled;
+    onCostRef.current = options.onCost;
+    onPipelineStepRef.current = options.onPipelineStep;
+    onMessageRef.current = options.onMessage;
+    onRepoSyncRef.current = options.onRepoSync;
     onErrorRef.current = options.onError;
-  }, [options.onOutput, options.onStatus, options.onError]);
+  }, [
+    options.onOutput,
+    options.onStatus,
+    options.onQueuePosition,
+    options.onFileChanged,
+    options.onLimitExceeded,
+    options.onStalled,
+    options.onCost,
+    options.onPipelineStep,
+    options.onMessage,
+    options.onRepoSync,
+    options.onError,
+  ]);
 
   const send = useCallback((message: WsClientMessage) => {
     if (wsRef.current?.readyState === WebSocket.OPEN) {
@@ -70,6 +116,42 @@ export function useWebSocket(options: UseWebSocketOptions = {}): UseWebSocketRet
           onStatusRef.current?.(message.session_id, message.status);
           break;
 
+        case "queue_position":
+          onQueuePositionRef.current?.(message.session_id, message.position);
+          break;
+
+        case "file_changed":
+          onFileChangedRef.current?.(message.session_id, message.path, message.kind);
+          break;
+
+        case "limit_exceeded":
+          onLimitExceededRef.current?.(message.session_id, message.limit, message.max, message.value);
+          break;
+
+        case "stalled":
+          onStalledRef.current?.(message.session_id, message.idle_secs, message.stopping);
+          break;
+
+        case "cost": {
+          const { type: _type, session_id, ...totals } = message;
+          onCostRef.current?.(session_id, totals);
+          break;
+        }
+
+        case "pipeline_step":
+          onPipelineStepRef.current?.(message.session_id, message.step, message.steps, message.status);
+          break;
+
+        case "message":
+          onMessageRef.current?.(message.session_id, message.message);
+          break;
+
+        case "repo_sync": {
+          const { type: _type, ...sync } = message;
+          onRepoSyncRef.current?.(sync);
+          break;
+        }
+
         case "error":
           onErrorRef.current?.(message.message);
           break;
@@ -82,6 +164,9 @@ export function useWebSocket(options: UseWebSocketOptions = {}): UseWebSocketRet
           subscribedSessionsRef.current.delete(message.session_id);
           break;
 
+        case "subscribed_global":
+          break;
+
         case "pong":
           // Connection is alive
           break;
@@ -106,6 +191,10 @@ export function useWebSocket(options: UseWebSocketOptions = {}): UseWebSocketRet
         send({ type: "subscribe", session_id: sessionId });
       });
 
+      if (onRepoSyncRef.current) {
+        send({ type: "subscribe_global" });
+      }
+
       // Start ping interval
       pingIntervalRef.current = setInterval(() => {
         send({ type: "ping" });
@@ -173,10 +262,18 @@ export function useWebSocket(options: UseWebSocketOptions = {}): UseWebSocketRet
     [send]
   );
 
+  const sendInput = useCallback(
+    (sessionId: string, text: string) => {
+      send({ type: "input", session_id: sessionId, text });
+    },
+    [send]
+  );
+
   return {
     isConnected,
     subscribe,
     unsubscribe,
     cancel,
+    sendInput,
   };
 }
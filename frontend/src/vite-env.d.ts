@@ -1 +1,6 @@
 /// <reference types="vite/client" />
+
+interface Window {
+  /** Path prefix the server injects into index.html when not served from the root */
+  __RALPHTOWN_BASE_PATH__?: string;
+}